@@ -14,7 +14,7 @@ use log::*;
 use serde_json::Value;
 
 use pact_cli::{glob_value, setup_loggers};
-use pact_cli::verification::{display_results, VerificationResult, verify_json};
+use pact_cli::verification::{display_results, VerificationResult, verify_json, verify_schema};
 use pact_models::http_utils::{self, HttpAuth};
 use pact_models::PactSpecification;
 
@@ -133,6 +133,9 @@ fn setup_app<'a, 'b>(program: &str, version: &'b str) -> App<'a, 'b> {
     .arg(Arg::with_name("strict")
       .long("strict")
       .help("Enable strict validation. This will reject things like additional attributes"))
+    .arg(Arg::with_name("schema")
+      .long("schema")
+      .help("Also validate the Pact files against the JSON schema for the specification version"))
 }
 
 fn handle_cli() -> Result<(), i32> {
@@ -177,7 +180,10 @@ fn handle_matches(args: &ArgMatches) -> Result<(), i32> {
   let files = load_files(args).map_err(|_| 1)?;
 
   let results = files.iter().map(|(source, pact_json)| {
-    let results = verify_json(pact_json, spec_version, source, args.is_present("strict"));
+    let mut results = verify_json(pact_json, spec_version, source, args.is_present("strict"));
+    if args.is_present("schema") {
+      results.extend(verify_schema(pact_json, spec_version, source));
+    }
     VerificationResult::new(source, results)
   }).collect();
 
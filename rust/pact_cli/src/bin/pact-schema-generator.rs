@@ -6,11 +6,11 @@ use std::env;
 
 use clap::{App, AppSettings, Arg, ArgMatches, ErrorKind};
 use log::*;
-use serde_json::{json, to_string_pretty};
+use serde_json::to_string_pretty;
 
+use pact_cli::schema::pact_schema;
 use pact_cli::setup_loggers;
 use pact_models::PactSpecification;
-use pact_models::sync_pact::RequestResponsePact;
 
 fn setup_app<'a, 'b>(program: &str, version: &'b str) -> App<'a, 'b> {
   App::new(program)
@@ -31,6 +31,13 @@ fn setup_app<'a, 'b>(program: &str, version: &'b str) -> App<'a, 'b> {
       .possible_values(&["v1", "v2", "v3", "v4"])
       .default_value("v4")
       .help("Pact specification to generate the schema for."))
+    .arg(Arg::with_name("type")
+      .long("type")
+      .short("t")
+      .takes_value(true)
+      .possible_values(&["http", "message"])
+      .default_value("http")
+      .help("Type of Pact to generate the schema for. Only V3 has separate schemas, as V4 Pacts can contain both types of interaction."))
 }
 
 fn handle_cli() -> Result<(), i32> {
@@ -68,32 +75,14 @@ fn handle_matches(args: &ArgMatches) -> Result<(), i32> {
     .map(|version| PactSpecification::from(version))
     .unwrap_or(PactSpecification::V4);
 
-  let mut schema = json!({
-    "$schema": "https://json-schema.org/draft/2020-12/schema",
-    "$id": format!("https://pact.io/schema/pact-{}.json", spec_version.to_string()),
-    "title": format!("Pact File {} Schema", spec_version.to_string()),
-    "description": format!("JSON schema for a {} specification pact file", spec_version.to_string()),
-    "type": "object"
-  });
-
-  if let Some(map) = schema.as_object_mut() {
-    match spec_version {
-      PactSpecification::V1_1 |PactSpecification::V1 | PactSpecification::V2 => {
-        if let Some(attributes) = RequestResponsePact::schema(spec_version).as_object() {
-          for (k, v) in attributes {
-            map.insert(k.clone(), v.clone());
-          }
-        }
-      }
-      PactSpecification::V3 => {}
-      PactSpecification::V4 => {}
-      _ => {
-        eprintln!("ERROR: Mat a valid Pact specification version: '{}'",
-                  args.value_of("spec").unwrap_or_default());
-        return Err(2);
-      }
+  let message_pact = args.value_of("type") == Some("message");
+  let schema = match pact_schema(spec_version, message_pact) {
+    Ok(schema) => schema,
+    Err(err) => {
+      eprintln!("ERROR: {}", err);
+      return Err(2);
     }
-  }
+  };
 
   let result = to_string_pretty(&schema);
   match result {
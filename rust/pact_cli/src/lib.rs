@@ -4,6 +4,7 @@ use std::str::FromStr;
 use log::{LevelFilter, SetLoggerError};
 use simplelog::{ColorChoice, Config, TerminalMode, TermLogger};
 
+pub mod schema;
 pub mod verification;
 
 pub fn setup_loggers(level: &str) -> Result<(), SetLoggerError> {
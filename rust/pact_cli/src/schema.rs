@@ -0,0 +1,208 @@
+//! JSON schemas for Pact files
+
+use anyhow::anyhow;
+use jsonschema::{Draft, JSONSchema};
+use serde_json::{json, Value};
+
+use pact_models::message_pact::MessagePact;
+use pact_models::PactSpecification;
+use pact_models::sync_pact::RequestResponsePact;
+use pact_models::v4::pact::V4Pact;
+use pact_models::verify_json::{PactFileVerificationResult, ResultLevel};
+
+/// Generates the JSON schema for a Pact file with the given specification version. V3 Pacts are
+/// either request/response or message Pacts, so `message_pact` selects which form to generate.
+/// Message Pacts are not supported before V3, and V4 Pacts can contain both types of interactions.
+pub fn pact_schema(spec_version: PactSpecification, message_pact: bool) -> anyhow::Result<Value> {
+  let (name, attributes) = match spec_version {
+    PactSpecification::V1 | PactSpecification::V1_1 | PactSpecification::V2 => if message_pact {
+      return Err(anyhow!("Message pacts are not supported by the {} specification", spec_version));
+    } else {
+      (format!("pact-{}", spec_version), RequestResponsePact::schema(spec_version))
+    },
+    PactSpecification::V3 => if message_pact {
+      (format!("pact-{}-message", spec_version), MessagePact::schema(spec_version))
+    } else {
+      (format!("pact-{}", spec_version), RequestResponsePact::schema(spec_version))
+    },
+    PactSpecification::V4 => (format!("pact-{}", spec_version), V4Pact::schema()),
+    _ => return Err(anyhow!("'{}' is not a valid Pact specification version", spec_version))
+  };
+
+  let mut schema = json!({
+    "$schema": "http://json-schema.org/draft-07/schema#",
+    "$id": format!("https://pact.io/schema/{}.json", name),
+    "title": format!("Pact File {} Schema", spec_version),
+    "description": if message_pact {
+      format!("JSON schema for a {} specification message pact file", spec_version)
+    } else {
+      format!("JSON schema for a {} specification pact file", spec_version)
+    },
+    "type": "object"
+  });
+  if let (Some(map), Value::Object(attributes)) = (schema.as_object_mut(), attributes) {
+    map.extend(attributes);
+  }
+  Ok(schema)
+}
+
+/// Validates the Pact JSON against the JSON schema for the given specification version. Each
+/// schema violation is returned as an error with the path to the invalid item.
+pub fn validate_against_schema(pact_json: &Value, spec_version: PactSpecification) -> Vec<PactFileVerificationResult> {
+  let message_pact = spec_version < PactSpecification::V4 && pact_json.get("messages").is_some();
+  let schema = match pact_schema(spec_version, message_pact) {
+    Ok(schema) => schema,
+    Err(err) => return vec![PactFileVerificationResult::new("/", ResultLevel::ERROR, err.to_string())]
+  };
+  let compiled = match JSONSchema::options().with_draft(Draft::Draft7).compile(&schema) {
+    Ok(compiled) => compiled,
+    Err(err) => return vec![PactFileVerificationResult::new("/", ResultLevel::ERROR,
+      format!("Failed to compile the JSON schema for {} - {}", spec_version, err))]
+  };
+  match compiled.validate(pact_json) {
+    Ok(_) => vec![],
+    Err(errors) => errors.map(|error| {
+      let path = error.instance_path.to_string();
+      PactFileVerificationResult::new(if path.is_empty() { "/".to_string() } else { path },
+        ResultLevel::ERROR, format!("Schema violation: {}", error))
+    }).collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use pact_models::PactSpecification;
+  use pact_models::verify_json::ResultLevel;
+
+  use super::{pact_schema, validate_against_schema};
+
+  #[test]
+  fn generates_schemas_for_all_versions() {
+    for spec in [PactSpecification::V1, PactSpecification::V1_1, PactSpecification::V2,
+      PactSpecification::V3, PactSpecification::V4] {
+      let schema = pact_schema(spec, false).unwrap();
+      expect!(schema.get("properties").is_some()).to(be_true());
+      expect!(schema.get("definitions").is_some()).to(be_true());
+    }
+    let schema = pact_schema(PactSpecification::V3, true).unwrap();
+    expect!(schema["properties"].get("messages").is_some()).to(be_true());
+    expect!(pact_schema(PactSpecification::V2, true)).to(be_err());
+    expect!(pact_schema(PactSpecification::Unknown, false)).to(be_err());
+  }
+
+  #[test]
+  fn validate_v3_pact() {
+    let pact = json!({
+      "consumer": { "name": "consumer" },
+      "provider": { "name": "provider" },
+      "interactions": [
+        {
+          "description": "a request",
+          "providerStates": [{ "name": "state", "params": { "id": 1 } }],
+          "request": {
+            "method": "GET",
+            "path": "/",
+            "query": { "a": ["b"] },
+            "matchingRules": {
+              "path": { "matchers": [{ "match": "regex", "regex": "/\\w+" }] },
+              "query": { "a": { "combine": "AND", "matchers": [{ "match": "type" }] } }
+            },
+            "generators": {
+              "path": { "type": "Regex", "regex": "/\\w+" }
+            }
+          },
+          "response": { "status": 200 }
+        }
+      ],
+      "metadata": { "pactSpecification": { "version": "3.0.0" } }
+    });
+    expect!(validate_against_schema(&pact, PactSpecification::V3).iter()).to(be_empty());
+
+    let pact = json!({
+      "consumer": { "name": "consumer" },
+      "provider": { "name": "provider" },
+      "interactions": [
+        {
+          "description": "a request",
+          "request": {
+            "method": "GET",
+            "path": "/",
+            "matchingRules": {
+              "body": { "$.a": { "matchers": [{ "match": "regex" }] } }
+            }
+          },
+          "response": { "status": "200" }
+        }
+      ]
+    });
+    let results = validate_against_schema(&pact, PactSpecification::V3);
+    let paths = results.iter().map(|result| result.path.as_str()).collect::<Vec<_>>();
+    expect!(results.iter().all(|result| result.level == ResultLevel::ERROR)).to(be_true());
+    expect!(paths.contains(&"/interactions/0/response/status")).to(be_true());
+    expect!(paths.contains(&"/interactions/0/request/matchingRules/body/$.a/matchers/0")).to(be_true());
+  }
+
+  #[test]
+  fn validate_v4_pact() {
+    let pact = json!({
+      "consumer": { "name": "consumer" },
+      "provider": { "name": "provider" },
+      "interactions": [
+        {
+          "type": "Synchronous/HTTP",
+          "description": "a request",
+          "key": "1234",
+          "pending": false,
+          "comments": { "text": ["a comment"], "testname": "a test" },
+          "request": { "method": "GET", "path": "/", "headers": { "Accept": ["application/json"] } },
+          "response": {
+            "status": 200,
+            "body": { "content": { "a": 1 }, "contentType": "application/json", "encoded": false }
+          }
+        },
+        {
+          "type": "Asynchronous/Messages",
+          "description": "a message",
+          "contents": { "content": "AAEC", "contentType": "application/octet-stream", "encoded": "base64" },
+          "interactionMarkup": { "markup": "# A message", "markupType": "COMMON_MARK" },
+          "pluginConfiguration": { "protobuf": { "descriptorKey": "abc" } }
+        },
+        {
+          "type": "Synchronous/Messages",
+          "description": "a sync message",
+          "request": { "contents": { "content": "a" } },
+          "response": [{ "contents": { "content": "b" } }]
+        }
+      ],
+      "metadata": {
+        "pactSpecification": { "version": "4.0" },
+        "plugins": [{ "name": "protobuf", "version": "0.1.0", "configuration": {} }]
+      }
+    });
+    expect!(validate_against_schema(&pact, PactSpecification::V4).iter()).to(be_empty());
+
+    let pact = json!({
+      "consumer": { "name": "consumer" },
+      "provider": { "name": "provider" },
+      "interactions": [
+        {
+          "type": "Synchronous/HTTP",
+          "description": "a request",
+          "request": { "method": "GET", "path": "/" },
+          "response": { "status": 200, "body": { "contentType": "text/plain" } }
+        },
+        {
+          "type": "Other",
+          "description": "unknown"
+        }
+      ]
+    });
+    let results = validate_against_schema(&pact, PactSpecification::V4);
+    let paths = results.iter().map(|result| result.path.as_str()).collect::<Vec<_>>();
+    expect!(paths.contains(&"/interactions/0/response/body")).to(be_true());
+    expect!(paths.contains(&"/interactions/1/type")).to(be_true());
+  }
+}
@@ -12,6 +12,8 @@ use pact_models::sync_pact::RequestResponsePact;
 use pact_models::v4::pact::V4Pact;
 use pact_models::verify_json::{json_type_of, PactFileVerificationResult, PactJsonVerifier, ResultLevel};
 
+use crate::schema::validate_against_schema;
+
 #[derive(Debug, Clone, Serialize)]
 pub struct VerificationResult {
   /// source of the verification
@@ -35,14 +37,18 @@ impl VerificationResult {
   }
 }
 
-pub fn verify_json(pact_json: &Value, spec_version: PactSpecification, source: &str, strict: bool) -> Vec<PactFileVerificationResult> {
-  let spec_version = match spec_version {
+fn resolve_spec_version(pact_json: &Value, spec_version: PactSpecification, source: &str) -> PactSpecification {
+  match spec_version {
     PactSpecification::Unknown => {
       let metadata = parse_meta_data(pact_json);
       determine_spec_version(source, &metadata)
     }
     _ => spec_version.clone()
-  };
+  }
+}
+
+pub fn verify_json(pact_json: &Value, spec_version: PactSpecification, source: &str, strict: bool) -> Vec<PactFileVerificationResult> {
+  let spec_version = resolve_spec_version(pact_json, spec_version, source);
   match spec_version {
     PactSpecification::V4 => V4Pact::verify_json("/", pact_json, strict, spec_version),
     _ => match pact_json {
@@ -57,6 +63,16 @@ pub fn verify_json(pact_json: &Value, spec_version: PactSpecification, source: &
   }
 }
 
+/// Validates the Pact JSON against the JSON schema for the specification version. If the version
+/// is not known, it is determined from the Pact metadata (defaulting to V3).
+pub fn verify_schema(pact_json: &Value, spec_version: PactSpecification, source: &str) -> Vec<PactFileVerificationResult> {
+  let spec_version = match resolve_spec_version(pact_json, spec_version, source) {
+    PactSpecification::Unknown => PactSpecification::V3,
+    spec_version => spec_version
+  };
+  validate_against_schema(pact_json, spec_version)
+}
+
 pub fn display_results(result: &Vec<VerificationResult>, output_type: &str) -> anyhow::Result<()> {
   if output_type == "json" {
     generate_json_output(result)
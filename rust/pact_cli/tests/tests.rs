@@ -1,7 +1,7 @@
 use expectest::prelude::*;
 use serde_json::Value;
 
-use pact_cli::verification::verify_json;
+use pact_cli::verification::{verify_json, verify_schema};
 use pact_models::PactSpecification;
 use pact_models::verify_json::ResultLevel;
 
@@ -64,3 +64,23 @@ fn valid_v4_pact() {
 
   expect!(results.iter().filter(|result| result.level == ResultLevel::ERROR)).to(be_empty());
 }
+
+#[test]
+fn v3_message_pact_is_valid_against_the_schema() {
+  let pact_file = include_str!("v3-message-pact.json");
+  let json: Value = serde_json::from_str(pact_file).unwrap();
+
+  let results = verify_schema(&json, PactSpecification::Unknown, pact_file);
+
+  expect!(results.iter()).to(be_empty());
+}
+
+#[test]
+fn v4_pact_is_valid_against_the_schema() {
+  let pact_file = include_str!("v4-combined-pact.json");
+  let json: Value = serde_json::from_str(pact_file).unwrap();
+
+  let results = verify_schema(&json, PactSpecification::Unknown, pact_file);
+
+  expect!(results.iter()).to(be_empty());
+}
@@ -0,0 +1,185 @@
+//! Builder for CSV and TSV bodies with column patterns
+
+use pact_models::csv_utils::{COLUMNS_PATH, CsvDocument};
+use pact_models::matchingrules::{Category, MatchingRule, MatchingRuleCategory, RuleLogic};
+use pact_models::path_exp::DocPath;
+
+use crate::prelude::*;
+
+/// Builder for CSV (or TSV) bodies. Each column has a pattern that is applied to all the rows, and
+/// the number of rows can be constrained with a minimum and maximum.
+///
+/// ```
+/// use pact_consumer::prelude::*;
+/// use pact_consumer::*;
+/// use pact_consumer::builders::{CsvBodyBuilder, ResponseBuilder};
+///
+/// ResponseBuilder::default().csv_body(CsvBodyBuilder::csv()
+///     .column("id", term!("^[0-9]+$", "1"))
+///     .column("name", "Fred")
+///     .column("amount", term!("^[0-9]+\\.[0-9]{2}$", "10.50"))
+///     .min_rows(1));
+/// ```
+#[derive(Debug)]
+pub struct CsvBodyBuilder {
+  delimiter: char,
+  content_type: String,
+  columns: Vec<(String, StringPattern)>,
+  examples: usize,
+  min_rows: Option<usize>,
+  max_rows: Option<usize>,
+  strict_column_order: bool
+}
+
+impl CsvBodyBuilder {
+  /// Create a builder for a CSV body (`text/csv`)
+  pub fn csv() -> Self {
+    CsvBodyBuilder {
+      delimiter: ',',
+      content_type: "text/csv".to_string(),
+      columns: vec![],
+      examples: 1,
+      min_rows: None,
+      max_rows: None,
+      strict_column_order: false
+    }
+  }
+
+  /// Create a builder for a TSV body (`text/tab-separated-values`)
+  pub fn tsv() -> Self {
+    CsvBodyBuilder {
+      delimiter: '\t',
+      content_type: "text/tab-separated-values".to_string(),
+      .. CsvBodyBuilder::csv()
+    }
+  }
+
+  /// Add a column with the pattern to apply to the values in the column
+  pub fn column<N, P>(&mut self, name: N, pattern: P) -> &mut Self
+  where
+    N: Into<String>,
+    P: Into<StringPattern>
+  {
+    self.columns.push((name.into(), pattern.into()));
+    self
+  }
+
+  /// Number of example rows to generate (defaults to 1, or the minimum number of rows if that is
+  /// greater)
+  pub fn examples(&mut self, examples: usize) -> &mut Self {
+    self.examples = examples;
+    self
+  }
+
+  /// Minimum number of rows
+  pub fn min_rows(&mut self, min: usize) -> &mut Self {
+    self.min_rows = Some(min);
+    self
+  }
+
+  /// Maximum number of rows
+  pub fn max_rows(&mut self, max: usize) -> &mut Self {
+    self.max_rows = Some(max);
+    self
+  }
+
+  /// Require the columns to be in the same order as they were added
+  pub fn strict_column_order(&mut self) -> &mut Self {
+    self.strict_column_order = true;
+    self
+  }
+
+  /// Content type of the body
+  pub fn content_type(&self) -> &str {
+    self.content_type.as_str()
+  }
+
+  /// Example body, with the header row and example rows
+  pub fn to_example(&self) -> String {
+    let examples = self.min_rows.map(|min| min.max(self.examples)).unwrap_or(self.examples);
+    let row: Vec<String> = self.columns.iter().map(|(_, pattern)| pattern.to_example()).collect();
+    CsvDocument {
+      columns: Some(self.columns.iter().map(|(name, _)| name.clone()).collect()),
+      rows: vec![row; examples]
+    }.write(self.delimiter)
+  }
+
+  /// Matching rules for the rows and columns
+  pub fn matching_rules(&self) -> MatchingRuleCategory {
+    let mut rules = MatchingRuleCategory::empty(Category::BODY);
+    let row_count_rule = match (self.min_rows, self.max_rows) {
+      (Some(min), Some(max)) => Some(MatchingRule::MinMaxType(min, max)),
+      (Some(min), None) => Some(MatchingRule::MinType(min)),
+      (None, Some(max)) => Some(MatchingRule::MaxType(max)),
+      (None, None) => None
+    };
+    if let Some(rule) = row_count_rule {
+      rules.add_rule(DocPath::root(), rule, RuleLogic::And);
+    }
+    if self.strict_column_order {
+      rules.add_rule(DocPath::new_unwrap(COLUMNS_PATH), MatchingRule::Equality, RuleLogic::And);
+    }
+    for (name, pattern) in &self.columns {
+      let mut path = DocPath::root();
+      path.push_star_index().push_field(name);
+      pattern.extract_matching_rules(path, &mut rules);
+    }
+    rules
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use expectest::prelude::*;
+  use maplit::hashmap;
+  use pact_models::matchingrules::RuleList;
+
+  use crate::builders::{HttpPartBuilder, PactBuilder};
+
+  use super::*;
+
+  #[test]
+  fn csv_body_example_and_rules() {
+    let mut builder = CsvBodyBuilder::csv();
+    builder
+      .column("id", term!("^[0-9]+$", "1"))
+      .column("name", "Fred, Jr")
+      .min_rows(2)
+      .strict_column_order();
+    expect!(builder.to_example()).to(be_equal_to("id,name\n1,\"Fred, Jr\"\n1,\"Fred, Jr\"\n"));
+    expect!(builder.matching_rules().rules).to(be_equal_to(hashmap!{
+      DocPath::root() => RuleList::new(MatchingRule::MinType(2)),
+      DocPath::new_unwrap("$.columns") => RuleList::new(MatchingRule::Equality),
+      DocPath::new_unwrap("$[*].id") => RuleList::new(MatchingRule::Regex("^[0-9]+$".to_string()))
+    }));
+  }
+
+  #[test]
+  fn csv_body_pattern() {
+    let pattern = PactBuilder::new("C", "P")
+      .interaction("I", "", |mut i| {
+        i.request.csv_body(CsvBodyBuilder::csv()
+          .column("id", term!("^[0-9]+$", "1"))
+          .column("name", "Fred")
+          .min_rows(1));
+        i
+      })
+      .build();
+    let good = PactBuilder::new("C", "P")
+      .interaction("I", "", |mut i| {
+        i.request.body2("id,name\n2,Mary\n3,Joe\n", "text/csv");
+        i
+      })
+      .build();
+    let bad = PactBuilder::new("C", "P")
+      .interaction("I", "", |mut i| {
+        i.request.body2("id,name\nA,Mary\n", "text/csv");
+        i
+      })
+      .build();
+    assert_requests_match!(good, pattern);
+    assert_requests_do_not_match!(bad, pattern);
+  }
+}
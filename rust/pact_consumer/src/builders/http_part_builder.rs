@@ -3,10 +3,14 @@ use std::collections::HashMap;
 use pact_models::bodies::OptionalBody;
 use pact_models::expression_parser::DataType;
 use pact_models::generators::{Generator, GeneratorCategory, Generators};
+use pact_models::graphql::mark_as_graphql;
 use pact_models::headers::parse_header;
-use pact_models::matchingrules::MatchingRules;
+use pact_models::matchingrules::{Category, MatchingRules};
 use pact_models::path_exp::DocPath;
+use pact_models::text_template::TextTemplate;
+use serde_json::{json, Value};
 
+use crate::builders::CsvBodyBuilder;
 use crate::prelude::*;
 
 /// Various methods shared between `RequestBuilder` and `ResponseBuilder`.
@@ -225,6 +229,99 @@ pub trait HttpPartBuilder {
     }
     self
   }
+
+  /// Specify a CSV (or TSV) body, with patterns for the columns.
+  ///
+  /// ```
+  /// use pact_consumer::prelude::*;
+  /// use pact_consumer::*;
+  /// use pact_consumer::builders::{CsvBodyBuilder, RequestBuilder};
+  ///
+  /// RequestBuilder::default().csv_body(CsvBodyBuilder::csv()
+  ///     .column("name", "Fred")
+  ///     .column("amount", term!("^[0-9]+$", "100"))
+  ///     .min_rows(1));
+  /// ```
+  fn csv_body(&mut self, body: &CsvBodyBuilder) -> &mut Self {
+    {
+      let (body_ref, rules) = self.body_and_matching_rules_mut();
+      *body_ref = OptionalBody::Present(body.to_example().into(), body.content_type().parse().ok(), None);
+      rules.add_rules(Category::BODY, body.matching_rules());
+    }
+    self
+  }
+
+  /// Specify a text body (text/plain) using a text template. Captures in the template are enclosed
+  /// in double braces, with an optional type and example value (i.e. `{{id:integer=100}}`). Each
+  /// capture gets its own matching rule, and captures without an example value get a generator
+  /// for their type.
+  ///
+  /// ```
+  /// use pact_consumer::prelude::*;
+  /// use pact_consumer::builders::RequestBuilder;
+  ///
+  /// RequestBuilder::default().body_template("Order {{id:integer}} shipped on {{date:date(yyyy-MM-dd)=2024-01-31}}");
+  /// ```
+  ///
+  /// # Panics
+  ///
+  /// Panics if the template is not valid.
+  fn body_template<T: Into<String>>(&mut self, template: T) -> &mut Self {
+    self.body_template2(template, "text/plain")
+  }
+
+  /// Specify a text body using a text template with a content type.
+  ///
+  /// ```
+  /// use pact_consumer::prelude::*;
+  /// use pact_consumer::builders::RequestBuilder;
+  ///
+  /// RequestBuilder::default().body_template2("{{id:integer}},{{name=Fred}}", "text/csv");
+  /// ```
+  ///
+  /// # Panics
+  ///
+  /// Panics if the template is not valid.
+  fn body_template2<T: Into<String>, B: Into<String>>(&mut self, template: T, content_type: B) -> &mut Self {
+    let template = template.into();
+    let template = TextTemplate::parse(&template)
+      .unwrap_or_else(|err| panic!("'{}' is not a valid text template: {}", template, err));
+    {
+      let (body_ref, rules) = self.body_and_matching_rules_mut();
+      *body_ref = OptionalBody::Present(template.to_example().into(), content_type.into().parse().ok(), None);
+      rules.add_rules(Category::BODY, template.matching_rules());
+    }
+    {
+      let generators = self.generators();
+      for (path, generator) in template.generators() {
+        generators.add_generator_with_subcategory(&GeneratorCategory::BODY, path, generator);
+      }
+    }
+    self
+  }
+}
+
+/// Sets a field of a JSON object body from the pattern, keeping any other fields already in the
+/// body. Used to build up the fields of GraphQL requests and responses, so the body is also marked
+/// as GraphQL.
+pub(crate) fn set_json_body_field(body: &mut OptionalBody, rules: &mut MatchingRules, field: &str, pattern: JsonPattern) {
+  let mut json = match body {
+    OptionalBody::Present(bytes, _, _) => serde_json::from_slice::<Value>(bytes)
+      .ok()
+      .filter(|json| json.is_object())
+      .unwrap_or_else(|| json!({})),
+    _ => json!({})
+  };
+  if let Some(map) = json.as_object_mut() {
+    map.insert(field.to_string(), pattern.to_example());
+  }
+  *body = OptionalBody::Present(json.to_string().into(), Some("application/json".into()), None);
+
+  let mut path = DocPath::root();
+  path.push_field(field);
+  let body_rules = rules.add_category("body");
+  pattern.extract_matching_rules(path, body_rules);
+  mark_as_graphql(body_rules);
 }
 
 #[cfg(test)]
@@ -366,6 +463,36 @@ mod tests {
     );
   }
 
+  #[test]
+  fn text_body_template() {
+    let pattern = PactBuilder::new("C", "P")
+      .interaction("I", "", |mut i| {
+        i.request.body_template("Order {{id:integer=100}} shipped on {{date:date(yyyy-MM-dd)=2024-01-31}}");
+        i
+      })
+      .build();
+    let good = PactBuilder::new("C", "P")
+      .interaction("I", "", |mut i| {
+        i.request.body2("Order 1234 shipped on 2024-02-29", "text/plain");
+        i
+      })
+      .build();
+    let bad = PactBuilder::new("C", "P")
+      .interaction("I", "", |mut i| {
+        i.request.body2("Order abc shipped on 2024-02-29", "text/plain");
+        i
+      })
+      .build();
+    assert_requests_match!(good, pattern);
+    assert_requests_do_not_match!(bad, pattern);
+
+    let pact = pattern.as_v4_pact().unwrap();
+    let interaction = pact.interactions.first()
+      .unwrap().as_request_response().unwrap();
+    expect!(interaction.request.body.value_as_string().unwrap()).to(be_equal_to("Order 100 shipped on 2024-01-31"));
+    expect!(interaction.request.generators.categories.is_empty()).to(be_true());
+  }
+
   #[test]
   fn header_with_different_case_keys() {
     let pattern = PactBuilder::new("C", "P")
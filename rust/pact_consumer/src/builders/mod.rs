@@ -2,6 +2,7 @@
 //! theoretically be moved into `pact_matching::models` at some future date,
 //! but that's currently undergoing heavy construction.
 
+mod csv_body_builder;
 mod interaction_builder;
 mod http_part_builder;
 mod pact_builder;
@@ -13,6 +14,7 @@ mod message_builder;
 mod message_iter;
 mod sync_message_builder;
 
+pub use self::csv_body_builder::*;
 pub use self::interaction_builder::*;
 pub use self::http_part_builder::*;
 pub use self::pact_builder::*;
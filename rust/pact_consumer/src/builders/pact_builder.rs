@@ -30,6 +30,7 @@ use crate::builders::message_iter::{
 use crate::builders::sync_message_builder::SyncMessageInteractionBuilder;
 use crate::mock_server::http_mock_server::ValidatingHttpMockServer;
 #[cfg(feature = "plugins")] use crate::mock_server::plugin_mock_server::PluginMockServer;
+use crate::mock_server::websocket_mock_server::{is_websocket_entry, ValidatingWebSocketMockServer};
 use crate::PACT_CONSUMER_VERSION;
 use crate::prelude::*;
 
@@ -282,10 +283,15 @@ impl PactBuilder {
 }
 
 impl StartMockServer for PactBuilder {
-  fn start_mock_server(&self, _catalog_entry: Option<&str>) -> Box<dyn ValidatingMockServer> {
+  fn start_mock_server(&self, catalog_entry: Option<&str>) -> Box<dyn ValidatingMockServer> {
+    if catalog_entry.map(is_websocket_entry).unwrap_or(false) {
+      return ValidatingWebSocketMockServer::start(self.build(), self.output_dir.clone())
+        .expect("Could not start the WebSocket mock server");
+    }
+
     #[cfg(feature = "plugins")]
     {
-      match _catalog_entry {
+      match catalog_entry {
         Some(entry_name) => match catalogue_manager::lookup_entry(entry_name) {
           Some(entry) => if entry.entry_type == CatalogueEntryType::TRANSPORT {
             PluginMockServer::start(self.build(), self.output_dir.clone(), &entry)
@@ -9,10 +9,13 @@ use pact_models::bodies::OptionalBody;
 use pact_models::content_types::ContentType;
 use pact_models::expression_parser::DataType;
 use pact_models::generators::{Generator, GeneratorCategory, Generators};
+use pact_models::graphql::validate_document;
 #[cfg(feature = "plugins")] use pact_models::http_parts::HttpPart;
 use pact_models::json_utils::body_from_json;
 use pact_models::matchingrules::{Category, MatchingRules};
 use pact_models::path_exp::DocPath;
+use pact_models::path_template::{path_segments, segment_path, PathSegment, PathTemplate};
+use pact_models::query_strings::encode_nested_query_param;
 use pact_models::request::Request;
 use pact_models::v4::http_parts::HttpRequest;
 use pact_models::v4::interaction::InteractionMarkup;
@@ -25,6 +28,7 @@ use serde_json::json;
 use serde_json::Value;
 #[allow(unused_imports)] use tracing::debug;
 
+use crate::builders::http_part_builder::set_json_body_field;
 use crate::prelude::*;
 use crate::util::GetDefaulting;
 
@@ -87,6 +91,31 @@ impl RequestBuilder {
         self
     }
 
+    /// Specify the request path with a template that has typed parameters for any of the path
+    /// segments (i.e. `/orders/{id:integer}`). Each parameter segment is matched with a matching
+    /// rule for its type. Parameters can have an example value, otherwise a default example for
+    /// the type is used.
+    ///
+    /// ```
+    /// use pact_consumer::builders::RequestBuilder;
+    /// use pact_consumer::prelude::*;
+    ///
+    /// let request = RequestBuilder::default()
+    ///     .path_template("/orders/{id:integer=1234}/items/{itemId:uuid}")
+    ///     .build();
+    /// assert_eq!(request.path, "/orders/1234/items/e2490de5-5bd3-43d5-b7c4-526e33f71304");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If the path is an invalid template (i.e. has a parameter with an unknown type).
+    pub fn path_template<S: Into<String>>(&mut self, template: S) -> &mut Self {
+        let template = parse_path_template(template.into());
+        self.request.path = template.to_example();
+        self.request.matching_rules.add_rules(Category::PATH, template.matching_rules());
+        self
+    }
+
     /// Specify the request path with generators. Defaults to `"/"`.
     pub fn path_from_provider_state<E, P: Into<StringPattern>>(&mut self, expression: E, path: P) -> &mut Self
         where
@@ -102,6 +131,51 @@ impl RequestBuilder {
         self
     }
 
+    /// Specify the request path with a template (see `path_template`) and generators. If the
+    /// expression has the same number of path segments as the template, a provider state generator
+    /// is added for each parameter segment that has an expression in the corresponding segment.
+    /// Otherwise a generator is added for the whole path.
+    ///
+    /// ```
+    /// use pact_consumer::builders::RequestBuilder;
+    /// use pact_consumer::prelude::*;
+    ///
+    /// let request = RequestBuilder::default()
+    ///     .path_template_from_provider_state("/orders/${orderId}/items/${itemId}", "/orders/{id:integer}/items/{itemId:uuid}")
+    ///     .build();
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If the path is an invalid template (i.e. has a parameter with an unknown type).
+    pub fn path_template_from_provider_state<E, S>(&mut self, expression: E, template: S) -> &mut Self
+        where
+          E: Into<String>,
+          S: Into<String>
+    {
+        let template = parse_path_template(template.into());
+        let expression = expression.into();
+        self.request.path = template.to_example();
+        self.request.matching_rules.add_rules(Category::PATH, template.matching_rules());
+        {
+            let generators = self.generators();
+            let expression_segments = path_segments(&expression);
+            if template.segments.len() == expression_segments.len() {
+                for (index, (segment, expression)) in template.segments.iter().zip(expression_segments).enumerate() {
+                    if let PathSegment::Parameter { param_type, .. } = segment {
+                        if expression.contains("${") {
+                            generators.add_generator_with_subcategory(&GeneratorCategory::PATH, segment_path(index),
+                              Generator::ProviderStateGenerator(expression.to_string(), Some(param_type.data_type())));
+                        }
+                    }
+                }
+            } else {
+                generators.add_generator(&GeneratorCategory::PATH, Generator::ProviderStateGenerator(expression, Some(DataType::STRING)))
+            }
+        }
+        self
+    }
+
     /// Specify a query parameter. You may pass either a single value or
     /// a list of values to represent a repeated parameter.
     ///
@@ -146,6 +220,127 @@ impl RequestBuilder {
         self
     }
 
+    /// Specify a query parameter with a nested value, which is encoded using bracket notation.
+    /// Any matching rules in the pattern are applied to the decoded value of the parameter,
+    /// in the same way as for a JSON body.
+    ///
+    /// ```
+    /// use pact_consumer::*;
+    /// use pact_consumer::builders::RequestBuilder;
+    ///
+    /// // Results in the query string `filter[status]=open&filter[tags][]=a`
+    /// RequestBuilder::default()
+    ///     .query_param_nested("filter", json_pattern!({
+    ///         "status": term!("^(open|closed)$", "open"),
+    ///         "tags": each_like!("a")
+    ///     }));
+    /// ```
+    pub fn query_param_nested<K, V>(&mut self, key: K, value: V) -> &mut Self
+    where
+        K: Into<String>,
+        V: Into<JsonPattern>,
+    {
+        let key = key.into();
+        let value = value.into();
+
+        let query = self.request.query.get_defaulting();
+        for (name, example) in encode_nested_query_param(&key, &value.to_example()) {
+            query.entry(name).or_insert_with(Default::default).push(Some(example));
+        }
+
+        let mut path = DocPath::root();
+        path.push_field(key);
+        value.extract_matching_rules(
+            path,
+            self.request.matching_rules.add_category("query"),
+        );
+
+        self
+    }
+
+    /// Specify a query parameter with a JSON encoded value (i.e. `q={"age":{"gt":3}}`). Any
+    /// matching rules in the pattern are applied to the decoded value of the parameter, in the
+    /// same way as for a JSON body.
+    ///
+    /// ```
+    /// use pact_consumer::*;
+    /// use pact_consumer::builders::RequestBuilder;
+    ///
+    /// RequestBuilder::default()
+    ///     .query_param_json("q", json_pattern!({
+    ///         "age": { "gt": like!(3) }
+    ///     }));
+    /// ```
+    pub fn query_param_json<K, V>(&mut self, key: K, value: V) -> &mut Self
+    where
+        K: Into<String>,
+        V: Into<JsonPattern>,
+    {
+        let key = key.into();
+        let value = value.into();
+
+        self.request
+            .query
+            .get_defaulting()
+            .entry(key.clone())
+            .or_insert_with(Default::default)
+            .push(Some(value.to_example().to_string()));
+
+        let mut path = DocPath::root();
+        path.push_field(key);
+        value.extract_matching_rules(
+            path,
+            self.request.matching_rules.add_category("query"),
+        );
+
+        self
+    }
+
+    /// Specify a GraphQL query. This sets the request method to `POST` and the `query` field of
+    /// the JSON body to the query document. When matching, the query is compared with the
+    /// normalised form of the operation, so differences in whitespace, field order and fragments
+    /// are ignored.
+    ///
+    /// ```
+    /// use pact_consumer::*;
+    /// use pact_consumer::builders::RequestBuilder;
+    ///
+    /// RequestBuilder::default()
+    ///     .path("/graphql")
+    ///     .graphql_query("query GetUser($id: ID!) { user(id: $id) { id name } }")
+    ///     .graphql_variables(json_pattern!({ "id": like!("100") }));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the query is not a valid GraphQL document.
+    pub fn graphql_query<Q: Into<String>>(&mut self, query: Q) -> &mut Self {
+        let query = query.into();
+        if let Err(err) = validate_document(&query) {
+            panic!("'{}' is not a valid GraphQL document: {}", query, err);
+        }
+        self.post();
+        let (body, rules) = self.body_and_matching_rules_mut();
+        set_json_body_field(body, rules, "query", JsonPattern::from(query));
+        self
+    }
+
+    /// Specify the variables for a GraphQL query. Any matching rules in the pattern are applied to
+    /// the `variables` field of the body.
+    pub fn graphql_variables<V: Into<JsonPattern>>(&mut self, variables: V) -> &mut Self {
+        let (body, rules) = self.body_and_matching_rules_mut();
+        set_json_body_field(body, rules, "variables", variables.into());
+        self
+    }
+
+    /// Specify the name of the operation to execute, for GraphQL documents with more than one
+    /// operation.
+    pub fn graphql_operation_name<N: Into<String>>(&mut self, name: N) -> &mut Self {
+        let (body, rules) = self.body_and_matching_rules_mut();
+        set_json_body_field(body, rules, "operationName", JsonPattern::from(name.into()));
+        self
+    }
+
     /// Build the specified `Request` object.
     pub fn build(&self) -> Request {
          self.request.as_v3_request()
@@ -266,6 +461,13 @@ impl RequestBuilder {
   }
 }
 
+fn parse_path_template(template: String) -> PathTemplate {
+    match PathTemplate::parse(&template) {
+        Ok(template) => template,
+        Err(err) => panic!("'{}' is not a valid path template - {}", template, err)
+    }
+}
+
 impl Default for RequestBuilder {
     fn default() -> Self {
         RequestBuilder {
@@ -340,6 +542,59 @@ fn path_generator() {
     assert_requests_with_context_do_not_match!(actual, expected, bad_context);
 }
 
+#[test]
+fn path_template_pattern() {
+    let pattern = PactBuilder::new("C", "P")
+        .interaction("I", "", |mut i| {
+            i.request.path_template("/orders/{id:integer=1234}/items/{itemId:uuid}");
+            i
+        })
+        .build();
+    let good = PactBuilder::new("C", "P")
+        .interaction("I", "", |mut i| { i.request.path("/orders/99/items/3f5e6f1a-7c0d-4b9e-8d4e-1a2b3c4d5e6f"); i })
+        .build();
+    let bad = PactBuilder::new("C", "P")
+        .interaction("I", "", |mut i| { i.request.path("/orders/abc/items/3f5e6f1a-7c0d-4b9e-8d4e-1a2b3c4d5e6f"); i })
+        .build();
+    assert_requests_match!(good, pattern);
+    assert_requests_do_not_match!(bad, pattern);
+}
+
+#[test]
+fn path_with_braces_is_not_a_template() {
+    let request = RequestBuilder::default().path("/users/{id}").build();
+    assert_eq!(request.path, "/users/{id}");
+    assert!(request.matching_rules.rules_for_category(Category::PATH).unwrap_or_default().is_empty());
+
+    let request = RequestBuilder::default().path("/a{b}").build();
+    assert_eq!(request.path, "/a{b}");
+}
+
+#[test]
+fn path_template_generator() {
+    let actual = PactBuilder::new("C", "P")
+      .interaction("I", "", |mut i| {
+          i.request.path_template_from_provider_state("/orders/${orderId}/items/1", "/orders/{id:integer}/items/{itemId:integer=1}");
+          i
+      })
+      .build();
+
+    let expected = PactBuilder::new("C", "P")
+      .interaction("I", "", |mut i| {
+          i.request.path("/orders/5678/items/1");
+          i
+      })
+      .build();
+
+    let good_context = &mut HashMap::new();
+    good_context.insert("orderId", json!(5678));
+    assert_requests_with_context_match!(actual, expected, good_context);
+
+    let bad_context = &mut HashMap::new();
+    bad_context.insert("orderId", json!(1));
+    assert_requests_with_context_do_not_match!(actual, expected, bad_context);
+}
+
 #[test]
 fn query_param_pattern() {
     let pattern = PactBuilder::new("C", "P")
@@ -358,6 +613,58 @@ fn query_param_pattern() {
     assert_requests_do_not_match!(bad, pattern);
 }
 
+#[test]
+fn query_param_nested_pattern() {
+    let pattern = PactBuilder::new("C", "P")
+        .interaction("I", "", |mut i| {
+            i.request.query_param_nested("filter", json_pattern!({
+                "status": term!("^(open|closed)$", "open"),
+                "tags": each_like!("a")
+            }));
+            i
+        })
+        .build();
+    let good = PactBuilder::new("C", "P")
+        .interaction("I", "", |mut i| {
+            i.request
+                .query_param("filter[status]", "closed")
+                .query_param("filter[tags][]", "x")
+                .query_param("filter[tags][]", "y");
+            i
+        })
+        .build();
+    let bad = PactBuilder::new("C", "P")
+        .interaction("I", "", |mut i| {
+            i.request
+                .query_param("filter[status]", "pending")
+                .query_param("filter[tags][]", "x");
+            i
+        })
+        .build();
+    assert_requests_match!(good, pattern);
+    assert_requests_do_not_match!(bad, pattern);
+}
+
+#[test]
+fn query_param_json_pattern() {
+    let pattern = PactBuilder::new("C", "P")
+        .interaction("I", "", |mut i| {
+            i.request.query_param_json("q", json_pattern!({
+                "age": { "gt": like!(3) }
+            }));
+            i
+        })
+        .build();
+    let good = PactBuilder::new("C", "P")
+        .interaction("I", "", |mut i| { i.request.query_param("q", r#"{"age":{"gt":21}}"#); i })
+        .build();
+    let bad = PactBuilder::new("C", "P")
+        .interaction("I", "", |mut i| { i.request.query_param("q", r#"{"age":{"gt":"21"}}"#); i })
+        .build();
+    assert_requests_match!(good, pattern);
+    assert_requests_do_not_match!(bad, pattern);
+}
+
 #[test]
 fn query_param_with_underscore() {
     let pattern = PactBuilder::new("C", "P")
@@ -407,3 +714,48 @@ fn term_does_not_require_anchors() {
     assert_requests_do_not_match!(bad1, pattern);
     assert_requests_do_not_match!(bad2, pattern);
 }
+
+#[test]
+fn graphql_query_pattern() {
+    let pattern = PactBuilder::new("C", "P")
+        .interaction("I", "", |mut i| {
+            i.request
+                .path("/graphql")
+                .graphql_query("query GetUser($id: ID!) { user(id: $id) { id name } }")
+                .graphql_variables(json_pattern!({ "id": like!("100") }));
+            i
+        })
+        .build();
+    let good = PactBuilder::new("C", "P")
+        .interaction("I", "", |mut i| {
+            i.request
+                .post()
+                .path("/graphql")
+                .json_body(json_pattern!({
+                    "query": "query GetUser($id: ID!) {\n  user(id: $id) { name, id }\n}",
+                    "variables": { "id": "200" }
+                }));
+            i
+        })
+        .build();
+    let bad = PactBuilder::new("C", "P")
+        .interaction("I", "", |mut i| {
+            i.request
+                .post()
+                .path("/graphql")
+                .json_body(json_pattern!({
+                    "query": "query GetUser($id: ID!) { user(id: $id) { id email } }",
+                    "variables": { "id": "200" }
+                }));
+            i
+        })
+        .build();
+    assert_requests_match!(good, pattern);
+    assert_requests_do_not_match!(bad, pattern);
+}
+
+#[test]
+#[should_panic]
+fn graphql_query_with_invalid_document() {
+    RequestBuilder::default().graphql_query("{ user { id }");
+}
@@ -5,7 +5,8 @@ use maplit::*;
 use pact_models::bodies::OptionalBody;
 use pact_models::generators::Generators;
 #[cfg(feature = "plugins")] use pact_models::http_parts::HttpPart;
-use pact_models::matchingrules::MatchingRules;
+use pact_models::matchingrules::{MatchingRule, MatchingRules, RuleLogic};
+use pact_models::path_exp::DocPath;
 use pact_models::prelude::ContentType;
 use pact_models::response::Response;
 use pact_models::v4::http_parts::{body_from_json, HttpResponse};
@@ -15,6 +16,9 @@ use pact_models::v4::interaction::InteractionMarkup;
 use serde_json::Value;
 #[allow(unused_imports)] use tracing::debug;
 
+use pact_matching::sse::{EVENT_STREAM_CONTENT_TYPE, parse_event_stream, ServerSentEvent, to_event_stream};
+
+use crate::builders::http_part_builder::set_json_body_field;
 use crate::prelude::*;
 
 #[cfg(not(feature = "plugins"))]
@@ -77,6 +81,89 @@ impl ResponseBuilder {
         self.status(404)
     }
 
+    /// Specify the `data` field of a GraphQL response. Any matching rules in the pattern are
+    /// applied to the `data` field of the body.
+    ///
+    /// ```
+    /// use pact_consumer::*;
+    /// use pact_consumer::builders::ResponseBuilder;
+    ///
+    /// ResponseBuilder::default()
+    ///     .graphql_data(json_pattern!({
+    ///         "user": { "id": like!("100"), "name": like!("Fred") }
+    ///     }));
+    /// ```
+    pub fn graphql_data<D: Into<JsonPattern>>(&mut self, data: D) -> &mut Self {
+        let (body, rules) = self.body_and_matching_rules_mut();
+        set_json_body_field(body, rules, "data", data.into());
+        self
+    }
+
+    /// Specify the `errors` field of a GraphQL response. If no errors are specified, any errors
+    /// received will be reported as a mismatch.
+    pub fn graphql_errors<E: Into<JsonPattern>>(&mut self, errors: E) -> &mut Self {
+        let (body, rules) = self.body_and_matching_rules_mut();
+        set_json_body_field(body, rules, "errors", errors.into());
+        self
+    }
+
+    /// Add an event to a Server-Sent Event (`text/event-stream`) response. The events are matched
+    /// in order, and any matching rules in the data pattern are applied to the `data` of the event
+    /// (JSON data can be matched with nested rules). The event IDs are not matched.
+    ///
+    /// ```
+    /// use pact_consumer::*;
+    /// use pact_consumer::builders::ResponseBuilder;
+    ///
+    /// ResponseBuilder::default()
+    ///     .sse_event(Some("price"), json_pattern!({ "symbol": "ABC", "price": like!(10.5) }))
+    ///     .sse_event(None, like!("heartbeat"));
+    /// ```
+    pub fn sse_event<D: Into<JsonPattern>>(&mut self, event: Option<&str>, data: D) -> &mut Self {
+        let data = data.into();
+        {
+            let (headers, _) = self.headers_and_matching_rules_mut();
+            headers.retain(|name, _| !name.eq_ignore_ascii_case("content-type"));
+            headers.insert("Content-Type".to_string(), vec![EVENT_STREAM_CONTENT_TYPE.to_string()]);
+        }
+
+        let (body, rules) = self.body_and_matching_rules_mut();
+        let mut events = match body {
+            OptionalBody::Present(bytes, _, _) => parse_event_stream(&String::from_utf8_lossy(bytes)),
+            _ => vec![]
+        };
+        let example = match data.to_example() {
+            Value::String(text) => text,
+            json => json.to_string()
+        };
+        events.push(ServerSentEvent {
+            event: event.map(|event| event.to_string()),
+            data: example,
+            .. ServerSentEvent::default()
+        });
+        *body = OptionalBody::Present(Bytes::from(to_event_stream(&events)),
+            Some(ContentType::from(EVENT_STREAM_CONTENT_TYPE)), None);
+
+        let mut path = DocPath::root();
+        path.push_index(events.len() - 1).push_field("data");
+        data.extract_matching_rules(path, rules.add_category("body"));
+        self
+    }
+
+    /// Allow the number of events in a Server-Sent Event response to vary between the minimum and
+    /// (optional) maximum. Additional events are matched against the first event. When verifying
+    /// the provider, the event stream is read until the maximum number of events have been
+    /// received (or the minimum if there is no maximum).
+    pub fn sse_event_count(&mut self, min: usize, max: Option<usize>) -> &mut Self {
+        let (_, rules) = self.body_and_matching_rules_mut();
+        let rule = match max {
+            Some(max) => MatchingRule::MinMaxType(min, max),
+            None => MatchingRule::MinType(min)
+        };
+        rules.add_category("body").add_rule(DocPath::root(), rule, RuleLogic::And);
+        self
+    }
+
     /// Build the specified `Response` object.
     pub fn build(&self) -> Response {
         self.response.as_v3_response()
@@ -0,0 +1,408 @@
+//! Mock server for synchronous messages sent over a WebSocket connection
+
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::panic::RefUnwindSafe;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::anyhow;
+use bytes::Bytes;
+use futures::{SinkExt, StreamExt};
+use itertools::Itertools;
+use maplit::hashmap;
+use pact_models::bodies::OptionalBody;
+use pact_models::generators::GeneratorTestMode;
+use pact_models::pact::{Pact, ReadWritePact, write_pact};
+use pact_models::PactSpecification;
+use pact_models::v4::message_parts::MessageContents;
+use pact_models::v4::pact::V4Pact;
+use pact_models::v4::sync_message::SynchronousMessage;
+use serde_json::Value;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+use tokio::task::JoinSet;
+use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+use tokio_tungstenite::tungstenite::Message as Frame;
+use tracing::{debug, info, trace, warn};
+use url::Url;
+
+use pact_matching::{match_sync_message_request, Mismatch};
+use pact_matching::generators::apply_generators_to_sync_message;
+use pact_matching::metrics::{MetricEvent, send_metrics};
+use pact_mock_server::matching::MatchResult;
+use pact_mock_server::mock_server::MockServerMetrics;
+
+use crate::mock_server::ValidatingMockServer;
+use crate::util::panic_or_print_error;
+
+/// Transport key that is set on the interactions in the pact file
+pub const WEBSOCKET_TRANSPORT: &str = "websocket";
+
+/// If the catalogue entry is for the built-in WebSocket transport
+pub(crate) fn is_websocket_entry(catalog_entry: &str) -> bool {
+  matches!(catalog_entry, "websocket" | "ws" | "transport/websocket" | "transport/ws")
+}
+
+/// Results of the frames received by the mock server
+#[derive(Debug, Default)]
+struct WebSocketMockServerState {
+  // Descriptions of the interactions that have been received
+  received: HashSet<String>,
+  // Mismatches for frames that did not match any interaction
+  mismatches: Vec<(String, Vec<Mismatch>)>
+}
+
+/// A mock server that accepts WebSocket connections and replies to each request frame with the
+/// responses of the matching synchronous message in the `Pact`. The handshake headers are used as
+/// the request metadata.
+///
+/// Because this is intended for use in tests, it will panic if something goes
+/// wrong.
+pub struct ValidatingWebSocketMockServer {
+  // A description of our mock server, for use in error messages.
+  description: String,
+  // The URL of our mock server.
+  url: Url,
+  // The pact that the mock server is using
+  pact: V4Pact,
+  // Results of the frames received
+  state: Arc<Mutex<WebSocketMockServerState>>,
+  // Signal to shut the mock server down
+  shutdown_tx: Option<oneshot::Sender<()>>,
+  // Signal received when the server thread is done executing
+  done_rx: Option<std::sync::mpsc::Receiver<()>>,
+  // Output directory to write pact files
+  output_dir: Option<PathBuf>
+}
+
+impl ValidatingWebSocketMockServer {
+  /// Create a new mock server which handles the synchronous messages in the pact, and runs in a
+  /// background thread
+  pub fn start(
+    pact: Box<dyn Pact + Send + Sync>,
+    output_dir: Option<PathBuf>
+  ) -> anyhow::Result<Box<dyn ValidatingMockServer>> {
+    let pact = pact.as_v4_pact()?;
+    let state = Arc::new(Mutex::new(WebSocketMockServerState::default()));
+    let (shutdown_tx, shutdown_rx) = oneshot::channel();
+    let (done_tx, done_rx) = std::sync::mpsc::channel::<()>();
+    let (addr_tx, addr_rx) = std::sync::mpsc::channel::<anyhow::Result<SocketAddr>>();
+
+    let server_pact = pact.clone();
+    let server_state = state.clone();
+    std::thread::Builder::new()
+      .name(format!("test({})-pact-websocket-mock-server", std::thread::current().name().unwrap_or("<unknown>")))
+      .spawn(move || {
+        let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+          Ok(runtime) => runtime,
+          Err(err) => {
+            let _ = addr_tx.send(Err(anyhow!(err)));
+            return;
+          }
+        };
+        runtime.block_on(async move {
+          match TcpListener::bind("127.0.0.1:0").await {
+            Ok(listener) => {
+              let _ = addr_tx.send(listener.local_addr().map_err(|err| anyhow!(err)));
+              run_server(listener, server_pact, server_state, shutdown_rx).await;
+            }
+            Err(err) => {
+              let _ = addr_tx.send(Err(anyhow!(err)));
+            }
+          }
+        });
+        let _ = done_tx.send(());
+      })?;
+
+    let addr = addr_rx.recv()??;
+    Self::create(pact, addr, state, shutdown_tx, Some(done_rx), output_dir)
+  }
+
+  /// Create a new mock server which handles the synchronous messages in the pact, and runs in a
+  /// background task in the current Tokio runtime.
+  pub async fn start_async(
+    pact: Box<dyn Pact + Send + Sync>,
+    output_dir: Option<PathBuf>
+  ) -> anyhow::Result<Box<dyn ValidatingMockServer>> {
+    let pact = pact.as_v4_pact()?;
+    let state = Arc::new(Mutex::new(WebSocketMockServerState::default()));
+    let (shutdown_tx, shutdown_rx) = oneshot::channel();
+
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    let server_pact = pact.clone();
+    let server_state = state.clone();
+    tokio::spawn(async move {
+      run_server(listener, server_pact, server_state, shutdown_rx).await;
+    });
+
+    Self::create(pact, addr, state, shutdown_tx, None, output_dir)
+  }
+
+  fn create(
+    pact: V4Pact,
+    addr: SocketAddr,
+    state: Arc<Mutex<WebSocketMockServerState>>,
+    shutdown_tx: oneshot::Sender<()>,
+    done_rx: Option<std::sync::mpsc::Receiver<()>>,
+    output_dir: Option<PathBuf>
+  ) -> anyhow::Result<Box<dyn ValidatingMockServer>> {
+    let description = format!("{}/{}", pact.consumer.name, pact.provider.name);
+    let url = Url::parse(&format!("ws://{}", addr))?;
+    debug!(%url, "Started WebSocket mock server for {}", description);
+    Ok(Box::new(ValidatingWebSocketMockServer {
+      description,
+      url,
+      pact,
+      state,
+      shutdown_tx: Some(shutdown_tx),
+      done_rx,
+      output_dir
+    }))
+  }
+
+  /// Helper function called by our `drop` implementation
+  fn drop_helper(&mut self) -> anyhow::Result<()> {
+    if let Some(shutdown_tx) = self.shutdown_tx.take() {
+      let _ = shutdown_tx.send(());
+    }
+    // The server task can't run while the async version is blocked here, so only wait for the thread
+    if let Some(done_rx) = &self.done_rx {
+      if done_rx.recv_timeout(Duration::from_secs(3)).is_err() {
+        warn!("Timed out waiting for the WebSocket mock server to finish");
+      }
+    }
+
+    // Send any metrics in another thread as this thread could be panicking due to an assertion.
+    let interactions = self.pact.interactions.len();
+    std::thread::spawn(move || {
+      send_metrics(MetricEvent::ConsumerTestRun {
+        interactions,
+        test_framework: "pact_consumer".to_string(),
+        app_name: "pact_consumer".to_string(),
+        app_version: env!("CARGO_PKG_VERSION").to_string()
+      });
+    });
+
+    let state = self.state.lock().unwrap();
+    let missing = self.pact.interactions.iter()
+      .filter_map(|interaction| interaction.as_v4_sync_message())
+      .filter(|message| !state.received.contains(&message.description))
+      .map(|message| message.description)
+      .collect_vec();
+
+    if state.mismatches.is_empty() && missing.is_empty() {
+      self.write_pact()
+    } else {
+      let mut message = format!("WebSocket mock server {} failed verification:\n", self.description);
+      let mut index = 0;
+      for (frame, mismatches) in &state.mismatches {
+        index += 1;
+        let _ = writeln!(&mut message, "    {}) {} - the following mismatches occurred:", index, frame);
+        for (n, mismatch) in mismatches.iter().enumerate() {
+          let _ = writeln!(&mut message, "        {}.{}) {}", index, n + 1, mismatch.description());
+        }
+      }
+      for description in missing {
+        index += 1;
+        let _ = writeln!(&mut message, "    {}) Expected a request for '{}' but it was not received", index, description);
+      }
+      Err(anyhow!(message))
+    }
+  }
+
+  fn write_pact(&self) -> anyhow::Result<()> {
+    let output_dir = self.output_dir.as_ref().map(|dir| dir.to_string_lossy().to_string())
+      .unwrap_or_else(|| {
+        let val = env::var("PACT_OUTPUT_DIR");
+        debug!("env:PACT_OUTPUT_DIR = {:?}", val);
+        val.unwrap_or_else(|_| "target/pacts".to_owned())
+      });
+    let overwrite = env::var("PACT_OVERWRITE")
+      .unwrap_or_else(|_| "false".to_owned()) == "true";
+    debug!("env:PACT_OVERWRITE = {:?}", overwrite);
+
+    let mut pact = self.pact.clone();
+    for interaction in &mut pact.interactions {
+      if interaction.as_v4_sync_message().is_some() {
+        interaction.set_transport(Some(WEBSOCKET_TRANSPORT.to_string()));
+      }
+    }
+
+    let mut filename = PathBuf::from(output_dir);
+    filename.push(pact.default_file_name());
+
+    info!("Writing pact out to '{}'", filename.display());
+    write_pact(pact.boxed(), filename.as_path(), PactSpecification::V4, overwrite)
+  }
+}
+
+async fn run_server(
+  listener: TcpListener,
+  pact: V4Pact,
+  state: Arc<Mutex<WebSocketMockServerState>>,
+  mut shutdown_rx: oneshot::Receiver<()>
+) {
+  let pact = Arc::new(pact);
+  let mut connections = JoinSet::new();
+  loop {
+    tokio::select! {
+      _ = &mut shutdown_rx => break,
+      result = listener.accept() => match result {
+        Ok((stream, addr)) => {
+          trace!(%addr, "Accepted connection");
+          connections.spawn(handle_connection(stream, pact.clone(), state.clone()));
+        }
+        Err(err) => warn!("Failed to accept a WebSocket connection: {}", err)
+      }
+    }
+  }
+
+  // Give any frames that are still being processed a chance to complete
+  let pending = async { while connections.join_next().await.is_some() {} };
+  if tokio::time::timeout(Duration::from_millis(500), pending).await.is_err() {
+    debug!("WebSocket connections are still open after the mock server was shutdown");
+  }
+}
+
+async fn handle_connection(
+  stream: TcpStream,
+  pact: Arc<V4Pact>,
+  state: Arc<Mutex<WebSocketMockServerState>>
+) {
+  let mut metadata = hashmap!{};
+  // The error type of the callback is defined by tungstenite
+  #[allow(clippy::result_large_err)]
+  let callback = |request: &Request, response: Response| {
+    for (name, value) in request.headers() {
+      if let Ok(value) = value.to_str() {
+        metadata.insert(name.as_str().to_string(), Value::String(value.to_string()));
+      }
+    }
+    Ok(response)
+  };
+  let mut ws = match tokio_tungstenite::accept_hdr_async(stream, callback).await {
+    Ok(ws) => ws,
+    Err(err) => {
+      warn!("WebSocket handshake failed: {}", err);
+      return;
+    }
+  };
+
+  while let Some(frame) = ws.next().await {
+    let data = match frame {
+      Ok(Frame::Text(text)) => Bytes::from(text),
+      Ok(Frame::Binary(data)) => Bytes::from(data),
+      Ok(Frame::Close(_)) => break,
+      Ok(frame) => {
+        trace!("Ignoring WebSocket control frame {:?}", frame);
+        continue;
+      }
+      Err(err) => {
+        warn!("Failed to read from the WebSocket connection: {}", err);
+        break;
+      }
+    };
+
+    match find_interaction(&pact, &data, &metadata).await {
+      Ok(message) => {
+        state.lock().unwrap().received.insert(message.description.clone());
+        let (_, responses) = apply_generators_to_sync_message(&message, &GeneratorTestMode::Consumer,
+          &hashmap!{}, &pact.plugin_data(), &message.plugin_config).await;
+        for response in responses {
+          if let Err(err) = ws.send(response_frame(&response)).await {
+            warn!("Failed to send the response frame: {}", err);
+          }
+        }
+      }
+      Err(mismatches) => {
+        let frame = String::from_utf8_lossy(&data).to_string();
+        state.lock().unwrap().mismatches.push((format!("Frame '{}'", frame), mismatches));
+      }
+    }
+  }
+}
+
+/// Finds the synchronous message whose request matches the received frame. If none match, the
+/// mismatches for the closest interaction are returned.
+async fn find_interaction(
+  pact: &V4Pact,
+  data: &Bytes,
+  metadata: &HashMap<String, Value>
+) -> Result<SynchronousMessage, Vec<Mismatch>> {
+  let boxed_pact: Box<dyn Pact + Send + Sync + RefUnwindSafe> = pact.boxed();
+  let mut results = vec![];
+  for message in pact.interactions.iter().filter_map(|i| i.as_v4_sync_message()) {
+    let content_type = message.request.message_content_type();
+    let mut actual_metadata = metadata.clone();
+    if let Some(content_type) = &content_type {
+      actual_metadata.insert("contentType".to_string(), Value::String(content_type.to_string()));
+    }
+    let actual = SynchronousMessage {
+      request: MessageContents {
+        contents: OptionalBody::Present(data.clone(), content_type, None),
+        metadata: actual_metadata,
+        .. MessageContents::default()
+      },
+      .. SynchronousMessage::default()
+    };
+    let mismatches = match_sync_message_request(&message, &actual, &boxed_pact).await;
+    if mismatches.is_empty() {
+      return Ok(message);
+    }
+    results.push(mismatches);
+  }
+
+  Err(results.into_iter().min_by_key(|mismatches| mismatches.len())
+    .unwrap_or_else(|| vec![Mismatch::BodyMismatch {
+      path: "$".to_string(),
+      expected: None,
+      actual: Some(data.clone()),
+      mismatch: "Received a frame but there are no synchronous messages in the pact".to_string()
+    }]))
+}
+
+/// Text contents are sent as a text frame, everything else as a binary frame
+fn response_frame(response: &MessageContents) -> Frame {
+  let data = response.contents.value().unwrap_or_default();
+  let is_text = response.message_content_type()
+    .map(|ct| ct.is_text())
+    .unwrap_or(true);
+  match (is_text, String::from_utf8(data.to_vec())) {
+    (true, Ok(text)) => Frame::Text(text),
+    _ => Frame::Binary(data.to_vec())
+  }
+}
+
+impl ValidatingMockServer for ValidatingWebSocketMockServer {
+  fn url(&self) -> Url {
+    self.url.clone()
+  }
+
+  fn path(&self, path: &str) -> Url {
+    self.url.join(path).expect("Could not join the path to the base URL")
+  }
+
+  // Frames received over a WebSocket are not HTTP requests, so any mismatches are reported when
+  // the mock server is dropped
+  fn status(&self) -> Vec<MatchResult> {
+    vec![]
+  }
+
+  fn metrics(&self) -> MockServerMetrics {
+    MockServerMetrics::default()
+  }
+}
+
+impl Drop for ValidatingWebSocketMockServer {
+  fn drop(&mut self) {
+    let result = self.drop_helper();
+    if let Err(msg) = result {
+      panic_or_print_error(msg.to_string().as_str());
+    }
+  }
+}
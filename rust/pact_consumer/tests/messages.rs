@@ -120,3 +120,56 @@ fn test_req_res_message_client() {
     expect!(processed.state).to(be_equal_to("VA"));
   }
 }
+
+// Sync messages can also be tested over a WebSocket connection, with each request frame being
+// replied to with the response messages
+#[test_log::test(tokio::test)]
+async fn test_req_res_message_over_websocket() {
+  use futures::{SinkExt, StreamExt};
+  use tokio_tungstenite::tungstenite::Message;
+
+  let mut pact_builder = PactBuilder::new_v4("websocket-consumer", "websocket-provider");
+  pact_builder.synchronous_message_interaction("hello message", |mut i| {
+    i.request_json_body(json_pattern!({
+      "name": like!("mai")
+    }));
+    i.response_json_body(json_pattern!({
+      "street": like!("5th"),
+      "state": like!("VA")
+    }));
+    i
+  });
+  let mock_server = pact_builder.start_mock_server(Some("websocket"));
+  expect!(mock_server.url().scheme()).to(be_equal_to("ws"));
+
+  let (mut ws, _) = tokio_tungstenite::connect_async(mock_server.url().as_str()).await.unwrap();
+  ws.send(Message::Text(r#"{"name":"Fred"}"#.to_string())).await.unwrap();
+  let reply = ws.next().await.unwrap().unwrap().into_text().unwrap();
+  let _ = ws.close(None).await;
+
+  let handler = MessageHandler::new();
+  let processed = handler.process(Bytes::from(reply)).unwrap();
+  expect!(processed.street).to(be_equal_to("5th"));
+  expect!(processed.state).to(be_equal_to("VA"));
+}
+
+#[test_log::test(tokio::test)]
+#[should_panic]
+async fn test_req_res_message_over_websocket_with_mismatch() {
+  use futures::SinkExt;
+  use tokio_tungstenite::tungstenite::Message;
+
+  let mut pact_builder = PactBuilder::new_v4("websocket-consumer", "websocket-provider");
+  pact_builder.synchronous_message_interaction("hello message with mismatch", |mut i| {
+    i.request_json_body(json_pattern!({
+      "name": like!("mai")
+    }));
+    i.response_json_body(json_pattern!({ "state": like!("VA") }));
+    i
+  });
+  let mock_server = pact_builder.start_mock_server(Some("websocket"));
+
+  let (mut ws, _) = tokio_tungstenite::connect_async(mock_server.url().as_str()).await.unwrap();
+  ws.send(Message::Text(r#"{"name":100}"#.to_string())).await.unwrap();
+  let _ = ws.close(None).await;
+}
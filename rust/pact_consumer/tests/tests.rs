@@ -357,3 +357,31 @@ fn each_key_matcher()     {
     .unwrap();
   expect!(response.status().is_server_error()).to(be_true());
 }
+
+#[test_log::test(tokio::test)]
+async fn server_sent_events_response() {
+  let price_service = PactBuilderAsync::new_v4("Consumer", "Price Service")
+    .interaction("a request for price updates", "", |mut i| async move {
+      i.request.path("/prices").header("Accept", "text/event-stream");
+      i.response
+        .ok()
+        .sse_event(Some("price"), json_pattern!({ "symbol": "ABC", "price": like!(10.5) }))
+        .sse_event(Some("price"), json_pattern!({ "symbol": "DEF", "price": like!(1.25) }))
+        .sse_event_count(1, Some(10));
+      i.clone()
+    })
+    .await
+    .start_mock_server(None);
+
+  let response = Client::new().get(price_service.path("/prices"))
+    .header("Accept", "text/event-stream")
+    .send()
+    .await
+    .expect("could not fetch URL");
+  expect!(response.headers().get("content-type").unwrap().to_str().unwrap()).to(be_equal_to("text/event-stream"));
+  let body = response.text().await.expect("could not read response body");
+  let events = pact_matching::sse::parse_event_stream(&body);
+  expect!(events.len()).to(be_equal_to(2));
+  expect!(events[0].event.clone()).to(be_some().value("price"));
+  expect!(serde_json::from_str::<serde_json::Value>(&events[1].data).unwrap()).to(be_equal_to(json!({ "symbol": "DEF", "price": 1.25 })));
+}
@@ -1,13 +1,24 @@
-//! Module provides FFI functions to match values using Pact matching rules
+//! Module provides FFI functions to match values, requests and responses using Pact matching rules
 
+use anyhow::anyhow;
 use bytes::Bytes;
 use libc::{c_char, c_uchar};
+use pact_models::interaction::Interaction;
 use pact_models::matchingrules::MatchingRule;
+use pact_models::pact::Pact;
+use pact_models::PactSpecification;
+use pact_models::sync_interaction::RequestResponseInteraction;
+use pact_models::v4::http_parts::{HttpRequest, HttpResponse};
+use pact_models::v4::interaction::interaction_from_json;
+use pact_models::v4::pact::V4Pact;
+use pact_models::v4::synch_http::SynchronousHttp;
 use serde_json::Value;
 
 use pact_matching::matchers::Matches;
 
-use crate::{as_ref, ffi_fn, safe_str};
+use crate::{as_ref, ffi_fn, Mismatches, RUNTIME, safe_str};
+use crate::mock_server::handles::InteractionHandle;
+use crate::ptr;
 use crate::util::string;
 
 ffi_fn! {
@@ -260,6 +271,181 @@ ffi_fn! {
     }
 }
 
+ffi_fn! {
+    /// Matches an actual HTTP request against the request of the HTTP interaction pointed to by the
+    /// interaction handle, producing a collection of mismatches which is empty if the request
+    /// matched. The returned mismatches can be iterated over with `pactffi_mismatches_get_iter`,
+    /// and must be deleted with `pactffi_mismatches_delete` once they are no longer required.
+    ///
+    /// * interaction - handle to an HTTP interaction
+    /// * request_json - the actual request as a NULL terminated JSON string, in the same format as
+    ///   the request part of a V4 interaction (i.e. `method`, `path`, `query`, `headers` and `body`)
+    ///
+    /// # Safety
+    ///
+    /// The request JSON parameter must be a valid pointer to a NULL terminated string.
+    /// This function must only ever be called from a foreign language. Calling it from a Rust function
+    /// that has a Tokio runtime in its call stack can result in a deadlock.
+    ///
+    /// # Error Handling
+    ///
+    /// Returns NULL if the handle is not valid, does not refer to an HTTP interaction, or the
+    /// request JSON could not be parsed. The error message can be retrieved with
+    /// `pactffi_get_error_message`.
+    fn pactffi_match_request(interaction: InteractionHandle, request_json: *const c_char) -> *const Mismatches {
+      let request_json = safe_str!(request_json);
+      let (expected, pact) = http_interaction_from_handle(interaction)?;
+      let mismatches = match_http_request(expected, pact, request_json)?;
+      ptr::raw_to(mismatches) as *const Mismatches
+    } {
+      std::ptr::null()
+    }
+}
+
+ffi_fn! {
+    /// Matches an actual HTTP response against the response of the HTTP interaction pointed to by
+    /// the interaction handle, producing a collection of mismatches which is empty if the response
+    /// matched. The returned mismatches can be iterated over with `pactffi_mismatches_get_iter`,
+    /// and must be deleted with `pactffi_mismatches_delete` once they are no longer required.
+    ///
+    /// * interaction - handle to an HTTP interaction
+    /// * response_json - the actual response as a NULL terminated JSON string, in the same format as
+    ///   the response part of a V4 interaction (i.e. `status`, `headers` and `body`)
+    ///
+    /// # Safety
+    ///
+    /// The response JSON parameter must be a valid pointer to a NULL terminated string.
+    /// This function must only ever be called from a foreign language. Calling it from a Rust function
+    /// that has a Tokio runtime in its call stack can result in a deadlock.
+    ///
+    /// # Error Handling
+    ///
+    /// Returns NULL if the handle is not valid, does not refer to an HTTP interaction, or the
+    /// response JSON could not be parsed. The error message can be retrieved with
+    /// `pactffi_get_error_message`.
+    fn pactffi_match_response(interaction: InteractionHandle, response_json: *const c_char) -> *const Mismatches {
+      let response_json = safe_str!(response_json);
+      let (expected, pact) = http_interaction_from_handle(interaction)?;
+      let mismatches = match_http_response(expected, pact, response_json)?;
+      ptr::raw_to(mismatches) as *const Mismatches
+    } {
+      std::ptr::null()
+    }
+}
+
+ffi_fn! {
+    /// Matches an actual HTTP request against the request of an HTTP interaction supplied as JSON,
+    /// producing a collection of mismatches which is empty if the request matched. The returned
+    /// mismatches can be iterated over with `pactffi_mismatches_get_iter`, and must be deleted with
+    /// `pactffi_mismatches_delete` once they are no longer required.
+    ///
+    /// * interaction_json - the expected interaction as a NULL terminated JSON string. This can either
+    ///   be a V4 interaction (with a `type` attribute of `Synchronous/HTTP`), or a V1-V3 interaction
+    ///   as found in a Pact file
+    /// * request_json - the actual request as a NULL terminated JSON string, in the same format as
+    ///   the request part of a V4 interaction (i.e. `method`, `path`, `query`, `headers` and `body`)
+    ///
+    /// # Safety
+    ///
+    /// Both parameters must be valid pointers to NULL terminated strings.
+    /// This function must only ever be called from a foreign language. Calling it from a Rust function
+    /// that has a Tokio runtime in its call stack can result in a deadlock.
+    ///
+    /// # Error Handling
+    ///
+    /// Returns NULL if the interaction is not an HTTP interaction, or either of the JSON values
+    /// could not be parsed. The error message can be retrieved with `pactffi_get_error_message`.
+    fn pactffi_match_request_with_json(interaction_json: *const c_char, request_json: *const c_char) -> *const Mismatches {
+      let interaction_json = safe_str!(interaction_json);
+      let request_json = safe_str!(request_json);
+      let expected = http_interaction_from_json(interaction_json)?;
+      let mismatches = match_http_request(expected, V4Pact::default(), request_json)?;
+      ptr::raw_to(mismatches) as *const Mismatches
+    } {
+      std::ptr::null()
+    }
+}
+
+ffi_fn! {
+    /// Matches an actual HTTP response against the response of an HTTP interaction supplied as JSON,
+    /// producing a collection of mismatches which is empty if the response matched. The returned
+    /// mismatches can be iterated over with `pactffi_mismatches_get_iter`, and must be deleted with
+    /// `pactffi_mismatches_delete` once they are no longer required.
+    ///
+    /// * interaction_json - the expected interaction as a NULL terminated JSON string. This can either
+    ///   be a V4 interaction (with a `type` attribute of `Synchronous/HTTP`), or a V1-V3 interaction
+    ///   as found in a Pact file
+    /// * response_json - the actual response as a NULL terminated JSON string, in the same format as
+    ///   the response part of a V4 interaction (i.e. `status`, `headers` and `body`)
+    ///
+    /// # Safety
+    ///
+    /// Both parameters must be valid pointers to NULL terminated strings.
+    /// This function must only ever be called from a foreign language. Calling it from a Rust function
+    /// that has a Tokio runtime in its call stack can result in a deadlock.
+    ///
+    /// # Error Handling
+    ///
+    /// Returns NULL if the interaction is not an HTTP interaction, or either of the JSON values
+    /// could not be parsed. The error message can be retrieved with `pactffi_get_error_message`.
+    fn pactffi_match_response_with_json(interaction_json: *const c_char, response_json: *const c_char) -> *const Mismatches {
+      let interaction_json = safe_str!(interaction_json);
+      let response_json = safe_str!(response_json);
+      let expected = http_interaction_from_json(interaction_json)?;
+      let mismatches = match_http_response(expected, V4Pact::default(), response_json)?;
+      ptr::raw_to(mismatches) as *const Mismatches
+    } {
+      std::ptr::null()
+    }
+}
+
+/// Returns a copy of the HTTP interaction the handle refers to, along with the Pact it belongs to
+/// (required for any plugin configuration).
+fn http_interaction_from_handle(handle: InteractionHandle) -> anyhow::Result<(SynchronousHttp, V4Pact)> {
+  let (interaction, pact) = handle.interaction_with_pact()
+    .ok_or_else(|| anyhow!("Interaction handle {:?} is not valid", handle))?;
+  let interaction = interaction.as_v4_http()
+    .ok_or_else(|| anyhow!("Interaction handle {:?} does not refer to an HTTP interaction", handle))?;
+  Ok((interaction, pact))
+}
+
+/// Parses an HTTP interaction from either V4 or V1-V3 formatted JSON
+fn http_interaction_from_json(json: &str) -> anyhow::Result<SynchronousHttp> {
+  let json = parse_json(json, "interaction")?;
+  let interaction = if json.get("type").is_some() {
+    interaction_from_json("FFI", 0, &json)?.as_v4_http()
+  } else {
+    RequestResponseInteraction::from_json(0, &json, &PactSpecification::V3)?.as_v4_http()
+  };
+  interaction.ok_or_else(|| anyhow!("Interaction JSON is not for an HTTP interaction"))
+}
+
+fn parse_json(json: &str, name: &str) -> anyhow::Result<Value> {
+  serde_json::from_str(json).map_err(|err| anyhow!("Failed to parse {} JSON: {}", name, err))
+}
+
+fn match_http_request(expected: SynchronousHttp, pact: V4Pact, request_json: &str) -> anyhow::Result<Mismatches> {
+  let actual = HttpRequest::from_json(&parse_json(request_json, "request")?)?;
+  let expected_request = expected.request.clone();
+  let interaction = expected.boxed();
+  let pact = pact.boxed();
+  let result = RUNTIME.block_on(async {
+    pact_matching::match_request(expected_request, actual, &pact, &interaction).await
+  });
+  Ok(Mismatches(result.mismatches()))
+}
+
+fn match_http_response(expected: SynchronousHttp, pact: V4Pact, response_json: &str) -> anyhow::Result<Mismatches> {
+  let actual = HttpResponse::from_json(&parse_json(response_json, "response")?)?;
+  let expected_response = expected.response.clone();
+  let interaction = expected.boxed();
+  let pact = pact.boxed();
+  let mismatches = RUNTIME.block_on(async {
+    pact_matching::match_response(expected_response, actual, &pact, &interaction).await
+  });
+  Ok(Mismatches(mismatches))
+}
+
 #[cfg(test)]
 mod tests {
   use std::ffi::{c_char, CString};
@@ -267,7 +453,29 @@ mod tests {
   use expectest::prelude::*;
   use pact_models::matchingrules::MatchingRule;
 
-  use crate::matching::{pactffi_matches_binary_value, pactffi_matches_bool_value, pactffi_matches_f64_value, pactffi_matches_i64_value, pactffi_matches_json_value, pactffi_matches_string_value, pactffi_matches_u64_value};
+  use crate::matching::{
+    pactffi_match_request,
+    pactffi_match_request_with_json,
+    pactffi_match_response,
+    pactffi_match_response_with_json,
+    pactffi_matches_binary_value,
+    pactffi_matches_bool_value,
+    pactffi_matches_f64_value,
+    pactffi_matches_i64_value,
+    pactffi_matches_json_value,
+    pactffi_matches_string_value,
+    pactffi_matches_u64_value
+  };
+  use crate::mock_server::handles::{
+    InteractionPart,
+    pactffi_free_pact_handle,
+    pactffi_new_interaction,
+    pactffi_new_pact,
+    pactffi_response_status,
+    pactffi_with_body,
+    pactffi_with_request
+  };
+  use crate::Mismatches;
 
   #[test_log::test]
   fn pactffi_matches_string_value_test() {
@@ -386,4 +594,124 @@ mod tests {
     let string = unsafe { CString::from_raw(err_result as *mut c_char) };
     expect!(string.to_string_lossy()).to(be_equal_to("Failed to parse actual JSON: EOF while parsing a string at line 1 column 11"));
   }
+
+  #[test_log::test]
+  fn pactffi_match_request_test() {
+    let consumer = CString::new("match_request_consumer").unwrap();
+    let provider = CString::new("match_request_provider").unwrap();
+    let pact_handle = pactffi_new_pact(consumer.as_ptr(), provider.as_ptr());
+    let description = CString::new("a request to match").unwrap();
+    let interaction = pactffi_new_interaction(pact_handle, description.as_ptr());
+    let method = CString::new("POST").unwrap();
+    let path = CString::new("{\"value\":\"/orders/1234\",\"pact:matcher:type\":\"regex\",\"regex\":\"\\\\/orders\\\\/\\\\d+\"}").unwrap();
+    let content_type = CString::new("application/json").unwrap();
+    let body = CString::new("{\"id\":{\"value\":1,\"pact:matcher:type\":\"integer\"}}").unwrap();
+    pactffi_with_request(interaction, method.as_ptr(), path.as_ptr());
+    pactffi_with_body(interaction, InteractionPart::Request, content_type.as_ptr(), body.as_ptr());
+
+    let ok_request = CString::new(r#"{
+      "method": "POST",
+      "path": "/orders/9999",
+      "headers": { "Content-Type": "application/json" },
+      "body": { "content": { "id": 100 } }
+    }"#).unwrap();
+    let result = pactffi_match_request(interaction, ok_request.as_ptr());
+    expect!(result.is_null()).to(be_false());
+    let mismatches = unsafe { Box::from_raw(result as *mut Mismatches) };
+    expect!(mismatches.0.is_empty()).to(be_true());
+
+    let bad_request = CString::new(r#"{
+      "method": "POST",
+      "path": "/orders/abc",
+      "headers": { "Content-Type": "application/json" },
+      "body": { "content": { "id": "abc" } }
+    }"#).unwrap();
+    let result = pactffi_match_request(interaction, bad_request.as_ptr());
+    let mismatches = unsafe { Box::from_raw(result as *mut Mismatches) };
+    let types = mismatches.0.iter().map(|m| m.mismatch_type()).collect::<Vec<_>>();
+    expect!(types).to(be_equal_to(vec!["PathMismatch", "BodyMismatch"]));
+
+    let invalid_request = CString::new("{").unwrap();
+    let result = pactffi_match_request(interaction, invalid_request.as_ptr());
+    expect!(result.is_null()).to(be_true());
+
+    pactffi_free_pact_handle(pact_handle);
+  }
+
+  #[test_log::test]
+  fn pactffi_match_response_test() {
+    let consumer = CString::new("match_response_consumer").unwrap();
+    let provider = CString::new("match_response_provider").unwrap();
+    let pact_handle = pactffi_new_pact(consumer.as_ptr(), provider.as_ptr());
+    let description = CString::new("a response to match").unwrap();
+    let interaction = pactffi_new_interaction(pact_handle, description.as_ptr());
+    let content_type = CString::new("application/json").unwrap();
+    let body = CString::new("{\"name\":{\"value\":\"Fred\",\"pact:matcher:type\":\"type\"}}").unwrap();
+    pactffi_response_status(interaction, 201);
+    pactffi_with_body(interaction, InteractionPart::Response, content_type.as_ptr(), body.as_ptr());
+
+    let ok_response = CString::new(r#"{
+      "status": 201,
+      "headers": { "Content-Type": "application/json" },
+      "body": { "content": { "name": "Mary" } }
+    }"#).unwrap();
+    let result = pactffi_match_response(interaction, ok_response.as_ptr());
+    let mismatches = unsafe { Box::from_raw(result as *mut Mismatches) };
+    expect!(mismatches.0.is_empty()).to(be_true());
+
+    let bad_response = CString::new(r#"{
+      "status": 200,
+      "headers": { "Content-Type": "application/json" },
+      "body": { "content": { "name": 100 } }
+    }"#).unwrap();
+    let result = pactffi_match_response(interaction, bad_response.as_ptr());
+    let mismatches = unsafe { Box::from_raw(result as *mut Mismatches) };
+    let types = mismatches.0.iter().map(|m| m.mismatch_type()).collect::<Vec<_>>();
+    expect!(types).to(be_equal_to(vec!["BodyMismatch", "StatusMismatch"]));
+
+    pactffi_free_pact_handle(pact_handle);
+  }
+
+  #[test_log::test]
+  fn pactffi_match_with_json_test() {
+    let interaction = CString::new(r#"{
+      "type": "Synchronous/HTTP",
+      "description": "a request for an order",
+      "request": {
+        "method": "GET",
+        "path": "/orders/100",
+        "matchingRules": {
+          "path": { "matchers": [{ "match": "regex", "regex": "\\/orders\\/\\d+" }] }
+        }
+      },
+      "response": {
+        "status": 200
+      }
+    }"#).unwrap();
+    let request = CString::new(r#"{ "method": "GET", "path": "/orders/200" }"#).unwrap();
+    let result = pactffi_match_request_with_json(interaction.as_ptr(), request.as_ptr());
+    let mismatches = unsafe { Box::from_raw(result as *mut Mismatches) };
+    expect!(mismatches.0.is_empty()).to(be_true());
+
+    let request = CString::new(r#"{ "method": "PUT", "path": "/orders/200" }"#).unwrap();
+    let result = pactffi_match_request_with_json(interaction.as_ptr(), request.as_ptr());
+    let mismatches = unsafe { Box::from_raw(result as *mut Mismatches) };
+    let types = mismatches.0.iter().map(|m| m.mismatch_type()).collect::<Vec<_>>();
+    expect!(types).to(be_equal_to(vec!["MethodMismatch"]));
+
+    let v3_interaction = CString::new(r#"{
+      "description": "a request for an order",
+      "request": { "method": "GET", "path": "/orders/100" },
+      "response": { "status": 200 }
+    }"#).unwrap();
+    let response = CString::new(r#"{ "status": 404 }"#).unwrap();
+    let result = pactffi_match_response_with_json(v3_interaction.as_ptr(), response.as_ptr());
+    let mismatches = unsafe { Box::from_raw(result as *mut Mismatches) };
+    let types = mismatches.0.iter().map(|m| m.mismatch_type()).collect::<Vec<_>>();
+    expect!(types).to(be_equal_to(vec!["StatusMismatch"]));
+
+    let message = CString::new(r#"{ "type": "Asynchronous/Messages", "description": "a message" }"#).unwrap();
+    let result = pactffi_match_request_with_json(message.as_ptr(), request.as_ptr());
+    expect!(result.is_null()).to(be_true());
+  }
 }
@@ -105,6 +105,7 @@ use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 use std::collections::hash_map::Entry;
 use std::ffi::{CStr, CString};
+use std::panic::RefUnwindSafe;
 use std::path::PathBuf;
 use std::ptr::null_mut;
 use std::str::from_utf8;
@@ -303,6 +304,22 @@ impl InteractionHandle {
       }
     }).flatten()
   }
+
+  /// Returns a copy of the inner Interaction model, along with the Pact model it belongs to
+  ///
+  /// # Errors
+  /// This function acquires a lock on the PACT_HANDLES mutex.
+  pub(crate) fn interaction_with_pact(&self) -> Option<(Box<dyn V4Interaction + Send + Sync + RefUnwindSafe>, V4Pact)> {
+    let handles = PACT_HANDLES.lock().unwrap();
+    let index = (self.interaction_ref >> 16) as u16;
+    let interaction = (self.interaction_ref & 0x0000FFFF) as usize;
+
+    handles.get(&index).and_then(|inner| {
+      let inner = inner.borrow();
+      let interaction = inner.pact.interactions.get(interaction.checked_sub(1)?)?.boxed_v4();
+      Some((interaction, inner.pact.clone()))
+    })
+  }
 }
 
 #[repr(transparent)]
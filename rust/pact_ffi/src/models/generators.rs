@@ -1,24 +1,32 @@
 //! FFI functions to deal with generators and generated values
 
 use std::collections::HashMap;
+use std::panic::RefUnwindSafe;
 use anyhow::anyhow;
 use itertools::Itertools;
 use libc::{c_char, c_ushort};
 use maplit::hashmap;
+use pact_matching::generators::generate_message;
 use pact_models::generators::{
   GeneratorCategory as CoreGeneratorCategory,
+  GeneratorTestMode as CoreGeneratorTestMode,
   GenerateValue,
   Generator,
   NoopVariantMatcher,
   VariantMatcher
 };
+use pact_models::pact::Pact;
 use pact_models::path_exp::DocPath;
+use pact_models::plugins::PluginData;
 use pact_models::v4::http_parts::{HttpRequest, HttpResponse};
+use pact_models::v4::interaction::V4Interaction;
 use pact_models::v4::message_parts::MessageContents;
+use pact_models::v4::pact::V4Pact;
 use serde_json::Value;
 use tracing::{error, warn, trace};
 
-use crate::{as_mut, as_ref, ffi_fn};
+use crate::{as_mut, as_ref, ffi_fn, RUNTIME};
+use crate::mock_server::handles::{InteractionHandle, InteractionPart};
 use crate::util::{ptr, string};
 use crate::util::ptr::{drop_raw, raw_to};
 
@@ -116,6 +124,203 @@ ffi_fn! {
   }
 }
 
+/// Enum defining the test mode that generators are applied in
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum GeneratorTestMode {
+  /// Generate values in the context of the consumer (i.e. for a mock server)
+  Consumer,
+  /// Generate values in the context of the provider (i.e. for a provider verification)
+  Provider
+}
+
+impl From<CoreGeneratorTestMode> for GeneratorTestMode {
+  #[inline]
+  fn from(mode: CoreGeneratorTestMode) -> GeneratorTestMode {
+    match mode {
+      CoreGeneratorTestMode::Consumer => GeneratorTestMode::Consumer,
+      CoreGeneratorTestMode::Provider => GeneratorTestMode::Provider
+    }
+  }
+}
+
+impl From<GeneratorTestMode> for CoreGeneratorTestMode {
+  #[inline]
+  fn from(mode: GeneratorTestMode) -> CoreGeneratorTestMode {
+    match mode {
+      GeneratorTestMode::Consumer => CoreGeneratorTestMode::Consumer,
+      GeneratorTestMode::Provider => CoreGeneratorTestMode::Provider
+    }
+  }
+}
+
+ffi_fn! {
+  /// Generates the request of the HTTP interaction pointed to by the interaction handle, by applying
+  /// any generators defined on it in the given test mode. The optional JSON payload containing any
+  /// generator context is used for generators like `MockServerURL` and `ProviderStateGenerator`
+  /// (which should be the values returned from the Provider State callback function).
+  ///
+  /// Returns the generated request as a JSON string with `method`, `path`, `query`, `headers`
+  /// and `body` attributes, in the same format as the request part of a V4 interaction.
+  /// The returned string must be deleted with `pactffi_string_delete`.
+  ///
+  /// # Safety
+  ///
+  /// This function must only ever be called from a foreign language. Calling it from a Rust function
+  /// that has a Tokio runtime in its call stack can result in a deadlock.
+  ///
+  /// # Error Handling
+  ///
+  /// Returns NULL if the handle is not valid, does not refer to an HTTP interaction or the context
+  /// is not valid JSON.
+  fn pactffi_generate_request(
+    interaction: InteractionHandle,
+    mode: GeneratorTestMode,
+    context_json: *const c_char
+  ) -> *const c_char {
+    let context_entries = context_map(string::optional_str(context_json))?;
+    let context = context_entries.iter().map(|(k, v)| (k.as_str(), v.clone())).collect();
+    let (interaction, _) = interaction_from_handle(interaction)?;
+    let http = interaction.as_v4_http()
+      .ok_or_else(|| anyhow!("'{}' is not an HTTP interaction", interaction.description()))?;
+
+    let request = RUNTIME.block_on(async {
+      pact_matching::generate_request(&http.request, &mode.into(), &context).await
+    });
+    let json = without_rules(request.to_json());
+    string::to_c(&json.to_string())? as *const c_char
+  } {
+    std::ptr::null()
+  }
+}
+
+ffi_fn! {
+  /// Generates the response of the HTTP interaction pointed to by the interaction handle, by applying
+  /// any generators defined on it in the given test mode. The optional JSON payload containing any
+  /// generator context is used for generators like `MockServerURL` and `ProviderStateGenerator`
+  /// (which should be the values returned from the Provider State callback function).
+  ///
+  /// Returns the generated response as a JSON string with `status`, `headers` and `body`
+  /// attributes, in the same format as the response part of a V4 interaction.
+  /// The returned string must be deleted with `pactffi_string_delete`.
+  ///
+  /// # Safety
+  ///
+  /// This function must only ever be called from a foreign language. Calling it from a Rust function
+  /// that has a Tokio runtime in its call stack can result in a deadlock.
+  ///
+  /// # Error Handling
+  ///
+  /// Returns NULL if the handle is not valid, does not refer to an HTTP interaction or the context
+  /// is not valid JSON.
+  fn pactffi_generate_response(
+    interaction: InteractionHandle,
+    mode: GeneratorTestMode,
+    context_json: *const c_char
+  ) -> *const c_char {
+    let context_entries = context_map(string::optional_str(context_json))?;
+    let context = context_entries.iter().map(|(k, v)| (k.as_str(), v.clone())).collect();
+    let (interaction, _) = interaction_from_handle(interaction)?;
+    let http = interaction.as_v4_http()
+      .ok_or_else(|| anyhow!("'{}' is not an HTTP interaction", interaction.description()))?;
+
+    let response = RUNTIME.block_on(async {
+      pact_matching::generate_response(&http.response, &mode.into(), &context).await
+    });
+    let json = without_rules(response.to_json());
+    string::to_c(&json.to_string())? as *const c_char
+  } {
+    std::ptr::null()
+  }
+}
+
+ffi_fn! {
+  /// Generates the contents of the message interaction pointed to by the interaction handle, by
+  /// applying any generators defined on it in the given test mode. The optional JSON payload
+  /// containing any generator context is used for generators like `ProviderStateGenerator`
+  /// (which should be the values returned from the Provider State callback function).
+  ///
+  /// For asynchronous messages the `part` parameter is ignored, and the result is a JSON
+  /// string with `contents` and `metadata` attributes, in the same format as a V4 message.
+  /// For synchronous messages, `part` selects either the request message (which is returned in
+  /// the same format), or the response messages (which are returned as a JSON array).
+  /// The returned string must be deleted with `pactffi_string_delete`.
+  ///
+  /// # Safety
+  ///
+  /// This function must only ever be called from a foreign language. Calling it from a Rust function
+  /// that has a Tokio runtime in its call stack can result in a deadlock.
+  ///
+  /// # Error Handling
+  ///
+  /// Returns NULL if the handle is not valid, does not refer to a message interaction or the context
+  /// is not valid JSON.
+  fn pactffi_generate_message_contents(
+    interaction: InteractionHandle,
+    part: InteractionPart,
+    mode: GeneratorTestMode,
+    context_json: *const c_char
+  ) -> *const c_char {
+    let context_entries = context_map(string::optional_str(context_json))?;
+    let context = context_entries.iter().map(|(k, v)| (k.as_str(), v.clone())).collect();
+    let (interaction, pact) = interaction_from_handle(interaction)?;
+    let mode = mode.into();
+    let plugin_data = pact.plugin_data();
+    let interaction_data = interaction.plugin_config();
+
+    let json = if let Some(message) = interaction.as_v4_async_message() {
+      generate_contents(&message.contents, &mode, &context, &plugin_data, &interaction_data)
+    } else if let Some(message) = interaction.as_v4_sync_message() {
+      match part {
+        InteractionPart::Request => generate_contents(&message.request, &mode, &context,
+          &plugin_data, &interaction_data),
+        InteractionPart::Response => Value::Array(message.response.iter()
+          .map(|contents| generate_contents(contents, &mode, &context, &plugin_data, &interaction_data))
+          .collect())
+      }
+    } else {
+      return Err(anyhow!("'{}' is not a message interaction", interaction.description()));
+    };
+    string::to_c(&json.to_string())? as *const c_char
+  } {
+    std::ptr::null()
+  }
+}
+
+fn interaction_from_handle(
+  handle: InteractionHandle
+) -> anyhow::Result<(Box<dyn V4Interaction + Send + Sync + RefUnwindSafe>, V4Pact)> {
+  handle.interaction_with_pact()
+    .ok_or_else(|| anyhow!("Interaction handle {:?} is not valid", handle))
+}
+
+fn generate_contents(
+  contents: &MessageContents,
+  mode: &CoreGeneratorTestMode,
+  context: &HashMap<&str, Value>,
+  plugin_data: &Vec<PluginData>,
+  interaction_data: &HashMap<String, HashMap<String, Value>>
+) -> Value {
+  let message = RUNTIME.block_on(async {
+    generate_message(&contents.as_v3_message(), mode, context, plugin_data, interaction_data).await
+  });
+  MessageContents {
+    contents: message.contents,
+    metadata: message.metadata,
+    .. MessageContents::default()
+  }.to_json()
+}
+
+/// Removes the matching rules and generators from the JSON form of a request or response, as
+/// they have already been applied
+fn without_rules(mut json: Value) -> Value {
+  if let Value::Object(map) = &mut json {
+    map.remove("matchingRules");
+    map.remove("generators");
+  }
+  json
+}
+
 /// Enum defining the categories that generators can be applied to
 #[repr(C)]
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -296,7 +501,22 @@ mod tests {
   use pact_models::generators::Generator;
   use pact_models::prelude::Generator::{RandomInt, RandomString};
 
+  use serde_json::{json, Value};
+
+  use crate::mock_server::handles::{
+    InteractionPart,
+    pactffi_free_pact_handle,
+    pactffi_new_interaction,
+    pactffi_new_message_interaction,
+    pactffi_new_pact,
+    pactffi_with_body,
+    pactffi_with_request
+  };
   use crate::models::generators::{
+    GeneratorTestMode,
+    pactffi_generate_message_contents,
+    pactffi_generate_request,
+    pactffi_generate_response,
     pactffi_generator_generate_integer,
     pactffi_generator_generate_string,
     pactffi_generator_to_json
@@ -340,4 +560,93 @@ mod tests {
     let json = unsafe { CString::from_raw(json_ptr as *mut c_char) };
     expect!(json.to_string_lossy()).to(be_equal_to("{\"max\":100,\"min\":10,\"type\":\"RandomInt\"}"));
   }
+
+  fn json_from_c(value: *const c_char) -> Value {
+    expect!(value.is_null()).to(be_false());
+    let string = unsafe { CString::from_raw(value as *mut c_char) };
+    serde_json::from_str(string.to_str().unwrap()).unwrap()
+  }
+
+  #[test_log::test]
+  fn generate_request_test() {
+    let consumer = CString::new("generate_request_consumer").unwrap();
+    let provider = CString::new("generate_request_provider").unwrap();
+    let pact_handle = pactffi_new_pact(consumer.as_ptr(), provider.as_ptr());
+    let description = CString::new("a request with generators").unwrap();
+    let interaction = pactffi_new_interaction(pact_handle, description.as_ptr());
+    let method = CString::new("PUT").unwrap();
+    let path = CString::new("{\"value\":\"/orders/100\",\"pact:matcher:type\":\"type\",\"pact:generator:type\":\"ProviderState\",\"expression\":\"/orders/${id}\"}").unwrap();
+    let content_type = CString::new("application/json").unwrap();
+    let body = CString::new("{\"id\":{\"value\":100,\"pact:matcher:type\":\"integer\",\"pact:generator:type\":\"ProviderState\",\"expression\":\"${id}\"}}").unwrap();
+    pactffi_with_request(interaction, method.as_ptr(), path.as_ptr());
+    pactffi_with_body(interaction, InteractionPart::Request, content_type.as_ptr(), body.as_ptr());
+    let context = CString::new("{\"id\": 1234}").unwrap();
+
+    let json = json_from_c(pactffi_generate_request(interaction, GeneratorTestMode::Provider, context.as_ptr()));
+    expect!(json.get("method").cloned()).to(be_some().value(json!("PUT")));
+    expect!(json.get("path").cloned()).to(be_some().value(json!("/orders/1234")));
+    expect!(json.pointer("/body/content").cloned()).to(be_some().value(json!({"id": 1234})));
+    expect!(json.get("matchingRules")).to(be_none());
+    expect!(json.get("generators")).to(be_none());
+
+    let json = json_from_c(pactffi_generate_request(interaction, GeneratorTestMode::Consumer, context.as_ptr()));
+    expect!(json.get("path").cloned()).to(be_some().value(json!("/orders/100")));
+    expect!(json.pointer("/body/content").cloned()).to(be_some().value(json!({"id": 100})));
+
+    let invalid_context = CString::new("{not valid").unwrap();
+    let result = pactffi_generate_request(interaction, GeneratorTestMode::Provider, invalid_context.as_ptr());
+    expect!(result.is_null()).to(be_true());
+
+    pactffi_free_pact_handle(pact_handle);
+  }
+
+  #[test_log::test]
+  fn generate_response_test() {
+    let consumer = CString::new("generate_response_consumer").unwrap();
+    let provider = CString::new("generate_response_provider").unwrap();
+    let pact_handle = pactffi_new_pact(consumer.as_ptr(), provider.as_ptr());
+    let description = CString::new("a response with generators").unwrap();
+    let interaction = pactffi_new_interaction(pact_handle, description.as_ptr());
+    let content_type = CString::new("application/json").unwrap();
+    let body = CString::new("{\"name\":{\"value\":\"abcd\",\"pact:matcher:type\":\"type\",\"pact:generator:type\":\"RandomString\",\"size\":10}}").unwrap();
+    pactffi_with_body(interaction, InteractionPart::Response, content_type.as_ptr(), body.as_ptr());
+
+    let json = json_from_c(pactffi_generate_response(interaction, GeneratorTestMode::Consumer, std::ptr::null()));
+    expect!(json.get("status").cloned()).to(be_some().value(json!(200)));
+    let name = json.pointer("/body/content/name").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+    expect!(name.len()).to(be_equal_to(10));
+
+    let message_description = CString::new("a message").unwrap();
+    let message = pactffi_new_message_interaction(pact_handle, message_description.as_ptr());
+    let result = pactffi_generate_response(message, GeneratorTestMode::Consumer, std::ptr::null());
+    expect!(result.is_null()).to(be_true());
+
+    pactffi_free_pact_handle(pact_handle);
+  }
+
+  #[test_log::test]
+  fn generate_message_contents_test() {
+    let consumer = CString::new("generate_message_consumer").unwrap();
+    let provider = CString::new("generate_message_provider").unwrap();
+    let pact_handle = pactffi_new_pact(consumer.as_ptr(), provider.as_ptr());
+    let description = CString::new("a message with generators").unwrap();
+    let message = pactffi_new_message_interaction(pact_handle, description.as_ptr());
+    let content_type = CString::new("application/json").unwrap();
+    let body = CString::new("{\"id\":{\"value\":100,\"pact:matcher:type\":\"integer\",\"pact:generator:type\":\"ProviderState\",\"expression\":\"${id}\"}}").unwrap();
+    pactffi_with_body(message, InteractionPart::Request, content_type.as_ptr(), body.as_ptr());
+    let context = CString::new("{\"id\": 1234}").unwrap();
+
+    let json = json_from_c(pactffi_generate_message_contents(message, InteractionPart::Request,
+      GeneratorTestMode::Provider, context.as_ptr()));
+    expect!(json.pointer("/contents/content").cloned()).to(be_some().value(json!({"id": 1234})));
+    expect!(json.pointer("/metadata/contentType").cloned()).to(be_some().value(json!("application/json")));
+
+    let http_description = CString::new("an HTTP interaction").unwrap();
+    let interaction = pactffi_new_interaction(pact_handle, http_description.as_ptr());
+    let result = pactffi_generate_message_contents(interaction, InteractionPart::Request,
+      GeneratorTestMode::Provider, context.as_ptr());
+    expect!(result.is_null()).to(be_true());
+
+    pactffi_free_pact_handle(pact_handle);
+  }
 }
@@ -1,15 +1,18 @@
 //! Handle interface to creating a verifier
 
+use std::path::PathBuf;
 use std::sync::Arc;
 
+use anyhow::anyhow;
 use itertools::Itertools;
 use pact_models::prelude::HttpAuth;
 use serde_json::Value;
 use tracing::{debug, error};
 
-use pact_verifier::{ConsumerVersionSelector, FilterInfo, NullRequestFilterExecutor, PactSource, ProviderInfo, ProviderTransport, PublishOptions, VerificationOptions, verify_provider_async};
+use pact_verifier::{ClientIdentity, COMMAND_SCHEME, ConsumerVersionSelector, FilterInfo, PactSource, ProviderInfo, ProviderTransport, PublishOptions, TlsConfig, UNIX_SOCKET_SCHEME, VerificationOptions, verify_provider_async};
 use pact_verifier::callback_executors::HttpRequestProviderStateExecutor;
 use pact_verifier::metrics::VerificationMetrics;
+use pact_verifier::request_filters::{RequestFilter, RequestFilters};
 use pact_verifier::verification_result::VerificationExecutionResult;
 
 use crate::RUNTIME;
@@ -21,7 +24,7 @@ pub struct VerifierHandle {
   sources: Vec<PactSource>,
   filter: FilterInfo,
   state_change: Arc<HttpRequestProviderStateExecutor>,
-  verification_options: VerificationOptions<NullRequestFilterExecutor>,
+  verification_options: VerificationOptions<RequestFilters>,
   publish_options: Option<PublishOptions>,
   consumers: Vec<String>,
   /// Calling application name and version
@@ -75,12 +78,23 @@ impl VerifierHandle {
     path: String,
     scheme: Option<String>
   ) {
-
-    let transport = ProviderTransport {
-      transport: protocol,
-      port: Some(port),
-      path: if path.is_empty() { None } else { Some(path) },
-      scheme: scheme
+    let (protocol, http2) = match protocol.strip_suffix("+h2c") {
+      Some(protocol) => (protocol.to_string(), true),
+      None => (protocol, false)
+    };
+    let path = if path.is_empty() { None } else { Some(path) };
+    let transport = match scheme.as_deref() {
+      Some(UNIX_SOCKET_SCHEME) => match path {
+        Some(path) => ProviderTransport::unix_socket(protocol, path).with_http2(http2),
+        None => ProviderTransport::new(protocol, None, None, scheme).with_http2(http2)
+      },
+      Some(COMMAND_SCHEME) => match path {
+        Some(command) => ProviderTransport::message_command(protocol, command),
+        None => ProviderTransport::new(protocol, None, None, scheme)
+      },
+      Some("h2c") => ProviderTransport::new(protocol, Some(port), path, Some("http".to_string()))
+        .with_http2(true),
+      _ => ProviderTransport::new(protocol, Some(port), path, scheme).with_http2(http2)
     };
 
     self.provider.transports.push(transport);
@@ -103,12 +117,8 @@ impl VerifierHandle {
       host,
       port: port.clone(),
       path: path.clone(),
-      transports: vec![ ProviderTransport {
-        transport: scheme.clone(),
-        port,
-        path: if path.is_empty() { None } else { Some(path) },
-        scheme: None
-      } ]
+      transports: vec![ ProviderTransport::new(scheme.clone(), port,
+        if path.is_empty() { None } else { Some(path) }, None) ]
     }
   }
 
@@ -286,6 +296,14 @@ impl VerifierHandle {
     let (calling_app_name, calling_app_version) = self.calling_app.clone().unwrap_or_else(|| {
       ("pact_ffi".to_string(), env!("CARGO_PKG_VERSION").to_string())
     });
+    // The provider base path is included in the request signatures
+    #[allow(deprecated)]
+    let request_filter = self.verification_options.request_filter.as_ref()
+      .map(|filters| Arc::new(RequestFilters {
+        base_path: self.provider.path.clone(),
+        .. filters.as_ref().clone()
+      }));
+    self.verification_options.request_filter = request_filter;
     match RUNTIME.block_on(async {
       verify_provider_async(
         self.provider.clone(),
@@ -330,10 +348,69 @@ impl VerifierHandle {
     self.verifier_output.output = out.split('\n').map(|s| s.to_string()).collect();
   }
 
+  /// Returns the options used for the verification
+  #[cfg(test)]
+  pub fn verification_options(&self) -> &VerificationOptions<RequestFilters> {
+    &self.verification_options
+  }
+
   /// Add a custom header to be included in the call to the provider
   pub fn add_custom_header(&mut self, header_name: &str, header_value: &str) {
     self.verification_options.custom_headers.insert(header_name.to_string(), header_value.to_string());
   }
+
+  /// Adds a built-in request filter to sign or authenticate the requests made to the provider.
+  /// The filters are applied in the order they are added.
+  pub fn add_request_filter(&mut self, filter: RequestFilter) {
+    let mut filters = self.verification_options.request_filter.as_ref()
+      .map(|filters| filters.as_ref().clone())
+      .unwrap_or_default();
+    filters.filters.push(filter);
+    self.verification_options.request_filter = Some(Arc::new(filters));
+  }
+
+  /// Adds a PEM file with CA certificates to trust for the requests to the target (one of
+  /// `provider`, `state-change` or `broker`). Returns an error if the certificates can not be
+  /// loaded.
+  pub fn add_ca_certificate(&mut self, target: &str, path: &str) -> anyhow::Result<()> {
+    let mut tls = self.tls_config(target)?;
+    tls.ca_certificates.push(PathBuf::from(path));
+    self.set_tls_config(target, tls)
+  }
+
+  /// Sets the client certificate to use for mutual TLS with the target (one of `provider`,
+  /// `state-change` or `broker`). Returns an error if the certificate can not be loaded.
+  pub fn set_client_identity(&mut self, target: &str, identity: ClientIdentity) -> anyhow::Result<()> {
+    let mut tls = self.tls_config(target)?;
+    tls.client_identity = Some(identity);
+    self.set_tls_config(target, tls)
+  }
+
+  fn tls_config(&self, target: &str) -> anyhow::Result<TlsConfig> {
+    match target {
+      "provider" => Ok(self.verification_options.provider_tls.clone()),
+      "state-change" => Ok(self.verification_options.state_change_tls.clone().unwrap_or_default()),
+      "broker" => Ok(self.verification_options.broker_tls.clone()),
+      _ => Err(invalid_tls_target(target))
+    }
+  }
+
+  fn set_tls_config(&mut self, target: &str, tls: TlsConfig) -> anyhow::Result<()> {
+    // Load the certificates now, so any errors are returned to the caller instead of failing
+    // the verification
+    tls.validate()?;
+    match target {
+      "provider" => self.verification_options.provider_tls = tls,
+      "state-change" => self.verification_options.state_change_tls = Some(tls),
+      "broker" => self.verification_options.broker_tls = tls,
+      _ => return Err(invalid_tls_target(target))
+    }
+    Ok(())
+  }
+}
+
+fn invalid_tls_target(target: &str) -> anyhow::Error {
+  anyhow!("'{}' is not a valid TLS target, it must be one of provider, state-change or broker", target)
 }
 
 impl Default for VerifierHandle {
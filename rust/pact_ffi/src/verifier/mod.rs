@@ -6,6 +6,7 @@
 use std::env;
 use std::ffi::{CStr, CString, OsStr, OsString};
 use std::panic::catch_unwind;
+use std::path::PathBuf;
 use std::str::from_utf8;
 
 use clap::ArgSettings;
@@ -17,6 +18,8 @@ use regex::Regex;
 use serde::{Deserialize, Serialize};
 
 use pact_matching::logging::fetch_buffer_contents;
+use pact_verifier::ClientIdentity;
+use pact_verifier::request_filters::RequestFilter;
 use pact_verifier::selectors::{consumer_tags_to_selectors, json_to_selectors};
 
 use crate::{as_mut, as_ref, ffi_fn, RUNTIME, safe_str};
@@ -165,6 +168,13 @@ ffi_fn! {
     /// to an empty string or "https" if secure HTTP is required. Communication to the calling
     /// application will be over HTTP to the default provider hostname.
     ///
+    /// If the provider is listening on a Unix domain socket, set scheme to "unix" and path to
+    /// the path of the socket (port is ignored). To send requests using HTTP/2 without TLS, either
+    /// set scheme to "h2c" or append "+h2c" to the protocol (i.e. "http+h2c").
+    ///
+    /// To get messages by running a command instead of over HTTP, set protocol to "message", scheme
+    /// to "command" and path to the command to run (port is ignored).
+    ///
     /// # Safety
     ///
     /// All string fields must contain valid UTF-8. Invalid UTF-8
@@ -412,6 +422,166 @@ ffi_fn! {
     }
 }
 
+ffi_fn! {
+    /// Adds a built-in request filter to sign or authenticate the requests made to the provider.
+    /// The filter is configured with a JSON document, where the `type` attribute is one of
+    /// `hmac`, `sigv4` or `oauth2`. Filters are applied in the order they are added, after any
+    /// generators have been applied to the requests.
+    ///
+    /// * `hmac` - `secret` and optionally `signatureHeader` and `timestampHeader`
+    /// * `sigv4` - `accessKeyId`, `secretAccessKey`, `region`, `service` and optionally
+    ///   `sessionToken` and `host`
+    /// * `oauth2` - `tokenUrl`, `clientId`, `clientSecret` and optionally `scope` and `audience`
+    ///
+    /// For example: `{"type": "hmac", "secret": "my-secret"}`
+    ///
+    /// # Errors
+    ///
+    /// Returns 1 if the configuration is not valid.
+    ///
+    /// # Safety
+    ///
+    /// The configuration must point to a valid NULL terminated string and must contain
+    /// valid UTF-8.
+    fn pactffi_verifier_add_request_filter(
+      handle: *mut handle::VerifierHandle,
+      filter_config: *const c_char
+    ) -> c_int {
+      let handle = as_mut!(handle);
+      let filter_config = safe_str!(filter_config);
+
+      match RequestFilter::from_json_str(filter_config) {
+        Ok(filter) => {
+          handle.add_request_filter(filter);
+          EXIT_SUCCESS
+        }
+        Err(err) => {
+          error!("pactffi_verifier_add_request_filter: {}", err);
+          EXIT_FAILURE
+        }
+      }
+    } {
+      EXIT_FAILURE
+    }
+}
+
+ffi_fn! {
+    /// Adds a PEM file with CA certificates to trust, in addition to the system root certificates.
+    /// The target is the requests the certificates are used for, and must be one of `provider`,
+    /// `state-change` or `broker`. The broker certificates are also used when fetching pacts from
+    /// URLs. If no state change TLS options are set, the provider ones are used for the provider
+    /// state change requests.
+    ///
+    /// # Errors
+    ///
+    /// Returns 1 if the target is not valid, or the certificates can not be loaded.
+    ///
+    /// # Safety
+    ///
+    /// The target and path must point to a valid NULL terminated string and must contain
+    /// valid UTF-8.
+    fn pactffi_verifier_add_ca_certificate(
+      handle: *mut handle::VerifierHandle,
+      target: *const c_char,
+      path: *const c_char
+    ) -> c_int {
+      let handle = as_mut!(handle);
+      let target = safe_str!(target);
+      let path = safe_str!(path);
+
+      match handle.add_ca_certificate(target, path) {
+        Ok(_) => EXIT_SUCCESS,
+        Err(err) => {
+          error!("pactffi_verifier_add_ca_certificate: {}", err);
+          EXIT_FAILURE
+        }
+      }
+    } {
+      EXIT_FAILURE
+    }
+}
+
+ffi_fn! {
+    /// Sets the client certificate to use for mutual TLS. The certificate must be a PEM file,
+    /// and the private key a PEM file or NULL if the key is included in the certificate file. The
+    /// target must be one of `provider`, `state-change` or `broker`.
+    ///
+    /// # Errors
+    ///
+    /// Returns 1 if the target is not valid, or the certificates can not be loaded.
+    ///
+    /// # Safety
+    ///
+    /// The target, certificate and private key must point to a valid NULL terminated string and
+    /// must contain valid UTF-8. The private key can be NULL.
+    fn pactffi_verifier_set_client_certificate(
+      handle: *mut handle::VerifierHandle,
+      target: *const c_char,
+      certificate: *const c_char,
+      private_key: *const c_char
+    ) -> c_int {
+      let handle = as_mut!(handle);
+      let target = safe_str!(target);
+      let certificate = safe_str!(certificate);
+      let private_key = optional_str(private_key);
+
+      let identity = ClientIdentity::Pem {
+        certificate: PathBuf::from(certificate),
+        private_key: private_key.map(PathBuf::from)
+      };
+      match handle.set_client_identity(target, identity) {
+        Ok(_) => EXIT_SUCCESS,
+        Err(err) => {
+          error!("pactffi_verifier_set_client_certificate: {}", err);
+          EXIT_FAILURE
+        }
+      }
+    } {
+      EXIT_FAILURE
+    }
+}
+
+ffi_fn! {
+    /// Sets a PKCS#12 archive with the client certificate and private key to use for mutual TLS.
+    /// The target must be one of `provider`, `state-change` or `broker`. Using PKCS#12 archives
+    /// requires the library to be built with the `native-tls` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns 1 if the target is not valid, or the archive can not be loaded (including when the
+    /// library has been built without the `native-tls` feature).
+    ///
+    /// # Safety
+    ///
+    /// The target, archive path and password must point to a valid NULL terminated string and
+    /// must contain valid UTF-8. The password can be NULL.
+    fn pactffi_verifier_set_client_pkcs12(
+      handle: *mut handle::VerifierHandle,
+      target: *const c_char,
+      archive: *const c_char,
+      password: *const c_char
+    ) -> c_int {
+      let handle = as_mut!(handle);
+      let target = safe_str!(target);
+      let archive = safe_str!(archive);
+      let password = optional_str(password);
+
+      let identity = ClientIdentity::Pkcs12 {
+        archive: PathBuf::from(archive),
+        password
+      };
+      match handle.set_client_identity(target, identity) {
+        Ok(_) => EXIT_SUCCESS,
+        Err(err) => {
+          error!("pactffi_verifier_set_client_pkcs12: {}", err);
+          EXIT_FAILURE
+        }
+      }
+    } {
+      EXIT_FAILURE
+    }
+}
+
 ffi_fn! {
     /// Adds a Pact file as a source to verify.
     ///
@@ -902,12 +1072,21 @@ ffi_fn! {
 #[cfg(test)]
 mod tests {
   use std::ffi::CString;
+  use std::path::PathBuf;
 
   use expectest::prelude::*;
   use libc::c_char;
+  use pact_verifier::ClientIdentity;
+  use pact_verifier::request_filters::RequestFilter;
 
   use crate::verifier::handle::VerifierHandle;
-  use crate::verifier::pactffi_verifier_output;
+  use crate::verifier::{
+    pactffi_verifier_add_ca_certificate,
+    pactffi_verifier_add_request_filter,
+    pactffi_verifier_output,
+    pactffi_verifier_set_client_certificate,
+    pactffi_verifier_set_client_pkcs12
+  };
 
   #[test]
   fn pactffi_verifier_output_test() {
@@ -956,4 +1135,89 @@ We are tracking events anonymously to gather important usage statistics like Pac
 1) Verifying a pact between test_consumer and test_provider Given test state - test interaction - error sending request for url (http://localhost/): error trying to connect: tcp connect error: Connection refused (os error 111)\n\
 \n\nThere were 1 pact failures\n\n"));
   }
+
+  #[test]
+  fn pactffi_verifier_add_request_filter_test() {
+    let mut handle = VerifierHandle::new_for_application("tests", "1.0.0");
+
+    let config = CString::new(r#"{"type": "hmac", "secret": "secret"}"#).unwrap();
+    expect!(pactffi_verifier_add_request_filter(&mut handle, config.as_ptr())).to(be_equal_to(0));
+    let filters = handle.verification_options().request_filter.clone().unwrap();
+    expect!(filters.filters.len()).to(be_equal_to(1));
+    expect!(matches!(filters.filters[0], RequestFilter::Hmac(_))).to(be_true());
+
+    let config = CString::new(r#"{"type": "unknown"}"#).unwrap();
+    expect!(pactffi_verifier_add_request_filter(&mut handle, config.as_ptr())).to(be_equal_to(1));
+    let filters = handle.verification_options().request_filter.clone().unwrap();
+    expect!(filters.filters.len()).to(be_equal_to(1));
+  }
+
+  fn tls_file(name: &str) -> CString {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../pact_verifier/tests/tls").join(name);
+    CString::new(path.to_string_lossy().to_string()).unwrap()
+  }
+
+  #[test]
+  fn pactffi_verifier_tls_options_test() {
+    let mut handle = VerifierHandle::new_for_application("tests", "1.0.0");
+
+    let provider = CString::new("provider").unwrap();
+    let state_change = CString::new("state-change").unwrap();
+    let invalid = CString::new("other").unwrap();
+    let ca = tls_file("ca.pem");
+    let cert = tls_file("client.pem");
+    let key = tls_file("client.key");
+
+    expect!(pactffi_verifier_add_ca_certificate(&mut handle, provider.as_ptr(), ca.as_ptr())).to(be_equal_to(0));
+    expect!(pactffi_verifier_set_client_certificate(&mut handle, state_change.as_ptr(), cert.as_ptr(), key.as_ptr())).to(be_equal_to(0));
+    expect!(pactffi_verifier_add_ca_certificate(&mut handle, invalid.as_ptr(), ca.as_ptr())).to(be_equal_to(1));
+
+    let options = handle.verification_options();
+    expect!(options.provider_tls.ca_certificates.clone()).to(be_equal_to(vec![
+      PathBuf::from(ca.to_str().unwrap())
+    ]));
+    expect!(options.provider_tls.client_identity.clone()).to(be_none());
+    expect!(options.state_change_tls.clone().and_then(|tls| tls.client_identity)).to(be_some().value(ClientIdentity::Pem {
+      certificate: PathBuf::from(cert.to_str().unwrap()),
+      private_key: Some(PathBuf::from(key.to_str().unwrap()))
+    }));
+    expect!(options.broker_tls.is_empty()).to(be_true());
+  }
+
+  #[test]
+  #[cfg(feature = "native-tls")]
+  fn pactffi_verifier_set_client_pkcs12_test() {
+    let mut handle = VerifierHandle::new_for_application("tests", "1.0.0");
+
+    let broker = CString::new("broker").unwrap();
+    let archive = tls_file("client.p12");
+    let password = CString::new("changeit").unwrap();
+
+    expect!(pactffi_verifier_set_client_pkcs12(&mut handle, broker.as_ptr(), archive.as_ptr(), password.as_ptr())).to(be_equal_to(0));
+    expect!(handle.verification_options().broker_tls.client_identity.clone()).to(be_some().value(ClientIdentity::Pkcs12 {
+      archive: PathBuf::from(archive.to_str().unwrap()),
+      password: Some("changeit".to_string())
+    }));
+  }
+
+  #[test]
+  fn pactffi_verifier_tls_options_return_an_error_for_invalid_certificates() {
+    let mut handle = VerifierHandle::new_for_application("tests", "1.0.0");
+
+    let provider = CString::new("provider").unwrap();
+    let broker = CString::new("broker").unwrap();
+    let missing = CString::new("/does/not/exist.pem").unwrap();
+    let not_pem = tls_file("client.p12");
+    let not_pkcs12 = tls_file("ca.pem");
+    let password = CString::new("changeit").unwrap();
+
+    expect!(pactffi_verifier_add_ca_certificate(&mut handle, provider.as_ptr(), missing.as_ptr())).to(be_equal_to(1));
+    expect!(pactffi_verifier_add_ca_certificate(&mut handle, provider.as_ptr(), not_pem.as_ptr())).to(be_equal_to(1));
+    expect!(pactffi_verifier_set_client_certificate(&mut handle, provider.as_ptr(), not_pem.as_ptr(), std::ptr::null())).to(be_equal_to(1));
+    expect!(pactffi_verifier_set_client_pkcs12(&mut handle, broker.as_ptr(), not_pkcs12.as_ptr(), password.as_ptr())).to(be_equal_to(1));
+
+    let options = handle.verification_options();
+    expect!(options.provider_tls.is_empty()).to(be_true());
+    expect!(options.broker_tls.is_empty()).to(be_true());
+  }
 }
@@ -0,0 +1,105 @@
+//! Benchmarks for matching large bodies with a large number of matching rules.
+//! Run with `cargo bench --bench matching`.
+
+use bytes::Bytes;
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use serde_json::{json, Value};
+
+use pact_matching::{CoreMatchingContext, DiffConfig, MatchingContext};
+use pact_matching::json::match_json;
+use pact_matching::matcher_index::MatcherIndex;
+use pact_models::bodies::OptionalBody;
+use pact_models::content_types::JSON;
+use pact_models::matchingrules::{Category, MatchingRule, MatchingRuleCategory, RuleLogic};
+use pact_models::path_exp::DocPath;
+use pact_models::v4::http_parts::HttpResponse;
+
+fn body(items: usize) -> Value {
+  Value::Array((0..items).map(|i| json!({
+    "id": i,
+    "name": format!("item {}", i),
+    "code": format!("C{:06}", i),
+    "price": i as f64 * 1.25,
+    "tags": ["a", "b", "c"],
+    "attributes": {
+      "colour": "red",
+      "size": i % 10,
+      "created": "2024-01-01T00:00:00"
+    }
+  })).collect())
+}
+
+fn rules(extra: usize) -> MatchingRuleCategory {
+  let mut category = MatchingRuleCategory::empty(Category::BODY);
+  category.add_rule(DocPath::new_unwrap("$"), MatchingRule::MinType(1), RuleLogic::And);
+  category.add_rule(DocPath::new_unwrap("$[*].id"), MatchingRule::Integer, RuleLogic::And);
+  category.add_rule(DocPath::new_unwrap("$[*].name"), MatchingRule::Type, RuleLogic::And);
+  category.add_rule(DocPath::new_unwrap("$[*].code"), MatchingRule::Regex("C\\d{6}".to_string()), RuleLogic::And);
+  category.add_rule(DocPath::new_unwrap("$[*].price"), MatchingRule::Decimal, RuleLogic::And);
+  category.add_rule(DocPath::new_unwrap("$[*].tags"), MatchingRule::Type, RuleLogic::And);
+  category.add_rule(DocPath::new_unwrap("$[*].attributes.*"), MatchingRule::Type, RuleLogic::And);
+  for i in 0..extra {
+    category.add_rule(DocPath::new(format!("$[{}].name", i)).unwrap(), MatchingRule::Regex("item \\d+".to_string()),
+      RuleLogic::And);
+  }
+  category
+}
+
+fn response(body: &Value) -> HttpResponse {
+  HttpResponse {
+    body: OptionalBody::Present(Bytes::from(body.to_string()), Some(JSON.clone()), None),
+    .. HttpResponse::default()
+  }
+}
+
+fn match_large_json_body(c: &mut Criterion) {
+  let mut group = c.benchmark_group("match_json");
+  group.sample_size(10);
+  for (items, extra) in [(100, 50), (1000, 300)] {
+    let body = body(items);
+    let expected = response(&body);
+    let actual = response(&body);
+    let rules = rules(extra);
+    group.bench_with_input(BenchmarkId::from_parameter(format!("{} items, {} rules", items, rules.rules.len())),
+      &rules, |b, rules| {
+      b.iter(|| {
+        let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, rules, &Default::default());
+        match_json(&expected, &actual, &context)
+      })
+    });
+  }
+  group.finish();
+}
+
+fn select_best_matcher(c: &mut Criterion) {
+  let rules = rules(300);
+  let index = MatcherIndex::new(&rules);
+  let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &rules, &Default::default());
+  let paths = (0..500)
+    .map(|i| vec!["$".to_string(), i.to_string(), "attributes".to_string(), "size".to_string()])
+    .collect::<Vec<_>>();
+
+  let mut group = c.benchmark_group("select_best_matcher");
+  group.bench_function("matching rule category", |b| b.iter(|| {
+    for path in &paths {
+      let path = path.iter().map(|p| p.as_str()).collect::<Vec<_>>();
+      rules.select_best_matcher(&path);
+    }
+  }));
+  group.bench_function("matcher index", |b| b.iter(|| {
+    for path in &paths {
+      let path = path.iter().map(|p| p.as_str()).collect::<Vec<_>>();
+      index.select_best_matcher(&path);
+    }
+  }));
+  group.bench_function("matching context", |b| b.iter(|| {
+    for path in &paths {
+      let path = DocPath::root().join(path[1].as_str()).join("attributes").join("size");
+      context.select_best_matcher(&path);
+    }
+  }));
+  group.finish();
+}
+
+criterion_group!(benches, match_large_json_body, select_best_matcher);
+criterion_main!(benches);
@@ -16,7 +16,6 @@ use bytes::Bytes;
 #[cfg(feature = "multipart")] use http::header::{HeaderMap, HeaderName};
 #[cfg(feature = "multipart")] use itertools::Itertools;
 #[cfg(feature = "multipart")] use multer::Multipart;
-#[cfg(feature = "multipart")] use onig::Regex;
 #[cfg(feature = "multipart")] use pact_models::bodies::OptionalBody;
 use pact_models::content_types::{ContentType, detect_content_type_from_bytes};
 use pact_models::http_parts::HttpPart;
@@ -31,12 +30,13 @@ use crate::{MatchingContext, Mismatch};
 #[cfg(feature = "multipart")] use crate::{BodyMatchResult, CoreMatchingContext, HeaderMatchingContext};
 use crate::matchers::Matches;
 #[cfg(feature = "multipart")] use crate::matchers::match_values;
+#[cfg(feature = "multipart")] use crate::regex_cache::compile_regex;
 
 /// Compares the binary data using a magic test and comparing the resulting detected content
 /// type against the expected content type
 pub fn match_content_type<S>(data: &[u8], expected_content_type: S) -> anyhow::Result<()>
   where S: Into<String> {
-  let result = tree_magic_mini::from_u8(data);
+  let result = detect_content_type(data);
   let expected = expected_content_type.into();
   let matches = result == expected;
   debug!("Matching binary contents by content type: expected '{}', detected '{}' -> {}",
@@ -54,6 +54,19 @@ pub fn match_content_type<S>(data: &[u8], expected_content_type: S) -> anyhow::R
   }
 }
 
+#[cfg(not(target_family = "wasm"))]
+fn detect_content_type(data: &[u8]) -> String {
+  tree_magic_mini::from_u8(data).to_string()
+}
+
+#[cfg(target_family = "wasm")]
+fn detect_content_type(data: &[u8]) -> String {
+  // The shared MIME info database is not available with WASM, so only text formats can be detected
+  detect_content_type_from_bytes(data)
+    .map(|ct| ct.base_type().to_string())
+    .unwrap_or_else(|| "application/octet-stream".to_string())
+}
+
 pub(crate) fn convert_data(data: &Value) -> Vec<u8> {
   match data {
     Value::String(s) => BASE64.decode(s.as_str()).unwrap_or_else(|_| s.clone().into_bytes()),
@@ -535,7 +548,7 @@ impl Matches<&MimeFile> for &MimeFile {
     debug!("FilePart: comparing binary data to '{:?}' using {:?}", actual.content_type, matcher);
     match matcher {
       MatchingRule::Regex(ref regex) => {
-        match Regex::new(regex) {
+        match compile_regex(regex) {
           Ok(re) => {
             match from_utf8(&*actual.data) {
               Ok(a) => if re.is_match(a) {
@@ -0,0 +1,253 @@
+//! Functions for matching CSV and TSV bodies. Documents are parsed and converted to a JSON array of
+//! rows (see `pact_models::csv_utils`), which is then matched using the JSON matching rules. Cells
+//! are addressed with paths like `$[*].amount` or `$[0].name`, row counts are checked with the
+//! min/max type matchers on `$`, and the column order is only checked if there is an equality
+//! matcher on `$.columns`.
+
+use bytes::Bytes;
+use itertools::Itertools;
+use pact_models::bodies::OptionalBody;
+use pact_models::content_types::ContentType;
+use pact_models::csv_utils::{COLUMNS_PATH, csv_delimiter, csv_has_header, CsvDocument};
+use pact_models::http_parts::HttpPart;
+use pact_models::matchingrules::MatchingRule;
+use pact_models::path_exp::DocPath;
+use pact_models::text_template::template_regex;
+use serde_json::Value;
+
+use crate::{DiffConfig, MatchingContext, Mismatch};
+use crate::json::compare_json;
+
+/// If there are matching rules for the rows or columns of a CSV document (any rule below `$`, or a
+/// type matcher on `$` for the number of rows). Text templates are not CSV rules.
+pub(crate) fn csv_rules_defined(context: &dyn MatchingContext) -> bool {
+  let matchers = context.matchers();
+  template_regex(matchers).is_none() && matchers.rules.iter().any(|(path, rules)| {
+    !path.is_root() || rules.rules.iter().any(|rule| matches!(rule, MatchingRule::Type |
+      MatchingRule::MinType(_) | MatchingRule::MaxType(_) | MatchingRule::MinMaxType(_, _)))
+  })
+}
+
+/// Matches the bodies as CSV (or TSV) documents
+pub(crate) fn match_csv(
+  expected: &(dyn HttpPart + Send + Sync),
+  actual: &(dyn HttpPart + Send + Sync),
+  context: &(dyn MatchingContext + Send + Sync)
+) -> Result<(), Vec<Mismatch>> {
+  let expected_body = expected.body();
+  let actual_body = actual.body();
+  match expected_body {
+    OptionalBody::Missing | OptionalBody::Null => Ok(()),
+    OptionalBody::Empty => match actual_body {
+      OptionalBody::Empty => Ok(()),
+      _ => Err(vec![
+        Mismatch::BodyMismatch {
+          path: "$".into(),
+          expected: expected_body.value(),
+          actual: actual_body.value(),
+          mismatch: format!("Expected an empty body, but got '{}'", actual_body.value_as_string().unwrap_or(actual_body.display_string()))
+        }
+      ])
+    }
+    OptionalBody::Present(..) => {
+      let content_type = expected.content_type().unwrap_or_else(|| ContentType::from("text/csv"));
+      let expected_doc = parse_body(expected_body, &content_type, "expected");
+      let actual_doc = parse_body(actual_body, &actual.content_type().unwrap_or(content_type), "actual");
+      match (expected_doc, actual_doc) {
+        (Err(m), Err(m2)) => Err(vec![*m, *m2]),
+        (Err(m), Ok(_)) => Err(vec![*m]),
+        (Ok(_), Err(m2)) => Err(vec![*m2]),
+        (Ok(e), Ok(a)) => {
+          let result = compare_csv(&e, &a, context);
+          if result.is_empty() {
+            Ok(())
+          } else {
+            Err(result)
+          }
+        }
+      }
+    }
+  }
+}
+
+fn parse_body(body: &OptionalBody, content_type: &ContentType, name: &str) -> Result<CsvDocument, Box<Mismatch>> {
+  let text = String::from_utf8_lossy(&body.value().unwrap_or_default()).to_string();
+  CsvDocument::parse(&text, csv_delimiter(content_type), csv_has_header(content_type))
+    .map_err(|err| Box::new(Mismatch::BodyMismatch {
+      path: "$".into(),
+      expected: None,
+      actual: body.value(),
+      mismatch: format!("Could not parse {} body as CSV: {}", name, err)
+    }))
+}
+
+fn compare_csv(expected: &CsvDocument, actual: &CsvDocument, context: &(dyn MatchingContext + Send + Sync)) -> Vec<Mismatch> {
+  let mut mismatches = vec![];
+  let mut expected_json = expected.to_json();
+  let mut actual_json = actual.to_json();
+
+  if let (Some(expected_columns), Some(actual_columns)) = (&expected.columns, &actual.columns) {
+    let columns_path = DocPath::new_unwrap(COLUMNS_PATH);
+    let column_mismatch = |message: String| Mismatch::BodyMismatch {
+      path: COLUMNS_PATH.to_string(),
+      expected: Some(Bytes::from(expected_columns.join(","))),
+      actual: Some(Bytes::from(actual_columns.join(","))),
+      mismatch: message
+    };
+
+    let missing = expected_columns.iter().filter(|c| !actual_columns.contains(c)).collect_vec();
+    for column in &missing {
+      mismatches.push(column_mismatch(format!("Expected column '{}' but was missing", column)));
+    }
+    let unexpected = actual_columns.iter().filter(|c| !expected_columns.contains(c)).collect_vec();
+    if context.config() == DiffConfig::NoUnexpectedKeys {
+      for column in &unexpected {
+        mismatches.push(column_mismatch(format!("Unexpected column '{}' received", column)));
+      }
+    }
+
+    let strict_order = context.matcher_is_defined(&columns_path) && context.select_best_matcher(&columns_path)
+      .rules.contains(&MatchingRule::Equality);
+    if strict_order && missing.is_empty() {
+      let actual_order = actual_columns.iter().filter(|c| expected_columns.contains(c)).collect_vec();
+      if actual_order != expected_columns.iter().collect_vec() {
+        mismatches.push(column_mismatch(format!("Expected columns [{}] to be in the order [{}]",
+          actual_order.iter().join(", "), expected_columns.join(", "))));
+      }
+    }
+
+    // Missing and unexpected columns have already been reported, so remove them from the rows
+    remove_columns(&mut expected_json, &missing);
+    remove_columns(&mut actual_json, &unexpected);
+  }
+
+  let rules = context.matchers().filter(|(path, _)| path.to_string() != COLUMNS_PATH);
+  let row_context = context.clone_with(&rules);
+  if let Err(result) = compare_json(&DocPath::root(), &expected_json, &actual_json, row_context.as_ref()) {
+    mismatches.extend(result.iter().map(|mismatch| mismatch.to_body_mismatch()));
+  }
+  mismatches
+}
+
+fn remove_columns(rows: &mut Value, columns: &[&String]) {
+  if let Some(rows) = rows.as_array_mut() {
+    for row in rows {
+      if let Some(row) = row.as_object_mut() {
+        for column in columns {
+          row.remove(column.as_str());
+        }
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use maplit::hashmap;
+  use pact_models::bodies::OptionalBody;
+  use pact_models::matchingrules_list;
+  use pact_models::matchingrules::{MatchingRule, MatchingRuleCategory};
+  use pact_models::request::Request;
+  use pact_models::text_template::TextTemplate;
+
+  use crate::CoreMatchingContext;
+
+  use super::*;
+
+  fn csv_request(body: &str) -> Request {
+    Request {
+      headers: Some(hashmap!{ "Content-Type".to_string() => vec!["text/csv".to_string()] }),
+      body: OptionalBody::Present(body.to_string().into(), Some("text/csv".into()), None),
+      .. Request::default()
+    }
+  }
+
+  fn descriptions(result: Result<(), Vec<Mismatch>>) -> Vec<String> {
+    result.unwrap_err().iter().map(|m| m.description()).collect()
+  }
+
+  #[test]
+  fn match_csv_with_column_rules() {
+    let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &matchingrules_list! {
+      "body";
+      "$" => [ MatchingRule::MinType(1) ],
+      "$[*].amount" => [ MatchingRule::Decimal ],
+      "$[*].id" => [ MatchingRule::Regex("^\\d+$".to_string()) ]
+    }, &hashmap!{});
+    let expected = csv_request("id,name,amount\n1,Fred,10.50\n");
+
+    let actual = csv_request("name,id,amount,extra\nMary,2,3.25,x\nJoe,3,1.00,y\n");
+    expect!(match_csv(&expected, &actual, &context)).to(be_ok());
+
+    let actual = csv_request("id,name,amount\nA,Mary,3.25\n");
+    expect!(descriptions(match_csv(&expected, &actual, &context))).to(be_equal_to(vec![
+      "$[0].id -> Expected 'A' to match '^\\d+$'".to_string()
+    ]));
+
+    let actual = csv_request("id,name\n1,Mary\n");
+    expect!(descriptions(match_csv(&expected, &actual, &context))).to(be_equal_to(vec![
+      "$.columns -> Expected column 'amount' but was missing".to_string()
+    ]));
+
+    let actual = csv_request("id,name,amount\n");
+    expect!(descriptions(match_csv(&expected, &actual, &context))).to(be_equal_to(vec![
+      "$ -> Expected [] (size 0) to have minimum size of 1".to_string()
+    ]));
+  }
+
+  #[test]
+  fn match_csv_column_order() {
+    let expected = csv_request("id,name\n1,Fred\n");
+    let actual = csv_request("name,id\nFred,1\n");
+
+    let context = CoreMatchingContext::new(DiffConfig::NoUnexpectedKeys, &matchingrules_list! {
+      "body"; "$" => [ MatchingRule::Type ]
+    }, &hashmap!{});
+    expect!(match_csv(&expected, &actual, &context)).to(be_ok());
+
+    let context = CoreMatchingContext::new(DiffConfig::NoUnexpectedKeys, &matchingrules_list! {
+      "body"; "$.columns" => [ MatchingRule::Equality ]
+    }, &hashmap!{});
+    expect!(descriptions(match_csv(&expected, &actual, &context))).to(be_equal_to(vec![
+      "$.columns -> Expected columns [name, id] to be in the order [id, name]".to_string()
+    ]));
+  }
+
+  #[test]
+  fn csv_rules_defined_test() {
+    let context = CoreMatchingContext::new(DiffConfig::NoUnexpectedKeys, &MatchingRuleCategory::empty("body"), &hashmap!{});
+    expect!(csv_rules_defined(&context)).to(be_false());
+
+    let context = CoreMatchingContext::new(DiffConfig::NoUnexpectedKeys, &matchingrules_list! {
+      "body"; "$" => [ MatchingRule::Regex("^id,name\\n".to_string()) ]
+    }, &hashmap!{});
+    expect!(csv_rules_defined(&context)).to(be_false());
+
+    let context = CoreMatchingContext::new(DiffConfig::NoUnexpectedKeys,
+      &TextTemplate::parse("id\n{{id:integer}}\n").unwrap().matching_rules(), &hashmap!{});
+    expect!(csv_rules_defined(&context)).to(be_false());
+
+    let context = CoreMatchingContext::new(DiffConfig::NoUnexpectedKeys, &matchingrules_list! {
+      "body"; "$" => [ MatchingRule::MinType(1) ]
+    }, &hashmap!{});
+    expect!(csv_rules_defined(&context)).to(be_true());
+
+    let context = CoreMatchingContext::new(DiffConfig::NoUnexpectedKeys, &matchingrules_list! {
+      "body"; "$[*].id" => [ MatchingRule::Integer ]
+    }, &hashmap!{});
+    expect!(csv_rules_defined(&context)).to(be_true());
+  }
+
+  #[test]
+  fn match_csv_with_unexpected_columns() {
+    let context = CoreMatchingContext::new(DiffConfig::NoUnexpectedKeys, &matchingrules_list! {
+      "body"; "$[*].id" => [ MatchingRule::Integer ]
+    }, &hashmap!{});
+    let expected = csv_request("id\n1\n");
+    let actual = csv_request("id,name\n2,Fred\n");
+    expect!(descriptions(match_csv(&expected, &actual, &context))).to(be_equal_to(vec![
+      "$.columns -> Unexpected column 'name' received".to_string()
+    ]));
+  }
+}
@@ -4,8 +4,9 @@ use expectest::expect;
 use expectest::prelude::*;
 use pact_models::bodies::OptionalBody;
 use pact_models::content_types::JSON;
+use pact_models::expression_parser::DataType;
 use pact_models::generators;
-use pact_models::generators::{ContentTypeHandler, Generator, JsonHandler};
+use pact_models::generators::{ContentTypeHandler, Generator, Generators, JsonHandler};
 use pact_models::message::Message;
 use pact_models::path_exp::DocPath;
 use serde_json::Value;
@@ -57,6 +58,22 @@ async fn applies_path_generator_for_the_path_to_the_copy_of_the_request() {
   expect!(generate_request(&request, &GeneratorTestMode::Provider, &hashmap!{}).await.path).to_not(be_equal_to("/path"));
 }
 
+#[tokio::test]
+async fn applies_path_segment_generators_to_the_copy_of_the_request() {
+  let mut generators = Generators::default();
+  generators.add_generator_with_subcategory(&GeneratorCategory::PATH, path_template::segment_path(1),
+    Generator::ProviderStateGenerator("${orderId}".to_string(), Some(DataType::INTEGER)));
+  generators.add_generator_with_subcategory(&GeneratorCategory::PATH, path_template::segment_path(3),
+    Generator::ProviderStateGenerator("itemId".to_string(), None));
+  let request = HttpRequest { path: s!("/orders/100/items/abc"), generators, .. HttpRequest::default() };
+  let context = hashmap! {
+    "orderId" => json!(1234),
+    "itemId" => json!("xyz")
+  };
+  let result = generate_request(&request, &GeneratorTestMode::Provider, &context).await;
+  expect!(result.path).to(be_equal_to("/orders/1234/items/xyz"));
+}
+
 #[tokio::test]
 async fn applies_header_generator_for_headers_to_the_copy_of_the_request() {
   let request = HttpRequest { headers: Some(hashmap!{
@@ -114,6 +131,34 @@ async fn applies_provider_state_generator_for_query_parameters_with_square_brack
   expect!(q_val).to(be_equal_to(&vec![Some("5678".to_string()), Some("5678".to_string())]));
 }
 
+#[tokio::test]
+async fn applies_generators_to_nested_query_parameters() {
+  let mut generators = Generators::default();
+  generators.add_generator_with_subcategory(&GeneratorCategory::QUERY, DocPath::new_unwrap("$.filter.status"),
+    Generator::ProviderStateGenerator("status".to_string(), None));
+  generators.add_generator_with_subcategory(&GeneratorCategory::QUERY, DocPath::new_unwrap("$.q.age.gt"),
+    Generator::ProviderStateGenerator("${minAge}".to_string(), Some(DataType::INTEGER)));
+  let request = HttpRequest {
+    query: Some(hashmap!{
+      "filter[status]".to_string() => vec![ Some("open".to_string()) ],
+      "filter[tags][]".to_string() => vec![ Some("a".to_string()), Some("b".to_string()) ],
+      "q".to_string() => vec![ Some(r#"{"age":{"gt":3}}"#.to_string()) ]
+    }),
+    generators,
+    .. HttpRequest::default()
+  };
+  let context = hashmap! {
+    "status" => json!("closed"),
+    "minAge" => json!(18)
+  };
+  let result = generate_request(&request, &GeneratorTestMode::Provider, &context).await;
+  expect!(result.query).to(be_some().value(hashmap!{
+    "filter[status]".to_string() => vec![ Some("closed".to_string()) ],
+    "filter[tags][]".to_string() => vec![ Some("a".to_string()), Some("b".to_string()) ],
+    "q".to_string() => vec![ Some(r#"{"age":{"gt":18}}"#.to_string()) ]
+  }));
+}
+
 #[tokio::test]
 async fn applies_body_generator_to_the_copy_of_the_request() {
   let request = HttpRequest { body: OptionalBody::Present("{\"a\": 100, \"b\": \"B\"}".into(), None, None),
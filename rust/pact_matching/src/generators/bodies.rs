@@ -8,12 +8,14 @@ use tracing::{debug, error, warn};
 
 use pact_models::bodies::OptionalBody;
 use pact_models::content_types::ContentType;
+use pact_models::csv_utils::{csv_delimiter, csv_has_header, CsvDocument};
 use pact_models::generators::{ContentTypeHandler, Generator, GeneratorTestMode, JsonHandler, VariantMatcher};
 use pact_models::path_exp::DocPath;
 use pact_models::plugins::PluginData;
 #[cfg(feature = "xml")] use pact_models::xml_utils::parse_bytes;
 
 #[cfg(feature = "xml")] use crate::generators::XmlHandler;
+#[cfg(feature = "protobuf")] use crate::protobuf::generate_protobuf_body;
 
 /// Apply the generators to the body, returning a new body
 #[allow(unused_variables)]
@@ -68,25 +70,53 @@ pub async fn generators_process_body(
         Ok(body.clone())
       }
     }
-    else {
-      #[cfg(feature = "plugins")]
-      {
-        if let Some(content_generator) = find_content_generator(&content_type) {
-          debug!("apply_body_generators: Found a content generator from a plugin");
-          let generators = generators.iter()
-            .map(|(k, v)| (k.to_string(), v.clone()))
-            .collect();
-          content_generator.generate_content(&content_type, &generators, body, plugin_data, interaction_data, context).await
-        } else {
-          warn!("Unsupported content type {} - Generators only support JSON and XML", content_type);
+    else if content_type.is_csv() {
+      debug!("apply_body_generators: CSV content type");
+      let text = String::from_utf8_lossy(&body.value().unwrap_or_default()).to_string();
+      let delimiter = csv_delimiter(&content_type);
+      match CsvDocument::parse(&text, delimiter, csv_has_header(&content_type)) {
+        Ok(doc) => {
+          let mut handler = JsonHandler { value: doc.to_json() };
+          match handler.process_body(generators, mode, context, &matcher.boxed()) {
+            Ok(_) => match CsvDocument::from_json(&handler.value, doc.columns.as_ref()) {
+              Ok(doc) => Ok(OptionalBody::Present(doc.write(delimiter).into(), Some(content_type), None)),
+              Err(err) => {
+                error!("Failed to generate the body: {}", err);
+                Ok(body.clone())
+              }
+            },
+            Err(err) => {
+              error!("Failed to generate the body: {}", err);
+              Ok(body.clone())
+            }
+          }
+        },
+        Err(err) => {
+          error!("Failed to parse the body, so not applying any generators: {}", err);
           Ok(body.clone())
         }
       }
-      #[cfg(not(feature = "plugins"))]
-      {
-        warn!("Unsupported content type {} - Generators only support JSON and XML", content_type);
-        Ok(body.clone())
+    }
+    else {
+      #[cfg(feature = "plugins")]
+      if let Some(content_generator) = find_content_generator(&content_type) {
+        debug!("apply_body_generators: Found a content generator from a plugin");
+        let generators = generators.iter()
+          .map(|(k, v)| (k.to_string(), v.clone()))
+          .collect();
+        return content_generator.generate_content(&content_type, &generators, body, plugin_data, interaction_data, context).await;
+      }
+      #[cfg(feature = "protobuf")]
+      if content_type.is_protobuf() {
+        debug!("apply_body_generators: Protobuf content type");
+        return Ok(generate_protobuf_body(mode, body, &content_type, context, generators, matcher, plugin_data,
+          interaction_data).unwrap_or_else(|err| {
+          error!("Failed to generate the body: {}", err);
+          body.clone()
+        }));
       }
+      warn!("Unsupported content type {} - Generators only support JSON and XML", content_type);
+      Ok(body.clone())
     },
     _ => Ok(body.clone())
   }
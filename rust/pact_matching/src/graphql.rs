@@ -0,0 +1,280 @@
+//! Functions for matching GraphQL requests and responses. The `query` of a GraphQL request is
+//! compared using the normalised form of the operation document (see `pact_models::graphql`), and
+//! the other fields (`variables`, `operationName` and `extensions`) are matched with the JSON
+//! matching rules. For responses, `data` and `errors` are matched with the JSON matching rules, and
+//! any errors received when none were expected are reported. Bodies are only compared as GraphQL
+//! if they have been marked as GraphQL (see `pact_models::graphql::mark_as_graphql`) or have the
+//! GraphQL response content type.
+
+use bytes::Bytes;
+use itertools::Itertools;
+use pact_models::content_types::ContentType;
+use pact_models::graphql::{
+  is_graphql_request,
+  is_graphql_response,
+  is_graphql_response_content_type,
+  is_marked_as_graphql,
+  normalise_query
+};
+use pact_models::json_utils::json_to_string;
+use pact_models::path_exp::DocPath;
+use serde_json::Value;
+use tracing::debug;
+
+use crate::{CommonMismatch, MatchingContext, Mismatch};
+use crate::json::compare_json;
+
+/// If the JSON bodies should be compared as a GraphQL request or response. This is the case if the
+/// matching rules mark the body as GraphQL, or the expected body has the GraphQL response content type.
+pub(crate) fn is_graphql_body(content_type: Option<ContentType>, context: &dyn MatchingContext) -> bool {
+  is_marked_as_graphql(context.matchers()) ||
+    content_type.map(|ct| is_graphql_response_content_type(&ct)).unwrap_or(false)
+}
+
+/// Compares the JSON bodies as a GraphQL request or response. Returns `None` if the expected body
+/// is not a GraphQL request or response, or if there is a matching rule defined for the query.
+pub(crate) fn compare_graphql(
+  expected: &Value,
+  actual: &Value,
+  context: &(dyn MatchingContext + Send + Sync)
+) -> Option<Result<(), Vec<CommonMismatch>>> {
+  let query_path = DocPath::new_unwrap("$.query");
+  if is_graphql_request(expected) && !context.matcher_is_defined(&query_path) {
+    Some(compare_graphql_request(expected, actual, context))
+  } else if is_graphql_response(expected) && actual.is_object() {
+    Some(compare_graphql_response(expected, actual, context))
+  } else {
+    None
+  }
+}
+
+fn compare_graphql_request(
+  expected: &Value,
+  actual: &Value,
+  context: &(dyn MatchingContext + Send + Sync)
+) -> Result<(), Vec<CommonMismatch>> {
+  let mut mismatches = vec![];
+  let expected_query = expected.get("query").and_then(|query| query.as_str()).unwrap_or_default();
+  let expected_operation = expected.get("operationName").and_then(|name| name.as_str());
+  let actual_query = actual.get("query").and_then(|query| query.as_str());
+  let actual_operation = actual.get("operationName").and_then(|name| name.as_str());
+
+  match actual_query {
+    Some(actual_query) => {
+      if let Err(mismatch) = compare_documents(expected_query, expected_operation, actual_query, actual_operation) {
+        mismatches.push(mismatch);
+      }
+    }
+    None => mismatches.push(CommonMismatch {
+      path: "$.query".to_string(),
+      expected: expected_query.to_string(),
+      actual: actual.get("query").map(json_to_string).unwrap_or_default(),
+      description: "Expected a GraphQL query string".to_string()
+    })
+  }
+
+  // The query has already been compared, so the remaining fields are matched as JSON
+  let mut expected = expected.clone();
+  let mut actual = actual.clone();
+  for body in [&mut expected, &mut actual] {
+    if let Some(map) = body.as_object_mut() {
+      map.remove("query");
+    }
+  }
+  if let Err(result) = compare_json(&DocPath::root(), &expected, &actual, context) {
+    mismatches.extend(result);
+  }
+
+  if mismatches.is_empty() {
+    Ok(())
+  } else {
+    Err(mismatches)
+  }
+}
+
+fn compare_documents(
+  expected: &str,
+  expected_operation: Option<&str>,
+  actual: &str,
+  actual_operation: Option<&str>
+) -> Result<(), CommonMismatch> {
+  let expected_normalised = normalise_query(expected, expected_operation).unwrap_or_else(|_| expected.to_string());
+  match normalise_query(actual, actual_operation) {
+    Ok(actual_normalised) => {
+      debug!(expected = %expected_normalised, actual = %actual_normalised, "Comparing GraphQL operations");
+      if expected_normalised == actual_normalised {
+        Ok(())
+      } else {
+        Err(CommonMismatch {
+          path: "$.query".to_string(),
+          expected: expected.to_string(),
+          actual: actual.to_string(),
+          description: format!("Expected GraphQL operation '{}' but received '{}'", expected_normalised, actual_normalised)
+        })
+      }
+    }
+    Err(err) => Err(CommonMismatch {
+      path: "$.query".to_string(),
+      expected: expected.to_string(),
+      actual: actual.to_string(),
+      description: format!("Failed to parse the actual GraphQL query: {}", err)
+    })
+  }
+}
+
+fn compare_graphql_response(
+  expected: &Value,
+  actual: &Value,
+  context: &(dyn MatchingContext + Send + Sync)
+) -> Result<(), Vec<CommonMismatch>> {
+  let mut mismatches = vec![];
+  let mut actual = actual.clone();
+
+  let actual_errors = actual.get("errors").and_then(|errors| errors.as_array()).cloned().unwrap_or_default();
+  if expected.get("errors").is_none() && !actual_errors.is_empty() {
+    let messages = actual_errors.iter()
+      .map(|error| error.get("message").map(json_to_string).unwrap_or_else(|| error.to_string()))
+      .join("; ");
+    mismatches.push(CommonMismatch {
+      path: "$.errors".to_string(),
+      expected: String::default(),
+      actual: Value::Array(actual_errors.clone()).to_string(),
+      description: format!("Expected no GraphQL errors but received {}: {}", actual_errors.len(), messages)
+    });
+    // Errors have already been reported, so don't report them again as an unexpected key
+    if let Some(map) = actual.as_object_mut() {
+      map.remove("errors");
+    }
+  }
+
+  if let Err(result) = compare_json(&DocPath::root(), expected, &actual, context) {
+    mismatches.extend(result);
+  }
+
+  if mismatches.is_empty() {
+    Ok(())
+  } else {
+    Err(mismatches)
+  }
+}
+
+/// Matches raw GraphQL documents (`application/graphql` bodies)
+pub(crate) fn match_graphql_document(
+  expected: &Option<Bytes>,
+  actual: &Option<Bytes>
+) -> Result<(), Vec<Mismatch>> {
+  let expected_str = String::from_utf8_lossy(expected.as_ref().map(|b| b.as_ref()).unwrap_or_default());
+  let actual_str = String::from_utf8_lossy(actual.as_ref().map(|b| b.as_ref()).unwrap_or_default());
+  compare_documents(&expected_str, None, &actual_str, None)
+    .map_err(|mismatch| vec![Mismatch::BodyMismatch {
+      path: "$".to_string(),
+      expected: expected.clone(),
+      actual: actual.clone(),
+      mismatch: mismatch.description
+    }])
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use maplit::hashmap;
+  use pact_models::graphql::mark_as_graphql;
+  use pact_models::matchingrules::MatchingRule;
+  use pact_models::matchingrules_list;
+  use serde_json::json;
+
+  use crate::{CoreMatchingContext, DiffConfig};
+
+  use super::*;
+
+  fn descriptions(result: Option<Result<(), Vec<CommonMismatch>>>) -> Vec<String> {
+    result.unwrap().unwrap_err().iter().map(|m| m.description.clone()).collect()
+  }
+
+  #[test]
+  fn compare_graphql_request_test() {
+    let context = CoreMatchingContext::new(DiffConfig::NoUnexpectedKeys, &matchingrules_list! {
+      "body"; "$.variables.id" => [ MatchingRule::Integer ]
+    }, &hashmap!{});
+    let expected = json!({
+      "query": "query GetUser($id: Int!) { user(id: $id) { ... on User { id name } } }",
+      "variables": { "id": 100 }
+    });
+
+    let actual = json!({
+      "query": "query GetUser($id: Int!) {\n  user(id: $id) {\n    ...Fields\n  }\n}\nfragment Fields on User { name id }",
+      "variables": { "id": 2 }
+    });
+    expect!(compare_graphql(&expected, &actual, &context)).to(be_some().value(Ok(())));
+
+    let actual = json!({
+      "query": "query GetUser($id: Int!) { user(id: $id) { ... on User { id email } } }",
+      "variables": { "id": "two" }
+    });
+    expect!(descriptions(compare_graphql(&expected, &actual, &context))).to(be_equal_to(vec![
+      "Expected GraphQL operation 'query($id:Int!){user(id:$id){... on User{id name}}}' but received 'query($id:Int!){user(id:$id){... on User{email id}}}'".to_string(),
+      "Expected 'two' (String) to be an integer number".to_string()
+    ]));
+
+    let actual = json!({ "query": "query { user( }", "variables": { "id": 2 } });
+    expect!(descriptions(compare_graphql(&expected, &actual, &context))).to(be_equal_to(vec![
+      "Failed to parse the actual GraphQL query: Expected a name but found Punctuator('}')".to_string()
+    ]));
+  }
+
+  #[test]
+  fn compare_graphql_request_with_query_matcher() {
+    let context = CoreMatchingContext::new(DiffConfig::NoUnexpectedKeys, &matchingrules_list! {
+      "body"; "$.query" => [ MatchingRule::Type ]
+    }, &hashmap!{});
+    let expected = json!({ "query": "{ user { id } }" });
+    expect!(compare_graphql(&expected, &json!({ "query": "{ other }" }), &context)).to(be_none());
+  }
+
+  #[test]
+  fn compare_graphql_response_test() {
+    let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &matchingrules_list! {
+      "body"; "$.data.user.id" => [ MatchingRule::Type ]
+    }, &hashmap!{});
+    let expected = json!({ "data": { "user": { "id": 1, "name": "Fred" } } });
+
+    let actual = json!({ "data": { "user": { "id": 2, "name": "Fred", "email": "fred@example.com" } } });
+    expect!(compare_graphql(&expected, &actual, &context)).to(be_some().value(Ok(())));
+
+    let actual = json!({
+      "data": { "user": { "id": 2, "name": "Fred" } },
+      "errors": [ { "message": "Not authorised", "path": ["user", "email"] } ]
+    });
+    expect!(descriptions(compare_graphql(&expected, &actual, &context))).to(be_equal_to(vec![
+      "Expected no GraphQL errors but received 1: Not authorised".to_string()
+    ]));
+
+    let expected = json!({ "data": { "user": null }, "errors": [ { "message": "Not found" } ] });
+    let actual = json!({ "data": { "user": null }, "errors": [ { "message": "Not authorised" } ] });
+    expect!(descriptions(compare_graphql(&expected, &actual, &context))).to(be_equal_to(vec![
+      "Expected 'Not authorised' (String) to be equal to 'Not found' (String)".to_string()
+    ]));
+  }
+
+  #[test]
+  fn is_graphql_body_test() {
+    let context = CoreMatchingContext::new(DiffConfig::NoUnexpectedKeys, &matchingrules_list! {
+      "body"; "$.data.user.id" => [ MatchingRule::Type ]
+    }, &hashmap!{});
+    expect!(is_graphql_body(Some(ContentType::from("application/json")), &context)).to(be_false());
+    expect!(is_graphql_body(Some(ContentType::from("application/graphql-response+json")), &context)).to(be_true());
+
+    let mut rules = matchingrules_list! { "body"; "$.data.user.id" => [ MatchingRule::Type ] };
+    mark_as_graphql(&mut rules);
+    let context = CoreMatchingContext::new(DiffConfig::NoUnexpectedKeys, &rules, &hashmap!{});
+    expect!(is_graphql_body(Some(ContentType::from("application/json")), &context)).to(be_true());
+    expect!(is_graphql_body(None, &context)).to(be_true());
+  }
+
+  #[test]
+  fn match_graphql_document_test() {
+    let expected = Some(Bytes::from("{ hero { name id } }"));
+    expect!(match_graphql_document(&expected, &Some(Bytes::from("query { hero { id, name } }")))).to(be_ok());
+    expect!(match_graphql_document(&expected, &Some(Bytes::from("{ hero { id } }")))).to(be_err());
+  }
+}
@@ -14,6 +14,8 @@ use crate::{matchers, MatchingContext, Mismatch, CommonMismatch};
 use crate::matchers::Matches;
 use crate::matchingrules::compare_lists_with_matchingrules;
 
+pub mod structured;
+
 fn strip_whitespace<'a, T: FromIterator<&'a str>>(val: &'a str, split_by: &'a str) -> T {
   val.split(split_by).map(|v| v.trim()).filter(|v| !v.is_empty()).collect()
 }
@@ -148,8 +150,11 @@ fn match_header_maps(
       } else {
         let mut mismatches = vec![];
 
-        // Special case when the headers only have 1 value to improve messaging
-        if value.len() == 1 && actual_values.len() == 1 {
+        // Headers with structured values are matched component by component
+        if let Some(structured_result) = structured::match_structured_header(key, value, &actual_values, context) {
+          mismatches.extend(structured_result.err().unwrap_or_default());
+        } else if value.len() == 1 && actual_values.len() == 1 {
+          // Special case when the headers only have 1 value to improve messaging
           let comparison_result = match_header_value(key, 0, value.first().unwrap(),
             actual_values.first().unwrap(), context, true)
             .err()
@@ -846,4 +851,45 @@ mod tests {
       }
     ]));
   }
+
+  #[test]
+  fn match_headers_with_structured_header_values() {
+    let context = HeaderMatchingContext::new(&CoreMatchingContext::new(
+      DiffConfig::NoUnexpectedKeys,
+      &matchingrules! {
+        "header" => {
+          "$.Set-Cookie.session" => [ MatchingRule::Regex("[a-f0-9]+".to_string()) ],
+          "$.Cache-Control.max-age" => [ MatchingRule::Integer ]
+        }
+      }.rules_for_category("header").unwrap_or_default(), &hashmap!{}
+    ));
+    let expected = hashmap! {
+      "Set-Cookie".to_string() => vec!["session=abc123; Path=/; HttpOnly".to_string()],
+      "Cache-Control".to_string() => vec!["private".to_string(), "max-age=60".to_string()]
+    };
+    let actual = hashmap! {
+      "set-cookie".to_string() => vec!["session=ff00; HttpOnly; Path=/".to_string()],
+      "cache-control".to_string() => vec!["max-age=3600".to_string(), "private".to_string()]
+    };
+    let result = match_headers(Some(expected.clone()), Some(actual), &context);
+    expect!(result.values().flatten()).to(be_empty());
+
+    let actual = hashmap! {
+      "Set-Cookie".to_string() => vec!["session=xyz; Path=/".to_string()],
+      "Cache-Control".to_string() => vec!["max-age=3600".to_string()]
+    };
+    let result = match_headers(Some(expected), Some(actual), &context);
+    let mut mismatches = result.values().flatten()
+      .map(|m| match m {
+        Mismatch::HeaderMismatch { mismatch, .. } => mismatch.clone(),
+        _ => m.description()
+      })
+      .collect::<Vec<_>>();
+    mismatches.sort();
+    expect!(mismatches).to(be_equal_to(vec![
+      "Mismatch with header 'Cache-Control' directive 'private': Expected directive 'private' but was missing".to_string(),
+      "Mismatch with header 'Set-Cookie' cookie 'session' attribute 'httponly': Expected attribute 'httponly' but was missing".to_string(),
+      "Mismatch with header 'Set-Cookie' cookie 'session': Expected 'xyz' to match '[a-f0-9]+'".to_string()
+    ]));
+  }
 }
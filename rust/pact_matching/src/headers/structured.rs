@@ -0,0 +1,571 @@
+//! Parsers and matching functions for headers that have a structured value (cookies, links,
+//! cache directives, preferences and RFC 8941 structured fields). These headers are split into
+//! their components, so matching rules can target an individual component with a path like
+//! `$.Set-Cookie.session.HttpOnly` or `$.Cache-Control.max-age`.
+
+use itertools::Itertools;
+use pact_models::matchingrules::MatchingRule;
+use pact_models::path_exp::{DocPath, PathToken};
+use tracing::{debug, trace};
+
+use crate::{matchers, CommonMismatch, MatchingContext};
+use crate::matchers::Matches;
+
+/// Headers that have a structured value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StructuredHeaderKind {
+  /// Cookie request header (`name=value; name2=value2`)
+  Cookie,
+  /// Set-Cookie response header (`name=value; Path=/; HttpOnly`)
+  SetCookie,
+  /// Link header (RFC 8288), keyed by the link relation
+  Link,
+  /// Cache-Control directives (RFC 9111)
+  CacheControl,
+  /// Prefer header preferences (RFC 7240)
+  Prefer,
+  /// RFC 8941 structured field dictionary
+  Dictionary,
+  /// RFC 8941 structured field list
+  List
+}
+
+impl StructuredHeaderKind {
+  /// Name used for the components of the header in mismatch messages
+  fn component_label(&self) -> &'static str {
+    match self {
+      StructuredHeaderKind::Cookie | StructuredHeaderKind::SetCookie => "cookie",
+      StructuredHeaderKind::Link => "link relation",
+      StructuredHeaderKind::CacheControl => "directive",
+      StructuredHeaderKind::Prefer => "preference",
+      StructuredHeaderKind::Dictionary => "member",
+      StructuredHeaderKind::List => "item"
+    }
+  }
+
+  /// Name used for the parameters of a component in mismatch messages
+  fn parameter_label(&self) -> &'static str {
+    match self {
+      StructuredHeaderKind::SetCookie => "attribute",
+      _ => "parameter"
+    }
+  }
+
+  /// If the names of the components are case-sensitive. Cookie names are (RFC 6265), the names
+  /// of the components of the other headers are not.
+  fn case_sensitive_names(&self) -> bool {
+    matches!(self, StructuredHeaderKind::Cookie | StructuredHeaderKind::SetCookie)
+  }
+}
+
+/// Returns the kind of structured value the header has, if it is a known structured header.
+pub fn structured_header_kind(name: &str) -> Option<StructuredHeaderKind> {
+  match name.to_lowercase().as_str() {
+    "cookie" => Some(StructuredHeaderKind::Cookie),
+    "set-cookie" => Some(StructuredHeaderKind::SetCookie),
+    "link" => Some(StructuredHeaderKind::Link),
+    "cache-control" | "cdn-cache-control" | "surrogate-control" => Some(StructuredHeaderKind::CacheControl),
+    "prefer" | "preference-applied" => Some(StructuredHeaderKind::Prefer),
+    "priority" | "signature" | "signature-input" => Some(StructuredHeaderKind::Dictionary),
+    "cache-status" | "proxy-status" | "accept-ch" | "critical-ch" => Some(StructuredHeaderKind::List),
+    _ => None
+  }
+}
+
+/// Component of a structured header value (a cookie, link, directive, preference or structured
+/// field member)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeaderComponent {
+  /// Name of the component. Directive, preference and link relation names are case-insensitive
+  /// and are lower-cased.
+  pub name: String,
+  /// Value of the component, if it has one
+  pub value: Option<String>,
+  /// Parameters (or cookie attributes) of the component. Parameter names are lower-cased.
+  pub parameters: Vec<(String, Option<String>)>
+}
+
+impl HeaderComponent {
+  fn new(name: &str, value: Option<String>, parameters: Vec<(String, Option<String>)>) -> Self {
+    HeaderComponent {
+      name: name.to_string(),
+      value,
+      parameters
+    }
+  }
+
+  fn parameter(&self, name: &str) -> Option<&Option<String>> {
+    self.parameters.iter()
+      .find(|(param, _)| param.eq_ignore_ascii_case(name))
+      .map(|(_, value)| value)
+  }
+}
+
+/// Splits the value on the separator, ignoring any separators in quoted strings, URI references
+/// (`<...>`) or inner lists (`(...)`). Empty entries are removed.
+fn split_outside_quotes(value: &str, separator: char) -> Vec<&str> {
+  let mut result = vec![];
+  let mut in_quotes = false;
+  let mut escaped = false;
+  let mut depth = 0_usize;
+  let mut start = 0;
+  for (index, ch) in value.char_indices() {
+    if in_quotes {
+      if escaped {
+        escaped = false;
+      } else if ch == '\\' {
+        escaped = true;
+      } else if ch == '"' {
+        in_quotes = false;
+      }
+    } else {
+      match ch {
+        '"' => in_quotes = true,
+        '<' | '(' => depth += 1,
+        '>' | ')' => depth = depth.saturating_sub(1),
+        _ if ch == separator && depth == 0 => {
+          result.push(value[start..index].trim());
+          start = index + ch.len_utf8();
+        }
+        _ => {}
+      }
+    }
+  }
+  result.push(value[start..].trim());
+  result.iter().filter(|v| !v.is_empty()).cloned().collect()
+}
+
+/// Removes the quotes from a quoted string, un-escaping any escaped characters
+fn unquote(value: &str) -> String {
+  let value = value.trim();
+  if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+    let mut result = String::with_capacity(value.len());
+    let mut escaped = false;
+    for ch in value[1..value.len() - 1].chars() {
+      if !escaped && ch == '\\' {
+        escaped = true;
+      } else {
+        result.push(ch);
+        escaped = false;
+      }
+    }
+    result
+  } else {
+    value.to_string()
+  }
+}
+
+fn parse_key_value(value: &str) -> (String, Option<String>) {
+  match value.split_once('=') {
+    Some((key, value)) => (key.trim().to_string(), Some(unquote(value))),
+    None => (value.trim().to_string(), None)
+  }
+}
+
+fn parse_parameters(parameters: &[&str]) -> Vec<(String, Option<String>)> {
+  parameters.iter()
+    .map(|param| {
+      let (key, value) = parse_key_value(param);
+      (key.to_lowercase(), value)
+    })
+    .collect()
+}
+
+/// Parses a single Set-Cookie header value
+fn parse_set_cookie(value: &str) -> Option<HeaderComponent> {
+  let parts = split_outside_quotes(value, ';');
+  let (cookie, attributes) = parts.split_first()?;
+  let (name, value) = cookie.split_once('=')?;
+  Some(HeaderComponent::new(name.trim(), Some(unquote(value)), parse_parameters(attributes)))
+}
+
+/// Parses a single link value (`<uri>; rel="next"; title="..."`). A component is returned for each
+/// link relation. Links without a relation are keyed by the target URI.
+fn parse_link(value: &str) -> Option<Vec<HeaderComponent>> {
+  let parts = split_outside_quotes(value, ';');
+  let (target, parameters) = parts.split_first()?;
+  let target = target.strip_prefix('<')?.strip_suffix('>')?.trim();
+  let parameters = parse_parameters(parameters);
+  let relations = parameters.iter()
+    .find(|(name, _)| name == "rel")
+    .and_then(|(_, value)| value.clone())
+    .map(|rel| rel.split_whitespace().map(|r| r.to_lowercase()).collect_vec())
+    .unwrap_or_else(|| vec![target.to_string()]);
+  let parameters = parameters.iter()
+    .filter(|(name, _)| name != "rel")
+    .cloned()
+    .collect_vec();
+  Some(relations.iter()
+    .map(|rel| HeaderComponent::new(rel, Some(target.to_string()), parameters.clone()))
+    .collect())
+}
+
+/// Parses the values of a structured header into its components. The values are the individual
+/// header values. As the header values may have been split on commas that are inside quoted
+/// strings, comma separated values are joined back together before being parsed. Returns `None`
+/// if the values can not be parsed.
+pub fn parse_structured_header(kind: StructuredHeaderKind, values: &[String]) -> Option<Vec<HeaderComponent>> {
+  let joined = values.join(", ");
+  match kind {
+    StructuredHeaderKind::SetCookie => values.iter()
+      .map(|value| parse_set_cookie(value))
+      .collect(),
+    StructuredHeaderKind::Cookie => Some(values.iter()
+      .flat_map(|value| split_outside_quotes(value, ';'))
+      .map(|cookie| {
+        let (name, value) = parse_key_value(cookie);
+        HeaderComponent::new(&name, value, vec![])
+      })
+      .collect()),
+    StructuredHeaderKind::Link => {
+      let links = split_outside_quotes(&joined, ',').into_iter()
+        .map(parse_link)
+        .collect::<Option<Vec<_>>>()?;
+      Some(links.concat())
+    }
+    StructuredHeaderKind::CacheControl => Some(split_outside_quotes(&joined, ',').into_iter()
+      .map(|directive| {
+        let (name, value) = parse_key_value(directive);
+        HeaderComponent::new(&name.to_lowercase(), value, vec![])
+      })
+      .collect()),
+    StructuredHeaderKind::Prefer | StructuredHeaderKind::Dictionary => split_outside_quotes(&joined, ',').into_iter()
+      .map(|member| {
+        let parts = split_outside_quotes(member, ';');
+        let (first, parameters) = parts.split_first()?;
+        let (name, value) = parse_key_value(first);
+        Some(HeaderComponent::new(&name.to_lowercase(), value, parse_parameters(parameters)))
+      })
+      .collect(),
+    StructuredHeaderKind::List => split_outside_quotes(&joined, ',').into_iter()
+      .map(|item| {
+        let parts = split_outside_quotes(item, ';');
+        let (first, parameters) = parts.split_first()?;
+        Some(HeaderComponent::new(&unquote(first), None, parse_parameters(parameters)))
+      })
+      .collect()
+  }
+}
+
+/// Rule paths defined for the header
+fn header_rule_paths<'a>(key: &'a str, context: &'a dyn MatchingContext) -> impl Iterator<Item = &'a DocPath> + 'a {
+  context.matchers().rules.keys()
+    .filter(move |path| path.first_field().map(|field| field.eq_ignore_ascii_case(key)).unwrap_or(false))
+}
+
+/// If there are rules defined on the header value as a whole (or on the individual values by
+/// index), in which case the header is matched as a list of values.
+fn header_value_rules_defined(key: &str, context: &dyn MatchingContext) -> bool {
+  header_rule_paths(key, context)
+    .any(|path| {
+      let tokens = path.tokens();
+      tokens.len() <= 2 || matches!(tokens[2], PathToken::Index(_) | PathToken::StarIndex)
+    })
+}
+
+/// If there are rules defined for the components of the header (i.e. `$.Set-Cookie.session`).
+/// Structured header values are only matched by component if there are.
+fn component_rules_defined(key: &str, context: &dyn MatchingContext) -> bool {
+  header_rule_paths(key, context)
+    .any(|path| {
+      let tokens = path.tokens();
+      tokens.len() > 2 && matches!(tokens[2], PathToken::Field(_) | PathToken::Star)
+    })
+}
+
+fn match_component_value(
+  path: &DocPath,
+  expected: &str,
+  actual: &str,
+  context: &dyn MatchingContext
+) -> Result<(), Vec<String>> {
+  let rules = context.select_exact_matcher(path);
+  if rules.is_empty() {
+    expected.matches_with(actual, &MatchingRule::Equality, false)
+      .map_err(|err| vec![err.to_string()])
+  } else {
+    trace!(%path, ?rules, "match_component_value: matching with rules");
+    matchers::match_values(path, &rules, expected, actual)
+  }
+}
+
+/// Matches a header with a structured value by comparing each expected component (and component
+/// parameter) to the actual one. Additional components and parameters in the actual value are
+/// ignored.
+///
+/// Matching by component is opt-in, so this returns `None` if the header does not have a
+/// structured value, there are no matching rules defined for the components of the header, there
+/// are matching rules defined for the header value as a whole or either of the values can not be
+/// parsed. In these cases the header values should be matched as plain strings.
+pub fn match_structured_header(
+  key: &str,
+  expected: &[String],
+  actual: &[String],
+  context: &dyn MatchingContext
+) -> Option<Result<(), Vec<CommonMismatch>>> {
+  let kind = structured_header_kind(key)?;
+  if !component_rules_defined(key, context) || header_value_rules_defined(key, context) {
+    return None;
+  }
+
+  let expected_components = parse_structured_header(kind, expected)?;
+  let actual_components = parse_structured_header(kind, actual)?;
+  if expected_components.is_empty() {
+    return None;
+  }
+  debug!(?kind, ?expected_components, ?actual_components, "Matching structured header '{}'", key);
+
+  let expected_value = expected.join(", ");
+  let actual_value = actual.join(", ");
+  let header_path = DocPath::root().join(key.to_lowercase());
+  let mut mismatches = vec![];
+  let mut mismatch = |description: String| {
+    mismatches.push(CommonMismatch {
+      path: key.to_string(),
+      expected: expected_value.clone(),
+      actual: actual_value.clone(),
+      description: format!("Mismatch with header '{}' {}", key, description)
+    });
+  };
+
+  for component in &expected_components {
+    let label = kind.component_label();
+    let actual_component = match actual_components.iter().find(|c| c.name == component.name) {
+      Some(actual_component) => actual_component,
+      None => {
+        mismatch(format!("{} '{}': Expected {} '{}' but was missing", label, component.name, label, component.name));
+        continue;
+      }
+    };
+
+    let path = if kind.case_sensitive_names() {
+      header_path.join(&component.name)
+    } else {
+      header_path.join(component.name.to_lowercase())
+    };
+    let expected = component.value.clone().unwrap_or_default();
+    let actual = actual_component.value.clone().unwrap_or_default();
+    if let Err(messages) = match_component_value(&path, &expected, &actual, context) {
+      for message in messages {
+        mismatch(format!("{} '{}': {}", label, component.name, message));
+      }
+    }
+
+    let param_label = kind.parameter_label();
+    for (name, value) in &component.parameters {
+      match actual_component.parameter(name) {
+        Some(actual_value) => {
+          let path = path.join(name);
+          let expected = value.clone().unwrap_or_default();
+          let actual = actual_value.clone().unwrap_or_default();
+          if let Err(messages) = match_component_value(&path, &expected, &actual, context) {
+            for message in messages {
+              mismatch(format!("{} '{}' {} '{}': {}", label, component.name, param_label, name, message));
+            }
+          }
+        }
+        None => mismatch(format!("{} '{}' {} '{}': Expected {} '{}' but was missing", label,
+          component.name, param_label, name, param_label, name))
+      }
+    }
+  }
+
+  if mismatches.is_empty() {
+    Some(Ok(()))
+  } else {
+    Some(Err(mismatches))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use pact_models::matchingrules::{MatchingRule, MatchingRuleCategory};
+  use pact_models::matchingrules_list;
+
+  use crate::{CoreMatchingContext, DiffConfig, HeaderMatchingContext};
+
+  use super::*;
+
+  fn values(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+  }
+
+  #[test]
+  fn split_outside_quotes_ignores_separators_in_quotes_and_uris() {
+    expect!(split_outside_quotes(r#"a="1, 2", <http://x/?a=1,2>; rel=next, c"#, ','))
+      .to(be_equal_to(vec![r#"a="1, 2""#, "<http://x/?a=1,2>; rel=next", "c"]));
+    expect!(split_outside_quotes(r#"a=(1, 2);x, b"#, ',')).to(be_equal_to(vec!["a=(1, 2);x", "b"]));
+    expect!(split_outside_quotes("a; ;b;", ';')).to(be_equal_to(vec!["a", "b"]));
+  }
+
+  #[test]
+  fn parse_set_cookie_headers() {
+    let components = parse_structured_header(StructuredHeaderKind::SetCookie,
+      &values(&["session=abc123; Path=/; HttpOnly; Max-Age=3600", "theme=\"dark\""])).unwrap();
+    expect!(components).to(be_equal_to(vec![
+      HeaderComponent::new("session", Some("abc123".to_string()), vec![
+        ("path".to_string(), Some("/".to_string())),
+        ("httponly".to_string(), None),
+        ("max-age".to_string(), Some("3600".to_string()))
+      ]),
+      HeaderComponent::new("theme", Some("dark".to_string()), vec![])
+    ]));
+    expect!(parse_structured_header(StructuredHeaderKind::SetCookie, &values(&["HttpOnly"]))).to(be_none());
+  }
+
+  #[test]
+  fn parse_link_headers() {
+    let components = parse_structured_header(StructuredHeaderKind::Link,
+      &values(&[r#"<https://api.example.com/items?page=2>; rel="next last"; title="Next, page""#,
+        "<https://api.example.com/items?page=1>; rel=prev"])).unwrap();
+    expect!(components).to(be_equal_to(vec![
+      HeaderComponent::new("next", Some("https://api.example.com/items?page=2".to_string()),
+        vec![("title".to_string(), Some("Next, page".to_string()))]),
+      HeaderComponent::new("last", Some("https://api.example.com/items?page=2".to_string()),
+        vec![("title".to_string(), Some("Next, page".to_string()))]),
+      HeaderComponent::new("prev", Some("https://api.example.com/items?page=1".to_string()), vec![])
+    ]));
+    expect!(parse_structured_header(StructuredHeaderKind::Link, &values(&["not a link"]))).to(be_none());
+  }
+
+  #[test]
+  fn parse_dictionary_and_list_headers() {
+    expect!(parse_structured_header(StructuredHeaderKind::Dictionary, &values(&["u=1, i"])).unwrap())
+      .to(be_equal_to(vec![
+        HeaderComponent::new("u", Some("1".to_string()), vec![]),
+        HeaderComponent::new("i", None, vec![])
+      ]));
+    expect!(parse_structured_header(StructuredHeaderKind::Prefer,
+      &values(&["respond-async, wait=100", "return=minimal; foo=\"bar\""])).unwrap())
+      .to(be_equal_to(vec![
+        HeaderComponent::new("respond-async", None, vec![]),
+        HeaderComponent::new("wait", Some("100".to_string()), vec![]),
+        HeaderComponent::new("return", Some("minimal".to_string()), vec![("foo".to_string(), Some("bar".to_string()))])
+      ]));
+    expect!(parse_structured_header(StructuredHeaderKind::List,
+      &values(&["ExampleCache; hit; ttl=30, \"CDN Company Here\"; fwd=uri-miss"])).unwrap())
+      .to(be_equal_to(vec![
+        HeaderComponent::new("ExampleCache", None, vec![("hit".to_string(), None), ("ttl".to_string(), Some("30".to_string()))]),
+        HeaderComponent::new("CDN Company Here", None, vec![("fwd".to_string(), Some("uri-miss".to_string()))])
+      ]));
+  }
+
+  #[test]
+  fn match_structured_header_ignores_other_headers() {
+    let context = CoreMatchingContext::default();
+    expect!(match_structured_header("Content-Type", &values(&["text/plain"]), &values(&["text/plain"]), &context))
+      .to(be_none());
+  }
+
+  fn context_with_rules(rules: MatchingRuleCategory) -> HeaderMatchingContext {
+    HeaderMatchingContext::new(&CoreMatchingContext::new(DiffConfig::NoUnexpectedKeys, &rules, &Default::default()))
+  }
+
+  #[test]
+  fn match_structured_header_is_only_used_if_there_are_rules_for_the_components() {
+    let context = CoreMatchingContext::default();
+    expect!(match_structured_header("Cache-Control", &values(&["no-cache", "max-age=60"]),
+      &values(&["max-age=60", "no-cache"]), &context)).to(be_none());
+
+    let context = context_with_rules(matchingrules_list! {
+      "header"; "$.Set-Cookie.session" => [ MatchingRule::Type ]
+    });
+    expect!(match_structured_header("Cache-Control", &values(&["no-cache", "max-age=60"]),
+      &values(&["max-age=60", "no-cache"]), &context)).to(be_none());
+    expect!(match_structured_header("Set-Cookie", &values(&["session=abc"]),
+      &values(&["session=def"]), &context)).to(be_some().value(Ok(())));
+  }
+
+  #[test]
+  fn match_structured_header_ignores_the_order_of_components() {
+    let context = context_with_rules(matchingrules_list! {
+      "header";
+      "$.Cache-Control.max-age" => [ MatchingRule::Integer ],
+      "$.Cookie.a" => [ MatchingRule::Integer ]
+    });
+    expect!(match_structured_header("Cache-Control", &values(&["no-cache", "max-age=60"]),
+      &values(&["max-age=60", "private", "No-Cache"]), &context)).to(be_some().value(Ok(())));
+    expect!(match_structured_header("Cookie", &values(&["a=1; b=2"]), &values(&["b=2; c=3; a=1"]), &context))
+      .to(be_some().value(Ok(())));
+  }
+
+  #[test]
+  fn match_structured_header_compares_cookie_names_exactly() {
+    let context = context_with_rules(matchingrules_list! {
+      "header";
+      "$.Cookie.Session" => [ MatchingRule::Regex("[a-z]+".to_string()) ],
+      "$.cookie.session" => [ MatchingRule::Integer ]
+    });
+    expect!(match_structured_header("cookie", &values(&["Session=abc; session=100"]),
+      &values(&["session=200; Session=def"]), &context)).to(be_some().value(Ok(())));
+
+    let result = match_structured_header("Cookie", &values(&["Session=abc"]),
+      &values(&["session=abc"]), &context).unwrap();
+    let descriptions = result.unwrap_err().iter().map(|m| m.description.clone()).collect_vec();
+    expect!(descriptions).to(be_equal_to(vec![
+      "Mismatch with header 'Cookie' cookie 'Session': Expected cookie 'Session' but was missing".to_string()
+    ]));
+  }
+
+  #[test]
+  fn match_structured_header_names_the_mismatched_component() {
+    let context = context_with_rules(matchingrules_list! {
+      "header";
+      "$.Set-Cookie.theme" => [ MatchingRule::Regex("dark|light".to_string()) ],
+      "$.Cache-Control.no-store" => [ MatchingRule::Equality ]
+    });
+    let result = match_structured_header("Set-Cookie",
+      &values(&["session=abc123; Path=/; HttpOnly", "theme=dark"]),
+      &values(&["session=abc123; Path=/api"]), &context).unwrap();
+    let descriptions = result.unwrap_err().iter().map(|m| m.description.clone()).collect_vec();
+    expect!(descriptions).to(be_equal_to(vec![
+      "Mismatch with header 'Set-Cookie' cookie 'session' attribute 'path': Expected '/api' to be equal to '/'".to_string(),
+      "Mismatch with header 'Set-Cookie' cookie 'session' attribute 'httponly': Expected attribute 'httponly' but was missing".to_string(),
+      "Mismatch with header 'Set-Cookie' cookie 'theme': Expected cookie 'theme' but was missing".to_string()
+    ]));
+
+    let result = match_structured_header("Cache-Control", &values(&["max-age=60"]),
+      &values(&["max-age=120"]), &context).unwrap();
+    expect!(result.unwrap_err().first().unwrap().description.clone()).to(be_equal_to(
+      "Mismatch with header 'Cache-Control' directive 'max-age': Expected '120' to be equal to '60'"));
+  }
+
+  #[test]
+  fn match_structured_header_applies_rules_to_components() {
+    let context = HeaderMatchingContext::new(&CoreMatchingContext::new(DiffConfig::NoUnexpectedKeys,
+      &matchingrules_list! {
+        "header";
+        "$.Set-Cookie.session" => [ MatchingRule::Regex("[a-z0-9]+".to_string()) ],
+        "$.Set-Cookie.session.Max-Age" => [ MatchingRule::Integer ],
+        "$.Cache-Control.max-age" => [ MatchingRule::Integer ],
+        "$.Link.next" => [ MatchingRule::Regex("https://.*\\?page=\\d+".to_string()) ]
+      }, &Default::default()));
+
+    expect!(match_structured_header("Set-Cookie", &values(&["session=abc123; Max-Age=3600; HttpOnly"]),
+      &values(&["session=zzz999; HttpOnly; Max-Age=60"]), &context)).to(be_some().value(Ok(())));
+    expect!(match_structured_header("Cache-Control", &values(&["public, max-age=60"]),
+      &values(&["max-age=3600, public"]), &context)).to(be_some().value(Ok(())));
+    expect!(match_structured_header("Link", &values(&["<https://x/items?page=2>; rel=next"]),
+      &values(&["<https://y/items?page=5>; rel=\"next\""]), &context)).to(be_some().value(Ok(())));
+
+    let result = match_structured_header("Set-Cookie", &values(&["session=abc123; Max-Age=3600"]),
+      &values(&["session=ABC; Max-Age=never"]), &context).unwrap();
+    let descriptions = result.unwrap_err().iter().map(|m| m.description.clone()).collect_vec();
+    expect!(descriptions).to(be_equal_to(vec![
+      "Mismatch with header 'Set-Cookie' cookie 'session': Expected 'ABC' to match '[a-z0-9]+'".to_string(),
+      "Mismatch with header 'Set-Cookie' cookie 'session' attribute 'max-age': Expected 'never' to match an integer number".to_string()
+    ]));
+  }
+
+  #[test]
+  fn match_structured_header_falls_back_if_rules_are_defined_for_the_whole_header() {
+    let context = context_with_rules(matchingrules_list! {
+      "header";
+      "$.Cache-Control" => [ MatchingRule::Regex("max-age=\\d+".to_string()) ],
+      "$.Cache-Control.max-age" => [ MatchingRule::Integer ]
+    });
+    expect!(match_structured_header("Cache-Control", &values(&["max-age=60"]),
+      &values(&["max-age=120"]), &context)).to(be_none());
+  }
+}
@@ -6,7 +6,8 @@ use ansi_term::Colour::*;
 use anyhow::anyhow;
 use difference::*;
 use lazy_static::lazy_static;
-use onig::Regex;
+#[cfg(not(target_family = "wasm"))] use onig::Regex;
+#[cfg(target_family = "wasm")] use crate::wasm_regex::Regex;
 use semver::Version;
 use serde_json::{json, Value};
 
@@ -19,8 +20,10 @@ use tracing::debug;
 
 use crate::{DiffConfig, MatchingContext, Mismatch, CommonMismatch, merge_result};
 use crate::binary_utils::{convert_data, match_content_type};
+use crate::graphql::{compare_graphql, is_graphql_body, match_graphql_document};
 use crate::matchers::*;
 use crate::matchingrules::{compare_lists_with_matchingrules, compare_maps_with_matchingrule};
+use crate::regex_cache::compile_regex;
 
 lazy_static! {
   static ref DEC_REGEX: Regex = Regex::new(r"\d+\.\d+").unwrap();
@@ -68,7 +71,7 @@ impl Matches<&Value> for Value {
   fn matches_with(&self, actual: &Value, matcher: &MatchingRule, cascaded: bool) -> anyhow::Result<()> {
     let result = match matcher {
       MatchingRule::Regex(regex) => {
-        match Regex::new(regex) {
+        match compile_regex(regex) {
           Ok(re) => {
             let actual_str = match actual {
               Value::String(ref s) => s.clone(),
@@ -305,6 +308,10 @@ pub fn match_json(
   actual: &(dyn HttpPart + Send + Sync),
   context: &(dyn MatchingContext + Send + Sync)
 ) -> Result<(), Vec<super::Mismatch>> {
+  if expected.content_type().map(|ct| ct.sub_type == "graphql").unwrap_or(false) {
+    return match_graphql_document(&expected.body().value(), &actual.body().value());
+  }
+
   let expected_json = serde_json::from_slice(&*expected.body().value().unwrap_or_default());
   let actual_json = serde_json::from_slice(&*actual.body().value().unwrap_or_default());
 
@@ -328,8 +335,37 @@ pub fn match_json(
     }
     Err(mismatches.clone())
   } else {
-    compare_json(&DocPath::root(), &expected_json.unwrap(), &actual_json.unwrap(), context)
-      .map_err(|mismatches| mismatches.iter().map(|mismatch| mismatch.to_body_mismatch()).collect())
+    let expected_json = expected_json.unwrap();
+    let actual_json = actual_json.unwrap();
+    let graphql = is_graphql_body(expected.content_type(), context);
+    let compare = |context: &(dyn MatchingContext + Send + Sync)| {
+      graphql.then(|| compare_graphql(&expected_json, &actual_json, context))
+        .flatten()
+        .unwrap_or_else(|| compare_json(&DocPath::root(), &expected_json, &actual_json, context))
+    };
+    let result = if context.matchers().has_filters() {
+      // Filter expressions select items based on their values, so are resolved against the
+      // actual body to get the paths the rules apply to. A filter that does not select anything
+      // is a mismatch, otherwise its rules would be silently ignored.
+      let unresolved = context.matchers().unresolved_filters(&actual_json).iter()
+        .map(|path| CommonMismatch {
+          path: path.to_string(),
+          expected: path.to_string(),
+          actual: json_to_string(&actual_json),
+          description: format!("Expected the filter expression in '{}' to select at least one value, but it did not match anything", path)
+        })
+        .collect::<Vec<_>>();
+      let context = context.clone_with(&context.matchers().resolve_filters(&actual_json));
+      let result = compare(context.as_ref());
+      if unresolved.is_empty() {
+        result
+      } else {
+        merge_result(Err(unresolved), result)
+      }
+    } else {
+      compare(context)
+    };
+    result.map_err(|mismatches| mismatches.iter().map(|mismatch| mismatch.to_body_mismatch()).collect())
   }
 }
 
@@ -560,6 +596,7 @@ mod tests {
   use maplit::hashmap;
   use pact_models::{matchingrules, matchingrules_list};
   use pact_models::bodies::OptionalBody;
+  use pact_models::graphql::mark_as_graphql;
   use pact_models::matchingrules::{MatchingRule, MatchingRuleCategory};
   use pact_models::matchingrules::expressions::{MatchingRuleDefinition, ValueType};
   use pact_models::request::Request;
@@ -853,6 +890,72 @@ mod tests {
     expect!(result).to(be_ok());
   }
 
+  #[test]
+  fn match_json_with_recursive_descent_rules() {
+    let expected = request!(r#"{"id": 1, "items": [{"id": 2, "name": "a"}], "owner": {"id": 3}}"#);
+    let actual = request!(r#"{"id": 100, "items": [{"id": 200, "name": "a"}], "owner": {"id": 300}}"#);
+    let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &matchingrules_list! {
+      "body"; "$..id" => [ MatchingRule::Integer ]
+    }, &hashmap!{});
+    expect!(match_json(&expected, &actual, &context)).to(be_ok());
+
+    let actual = request!(r#"{"id": 100, "items": [{"id": 2.5, "name": "a"}], "owner": {"id": 300}}"#);
+    expect!(match_json(&expected, &actual, &context)).to(be_err());
+  }
+
+  #[test]
+  fn match_json_with_filter_expression_rules() {
+    let expected = request!(r#"{"items": [{"type": "refund", "amount": 10}, {"type": "sale", "amount": 100}]}"#);
+    let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &matchingrules_list! {
+      "body"; "$.items[?(@.type == 'refund')].amount" => [ MatchingRule::Integer ]
+    }, &hashmap!{});
+
+    let actual = request!(r#"{"items": [{"type": "refund", "amount": 25}, {"type": "sale", "amount": 100}]}"#);
+    expect!(match_json(&expected, &actual, &context)).to(be_ok());
+
+    let actual = request!(r#"{"items": [{"type": "refund", "amount": 25.5}, {"type": "sale", "amount": 100}]}"#);
+    expect!(match_json(&expected, &actual, &context)).to(be_err());
+
+    let actual = request!(r#"{"items": [{"type": "refund", "amount": 25}, {"type": "sale", "amount": 101}]}"#);
+    expect!(match_json(&expected, &actual, &context)).to(be_err());
+  }
+
+  #[test]
+  fn match_json_with_filter_expression_rules_that_do_not_select_anything() {
+    let expected = request!(r#"{"items": [{"type": "refund", "amount": 10}]}"#);
+    let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &matchingrules_list! {
+      "body";
+      "$.items" => [ MatchingRule::MinType(1) ],
+      "$.items[?(@.type == 'refund')].amount" => [ MatchingRule::Integer ]
+    }, &hashmap!{});
+
+    let actual = request!(r#"{"items": [{"type": "refund", "amount": 25}, {"type": "sale", "amount": 100}]}"#);
+    expect!(match_json(&expected, &actual, &context)).to(be_ok());
+
+    let actual = request!(r#"{"items": [{"type": "sale", "amount": 100}]}"#);
+    let mismatches = match_json(&expected, &actual, &context).unwrap_err();
+    expect!(mismatches.iter().map(|m| m.description()).collect::<Vec<_>>()).to(be_equal_to(vec![
+      "$.items[?(@.type == 'refund')].amount -> Expected the filter expression in '$.items[?(@.type == 'refund')].amount' to select at least one value, but it did not match anything".to_string()
+    ]));
+  }
+
+  #[test]
+  fn match_json_only_compares_bodies_marked_as_graphql_as_graphql() {
+    let expected = request!(r#"{"data": {"id": 1}}"#);
+    let actual = request!(r#"{"data": {"id": 1}, "errors": [{"message": "Not found"}]}"#);
+
+    let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &MatchingRuleCategory::empty("body"), &hashmap!{});
+    expect!(match_json(&expected, &actual, &context)).to(be_ok());
+
+    let mut rules = MatchingRuleCategory::empty("body");
+    mark_as_graphql(&mut rules);
+    let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &rules, &hashmap!{});
+    let mismatches = match_json(&expected, &actual, &context).unwrap_err();
+    expect!(mismatches.iter().map(|m| m.description()).collect::<Vec<_>>()).to(be_equal_to(vec![
+      "$.errors -> Expected no GraphQL errors but received 1: Not found".to_string()
+    ]));
+  }
+
     #[test]
     fn equality_matcher_test() {
         let matcher = MatchingRule::Equality;
@@ -359,6 +359,7 @@ use std::hash::Hash;
 use std::panic::RefUnwindSafe;
 use std::str;
 use std::str::from_utf8;
+use std::sync::OnceLock;
 
 use ansi_term::*;
 use ansi_term::Colour::*;
@@ -369,14 +370,16 @@ use lazy_static::*;
 use maplit::{hashmap, hashset};
 use pact_models::bodies::OptionalBody;
 use pact_models::content_types::ContentType;
-use pact_models::generators::{apply_generators, GenerateValue, GeneratorCategory, GeneratorTestMode, VariantMatcher};
+use pact_models::generators::{apply_generators, ContentTypeHandler, GenerateValue, GeneratorCategory, GeneratorTestMode, JsonHandler, VariantMatcher};
 use pact_models::http_parts::HttpPart;
 use pact_models::interaction::Interaction;
 use pact_models::json_utils::json_to_string;
 use pact_models::matchingrules::{Category, MatchingRule, MatchingRuleCategory, RuleList};
 use pact_models::pact::Pact;
 use pact_models::PactSpecification;
-use pact_models::path_exp::DocPath;
+use pact_models::path_exp::{DocPath, PathToken};
+use pact_models::path_template;
+use pact_models::query_strings;
 use pact_models::v4::http_parts::{HttpRequest, HttpResponse};
 use pact_models::v4::message_parts::MessageContents;
 use pact_models::v4::sync_message::SynchronousMessage;
@@ -390,6 +393,7 @@ use crate::generators::DefaultVariantMatcher;
 use crate::generators::bodies::generators_process_body;
 use crate::headers::{match_header_value, match_headers};
 #[cfg(feature = "plugins")] use crate::json::match_json;
+use crate::matcher_index::MatcherIndex;
 use crate::matchers::*;
 use crate::matchingrules::DisplayForMismatch;
 use crate::query::match_query_maps;
@@ -404,10 +408,11 @@ macro_rules! s {
 pub const PACT_RUST_VERSION: Option<&'static str> = option_env!("CARGO_PKG_VERSION");
 
 pub mod matchers;
+pub mod matcher_index;
 pub mod json;
 pub mod logging;
 pub mod matchingrules;
-pub mod metrics;
+#[cfg(not(target_family = "wasm"))] pub mod metrics;
 pub mod generators;
 
 #[cfg(feature = "xml")] mod xml;
@@ -415,11 +420,24 @@ pub mod binary_utils;
 pub mod headers;
 pub mod query;
 pub mod form_urlencoded;
+#[cfg(not(target_family = "wasm"))] pub mod csv;
+pub mod graphql;
+#[cfg(feature = "protobuf")] pub mod protobuf;
+#[cfg(not(target_family = "wasm"))] pub mod sse;
+mod regex_cache;
+mod text_template;
+#[cfg(target_family = "wasm")] mod wasm_regex;
 
 #[cfg(not(feature = "plugins"))]
 #[derive(Clone, Debug, PartialEq)]
-/// Stub for when plugins feature is not enabled
-pub struct PluginInteractionConfig {}
+/// Plugin configuration for an interaction, for when plugins feature is not enabled (the
+/// configuration is still used to match protobuf bodies with the protobuf feature)
+pub struct PluginInteractionConfig {
+  /// Plugin configuration from the Pact
+  pub pact_configuration: HashMap<String, Value>,
+  /// Plugin configuration from the interaction
+  pub interaction_configuration: HashMap<String, Value>
+}
 
 /// Context used to apply matching logic
 pub trait MatchingContext: Debug {
@@ -429,6 +447,18 @@ pub trait MatchingContext: Debug {
   /// Selected the best matcher from the context for the given path
   fn select_best_matcher(&self, path: &DocPath) -> RuleList;
 
+  /// Selects the best matcher defined for exactly the given path (matchers defined on parent
+  /// paths are not returned)
+  fn select_exact_matcher(&self, path: &DocPath) -> RuleList {
+    let path = path.to_vec();
+    let path_slice = path.iter().map(|p| p.as_str()).collect_vec();
+    self.matchers().rules.iter()
+      .filter(|(rule_path, rules)| !rules.is_empty() && rule_path.matches_path_exactly(path_slice.as_slice()))
+      .max_by_key(|(rule_path, _)| rule_path.path_weight(path_slice.as_slice()).0)
+      .map(|(_, rules)| rules.clone())
+      .unwrap_or_default()
+  }
+
   /// If there is a type matcher defined at the path in this context
   fn type_matcher_defined(&self, path: &DocPath) -> bool;
 
@@ -457,14 +487,17 @@ pub trait MatchingContext: Debug {
 #[derive(Debug, Clone)]
 /// Core implementation of a matching context
 pub struct CoreMatchingContext {
-  /// Matching rules that apply when matching with the context
+  /// Matching rules that apply when matching with the context. The rules are indexed on first
+  /// use, so use `set_matchers()` to change them after the context has been used.
   pub matchers: MatchingRuleCategory,
   /// Configuration to apply when matching with the context
   pub config: DiffConfig,
   /// Specification version to apply when matching with the context
   pub matching_spec: PactSpecification,
   /// Any plugin configuration available for the interaction
-  pub plugin_configuration: HashMap<String, PluginInteractionConfig>
+  pub plugin_configuration: HashMap<String, PluginInteractionConfig>,
+  /// Index of the matching rules, built from the matchers on first use
+  matcher_index: OnceLock<MatcherIndex>
 }
 
 impl CoreMatchingContext {
@@ -490,16 +523,37 @@ impl CoreMatchingContext {
     }
   }
 
+  /// Returns the index of the matching rules, building it if required
+  pub fn matcher_index(&self) -> &MatcherIndex {
+    self.matcher_index.get_or_init(|| MatcherIndex::new(&self.matchers))
+  }
+
+  /// Replaces the matching rules of the context. The index will be rebuilt on next use.
+  pub fn set_matchers(&mut self, matchers: MatchingRuleCategory) {
+    self.matchers = matchers;
+    self.matcher_index = OnceLock::new();
+  }
+
+  fn is_indexed(&self) -> bool {
+    matches!(self.matchers.name, Category::HEADER | Category::QUERY | Category::BODY |
+      Category::CONTENTS | Category::METADATA)
+  }
+
   fn matchers_for_exact_path(&self, path: &DocPath) -> MatchingRuleCategory {
     match self.matchers.name {
       Category::HEADER | Category::QUERY => self.matchers.filter(|&(val, _)| {
         path.len() == 1 && path.first_field() == val.first_field()
       }),
-      Category::BODY => self.matchers.filter(|&(val, _)| {
+      Category::BODY => {
         let p = path.to_vec();
         let p_slice = p.iter().map(|p| p.as_str()).collect_vec();
-        val.matches_path_exactly(p_slice.as_slice())
-      }),
+        MatchingRuleCategory {
+          name: self.matchers.name.clone(),
+          rules: self.matcher_index().exact_matches(p_slice.as_slice()).iter()
+            .map(|m| (m.path.clone(), m.rules.clone()))
+            .collect()
+        }
+      },
       _ => self.matchers.filter(|_| false)
     }
   }
@@ -521,7 +575,8 @@ impl Default for CoreMatchingContext {
       matchers: Default::default(),
       config: DiffConfig::AllowUnexpectedKeys,
       matching_spec: PactSpecification::V3,
-      plugin_configuration: Default::default()
+      plugin_configuration: Default::default(),
+      matcher_index: Default::default()
     }
   }
 }
@@ -529,21 +584,43 @@ impl Default for CoreMatchingContext {
 impl MatchingContext for CoreMatchingContext {
   #[instrument(level = "trace", ret, skip_all, fields(path, matchers = ?self.matchers))]
   fn matcher_is_defined(&self, path: &DocPath) -> bool {
-    let path = path.to_vec();
-    let path_slice = path.iter().map(|p| p.as_str()).collect_vec();
-    self.matchers.matcher_is_defined(path_slice.as_slice())
+    if self.is_indexed() {
+      let path = path.to_vec();
+      let path_slice = path.iter().map(|p| p.as_str()).collect_vec();
+      self.matcher_index().matcher_is_defined(path_slice.as_slice())
+    } else {
+      self.matchers.is_not_empty()
+    }
   }
 
   fn select_best_matcher(&self, path: &DocPath) -> RuleList {
     let path = path.to_vec();
     let path_slice = path.iter().map(|p| p.as_str()).collect_vec();
-    self.matchers.select_best_matcher(path_slice.as_slice())
+    match self.matchers.name {
+      Category::BODY | Category::METADATA => self.matcher_index().select_best_matcher(path_slice.as_slice()),
+      _ if self.is_indexed() => self.matcher_index().first_matching_rules(path_slice.as_slice()),
+      _ => self.matchers.select_best_matcher(path_slice.as_slice())
+    }
   }
 
-  fn type_matcher_defined(&self, path: &DocPath) -> bool {
+  fn select_exact_matcher(&self, path: &DocPath) -> RuleList {
     let path = path.to_vec();
     let path_slice = path.iter().map(|p| p.as_str()).collect_vec();
-    self.matchers.resolve_matchers_for_path(path_slice.as_slice()).type_matcher_defined()
+    self.matcher_index().exact_matches(path_slice.as_slice()).iter()
+      .filter(|m| !m.rules.is_empty())
+      .max_by_key(|m| m.weight)
+      .map(|m| m.rules.clone())
+      .unwrap_or_default()
+  }
+
+  fn type_matcher_defined(&self, path: &DocPath) -> bool {
+    if self.is_indexed() {
+      let path = path.to_vec();
+      let path_slice = path.iter().map(|p| p.as_str()).collect_vec();
+      self.matcher_index().type_matcher_defined(path_slice.as_slice())
+    } else {
+      self.matchers.type_matcher_defined()
+    }
   }
 
   fn values_matcher_defined(&self, path: &DocPath) -> bool {
@@ -651,7 +728,8 @@ impl MatchingContext for CoreMatchingContext {
       matchers: matchers.clone(),
       config: self.config.clone(),
       matching_spec: self.matching_spec,
-      plugin_configuration: self.plugin_configuration.clone()
+      plugin_configuration: self.plugin_configuration.clone(),
+      matcher_index: Default::default()
     })
   }
 }
@@ -663,7 +741,8 @@ pub struct HeaderMatchingContext {
 }
 
 impl HeaderMatchingContext {
-  /// Wraps a MatchingContext, downcasing all the matching path keys
+  /// Wraps a MatchingContext, downcasing all the matching path keys (except for cookie names,
+  /// which are case-sensitive)
   pub fn new(context: &(dyn MatchingContext + Send + Sync)) -> Self {
     let matchers = context.matchers();
     HeaderMatchingContext {
@@ -673,7 +752,7 @@ impl HeaderMatchingContext {
           name: matchers.name.clone(),
           rules: matchers.rules.iter()
             .map(|(path, rules)| {
-              (path.to_lower_case(), rules.clone())
+              (header_path_to_lower_case(path), rules.clone())
             })
             .collect()
         },
@@ -683,6 +762,26 @@ impl HeaderMatchingContext {
   }
 }
 
+/// Downcases the header rule path. Cookie names (i.e. `$.Cookie.Session`) are case-sensitive
+/// (RFC 6265), so are left as is.
+fn header_path_to_lower_case(path: &DocPath) -> DocPath {
+  let cookie_header = path.first_field()
+    .map(|header| header.eq_ignore_ascii_case("cookie") || header.eq_ignore_ascii_case("set-cookie"))
+    .unwrap_or(false);
+  if cookie_header {
+    let mut result = DocPath::empty();
+    for (index, token) in path.tokens().iter().enumerate() {
+      match token {
+        PathToken::Field(field) if index != 2 => result.push_field(field.to_lowercase()),
+        _ => result.push(token.clone())
+      };
+    }
+    result
+  } else {
+    path.to_lower_case()
+  }
+}
+
 impl MatchingContext for HeaderMatchingContext {
   fn matcher_is_defined(&self, path: &DocPath) -> bool {
     self.inner_context.matcher_is_defined(path)
@@ -692,6 +791,10 @@ impl MatchingContext for HeaderMatchingContext {
     self.inner_context.select_best_matcher(path)
   }
 
+  fn select_exact_matcher(&self, path: &DocPath) -> RuleList {
+    self.inner_context.select_exact_matcher(path)
+  }
+
   fn type_matcher_defined(&self, path: &DocPath) -> bool {
     self.inner_context.type_matcher_defined(path)
   }
@@ -726,7 +829,8 @@ impl MatchingContext for HeaderMatchingContext {
         matchers: matchers.clone(),
         config: self.inner_context.config.clone(),
         matching_spec: self.inner_context.matching_spec,
-        plugin_configuration: self.inner_context.plugin_configuration.clone()
+        plugin_configuration: self.inner_context.plugin_configuration.clone(),
+        .. CoreMatchingContext::default()
       }
     ))
   }
@@ -735,16 +839,71 @@ impl MatchingContext for HeaderMatchingContext {
 lazy_static! {
   static ref BODY_MATCHERS: [
     (fn(content_type: &ContentType) -> bool,
-    fn(expected: &(dyn HttpPart + Send + Sync), actual: &(dyn HttpPart + Send + Sync), context: &(dyn MatchingContext + Send + Sync)) -> Result<(), Vec<Mismatch>>); 5]
+    fn(expected: &(dyn HttpPart + Send + Sync), actual: &(dyn HttpPart + Send + Sync), context: &(dyn MatchingContext + Send + Sync)) -> Result<(), Vec<Mismatch>>); 8]
      = [
       (|content_type| { content_type.is_json() }, json::match_json),
       (|content_type| { content_type.is_xml() }, match_xml),
       (|content_type| { content_type.main_type == "multipart" }, binary_utils::match_mime_multipart),
       (|content_type| { content_type.base_type() == "application/x-www-form-urlencoded" }, form_urlencoded::match_form_urlencoded),
+      (|content_type| { content_type.is_csv() }, match_csv),
+      (|content_type| { content_type.is_protobuf() }, match_protobuf),
+      (|content_type| { content_type.base_type() == "text/event-stream" }, match_event_stream),
       (|content_type| { content_type.is_binary() || content_type.base_type() == "application/octet-stream" }, binary_utils::match_octet_stream)
   ];
 }
 
+fn match_protobuf(
+  expected: &(dyn HttpPart + Send + Sync),
+  actual: &(dyn HttpPart + Send + Sync),
+  context: &(dyn MatchingContext + Send + Sync)
+) -> Result<(), Vec<Mismatch>> {
+  #[cfg(feature = "protobuf")]
+  {
+    protobuf::match_protobuf(expected, actual, context)
+  }
+  #[cfg(not(feature = "protobuf"))]
+  {
+    warn!("Matching protobuf messages requires the protobuf feature to be enabled (or the protobuf plugin)");
+    binary_utils::match_octet_stream(expected, actual, context)
+  }
+}
+
+fn match_csv(
+  expected: &(dyn HttpPart + Send + Sync),
+  actual: &(dyn HttpPart + Send + Sync),
+  context: &(dyn MatchingContext + Send + Sync)
+) -> Result<(), Vec<Mismatch>> {
+  #[cfg(not(target_family = "wasm"))]
+  {
+    if csv::csv_rules_defined(context) {
+      csv::match_csv(expected, actual, context)
+    } else {
+      match_text(&expected.body().value(), &actual.body().value(), context)
+    }
+  }
+  #[cfg(target_family = "wasm")]
+  {
+    warn!("Matching CSV documents is not supported with WASM");
+    match_text(&expected.body().value(), &actual.body().value(), context)
+  }
+}
+
+fn match_event_stream(
+  expected: &(dyn HttpPart + Send + Sync),
+  actual: &(dyn HttpPart + Send + Sync),
+  context: &(dyn MatchingContext + Send + Sync)
+) -> Result<(), Vec<Mismatch>> {
+  #[cfg(not(target_family = "wasm"))]
+  {
+    sse::match_event_stream(expected, actual, context)
+  }
+  #[cfg(target_family = "wasm")]
+  {
+    warn!("Matching Server-Sent Event streams is not supported with WASM");
+    match_text(&expected.body().value(), &actual.body().value(), context)
+  }
+}
+
 fn match_xml(
   expected: &(dyn HttpPart + Send + Sync),
   actual: &(dyn HttpPart + Send + Sync),
@@ -1337,6 +1496,9 @@ pub enum DiffConfig {
 /// Matches the actual text body to the expected one.
 pub fn match_text(expected: &Option<Bytes>, actual: &Option<Bytes>, context: &dyn MatchingContext) -> Result<(), Vec<Mismatch>> {
   let path = DocPath::root();
+  if let Some(result) = text_template::match_text_template(expected, actual, context) {
+    return result;
+  }
   if context.matcher_is_defined(&path) {
     let mut mismatches = vec![];
     let empty = Bytes::default();
@@ -1407,14 +1569,35 @@ pub fn match_method(expected: &str, actual: &str) -> Result<(), Mismatch> {
   }
 }
 
-/// Matches the actual request path to the expected one.
+/// Matches the actual request path to the expected one. If there are matching rules defined for
+/// the individual path segments (i.e. from a path template), the path is matched segment by segment.
 pub fn match_path(expected: &str, actual: &str, context: &(dyn MatchingContext + Send + Sync)) -> Result<(), Vec<Mismatch>> {
-  let path = DocPath::empty();
-  let matcher_result = if context.matcher_is_defined(&path) {
-    match_values(&path, &context.select_best_matcher(&path), expected.to_string(), actual.to_string())
+  let matchers = context.matchers();
+  let segment_rules = matchers.rules.iter()
+    .filter(|(path, _)| path_template::segment_index(path).is_some())
+    .collect_vec();
+  let matcher_result = if !segment_rules.is_empty() {
+    let mut messages = vec![];
+    let path = DocPath::empty();
+    if let Some(rules) = matchers.rules.get(&path) {
+      if let Err(errors) = match_values(&path, rules, expected.to_string(), actual.to_string()) {
+        messages.extend(errors);
+      }
+    }
+    messages.extend(match_path_segments(expected, actual, &segment_rules));
+    if messages.is_empty() {
+      Ok(())
+    } else {
+      Err(messages)
+    }
   } else {
-    expected.matches_with(actual, &MatchingRule::Equality, false).map_err(|err| vec![err])
-      .map_err(|errors| errors.iter().map(|err| err.to_string()).collect())
+    let path = DocPath::empty();
+    if context.matcher_is_defined(&path) {
+      match_values(&path, &context.select_best_matcher(&path), expected.to_string(), actual.to_string())
+    } else {
+      expected.matches_with(actual, &MatchingRule::Equality, false).map_err(|err| vec![err])
+        .map_err(|errors| errors.iter().map(|err| err.to_string()).collect())
+    }
   };
   matcher_result.map_err(|messages| messages.iter().map(|message| {
     Mismatch::PathMismatch {
@@ -1424,6 +1607,28 @@ pub fn match_path(expected: &str, actual: &str, context: &(dyn MatchingContext +
   }).collect())
 }
 
+fn match_path_segments(expected: &str, actual: &str, segment_rules: &[(&DocPath, &RuleList)]) -> Vec<String> {
+  let expected_segments = path_template::path_segments(expected);
+  let actual_segments = path_template::path_segments(actual);
+  if expected_segments.len() != actual_segments.len() {
+    return vec![format!("Expected path '{}' with {} segment(s) but '{}' has {}", expected,
+      expected_segments.len(), actual, actual_segments.len())];
+  }
+
+  expected_segments.iter().zip(actual_segments.iter()).enumerate()
+    .flat_map(|(index, (expected_segment, actual_segment))| {
+      let result = match segment_rules.iter().find(|(path, _)| path_template::segment_index(path) == Some(index)) {
+        Some((path, rules)) => match_values(path, rules, *expected_segment, *actual_segment),
+        None => expected_segment.matches_with(*actual_segment, &MatchingRule::Equality, false)
+          .map_err(|err| vec![err.to_string()])
+      };
+      result.err().unwrap_or_default().iter()
+        .map(|message| format!("Mismatch with path segment {} ('{}'): {}", index, actual_segment, message))
+        .collect_vec()
+    })
+    .collect()
+}
+
 /// Matches the actual query parameters to the expected ones.
 pub fn match_query(
   expected: Option<HashMap<String, Vec<Option<String>>>>,
@@ -1473,6 +1678,23 @@ pub(crate) async fn compare_bodies(
 ) -> BodyMatchResult {
   let mut mismatches = vec![];
 
+  // Filter expressions are only resolved against JSON bodies, so would otherwise never match
+  if !content_type.is_json() && context.matchers().has_filters() {
+    for path in context.matchers().rules.keys().filter(|path| path.has_filters()) {
+      mismatches.push(Mismatch::BodyMismatch {
+        path: path.to_string(),
+        expected: expected.body().value(),
+        actual: actual.body().value(),
+        mismatch: format!("Filter expressions in matching rule paths are only supported for JSON bodies, but '{}' was used with a body with content type '{}'",
+                          path, content_type)
+      });
+    }
+    return BodyMatchResult::BodyMismatches(group_by(mismatches, |m| match m {
+      Mismatch::BodyMismatch { path: m, ..} => m.to_string(),
+      _ => String::default()
+    }));
+  }
+
   #[cfg(feature = "plugins")]
   {
     match find_content_matcher(content_type) {
@@ -1483,7 +1705,11 @@ pub(crate) async fn compare_bodies(
             "core/content-matcher/form-urlencoded" => form_urlencoded::match_form_urlencoded(expected, actual, context),
             "core/content-matcher/json" => match_json(expected, actual, context),
             "core/content-matcher/multipart-form-data" => binary_utils::match_mime_multipart(expected, actual, context),
-            "core/content-matcher/text" => match_text(&expected.body().value(), &actual.body().value(), context),
+            "core/content-matcher/text" => if content_type.is_csv() {
+              match_csv(expected, actual, context)
+            } else {
+              match_text(&expected.body().value(), &actual.body().value(), context)
+            },
             "core/content-matcher/xml" => {
               #[cfg(feature = "xml")]
               {
@@ -1668,7 +1894,7 @@ pub async fn match_request<'a>(
   debug!("     generators: {:?}", expected.generators);
 
   #[allow(unused_mut, unused_assignments)] let mut plugin_data = hashmap!{};
-  #[cfg(feature = "plugins")]
+  #[cfg(any(feature = "plugins", feature = "protobuf"))]
   {
     plugin_data = setup_plugin_config(pact, interaction);
   };
@@ -1739,7 +1965,7 @@ pub async fn match_response<'a>(
 
   info!("comparing to expected response: {}", expected);
   #[allow(unused_mut, unused_assignments)] let mut plugin_data = hashmap!{};
-  #[cfg(feature = "plugins")]
+  #[cfg(any(feature = "plugins", feature = "protobuf"))]
   {
     plugin_data = setup_plugin_config(pact, interaction);
   };
@@ -1774,7 +2000,7 @@ pub async fn match_response<'a>(
   mismatches
 }
 
-#[cfg(feature = "plugins")]
+#[cfg(any(feature = "plugins", feature = "protobuf"))]
 fn setup_plugin_config<'a>(
   pact: &Box<dyn Pact + Send + Sync + RefUnwindSafe + 'a>,
   interaction: &Box<dyn Interaction + Send + Sync + RefUnwindSafe>
@@ -1910,7 +2136,7 @@ pub async fn match_message<'a>(
 
     let matching_rules = &expected_message.matching_rules;
     #[allow(unused_mut, unused_assignments)] let mut plugin_data = hashmap!{};
-    #[cfg(feature = "plugins")]
+    #[cfg(any(feature = "plugins", feature = "protobuf"))]
     {
       plugin_data = setup_plugin_config(pact, expected);
     };
@@ -1920,7 +2146,8 @@ pub async fn match_message<'a>(
         matchers: matching_rules.rules_for_category("content").unwrap_or_default(),
         config: DiffConfig::AllowUnexpectedKeys,
         matching_spec: PactSpecification::V4,
-        plugin_configuration: plugin_data.clone()
+        plugin_configuration: plugin_data.clone(),
+        .. CoreMatchingContext::default()
       }
     } else {
       CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys,
@@ -1969,7 +2196,7 @@ pub async fn match_sync_message_request<'a>(
 
   let matching_rules = &expected.request.matching_rules;
   #[allow(unused_mut, unused_assignments)] let mut plugin_data = hashmap!{};
-  #[cfg(feature = "plugins")]
+  #[cfg(any(feature = "plugins", feature = "protobuf"))]
   {
     plugin_data = setup_plugin_config(pact, &expected.boxed());
   };
@@ -1978,7 +2205,8 @@ pub async fn match_sync_message_request<'a>(
     matchers: matching_rules.rules_for_category("content").unwrap_or_default(),
     config: DiffConfig::AllowUnexpectedKeys,
     matching_spec: PactSpecification::V4,
-    plugin_configuration: plugin_data.clone()
+    plugin_configuration: plugin_data.clone(),
+    .. CoreMatchingContext::default()
   };
 
   let metadata_context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys,
@@ -2027,7 +2255,7 @@ pub async fn match_sync_message_response<'a>(
     }
   } else {
     #[allow(unused_mut, unused_assignments)] let mut plugin_data = hashmap!{};
-    #[cfg(feature = "plugins")]
+    #[cfg(any(feature = "plugins", feature = "protobuf"))]
     {
       plugin_data = setup_plugin_config(pact, &expected.boxed());
     };
@@ -2037,7 +2265,8 @@ pub async fn match_sync_message_response<'a>(
         matchers: matching_rules.rules_for_category("content").unwrap_or_default(),
         config: DiffConfig::AllowUnexpectedKeys,
         matching_spec: PactSpecification::V4,
-        plugin_configuration: plugin_data.clone()
+        plugin_configuration: plugin_data.clone(),
+        .. CoreMatchingContext::default()
       };
 
       let metadata_context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys,
@@ -2060,7 +2289,9 @@ pub async fn generate_request(request: &HttpRequest, mode: &GeneratorTestMode, c
   trace!(?request, ?mode, ?context, "generate_request");
   let mut request = request.clone();
 
-  let generators = request.build_generators(&GeneratorCategory::PATH);
+  let (segment_generators, generators) = request.build_generators(&GeneratorCategory::PATH)
+    .into_iter()
+    .partition::<HashMap<_, _>, _>(|(path, _)| path_template::segment_index(path).is_some());
   if !generators.is_empty() {
     debug!("Applying path generator...");
     apply_generators(mode, &generators, &mut |_, generator| {
@@ -2069,6 +2300,20 @@ pub async fn generate_request(request: &HttpRequest, mode: &GeneratorTestMode, c
       }
     });
   }
+  if !segment_generators.is_empty() {
+    debug!("Applying path segment generators...");
+    let mut segments = path_template::path_segments(&request.path).iter()
+      .map(|s| s.to_string())
+      .collect_vec();
+    apply_generators(mode, &segment_generators, &mut |key, generator| {
+      if let Some(segment) = path_template::segment_index(key).and_then(|index| segments.get_mut(index)) {
+        if let Ok(v) = generator.generate_value(segment, context, &DefaultVariantMatcher.boxed()) {
+          *segment = v;
+        }
+      }
+    });
+    request.path = path_template::join_path_segments(segments.iter());
+  }
 
   let generators = request.build_generators(&GeneratorCategory::HEADER);
   if !generators.is_empty() {
@@ -2096,7 +2341,9 @@ pub async fn generate_request(request: &HttpRequest, mode: &GeneratorTestMode, c
     });
   }
 
-  let generators = request.build_generators(&GeneratorCategory::QUERY);
+  let (nested_generators, generators) = request.build_generators(&GeneratorCategory::QUERY)
+    .into_iter()
+    .partition::<HashMap<_, _>, _>(|(key, _)| query::structured::nested_param_name(key).is_some());
   if !generators.is_empty() {
     debug!("Applying query generators...");
     apply_generators(mode, &generators, &mut |key, generator| {
@@ -2122,14 +2369,41 @@ pub async fn generate_request(request: &HttpRequest, mode: &GeneratorTestMode, c
       }
     });
   }
+  if !nested_generators.is_empty() {
+    debug!("Applying nested query generators...");
+    let names: BTreeSet<&str> = nested_generators.keys()
+      .filter_map(query::structured::nested_param_name)
+      .collect();
+    for name in names {
+      let query = request.query.clone().unwrap_or_default();
+      if let Some(value) = query_strings::decode_nested_query_param(&query, name) {
+        let mut handler = JsonHandler { value: query::structured::wrap_value(name, value) };
+        let generators = nested_generators.iter()
+          .filter(|(key, _)| query::structured::nested_param_name(key) == Some(name))
+          .map(|(key, generator)| (key.clone(), generator.clone()))
+          .collect();
+        apply_generators(mode, &generators, &mut |key, generator| {
+          handler.apply_key(key, generator, context, &DefaultVariantMatcher.boxed());
+        });
+        if let Some(value) = handler.value.get(name) {
+          request.query = Some(query::structured::replace_nested_query_param(&query, name, value));
+        }
+      }
+    }
+  }
 
   let generators = request.build_generators(&GeneratorCategory::BODY);
   if !generators.is_empty() && request.body.is_present() {
     debug!("Applying body generators...");
-    match generators_process_body(mode, &request.body, request.content_type(),
-                                  context, &generators, &DefaultVariantMatcher {}, &vec![], &hashmap!{}).await {
-      Ok(body) => request.body = body,
-      Err(err) => error!("Failed to generate the body, will use the original: {}", err)
+    if let Some(body) = text_template::generate_text_template_body(mode, &request.body, &request.matching_rules,
+      &generators, context) {
+      request.body = body;
+    } else {
+      match generators_process_body(mode, &request.body, request.content_type(),
+                                    context, &generators, &DefaultVariantMatcher {}, &vec![], &hashmap!{}).await {
+        Ok(body) => request.body = body,
+        Err(err) => error!("Failed to generate the body, will use the original: {}", err)
+      }
     }
   }
 
@@ -2179,10 +2453,15 @@ pub async fn generate_response(response: &HttpResponse, mode: &GeneratorTestMode
   let generators = response.build_generators(&GeneratorCategory::BODY);
   if !generators.is_empty() && response.body.is_present() {
     debug!("Applying body generators...");
-    match generators_process_body(mode, &response.body, response.content_type(),
-      context, &generators, &DefaultVariantMatcher{}, &vec![], &hashmap!{}).await {
-      Ok(body) => response.body = body,
-      Err(err) => error!("Failed to generate the body, will use the original: {}", err)
+    if let Some(body) = text_template::generate_text_template_body(mode, &response.body, &response.matching_rules,
+      &generators, context) {
+      response.body = body;
+    } else {
+      match generators_process_body(mode, &response.body, response.content_type(),
+        context, &generators, &DefaultVariantMatcher{}, &vec![], &hashmap!{}).await {
+        Ok(body) => response.body = body,
+        Err(err) => error!("Failed to generate the body, will use the original: {}", err)
+      }
     }
   }
   response
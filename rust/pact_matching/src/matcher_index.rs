@@ -0,0 +1,260 @@
+//! Index of the matching rules for a category. The rule paths are stored in a trie keyed by the
+//! path tokens (with separate branches for the wildcards), so the rules that apply to a path can be
+//! found by walking the path once instead of calculating the weight of every rule in the category.
+//! Rule paths with recursive descent or filter tokens can not be stored in the trie, so they are
+//! checked separately with `DocPath::path_weight`.
+
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+
+use pact_models::matchingrules::{MatchingRuleCategory, RuleList};
+use pact_models::path_exp::{DocPath, PathToken};
+
+#[derive(Debug, Clone, Default)]
+struct IndexNode {
+  /// Positions of the rule paths that end at this node
+  rules: Vec<usize>,
+  root: Option<Box<IndexNode>>,
+  fields: HashMap<String, IndexNode>,
+  indices: HashMap<usize, IndexNode>,
+  star_index: Option<Box<IndexNode>>,
+  star: Option<Box<IndexNode>>
+}
+
+impl IndexNode {
+  fn child(&mut self, token: &PathToken) -> &mut IndexNode {
+    match token {
+      PathToken::Root => self.root.get_or_insert_with(Default::default),
+      PathToken::Field(name) => self.fields.entry(name.clone()).or_default(),
+      PathToken::Index(index) => self.indices.entry(*index).or_default(),
+      PathToken::StarIndex => self.star_index.get_or_insert_with(Default::default),
+      PathToken::Star => self.star.get_or_insert_with(Default::default)
+    }
+  }
+
+  /// Nodes that the path fragment leads to, along with the weight of the matching token. This
+  /// uses the same weights as `DocPath::path_weight`.
+  fn next<'a>(&'a self, fragment: &str, weight: usize, nodes: &mut Vec<(&'a IndexNode, usize)>) {
+    if fragment == "$" {
+      if let Some(node) = &self.root {
+        nodes.push((node, weight * 2));
+      }
+    }
+    if let Some(node) = self.fields.get(fragment) {
+      nodes.push((node, weight * 2));
+    }
+    if let Ok(index) = fragment.parse::<usize>() {
+      if let Some(node) = self.indices.get(&index) {
+        nodes.push((node, weight * 2));
+      }
+      if let Some(node) = &self.star_index {
+        nodes.push((node, weight));
+      }
+    }
+    if let Some(node) = &self.star {
+      nodes.push((node, weight));
+    }
+  }
+}
+
+/// Rule path that matches a given path
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IndexMatch<'a> {
+  /// Path of the rules
+  pub path: &'a DocPath,
+  /// Rules defined for the path
+  pub rules: &'a RuleList,
+  /// Calculated weight (see `DocPath::path_weight`)
+  pub weight: usize,
+  /// Number of path tokens matched
+  pub tokens: usize
+}
+
+/// Precompiled index of the rules in a matching rule category
+#[derive(Clone, Default)]
+pub struct MatcherIndex {
+  root: IndexNode,
+  /// Positions of the rule paths that are not in the trie
+  unindexed: Vec<usize>,
+  entries: Vec<(DocPath, RuleList)>
+}
+
+impl MatcherIndex {
+  /// Builds the index for the rules in the category
+  pub fn new(category: &MatchingRuleCategory) -> Self {
+    let mut index = MatcherIndex::default();
+    for (position, (path, rules)) in category.rules.iter().enumerate() {
+      // Recursive descent and filter expressions can not be indexed
+      if path.has_recursive_descent() || path.has_filters() {
+        index.unindexed.push(position);
+      } else {
+        path.tokens().iter().fold(&mut index.root, |node, token| node.child(token)).rules.push(position);
+      }
+      index.entries.push((path.clone(), rules.clone()));
+    }
+    index
+  }
+
+  /// Number of rule paths in the index
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  /// If the index has no rules
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  /// Returns all the rule paths that match the given path (i.e. have a path weight greater than
+  /// zero), in the same order as the rules in the category the index was built from.
+  pub fn matches(&self, path: &[&str]) -> Vec<IndexMatch<'_>> {
+    let mut positions = vec![];
+    positions.extend(self.root.rules.iter().map(|position| (*position, 1, 0)));
+
+    let mut nodes = vec![(&self.root, 1)];
+    let mut next_nodes = vec![];
+    for (depth, fragment) in path.iter().enumerate() {
+      for (node, weight) in &nodes {
+        node.next(fragment, *weight, &mut next_nodes);
+      }
+      if next_nodes.is_empty() {
+        break;
+      }
+      for (node, weight) in &next_nodes {
+        positions.extend(node.rules.iter().map(|position| (*position, *weight, depth + 1)));
+      }
+      std::mem::swap(&mut nodes, &mut next_nodes);
+      next_nodes.clear();
+    }
+
+    for position in &self.unindexed {
+      let (weight, tokens) = self.entries[*position].0.path_weight(path);
+      if weight > 0 {
+        positions.push((*position, weight, tokens));
+      }
+    }
+
+    positions.sort_by_key(|(position, _, _)| *position);
+    positions.iter()
+      .map(|(position, weight, tokens)| {
+        let (path, rules) = &self.entries[*position];
+        IndexMatch { path, rules, weight: *weight, tokens: *tokens }
+      })
+      .collect()
+  }
+
+  /// If any rule path matches the given path
+  pub fn matcher_is_defined(&self, path: &[&str]) -> bool {
+    !self.matches(path).is_empty()
+  }
+
+  /// Selects the rules for the path with the highest weighting. This is equivalent to
+  /// `MatchingRuleCategory::select_best_matcher` for body and metadata categories.
+  pub fn select_best_matcher(&self, path: &[&str]) -> RuleList {
+    self.matches(path).iter()
+      .max_by_key(|m| m.weight * m.tokens)
+      .map(|m| m.rules.as_cascaded(m.tokens != path.len()))
+      .unwrap_or_default()
+  }
+
+  /// Returns the rules for the first rule path that matches the given path
+  pub fn first_matching_rules(&self, path: &[&str]) -> RuleList {
+    self.matches(path).first()
+      .map(|m| m.rules.clone())
+      .unwrap_or_default()
+  }
+
+  /// If there is a type matcher defined for any rule path that matches the given path
+  pub fn type_matcher_defined(&self, path: &[&str]) -> bool {
+    self.matches(path).iter().any(|m| m.rules.type_matcher_defined())
+  }
+
+  /// Returns the rule paths that match the given path and have the same number of tokens
+  pub fn exact_matches(&self, path: &[&str]) -> Vec<IndexMatch<'_>> {
+    self.matches(path).into_iter()
+      .filter(|m| m.tokens == path.len())
+      .collect()
+  }
+}
+
+impl Debug for MatcherIndex {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("MatcherIndex")
+      .field("rules", &self.entries.len())
+      .finish()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use maplit::hashmap;
+  use pact_models::matchingrules::{Category, MatchingRule, MatchingRuleCategory, RuleList};
+  use pact_models::matchingrules_list;
+  use pact_models::path_exp::DocPath;
+
+  use super::MatcherIndex;
+
+  fn category() -> MatchingRuleCategory {
+    matchingrules_list! {
+      "body";
+      "$" => [ MatchingRule::Type ],
+      "$.a" => [ MatchingRule::Integer ],
+      "$.a.*" => [ MatchingRule::Decimal ],
+      "$.b[*].c" => [ MatchingRule::Regex("\\d+".to_string()) ],
+      "$.b[1].c" => [ MatchingRule::Equality ],
+      "$.*.d" => [ MatchingRule::Boolean ],
+      "$..e" => [ MatchingRule::Integer ]
+    }
+  }
+
+  #[test]
+  fn matches_returns_the_same_results_as_the_path_weight() {
+    let category = category();
+    let index = MatcherIndex::new(&category);
+    expect!(index.len()).to(be_equal_to(7));
+
+    for path in [
+      vec!["$"], vec!["$", "a"], vec!["$", "a", "x"], vec!["$", "b", "0", "c"], vec!["$", "b", "1", "c"],
+      vec!["$", "b", "c"], vec!["$", "x", "d"], vec!["$", "a", "d"], vec!["x"], vec![], vec!["$", "b", "0", "e"]
+    ] {
+      let mut expected = category.rules.keys()
+        .map(|p| (p.to_string(), p.path_weight(&path)))
+        .filter(|(_, (w, _))| *w > 0)
+        .collect::<Vec<_>>();
+      expected.sort();
+      let mut actual = index.matches(&path).iter()
+        .map(|m| (m.path.to_string(), (m.weight, m.tokens)))
+        .collect::<Vec<_>>();
+      actual.sort();
+      expect!(actual).to(be_equal_to(expected));
+    }
+  }
+
+  #[test]
+  fn select_best_matcher_is_equivalent_to_the_category() {
+    let category = category();
+    let index = MatcherIndex::new(&category);
+    for path in [
+      vec!["$"], vec!["$", "a"], vec!["$", "a", "x"], vec!["$", "b", "0", "c"], vec!["$", "b", "1", "c"],
+      vec!["$", "x", "d", "e"], vec!["x"]
+    ] {
+      expect!(index.select_best_matcher(&path)).to(be_equal_to(category.select_best_matcher(&path)));
+      expect!(index.matcher_is_defined(&path)).to(be_equal_to(category.matcher_is_defined(&path)));
+      expect!(index.type_matcher_defined(&path))
+        .to(be_equal_to(category.resolve_matchers_for_path(&path).type_matcher_defined()));
+    }
+  }
+
+  #[test]
+  fn empty_paths_match_everything() {
+    let category = MatchingRuleCategory {
+      name: Category::BODY,
+      rules: hashmap! { DocPath::empty() => RuleList::new(MatchingRule::Type) }
+    };
+    let index = MatcherIndex::new(&category);
+    expect!(index.matcher_is_defined(&["$", "a"])).to(be_true());
+    expect!(index.select_best_matcher(&["$", "a"])).to(be_equal_to(category.select_best_matcher(&["$", "a"])));
+    expect!(index.exact_matches(&["$", "a"]).is_empty()).to(be_true());
+  }
+}
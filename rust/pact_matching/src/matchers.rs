@@ -6,7 +6,6 @@ use anyhow::anyhow;
 use bytes::Bytes;
 #[cfg(feature = "plugins")] use lazy_static::lazy_static;
 #[cfg(feature = "plugins")] use maplit::hashmap;
-use onig::Regex;
 use pact_models::HttpStatus;
 use pact_models::matchingrules::{MatchingRule, RuleList, RuleLogic};
 use pact_models::path_exp::DocPath;
@@ -21,6 +20,7 @@ use semver::Version;
 use tracing::{debug, instrument, trace};
 
 use crate::binary_utils::match_content_type;
+use crate::regex_cache::compile_regex;
 use crate::{MatchingContext, CommonMismatch};
 
 #[cfg(feature = "plugins")]
@@ -165,7 +165,7 @@ impl Matches<&str> for &str {
   fn matches_with(&self, actual: &str, matcher: &MatchingRule, cascaded: bool) -> anyhow::Result<()> {
     let result = match matcher {
       MatchingRule::Regex(regex) => {
-        match Regex::new(regex) {
+        match compile_regex(regex) {
           Ok(re) => {
             if re.is_match(actual) {
               Ok(())
@@ -314,7 +314,7 @@ impl Matches<u64> for &str {
     debug!("String -> u64: comparing '{}' to {} using {:?}", self, actual, matcher);
     match matcher {
       MatchingRule::Regex(regex) => {
-        match Regex::new(regex) {
+        match compile_regex(regex) {
           Ok(re) => {
             if re.is_match(&actual.to_string()) {
               Ok(())
@@ -356,7 +356,7 @@ impl Matches<u64> for u64 {
     debug!("u64 -> u64: comparing {} to {} using {:?}", self, actual, matcher);
     match matcher {
       MatchingRule::Regex(regex) => {
-        match Regex::new(regex) {
+        match compile_regex(regex) {
           Ok(re) => {
             if re.is_match(&actual.to_string()) {
               Ok(())
@@ -403,7 +403,7 @@ impl Matches<f64> for u64 {
     debug!("u64 -> f64: comparing {} to {} using {:?}", self, actual, matcher);
     match matcher {
       MatchingRule::Regex(regex) => {
-        match Regex::new(regex) {
+        match compile_regex(regex) {
           Ok(re) => {
             if re.is_match(&actual.to_string()) {
               Ok(())
@@ -444,7 +444,7 @@ impl Matches<f64> for f64 {
   fn matches_with(&self, actual: f64, matcher: &MatchingRule, cascaded: bool) -> anyhow::Result<()> {
     let result = match matcher {
       MatchingRule::Regex(regex) => {
-        match Regex::new(regex) {
+        match compile_regex(regex) {
           Ok(re) => {
             if re.is_match(&actual.to_string()) {
               Ok(())
@@ -492,7 +492,7 @@ impl Matches<u64> for f64 {
     debug!("f64 -> u64: comparing {} to {} using {:?}", self, actual, matcher);
     match matcher {
       MatchingRule::Regex(ref regex) => {
-        match Regex::new(regex) {
+        match compile_regex(regex) {
           Ok(re) => {
             if re.is_match(&actual.to_string()) {
               Ok(())
@@ -565,7 +565,7 @@ impl Matches<i64> for &str {
     debug!("String -> i64: comparing '{}' to {} using {:?}", self, actual, matcher);
     match matcher {
       MatchingRule::Regex(regex) => {
-        match Regex::new(regex) {
+        match compile_regex(regex) {
           Ok(re) => {
             if re.is_match(&actual.to_string()) {
               Ok(())
@@ -606,7 +606,7 @@ impl Matches<i64> for i64 {
     debug!("i64 -> i64: comparing {} to {} using {:?}", self, actual, matcher);
     match matcher {
       MatchingRule::Regex(regex) => {
-        match Regex::new(regex) {
+        match compile_regex(regex) {
           Ok(re) => {
             if re.is_match(&actual.to_string()) {
               Ok(())
@@ -673,7 +673,7 @@ impl Matches<bool> for bool {
     debug!("bool -> bool: comparing '{}' to {} using {:?}", self, actual, matcher);
     match matcher {
       MatchingRule::Regex(regex) => {
-        match Regex::new(regex) {
+        match compile_regex(regex) {
           Ok(re) => {
             if re.is_match(&actual.to_string()) {
               Ok(())
@@ -716,7 +716,7 @@ impl Matches<&Bytes> for Bytes {
     debug!("Bytes -> Bytes: comparing {} bytes to {} bytes using {:?}", self.len(), actual.len(), matcher);
     match matcher {
       MatchingRule::Regex(regex) => {
-        match Regex::new(regex) {
+        match compile_regex(regex) {
           Ok(re) => {
             match from_utf8(actual) {
               Ok(s) => if re.is_match(s) {
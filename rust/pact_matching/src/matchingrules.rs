@@ -7,7 +7,6 @@ use std::str::from_utf8;
 use anyhow::anyhow;
 use itertools::Itertools;
 use maplit::hashmap;
-use onig::Regex;
 use pact_models::matchingrules::{Category, MatchingRule, MatchingRuleCategory, RuleList, RuleLogic};
 use pact_models::path_exp::DocPath;
 use serde_json::{self, json, Value};
@@ -16,6 +15,7 @@ use tracing::{debug, trace};
 use crate::{Either, MatchingContext, merge_result, CommonMismatch};
 use crate::binary_utils::match_content_type;
 use crate::matchers::Matches;
+use crate::regex_cache::compile_regex;
 
 impl <T: Debug + Display + PartialEq + Clone> Matches<&Vec<T>> for &Vec<T> {
   fn matches_with(&self, actual: &Vec<T>, matcher: &MatchingRule, cascaded: bool) -> anyhow::Result<()> {
@@ -103,7 +103,7 @@ impl Matches<&[u8]> for Vec<u8> {
     debug!("byte slice -> byte slice: comparing {:?} to {:?} using {:?}", self, actual, matcher);
     let result = match matcher {
       MatchingRule::Regex(regex) => {
-        match Regex::new(regex) {
+        match compile_regex(regex) {
           Ok(re) => {
             let text = from_utf8(actual).unwrap_or_default();
             if re.is_match(text) {
@@ -0,0 +1,773 @@
+//! Native matching and generation of protobuf message bodies. The message descriptors are loaded
+//! from the `FileDescriptorSet` stored in the protobuf plugin configuration of the Pact (the
+//! `descriptors` entry, keyed by the `descriptorKey` in the interaction configuration), so pacts
+//! written with the gRPC/protobuf plugin can be verified without running the plugin.
+//!
+//! Message bodies are decoded into a JSON tree keyed by the field names, so matching rules can be
+//! addressed with paths like `$.location.latitude` or `$.features[*].name`, and the tree is then
+//! matched with the JSON matching rules. The message type is taken from the `message` parameter of
+//! the content type (i.e. `application/protobuf;message=.routeguide.Feature`), or the `message`
+//! entry of the interaction configuration.
+
+use std::collections::HashMap;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use anyhow::{anyhow, Context};
+use bytes::Bytes;
+use pact_models::bodies::OptionalBody;
+use pact_models::content_types::ContentType;
+use pact_models::generators::{ContentTypeHandler, Generator, GeneratorTestMode, JsonHandler, VariantMatcher};
+use pact_models::http_parts::HttpPart;
+use pact_models::json_utils::json_to_string;
+use pact_models::path_exp::DocPath;
+use pact_models::plugins::PluginData;
+use prost::Message;
+use prost_types::{DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorSet};
+use prost_types::field_descriptor_proto::{Label, Type};
+use serde_json::{Map, Value};
+use tracing::{debug, warn};
+
+use crate::{MatchingContext, Mismatch};
+use crate::binary_utils::match_octet_stream;
+use crate::json::compare_json;
+
+/// Name of the plugin whose configuration holds the protobuf descriptors
+pub const PROTOBUF_PLUGIN: &str = "protobuf";
+
+const WIRE_VARINT: u8 = 0;
+const WIRE_FIXED64: u8 = 1;
+const WIRE_LENGTH_DELIMITED: u8 = 2;
+const WIRE_START_GROUP: u8 = 3;
+const WIRE_END_GROUP: u8 = 4;
+const WIRE_FIXED32: u8 = 5;
+
+/// Message and enum descriptors loaded from a `FileDescriptorSet`, keyed by their fully
+/// qualified names (i.e. `.routeguide.Feature`)
+#[derive(Debug, Clone, Default)]
+pub struct ProtobufDescriptors {
+  messages: HashMap<String, (DescriptorProto, bool)>,
+  enums: HashMap<String, EnumDescriptorProto>
+}
+
+impl ProtobufDescriptors {
+  /// Loads the descriptors from the bytes of an encoded `FileDescriptorSet`
+  pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<ProtobufDescriptors> {
+    let descriptor_set = FileDescriptorSet::decode(bytes)
+      .context("Failed to decode the file descriptor set")?;
+    let mut descriptors = ProtobufDescriptors::default();
+    for file in &descriptor_set.file {
+      let prefix = match file.package.as_ref() {
+        Some(package) if !package.is_empty() => format!(".{}", package),
+        _ => String::default()
+      };
+      let proto3 = file.syntax() == "proto3";
+      for message in &file.message_type {
+        descriptors.add_message(&prefix, message, proto3);
+      }
+      for enum_type in &file.enum_type {
+        descriptors.enums.insert(format!("{}.{}", prefix, enum_type.name()), enum_type.clone());
+      }
+    }
+    Ok(descriptors)
+  }
+
+  /// Loads the descriptors from the protobuf plugin configuration. The Pact level configuration
+  /// has a `descriptors` map of base64 encoded `FileDescriptorSet`s (the `protoDescriptors`
+  /// entries), and the interaction configuration selects one with `descriptorKey`.
+  pub fn from_config(
+    pact_config: &HashMap<String, Value>,
+    interaction_config: &HashMap<String, Value>
+  ) -> anyhow::Result<ProtobufDescriptors> {
+    let descriptors = pact_config.get("descriptors")
+      .and_then(|descriptors| descriptors.as_object())
+      .ok_or_else(|| anyhow!("The Pact does not have any protobuf descriptors in the plugin configuration"))?;
+    let entry = match interaction_config.get("descriptorKey").map(json_to_string) {
+      Some(key) => descriptors.get(&key)
+        .ok_or_else(|| anyhow!("The Pact does not have protobuf descriptors with key '{}'", key))?,
+      None if descriptors.len() == 1 => descriptors.values().next().unwrap(),
+      None => return Err(anyhow!("The interaction does not have a descriptor key, and the Pact has more than one set of protobuf descriptors"))
+    };
+    let encoded = entry.get("protoDescriptors").and_then(|value| value.as_str())
+      .ok_or_else(|| anyhow!("The protobuf descriptors entry does not have any 'protoDescriptors'"))?;
+    let bytes = BASE64.decode(encoded).context("Failed to decode the base64 encoded protobuf descriptors")?;
+    ProtobufDescriptors::from_bytes(&bytes)
+  }
+
+  fn add_message(&mut self, prefix: &str, message: &DescriptorProto, proto3: bool) {
+    let name = format!("{}.{}", prefix, message.name());
+    for nested in &message.nested_type {
+      self.add_message(&name, nested, proto3);
+    }
+    for enum_type in &message.enum_type {
+      self.enums.insert(format!("{}.{}", name, enum_type.name()), enum_type.clone());
+    }
+    self.messages.insert(name, (message.clone(), proto3));
+  }
+
+  fn message(&self, name: &str) -> anyhow::Result<&(DescriptorProto, bool)> {
+    let name = if name.starts_with('.') { name.to_string() } else { format!(".{}", name) };
+    self.messages.get(&name).ok_or_else(|| anyhow!("There is no descriptor for message type '{}'", name))
+  }
+
+  fn is_map_field(&self, field: &FieldDescriptorProto) -> bool {
+    field.label() == Label::Repeated && field.r#type() == Type::Message &&
+      self.message(field.type_name()).ok()
+        .and_then(|(message, _)| message.options.as_ref())
+        .map(|options| options.map_entry())
+        .unwrap_or(false)
+  }
+
+  /// Decodes the protobuf encoded message into a JSON tree keyed by the field names. Default
+  /// values are added for any proto3 scalar fields that are not present, and empty values for
+  /// repeated and map fields.
+  pub fn decode_message(&self, message_type: &str, bytes: &[u8]) -> anyhow::Result<Value> {
+    let (descriptor, proto3) = self.message(message_type)?;
+    let mut reader = WireReader { buf: bytes, pos: 0 };
+    let mut map = Map::new();
+
+    while !reader.is_empty() {
+      let key = reader.varint()?;
+      let number = (key >> 3) as i32;
+      let wire_type = (key & 0x7) as u8;
+      match descriptor.field.iter().find(|field| field.number() == number) {
+        Some(field) => {
+          let values = self.decode_field(field, wire_type, &mut reader)?;
+          if self.is_map_field(field) {
+            let entries = map.entry(field.name()).or_insert_with(|| Value::Object(Map::new()));
+            for entry in values {
+              let key = entry.get("key").map(json_to_string).unwrap_or_default();
+              let value = entry.get("value").cloned().unwrap_or(Value::Null);
+              if let Some(entries) = entries.as_object_mut() {
+                entries.insert(key, value);
+              }
+            }
+          } else if field.label() == Label::Repeated {
+            let items = map.entry(field.name()).or_insert_with(|| Value::Array(vec![]));
+            if let Some(items) = items.as_array_mut() {
+              items.extend(values);
+            }
+          } else if let Some(value) = values.into_iter().last() {
+            map.insert(field.name().to_string(), value);
+          }
+        }
+        None => {
+          debug!("Ignoring unknown field {} of message '{}'", number, message_type);
+          reader.skip(wire_type)?;
+        }
+      }
+    }
+
+    for field in &descriptor.field {
+      if !map.contains_key(field.name()) {
+        if self.is_map_field(field) {
+          map.insert(field.name().to_string(), Value::Object(Map::new()));
+        } else if field.label() == Label::Repeated {
+          map.insert(field.name().to_string(), Value::Array(vec![]));
+        } else if *proto3 && field.oneof_index.is_none() && !field.proto3_optional() {
+          if let Some(value) = self.default_value(field) {
+            map.insert(field.name().to_string(), value);
+          }
+        }
+      }
+    }
+
+    Ok(Value::Object(map))
+  }
+
+  fn decode_field(&self, field: &FieldDescriptorProto, wire_type: u8, reader: &mut WireReader) -> anyhow::Result<Vec<Value>> {
+    let expected_wire_type = wire_type_for(field.r#type());
+    if wire_type == WIRE_LENGTH_DELIMITED && expected_wire_type != WIRE_LENGTH_DELIMITED {
+      // Packed repeated scalar values
+      let bytes = reader.length_delimited()?;
+      let mut packed = WireReader { buf: bytes, pos: 0 };
+      let mut values = vec![];
+      while !packed.is_empty() {
+        values.push(self.decode_value(field, &mut packed)?);
+      }
+      Ok(values)
+    } else if wire_type == expected_wire_type {
+      Ok(vec![self.decode_value(field, reader)?])
+    } else {
+      Err(anyhow!("Field '{}' has wire type {}, but expected {}", field.name(), wire_type, expected_wire_type))
+    }
+  }
+
+  fn decode_value(&self, field: &FieldDescriptorProto, reader: &mut WireReader) -> anyhow::Result<Value> {
+    Ok(match field.r#type() {
+      Type::Int32 => Value::from(reader.varint()? as i32),
+      Type::Int64 => Value::from(reader.varint()? as i64),
+      Type::Uint32 => Value::from(reader.varint()? as u32),
+      Type::Uint64 => Value::from(reader.varint()?),
+      Type::Sint32 => Value::from(zigzag_decode(reader.varint()?) as i32),
+      Type::Sint64 => Value::from(zigzag_decode(reader.varint()?)),
+      Type::Bool => Value::Bool(reader.varint()? != 0),
+      Type::Enum => {
+        let number = reader.varint()? as i32;
+        self.enums.get(field.type_name())
+          .and_then(|enum_type| enum_type.value.iter().find(|value| value.number() == number))
+          .map(|value| Value::String(value.name().to_string()))
+          .unwrap_or_else(|| Value::from(number))
+      }
+      Type::Fixed32 => Value::from(u32::from_le_bytes(reader.fixed::<4>()?)),
+      Type::Sfixed32 => Value::from(i32::from_le_bytes(reader.fixed::<4>()?)),
+      Type::Float => Value::from(f32::from_le_bytes(reader.fixed::<4>()?) as f64),
+      Type::Fixed64 => Value::from(u64::from_le_bytes(reader.fixed::<8>()?)),
+      Type::Sfixed64 => Value::from(i64::from_le_bytes(reader.fixed::<8>()?)),
+      Type::Double => Value::from(f64::from_le_bytes(reader.fixed::<8>()?)),
+      Type::String => Value::String(String::from_utf8(reader.length_delimited()?.to_vec())
+        .with_context(|| format!("Field '{}' is not a valid UTF-8 string", field.name()))?),
+      Type::Bytes => Value::String(BASE64.encode(reader.length_delimited()?)),
+      Type::Message => self.decode_message(field.type_name(), reader.length_delimited()?)?,
+      Type::Group => return Err(anyhow!("Field '{}' is a group, which is not supported", field.name()))
+    })
+  }
+
+  fn default_value(&self, field: &FieldDescriptorProto) -> Option<Value> {
+    match field.r#type() {
+      Type::Message | Type::Group => None,
+      Type::Bool => Some(Value::Bool(false)),
+      Type::String | Type::Bytes => Some(Value::String(String::default())),
+      Type::Float | Type::Double => Some(Value::from(0.0)),
+      Type::Enum => Some(self.enums.get(field.type_name())
+        .and_then(|enum_type| enum_type.value.iter().find(|value| value.number() == 0))
+        .map(|value| Value::String(value.name().to_string()))
+        .unwrap_or_else(|| Value::from(0))),
+      _ => Some(Value::from(0))
+    }
+  }
+
+  /// Encodes the JSON tree (in the form returned by `decode_message`) as a protobuf message
+  pub fn encode_message(&self, message_type: &str, value: &Value) -> anyhow::Result<Vec<u8>> {
+    let (descriptor, proto3) = self.message(message_type)?;
+    let map = value.as_object()
+      .ok_or_else(|| anyhow!("Expected a JSON object for message '{}', got {}", message_type, value))?;
+    let mut buf = vec![];
+
+    for field in &descriptor.field {
+      let value = match map.get(field.name()) {
+        Some(Value::Null) | None => continue,
+        Some(value) => value
+      };
+      if *proto3 && field.label() != Label::Repeated && field.oneof_index.is_none() && !field.proto3_optional() &&
+        self.default_value(field).as_ref() == Some(value) {
+        // proto3 does not write fields with default values
+        continue;
+      }
+      if self.is_map_field(field) {
+        let (entry_descriptor, _) = self.message(field.type_name())?;
+        let key_field = entry_descriptor.field.iter().find(|field| field.number() == 1);
+        for (key, value) in value.as_object().into_iter().flatten() {
+          let key = match key_field.map(|field| field.r#type()) {
+            Some(Type::String) | None => Value::String(key.clone()),
+            _ => serde_json::from_str(key).unwrap_or_else(|_| Value::String(key.clone()))
+          };
+          let mut entry = Map::new();
+          entry.insert("key".to_string(), key);
+          entry.insert("value".to_string(), value.clone());
+          let bytes = self.encode_message(field.type_name(), &Value::Object(entry))?;
+          write_key(&mut buf, field.number(), WIRE_LENGTH_DELIMITED);
+          write_length_delimited(&mut buf, &bytes);
+        }
+      } else if field.label() == Label::Repeated {
+        let items = value.as_array()
+          .ok_or_else(|| anyhow!("Expected a JSON array for repeated field '{}', got {}", field.name(), value))?;
+        let packed = field.options.as_ref().and_then(|options| options.packed).unwrap_or(*proto3);
+        if packed && wire_type_for(field.r#type()) != WIRE_LENGTH_DELIMITED {
+          let mut packed_buf = vec![];
+          for item in items {
+            self.encode_value(field, item, &mut packed_buf)?;
+          }
+          write_key(&mut buf, field.number(), WIRE_LENGTH_DELIMITED);
+          write_length_delimited(&mut buf, &packed_buf);
+        } else {
+          for item in items {
+            write_key(&mut buf, field.number(), wire_type_for(field.r#type()));
+            self.encode_value(field, item, &mut buf)?;
+          }
+        }
+      } else {
+        write_key(&mut buf, field.number(), wire_type_for(field.r#type()));
+        self.encode_value(field, value, &mut buf)?;
+      }
+    }
+
+    Ok(buf)
+  }
+
+  fn encode_value(&self, field: &FieldDescriptorProto, value: &Value, buf: &mut Vec<u8>) -> anyhow::Result<()> {
+    let name = field.name();
+    match field.r#type() {
+      Type::Int32 | Type::Int64 => write_varint(buf, as_i64(name, value)? as u64),
+      Type::Uint32 | Type::Uint64 => write_varint(buf, as_u64(name, value)?),
+      Type::Sint32 | Type::Sint64 => write_varint(buf, zigzag_encode(as_i64(name, value)?)),
+      Type::Bool => write_varint(buf, match value {
+        Value::Bool(b) => *b as u64,
+        _ => (json_to_string(value) == "true") as u64
+      }),
+      Type::Enum => {
+        let number = match value {
+          Value::String(s) => self.enums.get(field.type_name())
+            .and_then(|enum_type| enum_type.value.iter().find(|value| value.name() == s))
+            .map(|value| value.number())
+            .ok_or_else(|| anyhow!("'{}' is not a valid value for enum field '{}'", s, name))?,
+          _ => as_i64(name, value)? as i32
+        };
+        write_varint(buf, number as i64 as u64);
+      }
+      Type::Fixed32 => buf.extend_from_slice(&(as_u64(name, value)? as u32).to_le_bytes()),
+      Type::Sfixed32 => buf.extend_from_slice(&(as_i64(name, value)? as i32).to_le_bytes()),
+      Type::Float => buf.extend_from_slice(&(as_f64(name, value)? as f32).to_le_bytes()),
+      Type::Fixed64 => buf.extend_from_slice(&as_u64(name, value)?.to_le_bytes()),
+      Type::Sfixed64 => buf.extend_from_slice(&as_i64(name, value)?.to_le_bytes()),
+      Type::Double => buf.extend_from_slice(&as_f64(name, value)?.to_le_bytes()),
+      Type::String => write_length_delimited(buf, json_to_string(value).as_bytes()),
+      Type::Bytes => {
+        let bytes = BASE64.decode(json_to_string(value))
+          .with_context(|| format!("Field '{}' is not valid base64 encoded bytes", name))?;
+        write_length_delimited(buf, &bytes);
+      }
+      Type::Message => {
+        let bytes = self.encode_message(field.type_name(), value)?;
+        write_length_delimited(buf, &bytes);
+      }
+      Type::Group => return Err(anyhow!("Field '{}' is a group, which is not supported", name))
+    }
+    Ok(())
+  }
+}
+
+struct WireReader<'a> {
+  buf: &'a [u8],
+  pos: usize
+}
+
+impl <'a> WireReader<'a> {
+  fn is_empty(&self) -> bool {
+    self.pos >= self.buf.len()
+  }
+
+  fn varint(&mut self) -> anyhow::Result<u64> {
+    let mut result = 0_u64;
+    for shift in (0..64).step_by(7) {
+      let byte = *self.buf.get(self.pos).ok_or_else(|| anyhow!("Unexpected end of message reading a varint"))?;
+      self.pos += 1;
+      result |= ((byte & 0x7F) as u64) << shift;
+      if byte & 0x80 == 0 {
+        return Ok(result);
+      }
+    }
+    Err(anyhow!("Varint is too long"))
+  }
+
+  fn bytes(&mut self, len: usize) -> anyhow::Result<&'a [u8]> {
+    if len > self.buf.len() - self.pos {
+      return Err(anyhow!("Unexpected end of message, expected {} more bytes", len));
+    }
+    let bytes = &self.buf[self.pos..self.pos + len];
+    self.pos += len;
+    Ok(bytes)
+  }
+
+  fn fixed<const N: usize>(&mut self) -> anyhow::Result<[u8; N]> {
+    let mut result = [0_u8; N];
+    result.copy_from_slice(self.bytes(N)?);
+    Ok(result)
+  }
+
+  fn length_delimited(&mut self) -> anyhow::Result<&'a [u8]> {
+    let len = self.varint()? as usize;
+    self.bytes(len)
+  }
+
+  fn skip(&mut self, wire_type: u8) -> anyhow::Result<()> {
+    match wire_type {
+      WIRE_VARINT => self.varint().map(|_| ()),
+      WIRE_FIXED64 => self.bytes(8).map(|_| ()),
+      WIRE_LENGTH_DELIMITED => self.length_delimited().map(|_| ()),
+      WIRE_FIXED32 => self.bytes(4).map(|_| ()),
+      WIRE_START_GROUP | WIRE_END_GROUP => Err(anyhow!("Groups are not supported")),
+      _ => Err(anyhow!("Invalid wire type {}", wire_type))
+    }
+  }
+}
+
+fn wire_type_for(field_type: Type) -> u8 {
+  match field_type {
+    Type::Fixed64 | Type::Sfixed64 | Type::Double => WIRE_FIXED64,
+    Type::Fixed32 | Type::Sfixed32 | Type::Float => WIRE_FIXED32,
+    Type::String | Type::Bytes | Type::Message => WIRE_LENGTH_DELIMITED,
+    Type::Group => WIRE_START_GROUP,
+    _ => WIRE_VARINT
+  }
+}
+
+fn zigzag_decode(value: u64) -> i64 {
+  ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+fn zigzag_encode(value: i64) -> u64 {
+  ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn write_varint(buf: &mut Vec<u8>, mut value: u64) {
+  while value >= 0x80 {
+    buf.push((value as u8) | 0x80);
+    value >>= 7;
+  }
+  buf.push(value as u8);
+}
+
+fn write_key(buf: &mut Vec<u8>, number: i32, wire_type: u8) {
+  write_varint(buf, ((number as u64) << 3) | wire_type as u64);
+}
+
+fn write_length_delimited(buf: &mut Vec<u8>, bytes: &[u8]) {
+  write_varint(buf, bytes.len() as u64);
+  buf.extend_from_slice(bytes);
+}
+
+fn as_i64(name: &str, value: &Value) -> anyhow::Result<i64> {
+  value.as_i64()
+    .or_else(|| value.as_f64().map(|f| f as i64))
+    .or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+    .ok_or_else(|| anyhow!("Expected an integer value for field '{}', got {}", name, value))
+}
+
+fn as_u64(name: &str, value: &Value) -> anyhow::Result<u64> {
+  value.as_u64()
+    .or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+    .ok_or_else(|| anyhow!("Expected an unsigned integer value for field '{}', got {}", name, value))
+}
+
+fn as_f64(name: &str, value: &Value) -> anyhow::Result<f64> {
+  value.as_f64()
+    .or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+    .ok_or_else(|| anyhow!("Expected a number value for field '{}', got {}", name, value))
+}
+
+/// Returns the message type for the body, from the `message` parameter of the content type or the
+/// `message` entry of the interaction configuration
+pub fn message_type(content_type: &ContentType, interaction_config: &HashMap<String, Value>) -> Option<String> {
+  content_type.attributes.get("message").cloned()
+    .or_else(|| interaction_config.get("message").map(json_to_string))
+}
+
+/// If the message bytes start with a gRPC length prefix (an uncompressed flag and the length of
+/// the message)
+fn has_grpc_frame(content_type: &ContentType, bytes: &[u8]) -> bool {
+  content_type.sub_type == "grpc" && bytes.len() >= 5 && bytes[0] == 0 &&
+    u32::from_be_bytes([bytes[1], bytes[2], bytes[3], bytes[4]]) as usize == bytes.len() - 5
+}
+
+/// Removes the gRPC length prefix from the message bytes, if there is one
+fn strip_grpc_frame(content_type: &ContentType, bytes: &[u8]) -> Vec<u8> {
+  if has_grpc_frame(content_type, bytes) {
+    bytes[5..].to_vec()
+  } else {
+    bytes.to_vec()
+  }
+}
+
+/// Adds a gRPC length prefix to the message bytes
+fn add_grpc_frame(bytes: &[u8]) -> Vec<u8> {
+  let mut framed = Vec::with_capacity(bytes.len() + 5);
+  framed.push(0);
+  framed.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+  framed.extend_from_slice(bytes);
+  framed
+}
+
+fn plugin_config(context: &(dyn MatchingContext + Send + Sync)) -> (HashMap<String, Value>, HashMap<String, Value>) {
+  context.plugin_configuration().get(PROTOBUF_PLUGIN)
+    .map(|config| (config.pact_configuration.clone(), config.interaction_configuration.clone()))
+    .unwrap_or_default()
+}
+
+/// Matches the bodies as protobuf messages. If the Pact does not have any protobuf descriptors, the
+/// bodies are matched as binary.
+pub(crate) fn match_protobuf(
+  expected: &(dyn HttpPart + Send + Sync),
+  actual: &(dyn HttpPart + Send + Sync),
+  context: &(dyn MatchingContext + Send + Sync)
+) -> Result<(), Vec<Mismatch>> {
+  let expected_body = expected.body().value();
+  let actual_body = actual.body().value();
+  let mismatch = |message: String| vec![Mismatch::BodyMismatch {
+    path: "$".to_string(),
+    expected: expected_body.clone(),
+    actual: actual_body.clone(),
+    mismatch: message
+  }];
+
+  let content_type = expected.content_type().unwrap_or_default();
+  let (pact_config, interaction_config) = plugin_config(context);
+  if !pact_config.contains_key("descriptors") {
+    debug!("The Pact does not have any protobuf descriptors, matching the bodies as binary");
+    return match_octet_stream(expected, actual, context);
+  }
+  let descriptors = ProtobufDescriptors::from_config(&pact_config, &interaction_config)
+    .map_err(|err| mismatch(format!("Could not load the protobuf descriptors: {}", err)))?;
+  let message = message_type(&content_type, &interaction_config)
+    .ok_or_else(|| mismatch(format!("Could not determine the protobuf message type for content type '{}'", content_type)))?;
+  debug!(%message, "Matching bodies as protobuf messages");
+
+  let expected_bytes = strip_grpc_frame(&content_type, &expected_body.clone().unwrap_or_default());
+  let actual_content_type = actual.content_type().unwrap_or_else(|| content_type.clone());
+  let actual_bytes = strip_grpc_frame(&actual_content_type, &actual_body.clone().unwrap_or_default());
+  let expected_json = descriptors.decode_message(&message, &expected_bytes)
+    .map_err(|err| mismatch(format!("Failed to decode the expected body as a '{}' message: {}", message, err)))?;
+  let actual_json = descriptors.decode_message(&message, &actual_bytes)
+    .map_err(|err| mismatch(format!("Failed to decode the actual body as a '{}' message: {}", message, err)))?;
+
+  compare_json(&DocPath::root(), &expected_json, &actual_json, context)
+    .map_err(|mismatches| mismatches.iter().map(|mismatch| mismatch.to_body_mismatch()).collect())
+}
+
+/// Applies the generators to the fields of the protobuf message body
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn generate_protobuf_body(
+  mode: &GeneratorTestMode,
+  body: &OptionalBody,
+  content_type: &ContentType,
+  context: &HashMap<&str, Value>,
+  generators: &HashMap<DocPath, Generator>,
+  matcher: &(dyn VariantMatcher + Send + Sync),
+  plugin_data: &[PluginData],
+  interaction_data: &HashMap<String, HashMap<String, Value>>
+) -> anyhow::Result<OptionalBody> {
+  let pact_config = plugin_data.iter()
+    .find(|data| data.name == PROTOBUF_PLUGIN)
+    .map(|data| data.configuration.clone())
+    .unwrap_or_default();
+  let interaction_config = interaction_data.get(PROTOBUF_PLUGIN).cloned().unwrap_or_default();
+  let descriptors = ProtobufDescriptors::from_config(&pact_config, &interaction_config)?;
+  let message = message_type(content_type, &interaction_config)
+    .ok_or_else(|| anyhow!("Could not determine the protobuf message type for content type '{}'", content_type))?;
+
+  let body_bytes = body.value().unwrap_or_default();
+  let framed = has_grpc_frame(content_type, &body_bytes);
+  let bytes = strip_grpc_frame(content_type, &body_bytes);
+  let mut handler = JsonHandler { value: descriptors.decode_message(&message, &bytes)? };
+  if let Err(err) = handler.process_body(generators, mode, context, &matcher.boxed()) {
+    warn!("Failed to apply the generators to the protobuf message: {}", err);
+    return Ok(body.clone());
+  }
+  let bytes = descriptors.encode_message(&message, &handler.value)?;
+  let bytes = if framed { add_grpc_frame(&bytes) } else { bytes };
+  Ok(OptionalBody::Present(Bytes::from(bytes), Some(content_type.clone()), None))
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use maplit::hashmap;
+  use pact_models::matchingrules::{MatchingRule, MatchingRuleCategory};
+  use pact_models::matchingrules_list;
+  use pact_models::request::Request;
+  use prost_types::{EnumValueDescriptorProto, FileDescriptorProto, MessageOptions};
+  use serde_json::json;
+
+  use crate::{CoreMatchingContext, DiffConfig, PluginInteractionConfig};
+  use crate::generators::DefaultVariantMatcher;
+
+  use super::*;
+
+  fn field(name: &str, number: i32, field_type: Type, type_name: Option<&str>, repeated: bool) -> FieldDescriptorProto {
+    FieldDescriptorProto {
+      name: Some(name.to_string()),
+      number: Some(number),
+      label: Some((if repeated { Label::Repeated } else { Label::Optional }) as i32),
+      r#type: Some(field_type as i32),
+      type_name: type_name.map(|name| name.to_string()),
+      .. FieldDescriptorProto::default()
+    }
+  }
+
+  fn descriptor_set() -> Vec<u8> {
+    FileDescriptorSet {
+      file: vec![FileDescriptorProto {
+        name: Some("test.proto".to_string()),
+        package: Some("test".to_string()),
+        syntax: Some("proto3".to_string()),
+        message_type: vec![
+          DescriptorProto {
+            name: Some("Point".to_string()),
+            field: vec![
+              field("latitude", 1, Type::Int32, None, false),
+              field("longitude", 2, Type::Sint64, None, false)
+            ],
+            .. DescriptorProto::default()
+          },
+          DescriptorProto {
+            name: Some("Feature".to_string()),
+            field: vec![
+              field("name", 1, Type::String, None, false),
+              field("location", 2, Type::Message, Some(".test.Point"), false),
+              field("tags", 3, Type::Int32, None, true),
+              field("kind", 4, Type::Enum, Some(".test.Kind"), false),
+              field("attributes", 5, Type::Message, Some(".test.Feature.AttributesEntry"), true),
+              field("score", 6, Type::Double, None, false)
+            ],
+            nested_type: vec![DescriptorProto {
+              name: Some("AttributesEntry".to_string()),
+              field: vec![
+                field("key", 1, Type::String, None, false),
+                field("value", 2, Type::String, None, false)
+              ],
+              options: Some(MessageOptions { map_entry: Some(true), .. MessageOptions::default() }),
+              .. DescriptorProto::default()
+            }],
+            .. DescriptorProto::default()
+          }
+        ],
+        enum_type: vec![EnumDescriptorProto {
+          name: Some("Kind".to_string()),
+          value: vec![
+            EnumValueDescriptorProto { name: Some("UNKNOWN".to_string()), number: Some(0), options: None },
+            EnumValueDescriptorProto { name: Some("PARK".to_string()), number: Some(1), options: None }
+          ],
+          .. EnumDescriptorProto::default()
+        }],
+        .. FileDescriptorProto::default()
+      }]
+    }.encode_to_vec()
+  }
+
+  fn plugin_configuration() -> HashMap<String, Value> {
+    hashmap!{
+      "descriptors".to_string() => json!({
+        "abc123": { "protoDescriptors": BASE64.encode(descriptor_set()), "protoFile": "..." }
+      })
+    }
+  }
+
+  fn feature(name: &str, latitude: i32, tags: &[i32]) -> Vec<u8> {
+    let descriptors = ProtobufDescriptors::from_bytes(&descriptor_set()).unwrap();
+    descriptors.encode_message(".test.Feature", &json!({
+      "name": name,
+      "location": { "latitude": latitude, "longitude": -20 },
+      "tags": tags,
+      "kind": "PARK",
+      "attributes": { "colour": "green" }
+    })).unwrap()
+  }
+
+  fn protobuf_request(bytes: Vec<u8>) -> Request {
+    Request {
+      body: OptionalBody::Present(Bytes::from(bytes), Some("application/protobuf;message=.test.Feature".into()), None),
+      .. Request::default()
+    }
+  }
+
+  #[test]
+  fn decode_and_encode_messages() {
+    let descriptors = ProtobufDescriptors::from_bytes(&descriptor_set()).unwrap();
+    let bytes = feature("Park", 10, &[1, 2, 300]);
+    let decoded = descriptors.decode_message("test.Feature", &bytes).unwrap();
+    expect!(decoded.clone()).to(be_equal_to(json!({
+      "name": "Park",
+      "location": { "latitude": 10, "longitude": -20 },
+      "tags": [1, 2, 300],
+      "kind": "PARK",
+      "attributes": { "colour": "green" },
+      "score": 0.0
+    })));
+    expect!(descriptors.encode_message(".test.Feature", &decoded).unwrap()).to(be_equal_to(bytes));
+
+    let decoded = descriptors.decode_message(".test.Feature", &[]).unwrap();
+    expect!(decoded).to(be_equal_to(json!({
+      "name": "", "tags": [], "kind": "UNKNOWN", "attributes": {}, "score": 0.0
+    })));
+    expect!(descriptors.decode_message(".test.Feature", &[0x0A, 0x05, 0x41])).to(be_err());
+    expect!(descriptors.decode_message(".test.Other", &[])).to(be_err());
+  }
+
+  #[test]
+  fn decode_message_with_a_length_that_overflows() {
+    let descriptors = ProtobufDescriptors::from_bytes(&descriptor_set()).unwrap();
+    let message = [0x0A, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01];
+    expect!(descriptors.decode_message(".test.Feature", &message)).to(be_err());
+  }
+
+  #[test]
+  fn match_protobuf_bodies() {
+    let plugin_config = hashmap!{
+      PROTOBUF_PLUGIN.to_string() => PluginInteractionConfig {
+        pact_configuration: plugin_configuration(),
+        interaction_configuration: hashmap!{ "descriptorKey".to_string() => json!("abc123") }
+      }
+    };
+    let context = CoreMatchingContext::new(DiffConfig::NoUnexpectedKeys, &matchingrules_list! {
+      "body";
+      "$.location.latitude" => [ MatchingRule::Integer ],
+      "$.tags" => [ MatchingRule::MinType(1) ]
+    }, &plugin_config);
+    let expected = protobuf_request(feature("Park", 10, &[1]));
+
+    let actual = protobuf_request(feature("Park", 55, &[4, 5]));
+    expect!(match_protobuf(&expected, &actual, &context)).to(be_ok());
+
+    let actual = protobuf_request(feature("Other", 55, &[]));
+    let mismatches = match_protobuf(&expected, &actual, &context).unwrap_err();
+    expect!(mismatches.iter().map(|m| m.description()).collect::<Vec<_>>()).to(be_equal_to(vec![
+      "$.name -> Expected 'Other' (String) to be equal to 'Park' (String)".to_string(),
+      "$.tags -> Expected [] (size 0) to have minimum size of 1".to_string()
+    ]));
+
+    let actual = protobuf_request(vec![0x12, 0x05]);
+    let mismatches = match_protobuf(&expected, &actual, &context).unwrap_err();
+    expect!(mismatches[0].description()).to(be_equal_to(
+      "$ -> Failed to decode the actual body as a '.test.Feature' message: Unexpected end of message, expected 5 more bytes"));
+  }
+
+  #[test]
+  fn match_protobuf_bodies_without_descriptors() {
+    let context = CoreMatchingContext::new(DiffConfig::NoUnexpectedKeys, &MatchingRuleCategory::empty("body"), &hashmap!{});
+    let expected = protobuf_request(feature("Park", 10, &[1]));
+    expect!(match_protobuf(&expected, &protobuf_request(feature("Park", 10, &[1])), &context)).to(be_ok());
+    expect!(match_protobuf(&expected, &protobuf_request(feature("Park", 55, &[1])), &context)).to(be_err());
+  }
+
+  #[test]
+  fn generate_protobuf_body_test() {
+    let plugin_data = vec![PluginData {
+      name: PROTOBUF_PLUGIN.to_string(),
+      version: "0.3.0".to_string(),
+      configuration: plugin_configuration()
+    }];
+    let body = OptionalBody::Present(Bytes::from(feature("Park", 10, &[1])), None, None);
+    let content_type = ContentType::parse("application/protobuf;message=.test.Feature").unwrap();
+    let generators = hashmap!{
+      DocPath::new_unwrap("$.location.latitude") => Generator::RandomInt(100, 200)
+    };
+    let result = generate_protobuf_body(&GeneratorTestMode::Provider, &body, &content_type, &hashmap!{},
+      &generators, &DefaultVariantMatcher{}, &plugin_data, &hashmap!{}).unwrap();
+    let descriptors = ProtobufDescriptors::from_bytes(&descriptor_set()).unwrap();
+    let decoded = descriptors.decode_message(".test.Feature", &result.value().unwrap()).unwrap();
+    let latitude = decoded["location"]["latitude"].as_i64().unwrap();
+    expect!((100..=200).contains(&latitude)).to(be_true());
+    expect!(decoded["name"].clone()).to(be_equal_to(json!("Park")));
+  }
+
+  #[test]
+  fn generate_protobuf_body_keeps_the_grpc_length_prefix() {
+    let plugin_data = vec![PluginData {
+      name: PROTOBUF_PLUGIN.to_string(),
+      version: "0.3.0".to_string(),
+      configuration: plugin_configuration()
+    }];
+    let body = OptionalBody::Present(Bytes::from(add_grpc_frame(&feature("Park", 10, &[1]))), None, None);
+    let content_type = ContentType::parse("application/grpc;message=.test.Feature").unwrap();
+    let generators = hashmap!{
+      DocPath::new_unwrap("$.name") => Generator::ProviderStateGenerator("name".to_string(), None)
+    };
+    let result = generate_protobuf_body(&GeneratorTestMode::Provider, &body, &content_type,
+      &hashmap!{ "name" => json!("Lake") }, &generators, &DefaultVariantMatcher{}, &plugin_data, &hashmap!{}).unwrap();
+    let bytes = result.value().unwrap();
+    expect!(has_grpc_frame(&content_type, &bytes)).to(be_true());
+    let descriptors = ProtobufDescriptors::from_bytes(&descriptor_set()).unwrap();
+    let decoded = descriptors.decode_message(".test.Feature", &bytes[5..]).unwrap();
+    expect!(decoded["name"].clone()).to(be_equal_to(json!("Lake")));
+  }
+}
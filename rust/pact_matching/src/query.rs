@@ -3,7 +3,6 @@
 use std::collections::HashMap;
 
 use itertools::Itertools;
-use maplit::hashmap;
 use pact_models::matchingrules::MatchingRule;
 use pact_models::path_exp::DocPath;
 use tracing::debug;
@@ -11,13 +10,16 @@ use tracing::debug;
 use crate::{matchers, Matches, MatchingContext, merge_result, Mismatch, CommonMismatch};
 use crate::matchingrules::compare_lists_with_matchingrules;
 
+pub(crate) mod structured;
+
 /// Match the query parameters as Maps
 pub(crate) fn match_query_maps(
-  expected: HashMap<String, Vec<Option<String>>>,
-  actual: HashMap<String, Vec<Option<String>>>,
+  mut expected: HashMap<String, Vec<Option<String>>>,
+  mut actual: HashMap<String, Vec<Option<String>>>,
   context: &dyn MatchingContext
 ) -> HashMap<String, Vec<Mismatch>> {
-  let mut result: HashMap<String, Vec<Mismatch>> = hashmap!{};
+  let mut result: HashMap<String, Vec<Mismatch>> = structured::match_structured_query(&mut expected,
+    &mut actual, context);
   for (key, value) in &expected {
     let expected_value = value.iter().map(|v| v.clone().unwrap_or_default()).collect_vec();
     match actual.get(key) {
@@ -0,0 +1,226 @@
+//! Matching of structured query parameters. Query parameters that use bracket notation
+//! (`filter[status]=open&filter[tags][]=a`) or have JSON encoded values (`q={"age":{"gt":3}}`)
+//! can be decoded into a nested value, which is then matched in the same way as a JSON body.
+//!
+//! Decoding is opt-in: it is only applied to parameters that have matching rules defined for values
+//! inside the parameter (i.e. `$.filter.status` or `$.q.age.gt`).
+
+use std::collections::{BTreeSet, HashMap};
+
+use pact_models::matchingrules::{Category, MatchingRuleCategory};
+use pact_models::path_exp::{DocPath, PathToken};
+use pact_models::query_strings::{decode_nested_query_param, nested_query_param_names};
+use serde_json::{Map, Value};
+use tracing::debug;
+
+use crate::{MatchingContext, Mismatch};
+use crate::json::compare_json;
+
+/// Returns the base name of the parameter if the path refers to a value inside a query parameter.
+/// Paths like `$.id[0]` refer to a value of a repeated parameter, not a nested value.
+pub(crate) fn nested_param_name(path: &DocPath) -> Option<&str> {
+  match path.tokens().as_slice() {
+    [PathToken::Root, PathToken::Field(name), PathToken::Field(_) | PathToken::Star, ..] => Some(name.as_str()),
+    [PathToken::Root, PathToken::Field(name), _, _, ..] => Some(name.as_str()),
+    _ => None
+  }
+}
+
+/// Names of the query parameters that have matching rules defined for nested values
+pub(crate) fn structured_query_params(context: &dyn MatchingContext) -> BTreeSet<String> {
+  context.matchers().rules.keys()
+    .filter_map(nested_param_name)
+    .map(|name| name.to_string())
+    .collect()
+}
+
+/// Wraps the decoded value in a map keyed by the parameter name, so rule paths like
+/// `$.filter.status` apply to it
+pub(crate) fn wrap_value(name: &str, value: Value) -> Value {
+  let mut map = Map::new();
+  map.insert(name.to_string(), value);
+  Value::Object(map)
+}
+
+/// Matches the query parameters that have matching rules defined for nested values. The matched
+/// parameters are removed from the expected and actual query parameters, so the remaining
+/// parameters can be matched as lists of values.
+pub(crate) fn match_structured_query(
+  expected: &mut HashMap<String, Vec<Option<String>>>,
+  actual: &mut HashMap<String, Vec<Option<String>>>,
+  context: &dyn MatchingContext
+) -> HashMap<String, Vec<Mismatch>> {
+  let mut result = HashMap::new();
+
+  for name in structured_query_params(context) {
+    let expected_value = match decode_nested_query_param(expected, &name) {
+      Some(value) => value,
+      None => continue
+    };
+    let actual_value = decode_nested_query_param(actual, &name);
+    for key in nested_query_param_names(expected, &name) {
+      expected.remove(&key);
+    }
+    for key in nested_query_param_names(actual, &name) {
+      actual.remove(&key);
+    }
+    debug!(%name, %expected_value, ?actual_value, "Matching structured query parameter");
+
+    let mismatches = match actual_value {
+      Some(actual_value) => {
+        let rules = context.matchers().filter(|(path, _)| nested_param_name(path) == Some(name.as_str())
+          || path.first_field() == Some(name.as_str()));
+        let body_context = context.clone_with(&MatchingRuleCategory {
+          name: Category::BODY,
+          rules: rules.rules
+        });
+        compare_json(&DocPath::root(), &wrap_value(&name, expected_value.clone()),
+          &wrap_value(&name, actual_value), body_context.as_ref())
+          .err()
+          .unwrap_or_default()
+          .iter()
+          .map(|mismatch| Mismatch::QueryMismatch {
+            parameter: name.clone(),
+            expected: mismatch.expected.clone(),
+            actual: mismatch.actual.clone(),
+            mismatch: format!("Mismatch with query parameter '{}' at {}: {}", name, mismatch.path,
+              mismatch.description)
+          })
+          .collect()
+      }
+      None => vec![Mismatch::QueryMismatch {
+        parameter: name.clone(),
+        expected: expected_value.to_string(),
+        actual: "".to_string(),
+        mismatch: format!("Expected query parameter '{}' but was missing", name)
+      }]
+    };
+    result.insert(name, mismatches);
+  }
+
+  result
+}
+
+/// Returns the query parameters with the nested value for the parameter replaced. Parameters
+/// that were JSON encoded are re-encoded as JSON, otherwise bracket notation is used.
+pub(crate) fn replace_nested_query_param(
+  query: &HashMap<String, Vec<Option<String>>>,
+  name: &str,
+  value: &Value
+) -> HashMap<String, Vec<Option<String>>> {
+  let json_encoded = query.contains_key(name);
+  let mut query = query.clone();
+  for key in nested_query_param_names(&query, name) {
+    query.remove(&key);
+  }
+  if json_encoded {
+    query.insert(name.to_string(), vec![Some(value.to_string())]);
+  } else {
+    for (key, value) in pact_models::query_strings::encode_nested_query_param(name, value) {
+      query.entry(key).or_default().push(Some(value));
+    }
+  }
+  query
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use maplit::hashmap;
+  use pact_models::matchingrules::MatchingRule;
+  use pact_models::matchingrules_list;
+  use serde_json::json;
+
+  use crate::{CoreMatchingContext, DiffConfig};
+
+  use super::*;
+
+  fn query(params: &[(&str, &str)]) -> HashMap<String, Vec<Option<String>>> {
+    let mut query: HashMap<String, Vec<Option<String>>> = HashMap::new();
+    for (key, value) in params {
+      query.entry(key.to_string()).or_default().push(Some(value.to_string()));
+    }
+    query
+  }
+
+  #[test]
+  fn nested_param_names_for_rule_paths() {
+    expect!(nested_param_name(&DocPath::new_unwrap("$.filter.status"))).to(be_some().value("filter"));
+    expect!(nested_param_name(&DocPath::new_unwrap("$.filter.*"))).to(be_some().value("filter"));
+    expect!(nested_param_name(&DocPath::new_unwrap("$.q[0].age"))).to(be_some().value("q"));
+    expect!(nested_param_name(&DocPath::new_unwrap("$.id"))).to(be_none());
+    expect!(nested_param_name(&DocPath::new_unwrap("$.id[0]"))).to(be_none());
+  }
+
+  #[test]
+  fn match_structured_query_with_bracket_notation() {
+    let context = CoreMatchingContext::new(DiffConfig::NoUnexpectedKeys, &matchingrules_list! {
+      "query";
+      "$.filter.status" => [ MatchingRule::Regex("open|closed".to_string()) ],
+      "$.filter.tags" => [ MatchingRule::MinType(1) ],
+      "$.filter.size" => [ MatchingRule::Integer ]
+    }, &hashmap!{});
+    let mut expected = query(&[("filter[status]", "open"), ("filter[tags][]", "a"), ("filter[size]", "10"), ("page", "1")]);
+    let mut actual = query(&[("filter[status]", "closed"), ("filter[tags][]", "x"), ("filter[tags][]", "y"),
+      ("filter[size]", "20"), ("page", "1")]);
+    let result = match_structured_query(&mut expected, &mut actual, &context);
+    expect!(result).to(be_equal_to(hashmap!{ "filter".to_string() => vec![] }));
+    expect!(expected.keys().collect::<Vec<_>>()).to(be_equal_to(vec!["page"]));
+    expect!(actual.keys().collect::<Vec<_>>()).to(be_equal_to(vec!["page"]));
+
+    let mut expected = query(&[("filter[status]", "open"), ("filter[size]", "10")]);
+    let mut actual = query(&[("filter[status]", "pending"), ("filter[size]", "ten"), ("filter[other]", "1")]);
+    let result = match_structured_query(&mut expected, &mut actual, &context);
+    let mut messages = result.get("filter").unwrap().iter().map(|m| m.description()).collect::<Vec<_>>();
+    messages.sort();
+    expect!(messages).to(be_equal_to(vec![
+      "Mismatch with query parameter 'filter' at $.filter.size: Expected 'ten' (String) to be an integer number".to_string(),
+      "Mismatch with query parameter 'filter' at $.filter.status: Expected 'pending' to match 'open|closed'".to_string(),
+      "Mismatch with query parameter 'filter' at $.filter: Expected a Map with keys [size, status] but received one with keys [other, size, status]".to_string()
+    ]));
+  }
+
+  #[test]
+  fn match_structured_query_with_json_encoded_values() {
+    let context = CoreMatchingContext::new(DiffConfig::NoUnexpectedKeys, &matchingrules_list! {
+      "query";
+      "$.q.age.gt" => [ MatchingRule::Integer ]
+    }, &hashmap!{});
+    let mut expected = query(&[("q", r#"{"age":{"gt":3}}"#)]);
+    let mut actual = query(&[("q", r#"{ "age": { "gt": 18 } }"#)]);
+    expect!(match_structured_query(&mut expected, &mut actual, &context))
+      .to(be_equal_to(hashmap!{ "q".to_string() => vec![] }));
+
+    let mut expected = query(&[("q", r#"{"age":{"gt":3}}"#)]);
+    let mut actual = HashMap::new();
+    let result = match_structured_query(&mut expected, &mut actual, &context);
+    expect!(result.get("q").unwrap().iter().map(|m| m.description()).collect::<Vec<_>>())
+      .to(be_equal_to(vec!["Expected query parameter 'q' but was missing".to_string()]));
+  }
+
+  #[test]
+  fn match_structured_query_ignores_parameters_without_nested_rules() {
+    let context = CoreMatchingContext::new(DiffConfig::NoUnexpectedKeys, &matchingrules_list! {
+      "query";
+      "$.filter" => [ MatchingRule::Type ]
+    }, &hashmap!{});
+    let mut expected = query(&[("filter[status]", "open")]);
+    let mut actual = query(&[("filter[status]", "closed")]);
+    expect!(match_structured_query(&mut expected, &mut actual, &context)).to(be_equal_to(hashmap!{}));
+    expect!(expected.len()).to(be_equal_to(1));
+  }
+
+  #[test]
+  fn replace_nested_query_param_test() {
+    let query = query(&[("filter[status]", "open"), ("q", r#"{"a":1}"#), ("page", "1")]);
+    let result = replace_nested_query_param(&query, "filter", &json!({ "status": "closed", "tags": ["x"] }));
+    expect!(result).to(be_equal_to(hashmap!{
+      "filter[status]".to_string() => vec![Some("closed".to_string())],
+      "filter[tags][]".to_string() => vec![Some("x".to_string())],
+      "q".to_string() => vec![Some(r#"{"a":1}"#.to_string())],
+      "page".to_string() => vec![Some("1".to_string())]
+    }));
+    let result = replace_nested_query_param(&query, "q", &json!({ "a": 2 }));
+    expect!(result.get("q").unwrap()).to(be_equal_to(&vec![Some(r#"{"a":2}"#.to_string())]));
+  }
+}
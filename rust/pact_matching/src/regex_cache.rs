@@ -0,0 +1,60 @@
+//! Cache of compiled regular expressions. Regex matchers are applied to every value they match,
+//! so compiling the expression each time can take up most of the time spent matching large bodies.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use lazy_static::lazy_static;
+
+#[cfg(not(target_family = "wasm"))] use onig::{Error, Regex};
+
+#[cfg(target_family = "wasm")] use crate::wasm_regex::Regex;
+#[cfg(target_family = "wasm")] use regex::Error;
+
+/// Maximum number of compiled regular expressions to keep. The cache is cleared once it is full,
+/// so a large number of generated expressions can not grow it without bounds.
+const MAX_CACHED_REGEXES: usize = 1000;
+
+lazy_static! {
+  static ref REGEX_CACHE: Mutex<HashMap<String, Arc<Regex>>> = Mutex::new(HashMap::new());
+}
+
+/// Returns the compiled regular expression, compiling it if it is not already in the cache.
+/// Invalid expressions are not cached.
+pub fn compile_regex(regex: &str) -> Result<Arc<Regex>, Error> {
+  if let Some(re) = REGEX_CACHE.lock().ok().and_then(|cache| cache.get(regex).cloned()) {
+    return Ok(re);
+  }
+
+  let re = Arc::new(Regex::new(regex)?);
+  if let Ok(mut cache) = REGEX_CACHE.lock() {
+    if cache.len() >= MAX_CACHED_REGEXES {
+      cache.clear();
+    }
+    cache.insert(regex.to_string(), re.clone());
+  }
+  Ok(re)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+
+  use expectest::prelude::*;
+
+  use super::compile_regex;
+
+  #[test]
+  fn compile_regex_returns_the_cached_regex() {
+    let re = compile_regex("\\d+-cache-test").unwrap();
+    expect!(re.is_match("1234-cache-test")).to(be_true());
+    let re2 = compile_regex("\\d+-cache-test").unwrap();
+    expect!(Arc::ptr_eq(&re, &re2)).to(be_true());
+  }
+
+  #[test]
+  fn compile_regex_returns_an_error_for_invalid_expressions() {
+    expect!(compile_regex("[")).to(be_err());
+    expect!(compile_regex("[")).to(be_err());
+  }
+}
@@ -0,0 +1,327 @@
+//! Functions for parsing and matching Server-Sent Event streams (`text/event-stream` bodies).
+//! Streams are parsed into an ordered list of events, which is converted to a JSON array and
+//! matched using the JSON matching rules. Event data that is JSON can be addressed with paths like
+//! `$[*].data.id`, the number of events is checked with the min/max type matchers on `$`, and the
+//! event `id` and `retry` fields are only compared if there is a matcher defined for them (as they
+//! are normally generated by the provider).
+
+use bytes::Bytes;
+use pact_models::bodies::OptionalBody;
+use pact_models::http_parts::HttpPart;
+use pact_models::path_exp::DocPath;
+use serde_json::{json, Map, Value};
+
+use crate::{MatchingContext, Mismatch};
+use crate::json::compare_json;
+
+/// Content type of a Server-Sent Event stream
+pub const EVENT_STREAM_CONTENT_TYPE: &str = "text/event-stream";
+
+/// A single event from a Server-Sent Event stream
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ServerSentEvent {
+  /// Event type (the `event` field)
+  pub event: Option<String>,
+  /// Event ID (the `id` field)
+  pub id: Option<String>,
+  /// Event data. Multiple `data` fields are joined with a newline.
+  pub data: String,
+  /// Reconnection time in milliseconds (the `retry` field)
+  pub retry: Option<u64>
+}
+
+impl ServerSentEvent {
+  /// Event with the given data
+  pub fn new<S: Into<String>>(data: S) -> Self {
+    ServerSentEvent {
+      data: data.into(),
+      .. ServerSentEvent::default()
+    }
+  }
+
+  /// Converts the event to JSON. If the data is a JSON document, it will be included as JSON.
+  pub fn to_json(&self) -> Value {
+    let mut map = Map::new();
+    if let Some(event) = &self.event {
+      map.insert("event".to_string(), Value::String(event.clone()));
+    }
+    if let Some(id) = &self.id {
+      map.insert("id".to_string(), Value::String(id.clone()));
+    }
+    let data = serde_json::from_str::<Value>(&self.data)
+      .unwrap_or_else(|_| Value::String(self.data.clone()));
+    map.insert("data".to_string(), data);
+    if let Some(retry) = self.retry {
+      map.insert("retry".to_string(), json!(retry));
+    }
+    Value::Object(map)
+  }
+
+  /// Formats the event in the event stream format
+  pub fn to_stream_string(&self) -> String {
+    let mut buffer = String::new();
+    if let Some(event) = &self.event {
+      buffer.push_str(&format!("event: {}\n", event));
+    }
+    if let Some(id) = &self.id {
+      buffer.push_str(&format!("id: {}\n", id));
+    }
+    if let Some(retry) = self.retry {
+      buffer.push_str(&format!("retry: {}\n", retry));
+    }
+    for line in self.data.split('\n') {
+      buffer.push_str(&format!("data: {}\n", line));
+    }
+    buffer.push('\n');
+    buffer
+  }
+}
+
+/// Formats the events as an event stream
+pub fn to_event_stream(events: &[ServerSentEvent]) -> String {
+  events.iter().map(|event| event.to_stream_string()).collect()
+}
+
+/// Parses an event stream into the list of events. An event at the end of the stream that has
+/// not been terminated by a blank line is included.
+pub fn parse_event_stream(stream: &str) -> Vec<ServerSentEvent> {
+  parse(stream, true)
+}
+
+/// Returns the number of events in the stream that have been terminated by a blank line. This is
+/// used to determine when enough of a stream has been received.
+pub fn complete_events(stream: &str) -> usize {
+  parse(stream, false).len()
+}
+
+fn parse(stream: &str, include_trailing: bool) -> Vec<ServerSentEvent> {
+  let mut events = vec![];
+  let mut event = ServerSentEvent::default();
+  let mut data: Option<String> = None;
+  let mut has_fields = false;
+
+  let normalised = stream.replace("\r\n", "\n").replace('\r', "\n");
+  let mut lines = normalised.split('\n').peekable();
+  while let Some(line) = lines.next() {
+    // The last line is only complete if it was terminated
+    if lines.peek().is_none() && !line.is_empty() && !include_trailing {
+      break;
+    }
+
+    if line.is_empty() {
+      if lines.peek().is_none() && !include_trailing {
+        break;
+      }
+      if has_fields {
+        // Events without data are not dispatched
+        if let Some(data) = data.take() {
+          event.data = data;
+          events.push(event.clone());
+        }
+        event = ServerSentEvent::default();
+        has_fields = false;
+      }
+      continue;
+    }
+
+    if line.starts_with(':') {
+      continue;
+    }
+
+    let (field, value) = match line.split_once(':') {
+      Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+      None => (line, "")
+    };
+    has_fields = true;
+    match field {
+      "event" => event.event = Some(value.to_string()),
+      "data" => match &mut data {
+        Some(data) => {
+          data.push('\n');
+          data.push_str(value);
+        }
+        None => data = Some(value.to_string())
+      },
+      "id" if !value.contains('\0') => event.id = Some(value.to_string()),
+      "retry" => if let Ok(retry) = value.parse() {
+        event.retry = Some(retry);
+      },
+      _ => {}
+    }
+  }
+
+  if include_trailing && has_fields {
+    if let Some(data) = data {
+      event.data = data;
+      events.push(event);
+    }
+  }
+
+  events
+}
+
+/// Matches the bodies as Server-Sent Event streams
+pub(crate) fn match_event_stream(
+  expected: &(dyn HttpPart + Send + Sync),
+  actual: &(dyn HttpPart + Send + Sync),
+  context: &(dyn MatchingContext + Send + Sync)
+) -> Result<(), Vec<Mismatch>> {
+  let expected_body = expected.body();
+  let actual_body = actual.body();
+  match expected_body {
+    OptionalBody::Missing | OptionalBody::Null => Ok(()),
+    _ => {
+      let expected_events = parse_event_stream(&body_text(expected_body));
+      let actual_events = parse_event_stream(&body_text(actual_body));
+      let mismatches = compare_events(&expected_events, &actual_events, context);
+      if mismatches.is_empty() {
+        Ok(())
+      } else {
+        Err(mismatches)
+      }
+    }
+  }
+}
+
+fn body_text(body: &OptionalBody) -> String {
+  String::from_utf8_lossy(&body.value().unwrap_or_default()).to_string()
+}
+
+fn compare_events(
+  expected: &[ServerSentEvent],
+  actual: &[ServerSentEvent],
+  context: &(dyn MatchingContext + Send + Sync)
+) -> Vec<Mismatch> {
+  let mut expected_json = Value::Array(expected.iter().map(|event| event.to_json()).collect());
+  let mut actual_json = Value::Array(actual.iter().map(|event| event.to_json()).collect());
+
+  // IDs and retry times are only compared if there is a matcher defined for them
+  for field in ["id", "retry"] {
+    for (index, event) in actual_json.as_array_mut().unwrap().iter_mut().enumerate() {
+      let mut path = DocPath::root();
+      path.push_index(index).push_field(field);
+      if !context.matcher_is_defined(&path) {
+        if let Some(event) = event.as_object_mut() {
+          event.remove(field);
+        }
+        if let Some(event) = expected_json.get_mut(index).and_then(|event| event.as_object_mut()) {
+          event.remove(field);
+        }
+      }
+    }
+  }
+
+  match compare_json(&DocPath::root(), &expected_json, &actual_json, context) {
+    Ok(()) => vec![],
+    Err(result) => result.iter()
+      .map(|mismatch| Mismatch::BodyMismatch {
+        path: mismatch.path.clone(),
+        expected: Some(Bytes::from(mismatch.expected.clone())),
+        actual: Some(Bytes::from(mismatch.actual.clone())),
+        mismatch: mismatch.description.clone()
+      })
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use maplit::hashmap;
+  use pact_models::matchingrules::MatchingRule;
+  use pact_models::matchingrules_list;
+  use pact_models::response::Response;
+
+  use crate::{CoreMatchingContext, DiffConfig};
+
+  use super::*;
+
+  fn event_stream(body: &str) -> Response {
+    Response {
+      headers: Some(hashmap!{ "Content-Type".to_string() => vec![EVENT_STREAM_CONTENT_TYPE.to_string()] }),
+      body: OptionalBody::Present(body.to_string().into(), Some(EVENT_STREAM_CONTENT_TYPE.into()), None),
+      .. Response::default()
+    }
+  }
+
+  fn descriptions(result: Result<(), Vec<Mismatch>>) -> Vec<String> {
+    result.unwrap_err().iter().map(|m| m.description()).collect()
+  }
+
+  #[test]
+  fn parse_event_stream_test() {
+    let stream = ": comment\nevent: update\nid: 1\nretry: 5000\ndata: {\"a\":\ndata: 1}\n\ndata:second\r\n\r\nid: 3\n\ndata: last";
+    expect!(parse_event_stream(stream)).to(be_equal_to(vec![
+      ServerSentEvent {
+        event: Some("update".to_string()),
+        id: Some("1".to_string()),
+        data: "{\"a\":\n1}".to_string(),
+        retry: Some(5000)
+      },
+      ServerSentEvent::new("second"),
+      ServerSentEvent::new("last")
+    ]));
+    expect!(complete_events(stream)).to(be_equal_to(2));
+    expect!(complete_events("data: 1\n\ndata: 2\n")).to(be_equal_to(1));
+    expect!(complete_events("data: 1\n\ndata: 2\n\n")).to(be_equal_to(2));
+  }
+
+  #[test]
+  fn event_stream_round_trip() {
+    let events = vec![
+      ServerSentEvent { event: Some("update".to_string()), id: Some("1".to_string()), data: "line 1\nline 2".to_string(), retry: None },
+      ServerSentEvent::new("{\"id\":1}")
+    ];
+    let stream = to_event_stream(&events);
+    expect!(stream.as_str()).to(be_equal_to("event: update\nid: 1\ndata: line 1\ndata: line 2\n\ndata: {\"id\":1}\n\n"));
+    expect!(parse_event_stream(&stream)).to(be_equal_to(events));
+  }
+
+  #[test]
+  fn match_event_stream_test() {
+    let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &matchingrules_list! {
+      "body"; "$[*].data.price" => [ MatchingRule::Decimal ]
+    }, &hashmap!{});
+    let expected = event_stream("event: price\nid: 1\ndata: {\"symbol\": \"ABC\", \"price\": 10.5}\n\n");
+
+    let actual = event_stream("event: price\nid: 1715000000-0\ndata: {\"symbol\":\"ABC\",\n\ndata: \"price\": 11.25}\n\n");
+    expect!(match_event_stream(&expected, &actual, &context)).to(be_err());
+
+    let actual = event_stream("event: price\nid: 1715000000-0\ndata: {\"symbol\":\"ABC\",\"price\":11.25}\n\n");
+    expect!(match_event_stream(&expected, &actual, &context)).to(be_ok());
+
+    let actual = event_stream("event: quote\ndata: {\"symbol\":\"ABC\",\"price\":11}\n\n");
+    let mut mismatches = descriptions(match_event_stream(&expected, &actual, &context));
+    mismatches.sort();
+    expect!(mismatches).to(be_equal_to(vec![
+      "$[0].data.price -> Expected 11 (Integer) to be a decimal number".to_string(),
+      "$[0].event -> Expected 'quote' (String) to be equal to 'price' (String)".to_string()
+    ]));
+  }
+
+  #[test]
+  fn match_event_stream_with_event_count() {
+    let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &matchingrules_list! {
+      "body";
+      "$" => [ MatchingRule::MinMaxType(1, 3) ],
+      "$[*].id" => [ MatchingRule::Regex("^\\d+$".to_string()) ]
+    }, &hashmap!{});
+    let expected = event_stream("id: 1\ndata: tick\n\n");
+
+    let actual = event_stream("id: 10\ndata: tick\n\nid: 11\ndata: tick\n\n");
+    expect!(match_event_stream(&expected, &actual, &context)).to(be_ok());
+
+    let actual = event_stream("id: a\ndata: tick\n\nid: 11\ndata: tick\n\nid: 12\ndata: tick\n\nid: 13\ndata: tick\n\n");
+    let mismatches = descriptions(match_event_stream(&expected, &actual, &context));
+    expect!(mismatches.iter().any(|m| m.contains("maximum size of 3"))).to(be_true());
+    expect!(mismatches.iter().any(|m| m.starts_with("$[0].id"))).to(be_true());
+
+    let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &matchingrules_list! {
+      "body"; "$[*].data" => [ MatchingRule::Type ]
+    }, &hashmap!{});
+    let actual = event_stream("data: tick\n\ndata: tock\n\n");
+    expect!(descriptions(match_event_stream(&expected, &actual, &context))).to(be_equal_to(vec![
+      "$ -> Expected a List with 1 elements but received 2 elements".to_string()
+    ]));
+  }
+}
@@ -5,9 +5,11 @@ use pretty_assertions::assert_eq;
 
 use pact_models::{matchingrules, matchingrules_list};
 use pact_models::matchingrules::expressions::{MatchingRuleDefinition, ValueType};
+use pact_models::matchingrules::RuleLogic;
 use pact_models::bodies::OptionalBody;
 use pact_models::content_types::{JSON, TEXT};
 use pact_models::HttpStatus;
+use pact_models::path_template::PathTemplate;
 use pact_models::request::Request;
 
 use super::*;
@@ -792,6 +794,45 @@ fn match_path_returns_a_mismatch_if_the_path_does_not_match_with_a_matcher() {
   }]));
 }
 
+#[test]
+fn match_path_matches_each_segment_with_path_segment_matchers() {
+  let context = CoreMatchingContext::new(
+    DiffConfig::AllowUnexpectedKeys,
+    &PathTemplate::parse("/orders/{id:integer}/items/{itemId:uuid}").unwrap().matching_rules(),
+    &hashmap!{}
+  );
+  let expected = "/orders/100/items/e2490de5-5bd3-43d5-b7c4-526e33f71304";
+  expect!(match_path(expected, "/orders/2/items/3f5e6f1a-7c0d-4b9e-8d4e-1a2b3c4d5e6f", &context)).to(be_ok());
+
+  let result = match_path(expected, "/order/abc/items/3f5e6f1a-7c0d-4b9e-8d4e-1a2b3c4d5e6f", &context);
+  let messages = result.unwrap_err().iter().map(|m| m.description()).collect::<Vec<_>>();
+  expect!(messages).to(be_equal_to(vec![
+    s!("Mismatch with path segment 0 ('order'): Expected 'order' to be equal to 'orders'"),
+    s!("Mismatch with path segment 1 ('abc'): Expected 'abc' to match an integer number")
+  ]));
+
+  let result = match_path(expected, "/orders/2", &context);
+  let messages = result.unwrap_err().iter().map(|m| m.description()).collect::<Vec<_>>();
+  expect!(messages).to(be_equal_to(vec![
+    s!("Expected path '/orders/100/items/e2490de5-5bd3-43d5-b7c4-526e33f71304' with 4 segment(s) but '/orders/2' has 2")
+  ]));
+}
+
+#[test]
+fn match_path_applies_the_whole_path_matcher_with_path_segment_matchers() {
+  let mut rules = PathTemplate::parse("/orders/{id:integer}").unwrap().matching_rules();
+  rules.add_rule(DocPath::empty(), MatchingRule::Regex(s!("/orders/[1-5]")), RuleLogic::And);
+  rules.add_rule(DocPath::new_unwrap("$.other"), MatchingRule::Regex(s!("/other")), RuleLogic::And);
+  let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &rules, &hashmap!{});
+  expect!(match_path("/orders/1", "/orders/2", &context)).to(be_ok());
+
+  let result = match_path("/orders/1", "/orders/9", &context);
+  let messages = result.unwrap_err().iter().map(|m| m.description()).collect::<Vec<_>>();
+  expect!(messages).to(be_equal_to(vec![
+    s!("Expected '/orders/9' to match '/orders/[1-5]'")
+  ]));
+}
+
 macro_rules! request {
   ($e:expr) => (Request { body: OptionalBody::Present($e.into(), None, None), .. Request::default() })
 }
@@ -903,6 +944,35 @@ fn compare_bodies_core_should_check_for_content_type_matcher() {
   expect!(result.first().unwrap().description()).to(be_equal_to("$ -> Expected binary contents to have content type 'application/gif' but detected contents was 'image/gif'"));
 }
 
+#[tokio::test]
+async fn compare_bodies_rejects_filter_expressions_for_non_json_bodies() {
+  let content_type = ContentType::parse("application/xml").unwrap();
+  let matching_rules = matchingrules!{ "body" => {
+    "$.items[?(@.type == 'refund')].amount" => [ MatchingRule::Type ]
+  } };
+  let body = OptionalBody::Present(Bytes::from("<items><item type=\"refund\" amount=\"10\"/></items>"),
+    Some(content_type.clone()), None);
+  let expected = Request {
+    body: body.clone(),
+    matching_rules: matching_rules.clone(),
+    .. Request::default()
+  };
+  let actual = Request {
+    body,
+    .. Request::default()
+  };
+  let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys,
+    &matching_rules.rules_for_category("body").unwrap(),
+    &hashmap!{}
+  );
+
+  let result = compare_bodies(&content_type, &expected, &actual, &context).await;
+
+  let mismatches = result.mismatches();
+  expect!(mismatches.len()).to(be_equal_to(1));
+  expect!(mismatches.first().unwrap().description()).to(be_equal_to("$.items[?(@.type == 'refund')].amount -> Filter expressions in matching rule paths are only supported for JSON bodies, but '$.items[?(@.type == 'refund')].amount' was used with a body with content type 'application/xml'"));
+}
+
 #[test_log::test]
 fn match_metadata_value_test() {
   let expected = json!("value-a");
@@ -953,3 +1023,21 @@ fn match_metadata_value_with_content_type_test() {
   let result = match_metadata_value("key", &expected, &actual, &context);
   expect!(result).to(be_err());
 }
+
+#[test]
+fn core_matching_context_rebuilds_the_index_when_the_matchers_are_replaced() {
+  let rules = matchingrules!{
+    "body" => { "$.a" => [ MatchingRule::Type ] }
+  };
+  let mut context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys,
+    &rules.rules_for_category(Category::BODY).unwrap_or_default(), &hashmap!{});
+  expect!(context.matcher_is_defined(&DocPath::new_unwrap("$.a"))).to(be_true());
+  expect!(context.matcher_is_defined(&DocPath::new_unwrap("$.b"))).to(be_false());
+
+  let rules = matchingrules!{
+    "body" => { "$.b" => [ MatchingRule::Type ] }
+  };
+  context.set_matchers(rules.rules_for_category(Category::BODY).unwrap_or_default());
+  expect!(context.matcher_is_defined(&DocPath::new_unwrap("$.a"))).to(be_false());
+  expect!(context.matcher_is_defined(&DocPath::new_unwrap("$.b"))).to(be_true());
+}
@@ -0,0 +1,192 @@
+//! Matching and generation of text bodies defined by text templates. The body has a regex matching
+//! rule with named capture groups, and the values of the captures are matched using the matching
+//! rules defined for them (`$.id` for the capture named `id`), so that mismatches are reported for
+//! each capture.
+
+use std::collections::HashMap;
+use std::str::from_utf8;
+
+use bytes::Bytes;
+use pact_models::bodies::OptionalBody;
+use pact_models::generators::{apply_generators, GenerateValue, Generator, GeneratorTestMode, VariantMatcher};
+use pact_models::matchingrules::{Category, MatchingRules};
+use pact_models::path_exp::DocPath;
+use pact_models::text_template::{capture_name, capture_names, capture_path, capture_values, replace_capture_values, template_regex};
+use serde_json::Value;
+use tracing::debug;
+
+use crate::{MatchingContext, Mismatch};
+use crate::generators::DefaultVariantMatcher;
+use crate::matchers::match_values;
+
+/// Matches the actual text against the text template defined by the matching rules for the body.
+/// Returns `None` if the body does not have a text template.
+pub(crate) fn match_text_template(
+  expected: &Option<Bytes>,
+  actual: &Option<Bytes>,
+  context: &dyn MatchingContext
+) -> Option<Result<(), Vec<Mismatch>>> {
+  let regex = template_regex(context.matchers())?;
+  let empty = Bytes::default();
+  let expected_str = from_utf8(expected.as_ref().unwrap_or(&empty)).ok()?;
+  let actual_str = from_utf8(actual.as_ref().unwrap_or(&empty)).ok()?;
+  debug!(template = %regex, "Matching body against text template");
+
+  let actual_values: HashMap<String, Option<String>> = match capture_values(&regex, actual_str) {
+    Some(values) => values.into_iter().collect(),
+    None => return Some(Err(vec![Mismatch::BodyMismatch {
+      path: "$".to_string(),
+      expected: expected.clone(),
+      actual: actual.clone(),
+      mismatch: format!("Expected '{}' to match the text template '{}'", actual_str, regex)
+    }]))
+  };
+  let expected_values: HashMap<String, Option<String>> = capture_values(&regex, expected_str)
+    .unwrap_or_default()
+    .into_iter()
+    .collect();
+
+  let mut mismatches = vec![];
+  for name in capture_names(&regex) {
+    let path = capture_path(&name);
+    let expected_value = expected_values.get(&name).cloned().flatten().unwrap_or_default();
+    let result = match actual_values.get(&name).cloned().flatten() {
+      Some(actual_value) => {
+        let result = if context.matcher_is_defined(&path) {
+          match_values(&path, &context.select_best_matcher(&path), expected_value.as_str(), actual_value.as_str())
+        } else if expected_value != actual_value {
+          Err(vec![format!("Expected '{}' to be equal to '{}'", actual_value, expected_value)])
+        } else {
+          Ok(())
+        };
+        result.map_err(|messages| (messages, Some(Bytes::from(actual_value))))
+      }
+      None => Err((vec![format!("Expected a value for '{}' but it was missing", name)], None))
+    };
+    if let Err((messages, actual_value)) = result {
+      for message in messages {
+        mismatches.push(Mismatch::BodyMismatch {
+          path: path.to_string(),
+          expected: Some(Bytes::from(expected_value.clone())),
+          actual: actual_value.clone(),
+          mismatch: format!("Mismatch with text capture '{}': {}", name, message)
+        });
+      }
+    }
+  }
+
+  if mismatches.is_empty() {
+    Some(Ok(()))
+  } else {
+    Some(Err(mismatches))
+  }
+}
+
+/// Applies the generators for the captures of the text template defined by the matching rules for
+/// the body. Returns `None` if the body does not have a text template.
+pub(crate) fn generate_text_template_body(
+  mode: &GeneratorTestMode,
+  body: &OptionalBody,
+  matching_rules: &MatchingRules,
+  generators: &HashMap<DocPath, Generator>,
+  context: &HashMap<&str, Value>
+) -> Option<OptionalBody> {
+  let rules = matching_rules.rules_for_category(Category::BODY)?;
+  let regex = template_regex(&rules)?;
+  let text = from_utf8(&body.value()?).ok()?.to_string();
+  let current_values: HashMap<String, String> = capture_values(&regex, &text)?.into_iter()
+    .filter_map(|(name, value)| value.map(|value| (name, value)))
+    .collect();
+
+  debug!("Applying text template generators...");
+  let mut values = HashMap::new();
+  apply_generators(mode, generators, &mut |key, generator| {
+    if let Some(name) = capture_name(key) {
+      let value = current_values.get(name).cloned().unwrap_or_default();
+      if let Ok(v) = generator.generate_value(&value, context, &DefaultVariantMatcher.boxed()) {
+        values.insert(name.to_string(), v);
+      }
+    }
+  });
+
+  replace_capture_values(&regex, &text, &values)
+    .map(|text| OptionalBody::Present(Bytes::from(text), body.content_type(), None))
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use maplit::hashmap;
+  use pact_models::matchingrules::{MatchingRule, MatchingRuleCategory, RuleLogic};
+  use pact_models::text_template::{TEMPLATE_PATH, TextTemplate};
+
+  use crate::{CoreMatchingContext, DiffConfig};
+
+  use super::*;
+
+  fn template_rules(template: &str) -> MatchingRuleCategory {
+    TextTemplate::parse(template).unwrap().matching_rules()
+  }
+
+  #[test]
+  fn match_text_template_with_captures() {
+    let rules = template_rules("Order {{id:integer}} shipped on {{date:date(yyyy-MM-dd)}} by {{carrier}}");
+    let context = CoreMatchingContext::new(DiffConfig::NoUnexpectedKeys, &rules, &hashmap!{});
+    let expected = Some(Bytes::from("Order 100 shipped on 2000-01-01 by Post"));
+
+    expect!(match_text_template(&expected, &Some(Bytes::from("Order 1234 shipped on 2024-02-29 by Courier")), &context))
+      .to(be_some().value(Ok(())));
+
+    let result = match_text_template(&expected, &Some(Bytes::from("Order 12a shipped on 01/02/2024 by Post")), &context)
+      .unwrap();
+    let messages = result.unwrap_err().iter().map(|m| m.description()).collect::<Vec<_>>();
+    expect!(messages).to(be_equal_to(vec![
+      "$.id -> Mismatch with text capture 'id': Expected '12a' to match an integer number".to_string(),
+      "$.date -> Mismatch with text capture 'date': Expected '01/02/2024' to match a date pattern of 'yyyy-MM-dd'".to_string()
+    ]));
+
+    let result = match_text_template(&expected, &Some(Bytes::from("Invoice 1234")), &context).unwrap();
+    expect!(result.unwrap_err().iter().map(|m| m.description()).collect::<Vec<_>>()).to(be_equal_to(vec![
+      format!("$ -> Expected 'Invoice 1234' to match the text template '{}'",
+        TextTemplate::parse("Order {{id:integer}} shipped on {{date:date(yyyy-MM-dd)}} by {{carrier}}").unwrap().to_regex())
+    ]));
+  }
+
+  #[test]
+  fn match_text_template_without_a_template() {
+    let context = CoreMatchingContext::new(DiffConfig::NoUnexpectedKeys, &MatchingRuleCategory::empty("body"), &hashmap!{});
+    expect!(match_text_template(&Some(Bytes::from("a")), &Some(Bytes::from("b")), &context)).to(be_none());
+
+    // A regex with named groups is not a template unless it is marked as one
+    let mut rules = MatchingRuleCategory::empty("body");
+    rules.add_rule(DocPath::root(), MatchingRule::Regex(r"\A(?<id>\d+)\z".to_string()), RuleLogic::And);
+    let context = CoreMatchingContext::new(DiffConfig::NoUnexpectedKeys, &rules, &hashmap!{});
+    expect!(match_text_template(&Some(Bytes::from("1")), &Some(Bytes::from("2")), &context)).to(be_none());
+  }
+
+  #[test]
+  fn match_text_template_reports_captures_that_did_not_match() {
+    let mut rules = MatchingRuleCategory::empty("body");
+    rules.add_rule(DocPath::new_unwrap(TEMPLATE_PATH), MatchingRule::Regex(r"\AOrder (?<id>\d+)( for (?<name>\w+))?\z".to_string()), RuleLogic::And);
+    let context = CoreMatchingContext::new(DiffConfig::NoUnexpectedKeys, &rules, &hashmap!{});
+    let result = match_text_template(&Some(Bytes::from("Order 1 for Fred")), &Some(Bytes::from("Order 1")), &context)
+      .unwrap();
+    expect!(result.unwrap_err().iter().map(|m| m.description()).collect::<Vec<_>>()).to(be_equal_to(vec![
+      "$.name -> Mismatch with text capture 'name': Expected a value for 'name' but it was missing".to_string()
+    ]));
+  }
+
+  #[test]
+  fn generate_text_template_body_test() {
+    let template = TextTemplate::parse("Order {{id:integer}} for {{name=Fred}}").unwrap();
+    let mut rules = MatchingRules::default();
+    rules.add_rules(Category::BODY, template.matching_rules());
+    let generators = hashmap!{
+      capture_path("id") => Generator::ProviderStateGenerator("orderId".to_string(), None)
+    };
+    let body = OptionalBody::Present(Bytes::from(template.to_example()), Some("text/plain".into()), None);
+    let result = generate_text_template_body(&GeneratorTestMode::Provider, &body, &rules, &generators,
+      &hashmap!{ "orderId" => Value::from(5678) });
+    expect!(result.and_then(|body| body.value_as_string())).to(be_some().value("Order 5678 for Fred"));
+  }
+}
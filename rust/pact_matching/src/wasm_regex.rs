@@ -0,0 +1,22 @@
+//! Regular expression support when compiled to WASM. The Oniguruma library can not be used on
+//! that target, so this wraps the pure Rust regex crate with the same semantics the matchers
+//! rely on (a regex must match the whole of the value).
+
+/// Regular expression that only matches the entire input
+#[derive(Debug, Clone)]
+pub struct Regex {
+  regex: regex::Regex
+}
+
+impl Regex {
+  /// Compile the regular expression
+  pub fn new(re: &str) -> Result<Regex, regex::Error> {
+    regex::Regex::new(format!(r"\A(?:{})\z", re).as_str())
+      .map(|regex| Regex { regex })
+  }
+
+  /// If the regular expression matches the whole of the given string
+  pub fn is_match(&self, text: &str) -> bool {
+    self.regex.is_match(text)
+  }
+}
@@ -4,7 +4,6 @@ use anyhow::anyhow;
 use bytes::Bytes;
 use itertools::{EitherOrBoth, Itertools};
 use maplit::*;
-use onig::Regex;
 use sxd_document::dom::*;
 use sxd_document::QName;
 
@@ -16,6 +15,7 @@ use pact_models::xml_utils::parse_bytes;
 use tracing::debug;
 
 use crate::matchers::*;
+use crate::regex_cache::compile_regex;
 use crate::MatchingContext;
 
 use super::DiffConfig;
@@ -92,7 +92,7 @@ impl<'a> Matches<&'a Element<'a>> for &'a Element<'a> {
     fn matches_with(&self, actual: &Element, matcher: &MatchingRule, cascaded: bool) -> anyhow::Result<()> {
         let result = match *matcher {
           MatchingRule::Regex(ref regex) => {
-            match Regex::new(regex) {
+            match compile_regex(regex) {
               Ok(re) => {
                 if re.is_match(actual.name().local_part()) {
                   Ok(())
@@ -13,6 +13,7 @@ use serde_json::{json, Value};
 use tracing::warn;
 
 use crate::content_types::{ContentType, ContentTypeHint};
+use crate::PactSpecification;
 
 /// Enum that defines the four main states that a body of a request and response can be in a pact
 /// file.
@@ -32,6 +33,31 @@ pub enum OptionalBody {
 
 impl OptionalBody {
 
+  /// Generate the JSON schema for a body for the given Pact specification. Before V4, the body
+  /// can be any JSON value.
+  pub fn schema(spec_version: PactSpecification) -> Value {
+    if spec_version < PactSpecification::V4 {
+      json!({})
+    } else {
+      json!({
+        "properties": {
+          "content": {},
+          "contentType": {
+            "type": "string"
+          },
+          "contentTypeHint": {
+            "enum": ["DEFAULT", "TEXT", "BINARY"]
+          },
+          "encoded": {
+            "type": ["boolean", "string"]
+          }
+        },
+        "required": ["content"],
+        "type": "object"
+      })
+    }
+  }
+
   /// If the body is present in the pact file and not empty or null.
   pub fn is_present(&self) -> bool {
     matches!(*self, OptionalBody::Present(_, _, _))
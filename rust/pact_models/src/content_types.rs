@@ -107,6 +107,19 @@ impl ContentType {
     (self.main_type == "application" || self.main_type == "text") && self.sub_type == "xml"
   }
 
+  /// If it is a CSV or TSV type
+  pub fn is_csv(&self) -> bool {
+    (self.main_type == "text" || self.main_type == "application") &&
+      (self.sub_type == "csv" || self.sub_type == "tab-separated-values")
+  }
+
+  /// If it is a protobuf or gRPC type
+  pub fn is_protobuf(&self) -> bool {
+    self.main_type == "application" && (
+      ["protobuf", "x-protobuf", "vnd.google.protobuf", "grpc"].contains(&self.sub_type.as_str()) ||
+      self.suffix.as_ref().map(|suffix| suffix == "proto" || suffix == "protobuf").unwrap_or(false))
+  }
+
   /// If it is a text type
   pub fn is_text(&self) -> bool {
     self.main_type == "text" || self.is_xml() || self.is_json() || self.is_known_text_type()
@@ -405,6 +418,23 @@ mod tests {
     expect!(content_type.to_string()).to(be_equal_to("application/hal+json;b=c;charset=UTF-32".to_string()));
   }
 
+  #[test]
+  fn is_csv_test() {
+    expect!(ContentType::parse("text/csv").unwrap().is_csv()).to(be_true());
+    expect!(ContentType::parse("text/csv; header=present").unwrap().is_csv()).to(be_true());
+    expect!(ContentType::parse("text/tab-separated-values").unwrap().is_csv()).to(be_true());
+    expect!(ContentType::parse("text/plain").unwrap().is_csv()).to(be_false());
+  }
+
+  #[test]
+  fn is_protobuf_test() {
+    expect!(ContentType::parse("application/protobuf; message=.routeguide.Feature").unwrap().is_protobuf()).to(be_true());
+    expect!(ContentType::parse("application/x-protobuf").unwrap().is_protobuf()).to(be_true());
+    expect!(ContentType::parse("application/grpc").unwrap().is_protobuf()).to(be_true());
+    expect!(ContentType::parse("application/grpc+proto").unwrap().is_protobuf()).to(be_true());
+    expect!(ContentType::parse("application/octet-stream").unwrap().is_protobuf()).to(be_false());
+  }
+
   #[test]
   fn is_json_test() {
     let content_type = ContentType {
@@ -0,0 +1,242 @@
+//! Functions for dealing with CSV (and TSV) documents. Documents with a header row are converted to
+//! a JSON array of objects keyed by the column names, so that cells can be addressed by paths like
+//! `$[*].amount` or `$[0].name`. Documents without a header row are converted to an array of arrays.
+
+use anyhow::anyhow;
+use serde_json::{Map, Value};
+
+use crate::content_types::ContentType;
+use crate::json_utils::json_to_string;
+
+/// Path of the matching rule for the header row. An equality matcher on this path requires the
+/// columns to be in the same order.
+pub const COLUMNS_PATH: &str = "$.columns";
+
+/// Parsed CSV document
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CsvDocument {
+  /// Column names from the header row, if the document has one
+  pub columns: Option<Vec<String>>,
+  /// Data rows
+  pub rows: Vec<Vec<String>>
+}
+
+impl CsvDocument {
+  /// Parses the text as a CSV document, using the given delimiter. Fields can be quoted with double
+  /// quotes, and quotes in quoted fields are escaped by doubling them.
+  pub fn parse(text: &str, delimiter: char, has_header: bool) -> anyhow::Result<CsvDocument> {
+    let mut rows = parse_records(text, delimiter)?;
+    let columns = if has_header && !rows.is_empty() {
+      Some(rows.remove(0))
+    } else if has_header {
+      Some(vec![])
+    } else {
+      None
+    };
+    Ok(CsvDocument { columns, rows })
+  }
+
+  /// Converts the document to JSON. Rows are converted to objects keyed by the column names if the
+  /// document has a header row, otherwise to arrays of values.
+  pub fn to_json(&self) -> Value {
+    Value::Array(self.rows.iter().map(|row| match &self.columns {
+      Some(columns) => {
+        let mut map = Map::new();
+        for (index, column) in columns.iter().enumerate() {
+          map.insert(column.clone(), Value::String(row.get(index).cloned().unwrap_or_default()));
+        }
+        Value::Object(map)
+      }
+      None => Value::Array(row.iter().map(|cell| Value::String(cell.clone())).collect())
+    }).collect())
+  }
+
+  /// Converts a JSON array of rows back into a document. Object rows are written using the given
+  /// columns, with any other keys added as extra columns in the order they are found.
+  pub fn from_json(value: &Value, columns: Option<&Vec<String>>) -> anyhow::Result<CsvDocument> {
+    let rows = value.as_array()
+      .ok_or_else(|| anyhow!("Expected a JSON array of rows, got {}", value))?;
+    match columns {
+      Some(columns) => {
+        let mut columns = columns.clone();
+        for row in rows {
+          if let Some(map) = row.as_object() {
+            for key in map.keys() {
+              if !columns.contains(key) {
+                columns.push(key.clone());
+              }
+            }
+          }
+        }
+        let rows = rows.iter()
+          .map(|row| columns.iter()
+            .map(|column| row.get(column).map(cell_value).unwrap_or_default())
+            .collect())
+          .collect();
+        Ok(CsvDocument { columns: Some(columns), rows })
+      }
+      None => {
+        let rows = rows.iter()
+          .map(|row| row.as_array().map(|cells| cells.iter().map(cell_value).collect()).unwrap_or_default())
+          .collect();
+        Ok(CsvDocument { columns: None, rows })
+      }
+    }
+  }
+
+  /// Writes the document as CSV text, using the given delimiter. Fields are quoted if required.
+  pub fn write(&self, delimiter: char) -> String {
+    let mut buffer = String::new();
+    for record in self.columns.iter().chain(self.rows.iter()) {
+      let fields: Vec<String> = record.iter().map(|field| quote_field(field, delimiter)).collect();
+      buffer.push_str(&fields.join(&delimiter.to_string()));
+      buffer.push('\n');
+    }
+    buffer
+  }
+}
+
+fn cell_value(value: &Value) -> String {
+  match value {
+    Value::Null => String::default(),
+    _ => json_to_string(value)
+  }
+}
+
+fn quote_field(field: &str, delimiter: char) -> String {
+  if field.contains(delimiter) || field.contains('"') || field.contains('\n') || field.contains('\r') {
+    format!("\"{}\"", field.replace('"', "\"\""))
+  } else {
+    field.to_string()
+  }
+}
+
+fn parse_records(text: &str, delimiter: char) -> anyhow::Result<Vec<Vec<String>>> {
+  let mut records = vec![];
+  let mut record = vec![];
+  let mut field = String::new();
+  let mut in_quotes = false;
+  let mut field_started = false;
+  let mut chars = text.chars().peekable();
+  let mut line = 1;
+
+  while let Some(ch) = chars.next() {
+    if in_quotes {
+      match ch {
+        '"' if chars.peek() == Some(&'"') => {
+          chars.next();
+          field.push('"');
+        }
+        '"' => in_quotes = false,
+        _ => {
+          if ch == '\n' {
+            line += 1;
+          }
+          field.push(ch);
+        }
+      }
+    } else if ch == '"' && !field_started {
+      in_quotes = true;
+      field_started = true;
+    } else if ch == delimiter {
+      record.push(std::mem::take(&mut field));
+      field_started = false;
+    } else if ch == '\n' || ch == '\r' {
+      if ch == '\r' && chars.peek() == Some(&'\n') {
+        chars.next();
+      }
+      record.push(std::mem::take(&mut field));
+      records.push(std::mem::take(&mut record));
+      field_started = false;
+      line += 1;
+    } else if ch == '"' {
+      return Err(anyhow!("Unexpected quote in unquoted field on line {}", line));
+    } else {
+      field.push(ch);
+      field_started = true;
+    }
+  }
+
+  if in_quotes {
+    return Err(anyhow!("Unterminated quoted field on line {}", line));
+  }
+  if field_started || !record.is_empty() {
+    record.push(field);
+    records.push(record);
+  }
+  Ok(records)
+}
+
+/// Returns the field delimiter for the content type (tab for `text/tab-separated-values`,
+/// otherwise a comma)
+pub fn csv_delimiter(content_type: &ContentType) -> char {
+  if content_type.sub_type == "tab-separated-values" {
+    '\t'
+  } else {
+    ','
+  }
+}
+
+/// If CSV documents with the content type have a header row. This is true unless the `header`
+/// parameter of the content type is `absent` (see RFC 4180).
+pub fn csv_has_header(content_type: &ContentType) -> bool {
+  content_type.attributes.get("header")
+    .map(|header| !header.eq_ignore_ascii_case("absent"))
+    .unwrap_or(true)
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use super::*;
+
+  #[test]
+  fn parse_csv_with_header() {
+    let doc = CsvDocument::parse("name,amount,note\r\nFred,10.50,\"says \"\"hi\"\", twice\"\nMary,3,\n", ',', true).unwrap();
+    expect!(doc.columns.clone()).to(be_some().value(vec!["name".to_string(), "amount".to_string(), "note".to_string()]));
+    expect!(doc.rows.clone()).to(be_equal_to(vec![
+      vec!["Fred".to_string(), "10.50".to_string(), "says \"hi\", twice".to_string()],
+      vec!["Mary".to_string(), "3".to_string(), "".to_string()]
+    ]));
+    expect!(doc.to_json()).to(be_equal_to(json!([
+      { "name": "Fred", "amount": "10.50", "note": "says \"hi\", twice" },
+      { "name": "Mary", "amount": "3", "note": "" }
+    ])));
+    expect!(doc.write(',')).to(be_equal_to("name,amount,note\nFred,10.50,\"says \"\"hi\"\", twice\"\nMary,3,\n"));
+  }
+
+  #[test]
+  fn parse_tsv_without_header() {
+    let doc = CsvDocument::parse("a\t1\nb\t2", '\t', false).unwrap();
+    expect!(doc.columns.clone()).to(be_none());
+    expect!(doc.to_json()).to(be_equal_to(json!([["a", "1"], ["b", "2"]])));
+  }
+
+  #[test]
+  fn parse_invalid_csv() {
+    expect!(CsvDocument::parse("a,\"b\nc", ',', true)).to(be_err());
+    expect!(CsvDocument::parse("a,b\"c\"", ',', true)).to(be_err());
+  }
+
+  #[test]
+  fn csv_from_json() {
+    let columns = vec!["name".to_string(), "amount".to_string()];
+    let doc = CsvDocument::from_json(&json!([
+      { "amount": 10, "name": "Fred" },
+      { "name": "Mary", "amount": "3", "extra": true }
+    ]), Some(&columns)).unwrap();
+    expect!(doc.write(',')).to(be_equal_to("name,amount,extra\nFred,10,\nMary,3,true\n"));
+  }
+
+  #[test]
+  fn csv_content_type_parameters() {
+    let csv = ContentType::parse("text/csv; header=absent").unwrap();
+    expect!(csv_delimiter(&csv)).to(be_equal_to(','));
+    expect!(csv_has_header(&csv)).to(be_false());
+    let tsv = ContentType::parse("text/tab-separated-values").unwrap();
+    expect!(csv_delimiter(&tsv)).to(be_equal_to('\t'));
+    expect!(csv_has_header(&tsv)).to(be_true());
+  }
+}
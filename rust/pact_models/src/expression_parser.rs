@@ -382,7 +382,7 @@ fn replace_expressions(value: &str, value_resolver: &dyn ValueResolver<Value>) -
     if index > 0 {
       result.push(json!(&buffer[0..index]));
     }
-    let end_position = buffer.find('}')
+    let end_position = buffer[index..].find('}').map(|end| index + end)
       .ok_or_else(|| anyhow!("Missing closing brace in expression string '{}'", value))?;
     if end_position - index > 2 {
       let lookup_key = &buffer[(index + 2)..end_position];
@@ -467,6 +467,7 @@ mod tests {
     expect!(parse_expression(" ${value} ", &resolver)).to(be_ok().value(" [value] ".to_string()));
     expect!(parse_expression(" ${value} ${value} ", &resolver)).to(be_ok().value(" [value] [value] ".to_string()));
     expect!(parse_expression("$${value}}", &resolver)).to(be_ok().value("$[value]}".to_string()));
+    expect!(parse_expression("{} ${value}", &resolver)).to(be_ok().value("{} [value]".to_string()));
   }
 
   #[test]
@@ -29,6 +29,7 @@ use crate::json_utils::{get_field_as_string, json_to_string, JsonToNum};
 use crate::matchingrules::{Category, MatchingRuleCategory};
 use crate::PactSpecification;
 use crate::path_exp::{DocPath, PathToken};
+use crate::path_template::segment_index;
 #[cfg(feature = "datetime")] use crate::time_utils::{parse_pattern, to_chrono_pattern};
 
 #[cfg(feature = "datetime")] pub mod datetime_expressions;
@@ -147,6 +148,62 @@ pub enum Generator {
 }
 
 impl Generator {
+  /// Generate the JSON schema for a generator for the given Pact specification
+  pub fn schema(spec_version: PactSpecification) -> Value {
+    let mut generators = vec![
+      "RandomInt", "Uuid", "RandomDecimal", "RandomHexadecimal", "RandomString", "Regex", "Date",
+      "Time", "DateTime", "RandomBoolean"
+    ];
+    if spec_version >= PactSpecification::V4 {
+      generators.extend_from_slice(&["ProviderState", "MockServerURL"]);
+    }
+    json!({
+      "properties": {
+        "type": {
+          "enum": generators
+        },
+        "min": {
+          "type": "integer"
+        },
+        "max": {
+          "type": "integer"
+        },
+        "digits": {
+          "minimum": 0,
+          "type": "integer"
+        },
+        "size": {
+          "minimum": 0,
+          "type": "integer"
+        },
+        "regex": {
+          "type": "string"
+        },
+        "format": {
+          "type": "string"
+        },
+        "expression": {
+          "type": "string"
+        },
+        "example": {
+          "type": "string"
+        }
+      },
+      "required": ["type"],
+      "allOf": [
+        {
+          "if": { "properties": { "type": { "const": "Regex" } } },
+          "then": { "required": ["regex"] }
+        },
+        {
+          "if": { "properties": { "type": { "const": "ProviderState" } } },
+          "then": { "required": ["expression"] }
+        }
+      ],
+      "type": "object"
+    })
+  }
+
   /// Convert this generator to a JSON struct
   pub fn to_json(&self) -> Option<Value> {
     match self {
@@ -512,6 +569,26 @@ pub struct Generators {
 }
 
 impl Generators {
+  /// Generate the JSON schema for the generators for the given Pact specification. The schema
+  /// for the individual generators is referenced from the `generator` definition.
+  pub fn schema(_spec_version: PactSpecification) -> Value {
+    let generator = json!({
+      "$ref": "#/definitions/generator"
+    });
+    json!({
+      "properties": {
+        "method": generator,
+        "path": generator,
+        "status": generator
+      },
+      "additionalProperties": {
+        "additionalProperties": generator,
+        "type": "object"
+      },
+      "type": "object"
+    })
+  }
+
   /// If the generators are empty (that is there are no rules assigned to any categories)
   pub fn is_empty(&self) -> bool {
     self.categories.values().all(|category| category.is_empty())
@@ -529,13 +606,20 @@ impl Generators {
       match v {
         Value::Object(map) => match &GeneratorCategory::from_str(k) {
           Ok(category) => match category {
+            GeneratorCategory::PATH if !map.contains_key("type") => for (sub_k, sub_v) in map {
+              // Generators for the individual path segments
+              match sub_v {
+                Value::Object(map) => self.parse_generator_from_map(category, map, Some(DocPath::new(sub_k)?)),
+                _ => warn!("Ignoring invalid generator JSON '{}' -> {:?}", sub_k, sub_v)
+              }
+            },
             GeneratorCategory::PATH | GeneratorCategory::METHOD | GeneratorCategory::STATUS => {
               self.parse_generator_from_map(category, map, None);
             },
             _ => for (sub_k, sub_v) in map {
               match sub_v {
                 &Value::Object(ref map) => {
-                  if *category == GeneratorCategory::QUERY || *category == GeneratorCategory::HEADER {
+                  if (*category == GeneratorCategory::QUERY || *category == GeneratorCategory::HEADER) && !sub_k.starts_with('$') {
                     self.parse_generator_from_map(category, map, Some(DocPath::root().push_field(sub_k).clone()));
                   } else {
                     self.parse_generator_from_map(category, map, Some(DocPath::new(sub_k)?));
@@ -579,6 +663,15 @@ impl Generators {
       .fold(serde_json::Map::new(), |mut map, (name, category)| {
       let cat: String = name.clone().into();
       match name {
+        GeneratorCategory::PATH if category.keys().any(|path| segment_index(path).is_some()) => {
+          let mut generators = serde_json::Map::new();
+          for (key, val) in category {
+            if let Some(json) = val.to_json() {
+              generators.insert(String::from(key), json);
+            }
+          }
+          map.insert(cat.clone(), Value::Object(generators));
+        },
         GeneratorCategory::PATH | GeneratorCategory::METHOD | GeneratorCategory::STATUS => {
           match category.get(&DocPath::empty()).or_else(|| category.get(&DocPath::root())) {
             Some(generator) => {
@@ -595,8 +688,13 @@ impl Generators {
           for (key, val) in category {
             let json = val.to_json();
             if let Some(json) = json {
-              let name = key.first_field().map(|v| v.to_string())
-                .unwrap_or_else(|| key.to_string());
+              let name = if key.len() > 2 {
+                // Generators for nested query parameter values are keyed by the full path
+                String::from(key)
+              } else {
+                key.first_field().map(|v| v.to_string())
+                  .unwrap_or_else(|| key.to_string())
+              };
               generators.insert(name, json);
             }
           }
@@ -1284,6 +1382,18 @@ impl ContentTypeHandler<Value> for JsonHandler {
     matcher: &Box<dyn VariantMatcher + Send + Sync>,
   ) {
     let path_exp = key;
+    if path_exp.has_recursive_descent() || path_exp.has_filters() {
+      for path in path_exp.select_paths(&self.value) {
+        if let Some(json_value) = path.as_json_pointer().ok()
+          .and_then(|pointer| self.value.pointer_mut(&pointer)) {
+          if let Ok(new_value) = generator.generate_value(&json_value.clone(), context, matcher) {
+            *json_value = new_value;
+          }
+        }
+      }
+      return;
+    }
+
     let mut tree = Arena::new();
     let root = tree.new_node("".into());
     self.query_object_graph(path_exp.tokens(), &mut tree, root, self.value.clone());
@@ -2242,6 +2352,28 @@ mod tests {
     expect!(&json_handler.value["a"][1]["b"]["2"]).to_not(be_equal_to(&json!("2")));
   }
 
+  #[test]
+  fn applies_the_generator_with_recursive_descent_and_filters() {
+    let value = json!({
+      "id": "1",
+      "items": [
+        { "id": "2", "type": "sale", "amount": 100 },
+        { "id": "3", "type": "refund", "amount": 10 }
+      ]
+    });
+    let mut json_handler = JsonHandler { value };
+
+    json_handler.apply_key(&DocPath::new_unwrap("$..id"), &Generator::RandomInt(10, 20), &hashmap!{}, &NoopVariantMatcher.boxed());
+    json_handler.apply_key(&DocPath::new_unwrap("$.items[?(@.type == 'refund')].amount"),
+      &Generator::RandomInt(1000, 2000), &hashmap!{}, &NoopVariantMatcher.boxed());
+
+    expect!(&json_handler.value["id"]).to_not(be_equal_to(&json!("1")));
+    expect!(&json_handler.value["items"][0]["id"]).to_not(be_equal_to(&json!("2")));
+    expect!(&json_handler.value["items"][1]["id"]).to_not(be_equal_to(&json!("3")));
+    expect!(&json_handler.value["items"][0]["amount"]).to(be_equal_to(&json!(100)));
+    expect!(json_handler.value["items"][1]["amount"].as_i64().unwrap() >= 1000).to(be_true());
+  }
+
   #[test]
   fn does_not_apply_the_generator_to_the_object_graph_when_the_expression_does_not_match() {
     let value = json!({
@@ -0,0 +1,678 @@
+//! Support for GraphQL requests and responses. GraphQL operation documents are normalised so they
+//! can be compared semantically: insignificant whitespace, commas and comments are removed, the
+//! selections, arguments and variable definitions are sorted, fragment spreads are replaced with
+//! inline fragments, and duplicate selections are merged. JSON bodies are only treated as GraphQL
+//! requests or responses if they are marked as GraphQL with a matching rule under `GRAPHQL_PATH`.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::iter::Peekable;
+use std::str::Chars;
+
+use anyhow::anyhow;
+use itertools::Itertools;
+use serde_json::Value;
+
+use crate::content_types::ContentType;
+use crate::matchingrules::{MatchingRule, MatchingRuleCategory, RuleLogic};
+use crate::path_exp::DocPath;
+
+/// Keys that can appear in a GraphQL request body
+const REQUEST_KEYS: [&str; 4] = ["query", "variables", "operationName", "extensions"];
+/// Keys that can appear in a GraphQL response body
+const RESPONSE_KEYS: [&str; 3] = ["data", "errors", "extensions"];
+
+/// Path of the matching rule that marks a JSON body as a GraphQL request or response
+pub const GRAPHQL_PATH: &str = "$['@graphql']";
+
+/// Content type of GraphQL responses (from the GraphQL over HTTP specification)
+pub const GRAPHQL_RESPONSE_CONTENT_TYPE: &str = "application/graphql-response+json";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+  Punctuator(char),
+  Spread,
+  Name(String),
+  Number(String),
+  Str(String)
+}
+
+fn tokenise(document: &str) -> anyhow::Result<Vec<Token>> {
+  let mut tokens = vec![];
+  let mut chars = document.chars().peekable();
+  while let Some(ch) = chars.next() {
+    match ch {
+      ' ' | '\t' | '\n' | '\r' | ',' | '\u{feff}' => {}
+      '#' => {
+        while let Some(ch) = chars.peek() {
+          if *ch == '\n' || *ch == '\r' {
+            break;
+          }
+          chars.next();
+        }
+      }
+      '!' | '$' | '&' | '(' | ')' | ':' | '=' | '@' | '[' | ']' | '{' | '|' | '}' => tokens.push(Token::Punctuator(ch)),
+      '.' => {
+        if chars.next() == Some('.') && chars.next() == Some('.') {
+          tokens.push(Token::Spread);
+        } else {
+          return Err(anyhow!("Expected '...'"));
+        }
+      }
+      '"' => tokens.push(Token::Str(read_string(&mut chars)?)),
+      '-' | '0'..='9' => {
+        let mut number = ch.to_string();
+        while let Some(ch) = chars.peek() {
+          if ch.is_ascii_alphanumeric() || *ch == '.' || *ch == '-' || *ch == '+' {
+            number.push(*ch);
+            chars.next();
+          } else {
+            break;
+          }
+        }
+        tokens.push(Token::Number(number));
+      }
+      _ if ch == '_' || ch.is_ascii_alphabetic() => {
+        let mut name = ch.to_string();
+        while let Some(ch) = chars.peek() {
+          if *ch == '_' || ch.is_ascii_alphanumeric() {
+            name.push(*ch);
+            chars.next();
+          } else {
+            break;
+          }
+        }
+        tokens.push(Token::Name(name));
+      }
+      _ => return Err(anyhow!("Unexpected character '{}'", ch))
+    }
+  }
+  Ok(tokens)
+}
+
+fn read_string(chars: &mut Peekable<Chars>) -> anyhow::Result<String> {
+  if chars.peek() == Some(&'"') {
+    chars.next();
+    if chars.peek() == Some(&'"') {
+      chars.next();
+      return read_block_string(chars);
+    }
+    return Ok(String::default());
+  }
+
+  let mut value = String::new();
+  loop {
+    match chars.next() {
+      Some('"') => return Ok(value),
+      Some('\\') => match chars.next() {
+        Some('n') => value.push('\n'),
+        Some('r') => value.push('\r'),
+        Some('t') => value.push('\t'),
+        Some('b') => value.push('\u{8}'),
+        Some('f') => value.push('\u{c}'),
+        Some('u') => {
+          let code: String = chars.by_ref().take(4).collect();
+          let ch = u32::from_str_radix(&code, 16).ok().and_then(char::from_u32)
+            .ok_or_else(|| anyhow!("Invalid unicode escape '\\u{}'", code))?;
+          value.push(ch);
+        }
+        Some(ch) => value.push(ch),
+        None => return Err(anyhow!("Unterminated string"))
+      },
+      Some('\n') | Some('\r') | None => return Err(anyhow!("Unterminated string")),
+      Some(ch) => value.push(ch)
+    }
+  }
+}
+
+fn read_block_string(chars: &mut Peekable<Chars>) -> anyhow::Result<String> {
+  let mut raw = String::new();
+  loop {
+    match chars.next() {
+      Some('"') if chars.peek() == Some(&'"') => {
+        chars.next();
+        if chars.peek() == Some(&'"') {
+          chars.next();
+          break;
+        }
+        raw.push_str("\"\"");
+      }
+      Some('\\') if chars.peek() == Some(&'"') => {
+        let escaped: String = chars.clone().take(3).collect();
+        if escaped == "\"\"\"" {
+          chars.nth(2);
+          raw.push_str("\"\"\"");
+        } else {
+          raw.push('\\');
+        }
+      }
+      Some(ch) => raw.push(ch),
+      None => return Err(anyhow!("Unterminated block string"))
+    }
+  }
+
+  // Remove the common indentation and any leading and trailing blank lines
+  let lines = raw.lines().collect_vec();
+  let indent = lines.iter().skip(1)
+    .filter(|line| !line.trim().is_empty())
+    .map(|line| line.len() - line.trim_start().len())
+    .min()
+    .unwrap_or(0);
+  let lines = lines.iter().enumerate()
+    .map(|(index, line)| if index == 0 { line.to_string() } else { line.chars().skip(indent).collect() })
+    .collect_vec();
+  Ok(lines.iter()
+    .skip_while(|line| line.trim().is_empty())
+    .join("\n")
+    .trim_end()
+    .to_string())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Selection {
+  Field {
+    alias: Option<String>,
+    name: String,
+    arguments: String,
+    directives: String,
+    selections: Vec<Selection>
+  },
+  FragmentSpread {
+    name: String,
+    directives: String
+  },
+  InlineFragment {
+    type_condition: Option<String>,
+    directives: String,
+    selections: Vec<Selection>
+  }
+}
+
+#[derive(Debug, Clone)]
+struct Operation {
+  operation_type: String,
+  name: Option<String>,
+  variables: String,
+  directives: String,
+  selections: Vec<Selection>
+}
+
+#[derive(Debug, Clone)]
+struct Fragment {
+  type_condition: String,
+  directives: String,
+  selections: Vec<Selection>
+}
+
+struct Parser {
+  tokens: Vec<Token>,
+  position: usize
+}
+
+impl Parser {
+  fn peek(&self) -> Option<&Token> {
+    self.tokens.get(self.position)
+  }
+
+  fn next(&mut self) -> anyhow::Result<Token> {
+    let token = self.tokens.get(self.position).cloned()
+      .ok_or_else(|| anyhow!("Unexpected end of document"))?;
+    self.position += 1;
+    Ok(token)
+  }
+
+  fn is_punctuator(&self, ch: char) -> bool {
+    self.peek() == Some(&Token::Punctuator(ch))
+  }
+
+  fn expect_punctuator(&mut self, ch: char) -> anyhow::Result<()> {
+    match self.next()? {
+      Token::Punctuator(p) if p == ch => Ok(()),
+      token => Err(anyhow!("Expected '{}' but found {:?}", ch, token))
+    }
+  }
+
+  fn name(&mut self) -> anyhow::Result<String> {
+    match self.next()? {
+      Token::Name(name) => Ok(name),
+      token => Err(anyhow!("Expected a name but found {:?}", token))
+    }
+  }
+
+  fn document(&mut self) -> anyhow::Result<(Vec<Operation>, HashMap<String, Fragment>)> {
+    let mut operations = vec![];
+    let mut fragments = HashMap::new();
+    while let Some(token) = self.peek() {
+      match token {
+        Token::Punctuator('{') => operations.push(Operation {
+          operation_type: "query".to_string(),
+          name: None,
+          variables: String::default(),
+          directives: String::default(),
+          selections: self.selection_set()?
+        }),
+        Token::Name(name) if name == "fragment" => {
+          self.next()?;
+          let name = self.name()?;
+          let on = self.name()?;
+          if on != "on" {
+            return Err(anyhow!("Expected 'on' but found '{}'", on));
+          }
+          let type_condition = self.name()?;
+          let directives = self.directives()?;
+          fragments.insert(name, Fragment { type_condition, directives, selections: self.selection_set()? });
+        }
+        Token::Name(name) if name == "query" || name == "mutation" || name == "subscription" => {
+          let operation_type = name.clone();
+          self.next()?;
+          let name = match self.peek() {
+            Some(Token::Name(_)) => Some(self.name()?),
+            _ => None
+          };
+          let variables = self.variable_definitions()?;
+          let directives = self.directives()?;
+          operations.push(Operation { operation_type, name, variables, directives, selections: self.selection_set()? });
+        }
+        token => return Err(anyhow!("Expected an operation or fragment definition but found {:?}", token))
+      }
+    }
+    Ok((operations, fragments))
+  }
+
+  fn variable_definitions(&mut self) -> anyhow::Result<String> {
+    if !self.is_punctuator('(') {
+      return Ok(String::default());
+    }
+    self.next()?;
+    let mut definitions = vec![];
+    while !self.is_punctuator(')') {
+      self.expect_punctuator('$')?;
+      let name = self.name()?;
+      self.expect_punctuator(':')?;
+      let mut definition = format!("${}:{}", name, self.type_reference()?);
+      if self.is_punctuator('=') {
+        self.next()?;
+        definition.push('=');
+        definition.push_str(&self.value()?);
+      }
+      definition.push_str(&self.directives()?);
+      definitions.push(definition);
+    }
+    self.next()?;
+    definitions.sort();
+    Ok(format!("({})", definitions.join(",")))
+  }
+
+  fn type_reference(&mut self) -> anyhow::Result<String> {
+    let mut type_ref = if self.is_punctuator('[') {
+      self.next()?;
+      let inner = self.type_reference()?;
+      self.expect_punctuator(']')?;
+      format!("[{}]", inner)
+    } else {
+      self.name()?
+    };
+    if self.is_punctuator('!') {
+      self.next()?;
+      type_ref.push('!');
+    }
+    Ok(type_ref)
+  }
+
+  fn directives(&mut self) -> anyhow::Result<String> {
+    let mut directives = String::new();
+    while self.is_punctuator('@') {
+      self.next()?;
+      directives.push('@');
+      directives.push_str(&self.name()?);
+      directives.push_str(&self.arguments()?);
+    }
+    Ok(directives)
+  }
+
+  fn arguments(&mut self) -> anyhow::Result<String> {
+    if !self.is_punctuator('(') {
+      return Ok(String::default());
+    }
+    self.next()?;
+    let mut arguments = BTreeMap::new();
+    while !self.is_punctuator(')') {
+      let name = self.name()?;
+      self.expect_punctuator(':')?;
+      arguments.insert(name, self.value()?);
+    }
+    self.next()?;
+    Ok(format!("({})", arguments.iter().map(|(name, value)| format!("{}:{}", name, value)).join(",")))
+  }
+
+  fn value(&mut self) -> anyhow::Result<String> {
+    match self.next()? {
+      Token::Punctuator('$') => Ok(format!("${}", self.name()?)),
+      Token::Number(number) => Ok(number),
+      Token::Str(value) => Ok(Value::String(value).to_string()),
+      Token::Name(name) => Ok(name),
+      Token::Punctuator('[') => {
+        let mut values = vec![];
+        while !self.is_punctuator(']') {
+          values.push(self.value()?);
+        }
+        self.next()?;
+        Ok(format!("[{}]", values.join(",")))
+      }
+      Token::Punctuator('{') => {
+        let mut fields = BTreeMap::new();
+        while !self.is_punctuator('}') {
+          let name = self.name()?;
+          self.expect_punctuator(':')?;
+          fields.insert(name, self.value()?);
+        }
+        self.next()?;
+        Ok(format!("{{{}}}", fields.iter().map(|(name, value)| format!("{}:{}", name, value)).join(",")))
+      }
+      token => Err(anyhow!("Expected a value but found {:?}", token))
+    }
+  }
+
+  fn selection_set(&mut self) -> anyhow::Result<Vec<Selection>> {
+    self.expect_punctuator('{')?;
+    let mut selections = vec![];
+    while !self.is_punctuator('}') {
+      selections.push(self.selection()?);
+    }
+    self.next()?;
+    if selections.is_empty() {
+      Err(anyhow!("Selection sets can not be empty"))
+    } else {
+      Ok(selections)
+    }
+  }
+
+  fn selection(&mut self) -> anyhow::Result<Selection> {
+    if self.peek() == Some(&Token::Spread) {
+      self.next()?;
+      match self.peek() {
+        Some(Token::Name(name)) if name != "on" => {
+          let name = self.name()?;
+          Ok(Selection::FragmentSpread { name, directives: self.directives()? })
+        }
+        _ => {
+          let type_condition = match self.peek() {
+            Some(Token::Name(_)) => {
+              self.next()?;
+              Some(self.name()?)
+            }
+            _ => None
+          };
+          let directives = self.directives()?;
+          Ok(Selection::InlineFragment { type_condition, directives, selections: self.selection_set()? })
+        }
+      }
+    } else {
+      let mut name = self.name()?;
+      let mut alias = None;
+      if self.is_punctuator(':') {
+        self.next()?;
+        alias = Some(name);
+        name = self.name()?;
+      }
+      let arguments = self.arguments()?;
+      let directives = self.directives()?;
+      let selections = if self.is_punctuator('{') {
+        self.selection_set()?
+      } else {
+        vec![]
+      };
+      Ok(Selection::Field { alias: alias.filter(|alias| *alias != name), name, arguments, directives, selections })
+    }
+  }
+}
+
+/// Replaces fragment spreads with inline fragments, and merges duplicate selections
+fn expand_selections(
+  selections: &[Selection],
+  fragments: &HashMap<String, Fragment>,
+  visiting: &mut HashSet<String>
+) -> anyhow::Result<Vec<Selection>> {
+  let mut result: Vec<Selection> = vec![];
+  for selection in selections {
+    let expanded = match selection {
+      Selection::Field { alias, name, arguments, directives, selections } => Selection::Field {
+        alias: alias.clone(),
+        name: name.clone(),
+        arguments: arguments.clone(),
+        directives: directives.clone(),
+        selections: expand_selections(selections, fragments, visiting)?
+      },
+      Selection::FragmentSpread { name, directives } => {
+        let fragment = fragments.get(name)
+          .ok_or_else(|| anyhow!("Fragment '{}' is not defined", name))?;
+        if !visiting.insert(name.clone()) {
+          return Err(anyhow!("Fragment '{}' refers to itself", name));
+        }
+        let selections = expand_selections(&fragment.selections, fragments, visiting)?;
+        visiting.remove(name);
+        Selection::InlineFragment {
+          type_condition: Some(fragment.type_condition.clone()),
+          directives: format!("{}{}", directives, fragment.directives),
+          selections
+        }
+      }
+      Selection::InlineFragment { type_condition, directives, selections } => Selection::InlineFragment {
+        type_condition: type_condition.clone(),
+        directives: directives.clone(),
+        selections: expand_selections(selections, fragments, visiting)?
+      }
+    };
+    merge_selection(&mut result, expanded);
+  }
+  result.sort_by_cached_key(print_selection);
+  Ok(result)
+}
+
+fn merge_selection(selections: &mut Vec<Selection>, selection: Selection) {
+  for existing in selections.iter_mut() {
+    match (existing, &selection) {
+      (Selection::Field { alias, name, arguments, directives, selections },
+        Selection::Field { alias: alias2, name: name2, arguments: arguments2, directives: directives2, selections: selections2 })
+        if alias == alias2 && name == name2 && arguments == arguments2 && directives == directives2 => {
+        for selection in selections2 {
+          merge_selection(selections, selection.clone());
+        }
+        selections.sort_by_cached_key(print_selection);
+        return;
+      }
+      (Selection::InlineFragment { type_condition, directives, selections },
+        Selection::InlineFragment { type_condition: type_condition2, directives: directives2, selections: selections2 })
+        if type_condition == type_condition2 && directives == directives2 => {
+        for selection in selections2 {
+          merge_selection(selections, selection.clone());
+        }
+        selections.sort_by_cached_key(print_selection);
+        return;
+      }
+      _ => {}
+    }
+  }
+  selections.push(selection);
+}
+
+fn print_selection(selection: &Selection) -> String {
+  match selection {
+    Selection::Field { alias, name, arguments, directives, selections } => {
+      let alias = alias.as_ref().map(|alias| format!("{}:", alias)).unwrap_or_default();
+      format!("{}{}{}{}{}", alias, name, arguments, directives, print_selection_set(selections))
+    }
+    Selection::FragmentSpread { name, directives } => format!("...{}{}", name, directives),
+    Selection::InlineFragment { type_condition, directives, selections } => {
+      let type_condition = type_condition.as_ref().map(|t| format!(" on {}", t)).unwrap_or_default();
+      format!("...{}{}{}", type_condition, directives, print_selection_set(selections))
+    }
+  }
+}
+
+fn print_selection_set(selections: &[Selection]) -> String {
+  if selections.is_empty() {
+    String::default()
+  } else {
+    format!("{{{}}}", selections.iter().map(print_selection).join(" "))
+  }
+}
+
+/// Parses the GraphQL document and returns the normalised form of the operation. If the document
+/// has more than one operation, the operation name is used to select the operation. The name of
+/// the operation is not included in the normalised form.
+pub fn normalise_query(document: &str, operation_name: Option<&str>) -> anyhow::Result<String> {
+  let mut parser = Parser { tokens: tokenise(document)?, position: 0 };
+  let (operations, fragments) = parser.document()?;
+  let operation = match operation_name {
+    Some(name) => operations.iter().find(|op| op.name.as_deref() == Some(name))
+      .ok_or_else(|| anyhow!("Document does not have an operation named '{}'", name))?,
+    None => match operations.as_slice() {
+      [operation] => operation,
+      [] => return Err(anyhow!("Document does not have any operations")),
+      _ => return Err(anyhow!("Document has more than one operation, but no operation name was given"))
+    }
+  };
+  let selections = expand_selections(&operation.selections, &fragments, &mut HashSet::new())?;
+  Ok(format!("{}{}{}{}", operation.operation_type, operation.variables, operation.directives,
+    print_selection_set(&selections)))
+}
+
+/// Parses the GraphQL document, returning an error if it is not a valid executable document
+pub fn validate_document(document: &str) -> anyhow::Result<()> {
+  let mut parser = Parser { tokens: tokenise(document)?, position: 0 };
+  let (operations, fragments) = parser.document()?;
+  if operations.is_empty() {
+    return Err(anyhow!("Document does not have any operations"));
+  }
+  for operation in &operations {
+    expand_selections(&operation.selections, &fragments, &mut HashSet::new())?;
+  }
+  Ok(())
+}
+
+/// If the JSON body is a GraphQL request (an object with a `query` string that is a valid GraphQL
+/// document, and optionally `variables`, `operationName` and `extensions`)
+pub fn is_graphql_request(body: &Value) -> bool {
+  match body.as_object() {
+    Some(map) => map.keys().all(|key| REQUEST_KEYS.contains(&key.as_str())) &&
+      map.get("query").and_then(|query| query.as_str())
+        .map(|query| normalise_query(query, map.get("operationName").and_then(|name| name.as_str())).is_ok())
+        .unwrap_or(false),
+    None => false
+  }
+}
+
+/// If the JSON body is a GraphQL response (an object with `data` and optionally `errors` and
+/// `extensions`)
+pub fn is_graphql_response(body: &Value) -> bool {
+  match body.as_object() {
+    Some(map) => map.contains_key("data") && map.keys().all(|key| RESPONSE_KEYS.contains(&key.as_str())),
+    None => false
+  }
+}
+
+/// Marks the body matching rules as being for a GraphQL request or response
+pub fn mark_as_graphql(rules: &mut MatchingRuleCategory) {
+  if !is_marked_as_graphql(rules) {
+    rules.add_rule(DocPath::new_unwrap(GRAPHQL_PATH), MatchingRule::Type, RuleLogic::And);
+  }
+}
+
+/// If the body matching rules have been marked as being for a GraphQL request or response
+pub fn is_marked_as_graphql(rules: &MatchingRuleCategory) -> bool {
+  rules.rules.contains_key(&DocPath::new_unwrap(GRAPHQL_PATH))
+}
+
+/// If the content type is for a GraphQL response
+pub fn is_graphql_response_content_type(content_type: &ContentType) -> bool {
+  ContentType::parse(GRAPHQL_RESPONSE_CONTENT_TYPE)
+    .map(|graphql| graphql.is_equivalent_to(content_type))
+    .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use super::*;
+
+  #[test]
+  fn normalise_query_test() {
+    expect!(normalise_query("{ hero { name, id } }", None))
+      .to(be_ok().value("query{hero{id name}}"));
+    expect!(normalise_query(r#"
+      # Fetch the hero
+      query HeroQuery($episode: Episode = JEDI, $first: Int!) @cached(ttl: 60) {
+        hero(first: $first, episode: $episode) {
+          heroName: name
+          friends { name }
+          ...on Droid { primaryFunction }
+        }
+      }
+    "#, None)).to(be_ok().value(
+      "query($episode:Episode=JEDI,$first:Int!)@cached(ttl:60){hero(episode:$episode,first:$first){... on Droid{primaryFunction} friends{name} heroName:name}}"));
+  }
+
+  #[test]
+  fn normalised_queries_are_equal_when_the_operations_are_the_same() {
+    let q1 = r#"query GetUser { user(id: "1", active: true) { id name address { city } } }"#;
+    let q2 = r#"
+      query Other {
+        user(active: true, id: "1") {
+          ...UserFields
+          address { city }
+        }
+      }
+      fragment UserFields on User { name, id }
+    "#;
+    let q3 = r#"{ user(id: "1", active: true) { ... on User { id name } address { city } } }"#;
+    expect!(normalise_query(q2, None).unwrap()).to(be_equal_to(normalise_query(q3, None).unwrap()));
+    expect!(normalise_query(q1, None).unwrap()).to_not(be_equal_to(normalise_query(q2, None).unwrap()));
+    expect!(normalise_query("{ user { id } user { name } }", None).unwrap())
+      .to(be_equal_to(normalise_query("{ user { name id } }", None).unwrap()));
+  }
+
+  #[test]
+  fn normalise_query_with_multiple_operations() {
+    let document = "query A { a } mutation B { b(input: { z: 1, a: [\"x\", \"\\u0079\"] }) }";
+    expect!(normalise_query(document, Some("B"))).to(be_ok().value("mutation{b(input:{a:[\"x\",\"y\"],z:1})}"));
+    expect!(normalise_query(document, None)).to(be_err());
+    expect!(normalise_query(document, Some("C"))).to(be_err());
+  }
+
+  #[test]
+  fn normalise_invalid_queries() {
+    expect!(normalise_query("{ hero { name }", None)).to(be_err());
+    expect!(normalise_query("{ hero { ...Missing } }", None)).to(be_err());
+    expect!(normalise_query("{ hero { ...A } } fragment A on Hero { ...A }", None)).to(be_err());
+    expect!(normalise_query("not a query", None)).to(be_err());
+    expect!(validate_document("query A { a } query B { b }")).to(be_ok());
+    expect!(validate_document("query A { a } query B { ...C }")).to(be_err());
+    expect!(validate_document("fragment C on T { c }")).to(be_err());
+  }
+
+  #[test]
+  fn graphql_request_and_response_bodies() {
+    expect!(is_graphql_request(&json!({ "query": "{ hero { name } }", "variables": { "a": 1 } }))).to(be_true());
+    expect!(is_graphql_request(&json!({ "query": "{ hero { name } }", "other": 1 }))).to(be_false());
+    expect!(is_graphql_request(&json!({ "query": "select * from hero" }))).to(be_false());
+    expect!(is_graphql_response(&json!({ "data": { "hero": null }, "errors": [] }))).to(be_true());
+    expect!(is_graphql_response(&json!({ "data": [], "links": {} }))).to(be_false());
+  }
+
+  #[test]
+  fn mark_as_graphql_test() {
+    let mut rules = MatchingRuleCategory::empty("body");
+    expect!(is_marked_as_graphql(&rules)).to(be_false());
+    mark_as_graphql(&mut rules);
+    mark_as_graphql(&mut rules);
+    expect!(is_marked_as_graphql(&rules)).to(be_true());
+    expect!(rules.rules.len()).to(be_equal_to(1));
+    expect!(rules.rules.values().next().unwrap().rules.len()).to(be_equal_to(1));
+
+    expect!(is_graphql_response_content_type(&ContentType::parse("application/graphql-response+json; charset=utf-8").unwrap()))
+      .to(be_true());
+    expect!(is_graphql_response_content_type(&ContentType::parse("application/json").unwrap())).to(be_false());
+  }
+}
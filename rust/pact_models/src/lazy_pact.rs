@@ -0,0 +1,507 @@
+//! Lazy loading of Pact files.
+//!
+//! Loading a Pact with `read_pact` or `load_pact_from_json` parses all the interactions into the
+//! interaction models up front, which for very large Pact files (i.e. aggregated V4 Pacts with
+//! binary bodies) takes a lot of time and memory. `LazyPact` only loads the Pact attributes
+//! (consumer, provider, metadata and plugins) and keeps the raw JSON of each interaction along
+//! with an index of the interactions (key, ID, description and type). The interactions are then
+//! parsed when they are accessed.
+//!
+//! The JSON of the Pact file is still fully parsed, so this is not a streaming loader.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Debug, Formatter};
+#[cfg(not(target_family = "wasm"))] use std::fs::File;
+#[cfg(not(target_family = "wasm"))] use std::io::Read;
+use std::panic::RefUnwindSafe;
+#[cfg(not(target_family = "wasm"))] use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Context};
+use serde_json::{json, Value};
+use tracing::{error, trace};
+
+use crate::{Consumer, PactSpecification, Provider};
+#[cfg(not(target_family = "wasm"))] use crate::file_utils::with_read_lock_for_open_file;
+use crate::interaction::Interaction;
+use crate::message::Message;
+use crate::message_pact::MessagePact;
+use crate::pact::{InteractionRef, load_pact_from_json, Pact, ReadWritePact};
+use crate::plugins::PluginData;
+use crate::sync_interaction::RequestResponseInteraction;
+use crate::sync_pact::RequestResponsePact;
+use crate::v4::interaction::interaction_from_json;
+use crate::v4::pact::V4Pact;
+use crate::v4::V4InteractionType;
+
+/// Entry in the index of interactions of a `LazyPact`
+#[derive(Debug, Clone, PartialEq)]
+pub struct InteractionIndexEntry {
+  /// Index of the interaction in the Pact file
+  pub index: usize,
+  /// Interaction key (V4 only)
+  pub key: Option<String>,
+  /// Interaction ID (this is set when the Pact has been fetched from a Pact Broker)
+  pub id: Option<String>,
+  /// Interaction description
+  pub description: String,
+  /// Type of interaction
+  pub interaction_type: String,
+  /// If the interaction is marked as pending (V4 only)
+  pub pending: bool
+}
+
+impl InteractionIndexEntry {
+  fn from_json(
+    index: usize,
+    json: &Value,
+    spec_version: &PactSpecification,
+    is_message_pact: bool,
+    source: &str
+  ) -> anyhow::Result<InteractionIndexEntry> {
+    if !json.is_object() {
+      return Err(anyhow!("Interaction {} in Pact '{}' is not a JSON object", index, source));
+    }
+    let string_attr = |name: &str| json.get(name).and_then(Value::as_str).map(|s| s.to_string());
+    let interaction_type = match spec_version {
+      PactSpecification::V4 => string_attr("type")
+        .ok_or_else(|| anyhow!("Interaction {} in Pact '{}' has no type", index, source))?,
+      _ => if is_message_pact {
+        V4InteractionType::Asynchronous_Messages.to_string()
+      } else {
+        V4InteractionType::Synchronous_HTTP.to_string()
+      }
+    };
+    Ok(InteractionIndexEntry {
+      index,
+      key: string_attr("key"),
+      id: string_attr("_id"),
+      description: string_attr("description").unwrap_or_else(|| format!("Interaction {}", index)),
+      interaction_type,
+      pending: json.get("pending").and_then(Value::as_bool).unwrap_or_default()
+    })
+  }
+}
+
+/// Pact that has been indexed, with the interactions parsed when they are accessed. This can be
+/// used anywhere a `Pact` is expected, but as the interactions are not stored as models,
+/// `interactions_mut` will not return any interactions (use `load` to get a fully loaded Pact).
+pub struct LazyPact {
+  /// Source the Pact was loaded from
+  pub source: String,
+  /// Pact with all the attributes except the interactions
+  pact: Box<dyn Pact + Send + Sync + RefUnwindSafe>,
+  is_message_pact: bool,
+  interactions: Arc<Vec<Value>>,
+  index: Vec<InteractionIndexEntry>
+}
+
+impl Clone for LazyPact {
+  fn clone(&self) -> Self {
+    LazyPact {
+      source: self.source.clone(),
+      pact: self.pact.boxed(),
+      is_message_pact: self.is_message_pact,
+      interactions: self.interactions.clone(),
+      index: self.index.clone()
+    }
+  }
+}
+
+impl Debug for LazyPact {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("LazyPact")
+      .field("source", &self.source)
+      .field("consumer", &self.pact.consumer())
+      .field("provider", &self.pact.provider())
+      .field("specification_version", &self.pact.specification_version())
+      .field("interactions", &self.index.len())
+      .finish()
+  }
+}
+
+impl LazyPact {
+  /// Reads the Pact file at the given path and indexes the interactions
+  #[cfg(not(target_family = "wasm"))]
+  pub fn read(path: &Path) -> anyhow::Result<LazyPact> {
+    let mut f = File::open(path)?;
+    let buffer = with_read_lock_for_open_file(path, &mut f, 3, &mut |f| {
+      let mut buffer = vec![];
+      f.read_to_end(&mut buffer)?;
+      Ok(buffer)
+    })?;
+    LazyPact::from_bytes(&path.to_string_lossy(), &buffer)
+  }
+
+  /// Indexes the interactions from the raw bytes of a Pact file
+  pub fn from_bytes(source: &str, buffer: &[u8]) -> anyhow::Result<LazyPact> {
+    let json: Value = serde_json::from_slice(buffer)
+      .with_context(|| format!("Failed to parse Pact JSON from source '{}'", source))?;
+    LazyPact::from_json(source, &json)
+  }
+
+  /// Indexes the interactions from the JSON of a Pact file. The JSON of each interaction is kept,
+  /// and will only be parsed into an interaction when it is accessed.
+  pub fn from_json(source: &str, json: &Value) -> anyhow::Result<LazyPact> {
+    let map = json.as_object()
+      .ok_or_else(|| anyhow!("Failed to parse Pact JSON from source '{}' - it is not a valid pact file", source))?;
+
+    let is_message_pact = map.contains_key("messages") && !map.contains_key("interactions");
+    let attribute = if is_message_pact { "messages" } else { "interactions" };
+    let interactions = match map.get(attribute) {
+      Some(Value::Array(interactions)) => interactions.clone(),
+      Some(_) => return Err(anyhow!("Expecting '{}' field in Pact '{}' to be an Array", attribute, source)),
+      None => vec![]
+    };
+
+    // Load the Pact without the interactions to get the other attributes
+    let mut pact_json = map.clone();
+    pact_json.insert(attribute.to_string(), json!([]));
+    let pact = load_pact_from_json(source, &Value::Object(pact_json))?;
+    let specification_version = pact.specification_version();
+    trace!("LazyPact: found spec version {} in metadata", specification_version);
+    let is_message_pact = is_message_pact && specification_version != PactSpecification::V4;
+
+    let index = interactions.iter().enumerate()
+      .map(|(index, json)| InteractionIndexEntry::from_json(index, json, &specification_version,
+        is_message_pact, source))
+      .collect::<anyhow::Result<Vec<_>>>()?;
+
+    Ok(LazyPact {
+      source: source.to_string(),
+      pact,
+      is_message_pact,
+      interactions: Arc::new(interactions),
+      index
+    })
+  }
+
+  /// Index of the interactions in the Pact
+  pub fn index(&self) -> &[InteractionIndexEntry] {
+    &self.index
+  }
+
+  /// Finds the index entry for the interaction with the given key
+  pub fn find_by_key(&self, key: &str) -> Option<&InteractionIndexEntry> {
+    self.index.iter().find(|entry| entry.key.as_deref() == Some(key))
+  }
+
+  /// Finds the index entry for the interaction with the given ID
+  pub fn find_by_id(&self, id: &str) -> Option<&InteractionIndexEntry> {
+    self.index.iter().find(|entry| entry.id.as_deref() == Some(id))
+  }
+
+  /// Finds the index entries for the interactions with the given description
+  pub fn find_by_description<'a>(&'a self, description: &'a str) -> impl Iterator<Item = &'a InteractionIndexEntry> + 'a {
+    self.index.iter().filter(move |entry| entry.description == description)
+  }
+
+  /// Parses the interaction at the given index
+  pub fn interaction(&self, index: usize) -> anyhow::Result<Box<dyn Interaction + Send + Sync + RefUnwindSafe>> {
+    let json = self.interactions.get(index)
+      .ok_or_else(|| anyhow!("Pact '{}' has no interaction at index {}", self.source, index))?;
+    let specification_version = self.pact.specification_version();
+    match specification_version {
+      PactSpecification::V4 => interaction_from_json(&self.source, index, json)
+        .map(|interaction| interaction.boxed()),
+      _ => if self.is_message_pact {
+        Message::from_json(index, json, &specification_version)
+          .map(|message| message.boxed())
+      } else {
+        RequestResponseInteraction::from_json(index, json, &specification_version)
+          .map(|interaction| interaction.boxed())
+      }
+    }.with_context(|| format!("Failed to parse interaction {} in Pact '{}'", index, self.source))
+  }
+
+  /// Fully loads the Pact, parsing all the interactions
+  pub fn load(&self) -> anyhow::Result<Box<dyn Pact + Send + Sync + RefUnwindSafe>> {
+    let mut pact = self.pact.boxed();
+    for index in 0..self.interactions.len() {
+      pact.add_interaction(self.interaction(index)?.as_ref())?;
+    }
+    Ok(pact)
+  }
+
+  fn interaction_or_log_error(&self, index: usize) -> Option<Box<dyn Interaction + Send + Sync + RefUnwindSafe>> {
+    self.interaction(index)
+      .map_err(|err| error!("{:#}", err))
+      .ok()
+  }
+}
+
+impl ReadWritePact for LazyPact {
+  #[cfg(not(target_family = "wasm"))]
+  fn read_pact(path: &Path) -> anyhow::Result<LazyPact> {
+    LazyPact::read(path)
+  }
+
+  fn merge(&self, other: &dyn Pact) -> anyhow::Result<Box<dyn Pact + Send + Sync + RefUnwindSafe>> {
+    self.load()?.merge(other)
+  }
+
+  fn default_file_name(&self) -> String {
+    self.pact.default_file_name()
+  }
+}
+
+impl Pact for LazyPact {
+  fn consumer(&self) -> Consumer {
+    self.pact.consumer()
+  }
+
+  fn provider(&self) -> Provider {
+    self.pact.provider()
+  }
+
+  /// Parses all the interactions. Any that fail to parse are logged and skipped.
+  fn interactions(&self) -> Vec<Box<dyn Interaction + Send + Sync + RefUnwindSafe>> {
+    (0..self.interactions.len())
+      .filter_map(|index| self.interaction_or_log_error(index))
+      .collect()
+  }
+
+  /// Parses each interaction as it is reached. Any that fail to parse are logged and skipped.
+  fn iter_interactions(&self) -> Box<dyn Iterator<Item = InteractionRef<'_>> + '_> {
+    Box::new((0..self.interactions.len())
+      .filter_map(|index| self.interaction_or_log_error(index).map(InteractionRef::Owned)))
+  }
+
+  fn num_interactions(&self) -> usize {
+    self.interactions.len()
+  }
+
+  /// Parses the interaction at the given index. Returns `None` if it fails to parse.
+  fn interaction_at(&self, index: usize) -> Option<InteractionRef<'_>> {
+    self.interaction_or_log_error(index).map(InteractionRef::Owned)
+  }
+
+  fn interactions_mut(&mut self) -> Vec<&mut (dyn Interaction + Send + Sync)> {
+    vec![]
+  }
+
+  fn metadata(&self) -> BTreeMap<String, BTreeMap<String, String>> {
+    self.pact.metadata()
+  }
+
+  fn to_json(&self, pact_spec: PactSpecification) -> anyhow::Result<Value> {
+    self.load()?.to_json(pact_spec)
+  }
+
+  fn as_request_response_pact(&self) -> anyhow::Result<RequestResponsePact> {
+    self.load()?.as_request_response_pact()
+  }
+
+  fn as_message_pact(&self) -> anyhow::Result<MessagePact> {
+    self.load()?.as_message_pact()
+  }
+
+  fn as_v4_pact(&self) -> anyhow::Result<V4Pact> {
+    self.load()?.as_v4_pact()
+  }
+
+  fn specification_version(&self) -> PactSpecification {
+    self.pact.specification_version()
+  }
+
+  fn boxed(&self) -> Box<dyn Pact + Send + Sync + RefUnwindSafe> {
+    Box::new(self.clone())
+  }
+
+  fn arced(&self) -> Arc<dyn Pact + Send + Sync + RefUnwindSafe> {
+    Arc::new(self.clone())
+  }
+
+  fn thread_safe(&self) -> Arc<Mutex<dyn Pact + Send + Sync + RefUnwindSafe>> {
+    Arc::new(Mutex::new(self.clone()))
+  }
+
+  fn add_interaction(&mut self, interaction: &dyn Interaction) -> anyhow::Result<()> {
+    let specification_version = self.pact.specification_version();
+    let json = match specification_version {
+      PactSpecification::V4 => interaction.as_v4()
+        .map(|interaction| interaction.to_json())
+        .ok_or_else(|| anyhow!("Can only add interactions that can be converted to V4 to this Pact"))?,
+      _ => if self.is_message_pact {
+        interaction.as_message()
+          .map(|message| message.to_json(&specification_version))
+          .ok_or_else(|| anyhow!("Can only add message interactions to this Pact"))?
+      } else {
+        interaction.as_request_response()
+          .map(|interaction| interaction.to_json(&specification_version))
+          .ok_or_else(|| anyhow!("Can only add request/response interactions to this Pact"))?
+      }
+    };
+    let index = self.interactions.len();
+    self.index.push(InteractionIndexEntry::from_json(index, &json, &specification_version,
+      self.is_message_pact, &self.source)?);
+    Arc::make_mut(&mut self.interactions).push(json);
+    Ok(())
+  }
+
+  fn requires_plugins(&self) -> bool {
+    self.pact.requires_plugins()
+  }
+
+  fn plugin_data(&self) -> Vec<PluginData> {
+    self.pact.plugin_data()
+  }
+
+  fn is_v4(&self) -> bool {
+    self.pact.is_v4()
+  }
+
+  fn add_plugin(
+    &mut self,
+    name: &str,
+    version: &str,
+    plugin_data: Option<HashMap<String, Value>>
+  ) -> anyhow::Result<()> {
+    self.pact.add_plugin(name, version, plugin_data)
+  }
+
+  fn add_md_version(&mut self, key: &str, version: &str) {
+    self.pact.add_md_version(key, version)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::PactSpecification;
+  use crate::lazy_pact::LazyPact;
+  use crate::pact::Pact;
+  use crate::sync_interaction::RequestResponseInteraction;
+
+  fn v4_pact() -> String {
+    json!({
+      "consumer": { "name": "lazy-consumer" },
+      "provider": { "name": "lazy-provider" },
+      "interactions": [
+        {
+          "type": "Synchronous/HTTP",
+          "key": "001",
+          "description": "get a thing",
+          "request": { "method": "GET", "path": "/thing" },
+          "response": { "status": 200, "body": { "content": "AAECAwQ=", "contentType": "application/octet-stream", "encoded": "base64" } }
+        },
+        {
+          "type": "Asynchronous/Messages",
+          "key": "002",
+          "description": "a thing event",
+          "pending": true,
+          "contents": { "content": { "id": 100 } }
+        }
+      ],
+      "metadata": { "pactSpecification": { "version": "4.0" } }
+    }).to_string()
+  }
+
+  #[test]
+  fn indexes_v4_interactions() {
+    let pact = LazyPact::from_bytes("test", v4_pact().as_bytes()).unwrap();
+    expect!(pact.consumer().name).to(be_equal_to("lazy-consumer"));
+    expect!(pact.provider().name).to(be_equal_to("lazy-provider"));
+    expect!(pact.specification_version()).to(be_equal_to(PactSpecification::V4));
+    expect!(pact.num_interactions()).to(be_equal_to(2));
+
+    let entry = &pact.index()[1];
+    expect!(entry.key.clone()).to(be_some().value("002"));
+    expect!(entry.description.as_str()).to(be_equal_to("a thing event"));
+    expect!(entry.interaction_type.as_str()).to(be_equal_to("Asynchronous/Messages"));
+    expect!(entry.pending).to(be_true());
+
+    expect!(pact.find_by_key("001").map(|entry| entry.index)).to(be_some().value(0));
+    expect!(pact.find_by_key("003")).to(be_none());
+    expect!(pact.find_by_description("get a thing").count()).to(be_equal_to(1));
+  }
+
+  #[test]
+  fn loads_interactions_on_demand() {
+    let pact = LazyPact::from_bytes("test", v4_pact().as_bytes()).unwrap();
+
+    let interaction = pact.interaction(0).unwrap();
+    let http = interaction.as_v4_http().unwrap();
+    expect!(http.request.path.as_str()).to(be_equal_to("/thing"));
+    expect!(http.response.body.value().unwrap().to_vec()).to(be_equal_to(vec![0, 1, 2, 3, 4]));
+
+    let interaction = pact.interaction(1).unwrap();
+    expect!(interaction.is_v4()).to(be_true());
+    expect!(interaction.description()).to(be_equal_to("a thing event"));
+
+    expect!(pact.interaction(2)).to(be_err());
+    expect!(pact.iter_interactions().count()).to(be_equal_to(2));
+    expect!(pact.interaction_at(1).map(|i| i.description())).to(be_some().value("a thing event"));
+
+    let full_pact = pact.load().unwrap();
+    expect!(full_pact.num_interactions()).to(be_equal_to(2));
+    expect!(pact.as_v4_pact().unwrap().interactions.len()).to(be_equal_to(2));
+  }
+
+  #[test]
+  fn indexes_v3_pacts() {
+    let pact = LazyPact::from_json("test", &json!({
+      "consumer": { "name": "consumer" },
+      "provider": { "name": "provider" },
+      "messages": [
+        { "description": "a message", "contents": { "id": 100 } }
+      ],
+      "metadata": { "pactSpecification": { "version": "3.0.0" } }
+    })).unwrap();
+    expect!(pact.specification_version()).to(be_equal_to(PactSpecification::V3));
+    expect!(pact.index()[0].interaction_type.as_str()).to(be_equal_to("Asynchronous/Messages"));
+    let message = pact.interaction(0).unwrap();
+    expect!(message.is_message()).to(be_true());
+    expect!(pact.as_message_pact().unwrap().messages.len()).to(be_equal_to(1));
+
+    let pact = LazyPact::from_json("test", &json!({
+      "interactions": [
+        { "description": "a request", "request": { "method": "GET", "path": "/" }, "response": { "status": 204 } }
+      ],
+      "metadata": { "pactSpecification": { "version": "2.0.0" } }
+    })).unwrap();
+    expect!(pact.consumer().name).to(be_equal_to("consumer"));
+    expect!(pact.index()[0].interaction_type.as_str()).to(be_equal_to("Synchronous/HTTP"));
+    let interaction = pact.interaction(0).unwrap();
+    expect!(interaction.as_request_response().unwrap().response.status).to(be_equal_to(204));
+  }
+
+  #[test]
+  fn skips_interactions_that_fail_to_parse_when_iterating() {
+    let pact = LazyPact::from_json("test", &json!({
+      "interactions": [
+        { "type": "Synchronous/HTTP", "description": "ok", "request": { "method": "GET", "path": "/" }, "response": { "status": 200 } },
+        { "type": "Not a type", "description": "invalid" }
+      ],
+      "metadata": { "pactSpecification": { "version": "4.0" } }
+    })).unwrap();
+    expect!(pact.num_interactions()).to(be_equal_to(2));
+    expect!(pact.interaction(1)).to(be_err());
+    expect!(pact.interaction_at(1).is_none()).to(be_true());
+    expect!(pact.iter_interactions().count()).to(be_equal_to(1));
+    expect!(pact.load()).to(be_err());
+  }
+
+  #[test]
+  fn add_interaction_stores_the_interaction_json() {
+    let mut pact = LazyPact::from_json("test", &json!({
+      "interactions": [],
+      "metadata": { "pactSpecification": { "version": "3.0.0" } }
+    })).unwrap();
+    pact.add_interaction(&RequestResponseInteraction {
+      description: "added".to_string(),
+      .. RequestResponseInteraction::default()
+    }).unwrap();
+    expect!(pact.num_interactions()).to(be_equal_to(1));
+    expect!(pact.index()[0].description.as_str()).to(be_equal_to("added"));
+    expect!(pact.interaction(0).unwrap().description()).to(be_equal_to("added"));
+  }
+
+  #[test]
+  fn returns_an_error_for_invalid_json() {
+    expect!(LazyPact::from_bytes("test", b"{")).to(be_err());
+    expect!(LazyPact::from_bytes("test", br#"{"interactions": [1]}"#)).to(be_err());
+  }
+}
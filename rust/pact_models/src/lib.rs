@@ -33,7 +33,11 @@ pub mod expression_parser;
 pub mod matchingrules;
 pub mod generators;
 pub mod path_exp;
+pub mod path_template;
 pub mod query_strings;
+pub mod csv_utils;
+pub mod graphql;
+pub mod text_template;
 #[cfg(not(target_family = "wasm"))] pub mod http_utils;
 pub mod http_parts;
 pub mod request;
@@ -45,6 +49,7 @@ pub mod message;
 pub mod pact;
 pub mod sync_pact;
 pub mod message_pact;
+pub mod lazy_pact;
 mod iterator_utils;
 pub mod plugins;
 
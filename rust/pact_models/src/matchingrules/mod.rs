@@ -18,6 +18,7 @@ use crate::generators::{Generator, GeneratorCategory, Generators};
 use crate::json_utils::{json_to_num, json_to_string};
 use crate::matchingrules::expressions::{MatchingReference, MatchingRuleDefinition, ValueType};
 use crate::path_exp::{DocPath, PathToken};
+use crate::path_template::segment_index;
 
 pub mod expressions;
 
@@ -110,6 +111,124 @@ pub enum MatchingRule {
 
 impl MatchingRule {
 
+  /// Generate the JSON schema for a matching rule for the given Pact specification
+  pub fn schema(spec_version: PactSpecification) -> Value {
+    if spec_version < PactSpecification::V3 {
+      json!({
+        "properties": {
+          "match": {
+            "enum": ["regex", "type"]
+          },
+          "regex": {
+            "type": "string"
+          },
+          "min": {
+            "minimum": 0,
+            "type": "integer"
+          },
+          "max": {
+            "minimum": 0,
+            "type": "integer"
+          }
+        },
+        "type": "object"
+      })
+    } else {
+      let mut matchers = vec![
+        "equality", "regex", "type", "min", "max", "timestamp", "date", "time", "include", "number",
+        "integer", "decimal", "null", "values"
+      ];
+      if spec_version >= PactSpecification::V4 {
+        matchers.extend_from_slice(&[
+          "datetime", "real", "boolean", "contentType", "content-type", "arrayContains",
+          "array-contains", "statusCode", "status-code", "notEmpty", "not-empty", "semver",
+          "eachKey", "each-key", "eachValue", "each-value"
+        ]);
+      }
+      json!({
+        "properties": {
+          "match": {
+            "enum": matchers
+          },
+          "regex": {
+            "type": "string"
+          },
+          "min": {
+            "minimum": 0,
+            "type": "integer"
+          },
+          "max": {
+            "minimum": 0,
+            "type": "integer"
+          },
+          "format": {
+            "type": "string"
+          },
+          "timestamp": {
+            "type": "string"
+          },
+          "date": {
+            "type": "string"
+          },
+          "time": {
+            "type": "string"
+          },
+          "variants": {
+            "items": {
+              "properties": {
+                "index": {
+                  "minimum": 0,
+                  "type": "integer"
+                },
+                "rules": {
+                  "type": "object"
+                },
+                "generators": {
+                  "type": "object"
+                }
+              },
+              "required": ["index"],
+              "type": "object"
+            },
+            "type": "array"
+          },
+          "status": {
+            "oneOf": [
+              {
+                "enum": ["info", "success", "redirect", "clientError", "serverError", "nonError", "error"]
+              },
+              {
+                "items": {
+                  "type": "integer"
+                },
+                "type": "array"
+              }
+            ]
+          },
+          "rules": {
+            "type": "array"
+          }
+        },
+        "required": ["match"],
+        "allOf": [
+          {
+            "if": { "properties": { "match": { "const": "regex" } } },
+            "then": { "required": ["regex"] }
+          },
+          {
+            "if": { "properties": { "match": { "enum": ["include", "contentType", "content-type"] } } },
+            "then": { "required": ["value"] }
+          },
+          {
+            "if": { "properties": { "match": { "enum": ["arrayContains", "array-contains"] } } },
+            "then": { "required": ["variants"] }
+          }
+        ],
+        "type": "object"
+      })
+    }
+  }
+
   /// Builds a `MatchingRule` from a `Value` struct
   pub fn from_json(value: &Value) -> anyhow::Result<MatchingRule> {
     match value {
@@ -854,6 +973,11 @@ impl MatchingRuleCategory {
   pub fn to_v3_json(&self) -> Value {
     Value::Object(self.rules.iter().fold(serde_json::Map::new(), |mut map, (category, rulelist)| {
       match self.name {
+        Category::HEADER | Category::QUERY if category.len() > 2 => {
+          // Rules for a component of a structured header value or a nested query parameter value
+          // are keyed by the full path
+          map.insert(String::from(category), rulelist.to_v3_json());
+        }
         Category::HEADER | Category::QUERY => {
           let name = category.first_field().map(|v| v.to_string())
             .unwrap_or_else(|| category.to_string());
@@ -934,6 +1058,37 @@ impl MatchingRuleCategory {
     self.rules.values().next().cloned().unwrap_or_default()
   }
 
+  /// If any of the rule paths in this category have filter expressions (`[?(...)]`)
+  pub fn has_filters(&self) -> bool {
+    self.rules.keys().any(|path| path.has_filters())
+  }
+
+  /// Resolves the filter expressions in the rule paths against the document, so the rules apply
+  /// to the paths of the items the filters select (see `DocPath::resolve_filters`). Rule paths with
+  /// filters that do not select anything are dropped, use `unresolved_filters` to find them.
+  pub fn resolve_filters(&self, document: &Value) -> MatchingRuleCategory {
+    let mut category = MatchingRuleCategory::empty(self.name.clone());
+    for (path, rules) in &self.rules {
+      for resolved in path.resolve_filters(document) {
+        match category.rules.get_mut(&resolved) {
+          Some(existing) => existing.add_rules(rules),
+          None => {
+            category.rules.insert(resolved, rules.clone());
+          }
+        }
+      }
+    }
+    category
+  }
+
+  /// Returns the rule paths with filter expressions that do not select any values from the document
+  pub fn unresolved_filters(&self, document: &Value) -> Vec<DocPath> {
+    self.rules.keys()
+      .filter(|path| path.has_filters() && path.resolve_filters(document).is_empty())
+      .cloned()
+      .collect()
+  }
+
   /// Adds the rules to the category from the provided JSON
   pub fn add_rules_from_json(&mut self, rules: &Value) -> anyhow::Result<()> {
     if self.name == Category::PATH && rules.get("matchers").is_some() {
@@ -987,8 +1142,13 @@ impl MatchingRuleCategory {
         self.add_rule_list(DocPath::empty(), rules)?;
       } else if self.name == Category::QUERY || self.name == Category::HEADER {
         for (k, v) in m {
-          let mut path = DocPath::root();
-          path.push_field(k);
+          let path = if k.starts_with('$') {
+            DocPath::new(k)?
+          } else {
+            let mut path = DocPath::root();
+            path.push_field(k);
+            path
+          };
           self.add_rule_list(path, v)?;
         }
       } else {
@@ -1096,6 +1256,45 @@ pub struct MatchingRules {
 }
 
 impl MatchingRules {
+  /// Generate the JSON schema for the matching rules for the given Pact specification. The
+  /// schema for the individual rules is referenced from the `matchingRule` definition.
+  pub fn schema(spec_version: PactSpecification) -> Value {
+    if spec_version < PactSpecification::V3 {
+      json!({
+        "additionalProperties": {
+          "$ref": "#/definitions/matchingRule"
+        },
+        "type": "object"
+      })
+    } else {
+      let rule_list = json!({
+        "properties": {
+          "combine": {
+            "enum": ["AND", "OR"]
+          },
+          "matchers": {
+            "items": {
+              "$ref": "#/definitions/matchingRule"
+            },
+            "type": "array"
+          }
+        },
+        "required": ["matchers"],
+        "type": "object"
+      });
+      json!({
+        "properties": {
+          "path": rule_list
+        },
+        "additionalProperties": {
+          "additionalProperties": rule_list,
+          "type": "object"
+        },
+        "type": "object"
+      })
+    }
+  }
+
 
   /// If the matching rules are empty (that is there are no rules assigned to any categories)
   pub fn is_empty(&self) -> bool {
@@ -1247,7 +1446,10 @@ impl MatchingRules {
     Value::Object(self.rules.iter()
       .fold(serde_json::Map::new(), |mut map, (name, sub_category)| {
       match name {
-        Category::PATH => if let Some(rules) = sub_category.rules.get(&DocPath::empty()).or_else(|| sub_category.rules.get(&DocPath::root())) {
+        Category::PATH => if sub_category.rules.keys().any(|path| segment_index(path).is_some()) {
+          // Rules for the individual path segments are keyed by the segment path
+          map.insert(name.to_string(), sub_category.to_v3_json());
+        } else if let Some(rules) = sub_category.rules.get(&DocPath::empty()).or_else(|| sub_category.rules.get(&DocPath::root())) {
           map.insert(name.to_string(), rules.to_v3_json());
         }
         _ => {
@@ -2217,6 +2419,49 @@ mod tests {
     expect!(matchers.matcher_is_defined("body", &vec!["$", "a", "b", "c"])).to(be_true());
   }
 
+  #[test]
+  fn matcher_is_defined_with_recursive_descent() {
+    let matchers = matchingrules!{
+      "body" => {
+        "$..id" => [ MatchingRule::Integer ]
+      }
+    };
+    expect!(matchers.matcher_is_defined("body", &vec!["$", "a", "0", "id"])).to(be_true());
+    expect!(matchers.matcher_is_defined("body", &vec!["$", "a", "0", "name"])).to(be_false());
+  }
+
+  #[test]
+  fn resolve_filters_replaces_filters_with_the_selected_items() {
+    let category = matchingrules_list!{
+      "body";
+      "$.items[?(@.type == 'refund')].amount" => [ MatchingRule::Integer ],
+      "$.items[1].amount" => [ MatchingRule::Type ],
+      "$.items[*].type" => [ MatchingRule::Type ]
+    };
+    expect!(category.has_filters()).to(be_true());
+    let resolved = category.resolve_filters(&json!({
+      "items": [ { "type": "sale", "amount": 100 }, { "type": "refund", "amount": 10 } ]
+    }));
+    expect!(resolved.has_filters()).to(be_false());
+    expect!(resolved.rules.len()).to(be_equal_to(2));
+    expect!(resolved.rules.get(&DocPath::new_unwrap("$.items[1].amount")).unwrap().rules.len()).to(be_equal_to(2));
+    expect!(resolved.select_best_matcher(&["$", "items", "0", "amount"]).is_empty()).to(be_true());
+  }
+
+  #[test]
+  fn unresolved_filters_returns_the_filters_that_do_not_select_anything() {
+    let category = matchingrules_list!{
+      "body";
+      "$.items[?(@.type == 'refund')].amount" => [ MatchingRule::Integer ],
+      "$.items[?(@.type == 'sale')].amount" => [ MatchingRule::Integer ],
+      "$.items[*].type" => [ MatchingRule::Type ]
+    };
+    let unresolved = category.unresolved_filters(&json!({
+      "items": [ { "type": "sale", "amount": 100 } ]
+    }));
+    expect!(unresolved).to(be_equal_to(vec![DocPath::new_unwrap("$.items[?(@.type == 'refund')].amount")]));
+  }
+
   #[test]
   fn wildcard_matcher_is_defined_returns_false_when_there_are_no_matchers() {
     let matchers = matchingrules!{};
@@ -2601,4 +2846,23 @@ mod tests {
       }
     )
   }
+
+  #[test]
+  fn header_rules_for_structured_header_components() {
+    let json = json!({
+      "Cache-Control": { "matchers": [{ "match": "regex", "regex": "max-age=\\d+" }] },
+      "$.Set-Cookie.session.Max-Age": { "matchers": [{ "match": "integer" }] }
+    });
+    let mut category = MatchingRuleCategory::empty("header");
+    category.add_v3_rules_from_json(&json).unwrap();
+
+    expect!(category.rules.keys().cloned().collect::<HashSet<_>>()).to(be_equal_to(hashset!{
+      DocPath::root().join("Cache-Control"),
+      DocPath::new_unwrap("$.Set-Cookie.session.Max-Age")
+    }));
+    expect!(category.to_v3_json()).to(be_equal_to(json!({
+      "Cache-Control": { "combine": "AND", "matchers": [{ "match": "regex", "regex": "max-age=\\d+" }] },
+      "$.Set-Cookie.session.Max-Age": { "combine": "AND", "matchers": [{ "match": "integer" }] }
+    })));
+  }
 }
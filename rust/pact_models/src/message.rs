@@ -233,6 +233,41 @@ impl Message {
         }
     }
 
+    /// Generate the JSON schema properties for the given Pact specification
+    pub fn schema(_spec_version: PactSpecification) -> Value {
+      json!({
+        "properties": {
+          "_id": {
+            "type": "string"
+          },
+          "description": {
+            "type": "string"
+          },
+          "providerStates": {
+            "items": {
+              "$ref": "#/definitions/providerState"
+            },
+            "type": "array"
+          },
+          "contents": {},
+          "metadata": {
+            "type": "object"
+          },
+          "metaData": {
+            "type": "object"
+          },
+          "matchingRules": {
+            "$ref": "#/definitions/matchingRules"
+          },
+          "generators": {
+            "$ref": "#/definitions/generators"
+          }
+        },
+        "required": ["description"],
+        "type": "object"
+      })
+    }
+
     /// Converts this interaction to a `Value` struct.
     /// note: spec version is preserved for compatibility with the RequestResponsePact interface
     /// and for future use
@@ -22,7 +22,7 @@ use crate::{Consumer, PactSpecification, Provider};
 #[cfg(not(target_family = "wasm"))] use crate::http_utils::{self, HttpAuth};
 use crate::interaction::Interaction;
 use crate::message::Message;
-use crate::pact::{determine_spec_version, Pact, parse_meta_data, ReadWritePact};
+use crate::pact::{determine_spec_version, InteractionRef, Pact, parse_meta_data, ReadWritePact, schema_definitions};
 use crate::PACT_RUST_VERSION;
 use crate::plugins::PluginData;
 use crate::sync_pact::RequestResponsePact;
@@ -59,6 +59,14 @@ impl Pact for MessagePact {
     self.messages.iter().map(|i| i.boxed()).collect()
   }
 
+  fn iter_interactions(&self) -> Box<dyn Iterator<Item = InteractionRef<'_>> + '_> {
+    Box::new(self.messages.iter().map(|i| InteractionRef::Borrowed(i)))
+  }
+
+  fn num_interactions(&self) -> usize {
+    self.messages.len()
+  }
+
   fn interactions_mut(&mut self) -> Vec<&mut (dyn Interaction + Send + Sync)> {
     self.messages.iter_mut().map(|m| m as &mut (dyn Interaction + Send + Sync)).collect()
   }
@@ -170,6 +178,31 @@ impl MessagePact {
       determine_spec_version("<MessagePact>", &self.metadata)
     }
 
+    /// Generate the JSON schema properties for the given Pact specification
+    pub fn schema(spec_version: PactSpecification) -> Value {
+      json!({
+        "properties": {
+          "consumer": Consumer::schema(spec_version),
+          "messages": {
+            "description": "The messages between the consumer and provider",
+            "type": "array",
+            "items": Message::schema(spec_version),
+          },
+          "metadata": {
+            "description": "Metadata associated with the Pact file",
+            "$ref": "#/definitions/metadata"
+          },
+          "provider": Provider::schema(spec_version)
+        },
+        "required": [
+          "consumer",
+          "messages",
+          "provider"
+        ],
+        "definitions": schema_definitions(spec_version)
+      })
+    }
+
     /// Creates a `MessagePact` from a `Value` struct.
     pub fn from_json(file: &str, pact_json: &Value) -> anyhow::Result<MessagePact> {
         let metadata = parse_meta_data(pact_json);
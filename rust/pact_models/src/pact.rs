@@ -13,16 +13,19 @@ use std::sync::{Arc, Mutex};
 use anyhow::{anyhow, Context};
 use lazy_static::lazy_static;
 use maplit::btreemap;
-use serde_json::{json, Value};
+use serde_json::{json, Map, Value};
 use tracing::{debug, error, trace, warn};
 
 use crate::{Consumer, PactSpecification, Provider};
 #[cfg(not(target_family = "wasm"))] use crate::file_utils::{with_read_lock_for_open_file, with_write_lock};
 #[cfg(not(target_family = "wasm"))] use crate::http_utils;
 #[cfg(not(target_family = "wasm"))] use crate::http_utils::HttpAuth;
+use crate::generators::{Generator, Generators};
 use crate::interaction::Interaction;
+use crate::matchingrules::{MatchingRule, MatchingRules};
 use crate::message_pact::MessagePact;
 use crate::plugins::PluginData;
+use crate::provider_states::ProviderState;
 use crate::sync_pact::RequestResponsePact;
 use crate::v4;
 use crate::v4::pact::V4Pact;
@@ -39,6 +42,23 @@ pub trait Pact: Debug + ReadWritePact {
   /// Interactions in the Pact
   fn interactions(&self) -> Vec<Box<dyn Interaction + Send + Sync + RefUnwindSafe>>;
 
+  /// Iterates over the interactions in the Pact. Unlike `interactions`, Pacts that store their
+  /// interactions will return borrowed references to them instead of clones. The default
+  /// implementation returns the interactions from `interactions`.
+  fn iter_interactions(&self) -> Box<dyn Iterator<Item = InteractionRef<'_>> + '_> {
+    Box::new(self.interactions().into_iter().map(InteractionRef::Owned))
+  }
+
+  /// Number of interactions in the Pact
+  fn num_interactions(&self) -> usize {
+    self.iter_interactions().count()
+  }
+
+  /// Returns the interaction at the given index without cloning it
+  fn interaction_at(&self, index: usize) -> Option<InteractionRef<'_>> {
+    self.iter_interactions().nth(index)
+  }
+
   /// Mutable collection of interactions in the Pact
   fn interactions_mut(&mut self) -> Vec<&mut (dyn Interaction + Send + Sync)>;
 
@@ -95,6 +115,26 @@ pub trait Pact: Debug + ReadWritePact {
   fn add_md_version(&mut self, key: &str, version: &str);
 }
 
+/// Interaction returned when iterating over the interactions of a Pact
+#[derive(Debug)]
+pub enum InteractionRef<'a> {
+  /// Interaction borrowed from the Pact
+  Borrowed(&'a (dyn Interaction + Send + Sync + RefUnwindSafe)),
+  /// Interaction that was created for the iteration
+  Owned(Box<dyn Interaction + Send + Sync + RefUnwindSafe>)
+}
+
+impl <'a> Deref for InteractionRef<'a> {
+  type Target = dyn Interaction + Send + Sync + RefUnwindSafe + 'a;
+
+  fn deref(&self) -> &Self::Target {
+    match self {
+      InteractionRef::Borrowed(interaction) => *interaction,
+      InteractionRef::Owned(interaction) => interaction.as_ref()
+    }
+  }
+}
+
 impl Default for Box<dyn Pact> {
   fn default() -> Self {
     V4Pact::default().boxed()
@@ -312,7 +352,7 @@ pub(crate) fn metadata_schema(spec_version: PactSpecification) -> Value {
       "type": "object"
     })
   } else {
-    json!({
+    let mut schema = json!({
       "properties": {
         "pactSpecification": {
           "additionalProperties": false,
@@ -326,10 +366,62 @@ pub(crate) fn metadata_schema(spec_version: PactSpecification) -> Value {
         }
       },
       "type": "object"
-    })
+    });
+    if spec_version >= PactSpecification::V4 {
+      if let Some(properties) = schema.get_mut("properties").and_then(|p| p.as_object_mut()) {
+        properties.insert("plugins".to_string(), json!({
+          "items": {
+            "properties": {
+              "name": {
+                "type": "string"
+              },
+              "version": {
+                "type": "string"
+              },
+              "configuration": {
+                "type": "object"
+              }
+            },
+            "required": ["name", "version"],
+            "type": "object"
+          },
+          "type": "array"
+        }));
+      }
+    }
+    schema
+  }
+}
+
+/// Adds the `matchingRules` (V2+) and `generators` (V3+) properties to the JSON schema for a
+/// part of an interaction
+pub(crate) fn add_matching_rules_and_generators_schema(schema: &mut Value, spec_version: PactSpecification) {
+  if let Some(properties) = schema.get_mut("properties").and_then(|p| p.as_object_mut()) {
+    if spec_version >= PactSpecification::V2 {
+      properties.insert("matchingRules".to_string(), json!({ "$ref": "#/definitions/matchingRules" }));
+    }
+    if spec_version >= PactSpecification::V3 {
+      properties.insert("generators".to_string(), json!({ "$ref": "#/definitions/generators" }));
+    }
   }
 }
 
+/// Definitions referenced from the JSON schemas of the interactions
+pub(crate) fn schema_definitions(spec_version: PactSpecification) -> Map<String, Value> {
+  let mut definitions = Map::new();
+  definitions.insert("metadata".to_string(), metadata_schema(spec_version));
+  if spec_version >= PactSpecification::V2 {
+    definitions.insert("matchingRule".to_string(), MatchingRule::schema(spec_version));
+    definitions.insert("matchingRules".to_string(), MatchingRules::schema(spec_version));
+  }
+  if spec_version >= PactSpecification::V3 {
+    definitions.insert("generator".to_string(), Generator::schema(spec_version));
+    definitions.insert("generators".to_string(), Generators::schema(spec_version));
+    definitions.insert("providerState".to_string(), ProviderState::schema(spec_version));
+  }
+  definitions
+}
+
 /// Determines the Pact specification version from the metadata of the Pact file
 pub fn determine_spec_version(file: &str, metadata: &BTreeMap<String, BTreeMap<String, String>>) -> PactSpecification {
   let specification = if metadata.contains_key("pact-specification") {
@@ -452,6 +544,7 @@ mod tests {
   use crate::response::Response;
   use crate::sync_interaction::RequestResponseInteraction;
   use crate::sync_pact::RequestResponsePact;
+  use crate::v4::interaction::V4Interaction;
   use crate::v4::pact::V4Pact;
   use crate::v4::synch_http::SynchronousHttp;
 
@@ -1729,4 +1822,26 @@ mod tests {
     let merged_pact = pact.merge(&updated_pact);
     expect(merged_pact.unwrap().as_request_response_pact().unwrap()).to(be_equal_to(updated_pact));
   }
+
+  #[test]
+  fn iter_interactions_borrows_the_interactions() {
+    let pact = V4Pact {
+      interactions: vec![
+        SynchronousHttp { description: "first".to_string(), .. SynchronousHttp::default() }.boxed_v4(),
+        SynchronousHttp { description: "second".to_string(), .. SynchronousHttp::default() }.boxed_v4()
+      ],
+      .. V4Pact::default()
+    };
+    let descriptions = pact.iter_interactions().map(|i| i.description()).collect::<Vec<_>>();
+    expect!(descriptions).to(be_equal_to(vec!["first".to_string(), "second".to_string()]));
+    expect!(pact.num_interactions()).to(be_equal_to(2));
+    expect!(pact.interaction_at(1).map(|i| i.description())).to(be_some().value("second"));
+    expect!(pact.interaction_at(2).is_none()).to(be_true());
+
+    let pact = RequestResponsePact {
+      interactions: vec![ RequestResponseInteraction::default() ],
+      .. RequestResponsePact::default()
+    };
+    expect!(pact.iter_interactions().count()).to(be_equal_to(1));
+  }
 }
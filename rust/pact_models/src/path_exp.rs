@@ -10,8 +10,13 @@ use anyhow::anyhow;
 use lazy_static::lazy_static;
 use regex::{Captures, Regex};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use tracing::trace;
 
+use crate::path_exp::filters::FilterExpression;
+
+pub mod filters;
+
 lazy_static! {
   // Only use "." syntax for things which are obvious identifiers.
   static ref IDENT: Regex = Regex::new(r#"^[_A-Za-z][_A-Za-z0-9]*$"#)
@@ -48,6 +53,35 @@ impl Display for PathToken {
   }
 }
 
+/// Element of a parsed path expression. Recursive descent (`..`) and filter (`[?(...)]`)
+/// expressions are not path tokens, as they can only be used in matching rule and generator
+/// paths where they are evaluated against the document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PathElement {
+  /// Path token
+  Token(PathToken),
+  /// .. (recursive descent), which matches any number of path elements
+  RecursiveDescent,
+  /// ?(...) filter, which selects the array items (or object values) that match the filter
+  Filter(FilterExpression)
+}
+
+impl From<PathToken> for PathElement {
+  fn from(token: PathToken) -> Self {
+    PathElement::Token(token)
+  }
+}
+
+impl Display for PathElement {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      PathElement::Token(token) => write!(f, "{}", token),
+      PathElement::RecursiveDescent => write!(f, ".."),
+      PathElement::Filter(filter) => write!(f, "{}", filter)
+    }
+  }
+}
+
 fn matches_token(path_fragment: &str, path_token: &PathToken) -> usize {
   match path_token {
     PathToken::Root if path_fragment == "$" => 2,
@@ -65,24 +99,45 @@ fn matches_token(path_fragment: &str, path_token: &PathToken) -> usize {
   }
 }
 
+/// Calculates the best weight of the path elements against the path, where a recursive descent
+/// can match any number of path elements and filters do not match anything. Returns the weight
+/// and the number of path elements matched.
+fn weight_with_recursive_descent(elements: &[PathElement], path: &[&str]) -> Option<(usize, usize)> {
+  match elements.split_first() {
+    None => Some((1, 0)),
+    Some((PathElement::RecursiveDescent, rest)) => (0..=path.len())
+      .filter_map(|skip| weight_with_recursive_descent(rest, &path[skip..])
+        .map(|(weight, matched)| (weight, matched + skip)))
+      .max(),
+    Some((PathElement::Token(token), rest)) => match path.split_first() {
+      Some((fragment, remaining)) => match matches_token(fragment, token) {
+        0 => None,
+        weight => weight_with_recursive_descent(rest, remaining)
+          .map(|(w, matched)| (weight * w, matched + 1))
+      },
+      None => None
+    },
+    Some((PathElement::Filter(_), _)) => None
+  }
+}
+
 #[derive(Debug, Clone, Eq, Serialize, Deserialize)]
 #[serde(try_from = "String")]
 #[serde(into = "String")]
 pub struct DocPath {
   path_tokens: Vec<PathToken>,
   expr: String,
+  /// All the elements of the path, only set if the path has recursive descent or filter expressions
+  elements: Option<Vec<PathElement>>
 }
 
 impl DocPath {
   /// Construct a new document path from the provided string path
   pub fn new(expr: impl Into<String>) -> anyhow::Result<Self> {
     let expr = expr.into();
-    let path_tokens = parse_path_exp(&expr)
+    let elements = parse_path_elements(&expr)
       .map_err(|e| anyhow!(e))?;
-    Ok(Self {
-      path_tokens,
-      expr,
-    })
+    Ok(Self::from_elements(elements, expr))
   }
 
   /// Infallible construction for when the expression is statically known,
@@ -102,6 +157,7 @@ impl DocPath {
     Self {
       path_tokens: vec![],
       expr: "".into(),
+      elements: None
     }
   }
 
@@ -110,17 +166,19 @@ impl DocPath {
     Self {
       path_tokens: vec![PathToken::Root],
       expr: "$".into(),
+      elements: None
     }
   }
 
-  /// Return the list of tokens that comprise this path.
+  /// Return the list of tokens that comprise this path. Recursive descent and filter expressions
+  /// are not path tokens, so are not included (see `has_recursive_descent` and `has_filters`).
   pub fn tokens(&self) -> &Vec<PathToken> {
     &self.path_tokens
   }
 
-  /// Return the length, in parsed tokens.
+  /// Return the length, in parsed tokens (including any recursive descent and filter expressions).
   pub fn len(&self) -> usize {
-    self.path_tokens.len()
+    self.elements.as_ref().map(|elements| elements.len()).unwrap_or(self.path_tokens.len())
   }
 
   /// Extract the string contents of the first Field token.
@@ -146,21 +204,32 @@ impl DocPath {
 
   /// If this path is the root path (it has only one element, the root token `$`).
   pub fn is_root(&self) -> bool {
-    &self.path_tokens == &[PathToken::Root]
+    self.path_tokens == [PathToken::Root] && self.elements.is_none()
   }
 
   /// The path is a wildcard path if it ends in a star (`*`)
   pub fn is_wildcard(&self) -> bool {
-    self.path_tokens.last() == Some(&PathToken::Star)
+    match &self.elements {
+      Some(elements) => elements.last() == Some(&PathElement::Token(PathToken::Star)),
+      None => self.path_tokens.last() == Some(&PathToken::Star)
+    }
   }
 
   /// Calculates the path weight for this path expression and a given path.
   /// Returns a tuple of the calculated weight and the number of path tokens matched.
+  ///
+  /// A recursive descent token (`..`) matches any number of path elements with a weight of 1, so
+  /// the number of path tokens matched will be the number of path elements it matched.
+  /// Filter tokens (`[?(...)]`) need the document to be evaluated, so will not match any path
+  /// (see `resolve_filters`).
   pub fn path_weight(&self, path: &[&str]) -> (usize, usize) {
     trace!("Calculating weight for path tokens '{:?}' and path '{:?}'",
            self.path_tokens, path);
     let weight = {
-      if path.len() >= self.len() {
+      if let Some(elements) = &self.elements {
+        weight_with_recursive_descent(elements, path)
+          .unwrap_or((0, self.len()))
+      } else if path.len() >= self.len() {
         (
           self.path_tokens.iter().zip(path.iter())
           .fold(1, |acc, (token, fragment)| acc * matches_token(fragment, token)),
@@ -184,7 +253,89 @@ impl DocPath {
   /// If the path matches the given path (the calculated path weight is greater than zero) and
   /// both paths have the same length.
   pub fn matches_path_exactly(&self, path: &[&str]) -> bool {
-     self.len() == path.len() && self.matches_path(path)
+    let (weight, matched) = self.path_weight(path);
+    weight > 0 && matched == path.len()
+  }
+
+  /// If this path contains a recursive descent (`..`) expression
+  pub fn has_recursive_descent(&self) -> bool {
+    self.elements.as_ref()
+      .map(|elements| elements.contains(&PathElement::RecursiveDescent))
+      .unwrap_or(false)
+  }
+
+  /// If this path contains any filter (`[?(...)]`) expressions
+  pub fn has_filters(&self) -> bool {
+    self.elements.as_ref()
+      .map(|elements| elements.iter().any(|element| matches!(element, PathElement::Filter(_))))
+      .unwrap_or(false)
+  }
+
+  /// Returns the paths to all the values in the document selected by this path. The returned
+  /// paths will only contain field and index tokens.
+  pub fn select_paths(&self, document: &Value) -> Vec<DocPath> {
+    let mut result = vec![];
+    select_paths(&self.elements(), document, vec![], &mut result);
+    result.iter().map(|tokens| DocPath::from_tokens(tokens.clone())).collect()
+  }
+
+  /// Resolves any filter expressions in this path against the document. The part of the path up
+  /// to the last filter is replaced with the paths to the values it selects (so each selected item
+  /// will have an index or field token), and the rest of the path is kept as is. Paths without
+  /// filters are returned unchanged.
+  pub fn resolve_filters(&self, document: &Value) -> Vec<DocPath> {
+    let elements = self.elements();
+    match elements.iter().rposition(|element| matches!(element, PathElement::Filter(_))) {
+      Some(position) => {
+        let (prefix, rest) = elements.split_at(position + 1);
+        let mut selected = vec![];
+        select_paths(prefix, document, vec![], &mut selected);
+        let mut result: Vec<DocPath> = vec![];
+        for tokens in selected {
+          let mut path_elements = tokens.into_iter().map(PathElement::from).collect::<Vec<_>>();
+          path_elements.extend_from_slice(rest);
+          let path = DocPath::from_elements(path_elements.clone(), build_elements_expr(&path_elements));
+          if !result.contains(&path) {
+            result.push(path);
+          }
+        }
+        result
+      }
+      None => vec![self.clone()]
+    }
+  }
+
+  /// All the elements of the path, including any recursive descent and filter expressions
+  fn elements(&self) -> Vec<PathElement> {
+    match &self.elements {
+      Some(elements) => elements.clone(),
+      None => self.path_tokens.iter().cloned().map(PathElement::from).collect()
+    }
+  }
+
+  fn from_tokens(path_tokens: Vec<PathToken>) -> DocPath {
+    let mut path = DocPath {
+      path_tokens,
+      expr: "".to_string(),
+      elements: None
+    };
+    path.expr = path.build_expr();
+    path
+  }
+
+  fn from_elements(elements: Vec<PathElement>, expr: String) -> DocPath {
+    let path_tokens = elements.iter()
+      .filter_map(|element| match element {
+        PathElement::Token(token) => Some(token.clone()),
+        _ => None
+      })
+      .collect::<Vec<_>>();
+    let extended = path_tokens.len() < elements.len();
+    DocPath {
+      path_tokens,
+      expr,
+      elements: if extended { Some(elements) } else { None }
+    }
   }
 
   /// Creates a new path by cloning this one and pushing the string onto the end
@@ -203,56 +354,75 @@ impl DocPath {
 
   /// Mutates this path by pushing a field value onto the end.
   pub fn push_field(&mut self, field: impl Into<String>) -> &mut Self {
-    let field = field.into();
-    write_obj_key_for_path(&mut self.expr, &field);
-    self.path_tokens.push(PathToken::Field(field));
-    self
+    self.push(PathToken::Field(field.into()))
   }
 
   /// Mutates this path by pushing an index value onto the end.
   pub fn push_index(&mut self, index: usize) -> &mut Self {
-    self.path_tokens.push(PathToken::Index(index));
-    // unwrap is safe, as write! is infallible for String
-    let _ = write!(self.expr, "[{}]", index);
-    self
+    self.push(PathToken::Index(index))
   }
 
   /// Mutates this path by pushing a star value onto the end.
   pub fn push_star(&mut self) -> &mut Self {
-    self.path_tokens.push(PathToken::Star);
-    self.expr.push_str(".*");
-    self
+    self.push(PathToken::Star)
   }
 
   /// Mutates this path by pushing a star index value onto the end.
   pub fn push_star_index(&mut self) -> &mut Self {
-    self.path_tokens.push(PathToken::StarIndex);
-    self.expr.push_str("[*]");
-    self
+    self.push(PathToken::StarIndex)
   }
 
   /// Mutates this path by pushing a path token onto the end.
   pub fn push(&mut self, path_token: PathToken) -> &mut Self {
+    let after_recursive_descent = self.elements.as_ref()
+      .and_then(|elements| elements.last()) == Some(&PathElement::RecursiveDescent);
     match &path_token {
       PathToken::Root => self.expr.push_str("$"),
       PathToken::Field(v) => {
         let s = &mut self.expr;
-        write_obj_key_for_path(s, v.as_str())
+        if after_recursive_descent {
+          write_obj_key_after_recursive_descent(s, v.as_str())
+        } else {
+          write_obj_key_for_path(s, v.as_str())
+        }
       },
       PathToken::Index(i) => { let _ = write!(self.expr, "[{}]", i); },
-      PathToken::Star => self.expr.push_str(".*"),
+      PathToken::Star => if after_recursive_descent {
+        self.expr.push('*')
+      } else {
+        self.expr.push_str(".*")
+      },
       PathToken::StarIndex => self.expr.push_str("[*]")
     };
+    if let Some(elements) = &mut self.elements {
+      elements.push(path_token.clone().into());
+    }
     self.path_tokens.push(path_token);
     self
   }
 
+  /// Mutates this path by pushing a recursive descent (`..`) onto the end. This must be followed
+  /// by a field, index or star.
+  pub fn push_recursive_descent(&mut self) -> &mut Self {
+    self.expr.push_str("..");
+    self.elements.get_or_insert_with(|| self.path_tokens.iter().cloned().map(PathElement::from).collect())
+      .push(PathElement::RecursiveDescent);
+    self
+  }
+
   /// Mutates this path by pushing another path onto the end. Will drop the root marker from the
   /// other path
   pub fn push_path(&mut self, path: &DocPath) -> &mut Self {
-    for token in &path.path_tokens {
-      if token != &PathToken::Root {
-        self.push(token.clone());
+    for element in path.elements() {
+      match element {
+        PathElement::Token(PathToken::Root) => {}
+        PathElement::Token(token) => { self.push(token); }
+        PathElement::RecursiveDescent => { self.push_recursive_descent(); }
+        PathElement::Filter(filter) => {
+          let _ = write!(self.expr, "[{}]", filter);
+          self.elements.get_or_insert_with(|| self.path_tokens.iter().cloned().map(PathElement::from).collect())
+            .push(PathElement::Filter(filter));
+        }
       }
     }
     self
@@ -260,11 +430,20 @@ impl DocPath {
 
   /// Convert this path to a vector of strings
   pub fn to_vec(&self) -> Vec<String> {
-    self.path_tokens.iter().map(|t| t.to_string()).collect()
+    match &self.elements {
+      Some(elements) => elements.iter().map(|e| e.to_string()).collect(),
+      None => self.path_tokens.iter().map(|t| t.to_string()).collect()
+    }
   }
 
   /// Return the parent path from this one
   pub fn parent(&self) -> Option<Self> {
+    if let Some(elements) = &self.elements {
+      let elements = elements[..elements.len() - 1].to_vec();
+      let expr = build_elements_expr(&elements);
+      return Some(DocPath::from_elements(elements, expr));
+    }
+
     if self.path_tokens.len() <= 1 {
       None
     } else {
@@ -272,7 +451,8 @@ impl DocPath {
       vec.truncate(vec.len() - 1);
       let mut path = DocPath {
         path_tokens: vec,
-        expr: "".to_string()
+        expr: "".to_string(),
+        elements: None
       };
       path.expr = path.build_expr();
       Some(path)
@@ -308,17 +488,26 @@ impl DocPath {
 
   /// Returns a copy of this path will all parts lower case
   pub fn to_lower_case(&self) -> DocPath {
+    let lower_case = |p: &PathToken| match p {
+      PathToken::Field(f) => PathToken::Field(f.to_lowercase()),
+      _ => p.clone()
+    };
     DocPath {
-      path_tokens: self.path_tokens.iter().map(|p| match p {
-        PathToken::Field(f) => PathToken::Field(f.to_lowercase()),
-        _ => p.clone()
-      }).collect(),
-      expr: self.expr.to_lowercase()
+      path_tokens: self.path_tokens.iter().map(lower_case).collect(),
+      expr: self.expr.to_lowercase(),
+      elements: self.elements.as_ref().map(|elements| elements.iter().map(|element| match element {
+        PathElement::Token(token) => PathElement::Token(lower_case(token)),
+        _ => element.clone()
+      }).collect())
     }
   }
 
   /// Converts this path into a JSON pointer [RFC6901](https://datatracker.ietf.org/doc/html/rfc6901).
   pub fn as_json_pointer(&self) -> anyhow::Result<String> {
+    if self.elements.is_some() {
+      return Err(anyhow!("Recursive descent and filter expressions can not be converted to a JSON pointer"));
+    }
+
     let mut buffer = String::new();
 
     for token in &self.path_tokens {
@@ -362,6 +551,113 @@ fn write_obj_key_for_path(mut out: impl Write, key: &str) {
   };
 }
 
+/// Format a JSON object key that follows a recursive descent token (`..`), which already
+/// provides the separator.
+fn write_obj_key_after_recursive_descent(mut out: impl Write, key: &str) {
+  let _ = if IDENT.is_match(key) {
+    write!(out, "{}", key)
+  } else {
+    write!(
+      out,
+      "['{}']",
+      ESCAPE.replace_all(key, |caps: &Captures| format!(r#"\{}"#, &caps[0]))
+    )
+  };
+}
+
+/// Builds the path expression from the path elements
+fn build_elements_expr(elements: &[PathElement]) -> String {
+  let mut buffer = String::new();
+
+  for (index, element) in elements.iter().enumerate() {
+    let after_recursive_descent = index > 0 && elements[index - 1] == PathElement::RecursiveDescent;
+    match element {
+      PathElement::Token(PathToken::Root) => buffer.push('$'),
+      PathElement::Token(PathToken::Field(v)) => if after_recursive_descent {
+        write_obj_key_after_recursive_descent(&mut buffer, v.as_str());
+      } else {
+        write_obj_key_for_path(&mut buffer, v.as_str());
+      }
+      PathElement::Token(PathToken::Index(i)) => {
+        let _ = write!(buffer, "[{}]", i);
+      }
+      PathElement::Token(PathToken::Star) => {
+        if !after_recursive_descent {
+          buffer.push('.');
+        }
+        buffer.push('*');
+      }
+      PathElement::Token(PathToken::StarIndex) => buffer.push_str("[*]"),
+      PathElement::RecursiveDescent => buffer.push_str(".."),
+      PathElement::Filter(filter) => {
+        let _ = write!(buffer, "[{}]", filter);
+      }
+    }
+  }
+
+  buffer
+}
+
+/// Walks the document with the path elements, collecting the paths to all the values selected
+fn select_paths(elements: &[PathElement], value: &Value, path: Vec<PathToken>, result: &mut Vec<Vec<PathToken>>) {
+  let child_path = |token: PathToken| {
+    let mut child = path.clone();
+    child.push(token);
+    child
+  };
+  match elements.split_first() {
+    None => result.push(path),
+    Some((element, rest)) => match element {
+      PathElement::Token(PathToken::Root) => select_paths(rest, value, child_path(PathToken::Root), result),
+      PathElement::Token(token @ PathToken::Field(name)) => if let Some(child) = value.as_object().and_then(|map| map.get(name)) {
+        select_paths(rest, child, child_path(token.clone()), result);
+      },
+      PathElement::Token(token @ PathToken::Index(index)) => if let Some(child) = value.as_array().and_then(|array| array.get(*index)) {
+        select_paths(rest, child, child_path(token.clone()), result);
+      },
+      PathElement::Token(PathToken::Star) => match value {
+        Value::Object(map) => for (key, child) in map {
+          select_paths(rest, child, child_path(PathToken::Field(key.clone())), result);
+        },
+        Value::Array(array) => for (index, child) in array.iter().enumerate() {
+          select_paths(rest, child, child_path(PathToken::Index(index)), result);
+        },
+        _ => {}
+      },
+      PathElement::Token(PathToken::StarIndex) => if let Some(array) = value.as_array() {
+        for (index, child) in array.iter().enumerate() {
+          select_paths(rest, child, child_path(PathToken::Index(index)), result);
+        }
+      },
+      PathElement::RecursiveDescent => {
+        select_paths(rest, value, path.clone(), result);
+        match value {
+          Value::Object(map) => for (key, child) in map {
+            select_paths(elements, child, child_path(PathToken::Field(key.clone())), result);
+          },
+          Value::Array(array) => for (index, child) in array.iter().enumerate() {
+            select_paths(elements, child, child_path(PathToken::Index(index)), result);
+          },
+          _ => {}
+        }
+      }
+      PathElement::Filter(filter) => match value {
+        Value::Object(map) => for (key, child) in map {
+          if filter.matches(child) {
+            select_paths(rest, child, child_path(PathToken::Field(key.clone())), result);
+          }
+        },
+        Value::Array(array) => for (index, child) in array.iter().enumerate() {
+          if filter.matches(child) {
+            select_paths(rest, child, child_path(PathToken::Index(index)), result);
+          }
+        },
+        _ => {}
+      }
+    }
+  }
+}
+
 #[cfg(test)]
 fn obj_key_for_path(key: &str) -> String {
   let mut out = String::new();
@@ -428,7 +724,7 @@ fn is_identifier_char(ch: char) -> bool {
 }
 
 // identifier -> a-zA-Z0-9+
-fn identifier<I>(ch: char, chars: &mut Peekable<I>, tokens: &mut Vec<PathToken>, path: &str) -> Result<(), String>
+fn identifier<I>(ch: char, chars: &mut Peekable<I>, tokens: &mut Vec<PathElement>, path: &str) -> Result<(), String>
   where I: Iterator<Item=(usize, char)> {
   let mut id = String::new();
   id.push(ch);
@@ -446,17 +742,17 @@ fn identifier<I>(ch: char, chars: &mut Peekable<I>, tokens: &mut Vec<PathToken>,
     }
     next_char = peek(chars);
   }
-  tokens.push(PathToken::Field(id));
+  tokens.push(PathToken::Field(id).into());
   Ok(())
 }
 
 // path_identifier -> identifier | *
-fn path_identifier<I>(chars: &mut Peekable<I>, tokens: &mut Vec<PathToken>, path: &str, index: usize) -> Result<(), String>
+fn path_identifier<I>(chars: &mut Peekable<I>, tokens: &mut Vec<PathElement>, path: &str, index: usize) -> Result<(), String>
   where I: Iterator<Item=(usize, char)> {
   match chars.next() {
     Some(ch) => match ch.1 {
       '*' => {
-        tokens.push(PathToken::Star);
+        tokens.push(PathToken::Star.into());
         Ok(())
       },
       c if is_identifier_char(c) => {
@@ -472,7 +768,7 @@ fn path_identifier<I>(chars: &mut Peekable<I>, tokens: &mut Vec<PathToken>, path
 }
 
 // string_path -> [^']+
-fn string_path<I>(chars: &mut Peekable<I>, tokens: &mut Vec<PathToken>, path: &str, index: usize) -> Result<(), String>
+fn string_path<I>(chars: &mut Peekable<I>, tokens: &mut Vec<PathElement>, path: &str, index: usize) -> Result<(), String>
   where I: Iterator<Item=(usize, char)> {
   let mut id = String::new();
   let mut next_char = peek(chars);
@@ -490,7 +786,7 @@ fn string_path<I>(chars: &mut Peekable<I>, tokens: &mut Vec<PathToken>, path: &s
       if id.is_empty() {
         Err(format!("Empty strings are not allowed in path expression \"{}\" at index {}", path, ch.0))
       } else {
-        tokens.push(PathToken::Field(id));
+        tokens.push(PathToken::Field(id).into());
         Ok(())
       }
     } else {
@@ -502,7 +798,7 @@ fn string_path<I>(chars: &mut Peekable<I>, tokens: &mut Vec<PathToken>, path: &s
 }
 
 // index_path -> [0-9]+
-fn index_path<I>(chars: &mut Peekable<I>, tokens: &mut Vec<PathToken>, path: &str) -> Result<(), String>
+fn index_path<I>(chars: &mut Peekable<I>, tokens: &mut Vec<PathElement>, path: &str) -> Result<(), String>
   where I: Iterator<Item=(usize, char)> {
   let mut id = String::new();
   let mut next_char = chars.next();
@@ -526,12 +822,73 @@ fn index_path<I>(chars: &mut Peekable<I>, tokens: &mut Vec<PathToken>, path: &st
     }
   }
 
-  tokens.push(PathToken::Index(id.parse().unwrap()));
+  tokens.push(PathToken::Index(id.parse().unwrap()).into());
   Ok(())
 }
 
-// bracket_path -> (string_path | index | *) ]
-fn bracket_path<I>(chars: &mut Peekable<I>, tokens: &mut Vec<PathToken>, path: &str, index: usize) -> Result<(), String>
+// filter_path -> ? ( filter_expression )
+fn filter_path<I>(chars: &mut Peekable<I>, tokens: &mut Vec<PathElement>, path: &str, index: usize) -> Result<(), String>
+  where I: Iterator<Item=(usize, char)> {
+  match chars.next() {
+    Some((_, '(')) => {
+      let mut expression = String::new();
+      let mut depth = 0;
+      let mut quote = None;
+      loop {
+        match chars.next() {
+          Some((_, ch)) => {
+            match quote {
+              Some(q) => if ch == '\\' {
+                expression.push(ch);
+                if let Some((_, next)) = chars.next() {
+                  expression.push(next);
+                }
+                continue;
+              } else if ch == q {
+                quote = None;
+              },
+              None => match ch {
+                '\'' | '"' => quote = Some(ch),
+                '(' => depth += 1,
+                ')' if depth == 0 => break,
+                ')' => depth -= 1,
+                _ => {}
+              }
+            }
+            expression.push(ch);
+          }
+          None => return Err(format!("Unterminated filter expression in path expression \"{}\" at index {}",
+                                     path, path.len() - 1))
+        }
+      }
+      let filter = FilterExpression::parse(expression.as_str())
+        .map_err(|err| format!("{} in path expression \"{}\" at index {}", err, path, index))?;
+      tokens.push(PathElement::Filter(filter));
+      Ok(())
+    }
+    Some((i, ch)) => Err(format!("Expected a \"(\" after \"?\", found \"{}\" instead in path expression \"{}\" at index {}",
+                                 ch, path, i)),
+    None => Err(format!("Expected a \"(\" after \"?\" in path expression \"{}\" at index {}", path, index))
+  }
+}
+
+// recursive_descent_path -> .. (identifier | * | bracket_path)
+fn recursive_descent_path<I>(chars: &mut Peekable<I>, tokens: &mut Vec<PathElement>, path: &str, index: usize) -> Result<(), String>
+  where I: Iterator<Item=(usize, char)> {
+  tokens.push(PathElement::RecursiveDescent);
+  match peek(chars) {
+    Some((i, '[')) => {
+      chars.next();
+      bracket_path(chars, tokens, path, i)
+    }
+    Some(_) => path_identifier(chars, tokens, path, index),
+    None => Err(format!("Expected a path after \"..\" in path expression \"{}\" at index {}",
+                        path, index))
+  }
+}
+
+// bracket_path -> (string_path | index | * | filter_path) ]
+fn bracket_path<I>(chars: &mut Peekable<I>, tokens: &mut Vec<PathElement>, path: &str, index: usize) -> Result<(), String>
   where I: Iterator<Item=(usize, char)> {
   let mut ch = peek(chars);
   match ch {
@@ -543,7 +900,10 @@ fn bracket_path<I>(chars: &mut Peekable<I>, tokens: &mut Vec<PathToken>, path: &
         index_path(chars, tokens, path)?
       } else if c.1 == '*' {
         chars.next();
-        tokens.push(PathToken::StarIndex);
+        tokens.push(PathToken::StarIndex.into());
+      } else if c.1 == '?' {
+        chars.next();
+        filter_path(chars, tokens, path, c.0)?
       } else if c.1 == ']' {
         return Err(format!("Empty bracket expressions are not allowed in path expression \"{}\" at index {}",
                            path, c.0));
@@ -570,13 +930,18 @@ fn bracket_path<I>(chars: &mut Peekable<I>, tokens: &mut Vec<PathToken>, path: &
 }
 
 // path_exp -> (dot-path | bracket-path)*
-fn path_exp<I>(chars: &mut Peekable<I>, tokens: &mut Vec<PathToken>, path: &str) -> Result<(), String>
+fn path_exp<I>(chars: &mut Peekable<I>, tokens: &mut Vec<PathElement>, path: &str) -> Result<(), String>
   where I: Iterator<Item=(usize, char)> {
   let mut next_char = chars.next();
   while next_char.is_some() {
     let ch = next_char.unwrap();
     match ch.1 {
-      '.' => path_identifier(chars, tokens, path, ch.0)?,
+      '.' => if let Some((i, '.')) = peek(chars) {
+        chars.next();
+        recursive_descent_path(chars, tokens, path, i)?
+      } else {
+        path_identifier(chars, tokens, path, ch.0)?
+      },
       '[' => bracket_path(chars, tokens, path, ch.0)?,
       _ => return Err(format!("Expected a \".\" or \"[\" instead of \"{}\" in path expression \"{}\" at index {}",
                               ch.1, path, ch.0))
@@ -586,7 +951,18 @@ fn path_exp<I>(chars: &mut Peekable<I>, tokens: &mut Vec<PathToken>, path: &str)
   Ok(())
 }
 
+/// Parses the path expression into path tokens. Returns an error if the path has recursive descent
+/// (`..`) or filter (`[?(...)]`) expressions, as these are only supported by `DocPath`.
 pub fn parse_path_exp(path: &str) -> Result<Vec<PathToken>, String> {
+  parse_path_elements(path)?.into_iter()
+    .map(|element| match element {
+      PathElement::Token(token) => Ok(token),
+      _ => Err(format!("Path expression \"{}\" has a recursive descent or filter expression, which can not be converted to path tokens", path))
+    })
+    .collect()
+}
+
+fn parse_path_elements(path: &str) -> Result<Vec<PathElement>, String> {
   let mut tokens = vec![];
 
   // parse_path_exp -> $ path_exp | empty
@@ -595,12 +971,12 @@ pub fn parse_path_exp(path: &str) -> Result<Vec<PathToken>, String> {
     Some(ch) => {
       match ch.1 {
         '$' => {
-          tokens.push(PathToken::Root);
+          tokens.push(PathToken::Root.into());
           path_exp(&mut chars, &mut tokens, path)?;
           Ok(tokens)
         }
         c if c.is_alphabetic() || c.is_numeric() => {
-          tokens.push(PathToken::Root);
+          tokens.push(PathToken::Root.into());
           identifier(c, &mut chars, &mut tokens, path)?;
           path_exp(&mut chars, &mut tokens, path)?;
           Ok(tokens)
@@ -616,6 +992,7 @@ pub fn parse_path_exp(path: &str) -> Result<Vec<PathToken>, String> {
 mod tests {
   use expectest::expect;
   use expectest::prelude::*;
+  use serde_json::json;
 
   use super::*;
 
@@ -714,6 +1091,123 @@ mod tests {
     expect!(DocPath::new_unwrap("$[*]").path_weight(&vec!["$", "name"]).0 > 0).to(be_false());
   }
 
+  #[test]
+  fn parse_path_exp_with_recursive_descent() {
+    expect!(parse_path_elements("$..id")).to(
+      be_ok().value(vec![PathToken::Root.into(), PathElement::RecursiveDescent, PathToken::Field("id".to_string()).into()]));
+    expect!(parse_path_elements("$.a..*")).to(
+      be_ok().value(vec![PathToken::Root.into(), PathToken::Field("a".to_string()).into(), PathElement::RecursiveDescent,
+                         PathToken::Star.into()]));
+    expect!(parse_path_elements("$..['an id'][1]")).to(
+      be_ok().value(vec![PathToken::Root.into(), PathElement::RecursiveDescent, PathToken::Field("an id".to_string()).into(),
+                         PathToken::Index(1).into()]));
+    expect!(parse_path_elements("$..")).to(
+      be_err().value("Expected a path after \"..\" in path expression \"$..\" at index 2".to_string()));
+    expect!(parse_path_elements("$...a")).to(
+      be_err().value("Expected either a \"*\" or path identifier in path expression \"$...a\" at index 3".to_string()));
+  }
+
+  #[test]
+  fn parse_path_exp_with_filters() {
+    let filter = FilterExpression::parse("@.type=='refund'").unwrap();
+    expect!(parse_path_elements("$.items[?(@.type=='refund')].amount")).to(
+      be_ok().value(vec![PathToken::Root.into(), PathToken::Field("items".to_string()).into(), PathElement::Filter(filter),
+                         PathToken::Field("amount".to_string()).into()]));
+    let filter = FilterExpression::parse("@.a[0] == ')'").unwrap();
+    expect!(parse_path_elements("$[?(@.a[0] == ')')]").unwrap().len()).to(be_equal_to(2));
+    expect!(parse_path_elements("$[?(@.a[0] == ')')]").unwrap()[1].clone()).to(be_equal_to(PathElement::Filter(filter)));
+    expect!(parse_path_elements("$[?@.a]")).to(
+      be_err().value("Expected a \"(\" after \"?\", found \"@\" instead in path expression \"$[?@.a]\" at index 3".to_string()));
+    expect!(parse_path_elements("$[?(@.a == 1]")).to(
+      be_err().value("Unterminated filter expression in path expression \"$[?(@.a == 1]\" at index 12".to_string()));
+    expect!(parse_path_elements("$[?(@.a = 1)]")).to(be_err());
+  }
+
+  #[test]
+  fn path_with_recursive_descent_and_filters_round_trips() {
+    for expr in ["$..id", "$.a..*", "$..['an id']", "$..[*].id", "$.items[?(@.type == 'refund')].amount",
+      "$..items[?(@.a[0] == ']')]"] {
+      let path = DocPath::new(expr).unwrap();
+      expect!(path.to_string()).to(be_equal_to(expr));
+      expect!(build_elements_expr(&path.elements())).to(be_equal_to(expr));
+      expect!(path.parent().unwrap().len()).to(be_equal_to(path.len() - 1));
+    }
+    let mut path = DocPath::root();
+    path.push_recursive_descent().push_field("id");
+    expect!(path.to_string()).to(be_equal_to("$..id"));
+    let mut path = DocPath::root();
+    path.push_recursive_descent().push_star();
+    expect!(path.to_string()).to(be_equal_to("$..*"));
+    expect!(path.tokens()).to(be_equal_to(&vec![PathToken::Root, PathToken::Star]));
+    expect!(path.len()).to(be_equal_to(3));
+  }
+
+  #[test]
+  fn parse_path_exp_rejects_recursive_descent_and_filters() {
+    expect!(parse_path_exp("$..id")).to(be_err());
+    expect!(parse_path_exp("$.items[?(@.type == 'refund')]")).to(be_err());
+  }
+
+  #[test]
+  fn matches_path_with_recursive_descent() {
+    let path = DocPath::new_unwrap("$..id");
+    expect!(path.path_weight(&["$", "id"])).to(be_equal_to((4, 2)));
+    expect!(path.path_weight(&["$", "a", "b", "id"])).to(be_equal_to((4, 4)));
+    expect!(path.path_weight(&["$", "a", "0", "id", "b"])).to(be_equal_to((4, 4)));
+    expect!(path.path_weight(&["$", "a", "b"]).0).to(be_equal_to(0));
+    expect!(path.path_weight(&["$"]).0).to(be_equal_to(0));
+    expect!(path.matches_path_exactly(&["$", "a", "b", "id"])).to(be_true());
+    expect!(path.matches_path_exactly(&["$", "a", "id", "b"])).to(be_false());
+
+    let path = DocPath::new_unwrap("$.a..b[1]");
+    expect!(path.path_weight(&["$", "a", "x", "y", "b", "1"])).to(be_equal_to((16, 6)));
+    expect!(path.path_weight(&["$", "c", "b", "1"]).0).to(be_equal_to(0));
+  }
+
+  #[test]
+  fn filters_do_not_match_paths_without_the_document() {
+    let path = DocPath::new_unwrap("$.items[?(@.type == 'refund')].amount");
+    expect!(path.path_weight(&["$", "items", "0", "amount"]).0).to(be_equal_to(0));
+    expect!(path.has_filters()).to(be_true());
+    expect!(DocPath::new_unwrap("$.items[0]").has_filters()).to(be_false());
+  }
+
+  #[test]
+  fn select_paths_test() {
+    let document = json!({
+      "items": [
+        { "type": "sale", "amount": 100, "id": 1 },
+        { "type": "refund", "amount": 10, "id": 2 },
+        { "type": "refund", "id": 3 }
+      ],
+      "id": 4
+    });
+    let paths = |expr: &'static str| DocPath::new_unwrap(expr).select_paths(&document).iter()
+      .map(|p| p.to_string()).collect::<Vec<_>>();
+    expect!(paths("$.items[?(@.type == 'refund')].amount")).to(be_equal_to(vec!["$.items[1].amount"]));
+    expect!(paths("$.items[?(@.type == 'refund')]")).to(be_equal_to(vec!["$.items[1]", "$.items[2]"]));
+    expect!(paths("$..id")).to(be_equal_to(vec!["$.id", "$.items[0].id", "$.items[1].id", "$.items[2].id"]));
+    expect!(paths("$.items[*].type")).to(be_equal_to(vec!["$.items[0].type", "$.items[1].type", "$.items[2].type"]));
+    expect!(paths("$.missing")).to(be_equal_to(Vec::<String>::new()));
+  }
+
+  #[test]
+  fn resolve_filters_test() {
+    let document = json!({
+      "items": [
+        { "type": "sale", "amount": 100 },
+        { "type": "refund", "amount": 10 },
+        { "type": "refund" }
+      ]
+    });
+    let resolved = DocPath::new_unwrap("$.items[?(@.type == 'refund')].amount").resolve_filters(&document)
+      .iter().map(|p| p.to_string()).collect::<Vec<_>>();
+    expect!(resolved).to(be_equal_to(vec!["$.items[1].amount", "$.items[2].amount"]));
+    let resolved = DocPath::new_unwrap("$.items[*].amount").resolve_filters(&document)
+      .iter().map(|p| p.to_string()).collect::<Vec<_>>();
+    expect!(resolved).to(be_equal_to(vec!["$.items[*].amount"]));
+  }
+
   #[test]
   fn parse_path_exp_handles_empty_string() {
     expect!(parse_path_exp("")).to(be_ok().value(vec![]));
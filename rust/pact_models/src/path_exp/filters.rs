@@ -0,0 +1,446 @@
+//! Filter expressions (`[?(@.type == 'refund')]`) used in path expressions to select the items
+//! of an array (or values of an object) that match a predicate.
+//!
+//! Supported expressions are comparisons between the current item (`@`, optionally followed by
+//! a relative path like `@.type` or `@['item type']`) and a literal value, or a test that the
+//! relative path exists. Comparisons can be combined with `&&` and `||` (`&&` binds tighter).
+//! The operators are `==`, `!=`, `<`, `<=`, `>` and `>=`, and literals can be strings in single
+//! or double quotes, numbers, `true`, `false` or `null`.
+
+use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
+
+use serde_json::Value;
+
+/// Comparison operator in a filter expression
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FilterOperator {
+  /// ==
+  Equal,
+  /// !=
+  NotEqual,
+  /// <
+  LessThan,
+  /// <=
+  LessThanOrEqual,
+  /// >
+  GreaterThan,
+  /// >=
+  GreaterThanOrEqual
+}
+
+impl FilterOperator {
+  fn evaluate(&self, lhs: &Value, rhs: &Value) -> bool {
+    match self {
+      FilterOperator::Equal => values_equal(lhs, rhs),
+      FilterOperator::NotEqual => !values_equal(lhs, rhs),
+      _ => {
+        let ordering = match (lhs, rhs) {
+          (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()),
+          (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+          _ => None
+        };
+        match ordering {
+          Some(ordering) => match self {
+            FilterOperator::LessThan => ordering.is_lt(),
+            FilterOperator::LessThanOrEqual => ordering.is_le(),
+            FilterOperator::GreaterThan => ordering.is_gt(),
+            _ => ordering.is_ge()
+          },
+          None => false
+        }
+      }
+    }
+  }
+}
+
+fn values_equal(lhs: &Value, rhs: &Value) -> bool {
+  match (lhs, rhs) {
+    (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+    _ => lhs == rhs
+  }
+}
+
+impl Display for FilterOperator {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      FilterOperator::Equal => write!(f, "=="),
+      FilterOperator::NotEqual => write!(f, "!="),
+      FilterOperator::LessThan => write!(f, "<"),
+      FilterOperator::LessThanOrEqual => write!(f, "<="),
+      FilterOperator::GreaterThan => write!(f, ">"),
+      FilterOperator::GreaterThanOrEqual => write!(f, ">=")
+    }
+  }
+}
+
+/// Operand of a comparison in a filter expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterOperand {
+  /// Value at the relative path from the current item (`@`, `@.type`, `@.a[0]`)
+  Current(Vec<String>),
+  /// Literal value
+  Literal(Value)
+}
+
+impl FilterOperand {
+  fn resolve<'a>(&'a self, item: &'a Value) -> Option<&'a Value> {
+    match self {
+      FilterOperand::Current(path) => path.iter().try_fold(item, |value, segment| match value {
+        Value::Object(map) => map.get(segment),
+        Value::Array(array) => segment.parse::<usize>().ok().and_then(|index| array.get(index)),
+        _ => None
+      }),
+      FilterOperand::Literal(value) => Some(value)
+    }
+  }
+}
+
+/// Single predicate in a filter expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterPredicate {
+  /// The relative path exists in the current item
+  Exists(FilterOperand),
+  /// Comparison between two operands
+  Compare(FilterOperand, FilterOperator, FilterOperand)
+}
+
+impl FilterPredicate {
+  fn matches(&self, item: &Value) -> bool {
+    match self {
+      FilterPredicate::Exists(operand) => match operand.resolve(item) {
+        Some(Value::Bool(b)) => *b,
+        Some(Value::Null) | None => false,
+        Some(_) => true
+      },
+      FilterPredicate::Compare(lhs, op, rhs) => match (lhs.resolve(item), rhs.resolve(item)) {
+        (Some(lhs), Some(rhs)) => op.evaluate(lhs, rhs),
+        _ => false
+      }
+    }
+  }
+}
+
+/// Parsed filter expression. The predicates are stored as a disjunction of conjunctions
+/// (`a && b || c` is `[[a, b], [c]]`). The original expression is kept so paths with filters
+/// are written back to Pact files in the form they were defined.
+#[derive(Debug, Clone)]
+pub struct FilterExpression {
+  expression: String,
+  predicates: Vec<Vec<FilterPredicate>>
+}
+
+impl FilterExpression {
+  /// Parses the expression from inside the filter brackets (`@.type == 'refund'`)
+  pub fn parse(expression: &str) -> Result<FilterExpression, String> {
+    let mut parser = FilterParser { chars: expression.char_indices().collect(), index: 0, expression };
+    let predicates = parser.parse()?;
+    Ok(FilterExpression {
+      expression: expression.trim().to_string(),
+      predicates
+    })
+  }
+
+  /// The filter expression
+  pub fn expression(&self) -> &str {
+    self.expression.as_str()
+  }
+
+  /// The parsed predicates, as a disjunction of conjunctions
+  pub fn predicates(&self) -> &Vec<Vec<FilterPredicate>> {
+    &self.predicates
+  }
+
+  /// If the item matches the filter
+  pub fn matches(&self, item: &Value) -> bool {
+    self.predicates.iter().any(|predicates| predicates.iter().all(|p| p.matches(item)))
+  }
+}
+
+impl PartialEq for FilterExpression {
+  fn eq(&self, other: &Self) -> bool {
+    self.expression == other.expression
+  }
+}
+
+impl Eq for FilterExpression {}
+
+impl Hash for FilterExpression {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.expression.hash(state);
+  }
+}
+
+impl Display for FilterExpression {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "?({})", self.expression)
+  }
+}
+
+struct FilterParser<'a> {
+  chars: Vec<(usize, char)>,
+  index: usize,
+  expression: &'a str
+}
+
+impl <'a> FilterParser<'a> {
+  fn error(&self, message: &str) -> String {
+    match self.chars.get(self.index) {
+      Some((i, ch)) => format!("{}, found \"{}\" in filter expression \"{}\" at index {}",
+        message, ch, self.expression, i),
+      None => format!("{} in filter expression \"{}\"", message, self.expression)
+    }
+  }
+
+  fn peek(&self) -> Option<char> {
+    self.chars.get(self.index).map(|(_, ch)| *ch)
+  }
+
+  fn skip_whitespace(&mut self) {
+    while self.peek().map(|ch| ch.is_whitespace()).unwrap_or(false) {
+      self.index += 1;
+    }
+  }
+
+  fn consume(&mut self, token: &str) -> bool {
+    let matches = token.chars().enumerate()
+      .all(|(i, ch)| self.chars.get(self.index + i).map(|(_, c)| *c) == Some(ch));
+    if matches {
+      self.index += token.chars().count();
+    }
+    matches
+  }
+
+  // filter -> and_expr ( '||' and_expr )*
+  fn parse(&mut self) -> Result<Vec<Vec<FilterPredicate>>, String> {
+    let mut result = vec![self.and_expression()?];
+    loop {
+      self.skip_whitespace();
+      if self.peek().is_none() {
+        break;
+      } else if self.consume("||") {
+        result.push(self.and_expression()?);
+      } else {
+        return Err(self.error("Expected \"&&\" or \"||\""));
+      }
+    }
+    Ok(result)
+  }
+
+  // and_expr -> predicate ( '&&' predicate )*
+  fn and_expression(&mut self) -> Result<Vec<FilterPredicate>, String> {
+    let mut result = vec![self.predicate()?];
+    loop {
+      self.skip_whitespace();
+      if self.consume("&&") {
+        result.push(self.predicate()?);
+      } else {
+        break;
+      }
+    }
+    Ok(result)
+  }
+
+  // predicate -> operand ( operator operand )?
+  fn predicate(&mut self) -> Result<FilterPredicate, String> {
+    let lhs = self.operand()?;
+    self.skip_whitespace();
+    match self.operator() {
+      Some(op) => {
+        let rhs = self.operand()?;
+        Ok(FilterPredicate::Compare(lhs, op, rhs))
+      }
+      None => match lhs {
+        FilterOperand::Current(_) => Ok(FilterPredicate::Exists(lhs)),
+        FilterOperand::Literal(_) => Err(self.error("Expected a comparison operator"))
+      }
+    }
+  }
+
+  fn operator(&mut self) -> Option<FilterOperator> {
+    if self.consume("==") {
+      Some(FilterOperator::Equal)
+    } else if self.consume("!=") {
+      Some(FilterOperator::NotEqual)
+    } else if self.consume("<=") {
+      Some(FilterOperator::LessThanOrEqual)
+    } else if self.consume(">=") {
+      Some(FilterOperator::GreaterThanOrEqual)
+    } else if self.consume("<") {
+      Some(FilterOperator::LessThan)
+    } else if self.consume(">") {
+      Some(FilterOperator::GreaterThan)
+    } else {
+      None
+    }
+  }
+
+  // operand -> '@' relative_path | string | number | true | false | null
+  fn operand(&mut self) -> Result<FilterOperand, String> {
+    self.skip_whitespace();
+    match self.peek() {
+      Some('@') => {
+        self.index += 1;
+        self.relative_path().map(FilterOperand::Current)
+      }
+      Some(quote) if quote == '\'' || quote == '"' => {
+        self.index += 1;
+        self.string(quote).map(|s| FilterOperand::Literal(Value::String(s)))
+      }
+      Some(ch) if ch == '-' || ch.is_ascii_digit() => self.number().map(FilterOperand::Literal),
+      Some(_) => if self.consume("true") {
+        Ok(FilterOperand::Literal(Value::Bool(true)))
+      } else if self.consume("false") {
+        Ok(FilterOperand::Literal(Value::Bool(false)))
+      } else if self.consume("null") {
+        Ok(FilterOperand::Literal(Value::Null))
+      } else {
+        Err(self.error("Expected \"@\" or a literal value"))
+      },
+      None => Err(self.error("Expected \"@\" or a literal value"))
+    }
+  }
+
+  // relative_path -> ( '.' identifier | '[' (string | number) ']' )*
+  fn relative_path(&mut self) -> Result<Vec<String>, String> {
+    let mut path = vec![];
+    loop {
+      match self.peek() {
+        Some('.') => {
+          self.index += 1;
+          let start = self.index;
+          while self.peek().map(|ch| ch.is_alphanumeric() || ch == '_' || ch == '-').unwrap_or(false) {
+            self.index += 1;
+          }
+          if start == self.index {
+            return Err(self.error("Expected an identifier after \".\""));
+          }
+          path.push(self.chars[start..self.index].iter().map(|(_, ch)| ch).collect());
+        }
+        Some('[') => {
+          self.index += 1;
+          let segment = match self.peek() {
+            Some(quote) if quote == '\'' || quote == '"' => {
+              self.index += 1;
+              self.string(quote)?
+            }
+            Some(ch) if ch.is_ascii_digit() => {
+              let start = self.index;
+              while self.peek().map(|ch| ch.is_ascii_digit()).unwrap_or(false) {
+                self.index += 1;
+              }
+              self.chars[start..self.index].iter().map(|(_, ch)| ch).collect()
+            }
+            _ => return Err(self.error("Expected a string or index"))
+          };
+          if self.peek() != Some(']') {
+            return Err(self.error("Expected \"]\""));
+          }
+          self.index += 1;
+          path.push(segment);
+        }
+        _ => break
+      }
+    }
+    Ok(path)
+  }
+
+  fn string(&mut self, quote: char) -> Result<String, String> {
+    let mut result = String::new();
+    loop {
+      match self.peek() {
+        Some('\\') => {
+          self.index += 1;
+          match self.peek() {
+            Some(ch) => {
+              result.push(ch);
+              self.index += 1;
+            }
+            None => return Err(self.error("Unterminated string"))
+          }
+        }
+        Some(ch) if ch == quote => {
+          self.index += 1;
+          return Ok(result);
+        }
+        Some(ch) => {
+          result.push(ch);
+          self.index += 1;
+        }
+        None => return Err(self.error("Unterminated string"))
+      }
+    }
+  }
+
+  fn number(&mut self) -> Result<Value, String> {
+    let start = self.index;
+    if self.peek() == Some('-') {
+      self.index += 1;
+    }
+    while self.peek().map(|ch| ch.is_ascii_digit() || ch == '.' || ch == 'e' || ch == 'E').unwrap_or(false) {
+      self.index += 1;
+    }
+    let number: String = self.chars[start..self.index].iter().map(|(_, ch)| ch).collect();
+    serde_json::from_str::<serde_json::Number>(&number)
+      .map(Value::Number)
+      .map_err(|_| format!("\"{}\" is not a valid number in filter expression \"{}\"", number, self.expression))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use super::*;
+
+  #[test]
+  fn parse_filter_expressions() {
+    expect!(FilterExpression::parse("@.type == 'refund'").unwrap().predicates).to(be_equal_to(vec![vec![
+      FilterPredicate::Compare(FilterOperand::Current(vec!["type".to_string()]), FilterOperator::Equal,
+        FilterOperand::Literal(json!("refund")))
+    ]]));
+    expect!(FilterExpression::parse("@['item type']!=\"a\" && @.price>=10.5 || @.flag").unwrap().predicates)
+      .to(be_equal_to(vec![
+        vec![
+          FilterPredicate::Compare(FilterOperand::Current(vec!["item type".to_string()]), FilterOperator::NotEqual,
+            FilterOperand::Literal(json!("a"))),
+          FilterPredicate::Compare(FilterOperand::Current(vec!["price".to_string()]),
+            FilterOperator::GreaterThanOrEqual, FilterOperand::Literal(json!(10.5)))
+        ],
+        vec![ FilterPredicate::Exists(FilterOperand::Current(vec!["flag".to_string()])) ]
+      ]));
+    expect!(FilterExpression::parse("@ < -1").unwrap().predicates).to(be_equal_to(vec![vec![
+      FilterPredicate::Compare(FilterOperand::Current(vec![]), FilterOperator::LessThan,
+        FilterOperand::Literal(json!(-1)))
+    ]]));
+  }
+
+  #[test]
+  fn parse_invalid_filter_expressions() {
+    expect!(FilterExpression::parse("")).to(be_err());
+    expect!(FilterExpression::parse("@.type = 'a'")).to(be_err());
+    expect!(FilterExpression::parse("@.type == 'a")).to(be_err());
+    expect!(FilterExpression::parse("'a'")).to(be_err());
+    expect!(FilterExpression::parse("@. == 1")).to(be_err());
+  }
+
+  #[test]
+  fn filter_matches() {
+    let filter = FilterExpression::parse("@.type == 'refund' && @.amount > 10").unwrap();
+    expect!(filter.matches(&json!({ "type": "refund", "amount": 100 }))).to(be_true());
+    expect!(filter.matches(&json!({ "type": "refund", "amount": 1 }))).to(be_false());
+    expect!(filter.matches(&json!({ "type": "sale", "amount": 100 }))).to(be_false());
+    expect!(filter.matches(&json!({ "amount": 100 }))).to(be_false());
+
+    let filter = FilterExpression::parse("@.a[1] == 2.0 || @.b").unwrap();
+    expect!(filter.matches(&json!({ "a": [1, 2] }))).to(be_true());
+    expect!(filter.matches(&json!({ "b": true }))).to(be_true());
+    expect!(filter.matches(&json!({ "b": false }))).to(be_false());
+    expect!(filter.matches(&json!(100))).to(be_false());
+
+    let filter = FilterExpression::parse("@ != 'x'").unwrap();
+    expect!(filter.matches(&json!("y"))).to(be_true());
+    expect!(filter.matches(&json!("x"))).to(be_false());
+  }
+}
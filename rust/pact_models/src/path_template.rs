@@ -0,0 +1,311 @@
+//! Path templates (i.e. `/orders/{id:integer}/items/{itemId:uuid}`). The parameters in a
+//! template are stored in a Pact as matching rules (and generators) for the individual path
+//! segments, which are keyed by the index of the segment (`$[1]` is the second segment of the path).
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use itertools::Itertools;
+
+use crate::expression_parser::DataType;
+use crate::matchingrules::{Category, MatchingRule, MatchingRuleCategory, RuleLogic};
+use crate::path_exp::{DocPath, PathToken};
+
+/// Type of a path template parameter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PathParameterType {
+  /// Any non-empty value
+  String,
+  /// Integer values
+  Integer,
+  /// Decimal values
+  Decimal,
+  /// Any numeric value
+  Number,
+  /// Boolean values
+  Boolean,
+  /// UUIDs in the standard hyphenated form
+  Uuid,
+  /// ISO dates (yyyy-MM-dd)
+  Date
+}
+
+impl PathParameterType {
+  /// Matching rule to apply to path segments of this type
+  pub fn matching_rule(&self) -> MatchingRule {
+    match self {
+      PathParameterType::String => MatchingRule::Regex("^[^/]+$".to_string()),
+      PathParameterType::Integer => MatchingRule::Integer,
+      PathParameterType::Decimal => MatchingRule::Decimal,
+      PathParameterType::Number => MatchingRule::Number,
+      PathParameterType::Boolean => MatchingRule::Boolean,
+      PathParameterType::Uuid => MatchingRule::Regex(
+        "^[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}$".to_string()),
+      PathParameterType::Date => MatchingRule::Date("yyyy-MM-dd".to_string())
+    }
+  }
+
+  /// Example value to use when the template does not provide one
+  pub fn default_example(&self) -> &'static str {
+    match self {
+      PathParameterType::String => "value",
+      PathParameterType::Integer => "100",
+      PathParameterType::Decimal => "100.0",
+      PathParameterType::Number => "100",
+      PathParameterType::Boolean => "true",
+      PathParameterType::Uuid => "e2490de5-5bd3-43d5-b7c4-526e33f71304",
+      PathParameterType::Date => "2000-01-01"
+    }
+  }
+
+  /// Data type for values generated for path segments of this type
+  pub fn data_type(&self) -> DataType {
+    match self {
+      PathParameterType::Integer => DataType::INTEGER,
+      PathParameterType::Decimal => DataType::DECIMAL,
+      PathParameterType::Boolean => DataType::BOOLEAN,
+      _ => DataType::STRING
+    }
+  }
+}
+
+impl FromStr for PathParameterType {
+  type Err = anyhow::Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.to_lowercase().as_str() {
+      "string" => Ok(PathParameterType::String),
+      "integer" | "int" => Ok(PathParameterType::Integer),
+      "decimal" => Ok(PathParameterType::Decimal),
+      "number" => Ok(PathParameterType::Number),
+      "boolean" | "bool" => Ok(PathParameterType::Boolean),
+      "uuid" => Ok(PathParameterType::Uuid),
+      "date" => Ok(PathParameterType::Date),
+      _ => Err(anyhow!("'{}' is not a valid path parameter type", s))
+    }
+  }
+}
+
+impl Display for PathParameterType {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      PathParameterType::String => write!(f, "string"),
+      PathParameterType::Integer => write!(f, "integer"),
+      PathParameterType::Decimal => write!(f, "decimal"),
+      PathParameterType::Number => write!(f, "number"),
+      PathParameterType::Boolean => write!(f, "boolean"),
+      PathParameterType::Uuid => write!(f, "uuid"),
+      PathParameterType::Date => write!(f, "date")
+    }
+  }
+}
+
+/// Segment of a path template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+  /// Literal segment, which must be equal
+  Literal(String),
+  /// Parameter segment (`{name}`, `{name:type}` or `{name:type=example}`)
+  Parameter {
+    /// Name of the parameter
+    name: String,
+    /// Type of the parameter
+    param_type: PathParameterType,
+    /// Example value
+    example: Option<String>
+  }
+}
+
+impl PathSegment {
+  /// Example value for the segment
+  pub fn example(&self) -> String {
+    match self {
+      PathSegment::Literal(value) => value.clone(),
+      PathSegment::Parameter { param_type, example, .. } => example.clone()
+        .unwrap_or_else(|| param_type.default_example().to_string())
+    }
+  }
+}
+
+/// Path template, made up of literal and parameter segments
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathTemplate {
+  /// Segments of the path
+  pub segments: Vec<PathSegment>
+}
+
+impl PathTemplate {
+  /// Parses a path template. Parameters are enclosed in braces, with an optional type and example
+  /// value, i.e. `/orders/{id:integer=100}/items/{itemId:uuid}`. Parameters without a type are
+  /// strings, and parameters without an example use a default example value for the type.
+  pub fn parse(template: &str) -> anyhow::Result<PathTemplate> {
+    let segments = path_segments(template).iter()
+      .map(|segment| parse_segment(segment, template))
+      .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(PathTemplate { segments })
+  }
+
+  /// If the template has any parameter segments
+  pub fn has_parameters(&self) -> bool {
+    self.segments.iter().any(|segment| matches!(segment, PathSegment::Parameter { .. }))
+  }
+
+  /// Example path for the template
+  pub fn to_example(&self) -> String {
+    join_path_segments(self.segments.iter().map(|segment| segment.example()))
+  }
+
+  /// Matching rules for the parameter segments of the template
+  pub fn matching_rules(&self) -> MatchingRuleCategory {
+    let mut category = MatchingRuleCategory::empty(Category::PATH);
+    for (index, segment) in self.segments.iter().enumerate() {
+      if let PathSegment::Parameter { param_type, .. } = segment {
+        category.add_rule(segment_path(index), param_type.matching_rule(), RuleLogic::And);
+      }
+    }
+    category
+  }
+}
+
+impl Display for PathTemplate {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "/{}", self.segments.iter().map(|segment| match segment {
+      PathSegment::Literal(value) => value.clone(),
+      PathSegment::Parameter { name, param_type, example } => match example {
+        Some(example) => format!("{{{}:{}={}}}", name, param_type, example),
+        None => format!("{{{}:{}}}", name, param_type)
+      }
+    }).join("/"))
+  }
+}
+
+fn parse_segment(segment: &str, template: &str) -> anyhow::Result<PathSegment> {
+  if let Some(parameter) = segment.strip_prefix('{') {
+    let parameter = parameter.strip_suffix('}')
+      .ok_or_else(|| anyhow!("Path template '{}' has an unterminated parameter '{}'", template, segment))?;
+    let (definition, example) = match parameter.split_once('=') {
+      Some((definition, example)) => (definition, Some(example.to_string())),
+      None => (parameter, None)
+    };
+    let (name, param_type) = match definition.split_once(':') {
+      Some((name, param_type)) => (name.trim(), param_type.trim().parse()?),
+      None => (definition.trim(), PathParameterType::String)
+    };
+    if name.is_empty() {
+      Err(anyhow!("Path template '{}' has a parameter without a name", template))
+    } else {
+      Ok(PathSegment::Parameter { name: name.to_string(), param_type, example })
+    }
+  } else if segment.contains('{') || segment.contains('}') {
+    Err(anyhow!("Path template parameters must be a complete path segment, got '{}' in '{}'", segment, template))
+  } else {
+    Ok(PathSegment::Literal(segment.to_string()))
+  }
+}
+
+/// Splits the path into its segments. The leading slash is ignored, so the first segment of
+/// `/orders/100` is `orders`.
+pub fn path_segments(path: &str) -> Vec<&str> {
+  path.strip_prefix('/').unwrap_or(path).split('/').collect()
+}
+
+/// Joins path segments back into a path, with a leading slash
+pub fn join_path_segments<S: AsRef<str>>(segments: impl Iterator<Item = S>) -> String {
+  format!("/{}", segments.map(|s| s.as_ref().to_string()).join("/"))
+}
+
+/// Path of the matching rules or generators for the path segment at the given index (i.e. `$[1]`)
+pub fn segment_path(index: usize) -> DocPath {
+  let mut path = DocPath::root();
+  path.push_index(index);
+  path
+}
+
+/// Returns the index of the path segment a matching rule or generator path refers to, or `None` if
+/// it applies to the whole path.
+pub fn segment_index(path: &DocPath) -> Option<usize> {
+  match path.tokens().as_slice() {
+    [PathToken::Root, PathToken::Index(index)] => Some(*index),
+    _ => None
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use maplit::hashmap;
+
+  use serde_json::json;
+
+  use crate::generators::{Generator, GeneratorCategory, Generators, generators_from_json, generators_to_json};
+  use crate::matchingrules::{MatchingRule, MatchingRules, RuleList, matchers_from_json, matchers_to_json};
+  use crate::PactSpecification;
+
+  use super::*;
+
+  #[test]
+  fn parse_path_template() {
+    let template = PathTemplate::parse("/orders/{id:integer}/items/{itemId:uuid=3f5e6f1a-7c0d-4b9e-8d4e-1a2b3c4d5e6f}/{name}").unwrap();
+    expect!(template.segments.clone()).to(be_equal_to(vec![
+      PathSegment::Literal("orders".to_string()),
+      PathSegment::Parameter { name: "id".to_string(), param_type: PathParameterType::Integer, example: None },
+      PathSegment::Literal("items".to_string()),
+      PathSegment::Parameter { name: "itemId".to_string(), param_type: PathParameterType::Uuid,
+        example: Some("3f5e6f1a-7c0d-4b9e-8d4e-1a2b3c4d5e6f".to_string()) },
+      PathSegment::Parameter { name: "name".to_string(), param_type: PathParameterType::String, example: None }
+    ]));
+    expect!(template.has_parameters()).to(be_true());
+    expect!(template.to_example()).to(be_equal_to("/orders/100/items/3f5e6f1a-7c0d-4b9e-8d4e-1a2b3c4d5e6f/value"));
+    expect!(template.to_string()).to(be_equal_to("/orders/{id:integer}/items/{itemId:uuid=3f5e6f1a-7c0d-4b9e-8d4e-1a2b3c4d5e6f}/{name:string}"));
+
+    expect!(PathTemplate::parse("/orders/100").unwrap().has_parameters()).to(be_false());
+  }
+
+  #[test]
+  fn parse_invalid_path_templates() {
+    expect!(PathTemplate::parse("/orders/{id:integer")).to(be_err());
+    expect!(PathTemplate::parse("/orders/{:integer}")).to(be_err());
+    expect!(PathTemplate::parse("/orders/{id:other}")).to(be_err());
+    expect!(PathTemplate::parse("/orders/id-{id}")).to(be_err());
+  }
+
+  #[test]
+  fn path_template_matching_rules() {
+    let template = PathTemplate::parse("/orders/{id:integer}/items/{itemId:uuid}").unwrap();
+    expect!(template.matching_rules().rules).to(be_equal_to(hashmap!{
+      DocPath::new_unwrap("$[1]") => RuleList::new(MatchingRule::Integer),
+      DocPath::new_unwrap("$[3]") => RuleList::new(PathParameterType::Uuid.matching_rule())
+    }));
+  }
+
+  #[test]
+  fn segment_paths() {
+    expect!(path_segments("/orders/100/")).to(be_equal_to(vec!["orders", "100", ""]));
+    expect!(join_path_segments(["orders", "100", ""].iter())).to(be_equal_to("/orders/100/"));
+    expect!(segment_index(&segment_path(2))).to(be_some().value(2));
+    expect!(segment_index(&DocPath::empty())).to(be_none());
+    expect!(segment_index(&DocPath::root())).to(be_none());
+  }
+
+  #[test]
+  fn segment_rules_and_generators_round_trip_through_json() {
+    let mut rules = MatchingRules::default();
+    rules.add_rules(Category::PATH, PathTemplate::parse("/orders/{id:integer}").unwrap().matching_rules());
+    let mut generators = Generators::default();
+    generators.add_generator_with_subcategory(&GeneratorCategory::PATH, segment_path(1),
+      Generator::ProviderStateGenerator("${id}".to_string(), Some(DataType::INTEGER)));
+
+    let rules_json = matchers_to_json(&rules, &PactSpecification::V4);
+    expect!(&rules_json).to(be_equal_to(&json!({
+      "path": { "$[1]": { "combine": "AND", "matchers": [{ "match": "integer" }] } }
+    })));
+    let generators_json = generators_to_json(&generators, &PactSpecification::V4);
+    expect!(&generators_json).to(be_equal_to(&json!({
+      "path": { "$[1]": { "type": "ProviderState", "expression": "${id}", "dataType": "INTEGER" } }
+    })));
+
+    expect!(matchers_from_json(&json!({ "matchingRules": rules_json }), &None).unwrap()).to(be_equal_to(rules));
+    expect!(generators_from_json(&json!({ "generators": generators_json })).unwrap()).to(be_equal_to(generators));
+  }
+}
@@ -82,6 +82,22 @@ impl ProviderState {
     }
   }
 
+  /// Generate the JSON schema properties for the given Pact specification
+  pub fn schema(_spec_version: PactSpecification) -> Value {
+    json!({
+      "properties": {
+        "name": {
+          "type": "string"
+        },
+        "params": {
+          "type": "object"
+        }
+      },
+      "required": ["name"],
+      "type": "object"
+    })
+  }
+
   /// Converts this provider state into a JSON structure
   pub fn to_json(&self) -> Value {
     let mut value = json!({
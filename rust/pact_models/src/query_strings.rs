@@ -3,9 +3,10 @@ use std::str::from_utf8;
 
 use hex::FromHex;
 use itertools::Itertools;
-use serde_json::Value;
+use serde_json::{Map, Value};
 use tracing::{error, trace, warn};
 
+use crate::json_utils::json_to_string;
 use crate::PactSpecification;
 
 /// Decodes a query string using a percent-encoding scheme
@@ -192,6 +193,117 @@ pub fn query_to_json(query: HashMap<String, Vec<Option<String>>>, spec_version:
   }
 }
 
+/// Splits a query parameter name that uses bracket notation into the base name and the keys
+/// (i.e. `filter[tags][]` is split into `filter` and `["tags", ""]`). Returns `None` if the name
+/// does not use bracket notation.
+pub fn parse_bracket_name(name: &str) -> Option<(&str, Vec<&str>)> {
+  let start = name.find('[')?;
+  if start == 0 || !name.ends_with(']') {
+    return None;
+  }
+
+  let base = &name[..start];
+  let mut keys = vec![];
+  let mut remainder = &name[start..];
+  while !remainder.is_empty() {
+    let key = remainder.strip_prefix('[')?;
+    let end = key.find(']')?;
+    keys.push(&key[..end]);
+    remainder = &key[end + 1..];
+  }
+  Some((base, keys))
+}
+
+fn insert_nested_value(tree: &mut Value, keys: &[&str], values: &[String]) {
+  match keys.split_first() {
+    None => {
+      *tree = if values.len() == 1 {
+        Value::String(values[0].clone())
+      } else {
+        Value::Array(values.iter().map(|v| Value::String(v.clone())).collect())
+      };
+    }
+    Some((&"", rest)) => {
+      if !tree.is_array() {
+        *tree = Value::Array(vec![]);
+      }
+      if let Value::Array(items) = tree {
+        for value in values {
+          let mut item = Value::Null;
+          insert_nested_value(&mut item, rest, std::slice::from_ref(value));
+          items.push(item);
+        }
+      }
+    }
+    Some((key, rest)) => {
+      if !tree.is_object() {
+        *tree = Value::Object(Map::new());
+      }
+      if let Value::Object(map) = tree {
+        insert_nested_value(map.entry(key.to_string()).or_insert(Value::Null), rest, values);
+      }
+    }
+  }
+}
+
+/// Decodes the query parameters with the given base name into a nested value. Parameters using
+/// bracket notation (`filter[status]=open&filter[tags][]=a`) are decoded into objects and arrays,
+/// otherwise a single parameter value that is a JSON object or array (`q={"age":{"gt":3}}`) is
+/// decoded as JSON. Returns `None` if there are no parameters that can be decoded.
+pub fn decode_nested_query_param(query: &HashMap<String, Vec<Option<String>>>, name: &str) -> Option<Value> {
+  let bracket_params = query.iter()
+    .filter_map(|(key, values)| parse_bracket_name(key)
+      .filter(|(base, _)| *base == name)
+      .map(|(_, keys)| (key, keys, values)))
+    .sorted_by(|(a, _, _), (b, _, _)| Ord::cmp(a, b))
+    .collect_vec();
+  if bracket_params.is_empty() {
+    match query.get(name).map(|values| values.as_slice()) {
+      Some([Some(value)]) => serde_json::from_str::<Value>(value).ok()
+        .filter(|json| json.is_object() || json.is_array()),
+      _ => None
+    }
+  } else {
+    let mut tree = Value::Null;
+    for (_, keys, values) in bracket_params {
+      let values = values.iter().map(|v| v.clone().unwrap_or_default()).collect_vec();
+      insert_nested_value(&mut tree, &keys, &values);
+    }
+    Some(tree)
+  }
+}
+
+/// Returns the names of the query parameters that decode into the nested value for the base name
+/// (see `decode_nested_query_param`)
+pub fn nested_query_param_names(query: &HashMap<String, Vec<Option<String>>>, name: &str) -> Vec<String> {
+  query.keys()
+    .filter(|key| key.as_str() == name || parse_bracket_name(key)
+      .map(|(base, _)| base == name)
+      .unwrap_or(false))
+    .cloned()
+    .collect()
+}
+
+/// Encodes a nested value as query parameters using bracket notation. Objects are encoded as
+/// `name[key]` and arrays as repeated `name[]` parameters.
+pub fn encode_nested_query_param(name: &str, value: &Value) -> Vec<(String, String)> {
+  let mut params = vec![];
+  encode_nested_value(name, value, &mut params);
+  params
+}
+
+fn encode_nested_value(name: &str, value: &Value, params: &mut Vec<(String, String)>) {
+  match value {
+    Value::Object(map) => for (key, value) in map {
+      encode_nested_value(&format!("{}[{}]", name, key), value, params);
+    },
+    Value::Array(items) => for item in items {
+      encode_nested_value(&format!("{}[]", name), item, params);
+    },
+    _ => params.push((name.to_string(), json_to_string(value)))
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use std::collections::HashMap;
@@ -200,6 +312,7 @@ mod tests {
   use maplit::hashmap;
   use pretty_assertions::assert_eq;
   use rstest::rstest;
+  use serde_json::json;
 
   use crate::query_strings::parse_query_string;
 
@@ -288,4 +401,49 @@ mod tests {
     let result = super::build_query_string(map);
     assert_eq!(result, expected)
   }
+
+  #[test]
+  fn parse_bracket_name_test() {
+    expect!(super::parse_bracket_name("filter[tags][]")).to(be_some().value(("filter", vec!["tags", ""])));
+    expect!(super::parse_bracket_name("filter[status]")).to(be_some().value(("filter", vec!["status"])));
+    expect!(super::parse_bracket_name("filter")).to(be_none());
+    expect!(super::parse_bracket_name("[a]")).to(be_none());
+    expect!(super::parse_bracket_name("a[b]c")).to(be_none());
+  }
+
+  #[test]
+  fn decode_nested_query_param_test() {
+    let query = hashmap!{
+      "filter[status]".to_string() => vec![Some("open".to_string())],
+      "filter[tags][]".to_string() => vec![Some("a".to_string()), Some("b".to_string())],
+      "filter[owner][name]".to_string() => vec![Some("fred".to_string())],
+      "q".to_string() => vec![Some(r#"{"age":{"gt":3}}"#.to_string())],
+      "page".to_string() => vec![Some("1".to_string())]
+    };
+    expect!(super::decode_nested_query_param(&query, "filter")).to(be_some().value(json!({
+      "status": "open",
+      "tags": ["a", "b"],
+      "owner": { "name": "fred" }
+    })));
+    expect!(super::decode_nested_query_param(&query, "q")).to(be_some().value(json!({ "age": { "gt": 3 } })));
+    expect!(super::decode_nested_query_param(&query, "page")).to(be_none());
+    expect!(super::decode_nested_query_param(&query, "other")).to(be_none());
+
+    let mut names = super::nested_query_param_names(&query, "filter");
+    names.sort();
+    expect!(names).to(be_equal_to(vec!["filter[owner][name]", "filter[status]", "filter[tags][]"]));
+  }
+
+  #[test]
+  fn encode_nested_query_param_test() {
+    let params = super::encode_nested_query_param("filter", &json!({
+      "status": "open",
+      "tags": ["a", 2]
+    }));
+    expect!(params).to(be_equal_to(vec![
+      ("filter[status]".to_string(), "open".to_string()),
+      ("filter[tags][]".to_string(), "a".to_string()),
+      ("filter[tags][]".to_string(), "2".to_string())
+    ]));
+  }
 }
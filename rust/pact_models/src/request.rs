@@ -18,6 +18,7 @@ use crate::generators::{Generators, generators_from_json, generators_to_json};
 use crate::http_parts::HttpPart;
 use crate::json_utils::{body_from_json, headers_from_json, headers_to_json};
 use crate::matchingrules::{matchers_from_json, matchers_to_json, MatchingRules};
+use crate::pact::add_matching_rules_and_generators_schema;
 use crate::query_strings::{query_from_json, query_to_json, v3_query_from_json};
 use crate::v4::http_parts::HttpRequest;
 
@@ -175,6 +176,44 @@ impl Request {
     })
   }
 
+  /// Generate the JSON schema properties for the given Pact specification
+  pub fn schema(spec_version: PactSpecification) -> Value {
+    let mut schema = json!({
+      "properties": {
+        "method": {
+          "type": "string"
+        },
+        "path": {
+          "type": "string"
+        },
+        "query": if spec_version < PactSpecification::V3 {
+          json!({ "type": "string" })
+        } else {
+          json!({
+            "additionalProperties": {
+              "items": {
+                "type": "string"
+              },
+              "type": "array"
+            },
+            "type": "object"
+          })
+        },
+        "headers": {
+          "additionalProperties": {
+            "type": "string"
+          },
+          "type": "object"
+        },
+        "body": {}
+      },
+      "required": ["method", "path"],
+      "type": "object"
+    });
+    add_matching_rules_and_generators_schema(&mut schema, spec_version);
+    schema
+  }
+
   /// Converts this `Request` to a `Value` struct.
   pub fn to_json(&self, spec_version: &PactSpecification) -> Value {
     let mut json = json!({
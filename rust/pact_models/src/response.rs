@@ -16,6 +16,7 @@ use crate::generators::{Generators, generators_from_json, generators_to_json};
 use crate::http_parts::HttpPart;
 use crate::json_utils::{body_from_json, headers_from_json, headers_to_json};
 use crate::matchingrules::{matchers_from_json, matchers_to_json, MatchingRules};
+use crate::pact::add_matching_rules_and_generators_schema;
 use crate::v4::http_parts::HttpResponse;
 
 /// Struct that defines the response.
@@ -58,6 +59,30 @@ impl Response {
     Response::default()
   }
 
+  /// Generate the JSON schema properties for the given Pact specification
+  pub fn schema(spec_version: PactSpecification) -> Value {
+    let mut schema = json!({
+      "properties": {
+        "status": {
+          "maximum": 599,
+          "minimum": 100,
+          "type": "integer"
+        },
+        "headers": {
+          "additionalProperties": {
+            "type": "string"
+          },
+          "type": "object"
+        },
+        "body": {}
+      },
+      "required": ["status"],
+      "type": "object"
+    });
+    add_matching_rules_and_generators_schema(&mut schema, spec_version);
+    schema
+  }
+
   /// Converts this response to a `Value` struct.
   #[allow(unused_variables)]
   pub fn to_json(&self, spec_version: &PactSpecification) -> Value {
@@ -240,8 +240,34 @@ impl RequestResponseInteraction {
   }
 
   /// Generate the JSON schema properties for the given Pact specification
-  pub fn schema(_spec_version: PactSpecification) -> Value {
-    json!({})
+  pub fn schema(spec_version: PactSpecification) -> Value {
+    let mut schema = json!({
+      "properties": {
+        "_id": {
+          "type": "string"
+        },
+        "description": {
+          "type": "string"
+        },
+        "request": Request::schema(spec_version),
+        "response": Response::schema(spec_version)
+      },
+      "required": ["description", "request", "response"],
+      "type": "object"
+    });
+    if let Some(properties) = schema.get_mut("properties").and_then(|p| p.as_object_mut()) {
+      if spec_version < PactSpecification::V3 {
+        properties.insert("providerState".to_string(), json!({ "type": "string" }));
+      } else {
+        properties.insert("providerStates".to_string(), json!({
+          "items": {
+            "$ref": "#/definitions/providerState"
+          },
+          "type": "array"
+        }));
+      }
+    }
+    schema
   }
 }
 
@@ -19,7 +19,7 @@ use crate::{Consumer, PactSpecification, Provider};
 use crate::interaction::{Interaction, PactConflict, parse_interactions};
 use crate::iterator_utils::CartesianProductIterator;
 use crate::message_pact::MessagePact;
-use crate::pact::{determine_spec_version, metadata_schema, Pact, parse_meta_data, ReadWritePact, verify_metadata};
+use crate::pact::{determine_spec_version, InteractionRef, Pact, parse_meta_data, ReadWritePact, schema_definitions, verify_metadata};
 use crate::PACT_RUST_VERSION;
 use crate::plugins::PluginData;
 use crate::sync_interaction::RequestResponseInteraction;
@@ -54,6 +54,14 @@ impl Pact for RequestResponsePact {
     self.interactions.iter().map(|i| i.boxed()).collect()
   }
 
+  fn iter_interactions(&self) -> Box<dyn Iterator<Item = InteractionRef<'_>> + '_> {
+    Box::new(self.interactions.iter().map(|i| InteractionRef::Borrowed(i)))
+  }
+
+  fn num_interactions(&self) -> usize {
+    self.interactions.len()
+  }
+
   fn interactions_mut(&mut self) -> Vec<&mut (dyn Interaction + Send + Sync)> {
     self.interactions.iter_mut().map(|m| m as &mut (dyn Interaction + Send + Sync)).collect()
   }
@@ -264,9 +272,7 @@ impl RequestResponsePact {
         "interactions",
         "provider"
       ],
-      "definitions": {
-        "metadata": metadata_schema(spec_version)
-      }
+      "definitions": schema_definitions(spec_version)
     })
   }
 }
@@ -0,0 +1,480 @@
+//! Text body templates (i.e. `Order {{id:integer}} shipped on {{date:date(yyyy-MM-dd)}}`). A
+//! template is stored in a Pact as a regex matching rule with named capture groups for the body,
+//! along with matching rules (and generators) for each capture, which are keyed by the name of the
+//! capture (`$.id` is the capture named `id`). The template regex is also stored under
+//! `TEMPLATE_PATH`, which marks the body as a template (a regex rule with named groups on its own
+//! is matched as a normal regex).
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use itertools::Itertools;
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::generators::{GenerateValue, Generator, NoopVariantMatcher, VariantMatcher};
+use crate::matchingrules::{Category, MatchingRule, MatchingRuleCategory, RuleLogic};
+use crate::path_exp::{DocPath, PathToken};
+
+lazy_static! {
+  static ref CAPTURE_NAME: Regex = Regex::new("^[a-zA-Z_][a-zA-Z0-9_]*$").unwrap();
+}
+
+/// Path of the matching rule that marks a body as a text template. It has the template regex, and
+/// can not clash with a capture as capture names must be identifiers.
+pub const TEMPLATE_PATH: &str = "$['@template']";
+
+/// Regex used to match the value of a capture. The value is then checked using the matching rules
+/// for the capture, so that mismatches can be reported per capture.
+const CAPTURE_VALUE_REGEX: &str = r"[\s\S]*?";
+
+/// Type of a text template capture
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TextCaptureType {
+  /// Any value
+  String,
+  /// Integer values
+  Integer,
+  /// Decimal values
+  Decimal,
+  /// Any numeric value
+  Number,
+  /// Boolean values
+  Boolean,
+  /// UUIDs in the standard hyphenated form
+  Uuid,
+  /// Date-times with the given format
+  DateTime(String),
+  /// Dates with the given format
+  Date(String),
+  /// Times with the given format
+  Time(String),
+  /// Values that match the given regex
+  Regex(String)
+}
+
+impl TextCaptureType {
+  /// Matching rule to apply to captured values of this type
+  pub fn matching_rule(&self) -> MatchingRule {
+    match self {
+      TextCaptureType::String => MatchingRule::Type,
+      TextCaptureType::Integer => MatchingRule::Integer,
+      TextCaptureType::Decimal => MatchingRule::Decimal,
+      TextCaptureType::Number => MatchingRule::Number,
+      TextCaptureType::Boolean => MatchingRule::Boolean,
+      TextCaptureType::Uuid => MatchingRule::Regex(
+        "^[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}$".to_string()),
+      TextCaptureType::DateTime(format) => MatchingRule::Timestamp(format.clone()),
+      TextCaptureType::Date(format) => MatchingRule::Date(format.clone()),
+      TextCaptureType::Time(format) => MatchingRule::Time(format.clone()),
+      TextCaptureType::Regex(regex) => MatchingRule::Regex(regex.clone())
+    }
+  }
+
+  /// Generator to use for captures of this type that do not have an example value
+  pub fn generator(&self) -> Generator {
+    match self {
+      TextCaptureType::String => Generator::RandomString(10),
+      TextCaptureType::Integer | TextCaptureType::Number => Generator::RandomInt(0, 1000),
+      TextCaptureType::Decimal => Generator::RandomDecimal(6),
+      TextCaptureType::Boolean => Generator::RandomBoolean,
+      TextCaptureType::Uuid => Generator::Uuid(None),
+      TextCaptureType::DateTime(format) => Generator::DateTime(Some(format.clone()), None),
+      TextCaptureType::Date(format) => Generator::Date(Some(format.clone()), None),
+      TextCaptureType::Time(format) => Generator::Time(Some(format.clone()), None),
+      TextCaptureType::Regex(regex) => Generator::Regex(regex.clone())
+    }
+  }
+
+  /// Example value to use when the template does not provide one
+  pub fn default_example(&self) -> String {
+    match self {
+      TextCaptureType::String => "value".to_string(),
+      TextCaptureType::Integer | TextCaptureType::Number => "100".to_string(),
+      TextCaptureType::Decimal => "100.0".to_string(),
+      TextCaptureType::Boolean => "true".to_string(),
+      TextCaptureType::Uuid => "e2490de5-5bd3-43d5-b7c4-526e33f71304".to_string(),
+      TextCaptureType::DateTime(format) | TextCaptureType::Date(format) | TextCaptureType::Time(format) =>
+        example_date_time(format),
+      TextCaptureType::Regex(regex) => Generator::Regex(regex.clone())
+        .generate_value(&String::default(), &HashMap::new(), &NoopVariantMatcher.boxed())
+        .unwrap_or_default()
+    }
+  }
+}
+
+/// Formats 2000-01-01 00:00:00 UTC with the given format, so that templates have a stable example
+#[cfg(feature = "datetime")]
+fn example_date_time(format: &str) -> String {
+  use chrono::{TimeZone, Utc};
+  use crate::time_utils::{parse_pattern, to_chrono_pattern};
+
+  match parse_pattern(format) {
+    Ok(tokens) => Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap()
+      .format(to_chrono_pattern(&tokens).as_str()).to_string(),
+    Err(_) => format.to_string()
+  }
+}
+
+#[cfg(not(feature = "datetime"))]
+fn example_date_time(format: &str) -> String {
+  format.to_string()
+}
+
+impl FromStr for TextCaptureType {
+  type Err = anyhow::Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let (name, arg) = match s.split_once('(') {
+      Some((name, arg)) => match arg.strip_suffix(')') {
+        Some(arg) => (name.trim(), Some(arg.to_string())),
+        None => return Err(anyhow!("'{}' is not a valid text template capture type", s))
+      },
+      None => (s.trim(), None)
+    };
+    match (name.to_lowercase().as_str(), arg) {
+      ("string", None) => Ok(TextCaptureType::String),
+      ("integer" | "int", None) => Ok(TextCaptureType::Integer),
+      ("decimal", None) => Ok(TextCaptureType::Decimal),
+      ("number", None) => Ok(TextCaptureType::Number),
+      ("boolean" | "bool", None) => Ok(TextCaptureType::Boolean),
+      ("uuid", None) => Ok(TextCaptureType::Uuid),
+      ("datetime" | "timestamp", format) => Ok(TextCaptureType::DateTime(
+        format.unwrap_or_else(|| "yyyy-MM-dd'T'HH:mm:ss".to_string()))),
+      ("date", format) => Ok(TextCaptureType::Date(format.unwrap_or_else(|| "yyyy-MM-dd".to_string()))),
+      ("time", format) => Ok(TextCaptureType::Time(format.unwrap_or_else(|| "HH:mm:ss".to_string()))),
+      ("regex", Some(regex)) => Ok(TextCaptureType::Regex(regex)),
+      _ => Err(anyhow!("'{}' is not a valid text template capture type", s))
+    }
+  }
+}
+
+impl Display for TextCaptureType {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      TextCaptureType::String => write!(f, "string"),
+      TextCaptureType::Integer => write!(f, "integer"),
+      TextCaptureType::Decimal => write!(f, "decimal"),
+      TextCaptureType::Number => write!(f, "number"),
+      TextCaptureType::Boolean => write!(f, "boolean"),
+      TextCaptureType::Uuid => write!(f, "uuid"),
+      TextCaptureType::DateTime(format) => write!(f, "datetime({})", format),
+      TextCaptureType::Date(format) => write!(f, "date({})", format),
+      TextCaptureType::Time(format) => write!(f, "time({})", format),
+      TextCaptureType::Regex(regex) => write!(f, "regex({})", regex)
+    }
+  }
+}
+
+/// Part of a text template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextTemplatePart {
+  /// Literal text, which must be equal
+  Literal(String),
+  /// Capture (`{{name}}`, `{{name:type}}` or `{{name:type=example}}`)
+  Capture {
+    /// Name of the capture
+    name: String,
+    /// Type of the capture
+    capture_type: TextCaptureType,
+    /// Example value
+    example: Option<String>
+  }
+}
+
+impl TextTemplatePart {
+  /// Example value for the part
+  pub fn example(&self) -> String {
+    match self {
+      TextTemplatePart::Literal(value) => value.clone(),
+      TextTemplatePart::Capture { capture_type, example, .. } => example.clone()
+        .unwrap_or_else(|| capture_type.default_example())
+    }
+  }
+}
+
+/// Text template, made up of literal text and named captures
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextTemplate {
+  /// Parts of the template
+  pub parts: Vec<TextTemplatePart>
+}
+
+impl TextTemplate {
+  /// Parses a text template. Captures are enclosed in double braces, with an optional type and
+  /// example value, i.e. `Order {{id:integer=100}} shipped on {{date:date(yyyy-MM-dd)}}`. Captures
+  /// without a type are strings, and captures without an example use a default example value for
+  /// the type.
+  pub fn parse(template: &str) -> anyhow::Result<TextTemplate> {
+    let mut parts = vec![];
+    let mut remainder = template;
+    while let Some(start) = remainder.find("{{") {
+      if start > 0 {
+        parts.push(TextTemplatePart::Literal(remainder[..start].to_string()));
+      }
+      let capture = &remainder[start + 2..];
+      let end = capture.find("}}")
+        .ok_or_else(|| anyhow!("Text template '{}' has an unterminated capture", template))?;
+      parts.push(parse_capture(&capture[..end], template)?);
+      remainder = &capture[end + 2..];
+    }
+    if !remainder.is_empty() {
+      parts.push(TextTemplatePart::Literal(remainder.to_string()));
+    }
+
+    let names = parts.iter().filter_map(|part| match part {
+      TextTemplatePart::Capture { name, .. } => Some(name),
+      _ => None
+    }).collect_vec();
+    if let Some(name) = names.iter().duplicates().next() {
+      return Err(anyhow!("Text template '{}' has more than one capture named '{}'", template, name));
+    }
+
+    Ok(TextTemplate { parts })
+  }
+
+  /// If the template has any captures
+  pub fn has_captures(&self) -> bool {
+    self.parts.iter().any(|part| matches!(part, TextTemplatePart::Capture { .. }))
+  }
+
+  /// Example text for the template
+  pub fn to_example(&self) -> String {
+    self.parts.iter().map(|part| part.example()).join("")
+  }
+
+  /// Regex for the template, with a named capture group for each capture
+  pub fn to_regex(&self) -> String {
+    let parts = self.parts.iter().map(|part| match part {
+      TextTemplatePart::Literal(value) => regex::escape(value),
+      TextTemplatePart::Capture { name, .. } => format!("(?<{}>{})", name, CAPTURE_VALUE_REGEX)
+    }).join("");
+    format!(r"\A{}\z", parts)
+  }
+
+  /// Matching rules for the template. The regex for the template is applied to the whole body,
+  /// and the rule for each capture type to the captured values. The regex is also added under
+  /// `TEMPLATE_PATH` to mark the body as a template.
+  pub fn matching_rules(&self) -> MatchingRuleCategory {
+    let mut category = MatchingRuleCategory::empty(Category::BODY);
+    category.add_rule(DocPath::root(), MatchingRule::Regex(self.to_regex()), RuleLogic::And);
+    category.add_rule(DocPath::new_unwrap(TEMPLATE_PATH), MatchingRule::Regex(self.to_regex()), RuleLogic::And);
+    for part in &self.parts {
+      if let TextTemplatePart::Capture { name, capture_type, .. } = part {
+        category.add_rule(capture_path(name), capture_type.matching_rule(), RuleLogic::And);
+      }
+    }
+    category
+  }
+
+  /// Generators for the captures that do not have an example value
+  pub fn generators(&self) -> HashMap<DocPath, Generator> {
+    self.parts.iter().filter_map(|part| match part {
+      TextTemplatePart::Capture { name, capture_type, example: None } =>
+        Some((capture_path(name), capture_type.generator())),
+      _ => None
+    }).collect()
+  }
+}
+
+impl Display for TextTemplate {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.parts.iter().map(|part| match part {
+      TextTemplatePart::Literal(value) => value.clone(),
+      TextTemplatePart::Capture { name, capture_type, example } => match example {
+        Some(example) => format!("{{{{{}:{}={}}}}}", name, capture_type, example),
+        None => format!("{{{{{}:{}}}}}", name, capture_type)
+      }
+    }).join(""))
+  }
+}
+
+fn parse_capture(capture: &str, template: &str) -> anyhow::Result<TextTemplatePart> {
+  let (name, definition) = match capture.find([':', '=']) {
+    Some(index) => (capture[..index].trim(), &capture[index..]),
+    None => (capture.trim(), "")
+  };
+  if !CAPTURE_NAME.is_match(name) {
+    return Err(anyhow!("Text template '{}' has an invalid capture name '{}'", template, name));
+  }
+
+  let (capture_type, example) = if let Some(definition) = definition.strip_prefix(':') {
+    // The type argument (i.e. a regex) can contain an equals sign, so only look for the example
+    // after the closing bracket of the argument
+    let type_end = if definition.contains('(') {
+      definition.rfind(')').map(|index| index + 1).unwrap_or(definition.len())
+    } else {
+      0
+    };
+    match definition[type_end..].split_once('=') {
+      Some((suffix, example)) => (format!("{}{}", &definition[..type_end], suffix).trim().parse()?,
+        Some(example.to_string())),
+      None => (definition.trim().parse()?, None)
+    }
+  } else if let Some(example) = definition.strip_prefix('=') {
+    (TextCaptureType::String, Some(example.to_string()))
+  } else {
+    (TextCaptureType::String, None)
+  };
+
+  Ok(TextTemplatePart::Capture { name: name.to_string(), capture_type, example })
+}
+
+/// Path of the matching rules or generators for the capture with the given name (i.e. `$.id`)
+pub fn capture_path(name: &str) -> DocPath {
+  DocPath::root().join(name)
+}
+
+/// Returns the name of the capture a matching rule or generator path refers to, or `None` if it
+/// applies to the whole body.
+pub fn capture_name(path: &DocPath) -> Option<&str> {
+  match path.tokens().as_slice() {
+    [PathToken::Root, PathToken::Field(name)] => Some(name.as_str()),
+    _ => None
+  }
+}
+
+/// Returns the compiled template regex if the matching rules for a body are for a text template
+/// (there is a regex rule under `TEMPLATE_PATH`).
+pub fn template_regex(rules: &MatchingRuleCategory) -> Option<Regex> {
+  rules.rules.get(&DocPath::new_unwrap(TEMPLATE_PATH))?
+    .rules.iter()
+    .find_map(|rule| match rule {
+      MatchingRule::Regex(regex) => Regex::new(regex).ok(),
+      _ => None
+    })
+}
+
+/// Names of the captures of the template regex, in the order they appear in the template
+pub fn capture_names(regex: &Regex) -> Vec<String> {
+  regex.capture_names().flatten().map(|name| name.to_string()).collect()
+}
+
+/// Applies the template regex to the text, returning the value of each named capture in the order
+/// they appear in the template (`None` if the capture did not match anything). Returns `None` if
+/// the text does not match the template.
+pub fn capture_values(regex: &Regex, text: &str) -> Option<Vec<(String, Option<String>)>> {
+  regex.captures(text).map(|captures| {
+    capture_names(regex).into_iter()
+      .map(|name| {
+        let value = captures.name(&name).map(|m| m.as_str().to_string());
+        (name, value)
+      })
+      .collect()
+  })
+}
+
+/// Replaces the values of the named captures in the text. Returns `None` if the text does not
+/// match the template.
+pub fn replace_capture_values(regex: &Regex, text: &str, values: &HashMap<String, String>) -> Option<String> {
+  regex.captures(text).map(|captures| {
+    let mut result = String::new();
+    let mut last = 0;
+    let groups = regex.capture_names()
+      .flatten()
+      .filter_map(|name| captures.name(name).map(|m| (name, m)))
+      .sorted_by_key(|(_, m)| m.start());
+    for (name, m) in groups {
+      if let Some(value) = values.get(name) {
+        result.push_str(&text[last..m.start()]);
+        result.push_str(value);
+        last = m.end();
+      }
+    }
+    result.push_str(&text[last..]);
+    result
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use maplit::hashmap;
+  use serde_json::json;
+
+  use crate::generators::{GeneratorCategory, Generators, generators_from_json, generators_to_json};
+  use crate::matchingrules::{MatchingRules, matchers_from_json, matchers_to_json};
+  use crate::PactSpecification;
+
+  use super::*;
+
+  #[test]
+  fn parse_text_template() {
+    let template = TextTemplate::parse("Order {{id:integer}} shipped on {{date:date(yyyy-MM-dd)=2024-01-31}} to {{name}}").unwrap();
+    expect!(template.parts.clone()).to(be_equal_to(vec![
+      TextTemplatePart::Literal("Order ".to_string()),
+      TextTemplatePart::Capture { name: "id".to_string(), capture_type: TextCaptureType::Integer, example: None },
+      TextTemplatePart::Literal(" shipped on ".to_string()),
+      TextTemplatePart::Capture { name: "date".to_string(), capture_type: TextCaptureType::Date("yyyy-MM-dd".to_string()),
+        example: Some("2024-01-31".to_string()) },
+      TextTemplatePart::Literal(" to ".to_string()),
+      TextTemplatePart::Capture { name: "name".to_string(), capture_type: TextCaptureType::String, example: None }
+    ]));
+    expect!(template.has_captures()).to(be_true());
+    expect!(template.to_example()).to(be_equal_to("Order 100 shipped on 2024-01-31 to value"));
+    expect!(template.to_string()).to(be_equal_to("Order {{id:integer}} shipped on {{date:date(yyyy-MM-dd)=2024-01-31}} to {{name:string}}"));
+
+    expect!(TextTemplate::parse("{{code:regex(^[A-Z]{2}=\\d+$)=AB=12}}").unwrap().parts).to(be_equal_to(vec![
+      TextTemplatePart::Capture { name: "code".to_string(), capture_type: TextCaptureType::Regex("^[A-Z]{2}=\\d+$".to_string()),
+        example: Some("AB=12".to_string()) }
+    ]));
+    expect!(TextTemplate::parse("{{name=Fred}}").unwrap().parts).to(be_equal_to(vec![
+      TextTemplatePart::Capture { name: "name".to_string(), capture_type: TextCaptureType::String,
+        example: Some("Fred".to_string()) }
+    ]));
+    expect!(TextTemplate::parse("No captures").unwrap().has_captures()).to(be_false());
+  }
+
+  #[test]
+  fn parse_invalid_text_templates() {
+    expect!(TextTemplate::parse("Order {{id:integer")).to(be_err());
+    expect!(TextTemplate::parse("Order {{:integer}}")).to(be_err());
+    expect!(TextTemplate::parse("Order {{order-id}}")).to(be_err());
+    expect!(TextTemplate::parse("Order {{id:other}}")).to(be_err());
+    expect!(TextTemplate::parse("{{id}} and {{id}}")).to(be_err());
+  }
+
+  #[test]
+  fn text_template_regex_and_captures() {
+    let template = TextTemplate::parse("Order {{id:integer}} (total ${{total:decimal}})").unwrap();
+    let regex_str = template.to_regex();
+    expect!(regex_str.as_str()).to(be_equal_to(r"\AOrder (?<id>[\s\S]*?) \(total \$(?<total>[\s\S]*?)\)\z"));
+
+    let regex = template_regex(&template.matching_rules()).unwrap();
+    expect!(regex.as_str()).to(be_equal_to(regex_str.as_str()));
+    expect!(capture_names(&regex)).to(be_equal_to(vec!["id".to_string(), "total".to_string()]));
+    expect!(capture_values(&regex, "Order 1234 (total $10.50)")).to(be_some().value(vec![
+      ("id".to_string(), Some("1234".to_string())),
+      ("total".to_string(), Some("10.50".to_string()))
+    ]));
+    expect!(capture_values(&regex, "Invoice 1234")).to(be_none());
+    expect!(replace_capture_values(&regex, "Order 1234 (total $10.50)", &hashmap!{ "id".to_string() => "99".to_string() }))
+      .to(be_some().value("Order 99 (total $10.50)"));
+
+    let mut rules = MatchingRuleCategory::empty(Category::BODY);
+    rules.add_rule(DocPath::root(), MatchingRule::Regex(r"\A(?<id>\d+)\z".to_string()), RuleLogic::And);
+    expect!(template_regex(&rules)).to(be_none());
+  }
+
+  #[test]
+  fn text_template_rules_and_generators_round_trip_through_json() {
+    let template = TextTemplate::parse("Order {{id:integer}} for {{name=Fred}}").unwrap();
+    let mut rules = MatchingRules::default();
+    rules.add_rules(Category::BODY, template.matching_rules());
+    let mut generators = Generators::default();
+    for (path, generator) in template.generators() {
+      generators.add_generator_with_subcategory(&GeneratorCategory::BODY, path, generator);
+    }
+
+    let generators_json = generators_to_json(&generators, &PactSpecification::V4);
+    expect!(&generators_json).to(be_equal_to(&json!({
+      "body": { "$.id": { "type": "RandomInt", "min": 0, "max": 1000 } }
+    })));
+
+    let rules_json = matchers_to_json(&rules, &PactSpecification::V4);
+    expect!(matchers_from_json(&json!({ "matchingRules": rules_json }), &None).unwrap()).to(be_equal_to(rules));
+    expect!(generators_from_json(&json!({ "generators": generators_json })).unwrap()).to(be_equal_to(generators));
+  }
+}
@@ -25,7 +25,7 @@ use crate::PactSpecification;
 use crate::provider_states::ProviderState;
 use crate::sync_interaction::RequestResponseInteraction;
 use crate::v4::http_parts::body_from_json;
-use crate::v4::interaction::{InteractionMarkup, parse_plugin_config, V4Interaction};
+use crate::v4::interaction::{interaction_schema, InteractionMarkup, parse_plugin_config, V4Interaction};
 use crate::v4::message_parts::{MessageContents, metadata_to_headers};
 use crate::v4::sync_message::SynchronousMessage;
 use crate::v4::synch_http::SynchronousHttp;
@@ -62,6 +62,24 @@ pub struct AsynchronousMessage {
 }
 
 impl AsynchronousMessage {
+  /// Generate the JSON schema properties for the V4 specification
+  pub fn schema() -> Value {
+    interaction_schema(V4InteractionType::Asynchronous_Messages, json!({
+        "contents": {
+          "$ref": "#/definitions/body"
+        },
+        "metadata": {
+          "type": "object"
+        },
+        "matchingRules": {
+          "$ref": "#/definitions/matchingRules"
+        },
+        "generators": {
+          "$ref": "#/definitions/generators"
+        }
+      }), &[])
+  }
+
   fn calc_hash(&self) -> String {
     let mut s = DefaultHasher::new();
     self.hash(&mut s);
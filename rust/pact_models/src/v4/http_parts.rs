@@ -78,6 +78,56 @@ impl HttpRequest {
     })
   }
 
+  /// Generate the JSON schema properties for the V4 specification
+  pub fn schema() -> Value {
+    json!({
+      "properties": {
+        "method": {
+          "type": "string"
+        },
+        "path": {
+          "type": "string"
+        },
+        "query": {
+          "additionalProperties": {
+            "items": {
+              "type": "string"
+            },
+            "type": "array"
+          },
+          "type": "object"
+        },
+        "headers": {
+          "additionalProperties": {
+            "oneOf": [
+              {
+                "type": "string"
+              },
+              {
+                "items": {
+                  "type": "string"
+                },
+                "type": "array"
+              }
+            ]
+          },
+          "type": "object"
+        },
+        "body": {
+          "$ref": "#/definitions/body"
+        },
+        "matchingRules": {
+          "$ref": "#/definitions/matchingRules"
+        },
+        "generators": {
+          "$ref": "#/definitions/generators"
+        }
+      },
+      "required": ["method", "path"],
+      "type": "object"
+    })
+  }
+
   /// Converts this `HttpRequest` to a `Value` struct.
   pub fn to_json(&self) -> Value {
     let mut json = json!({
@@ -507,6 +557,46 @@ impl HttpResponse {
     })
   }
 
+  /// Generate the JSON schema properties for the V4 specification
+  pub fn schema() -> Value {
+    json!({
+      "properties": {
+        "status": {
+          "maximum": 599,
+          "minimum": 100,
+          "type": "integer"
+        },
+        "headers": {
+          "additionalProperties": {
+            "oneOf": [
+              {
+                "type": "string"
+              },
+              {
+                "items": {
+                  "type": "string"
+                },
+                "type": "array"
+              }
+            ]
+          },
+          "type": "object"
+        },
+        "body": {
+          "$ref": "#/definitions/body"
+        },
+        "matchingRules": {
+          "$ref": "#/definitions/matchingRules"
+        },
+        "generators": {
+          "$ref": "#/definitions/generators"
+        }
+      },
+      "required": ["status"],
+      "type": "object"
+    })
+  }
+
   /// Converts this response to a `Value` struct.
   pub fn to_json(&self) -> Value {
     let mut json = json!({
@@ -54,6 +54,22 @@ impl InteractionMarkup {
     })
   }
 
+  /// Generate the JSON schema properties for the V4 specification
+  pub fn schema() -> Value {
+    json!({
+      "properties": {
+        "markup": {
+          "type": "string"
+        },
+        "markupType": {
+          "enum": ["COMMON_MARK", "HTML"]
+        }
+      },
+      "required": ["markup"],
+      "type": "object"
+    })
+  }
+
   /// Merges this markup with the other
   pub fn merge(&self, other: InteractionMarkup) -> InteractionMarkup {
     if self.is_empty() {
@@ -76,6 +92,77 @@ impl InteractionMarkup {
   }
 }
 
+/// Generate the JSON schema for a V4 interaction of the given type. The attributes common to
+/// all interactions are added to the given properties.
+pub(crate) fn interaction_schema(
+  interaction_type: V4InteractionType,
+  properties: Value,
+  required: &[&str]
+) -> Value {
+  let mut schema = json!({
+    "properties": {
+      "_id": {
+        "type": "string"
+      },
+      "type": {
+        "const": interaction_type.to_string()
+      },
+      "key": {
+        "type": "string"
+      },
+      "description": {
+        "type": "string"
+      },
+      "providerStates": {
+        "items": {
+          "$ref": "#/definitions/providerState"
+        },
+        "type": "array"
+      },
+      "comments": {
+        "properties": {
+          "text": {
+            "items": {
+              "type": "string"
+            },
+            "type": "array"
+          },
+          "testname": {
+            "type": "string"
+          }
+        },
+        "type": "object"
+      },
+      "pending": {
+        "type": "boolean"
+      },
+      "pluginConfiguration": {
+        "additionalProperties": {
+          "type": "object"
+        },
+        "type": "object"
+      },
+      "interactionMarkup": {
+        "$ref": "#/definitions/interactionMarkup"
+      },
+      "transport": {
+        "type": "string"
+      }
+    },
+    "required": ["type", "description"],
+    "type": "object"
+  });
+  if let Some(map) = schema.get_mut("properties").and_then(|p| p.as_object_mut()) {
+    if let Value::Object(properties) = properties {
+      map.extend(properties);
+    }
+  }
+  if let Some(Value::Array(list)) = schema.get_mut("required") {
+    list.extend(required.iter().map(|attr| Value::String(attr.to_string())));
+  }
+  schema
+}
+
 /// V4 Interaction trait
 pub trait V4Interaction: Interaction + Send + Sync {
   /// Convert the interaction to a JSON Value
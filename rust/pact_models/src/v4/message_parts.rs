@@ -55,6 +55,27 @@ impl MessageContents {
     }
   }
 
+  /// Generate the JSON schema properties for the V4 specification
+  pub fn schema() -> Value {
+    json!({
+      "properties": {
+        "contents": {
+          "$ref": "#/definitions/body"
+        },
+        "metadata": {
+          "type": "object"
+        },
+        "matchingRules": {
+          "$ref": "#/definitions/matchingRules"
+        },
+        "generators": {
+          "$ref": "#/definitions/generators"
+        }
+      },
+      "type": "object"
+    })
+  }
+
   /// Convert this message part into a JSON struct
   pub fn to_json(&self) -> Value {
     let mut json = json!({});
@@ -14,15 +14,21 @@ use serde_json::{json, Map, Value};
 use tracing::{trace, warn};
 
 use crate::{Consumer, PactSpecification, Provider};
+use crate::bodies::OptionalBody;
 #[cfg(not(target_family = "wasm"))] use crate::file_utils::with_read_lock;
 use crate::interaction::Interaction;
 use crate::json_utils::json_to_string;
 use crate::message_pact::MessagePact;
-use crate::pact::{Pact, ReadWritePact};
+use crate::pact::{InteractionRef, Pact, ReadWritePact, schema_definitions};
 use crate::PACT_RUST_VERSION;
 use crate::plugins::PluginData;
 use crate::sync_pact::RequestResponsePact;
-use crate::v4::interaction::{interactions_from_json, V4Interaction};
+use crate::v4::async_message::AsynchronousMessage;
+use crate::v4::http_parts::{HttpRequest, HttpResponse};
+use crate::v4::interaction::{InteractionMarkup, interactions_from_json, V4Interaction};
+use crate::v4::message_parts::MessageContents;
+use crate::v4::sync_message::SynchronousMessage;
+use crate::v4::synch_http::SynchronousHttp;
 use crate::v4::V4InteractionType;
 use crate::verify_json::{json_type_of, PactFileVerificationResult, PactJsonVerifier, ResultLevel};
 
@@ -140,6 +146,59 @@ impl V4Pact {
       )
   }
 
+  /// Generate the JSON schema properties for the V4 specification
+  pub fn schema() -> Value {
+    let spec_version = PactSpecification::V4;
+    let mut definitions = schema_definitions(spec_version);
+    definitions.insert("body".to_string(), OptionalBody::schema(spec_version));
+    definitions.insert("httpRequest".to_string(), HttpRequest::schema());
+    definitions.insert("httpResponse".to_string(), HttpResponse::schema());
+    definitions.insert("messageContents".to_string(), MessageContents::schema());
+    definitions.insert("interactionMarkup".to_string(), InteractionMarkup::schema());
+    definitions.insert("synchronousHttp".to_string(), SynchronousHttp::schema());
+    definitions.insert("asynchronousMessage".to_string(), AsynchronousMessage::schema());
+    definitions.insert("synchronousMessage".to_string(), SynchronousMessage::schema());
+
+    let interaction_types = [
+      (V4InteractionType::Synchronous_HTTP, "synchronousHttp"),
+      (V4InteractionType::Asynchronous_Messages, "asynchronousMessage"),
+      (V4InteractionType::Synchronous_Messages, "synchronousMessage")
+    ];
+    json!({
+      "properties": {
+        "consumer": Consumer::schema(spec_version),
+        "interactions": {
+          "description": "The interactions between the consumer and provider",
+          "type": "array",
+          "items": {
+            "properties": {
+              "type": {
+                "enum": interaction_types.iter().map(|(t, _)| t.to_string()).collect::<Vec<_>>()
+              }
+            },
+            "required": ["type"],
+            "allOf": interaction_types.iter().map(|(t, definition)| json!({
+              "if": { "properties": { "type": { "const": t.to_string() } } },
+              "then": { "$ref": format!("#/definitions/{}", definition) }
+            })).collect::<Vec<_>>(),
+            "type": "object"
+          }
+        },
+        "metadata": {
+          "description": "Metadata associated with the Pact file",
+          "$ref": "#/definitions/metadata"
+        },
+        "provider": Provider::schema(spec_version)
+      },
+      "required": [
+        "consumer",
+        "interactions",
+        "provider"
+      ],
+      "definitions": definitions
+    })
+  }
+
   /// Parses a JSON value into a V4 Pact model
   pub fn pact_from_json(json: &Value, source: &str) -> anyhow::Result<V4Pact> {
     let mut metadata = meta_data_from_json(&json);
@@ -178,6 +237,14 @@ impl Pact for V4Pact {
     self.interactions.iter().map(|i| i.boxed()).collect()
   }
 
+  fn iter_interactions(&self) -> Box<dyn Iterator<Item = InteractionRef<'_>> + '_> {
+    Box::new(self.interactions.iter().map(|i| InteractionRef::Borrowed(i.to_super())))
+  }
+
+  fn num_interactions(&self) -> usize {
+    self.interactions.len()
+  }
+
   fn interactions_mut(&mut self) -> Vec<&mut (dyn Interaction + Send + Sync)> {
     self.interactions.iter_mut().map(|i| i.to_super_mut()).collect()
   }
@@ -21,7 +21,7 @@ use crate::message::Message;
 use crate::provider_states::ProviderState;
 use crate::sync_interaction::RequestResponseInteraction;
 use crate::v4::async_message::AsynchronousMessage;
-use crate::v4::interaction::{InteractionMarkup, parse_plugin_config, V4Interaction};
+use crate::v4::interaction::{interaction_schema, InteractionMarkup, parse_plugin_config, V4Interaction};
 use crate::v4::message_parts::MessageContents;
 use crate::v4::synch_http::SynchronousHttp;
 use crate::v4::V4InteractionType;
@@ -59,6 +59,21 @@ pub struct SynchronousMessage {
 }
 
 impl SynchronousMessage {
+  /// Generate the JSON schema properties for the V4 specification
+  pub fn schema() -> Value {
+    interaction_schema(V4InteractionType::Synchronous_Messages, json!({
+        "request": {
+          "$ref": "#/definitions/messageContents"
+        },
+        "response": {
+          "items": {
+            "$ref": "#/definitions/messageContents"
+          },
+          "type": "array"
+        }
+      }), &["request"])
+  }
+
   fn calc_hash(&self) -> String {
     let mut s = DefaultHasher::new();
     self.hash(&mut s);
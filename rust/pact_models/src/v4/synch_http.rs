@@ -21,7 +21,7 @@ use crate::provider_states::ProviderState;
 use crate::sync_interaction::RequestResponseInteraction;
 use crate::v4::async_message::AsynchronousMessage;
 use crate::v4::http_parts::{HttpRequest, HttpResponse};
-use crate::v4::interaction::{InteractionMarkup, parse_plugin_config, V4Interaction};
+use crate::v4::interaction::{interaction_schema, InteractionMarkup, parse_plugin_config, V4Interaction};
 use crate::v4::sync_message::SynchronousMessage;
 use crate::v4::V4InteractionType;
 
@@ -58,6 +58,18 @@ pub struct SynchronousHttp {
 }
 
 impl SynchronousHttp {
+  /// Generate the JSON schema properties for the V4 specification
+  pub fn schema() -> Value {
+    interaction_schema(V4InteractionType::Synchronous_HTTP, json!({
+        "request": {
+          "$ref": "#/definitions/httpRequest"
+        },
+        "response": {
+          "$ref": "#/definitions/httpResponse"
+        }
+      }), &["request", "response"])
+  }
+
   fn calc_hash(&self) -> String {
     let mut s = DefaultHasher::new();
     self.hash(&mut s);
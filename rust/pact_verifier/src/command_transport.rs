@@ -0,0 +1,253 @@
+//! Message transport that runs a command to get the messages from the provider. The message
+//! request (the interaction description, provider states and, for synchronous messages, the
+//! request message) is written as JSON to the standard input of the command, and is also available
+//! in the `PACT_MESSAGE_DESCRIPTION` and `PACT_PROVIDER_STATES` environment variables. The
+//! command must write the message to standard output as a JSON object in the form
+//! `{ "contents": <payload>, "metadata": { ... } }`. For synchronous messages, an array of these
+//! objects can be written to return multiple response messages.
+
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use bytes::Bytes;
+use pact_models::bodies::OptionalBody;
+use pact_models::content_types::{ContentType, JSON};
+use pact_models::v4::message_parts::MessageContents;
+use serde_json::Value;
+use tokio::io::AsyncWriteExt;
+use tracing::debug;
+
+use crate::ProviderTransport;
+use crate::provider_process::{kill_process_group, new_process_group, shell_command};
+
+/// Environment variable that the interaction description is passed to the command in
+pub(crate) const DESCRIPTION_ENV_VAR: &str = "PACT_MESSAGE_DESCRIPTION";
+/// Environment variable that the provider states are passed to the command in (as JSON)
+pub(crate) const PROVIDER_STATES_ENV_VAR: &str = "PACT_PROVIDER_STATES";
+
+/// Runs the command configured for the transport with the message request, and returns the
+/// messages that the command wrote to standard output
+pub(crate) async fn fetch_messages(
+  transport: &ProviderTransport,
+  message_request: &Value,
+  timeout: Duration
+) -> anyhow::Result<Vec<MessageContents>> {
+  let command_line = transport.command.as_deref()
+    .filter(|command| !command.trim().is_empty())
+    .ok_or_else(|| anyhow!("The transport '{}' does not have a command configured", transport.transport))?;
+
+  let description = message_request.get("description")
+    .and_then(|description| description.as_str())
+    .unwrap_or_default();
+  let provider_states = message_request.get("providerStates")
+    .cloned()
+    .unwrap_or_else(|| Value::Array(vec![]));
+  debug!(command = command_line, description, "Running message command");
+
+  let mut command = shell_command(command_line);
+  command
+    .env(DESCRIPTION_ENV_VAR, description)
+    .env(PROVIDER_STATES_ENV_VAR, provider_states.to_string())
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .kill_on_drop(true);
+  // Run the command in its own process group, so any processes started by the shell can be killed
+  // if it times out
+  new_process_group(&mut command);
+  let mut child = command.spawn()
+    .map_err(|err| anyhow!("Failed to run the message command '{}': {}", command_line, err))?;
+  let pid = child.id();
+
+  // The input is written while the output is being read, as the command may write its output
+  // before reading all its input (or not read it at all)
+  let stdin = child.stdin.take();
+  let input = message_request.to_string();
+  let write_input = async move {
+    if let Some(mut stdin) = stdin {
+      // The command may not read its input, so a failure to write to it is ignored
+      if let Err(err) = stdin.write_all(input.as_bytes()).await {
+        debug!("Failed to write the message request to the command: {}", err);
+      }
+    }
+  };
+  let run_command = async move {
+    let (_, output) = tokio::join!(write_input, child.wait_with_output());
+    output
+  };
+
+  let output = match tokio::time::timeout(timeout, run_command).await {
+    Ok(output) => output
+      .map_err(|err| anyhow!("Failed to run the message command '{}': {}", command_line, err))?,
+    Err(_) => {
+      kill_process_group(pid);
+      return Err(anyhow!("The message command '{}' did not complete within {} ms", command_line, timeout.as_millis()));
+    }
+  };
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  if !stderr.trim().is_empty() {
+    debug!("Message command stderr:\n{}", stderr);
+  }
+
+  if !output.status.success() {
+    return if stderr.trim().is_empty() {
+      Err(anyhow!("The message command '{}' failed with {}", command_line, output.status))
+    } else {
+      Err(anyhow!("The message command '{}' failed with {}: {}", command_line, output.status, stderr.trim()))
+    };
+  }
+
+  let json: Value = serde_json::from_slice(&output.stdout)
+    .with_context(|| format!("The message command '{}' did not write a JSON message to standard output", command_line))?;
+  match json {
+    Value::Array(messages) => messages.iter().map(message_from_json).collect(),
+    _ => Ok(vec![message_from_json(&json)?])
+  }
+}
+
+fn message_from_json(json: &Value) -> anyhow::Result<MessageContents> {
+  let message = json.as_object()
+    .ok_or_else(|| anyhow!("Expected the message to be a JSON object with contents and metadata, but got '{}'", json))?;
+  let metadata: HashMap<String, Value> = match message.get("metadata") {
+    Some(Value::Object(metadata)) => metadata.iter()
+      .map(|(k, v)| (k.clone(), v.clone()))
+      .collect(),
+    Some(Value::Null) | None => Default::default(),
+    Some(metadata) => return Err(anyhow!("Expected the message metadata to be a JSON object, but got '{}'", metadata))
+  };
+  let content_type = metadata.get("contentType")
+    .or_else(|| metadata.get("content-type"))
+    .and_then(|ct| ct.as_str())
+    .and_then(|ct| ContentType::parse(ct).ok())
+    .unwrap_or_else(|| JSON.clone());
+
+  let contents = match message.get("contents") {
+    None => OptionalBody::Missing,
+    Some(Value::Null) => OptionalBody::Null,
+    Some(Value::String(contents)) if !content_type.is_json() =>
+      OptionalBody::Present(Bytes::from(contents.clone()), Some(content_type), None),
+    Some(contents) => OptionalBody::Present(Bytes::from(contents.to_string()), Some(content_type), None)
+  };
+
+  Ok(MessageContents {
+    contents,
+    metadata,
+    .. MessageContents::default()
+  })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::COMMAND_SCHEME;
+
+  use super::*;
+
+  fn transport(command: &str) -> ProviderTransport {
+    ProviderTransport {
+      transport: "message".to_string(),
+      port: None,
+      scheme: Some(COMMAND_SCHEME.to_string()),
+      command: Some(command.to_string()),
+      .. ProviderTransport::default()
+    }
+  }
+
+  fn request() -> Value {
+    json!({
+      "description": "a user created event",
+      "providerStates": [ { "name": "user exists" } ]
+    })
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn returns_the_message_written_by_the_command() {
+    let command = r#"cat > /dev/null; echo "{\"contents\": {\"id\": 100, \"description\": \"$PACT_MESSAGE_DESCRIPTION\"}, \"metadata\": {\"topic\": \"users\"}}""#;
+    let messages = fetch_messages(&transport(command), &request(), Duration::from_secs(5)).await.unwrap();
+
+    expect!(messages.len()).to(be_equal_to(1));
+    let message = &messages[0];
+    let contents: Value = serde_json::from_slice(&message.contents.value().unwrap()).unwrap();
+    expect!(contents).to(be_equal_to(json!({ "id": 100, "description": "a user created event" })));
+    expect!(message.contents.content_type()).to(be_some().value(JSON.clone()));
+    expect!(message.metadata.get("topic")).to(be_some().value(&json!("users")));
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn passes_the_message_request_on_stdin() {
+    let command = r#"echo "[{\"contents\": $(cat)}, {\"contents\": \"hello\", \"metadata\": {\"contentType\": \"text/plain\"}}]""#;
+    let messages = fetch_messages(&transport(command), &request(), Duration::from_secs(5)).await.unwrap();
+
+    expect!(messages.len()).to(be_equal_to(2));
+    let contents: Value = serde_json::from_slice(&messages[0].contents.value().unwrap()).unwrap();
+    expect!(contents).to(be_equal_to(request()));
+    expect!(messages[1].contents.value_as_string()).to(be_some().value("hello"));
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn returns_an_error_with_stderr_if_the_command_fails() {
+    let command = "echo 'no such message' >&2; exit 2";
+    let result = fetch_messages(&transport(command), &request(), Duration::from_secs(5)).await;
+    expect!(result.unwrap_err().to_string()).to(be_equal_to(
+      format!("The message command '{}' failed with exit status: 2: no such message", command)));
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn returns_an_error_if_the_command_times_out() {
+    let result = fetch_messages(&transport("sleep 10"), &request(), Duration::from_millis(200)).await;
+    expect!(result.unwrap_err().to_string()).to(be_equal_to("The message command 'sleep 10' did not complete within 200 ms"));
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn times_out_if_the_command_does_not_read_a_large_message_request() {
+    let request = json!({
+      "description": "a large message",
+      "request": { "contents": "x".repeat(1024 * 1024) }
+    });
+    let start = std::time::Instant::now();
+    let result = fetch_messages(&transport("sleep 10"), &request, Duration::from_millis(200)).await;
+    expect!(result.unwrap_err().to_string()).to(be_equal_to("The message command 'sleep 10' did not complete within 200 ms"));
+    expect!(start.elapsed() < Duration::from_secs(5)).to(be_true());
+  }
+
+  #[cfg(target_os = "linux")]
+  #[test_log::test(tokio::test)]
+  async fn kills_the_processes_started_by_the_command_if_it_times_out() {
+    let pid_file = std::env::temp_dir().join(format!("pact-message-command-{}.pid", std::process::id()));
+    let command = format!("sleep 30 & echo $! > {}; wait", pid_file.display());
+    let result = fetch_messages(&transport(command.as_str()), &request(), Duration::from_millis(500)).await;
+    expect!(result).to(be_err());
+
+    let child_pid = std::fs::read_to_string(&pid_file).unwrap().trim().to_string();
+    let mut running = true;
+    for _ in 0..50 {
+      running = std::fs::read_to_string(format!("/proc/{}/stat", child_pid))
+        .map(|stat| !stat.contains(") Z "))
+        .unwrap_or(false);
+      if !running {
+        break;
+      }
+      tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    expect!(running).to(be_false());
+    let _ = std::fs::remove_file(&pid_file);
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn returns_an_error_if_the_command_does_not_output_json() {
+    let result = fetch_messages(&transport("echo not json"), &request(), Duration::from_secs(5)).await;
+    expect!(result.unwrap_err().to_string()).to(be_equal_to("The message command 'echo not json' did not write a JSON message to standard output"));
+  }
+
+  #[test]
+  fn message_from_json_test() {
+    expect!(message_from_json(&json!("not an object"))).to(be_err());
+    expect!(message_from_json(&json!({ "contents": 1, "metadata": "x" }))).to(be_err());
+    expect!(message_from_json(&json!({})).unwrap().contents).to(be_equal_to(OptionalBody::Missing));
+    expect!(message_from_json(&json!({ "contents": null })).unwrap().contents).to(be_equal_to(OptionalBody::Null));
+  }
+}
@@ -0,0 +1,570 @@
+//! Support for a dry run of a verification. This fetches and filters the pacts in the same way as
+//! a verification, and returns the pacts and interactions that would be verified, but does not
+//! call the provider, execute any provider states or publish any results.
+
+use std::fmt::Write;
+use std::ops::Deref;
+use std::panic::RefUnwindSafe;
+
+use itertools::Itertools;
+use pact_models::interaction::Interaction;
+use serde::Serialize;
+use tracing::{error, warn};
+
+use crate::{
+  ASYNC_MESSAGE_TRANSPORTS,
+  fetch_pacts,
+  filter_interaction,
+  FilterInfo,
+  HTTP_TRANSPORTS,
+  PactSource,
+  ProviderInfo,
+  ProviderTransport,
+  select_provider_transport,
+  SYNC_MESSAGE_TRANSPORTS,
+  VerificationOptions
+};
+use crate::callback_executors::RequestFilterExecutor;
+use crate::pact_broker::PactBrokerError;
+use crate::provider_client::provider_base_url;
+use crate::websockets::websocket_transport;
+
+/// Result of a dry run of a verification
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DryRunResult {
+  /// Pacts that would be verified
+  pub pacts: Vec<DryRunPact>,
+  /// Errors loading the pacts
+  pub errors: Vec<String>
+}
+
+/// Pact that would be verified
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DryRunPact {
+  /// Consumer name
+  pub consumer: String,
+  /// Provider name
+  pub provider: String,
+  /// Where the pact was loaded from
+  pub source: String,
+  /// Description of the selector that caused the Pact Broker to include the pact
+  pub selector: Option<String>,
+  /// If the pact is pending
+  pub pending: bool,
+  /// If the pact is a work in progress pact
+  pub wip: bool,
+  /// Interactions that would be verified, after the filters have been applied
+  pub interactions: Vec<DryRunInteraction>
+}
+
+/// Interaction that would be verified
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DryRunInteraction {
+  /// Interaction description
+  pub description: String,
+  /// Type of interaction
+  pub interaction_type: String,
+  /// Provider states that would be set up for the interaction
+  pub provider_states: Vec<String>,
+  /// Transport the interaction would be verified with
+  pub transport: String,
+  /// Where the interaction would be sent to. If the provider has no matching transport
+  /// configured, this is the protocol, host, port and path of the provider.
+  pub endpoint: String,
+  /// If the interaction is pending (either itself or because the pact is pending)
+  pub pending: bool
+}
+
+impl DryRunResult {
+  /// Total number of interactions that would be verified
+  pub fn interaction_count(&self) -> usize {
+    self.pacts.iter().map(|pact| pact.interactions.len()).sum()
+  }
+
+  /// Formats the result as a table for displaying on a console
+  pub fn to_table(&self) -> String {
+    let mut buffer = String::new();
+
+    for pact in &self.pacts {
+      let mut status = String::new();
+      if pact.pending {
+        status.push_str(" [PENDING]");
+      }
+      if pact.wip {
+        status.push_str(" [WIP]");
+      }
+      let _ = writeln!(buffer, "Pact between {} and {}{}", pact.consumer, pact.provider, status);
+      let _ = writeln!(buffer, "  Source:   {}", pact.source);
+      if let Some(selector) = &pact.selector {
+        let _ = writeln!(buffer, "  Selector: {}", selector);
+      }
+      buffer.push('\n');
+
+      if pact.interactions.is_empty() {
+        buffer.push_str("  No interactions to verify\n");
+      } else {
+        let rows = pact.interactions.iter()
+          .map(|interaction| vec![
+            if interaction.pending {
+              format!("{} [PENDING]", interaction.description)
+            } else {
+              interaction.description.clone()
+            },
+            interaction.interaction_type.clone(),
+            if interaction.provider_states.is_empty() {
+              "-".to_string()
+            } else {
+              interaction.provider_states.join(", ")
+            },
+            format!("{} ({})", interaction.transport, interaction.endpoint)
+          ])
+          .collect_vec();
+        write_table(&mut buffer, &["Description", "Type", "Provider states", "Transport"], &rows);
+      }
+      buffer.push('\n');
+    }
+
+    for error in &self.errors {
+      let _ = writeln!(buffer, "Failed to load pact - {}", error);
+    }
+    if !self.errors.is_empty() {
+      buffer.push('\n');
+    }
+
+    let pacts = self.pacts.len();
+    let interactions = self.interaction_count();
+    let _ = writeln!(buffer, "{} {} with {} {} would be verified",
+      pacts, if pacts == 1 { "pact" } else { "pacts" },
+      interactions, if interactions == 1 { "interaction" } else { "interactions" });
+
+    buffer
+  }
+}
+
+fn write_table(buffer: &mut String, headers: &[&str], rows: &[Vec<String>]) {
+  let widths = headers.iter().enumerate()
+    .map(|(index, header)| {
+      rows.iter()
+        .map(|row| row[index].chars().count())
+        .max()
+        .unwrap_or_default()
+        .max(header.len())
+    })
+    .collect_vec();
+
+  let mut write_row = |values: Vec<&str>| {
+    let line = values.iter().zip(&widths)
+      .map(|(value, width)| format!("{:width$}", value, width = *width))
+      .join("  ");
+    let _ = writeln!(buffer, "  {}", line.trim_end());
+  };
+  write_row(headers.to_vec());
+  for row in rows {
+    write_row(row.iter().map(|v| v.as_str()).collect());
+  }
+}
+
+/// Fetches and filters the pacts for the provider, and returns the pacts and interactions that
+/// would be verified. The provider is not called, and no provider states are executed or results
+/// published.
+pub async fn dry_run_verification<F: RequestFilterExecutor>(
+  provider_info: &ProviderInfo,
+  source: Vec<PactSource>,
+  filter: &FilterInfo,
+  consumers: Vec<String>,
+  verification_options: &VerificationOptions<F>
+) -> DryRunResult {
+  let mut result = DryRunResult::default();
+
+  for pact_result in fetch_pacts(source, consumers, provider_info, &verification_options.broker_tls).await {
+    match pact_result {
+      Ok((pact, context, pact_source, _)) => {
+        let pending = context.as_ref()
+          .map(|context| context.verification_properties.pending)
+          .unwrap_or_default();
+        let interactions = pact.iter_interactions()
+          .filter(|interaction| filter_interaction(interaction.deref(), filter))
+          .map(|interaction| dry_run_interaction(interaction.deref(), provider_info, verification_options, pending))
+          .collect();
+        result.pacts.push(DryRunPact {
+          consumer: pact.consumer().name,
+          provider: pact.provider().name,
+          source: source_description(&pact_source),
+          selector: context.as_ref().map(|context| context.short_description.clone()),
+          pending,
+          wip: context.as_ref()
+            .map(|context| context.verification_properties.wip)
+            .unwrap_or_default(),
+          interactions
+        });
+      }
+      Err(err) => {
+        if let Some(PactBrokerError::NotFound(_)) = err.downcast_ref() {
+          if verification_options.no_pacts_is_error {
+            error!("Failed to load pact - {}", err);
+            result.errors.push(err.to_string());
+          } else {
+            warn!("Ignoring no pacts error - {}", err);
+          }
+        } else {
+          let error = format!("{:#}", err);
+          error!("Failed to load pact - {}", error);
+          result.errors.push(error);
+        }
+      }
+    }
+  }
+
+  result
+}
+
+fn dry_run_interaction<F: RequestFilterExecutor>(
+  interaction: &(dyn Interaction + Send + Sync + RefUnwindSafe),
+  provider_info: &ProviderInfo,
+  verification_options: &VerificationOptions<F>,
+  pending: bool
+) -> DryRunInteraction {
+  let is_http = interaction.is_request_response() || interaction.is_v4_http();
+  let sync_message = interaction.as_v4_sync_message();
+  let default_transports = if is_http {
+    HTTP_TRANSPORTS
+  } else if sync_message.is_some() {
+    SYNC_MESSAGE_TRANSPORTS
+  } else {
+    ASYNC_MESSAGE_TRANSPORTS
+  };
+  let transport = interaction.as_v4()
+    .and_then(|interaction| interaction.transport());
+
+  // Select the transport the same way as the verification does. Synchronous messages are sent over
+  // a WebSocket transport if there is one, before the in-process message provider is checked.
+  let websocket = sync_message.as_ref()
+    .and_then(|message| websocket_transport(provider_info, message));
+  let provider_transport = websocket.clone()
+    .or_else(|| select_provider_transport(provider_info, transport.as_deref(), default_transports));
+
+  let endpoint = if is_http && verification_options.provider_service.is_some() {
+    "in-process provider service".to_string()
+  } else if !is_http && websocket.is_none() && verification_options.message_provider.is_some() {
+    "in-process message provider".to_string()
+  } else {
+    transport_endpoint(provider_transport.as_ref(), provider_info)
+  };
+
+  DryRunInteraction {
+    description: interaction.description(),
+    interaction_type: interaction.type_of(),
+    provider_states: interaction.provider_states().iter().map(|state| state.name.clone()).collect(),
+    transport: transport
+      .or_else(|| websocket.map(|t| t.transport))
+      .unwrap_or_else(|| default_transports[0].to_string()),
+    endpoint,
+    pending: pending || interaction.pending()
+  }
+}
+
+fn transport_endpoint(transport: Option<&ProviderTransport>, provider_info: &ProviderInfo) -> String {
+  match transport {
+    Some(transport) if transport.is_command() =>
+      format!("command: {}", transport.command.clone().unwrap_or_default()),
+    Some(transport) if transport.is_unix_socket() =>
+      format!("unix: {}", transport.socket_path.clone().unwrap_or_default()),
+    _ => provider_base_url(provider_info, transport)
+  }
+}
+
+fn source_description(source: &PactSource) -> String {
+  match source {
+    PactSource::File(file) => file.clone(),
+    PactSource::Dir(dir) => dir.clone(),
+    PactSource::URL(url, _) => url.clone(),
+    PactSource::BrokerUrl(_, broker_url, _, links) => links.iter()
+      .find(|link| link.name == "self")
+      .and_then(|link| link.href.clone())
+      .unwrap_or_else(|| broker_url.clone()),
+    PactSource::String(_) => "<json>".to_string(),
+    _ => source.to_string()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use pretty_assertions::assert_eq;
+  use serde_json::json;
+
+  use crate::{NullRequestFilterExecutor, ProviderTransport};
+
+  use super::*;
+
+  fn pact_json() -> String {
+    json!({
+      "consumer": { "name": "test_consumer" },
+      "provider": { "name": "test_provider" },
+      "interactions": [
+        {
+          "type": "Synchronous/HTTP",
+          "description": "a request for a user",
+          "providerStates": [ { "name": "a user exists" } ],
+          "request": { "method": "GET", "path": "/users/1" },
+          "response": { "status": 200 }
+        },
+        {
+          "type": "Synchronous/HTTP",
+          "description": "a request for an order",
+          "pending": true,
+          "request": { "method": "GET", "path": "/orders/1" },
+          "response": { "status": 200 }
+        },
+        {
+          "type": "Asynchronous/Messages",
+          "description": "an order created event",
+          "contents": { "content": { "id": 1 } }
+        }
+      ],
+      "metadata": { "pactSpecification": { "version": "4.0" } }
+    }).to_string()
+  }
+
+  fn provider() -> ProviderInfo {
+    ProviderInfo {
+      name: "test_provider".to_string(),
+      host: "localhost".to_string(),
+      transports: vec![ ProviderTransport {
+        port: Some(1234),
+        .. ProviderTransport::default()
+      } ],
+      .. ProviderInfo::default()
+    }
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn lists_the_interactions_without_calling_the_provider() {
+    let options = VerificationOptions::<NullRequestFilterExecutor>::default();
+    let result = dry_run_verification(&provider(), vec![ PactSource::String(pact_json()) ],
+      &FilterInfo::None, vec![], &options).await;
+
+    expect!(result.errors.iter()).to(be_empty());
+    expect!(result.pacts.len()).to(be_equal_to(1));
+    let pact = &result.pacts[0];
+    expect!(pact.consumer.as_str()).to(be_equal_to("test_consumer"));
+    expect!(pact.source.as_str()).to(be_equal_to("<json>"));
+    expect!(pact.selector.clone()).to(be_none());
+    assert_eq!(pact.interactions, vec![
+      DryRunInteraction {
+        description: "a request for a user".to_string(),
+        interaction_type: "V4 Synchronous/HTTP".to_string(),
+        provider_states: vec!["a user exists".to_string()],
+        transport: "http".to_string(),
+        endpoint: "http://localhost:1234".to_string(),
+        pending: false
+      },
+      DryRunInteraction {
+        description: "a request for an order".to_string(),
+        interaction_type: "V4 Synchronous/HTTP".to_string(),
+        provider_states: vec![],
+        transport: "http".to_string(),
+        endpoint: "http://localhost:1234".to_string(),
+        pending: true
+      },
+      DryRunInteraction {
+        description: "an order created event".to_string(),
+        interaction_type: "V4 Asynchronous/Messages".to_string(),
+        provider_states: vec![],
+        transport: "message".to_string(),
+        endpoint: "http://localhost:8080/".to_string(),
+        pending: false
+      }
+    ]);
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn selects_the_transports_the_same_way_as_the_verification() {
+    let pact = json!({
+      "consumer": { "name": "test_consumer" },
+      "provider": { "name": "test_provider" },
+      "interactions": [
+        {
+          "type": "Synchronous/Messages",
+          "description": "a request for a quote",
+          "request": { "contents": { "content": { "id": 1 } } },
+          "response": [ { "contents": { "content": { "price": 100 } } } ]
+        },
+        {
+          "type": "Asynchronous/Messages",
+          "description": "an order created event",
+          "contents": { "content": { "id": 1 } }
+        }
+      ],
+      "metadata": { "pactSpecification": { "version": "4.0" } }
+    }).to_string();
+    let provider = ProviderInfo {
+      name: "test_provider".to_string(),
+      host: "localhost".to_string(),
+      transports: vec![
+        ProviderTransport::new("ws", Some(5678), Some("/quotes".to_string()), None),
+        ProviderTransport::message_command("async-message", "./messages.sh")
+      ],
+      .. ProviderInfo::default()
+    };
+    let options = VerificationOptions::<NullRequestFilterExecutor>::default();
+    let result = dry_run_verification(&provider, vec![ PactSource::String(pact) ],
+      &FilterInfo::None, vec![], &options).await;
+
+    expect!(result.errors.iter()).to(be_empty());
+    let interactions = result.pacts[0].interactions.iter()
+      .map(|i| (i.transport.clone(), i.endpoint.clone()))
+      .collect::<Vec<_>>();
+    assert_eq!(interactions, vec![
+      ("ws".to_string(), "ws://localhost:5678/quotes".to_string()),
+      ("message".to_string(), "command: ./messages.sh".to_string())
+    ]);
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn uses_the_provider_port_if_there_are_no_transports() {
+    #[allow(deprecated)]
+    let provider = ProviderInfo {
+      name: "test_provider".to_string(),
+      host: "localhost".to_string(),
+      port: Some(5050),
+      path: "/api".to_string(),
+      .. ProviderInfo::default()
+    };
+    let options = VerificationOptions::<NullRequestFilterExecutor>::default();
+    let result = dry_run_verification(&provider, vec![ PactSource::String(pact_json()) ],
+      &FilterInfo::None, vec![], &options).await;
+
+    expect!(result.errors.iter()).to(be_empty());
+    let endpoints = result.pacts[0].interactions.iter()
+      .map(|i| i.endpoint.clone())
+      .collect::<Vec<_>>();
+    assert_eq!(endpoints, vec![
+      "http://localhost:5050/api".to_string(),
+      "http://localhost:5050/api".to_string(),
+      "http://localhost:5050/api".to_string()
+    ]);
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn applies_the_interaction_and_consumer_filters() {
+    let options = VerificationOptions::<NullRequestFilterExecutor>::default();
+    let result = dry_run_verification(&provider(), vec![ PactSource::String(pact_json()) ],
+      &FilterInfo::Description("user".to_string()), vec![], &options).await;
+    expect!(result.interaction_count()).to(be_equal_to(1));
+    expect!(result.pacts[0].interactions[0].description.as_str()).to(be_equal_to("a request for a user"));
+
+    let result = dry_run_verification(&provider(), vec![ PactSource::String(pact_json()) ],
+      &FilterInfo::None, vec!["other_consumer".to_string()], &options).await;
+    expect!(result.pacts.iter()).to(be_empty());
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn records_pacts_that_fail_to_load() {
+    let options = VerificationOptions::<NullRequestFilterExecutor>::default();
+    let result = dry_run_verification(&provider(), vec![ PactSource::File("/does/not/exist.json".to_string()) ],
+      &FilterInfo::None, vec![], &options).await;
+    expect!(result.pacts.iter()).to(be_empty());
+    expect!(result.errors.len()).to(be_equal_to(1));
+  }
+
+  #[test]
+  fn formats_the_result_as_a_table() {
+    let result = DryRunResult {
+      pacts: vec![
+        DryRunPact {
+          consumer: "test_consumer".to_string(),
+          provider: "test_provider".to_string(),
+          source: "http://localhost/pacts/1".to_string(),
+          selector: Some("latest prod".to_string()),
+          pending: true,
+          wip: false,
+          interactions: vec![
+            DryRunInteraction {
+              description: "a request for a user".to_string(),
+              interaction_type: "V4 Synchronous/HTTP".to_string(),
+              provider_states: vec!["a user exists".to_string()],
+              transport: "http".to_string(),
+              endpoint: "http://localhost:1234".to_string(),
+              pending: true
+            },
+            DryRunInteraction {
+              description: "an event".to_string(),
+              interaction_type: "V4 Asynchronous/Messages".to_string(),
+              provider_states: vec![],
+              transport: "message".to_string(),
+              endpoint: "http://localhost:8080/".to_string(),
+              pending: true
+            }
+          ]
+        }
+      ],
+      errors: vec![]
+    };
+
+    assert_eq!(result.to_table(),
+      "Pact between test_consumer and test_provider [PENDING]\n\
+      \x20 Source:   http://localhost/pacts/1\n\
+      \x20 Selector: latest prod\n\
+      \n\
+      \x20 Description                     Type                      Provider states  Transport\n\
+      \x20 a request for a user [PENDING]  V4 Synchronous/HTTP       a user exists    http (http://localhost:1234)\n\
+      \x20 an event [PENDING]              V4 Asynchronous/Messages  -                message (http://localhost:8080/)\n\
+      \n\
+      1 pact with 2 interactions would be verified\n");
+  }
+
+  #[test]
+  fn serialises_the_result_to_json() {
+    let result = DryRunResult {
+      pacts: vec![
+        DryRunPact {
+          consumer: "test_consumer".to_string(),
+          provider: "test_provider".to_string(),
+          source: "pact.json".to_string(),
+          selector: None,
+          pending: false,
+          wip: true,
+          interactions: vec![
+            DryRunInteraction {
+              description: "a request for a user".to_string(),
+              interaction_type: "V4 Synchronous/HTTP".to_string(),
+              provider_states: vec![],
+              transport: "http".to_string(),
+              endpoint: "http://localhost:8080/".to_string(),
+              pending: false
+            }
+          ]
+        }
+      ],
+      errors: vec!["Boom".to_string()]
+    };
+
+    expect!(serde_json::to_value(&result).unwrap()).to(be_equal_to(json!({
+      "pacts": [
+        {
+          "consumer": "test_consumer",
+          "provider": "test_provider",
+          "source": "pact.json",
+          "selector": null,
+          "pending": false,
+          "wip": true,
+          "interactions": [
+            {
+              "description": "a request for a user",
+              "interactionType": "V4 Synchronous/HTTP",
+              "providerStates": [],
+              "transport": "http",
+              "endpoint": "http://localhost:8080/",
+              "pending": false
+            }
+          ]
+        }
+      ],
+      "errors": ["Boom"]
+    })));
+  }
+}
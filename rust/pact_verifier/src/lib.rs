@@ -8,6 +8,7 @@ use std::fmt::{Debug, Display, Formatter};
 use std::fmt;
 use std::fs;
 use std::future::Future;
+use std::ops::Deref;
 use std::panic::RefUnwindSafe;
 use std::path::Path;
 use std::sync::Arc;
@@ -27,7 +28,8 @@ use pact_models::generators::GeneratorTestMode;
 use pact_models::http_utils::HttpAuth;
 use pact_models::interaction::Interaction;
 use pact_models::json_utils::json_to_string;
-use pact_models::pact::{load_pact_from_json, Pact, read_pact};
+use pact_models::lazy_pact::LazyPact;
+use pact_models::pact::Pact;
 use pact_models::prelude::v4::SynchronousHttp;
 use pact_models::provider_states::*;
 use pact_models::v4::interaction::V4Interaction;
@@ -59,7 +61,14 @@ use crate::pact_broker::{
   TestResult
 };
 pub use crate::pact_broker::{ConsumerVersionSelector, PactsForVerificationRequest};
-use crate::provider_client::make_provider_request;
+pub use crate::provider_process::{ProviderProcess, ProviderReadiness};
+pub use crate::provider_service::ProviderService;
+pub use crate::message_provider::{MessageProvider, MessageProviderRegistry};
+pub use crate::state_config::ConfigProviderStateExecutor;
+pub use crate::request_filters::{RequestFilter, RequestFilters};
+pub use crate::tls::{ClientIdentity, TlsConfig};
+pub use crate::dry_run::{dry_run_verification, DryRunResult};
+use crate::provider_client::make_provider_request_for_response;
 use crate::request_response::process_request_response_result;
 use crate::utils::as_safe_ref;
 use crate::verification_result::{
@@ -76,7 +85,17 @@ mod messages;
 pub mod selectors;
 pub mod metrics;
 pub mod verification_result;
+pub mod provider_process;
+pub mod provider_service;
+pub mod message_provider;
+pub mod state_config;
+pub mod request_filters;
+pub mod tls;
+pub mod dry_run;
 mod utils;
+mod unix_socket;
+mod command_transport;
+mod websockets;
 
 const VERIFIER_VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -163,8 +182,10 @@ impl Display for PactSource {
   }
 }
 
-/// Information about the Provider to verify
+/// Information about the Provider to verify. Use `ProviderTransport::new` (or one of the other
+/// constructors) to create one, as new fields may be added.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct ProviderTransport {
   /// Protocol Transport
   pub transport: String,
@@ -172,29 +193,94 @@ pub struct ProviderTransport {
   pub port: Option<u16>,
   /// Base path to use for the transport (for protocols that support paths)
   pub path: Option<String>,
-  /// Transport scheme to use. Will default to HTTP
-  pub scheme: Option<String>
+  /// Transport scheme to use. Will default to HTTP. Use `unix` to connect to a Unix domain socket,
+  /// or `command` to run a command to get messages from the provider.
+  pub scheme: Option<String>,
+  /// Path to the Unix domain socket the provider is listening on (for the `unix` scheme)
+  pub socket_path: Option<String>,
+  /// Use HTTP/2 with prior knowledge (i.e. h2c) instead of HTTP/1.1
+  pub http2: bool,
+  /// Command to run to get messages from the provider (for the `command` scheme)
+  pub command: Option<String>
 }
 
+/// Scheme for transports that connect to a Unix domain socket
+pub const UNIX_SOCKET_SCHEME: &str = "unix";
+
+/// Scheme for message transports that run a command to get the messages from the provider
+pub const COMMAND_SCHEME: &str = "command";
+
 impl ProviderTransport {
-  /// Calculate a base URL for the transport
+  /// Creates a transport that connects to the provider on the port
+  pub fn new<T: Into<String>>(
+    transport: T,
+    port: Option<u16>,
+    path: Option<String>,
+    scheme: Option<String>
+  ) -> Self {
+    ProviderTransport {
+      transport: transport.into(),
+      port,
+      path,
+      scheme,
+      socket_path: None,
+      http2: false,
+      command: None
+    }
+  }
+
+  /// Creates a transport that connects to the provider via the Unix domain socket
+  pub fn unix_socket<T: Into<String>, P: Into<String>>(transport: T, socket_path: P) -> Self {
+    ProviderTransport {
+      socket_path: Some(socket_path.into()),
+      .. ProviderTransport::new(transport, None, None, Some(UNIX_SOCKET_SCHEME.to_string()))
+    }
+  }
+
+  /// Creates a message transport that runs the command to get messages from the provider
+  pub fn message_command<T: Into<String>, C: Into<String>>(transport: T, command: C) -> Self {
+    ProviderTransport {
+      command: Some(command.into()),
+      .. ProviderTransport::new(transport, None, None, Some(COMMAND_SCHEME.to_string()))
+    }
+  }
+
+  /// Sets if HTTP/2 with prior knowledge (i.e. h2c) is used instead of HTTP/1.1
+  pub fn with_http2(self, http2: bool) -> Self {
+    ProviderTransport {
+      http2,
+      .. self
+    }
+  }
+
+  /// Calculate a base URL for the transport. For Unix domain sockets, this will be a HTTP URL
+  /// to localhost, as the socket path is not part of the URL.
   pub fn base_url(&self, hostname: &str) -> String {
+    if self.is_unix_socket() {
+      return format!("http://localhost{}", self.path.clone().unwrap_or_default());
+    }
+
     let scheme = self.scheme.clone().unwrap_or("http".to_string());
     match self.port {
       Some(port) => format!("{}://{}:{}{}", scheme, hostname, port, self.path.clone().unwrap_or_default()),
       None => format!("{}://{}{}", scheme, hostname, self.path.clone().unwrap_or_default())
     }
   }
+
+  /// If the transport connects to the provider via a Unix domain socket
+  pub fn is_unix_socket(&self) -> bool {
+    self.scheme.as_deref() == Some(UNIX_SOCKET_SCHEME)
+  }
+
+  /// If the transport runs a command to get messages from the provider
+  pub fn is_command(&self) -> bool {
+    self.scheme.as_deref() == Some(COMMAND_SCHEME)
+  }
 }
 
 impl Default for ProviderTransport {
   fn default() -> Self {
-    ProviderTransport {
-      transport: "http".to_string(),
-      port: Some(8080),
-      path: None,
-      scheme: Some("http".to_string())
-    }
+    ProviderTransport::new("http", Some(8080), None, Some("http".to_string()))
   }
 }
 
@@ -233,6 +319,14 @@ impl Default for ProviderInfo {
   }
 }
 
+impl ProviderInfo {
+  /// Returns the transport that HTTP interactions that do not specify a transport are verified
+  /// with, if one is configured
+  pub fn http_transport(&self) -> Option<ProviderTransport> {
+    select_provider_transport(self, None, HTTP_TRANSPORTS)
+  }
+}
+
 /// Result of performing a match
 pub enum MismatchResult {
     /// Response mismatches
@@ -341,33 +435,15 @@ async fn verify_response_from_provider<F: RequestFilterExecutor>(
   interaction: &SynchronousHttp,
   pact: &Box<dyn Pact + Send + Sync + RefUnwindSafe>,
   options: &VerificationOptions<F>,
-  client: &Client,
+  clients: &ProviderClients,
   verification_context: &HashMap<&str, Value>
 ) -> Result<Option<String>, MismatchResult> {
   let expected_response = &interaction.response;
   let request = pact_matching::generate_request(&interaction.request,
     &GeneratorTestMode::Provider, &verification_context).await;
-  let transport = if let Some(transport) = &interaction.transport {
-    provider.transports
-      .iter()
-      .find(|t| &t.transport == transport)
-      .cloned()
-  } else {
-    provider.transports
-      .iter()
-      .find(|t| t.transport == "http")
-      .cloned()
-  }.map(|t| {
-    if t.scheme.is_none() {
-      ProviderTransport {
-        scheme: Some("http".to_string()),
-        .. t
-      }
-    } else {
-      t
-    }
-  });
-  match make_provider_request(provider, &request, options, client, transport).await {
+  let transport = select_provider_transport(provider, interaction.transport.as_deref(), HTTP_TRANSPORTS);
+  let client = clients.for_transport(transport.as_ref());
+  match make_provider_request_for_response(provider, &request, Some(expected_response), options, client, transport).await {
     Ok(ref actual_response) => {
       let mismatches = match_response(expected_response.clone(), actual_response.clone(), pact, &interaction.boxed()).await;
       if mismatches.is_empty() {
@@ -413,19 +489,14 @@ async fn verify_interaction<'a, F: RequestFilterExecutor, S: ProviderStateExecut
   interaction: &(dyn Interaction + Send + Sync + RefUnwindSafe),
   pact: &Box<dyn Pact + Send + Sync + RefUnwindSafe + 'a>,
   options: &VerificationOptions<F>,
+  clients: &ProviderClients,
   provider_state_executor: &Arc<S>
 ) -> Result<(Option<String>, Vec<String>, Duration), (MismatchResult, Vec<String>, Duration)> {
   let start = Instant::now();
   trace!("Verifying interaction {} {} ({:?})", interaction.type_of(), interaction.description(), interaction.id());
-  let client = Arc::new(configure_http_client(options)
-    .map_err(|err| (
-      MismatchResult::Error(err.to_string(), interaction.id()),
-      vec![],
-      start.elapsed()
-    ))?);
 
   debug!("Executing provider states");
-  let context = execute_provider_states(interaction, provider_state_executor, &client, true)
+  let context = execute_provider_states(interaction, provider_state_executor, &clients.state_change, true)
     .await
     .map_err(|e| (e, vec![], start.elapsed()))?;
   let provider_states_context = context
@@ -449,9 +520,9 @@ async fn verify_interaction<'a, F: RequestFilterExecutor, S: ProviderStateExecut
 
     result = if let Some(transport) = &transport {
       trace!("Verifying interaction via {}", transport.key);
-      verify_interaction_using_transport(transport, provider, interaction, pact, options, &client, &provider_states_context).await
+      verify_interaction_using_transport(transport, provider, interaction, pact, options, clients, &provider_states_context).await
     } else {
-      verify_v3_interaction(provider, interaction, &pact, options, &client, &provider_states_context)
+      verify_v3_interaction(provider, interaction, &pact, options, clients, &provider_states_context)
         .await
         .map(|r| (r, vec![]))
         .map_err(|e| (e, vec![]))
@@ -460,14 +531,14 @@ async fn verify_interaction<'a, F: RequestFilterExecutor, S: ProviderStateExecut
 
   #[cfg(not(feature = "plugins"))]
   {
-    result = verify_v3_interaction(provider, interaction, &pact, options, &client, &provider_states_context)
+    result = verify_v3_interaction(provider, interaction, &pact, options, clients, &provider_states_context)
       .await
       .map(|r| (r, vec![]))
       .map_err(|e| (e, vec![]));
   }
 
   if provider_state_executor.teardown() {
-    execute_provider_states(interaction, provider_state_executor, &client, false)
+    execute_provider_states(interaction, provider_state_executor, &clients.state_change, false)
       .await
       .map_err(|e| (e, vec![], start.elapsed()))?;
   }
@@ -485,7 +556,7 @@ async fn verify_interaction_using_transport<'a, F: RequestFilterExecutor>(
   interaction: &(dyn Interaction + Send + Sync + RefUnwindSafe),
   pact: &Box<dyn Pact + Send + Sync + RefUnwindSafe + 'a>,
   options: &VerificationOptions<F>,
-  client: &Arc<Client>,
+  clients: &ProviderClients,
   config: &HashMap<&str, Value>
 ) -> Result<(Option<String>, Vec<String>), (MismatchResult, Vec<String>)> {
   if transport_entry.provider_type == CatalogueEntryProviderType::PLUGIN {
@@ -578,7 +649,7 @@ async fn verify_interaction_using_transport<'a, F: RequestFilterExecutor>(
       }
     }
   } else {
-    verify_v3_interaction(provider, interaction, pact, options, client, config)
+    verify_v3_interaction(provider, interaction, pact, options, clients, config)
       .await
       .map(|r| (r, vec![]))
       .map_err(|e| (e, vec![]))
@@ -591,7 +662,7 @@ async fn verify_v3_interaction<'a, F: RequestFilterExecutor>(
   interaction: &(dyn Interaction + Send + Sync + RefUnwindSafe),
   pact: &Box<dyn Pact + Send + Sync + RefUnwindSafe + 'a>,
   options: &VerificationOptions<F>,
-  client: &Arc<Client>,
+  clients: &ProviderClients,
   provider_states_context: &HashMap<&str, Value>
 ) -> Result<Option<String>, MismatchResult> {
   let mut result = Err(MismatchResult::Error("No interaction was verified".into(), interaction.id().clone()));
@@ -600,18 +671,18 @@ async fn verify_v3_interaction<'a, F: RequestFilterExecutor>(
   if let Some(interaction) = interaction.as_v4_http() {
     debug!("Verifying a HTTP interaction");
     result = verify_response_from_provider(provider, &interaction, &pact.boxed(), options,
-                                           &client, &provider_states_context).await;
+                                           clients, &provider_states_context).await;
   }
   // Verify an asynchronous message (single shot)
   if interaction.is_message() {
     debug!("Verifying an asynchronous message (single shot)");
     result = verify_message_from_provider(provider, pact, &interaction.boxed(), options,
-                                          &client, &provider_states_context).await;
+                                          clients, &provider_states_context).await;
   }
   // Verify a synchronous message (request/response)
   if let Some(message) = interaction.as_v4_sync_message() {
     debug!("Verifying a synchronous message (request/response)");
-    result = verify_sync_message_from_provider(provider, pact, message, options, &client,
+    result = verify_sync_message_from_provider(provider, pact, message, options, clients,
                                                &provider_states_context).await;
   }
 
@@ -679,16 +750,117 @@ async fn execute_provider_states<S: ProviderStateExecutor>(
 pub(crate) fn configure_http_client<F: RequestFilterExecutor>(
   options: &VerificationOptions<F>
 ) -> anyhow::Result<Client> {
-  let mut client_builder = reqwest::Client::builder()
+  http_client_builder(options, &options.provider_tls)?.build().map_err(|err| anyhow!(err))
+}
+
+/// Configure a HTTP client that uses HTTP/2 with prior knowledge for requests to the provider
+pub(crate) fn configure_http2_client<F: RequestFilterExecutor>(
+  options: &VerificationOptions<F>
+) -> anyhow::Result<Client> {
+  http_client_builder(options, &options.provider_tls)?.http2_prior_knowledge().build().map_err(|err| anyhow!(err))
+}
+
+/// HTTP clients to use for requests to the provider, created once for a verification run. The
+/// HTTP/2 (h2c) client is only created if the provider has a transport that uses it.
+#[derive(Debug, Clone)]
+pub(crate) struct ProviderClients {
+  /// Client for HTTP/1.1 requests
+  pub(crate) http: Arc<Client>,
+  /// Client for HTTP/2 with prior knowledge requests
+  pub(crate) http2: Option<Arc<Client>>,
+  /// Client for provider state change requests. This is the HTTP/1.1 client, unless the state
+  /// change requests have a separate TLS configuration.
+  pub(crate) state_change: Arc<Client>
+}
+
+impl ProviderClients {
+  /// Configure the clients for the provider transports
+  pub(crate) fn new<F: RequestFilterExecutor>(
+    provider: &ProviderInfo,
+    options: &VerificationOptions<F>
+  ) -> anyhow::Result<ProviderClients> {
+    let http2 = if provider.transports.iter().any(|t| t.http2) {
+      Some(Arc::new(configure_http2_client(options)?))
+    } else {
+      None
+    };
+    let http = Arc::new(configure_http_client(options)?);
+    let state_change = match configure_state_change_client(options)? {
+      Some(client) => Arc::new(client),
+      None => http.clone()
+    };
+    Ok(ProviderClients {
+      http,
+      http2,
+      state_change
+    })
+  }
+
+  /// Returns the client to use for requests via the transport
+  pub(crate) fn for_transport(&self, transport: Option<&ProviderTransport>) -> &Client {
+    match (transport.map(|t| t.http2).unwrap_or(false), &self.http2) {
+      (true, Some(client)) => client,
+      _ => &self.http
+    }
+  }
+}
+
+/// Names of the provider transports used for HTTP interactions that do not specify a transport
+pub(crate) const HTTP_TRANSPORTS: &[&str] = &["http"];
+/// Names of the provider transports used for asynchronous messages that do not specify a transport
+pub(crate) const ASYNC_MESSAGE_TRANSPORTS: &[&str] = &["message", "async-message"];
+/// Names of the provider transports used for synchronous messages that do not specify a transport
+pub(crate) const SYNC_MESSAGE_TRANSPORTS: &[&str] = &["message", "sync-message"];
+
+/// Selects the provider transport to use for an interaction. If the interaction specifies a
+/// transport, the provider transport with that name is used, otherwise the first one with one of
+/// the default names. The scheme of the transport defaults to HTTP.
+pub(crate) fn select_provider_transport(
+  provider: &ProviderInfo,
+  transport: Option<&str>,
+  default_transports: &[&str]
+) -> Option<ProviderTransport> {
+  let transport = match transport {
+    Some(transport) => provider.transports.iter().find(|t| t.transport == transport),
+    None => provider.transports.iter().find(|t| default_transports.contains(&t.transport.as_str()))
+  };
+  transport.map(|t| {
+    if t.scheme.is_none() {
+      ProviderTransport {
+        scheme: Some("http".to_string()),
+        .. t.clone()
+      }
+    } else {
+      t.clone()
+    }
+  })
+}
+
+/// Configure the HTTP client to use for provider state change requests, if they have a separate
+/// TLS configuration
+fn configure_state_change_client<F: RequestFilterExecutor>(
+  options: &VerificationOptions<F>
+) -> anyhow::Result<Option<Client>> {
+  options.state_change_tls.as_ref()
+    .map(|tls| http_client_builder(options, tls)?.build().map_err(|err| anyhow!(err)))
+    .transpose()
+}
+
+fn http_client_builder<F: RequestFilterExecutor>(
+  options: &VerificationOptions<F>,
+  tls: &TlsConfig
+) -> anyhow::Result<reqwest::ClientBuilder> {
+  let client_builder = reqwest::Client::builder()
     .danger_accept_invalid_certs(options.disable_ssl_verification)
     .timeout(Duration::from_millis(options.request_timeout));
+  let mut client_builder = tls.configure_client(client_builder)?;
 
   if !options.custom_headers.is_empty() {
     let headers = setup_custom_headers(&options.custom_headers)?;
     client_builder = client_builder.default_headers(headers);
   }
 
-  client_builder.build().map_err(|err| anyhow!(err))
+  Ok(client_builder)
 }
 
 fn setup_custom_headers(custom_headers: &HashMap<String, String>) -> anyhow::Result<HeaderMap> {
@@ -745,7 +917,7 @@ fn walkdir(
         if path.is_dir() {
             walkdir(&path, provider)?;
         } else {
-          match timeit(|| read_pact(&path)) {
+          match timeit(|| LazyPact::read(&path).map(|pact| pact.boxed())) {
             Ok((pact, tm)) => {
               if pact.provider().name == provider.name {
                 pacts.push(Ok((pact, tm)));
@@ -908,7 +1080,23 @@ pub struct VerificationOptions<F> where F: RequestFilterExecutor {
   /// If coloured output should be used (using ANSI escape codes)
   pub coloured_output: bool,
   /// If no pacts are found to verify, then this should be an error
-  pub no_pacts_is_error: bool
+  pub no_pacts_is_error: bool,
+  /// Provider process to start before running the verification. The process will be shut down
+  /// once the verification is complete.
+  pub provider_process: Option<ProviderProcess>,
+  /// In-process provider service to call for HTTP requests, instead of making requests to the
+  /// provider over the network
+  pub provider_service: Option<ProviderService>,
+  /// In-process message provider to get the actual messages from, instead of fetching them from
+  /// the provider over HTTP
+  pub message_provider: Option<Arc<dyn MessageProvider>>,
+  /// TLS configuration (CA certificates and client certificate) for the requests to the provider
+  pub provider_tls: TlsConfig,
+  /// TLS configuration for the provider state change requests. If not set, the provider TLS
+  /// configuration is used.
+  pub state_change_tls: Option<TlsConfig>,
+  /// TLS configuration for the requests to the Pact Broker, and to fetch pacts from URLs
+  pub broker_tls: TlsConfig
 }
 
 impl <F: RequestFilterExecutor> Default for VerificationOptions<F> {
@@ -919,7 +1107,13 @@ impl <F: RequestFilterExecutor> Default for VerificationOptions<F> {
       request_timeout: 5000,
       custom_headers: Default::default(),
       coloured_output: true,
-      no_pacts_is_error: true
+      no_pacts_is_error: true,
+      provider_process: None,
+      provider_service: None,
+      message_provider: None,
+      provider_tls: TlsConfig::default(),
+      state_change_tls: None,
+      broker_tls: TlsConfig::default()
     }
   }
 }
@@ -979,7 +1173,13 @@ pub async fn verify_provider_async<F: RequestFilterExecutor, S: ProviderStateExe
   pact_matching::matchers::configure_core_catalogue();
 
   LOG_ID.scope(format!("verify:{}", provider_info.name), async {
-    let pact_results = fetch_pacts(source, consumers, &provider_info).await;
+    let clients = ProviderClients::new(&provider_info, verification_options)?;
+    let provider_process = match &verification_options.provider_process {
+      Some(config) => Some(provider_process::start_provider(config, &provider_info, &clients.http).await?),
+      None => None
+    };
+
+    let pact_results = fetch_pacts(source, consumers, &provider_info, &verification_options.broker_tls).await;
 
     let mut total_results = 0;
     let mut pending_errors: Vec<(String, MismatchResult)> = vec![];
@@ -1018,7 +1218,7 @@ pub async fn verify_provider_async<F: RequestFilterExecutor, S: ProviderStateExe
               pact.consumer().name, pact.provider().name));
           }
 
-          if pact.interactions().is_empty() {
+          if pact.num_interactions() == 0 {
             if verification_options.coloured_output {
               verification_result.output.push(
                 Yellow.paint("WARNING: Pact file has no interactions").to_string()
@@ -1031,11 +1231,12 @@ pub async fn verify_provider_async<F: RequestFilterExecutor, S: ProviderStateExe
               Some(context) => context.verification_properties.pending,
               None => false
             };
-            let verify_result = verify_pact_internal(
+            let verify_result = verify_pact_with_clients(
               &provider_info,
               &filter,
               pact,
               &verification_options,
+              &clients,
               &provider_state_executor.clone(),
               pending,
               pact_source_duration
@@ -1074,7 +1275,8 @@ pub async fn verify_provider_async<F: RequestFilterExecutor, S: ProviderStateExe
             verification_result.interaction_results.extend_from_slice(results.as_slice());
 
             if let Some(publish) = publish_options {
-              publish_result(results.as_slice(), &pact_source, &publish, metrics_data.as_ref()).await;
+              publish_result(results.as_slice(), &pact_source, &publish, metrics_data.as_ref(),
+                &verification_options.broker_tls).await;
 
               if !errors.is_empty() || !pending_errors.is_empty() {
                 process_notices(&context, VERIFICATION_NOTICE_AFTER_ERROR_RESULT_AND_PUBLISH, &mut verification_result);
@@ -1146,6 +1348,10 @@ pub async fn verify_provider_async<F: RequestFilterExecutor, S: ProviderStateExe
       println!("{line}");
     }
 
+    if let Some(provider_process) = provider_process {
+      provider_process.shutdown().await;
+    }
+
     #[cfg(feature = "plugins")] shutdown_plugins();
 
     Ok(verification_result)
@@ -1203,13 +1409,14 @@ pub fn interaction_mismatch_output(
 #[tracing::instrument(level = "trace")]
 async fn fetch_pact(
   source: PactSource,
-  provider: &ProviderInfo
+  provider: &ProviderInfo,
+  tls: &TlsConfig
 ) -> Vec<anyhow::Result<(Box<dyn Pact + Send + Sync + RefUnwindSafe>, Option<PactVerificationContext>, PactSource, Duration)>> {
   trace!("fetch_pact(source={})", source);
 
   match &source {
     PactSource::File(file) => vec![
-      timeit(|| read_pact(Path::new(&file)))
+      timeit(|| LazyPact::read(Path::new(&file)).map(|pact| pact.boxed()))
         .map_err(|err| anyhow!("Failed to load pact '{}' - {}", file, err))
         .map(|(pact, tm)| {
           trace!(%file, duration = ?tm, "Loaded pact from file");
@@ -1229,7 +1436,7 @@ async fn fetch_pact(
       Err(err) => vec![Err(anyhow!("Could not load pacts from directory '{}' - {}", dir, err))]
     },
     PactSource::URL(url, auth) => vec![
-      timeit_async(pact_broker::fetch_pact_from_url(url, auth)).await
+      timeit_async(pact_broker::fetch_pact_from_url(url, auth, tls)).await
         .map_err(|err| anyhow!("Failed to load pact '{}' - {}", url, err))
         .map(|((pact, links), tm)| {
           trace!(%url, duration = ?tm, "Loaded pact from url");
@@ -1251,7 +1458,8 @@ async fn fetch_pact(
       let result = timeit_async(pact_broker::fetch_pacts_from_broker(
         broker_url.as_str(),
         provider_name.as_str(),
-        auth.clone()
+        auth.clone(),
+        tls
       )).await;
 
       match result {
@@ -1294,7 +1502,8 @@ async fn fetch_pact(
         provider_tags.clone(),
         provider_branch.clone(),
         selectors.clone(),
-        auth.clone()
+        auth.clone(),
+        tls
       )).await;
 
       match result {
@@ -1328,13 +1537,13 @@ async fn fetch_pact(
     PactSource::String(json) => vec![
       timeit(|| serde_json::from_str(json)
         .map_err(|err| anyhow!(err))
-        .and_then(|json| load_pact_from_json("<json>", &json)))
+        .and_then(|json| LazyPact::from_json("<json>", &json).map(|pact| pact.boxed())))
         .map(|(pact, tm)| {
           (pact, None, source.clone(), tm)
         })
     ],
     PactSource::WebhookCallbackUrl { pact_url, broker_url, auth, .. } => vec![
-      timeit_async(pact_broker::fetch_pact_from_url(pact_url, auth)).await
+      timeit_async(pact_broker::fetch_pact_from_url(pact_url, auth, tls)).await
         .map_err(|err| anyhow!("Failed to load pact '{}' - {}", pact_url, err))
         .map(|((pact, links), tm)| {
           trace!(%pact_url, duration = ?tm, "Loaded pact from url");
@@ -1371,13 +1580,14 @@ fn is_pact_broker_source(links: &Vec<Link>) -> bool {
 async fn fetch_pacts(
   source: Vec<PactSource>,
   consumers: Vec<String>,
-  provider: &ProviderInfo
+  provider: &ProviderInfo,
+  tls: &TlsConfig
 ) -> Vec<anyhow::Result<(Box<dyn Pact + Send + Sync + RefUnwindSafe>, Option<PactVerificationContext>, PactSource, Duration)>> {
   trace!("fetch_pacts(source={}, consumers={:?})", source.iter().map(|s| s.to_string()).join(", "), consumers);
 
   futures::stream::iter(source)
     .then(|pact_source| async {
-      futures::stream::iter(fetch_pact(pact_source, provider).await)
+      futures::stream::iter(fetch_pact(pact_source, provider, tls).await)
     })
     .flatten()
     .filter(|res| futures::future::ready(filter_consumers(&consumers, res)))
@@ -1396,20 +1606,50 @@ pub async fn verify_pact_internal<'a, F: RequestFilterExecutor, S: ProviderState
   pending: bool,
   pact_source_duration: Duration
 ) -> anyhow::Result<VerificationResult> {
-  let interactions = pact.interactions();
+  let clients = ProviderClients::new(provider_info, options)?;
+  verify_pact_with_clients(provider_info, filter, pact, options, &clients, provider_state_executor,
+    pending, pact_source_duration).await
+}
+
+/// Verifies the interactions of the pact, using the HTTP clients created for the verification run
+#[allow(clippy::too_many_arguments)]
+async fn verify_pact_with_clients<'a, F: RequestFilterExecutor, S: ProviderStateExecutor>(
+  provider_info: &ProviderInfo,
+  filter: &FilterInfo,
+  pact: Box<dyn Pact + Send + Sync + RefUnwindSafe + 'a>,
+  options: &VerificationOptions<F>,
+  clients: &ProviderClients,
+  provider_state_executor: &Arc<S>,
+  pending: bool,
+  pact_source_duration: Duration
+) -> anyhow::Result<VerificationResult> {
   let mut output = vec![];
 
+  // Interactions are loaded one at a time, as Pacts may be lazily loaded (see `LazyPact`)
+  let mut failed_to_load = vec![];
+  let pact_ref = &pact;
   let results: Vec<(Box<dyn Interaction + Send + Sync + RefUnwindSafe>, Result<(Option<String>, Vec<String>, Duration), (MismatchResult, Vec<String>, Duration)>)> =
-    futures::stream::iter(interactions.iter().map(|i| (&pact, i)))
-    .filter(|(_, interaction)| futures::future::ready(filter_interaction(interaction.as_ref(), filter)))
-    .then( |(pact, interaction)| async move {
+    futures::stream::iter((0..pact.num_interactions()).filter_map(|index| {
+      let interaction = pact_ref.interaction_at(index);
+      if interaction.is_none() {
+        failed_to_load.push(index);
+      }
+      interaction
+    }))
+    .filter(|interaction| futures::future::ready(filter_interaction(interaction.deref(), filter)))
+    .then( |interaction| async move {
       let interaction_desc = interaction.description();
-      (interaction.boxed(), verify_interaction(provider_info, interaction.as_ref(), &pact.boxed(), options, provider_state_executor)
+      (interaction.boxed(), verify_interaction(provider_info, interaction.deref(), pact_ref, options, clients, provider_state_executor)
         .instrument(debug_span!("verify_interaction", interaction = interaction_desc.as_str())).await)
     })
     .collect()
     .await;
 
+  if !failed_to_load.is_empty() {
+    return Err(anyhow!("Failed to load the interactions at {} from the Pact file, see the logs for the errors",
+      failed_to_load.iter().map(|index| format!("index {}", index)).join(", ")));
+  }
+
   let mut errors: Vec<VerificationInteractionResult> = vec![];
   for (interaction, match_result) in results {
     let mut description = format!("Verifying a pact between {} and {}",
@@ -1566,18 +1806,19 @@ async fn publish_result(
   results: &[VerificationInteractionResult],
   source: &PactSource,
   options: &PublishOptions,
-  metrics_data: Option<&VerificationMetrics>
+  metrics_data: Option<&VerificationMetrics>,
+  tls: &TlsConfig
 ) {
   let publish_result = match source {
     PactSource::BrokerUrl(_, broker_url, auth, links) => {
       publish_to_broker(results, source, &options.build_url, &options.provider_tags,
         &options.provider_branch, &options.provider_version, links.clone(), broker_url.clone(),
-        auth.clone(), metrics_data
+        auth.clone(), metrics_data, tls
       ).await
     }
     PactSource::BrokerWithDynamicConfiguration { broker_url, auth, links, provider_branch, provider_tags, .. } => {
       publish_to_broker(results, source, &options.build_url, &provider_tags, &provider_branch,
-        &options.provider_version, links.clone(), broker_url.clone(), auth.clone(), metrics_data
+        &options.provider_version, links.clone(), broker_url.clone(), auth.clone(), metrics_data, tls
       ).await
     }
     _ => {
@@ -1601,7 +1842,8 @@ async fn publish_to_broker(
   links: Vec<Link>,
   broker_url: String,
   auth: Option<HttpAuth>,
-  metrics_data: Option<&VerificationMetrics>
+  metrics_data: Option<&VerificationMetrics>,
+  tls: &TlsConfig
 ) -> Result<Value, pact_broker::PactBrokerError> {
   info!("Publishing verification results back to the Pact Broker");
   let result = if results.iter().all(|r| r.result.is_ok()) {
@@ -1624,7 +1866,8 @@ async fn publish_to_broker(
     build_url.clone(),
     provider_tags.clone(),
     provider_branch.clone(),
-    metrics_data
+    metrics_data,
+    tls
   ).await
 }
 
@@ -0,0 +1,156 @@
+//! Support for verifying message interactions by calling the message producer directly in-process,
+//! instead of fetching the messages from a HTTP endpoint exposed by the provider.
+
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::panic::RefUnwindSafe;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use itertools::Itertools;
+use pact_models::provider_states::ProviderState;
+use pact_models::v4::message_parts::MessageContents;
+
+/// Provider of messages that the verifier can call directly to get the actual messages for the
+/// message interactions in the Pact files being verified.
+#[async_trait]
+pub trait MessageProvider: Debug + Send + Sync + RefUnwindSafe {
+  /// Returns the message for an asynchronous message interaction, given the interaction
+  /// description and any provider states defined for it.
+  async fn message(
+    &self,
+    description: &str,
+    provider_states: &[ProviderState]
+  ) -> anyhow::Result<MessageContents>;
+
+  /// Returns the response messages for a synchronous message interaction, given the interaction
+  /// description, the request message and any provider states defined for it.
+  async fn sync_message(
+    &self,
+    description: &str,
+    request: &MessageContents,
+    provider_states: &[ProviderState]
+  ) -> anyhow::Result<Vec<MessageContents>> {
+    let _ = (request, provider_states);
+    Err(anyhow!("Synchronous messages are not supported by this message provider (interaction '{}')", description))
+  }
+}
+
+type MessageFn = dyn Fn(&[ProviderState]) -> anyhow::Result<MessageContents> + Send + Sync + RefUnwindSafe;
+type SyncMessageFn = dyn Fn(&MessageContents, &[ProviderState]) -> anyhow::Result<Vec<MessageContents>> + Send + Sync + RefUnwindSafe;
+
+/// Message provider that maps interaction descriptions to the functions that produce the messages
+#[derive(Clone, Default)]
+pub struct MessageProviderRegistry {
+  messages: HashMap<String, Arc<MessageFn>>,
+  sync_messages: HashMap<String, Arc<SyncMessageFn>>
+}
+
+impl MessageProviderRegistry {
+  /// Create an empty registry
+  pub fn new() -> Self {
+    MessageProviderRegistry::default()
+  }
+
+  /// Register the function that produces the message for the asynchronous message interaction
+  /// with the given description
+  pub fn add_message<D, F>(&mut self, description: D, producer: F) -> &mut Self
+    where D: Into<String>,
+          F: Fn(&[ProviderState]) -> anyhow::Result<MessageContents> + Send + Sync + RefUnwindSafe + 'static {
+    self.messages.insert(description.into(), Arc::new(producer));
+    self
+  }
+
+  /// Register the function that produces the response messages for the synchronous message
+  /// interaction with the given description
+  pub fn add_sync_message<D, F>(&mut self, description: D, producer: F) -> &mut Self
+    where D: Into<String>,
+          F: Fn(&MessageContents, &[ProviderState]) -> anyhow::Result<Vec<MessageContents>> + Send + Sync + RefUnwindSafe + 'static {
+    self.sync_messages.insert(description.into(), Arc::new(producer));
+    self
+  }
+}
+
+#[async_trait]
+impl MessageProvider for MessageProviderRegistry {
+  async fn message(
+    &self,
+    description: &str,
+    provider_states: &[ProviderState]
+  ) -> anyhow::Result<MessageContents> {
+    let producer = self.messages.get(description)
+      .ok_or_else(|| anyhow!("No message producer has been registered for '{}'", description))?;
+    producer(provider_states)
+  }
+
+  async fn sync_message(
+    &self,
+    description: &str,
+    request: &MessageContents,
+    provider_states: &[ProviderState]
+  ) -> anyhow::Result<Vec<MessageContents>> {
+    let producer = self.sync_messages.get(description)
+      .ok_or_else(|| anyhow!("No synchronous message producer has been registered for '{}'", description))?;
+    producer(request, provider_states)
+  }
+}
+
+impl Debug for MessageProviderRegistry {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("MessageProviderRegistry")
+      .field("messages", &self.messages.keys().sorted().collect_vec())
+      .field("sync_messages", &self.sync_messages.keys().sorted().collect_vec())
+      .finish()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use bytes::Bytes;
+  use expectest::prelude::*;
+  use maplit::hashmap;
+  use pact_models::bodies::OptionalBody;
+  use serde_json::json;
+
+  use super::*;
+
+  fn contents(body: &str) -> MessageContents {
+    MessageContents {
+      contents: OptionalBody::Present(Bytes::from(body.to_string()), Some("text/plain".into()), None),
+      .. MessageContents::default()
+    }
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn registry_calls_the_registered_message_producer() {
+    let mut registry = MessageProviderRegistry::new();
+    registry.add_message("a user created event", |states| {
+      Ok(contents(&format!("{} states", states.len())))
+    });
+
+    let states = vec![ProviderState::default("user exists")];
+    let message = registry.message("a user created event", &states).await.unwrap();
+    expect!(message.contents.value_as_string()).to(be_some().value("1 states"));
+
+    let result = registry.message("an unknown event", &[]).await;
+    expect!(result.unwrap_err().to_string()).to(be_equal_to("No message producer has been registered for 'an unknown event'"));
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn registry_calls_the_registered_sync_message_producer() {
+    let mut registry = MessageProviderRegistry::new();
+    registry.add_sync_message("a request for a user", |request, _| {
+      let mut response = contents(&request.contents.value_as_string().unwrap_or_default().to_uppercase());
+      response.metadata = hashmap!{ "contentType".to_string() => json!("text/plain") };
+      Ok(vec![response])
+    });
+
+    let responses = registry.sync_message("a request for a user", &contents("fred"), &[]).await.unwrap();
+    expect!(responses.len()).to(be_equal_to(1));
+    expect!(responses[0].contents.value_as_string()).to(be_some().value("FRED"));
+
+    let result = registry.message("a request for a user", &[]).await;
+    expect!(result).to(be_err());
+  }
+}
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use std::panic::RefUnwindSafe;
+use std::time::Duration;
 
 use ansi_term::{ANSIGenericString, Style};
 use ansi_term::Colour::*;
@@ -8,6 +9,7 @@ use base64::engine::general_purpose::STANDARD;
 use bytes::Bytes;
 use maplit::*;
 use pact_models::bodies::OptionalBody;
+use pact_models::generators::GeneratorTestMode;
 use pact_models::http_parts::HttpPart;
 use pact_models::interaction::Interaction;
 use pact_models::message::Message;
@@ -20,20 +22,39 @@ use serde_json::{json, Value};
 use tracing::{debug, trace, warn};
 
 use pact_matching::{match_message, match_sync_message_response, Mismatch};
-
-use crate::{MismatchResult, ProviderInfo, ProviderTransport, VerificationOptions};
+use pact_matching::generators::apply_generators_to_sync_message;
+
+use crate::{
+  ASYNC_MESSAGE_TRANSPORTS,
+  command_transport,
+  MismatchResult,
+  ProviderClients,
+  ProviderInfo,
+  select_provider_transport,
+  SYNC_MESSAGE_TRANSPORTS,
+  VerificationOptions
+};
 use crate::callback_executors::RequestFilterExecutor;
 use crate::provider_client::make_provider_request;
 use crate::utils::as_safe_ref;
+use crate::websockets::{verify_sync_message_over_websocket, websocket_transport};
 
 pub(crate) async fn verify_message_from_provider<'a, F: RequestFilterExecutor>(
   provider: &ProviderInfo,
   pact: &Box<dyn Pact + Send + Sync + RefUnwindSafe + 'a>,
   interaction: &Box<dyn Interaction + Send + Sync + RefUnwindSafe>,
   options: &VerificationOptions<F>,
-  client: &reqwest::Client,
+  clients: &ProviderClients,
   _: &HashMap<&str, Value>
 ) -> Result<Option<String>, MismatchResult> {
+  if let Some(message_provider) = &options.message_provider {
+    debug!("Fetching the message from the in-process message provider");
+    return match message_provider.message(&interaction.description(), &interaction.provider_states()).await {
+      Ok(contents) => match_actual_message(pact, interaction, contents).await,
+      Err(err) => Err(MismatchResult::Error(err.to_string(), interaction.id().clone()))
+    };
+  }
+
   let mut request_body = json!({
     "description": interaction.description()
   });
@@ -54,61 +75,37 @@ pub(crate) async fn verify_message_from_provider<'a, F: RequestFilterExecutor>(
     .. HttpRequest::default()
   };
 
-  let transport = if interaction.is_v4() {
-    if let Some(v4) = interaction.as_v4() {
-      v4.transport().clone()
-    } else {
-      None
-    }
-  } else {
-    None
-  };
-  let transport = if let Some(transport) = transport {
-    provider.transports
-      .iter()
-      .find(|t| t.transport == transport)
-      .cloned()
-  } else {
-    provider.transports
-      .iter()
-      .find(|t| t.transport == "message" || t.transport == "async-message")
-      .cloned()
-  }.map(|t| {
-    if t.scheme.is_none() {
-      ProviderTransport {
-        scheme: Some("http".to_string()),
-        .. t
+  let transport = interaction.as_v4().and_then(|v4| v4.transport());
+  let transport = select_provider_transport(provider, transport.as_deref(), ASYNC_MESSAGE_TRANSPORTS);
+
+  if let Some(transport) = transport.as_ref().filter(|t| t.is_command()) {
+    debug!("Fetching the message by running the command for the transport {:?}", transport);
+    let timeout = Duration::from_millis(options.request_timeout);
+    return match command_transport::fetch_messages(transport, &request_body, timeout).await {
+      Ok(messages) => {
+        if messages.len() > 1 {
+          warn!("The message command returned {} messages, will only use the first one", messages.len());
+        }
+        match messages.into_iter().next() {
+          Some(contents) => match_actual_message(pact, interaction, contents).await,
+          None => Err(MismatchResult::Error("The message command did not return a message".to_string(),
+            interaction.id().clone()))
+        }
       }
-    } else {
-      t
-    }
-  });
+      Err(err) => Err(MismatchResult::Error(err.to_string(), interaction.id().clone()))
+    };
+  }
 
+  let client = clients.for_transport(transport.as_ref());
   match make_provider_request(provider, &message_request, options, client, transport).await {
     Ok(ref actual_response) => {
       let metadata = extract_metadata(actual_response);
-      let actual = AsynchronousMessage {
-        contents: MessageContents {
-          metadata,
-          contents: actual_response.body.clone(),
-          .. MessageContents::default()
-        },
-        .. AsynchronousMessage::default()
+      let contents = MessageContents {
+        metadata,
+        contents: actual_response.body.clone(),
+        .. MessageContents::default()
       };
-
-      debug!("actual message = {:?}", actual);
-
-      let mismatches = match_message(interaction, &actual.boxed(), pact).await;
-      if mismatches.is_empty() {
-        Ok(interaction.id().clone())
-      } else {
-        Err(MismatchResult::Mismatches {
-          mismatches,
-          expected: as_safe_ref(interaction.as_ref()),
-          actual: as_safe_ref(&actual),
-          interaction_id: interaction.id().clone()
-        })
-      }
+      match_actual_message(pact, interaction, contents).await
     },
     Err(err) => {
       Err(MismatchResult::Error(err.to_string(), interaction.id().clone()))
@@ -116,6 +113,31 @@ pub(crate) async fn verify_message_from_provider<'a, F: RequestFilterExecutor>(
   }
 }
 
+async fn match_actual_message<'a>(
+  pact: &Box<dyn Pact + Send + Sync + RefUnwindSafe + 'a>,
+  interaction: &Box<dyn Interaction + Send + Sync + RefUnwindSafe>,
+  contents: MessageContents
+) -> Result<Option<String>, MismatchResult> {
+  let actual = AsynchronousMessage {
+    contents,
+    .. AsynchronousMessage::default()
+  };
+
+  debug!("actual message = {:?}", actual);
+
+  let mismatches = match_message(interaction, &actual.boxed(), pact).await;
+  if mismatches.is_empty() {
+    Ok(interaction.id().clone())
+  } else {
+    Err(MismatchResult::Mismatches {
+      mismatches,
+      expected: as_safe_ref(interaction.as_ref()),
+      actual: as_safe_ref(&actual),
+      interaction_id: interaction.id().clone()
+    })
+  }
+}
+
 pub fn process_message_result(
   interaction: &Message,
   match_result: &Result<Option<String>, MismatchResult>,
@@ -274,9 +296,26 @@ pub(crate) async fn verify_sync_message_from_provider<'a, F: RequestFilterExecut
   pact: &Box<dyn Pact + Send + Sync + RefUnwindSafe + 'a>,
   message: SynchronousMessage,
   options: &VerificationOptions<F>,
-  client: &reqwest::Client,
-  _: &HashMap<&str, Value>
+  clients: &ProviderClients,
+  provider_states_context: &HashMap<&str, Value>
 ) -> Result<Option<String>, MismatchResult> {
+  if let Some(transport) = websocket_transport(provider, &message) {
+    debug!("Verifying the synchronous message over the WebSocket transport {:?}", transport);
+    return verify_sync_message_over_websocket(provider, pact, &message, &transport, options,
+      provider_states_context).await;
+  }
+
+  if let Some(message_provider) = &options.message_provider {
+    debug!("Fetching the synchronous message response from the in-process message provider");
+    let (request, _) = apply_generators_to_sync_message(&message, &GeneratorTestMode::Provider,
+      provider_states_context, &pact.plugin_data(), &message.plugin_config).await;
+    return match message_provider.sync_message(&message.description(), &request,
+      &message.provider_states()).await {
+      Ok(responses) => match_actual_sync_message(pact, &message, responses).await,
+      Err(err) => Err(MismatchResult::Error(err.to_string(), message.id().clone()))
+    };
+  }
+
   if message.response.len() > 1 {
     warn!("Matching synchronous messages with more than one response is not currently supported, will only use the first response");
   }
@@ -302,27 +341,18 @@ pub(crate) async fn verify_sync_message_from_provider<'a, F: RequestFilterExecut
     .. HttpRequest::default()
   };
 
-  let transport = if let Some(transport) = &message.transport {
-    provider.transports
-      .iter()
-      .find(|t| &t.transport == transport)
-      .cloned()
-  } else {
-    provider.transports
-      .iter()
-      .find(|t| t.transport == "message" || t.transport == "sync-message")
-      .cloned()
-  }.map(|t| {
-    if t.scheme.is_none() {
-      ProviderTransport {
-        scheme: Some("http".to_string()),
-        .. t
-      }
-    } else {
-      t
-    }
-  });
+  let transport = select_provider_transport(provider, message.transport.as_deref(), SYNC_MESSAGE_TRANSPORTS);
 
+  if let Some(transport) = transport.as_ref().filter(|t| t.is_command()) {
+    debug!("Fetching the synchronous message response by running the command for the transport {:?}", transport);
+    let timeout = Duration::from_millis(options.request_timeout);
+    return match command_transport::fetch_messages(transport, &request_body, timeout).await {
+      Ok(responses) => match_actual_sync_message(pact, &message, responses).await,
+      Err(err) => Err(MismatchResult::Error(err.to_string(), message.id().clone()))
+    };
+  }
+
+  let client = clients.for_transport(transport.as_ref());
   match make_provider_request(provider, &message_request, options, client, transport).await {
     Ok(ref actual_response) => {
       if actual_response.is_success() {
@@ -332,24 +362,7 @@ pub(crate) async fn verify_sync_message_from_provider<'a, F: RequestFilterExecut
           contents: actual_response.body.clone(),
           ..MessageContents::default()
         };
-        let actual = SynchronousMessage {
-          response: vec![actual_contents],
-          .. SynchronousMessage::default()
-        };
-
-        debug!("actual synchronous message = {:?}", actual);
-
-        let mismatches = match_sync_message_response(&message, &message.response, &actual.response, pact).await;
-        if mismatches.is_empty() {
-          Ok(message.id().clone())
-        } else {
-          Err(MismatchResult::Mismatches {
-            mismatches,
-            expected: as_safe_ref(&message),
-            actual: as_safe_ref(&actual),
-            interaction_id: message.id().clone()
-          })
-        }
+        match_actual_sync_message(pact, &message, vec![actual_contents]).await
       } else {
         Err(MismatchResult::Error(format!("Request to fetch message from provider failed: status {}", actual_response.status), message.id().clone()))
       }
@@ -360,6 +373,31 @@ pub(crate) async fn verify_sync_message_from_provider<'a, F: RequestFilterExecut
   }
 }
 
+async fn match_actual_sync_message<'a>(
+  pact: &Box<dyn Pact + Send + Sync + RefUnwindSafe + 'a>,
+  message: &SynchronousMessage,
+  responses: Vec<MessageContents>
+) -> Result<Option<String>, MismatchResult> {
+  let actual = SynchronousMessage {
+    response: responses,
+    .. SynchronousMessage::default()
+  };
+
+  debug!("actual synchronous message = {:?}", actual);
+
+  let mismatches = match_sync_message_response(message, &message.response, &actual.response, pact).await;
+  if mismatches.is_empty() {
+    Ok(message.id().clone())
+  } else {
+    Err(MismatchResult::Mismatches {
+      mismatches,
+      expected: as_safe_ref(message),
+      actual: as_safe_ref(&actual),
+      interaction_id: message.id().clone()
+    })
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use expectest::prelude::*;
@@ -9,10 +9,10 @@ use anyhow::anyhow;
 use futures::stream::*;
 use itertools::Itertools;
 use maplit::hashmap;
-use pact_models::http_utils;
 use pact_models::http_utils::HttpAuth;
 use pact_models::json_utils::json_to_string;
-use pact_models::pact::{load_pact_from_json, Pact};
+use pact_models::lazy_pact::LazyPact;
+use pact_models::pact::Pact;
 use regex::{Captures, Regex};
 use reqwest::{Method, Url};
 use serde::{Deserialize, Serialize};
@@ -24,6 +24,7 @@ use pact_matching::Mismatch;
 
 use crate::{MismatchResult, VERIFIER_VERSION};
 use crate::metrics::VerificationMetrics;
+use crate::tls::TlsConfig;
 use crate::utils::with_retries;
 
 fn is_true(object: &serde_json::Map<String, Value>, field: &str) -> bool {
@@ -205,6 +206,16 @@ impl HALClient {
     HALClient { url: url.to_string(), auth, ..HALClient::default() }
   }
 
+  /// Configures the client with the custom CA certificates and client certificate
+  pub fn with_tls(self, tls: &TlsConfig) -> anyhow::Result<HALClient> {
+    if tls.is_empty() {
+      Ok(self)
+    } else {
+      let client = tls.configure_client(hal_client_builder())?.build()?;
+      Ok(HALClient { client, .. self })
+    }
+  }
+
   fn update_path_info(self, path_info: serde_json::Value) -> HALClient {
     HALClient {
       client: self.client.clone(),
@@ -325,6 +336,26 @@ impl HALClient {
         .await
   }
 
+  /// Fetches the JSON document at the absolute URL. The document does not have to be a HAL
+  /// resource, so the content type of the response is not checked.
+  async fn fetch_json_from_url(&self, url: &str) -> anyhow::Result<Value> {
+    info!("Fetching JSON from '{}'", url);
+
+    let request_builder = match self.auth {
+      Some(HttpAuth::User(ref username, ref password)) => self.client.get(url).basic_auth(username, password.clone()),
+      Some(HttpAuth::Token(ref token)) => self.client.get(url).bearer_auth(token),
+      _ => self.client.get(url)
+    }.header("accept", "application/hal+json, application/json");
+
+    let response = with_retries(self.retries, request_builder).await
+      .map_err(|err| anyhow!("Request failed - {}", err))?;
+    if response.status().is_success() {
+      response.json::<Value>().await.map_err(|err| anyhow!("Failed to parse JSON - {}", err))
+    } else {
+      Err(anyhow!("Request failed with status - {}", response.status()))
+    }
+  }
+
     async fn parse_broker_response(
         &self,
         path: String,
@@ -525,10 +556,7 @@ fn handle_validation_errors(body: Value) -> PactBrokerError {
 impl Default for HALClient {
   fn default() -> Self {
     HALClient {
-      client: reqwest::ClientBuilder::new()
-        .user_agent(format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")))
-        .build()
-        .unwrap(),
+      client: hal_client_builder().build().unwrap(),
       url: "".to_string(),
       path_info: None,
       auth: None,
@@ -537,6 +565,11 @@ impl Default for HALClient {
   }
 }
 
+fn hal_client_builder() -> reqwest::ClientBuilder {
+  reqwest::ClientBuilder::new()
+    .user_agent(format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")))
+}
+
 fn links_from_json(json: &Value) -> Vec<Link> {
    match json.get("_links") {
     Some(json) => match json {
@@ -552,16 +585,18 @@ fn links_from_json(json: &Value) -> Vec<Link> {
   }
 }
 
-/// Fetches the pacts from the broker that match the provider name
+/// Fetches the pacts from the broker that match the provider name, using the custom CA
+/// certificates and client certificate from the TLS configuration
 pub async fn fetch_pacts_from_broker(
   broker_url: &str,
   provider_name: &str,
-  auth: Option<HttpAuth>
+  auth: Option<HttpAuth>,
+  tls: &TlsConfig
 ) -> anyhow::Result<Vec<anyhow::Result<(Box<dyn Pact + Send + Sync + RefUnwindSafe>, Option<PactVerificationContext>, Vec<Link>)>>> {
   trace!("fetch_pacts_from_broker(broker_url='{}', provider_name='{}', auth={})", broker_url,
     provider_name, auth.clone().unwrap_or_default());
 
-    let mut hal_client = HALClient::with_url(broker_url, auth);
+    let mut hal_client = HALClient::with_url(broker_url, auth).with_tls(tls)?;
     let template_values = hashmap!{ "provider".to_string() => provider_name.to_string() };
 
     hal_client = hal_client.navigate("pb:latest-provider-pacts", &template_values)
@@ -605,7 +640,8 @@ pub async fn fetch_pacts_from_broker(
             Ok((pact_link, pact_json)) => {
               let href = pact_link.href.unwrap_or_default();
               let links = links_from_json(&pact_json);
-              load_pact_from_json(href.as_str(), &pact_json)
+              LazyPact::from_json(href.as_str(), &pact_json)
+                .map(|pact| pact.boxed())
                 .map(|pact| (pact, None, links))
             },
             Err(err) => Err(err.into())
@@ -618,7 +654,9 @@ pub async fn fetch_pacts_from_broker(
     Ok(results)
 }
 
-/// Fetch Pacts from the broker using the "provider-pacts-for-verification" endpoint
+/// Fetch Pacts from the broker using the "provider-pacts-for-verification" endpoint, using the
+/// custom CA certificates and client certificate from the TLS configuration
+#[allow(clippy::too_many_arguments)]
 pub async fn fetch_pacts_dynamically_from_broker(
   broker_url: &str,
   provider_name: String,
@@ -627,14 +665,15 @@ pub async fn fetch_pacts_dynamically_from_broker(
   provider_tags: Vec<String>,
   provider_branch: Option<String>,
   consumer_version_selectors: Vec<ConsumerVersionSelector>,
-  auth: Option<HttpAuth>
+  auth: Option<HttpAuth>,
+  tls: &TlsConfig
 ) -> anyhow::Result<Vec<Result<(Box<dyn Pact + Send + Sync + RefUnwindSafe>, Option<PactVerificationContext>, Vec<Link>), PactBrokerError>>> {
   trace!("fetch_pacts_dynamically_from_broker(broker_url='{}', provider_name='{}', pending={}, \
     include_wip_pacts_since={:?}, provider_tags: {:?}, consumer_version_selectors: {:?}, auth={})",
     broker_url, provider_name, pending, include_wip_pacts_since, provider_tags,
     consumer_version_selectors, auth.clone().unwrap_or_default());
 
-    let mut hal_client = HALClient::with_url(broker_url, auth);
+    let mut hal_client = HALClient::with_url(broker_url, auth).with_tls(tls)?;
     let template_values = hashmap!{ "provider".to_string() => provider_name.clone() };
 
     hal_client = hal_client.navigate("pb:provider-pacts-for-verification", &template_values)
@@ -736,7 +775,8 @@ pub async fn fetch_pacts_dynamically_from_broker(
           Ok((pact_link, pact_json, context)) => {
             let href = pact_link.href.unwrap_or_default();
             let links = links_from_json(&pact_json);
-            load_pact_from_json(href.as_str(), &pact_json)
+            LazyPact::from_json(href.as_str(), &pact_json)
+              .map(|pact| pact.boxed())
               .map(|pact| (pact, Some(context), links))
               .map_err(|err| PactBrokerError::ContentError(format!("{}", err)))
           },
@@ -750,16 +790,18 @@ pub async fn fetch_pacts_dynamically_from_broker(
     Ok(results)
 }
 
-/// Fetch the Pact from the given URL, using any required authentication. This will use a GET
-/// request to the given URL and parse the result into a Pact model. It will also look for any HAL
-/// links in the response, returning those if found.
-pub async fn fetch_pact_from_url(url: &str, auth: &Option<HttpAuth>) -> anyhow::Result<(Box<dyn Pact + Send + Sync + RefUnwindSafe>, Vec<Link>)> {
-  let url = url.to_string();
-  let auth = auth.clone();
-  let (url, pact_json) = tokio::task::spawn_blocking(move || {
-    http_utils::fetch_json_from_url(&url, &auth)
-  }).await??;
-  let pact = load_pact_from_json(&url, &pact_json)?;
+/// Fetch the Pact from the given URL, using any required authentication and the custom CA
+/// certificates or client certificate from the TLS configuration. This will use a GET request to
+/// the given URL and parse the result into a Pact model. It will also look for any HAL links in
+/// the response, returning those if found.
+pub async fn fetch_pact_from_url(
+  url: &str,
+  auth: &Option<HttpAuth>,
+  tls: &TlsConfig
+) -> anyhow::Result<(Box<dyn Pact + Send + Sync + RefUnwindSafe>, Vec<Link>)> {
+  let hal_client = HALClient::with_url(url, auth.clone()).with_tls(tls)?;
+  let pact_json = hal_client.fetch_json_from_url(url).await?;
+  let pact = LazyPact::from_json(url, &pact_json)?.boxed();
   let links = links_from_json(&pact_json);
   Ok((pact, links))
 }
@@ -783,7 +825,9 @@ impl TestResult {
   }
 }
 
-/// Publishes the result to the "pb:publish-verification-results" link in the links associated with the pact
+/// Publishes the result to the "pb:publish-verification-results" link in the links associated with
+/// the pact, using the custom CA certificates and client certificate from the TLS configuration
+#[allow(clippy::too_many_arguments)]
 pub async fn publish_verification_results(
   links: Vec<Link>,
   broker_url: &str,
@@ -793,9 +837,12 @@ pub async fn publish_verification_results(
   build_url: Option<String>,
   provider_tags: Vec<String>,
   branch: Option<String>,
-  metrics_data: Option<&VerificationMetrics>
+  metrics_data: Option<&VerificationMetrics>,
+  tls: &TlsConfig
 ) -> Result<serde_json::Value, PactBrokerError> {
-  let hal_client = HALClient::with_url(broker_url, auth.clone());
+  let hal_client = HALClient::with_url(broker_url, auth.clone())
+    .with_tls(tls)
+    .map_err(|err| PactBrokerError::IoError(err.to_string()))?;
 
   if branch.is_some() {
     publish_provider_branch(&hal_client, &links, &branch.unwrap(), &version).await?;
@@ -1096,6 +1143,9 @@ pub struct PactVerificationProperties {
   #[serde(default)]
   /// If the Pact is pending
   pub pending: bool,
+  #[serde(default)]
+  /// If the Pact is a work in progress pact
+  pub wip: bool,
   /// Notices provided by the Pact Broker
   pub notices: Vec<HashMap<String, String>>,
 }
@@ -1597,7 +1647,7 @@ mod tests {
             .start_mock_server(None);
 
         let result = fetch_pacts_from_broker(pact_broker.url().as_str(),
-                                             "sad_provider", None).await;
+                                             "sad_provider", None, &TlsConfig::default()).await;
         match result {
           Ok(_) => {
             panic!("Expected an error result, but got OK");
@@ -1685,7 +1735,7 @@ mod tests {
             .start_mock_server(None);
 
         let result = fetch_pacts_from_broker(pact_broker.url().as_str(),
-          "happy_provider", None).await;
+          "happy_provider", None, &TlsConfig::default()).await;
         match &result {
           Ok(_) => (),
           Err(err) => panic!("Expected an Ok result, got a error {}", err)
@@ -1826,7 +1876,7 @@ mod tests {
         main_branch: None,
         matching_branch: None,
         environment: None,
-      }), None).await;
+      }), None, &TlsConfig::default()).await;
 
       match &result {
         Ok(_) => (),
@@ -1925,7 +1975,7 @@ mod tests {
       main_branch: None,
       matching_branch: None,
       environment: None,
-    }), None).await;
+    }), None, &TlsConfig::default()).await;
 
     match result {
       Ok(_) => {
@@ -2027,7 +2077,8 @@ mod tests {
         matching_branch: None,
         environment: None,
       }),
-      None
+      None,
+      &TlsConfig::default()
     ).await;
 
     match result {
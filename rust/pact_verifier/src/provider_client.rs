@@ -6,24 +6,32 @@ use std::collections::hash_map::HashMap;
 use std::convert::TryFrom;
 use std::fmt;
 use std::fmt::{Display, Formatter};
+use std::pin::pin;
+use std::time::Duration;
 
 use anyhow::anyhow;
+use bytes::{Bytes, BytesMut};
 use futures::future::*;
 use http::{HeaderMap, HeaderValue, Method};
 use http::header::{HeaderName, InvalidHeaderName, InvalidHeaderValue};
-use http::header::CONTENT_TYPE;
+use http::header::{CONTENT_TYPE, HOST};
+use http_body_util::{BodyExt, Full};
 use http::method::InvalidMethod;
 use itertools::Itertools;
 use maplit::hashmap;
 use pact_models::bodies::OptionalBody;
 use pact_models::content_types::ContentType;
 use pact_models::headers::parse_header;
+use pact_models::matchingrules::MatchingRule;
+use pact_models::path_exp::DocPath;
 use pact_models::v4::http_parts::{HttpRequest, HttpResponse};
 use reqwest::{Client, Error, RequestBuilder};
 use serde_json::Value;
 use tracing::{debug, info, warn};
 
-use crate::{ProviderInfo, ProviderTransport, RequestFilterExecutor, VerificationOptions};
+use pact_matching::sse::{complete_events, EVENT_STREAM_CONTENT_TYPE, parse_event_stream};
+
+use crate::{ProviderInfo, ProviderTransport, RequestFilterExecutor, unix_socket, VerificationOptions};
 use crate::utils::with_retries;
 
 #[derive(Debug)]
@@ -135,7 +143,106 @@ fn create_native_request(
   Ok(builder)
 }
 
-fn extract_headers(headers: &HeaderMap) -> Option<HashMap<String, Vec<String>>> {
+/// Converts the request into a HTTP request that is sent directly to the provider (i.e. over a
+/// Unix domain socket or to an in-process service) instead of via the HTTP client. If
+/// `absolute_uri` is false, the URI will only contain the path and query, with a host header
+/// of localhost.
+pub(crate) fn create_http_request(
+  base_path: &str,
+  request: &HttpRequest,
+  custom_headers: &HashMap<String, String>,
+  absolute_uri: bool
+) -> anyhow::Result<http::Request<Full<Bytes>>> {
+  let mut path = join_paths(base_path, &request.path);
+  if let Some(query) = &request.query {
+    let query_string = url::form_urlencoded::Serializer::new(String::new())
+      .extend_pairs(query.iter()
+        .sorted_by(|a, b| Ord::cmp(&a.0, &b.0))
+        .flat_map(|(k, v)| v.iter().map(move |v| (k, v.clone().unwrap_or_default()))))
+      .finish();
+    if !query_string.is_empty() {
+      path = format!("{}?{}", path, query_string);
+    }
+  }
+
+  let uri = if absolute_uri { format!("http://localhost{}", path) } else { path };
+  let mut builder = http::Request::builder()
+    .method(Method::from_bytes(request.method.as_bytes()).unwrap_or(Method::GET))
+    .uri(uri);
+  if !absolute_uri {
+    builder = builder.header(HOST, "localhost");
+  }
+
+  if let Some(headers) = &request.headers {
+    for (name, values) in headers {
+      if !custom_headers.contains_key(name) {
+        for value in values {
+          builder = builder.header(HeaderName::try_from(name)?, HeaderValue::from_str(value)?);
+        }
+      }
+    }
+  }
+  for (name, value) in custom_headers {
+    builder = builder.header(HeaderName::try_from(name)?, HeaderValue::from_str(value)?);
+  }
+
+  let body = match &request.body {
+    OptionalBody::Present(body, _, _) => body.clone(),
+    OptionalBody::Null if request.content_type().unwrap_or_default().is_json() => Bytes::from("null"),
+    _ => Bytes::new()
+  };
+  builder.body(Full::new(body)).map_err(|err| anyhow!(err))
+}
+
+/// Converts a HTTP response received directly from the provider into a Pact response. If an
+/// event count is given and the response is a Server-Sent Event stream, the body will only be
+/// read until that number of events have been received.
+pub(crate) async fn http_response_to_pact_response<B>(
+  response: http::Response<B>,
+  event_count: Option<usize>
+) -> anyhow::Result<HttpResponse>
+  where B: http_body::Body<Data = Bytes>,
+        B::Error: Display {
+  let status = response.status().as_u16();
+  let headers = extract_headers(response.headers());
+  let pact_response = HttpResponse {
+    status,
+    headers,
+    .. HttpResponse::default()
+  };
+
+  let is_event_stream = pact_response.content_type()
+    .map(|ct| ct.base_type() == EVENT_STREAM_CONTENT_TYPE)
+    .unwrap_or(false);
+  let mut body = pin!(response.into_body());
+  let mut buffer = BytesMut::new();
+  while let Some(frame) = body.as_mut().frame().await {
+    match frame {
+      Ok(frame) => if let Some(data) = frame.data_ref() {
+        buffer.extend_from_slice(data);
+        if let Some(count) = event_count.filter(|_| is_event_stream) {
+          if complete_events(&String::from_utf8_lossy(&buffer)) >= count {
+            debug!("Received {} events from the event stream", count);
+            break;
+          }
+        }
+      }
+      Err(err) => {
+        warn!("Failed to read the response body from the provider: {}", err);
+        break;
+      }
+    }
+  }
+
+  let body = if buffer.is_empty() {
+    OptionalBody::Empty
+  } else {
+    OptionalBody::Present(buffer.freeze(), pact_response.content_type(), None)
+  };
+  Ok(HttpResponse { body, .. pact_response })
+}
+
+pub(crate) fn extract_headers(headers: &HeaderMap) -> Option<HashMap<String, Vec<String>>> {
   if !headers.is_empty() {
     let result = headers.keys()
       .map(|name| {
@@ -161,8 +268,18 @@ fn extract_headers(headers: &HeaderMap) -> Option<HashMap<String, Vec<String>>>
   }
 }
 
-async fn extract_body(response: reqwest::Response, pact_response: &HttpResponse) -> anyhow::Result<OptionalBody> {
-  let body = response.bytes().await?;
+async fn extract_body(
+  response: reqwest::Response,
+  pact_response: &HttpResponse,
+  event_count: Option<usize>
+) -> anyhow::Result<OptionalBody> {
+  let is_event_stream = pact_response.content_type()
+    .map(|ct| ct.base_type() == EVENT_STREAM_CONTENT_TYPE)
+    .unwrap_or(false);
+  let body = match event_count {
+    Some(count) if is_event_stream => read_event_stream(response, count).await,
+    _ => response.bytes().await?
+  };
   if !body.is_empty() {
     Ok(OptionalBody::Present(body, pact_response.content_type(), None))
   } else {
@@ -170,7 +287,58 @@ async fn extract_body(response: reqwest::Response, pact_response: &HttpResponse)
   }
 }
 
-async fn native_response_to_pact_response(native_response: reqwest::Response) -> anyhow::Result<HttpResponse> {
+/// Reads a Server-Sent Event stream until the number of events have been received, the stream
+/// ends or the request times out. Event streams are normally kept open by the provider, so reading
+/// the whole body would never complete.
+async fn read_event_stream(mut response: reqwest::Response, event_count: usize) -> Bytes {
+  let mut buffer = BytesMut::new();
+  loop {
+    match response.chunk().await {
+      Ok(Some(chunk)) => {
+        buffer.extend_from_slice(&chunk);
+        if complete_events(&String::from_utf8_lossy(&buffer)) >= event_count {
+          debug!("Received {} events from the event stream", event_count);
+          break;
+        }
+      }
+      Ok(None) => break,
+      Err(err) => {
+        warn!("Stopped reading the event stream from the provider: {}", err);
+        break;
+      }
+    }
+  }
+  buffer.freeze()
+}
+
+/// Number of events to read from a Server-Sent Event stream before the response is considered
+/// complete. This is the number of events in the expected response, or the maximum (or minimum)
+/// number of events if there is a min/max type matcher defined for the events.
+pub(crate) fn expected_event_count(expected: &HttpResponse) -> Option<usize> {
+  let is_event_stream = expected.content_type()
+    .map(|ct| ct.base_type() == EVENT_STREAM_CONTENT_TYPE)
+    .unwrap_or(false);
+  if !is_event_stream {
+    return None;
+  }
+
+  let events = parse_event_stream(&expected.body.value_as_string().unwrap_or_default()).len();
+  let rules = expected.matching_rules.rules_for_category("body")
+    .and_then(|category| category.rules.get(&DocPath::root()).cloned())
+    .map(|rule_list| rule_list.rules)
+    .unwrap_or_default();
+  let count = rules.iter().fold(events, |count, rule| match rule {
+    MatchingRule::MaxType(max) | MatchingRule::MinMaxType(_, max) => *max,
+    MatchingRule::MinType(min) => count.max(*min),
+    _ => count
+  });
+  Some(count)
+}
+
+async fn native_response_to_pact_response(
+  native_response: reqwest::Response,
+  event_count: Option<usize>
+) -> anyhow::Result<HttpResponse> {
   debug!("Received native response: {:?}", native_response);
 
   let status = native_response.status().as_u16();
@@ -181,21 +349,49 @@ async fn native_response_to_pact_response(native_response: reqwest::Response) ->
     .. HttpResponse::default()
   };
 
-  let body = extract_body(native_response, &response).await?;
+  let body = extract_body(native_response, &response, event_count).await?;
 
   Ok(HttpResponse {
     body, .. response.clone()
   })
 }
 
+/// Returns the base URL for requests to the provider. If no transport was selected for the
+/// interaction, the protocol, host, port and path of the provider are used.
+pub(crate) fn provider_base_url(provider: &ProviderInfo, transport: Option<&ProviderTransport>) -> String {
+  #[allow(deprecated)]
+  transport
+    .map(|trans| trans.base_url(&provider.host))
+    .unwrap_or_else(|| {
+      match provider.port {
+        Some(port) => format!("{}://{}:{}{}", provider.protocol, provider.host, port, provider.path),
+        None => format!("{}://{}{}", provider.protocol, provider.host, provider.path),
+      }
+    })
+}
+
 /// This function makes the actual request to the provider, executing any request filter before
-/// executing the request
+/// executing the request. For transports that use HTTP/2 with prior knowledge (h2c), the client
+/// must have been configured for it.
 pub async fn make_provider_request<F: RequestFilterExecutor>(
   provider: &ProviderInfo,
   request: &HttpRequest,
   options: &VerificationOptions<F>,
   client: &Client,
   transport: Option<ProviderTransport>
+) -> anyhow::Result<HttpResponse> {
+  make_provider_request_for_response(provider, request, None, options, client, transport).await
+}
+
+/// Makes the request to the provider, using the expected response to determine how much of a
+/// streamed (Server-Sent Event) response body to read
+pub(crate) async fn make_provider_request_for_response<F: RequestFilterExecutor>(
+  provider: &ProviderInfo,
+  request: &HttpRequest,
+  expected_response: Option<&HttpResponse>,
+  options: &VerificationOptions<F>,
+  client: &Client,
+  transport: Option<ProviderTransport>
 ) -> anyhow::Result<HttpResponse> {
   let request_filter_option = options.request_filter.clone();
   let request = if request_filter_option.is_some() {
@@ -206,15 +402,40 @@ pub async fn make_provider_request<F: RequestFilterExecutor>(
     request.clone()
   };
 
-  #[allow(deprecated)]
-  let base_url = transport
-    .map(|trans| trans.base_url(&provider.host))
-    .unwrap_or_else(|| {
-      match provider.port {
-        Some(port) => format!("{}://{}:{}{}", provider.protocol, provider.host, port, provider.path),
-        None => format!("{}://{}{}", provider.protocol, provider.host, provider.path),
-      }
-    });
+  let event_count = expected_response.and_then(expected_event_count);
+  if let Some(service) = &options.provider_service {
+    #[allow(deprecated)]
+    let base_path = transport.as_ref()
+      .and_then(|t| t.path.clone())
+      .unwrap_or_else(|| provider.path.clone());
+    info!("Calling the in-process provider service");
+    info!("Sending request {request}");
+    debug!("body:\n{}", request.body.display_string());
+    let native_request = create_http_request(&base_path, &request, &options.custom_headers, false)?;
+    let timeout = Duration::from_millis(options.request_timeout);
+    let response = tokio::time::timeout(timeout, async {
+      let response = service.call(native_request).await?;
+      http_response_to_pact_response(response, event_count).await
+    }).await
+      .map_err(|_| anyhow!("Request to the provider service timed out after {} ms", options.request_timeout))??;
+    info!("Received response: {}", response);
+    debug!("body:\n{}", response.body.display_string());
+    return Ok(response);
+  }
+
+  if let Some(transport) = transport.as_ref().filter(|t| t.is_unix_socket()) {
+    info!("Sending request to provider via Unix domain socket {:?}", transport.socket_path);
+    debug!("Provider details = {provider:?}");
+    info!("Sending request {request}");
+    debug!("body:\n{}", request.body.display_string());
+    let response = unix_socket::send_request(transport, &request, &options.custom_headers,
+      Duration::from_millis(options.request_timeout), event_count).await?;
+    info!("Received response: {}", response);
+    debug!("body:\n{}", response.body.display_string());
+    return Ok(response);
+  }
+
+  let base_url = provider_base_url(provider, transport.as_ref());
 
   info!("Sending request to provider at {base_url}");
   debug!("Provider details = {provider:?}");
@@ -224,7 +445,7 @@ pub async fn make_provider_request<F: RequestFilterExecutor>(
 
   let response = request.send()
     .map_err(|err| anyhow!(err))
-    .and_then(native_response_to_pact_response)
+    .and_then(|response| native_response_to_pact_response(response, event_count))
     .await?;
 
   info!("Received response: {}", response);
@@ -289,7 +510,11 @@ mod tests {
   use itertools::Itertools;
   use maplit::*;
   use pact_models::bodies::OptionalBody;
-  use pact_models::v4::http_parts::HttpRequest;
+  use pact_models::matchingrules;
+  use pact_models::matchingrules::MatchingRule;
+  use pact_models::v4::http_parts::{HttpRequest, HttpResponse};
+  use tokio::io::{AsyncReadExt, AsyncWriteExt};
+  use tokio::net::TcpListener;
 
   use pact_consumer::builders::{HttpPartBuilder, PactBuilderAsync};
   use pact_consumer::mock_server::StartMockServer;
@@ -301,7 +526,7 @@ mod tests {
     VerificationOptions
   };
 
-  use super::{create_native_request, extract_headers, join_paths};
+  use super::{create_native_request, expected_event_count, extract_headers, join_paths, make_provider_request_for_response};
 
   #[test]
   fn extract_headers_tests() {
@@ -518,4 +743,57 @@ mod tests {
       "x-c"
     ]));
   }
+
+  #[test]
+  fn expected_event_count_test() {
+    let response = HttpResponse {
+      headers: Some(hashmap! { "Content-Type".to_string() => vec![ "text/event-stream".to_string() ] }),
+      body: OptionalBody::Present("data: 1\n\ndata: 2\n\n".into(), Some("text/event-stream".into()), None),
+      .. HttpResponse::default()
+    };
+    expect!(expected_event_count(&response)).to(be_some().value(2));
+
+    let with_max = HttpResponse {
+      matching_rules: matchingrules! { "body" => { "$" => [ MatchingRule::MinMaxType(1, 5) ] } },
+      .. response.clone()
+    };
+    expect!(expected_event_count(&with_max)).to(be_some().value(5));
+
+    let with_min = HttpResponse {
+      matching_rules: matchingrules! { "body" => { "$" => [ MatchingRule::MinType(3) ] } },
+      .. response.clone()
+    };
+    expect!(expected_event_count(&with_min)).to(be_some().value(3));
+
+    expect!(expected_event_count(&HttpResponse::default())).to(be_none());
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn reads_event_stream_until_the_expected_events_are_received() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+      let (mut stream, _) = listener.accept().await.unwrap();
+      let mut buffer = [0_u8; 1024];
+      let _ = stream.read(&mut buffer).await;
+      stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\r\n").await.unwrap();
+      stream.write_all(b"id: 1\ndata: {\"tick\": 1}\n\n").await.unwrap();
+      stream.write_all(b"id: 2\ndata: {\"tick\": 2}\n\n").await.unwrap();
+      // Keep the stream open, as a provider would
+      tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+    });
+
+    #[allow(deprecated)]
+    let provider = ProviderInfo { host: "127.0.0.1".to_string(), port: Some(port), .. ProviderInfo::default() };
+    let expected = HttpResponse {
+      headers: Some(hashmap! { "Content-Type".to_string() => vec![ "text/event-stream".to_string() ] }),
+      body: OptionalBody::Present("data: {}\n\ndata: {}\n\n".into(), Some("text/event-stream".into()), None),
+      .. HttpResponse::default()
+    };
+    let options = VerificationOptions::<NullRequestFilterExecutor>::default();
+    let client = configure_http_client(&options).unwrap();
+    let response = make_provider_request_for_response(&provider, &HttpRequest::default(), Some(&expected),
+      &options, &client, None).await.unwrap();
+    expect!(response.body.value_as_string()).to(be_some().value("id: 1\ndata: {\"tick\": 1}\n\nid: 2\ndata: {\"tick\": 2}\n\n"));
+  }
 }
@@ -0,0 +1,539 @@
+//! Support for starting the provider process before the verification is run, waiting for it to
+//! be ready to receive requests, and then shutting it down once the verification is complete.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::net::TcpStream;
+use tokio::process::{Child, Command};
+use tokio::time::Instant;
+use tracing::{debug, info, warn};
+
+use crate::ProviderInfo;
+
+/// How to determine that the provider process is ready to receive requests
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum ProviderReadiness {
+  /// Wait for the port of the provider (taken from the provider transports) to accept connections
+  #[default]
+  ProviderPort,
+  /// Wait for the given port on the provider host to accept connections
+  Port(u16),
+  /// Wait for a GET request to the health check URL to return a successful response
+  HealthUrl(String),
+  /// Do not wait for the provider
+  None
+}
+
+/// Configuration for starting the provider process
+#[derive(Debug, Clone)]
+pub struct ProviderProcess {
+  /// Command to start the provider. This is run using the system shell.
+  pub command: String,
+  /// Additional environment variables to set for the provider process
+  pub env: HashMap<String, String>,
+  /// Working directory for the provider process. Defaults to the current working directory.
+  pub cwd: Option<PathBuf>,
+  /// How to determine that the provider is ready
+  pub readiness: ProviderReadiness,
+  /// Maximum time to wait for the provider to be ready
+  pub startup_timeout: Duration,
+  /// Time to wait for the provider to exit once it has been asked to shut down, after which it
+  /// will be killed
+  pub shutdown_timeout: Duration
+}
+
+impl ProviderProcess {
+  /// Create the configuration for the given command, using the default readiness check and timeouts
+  pub fn new<S: Into<String>>(command: S) -> Self {
+    ProviderProcess {
+      command: command.into(),
+      .. ProviderProcess::default()
+    }
+  }
+}
+
+impl Default for ProviderProcess {
+  fn default() -> Self {
+    ProviderProcess {
+      command: String::default(),
+      env: HashMap::default(),
+      cwd: None,
+      readiness: ProviderReadiness::default(),
+      startup_timeout: Duration::from_secs(30),
+      shutdown_timeout: Duration::from_secs(5)
+    }
+  }
+}
+
+/// Running provider process that has been started by the verifier. If this is dropped without
+/// being shut down (i.e. the verification returns early with an error, or panics), the process
+/// group of the provider is killed.
+#[derive(Debug)]
+pub struct RunningProvider {
+  child: Child,
+  /// Process ID of the shell, which is also the ID of the process group. This is kept as the
+  /// child will no longer return it once it has exited.
+  pid: Option<u32>,
+  shutdown_timeout: Duration,
+  shut_down: bool
+}
+
+/// Starts the provider process, and waits for it to be ready. The output of the process is
+/// forwarded to the verifier log. The health check URL (if configured) is requested with the
+/// given client, so it uses the same TLS configuration as the requests to the provider. Returns
+/// an error if the process fails to start, exits before it is ready, or is not ready within the
+/// startup timeout.
+pub async fn start_provider(
+  config: &ProviderProcess,
+  provider: &ProviderInfo,
+  client: &reqwest::Client
+) -> anyhow::Result<RunningProvider> {
+  if config.command.trim().is_empty() {
+    return Err(anyhow!("The provider command is empty"));
+  }
+
+  info!("Starting provider process: {}", config.command);
+  let mut command = shell_command(&config.command);
+  command.envs(&config.env)
+    .stdin(Stdio::null())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .kill_on_drop(true);
+  if let Some(cwd) = &config.cwd {
+    command.current_dir(cwd);
+  }
+  new_process_group(&mut command);
+
+  let mut child = command.spawn()
+    .map_err(|err| anyhow!("Failed to start the provider process '{}': {}", config.command, err))?;
+  debug!(pid = ?child.id(), "Provider process started");
+  if let Some(stdout) = child.stdout.take() {
+    tokio::spawn(forward_output(stdout, "stdout"));
+  }
+  if let Some(stderr) = child.stderr.take() {
+    tokio::spawn(forward_output(stderr, "stderr"));
+  }
+
+  let mut running = RunningProvider {
+    pid: child.id(),
+    child,
+    shutdown_timeout: config.shutdown_timeout,
+    shut_down: false
+  };
+  if let Err(err) = wait_until_ready(&mut running.child, config, provider, client).await {
+    running.shutdown().await;
+    return Err(err);
+  }
+  info!("Provider process is ready");
+  Ok(running)
+}
+
+impl RunningProvider {
+  /// Process ID of the provider process, if it is still running
+  pub fn id(&self) -> Option<u32> {
+    self.child.id()
+  }
+
+  /// Shuts the provider process down. The process group is first asked to terminate, and is then
+  /// killed if it has not exited within the shutdown timeout. The process group is always
+  /// signalled, as processes started by the shell may still be running after the shell has exited.
+  pub async fn shutdown(mut self) {
+    self.shut_down = true;
+    let exited = match self.child.try_wait() {
+      Ok(Some(status)) => {
+        debug!("Provider process has already exited with {}", status);
+        true
+      }
+      _ => false
+    };
+
+    info!("Shutting down the provider process");
+    terminate(self.pid);
+    let child = &mut self.child;
+    let pid = self.pid;
+    let wait_for_exit = async move {
+      if !exited {
+        match child.wait().await {
+          Ok(status) => debug!("Provider process exited with {}", status),
+          Err(err) => warn!("Failed to wait for the provider process to exit: {}", err)
+        }
+      }
+      wait_for_process_group(pid).await;
+    };
+    if tokio::time::timeout(self.shutdown_timeout, wait_for_exit).await.is_err() {
+      warn!("Provider process did not exit within {:?}, killing it", self.shutdown_timeout);
+      kill(self.pid, &mut self.child).await;
+    }
+  }
+}
+
+impl Drop for RunningProvider {
+  fn drop(&mut self) {
+    if !self.shut_down {
+      warn!("Provider process was not shut down, killing it");
+      kill_now(self.pid, &mut self.child);
+    }
+  }
+}
+
+/// Command that runs the given command line using the system shell
+#[cfg(unix)]
+pub(crate) fn shell_command(command: &str) -> Command {
+  let mut cmd = Command::new("sh");
+  cmd.arg("-c").arg(command);
+  cmd
+}
+
+/// Command that runs the given command line using the system shell
+#[cfg(windows)]
+pub(crate) fn shell_command(command: &str) -> Command {
+  let mut cmd = Command::new("cmd");
+  cmd.arg("/C").arg(command);
+  cmd
+}
+
+/// Starts the command in a new process group, so that it and any processes started by the shell
+/// can be signalled together
+#[cfg(unix)]
+pub(crate) fn new_process_group(command: &mut Command) {
+  unsafe {
+    command.pre_exec(|| {
+      if libc::setpgid(0, 0) == 0 {
+        Ok(())
+      } else {
+        Err(std::io::Error::last_os_error())
+      }
+    });
+  }
+}
+
+#[cfg(not(unix))]
+pub(crate) fn new_process_group(_command: &mut Command) {
+  // Process groups are only supported on Unix
+}
+
+/// Sends the signal to the process group of the provider, so any processes started by the shell
+/// also get signalled. Returns false if there are no processes left in the group.
+#[cfg(unix)]
+fn signal_process_group(pid: Option<u32>, signal: libc::c_int) -> bool {
+  match pid {
+    Some(pid) => unsafe { libc::kill(-(pid as libc::pid_t), signal) == 0 },
+    None => false
+  }
+}
+
+/// Sends SIGTERM to the process group of the provider, so any processes started by the shell
+/// also get terminated
+#[cfg(unix)]
+fn terminate(pid: Option<u32>) {
+  signal_process_group(pid, libc::SIGTERM);
+}
+
+#[cfg(not(unix))]
+fn terminate(_pid: Option<u32>) {
+  // There is no graceful way to terminate a process on this platform, it will be killed once the
+  // shutdown timeout expires
+}
+
+/// Waits for all the processes in the process group of the provider to exit
+#[cfg(unix)]
+async fn wait_for_process_group(pid: Option<u32>) {
+  while signal_process_group(pid, 0) {
+    tokio::time::sleep(Duration::from_millis(50)).await;
+  }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_process_group(_pid: Option<u32>) {
+  // Process groups are only supported on Unix
+}
+
+#[cfg(unix)]
+async fn kill(pid: Option<u32>, child: &mut Child) {
+  signal_process_group(pid, libc::SIGKILL);
+  let _ = child.wait().await;
+}
+
+/// Kills the process group of the provider without waiting for it to exit. `kill_on_drop` only
+/// kills the shell that was used to start the provider, and not the processes it started.
+#[cfg(unix)]
+fn kill_now(pid: Option<u32>, _child: &mut Child) {
+  kill_process_group(pid);
+}
+
+/// Kills all the processes in the process group of a command started with `new_process_group`
+#[cfg(unix)]
+pub(crate) fn kill_process_group(pid: Option<u32>) {
+  signal_process_group(pid, libc::SIGKILL);
+}
+
+#[cfg(not(unix))]
+pub(crate) fn kill_process_group(_pid: Option<u32>) {
+  // Process groups are only supported on Unix, the command will be killed when it is dropped
+}
+
+#[cfg(not(unix))]
+fn kill_now(_pid: Option<u32>, child: &mut Child) {
+  if let Err(err) = child.start_kill() {
+    warn!("Failed to kill the provider process: {}", err);
+  }
+}
+
+#[cfg(not(unix))]
+async fn kill(_pid: Option<u32>, child: &mut Child) {
+  if let Err(err) = child.kill().await {
+    warn!("Failed to kill the provider process: {}", err);
+  }
+}
+
+async fn forward_output<R: AsyncRead + Unpin>(output: R, stream: &'static str) {
+  let mut lines = BufReader::new(output).lines();
+  loop {
+    match lines.next_line().await {
+      Ok(Some(line)) => info!(target: "pact_verifier::provider", "[provider {}] {}", stream, line),
+      Ok(None) => break,
+      Err(err) => {
+        debug!("Failed to read the provider {}: {}", stream, err);
+        break;
+      }
+    }
+  }
+}
+
+async fn wait_until_ready(
+  child: &mut Child,
+  config: &ProviderProcess,
+  provider: &ProviderInfo,
+  client: &reqwest::Client
+) -> anyhow::Result<()> {
+  let readiness = match &config.readiness {
+    ProviderReadiness::ProviderPort => match provider_port(provider) {
+      Some(port) => ProviderReadiness::Port(port),
+      None => {
+        warn!("Could not determine the port of the provider, will not wait for the provider to be ready");
+        ProviderReadiness::None
+      }
+    },
+    readiness => readiness.clone()
+  };
+  if readiness == ProviderReadiness::None {
+    return Ok(());
+  }
+
+  debug!(?readiness, "Waiting for the provider to be ready");
+  let deadline = Instant::now() + config.startup_timeout;
+  loop {
+    if let Some(status) = child.try_wait()? {
+      return Err(anyhow!("The provider process exited with {} before it was ready", status));
+    }
+
+    let ready = match &readiness {
+      ProviderReadiness::Port(port) => TcpStream::connect((provider.host.as_str(), *port)).await.is_ok(),
+      ProviderReadiness::HealthUrl(url) => match client.get(url).timeout(Duration::from_secs(1)).send().await {
+        Ok(response) => response.status().is_success(),
+        Err(_) => false
+      },
+      _ => true
+    };
+    if ready {
+      return Ok(());
+    }
+
+    if Instant::now() >= deadline {
+      return Err(anyhow!("The provider was not ready after {:?} ({})", config.startup_timeout,
+        readiness_description(&readiness, provider)));
+    }
+    tokio::time::sleep(Duration::from_millis(100)).await;
+  }
+}
+
+fn readiness_description(readiness: &ProviderReadiness, provider: &ProviderInfo) -> String {
+  match readiness {
+    ProviderReadiness::Port(port) => format!("port {}:{} was not accepting connections", provider.host, port),
+    ProviderReadiness::HealthUrl(url) => format!("health check URL {} did not return a successful response", url),
+    _ => String::default()
+  }
+}
+
+/// Port the provider will be listening on. This is the first HTTP transport with a port, or the
+/// port configured on the provider info.
+#[allow(deprecated)]
+fn provider_port(provider: &ProviderInfo) -> Option<u16> {
+  provider.transports.iter()
+    .filter(|transport| !transport.is_unix_socket())
+    .find(|transport| transport.transport == "http" || transport.transport == "https")
+    .or_else(|| provider.transports.iter().find(|transport| !transport.is_unix_socket()))
+    .and_then(|transport| transport.port)
+    .or(provider.port)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+  use expectest::prelude::*;
+  use maplit::hashmap;
+  use tokio::net::TcpListener;
+
+  use crate::ProviderTransport;
+
+  use super::*;
+
+  #[test]
+  #[allow(deprecated)]
+  fn provider_port_test() {
+    let provider = ProviderInfo {
+      port: None,
+      .. ProviderInfo::default()
+    };
+    expect!(provider_port(&provider)).to(be_none());
+
+    let provider = ProviderInfo {
+      port: Some(1234),
+      .. ProviderInfo::default()
+    };
+    expect!(provider_port(&provider)).to(be_some().value(1234));
+
+    let provider = ProviderInfo {
+      port: Some(1234),
+      transports: vec![
+        ProviderTransport { transport: "grpc".to_string(), port: Some(5555), .. ProviderTransport::default() },
+        ProviderTransport { transport: "http".to_string(), port: Some(8000), .. ProviderTransport::default() }
+      ],
+      .. ProviderInfo::default()
+    };
+    expect!(provider_port(&provider)).to(be_some().value(8000));
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn waits_for_the_provider_port_to_be_open() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let provider = ProviderInfo {
+      host: "127.0.0.1".to_string(),
+      .. ProviderInfo::default()
+    };
+    let config = ProviderProcess {
+      readiness: ProviderReadiness::Port(port),
+      .. ProviderProcess::new("sleep 30")
+    };
+
+    let running = start_provider(&config, &provider, &reqwest::Client::new()).await.unwrap();
+    expect!(running.id()).to(be_some());
+    running.shutdown().await;
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn returns_an_error_if_the_provider_exits_before_it_is_ready() {
+    let config = ProviderProcess {
+      readiness: ProviderReadiness::Port(1),
+      .. ProviderProcess::new("exit 3")
+    };
+
+    let result = start_provider(&config, &ProviderInfo::default(), &reqwest::Client::new()).await;
+    expect!(result.unwrap_err().to_string().contains("exited with exit status: 3")).to(be_true());
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn returns_an_error_if_the_provider_is_not_ready_before_the_timeout() {
+    let config = ProviderProcess {
+      readiness: ProviderReadiness::HealthUrl("http://127.0.0.1:1/health".to_string()),
+      startup_timeout: Duration::from_millis(300),
+      .. ProviderProcess::new("sleep 30")
+    };
+
+    let result = start_provider(&config, &ProviderInfo::default(), &reqwest::Client::new()).await;
+    expect!(result.unwrap_err().to_string().starts_with("The provider was not ready after")).to(be_true());
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn passes_the_environment_and_working_directory_to_the_provider() {
+    let dir = std::env::temp_dir().join(format!("pact-provider-process-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let config = ProviderProcess {
+      env: hashmap!{ "PROVIDER_VALUE".to_string() => "from-env".to_string() },
+      cwd: Some(dir.clone()),
+      readiness: ProviderReadiness::None,
+      .. ProviderProcess::new("echo $PROVIDER_VALUE > output.txt")
+    };
+
+    let mut running = start_provider(&config, &ProviderInfo::default(), &reqwest::Client::new()).await.unwrap();
+    let _ = running.child.wait().await;
+    let output = std::fs::read_to_string(dir.join("output.txt")).unwrap();
+    expect!(output.trim()).to(be_equal_to("from-env"));
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[cfg(target_os = "linux")]
+  fn is_running(pid: &str) -> bool {
+    std::fs::read_to_string(format!("/proc/{}/stat", pid))
+      .map(|stat| !stat.contains(") Z "))
+      .unwrap_or(false)
+  }
+
+  #[cfg(target_os = "linux")]
+  async fn read_child_pid(pid_file: &std::path::Path) -> String {
+    let mut child_pid = String::default();
+    for _ in 0..50 {
+      child_pid = std::fs::read_to_string(pid_file).unwrap_or_default().trim().to_string();
+      if !child_pid.is_empty() {
+        break;
+      }
+      tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    child_pid
+  }
+
+  #[cfg(target_os = "linux")]
+  #[test_log::test(tokio::test)]
+  async fn shutdown_kills_the_process_group_if_the_shell_has_already_exited() {
+    let dir = std::env::temp_dir().join(format!("pact-provider-process-exited-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let config = ProviderProcess {
+      cwd: Some(dir.clone()),
+      readiness: ProviderReadiness::None,
+      shutdown_timeout: Duration::from_secs(2),
+      .. ProviderProcess::new("sleep 30 > /dev/null 2>&1 & echo $! > child.pid")
+    };
+
+    let mut running = start_provider(&config, &ProviderInfo::default(), &reqwest::Client::new()).await.unwrap();
+    let child_pid = read_child_pid(&dir.join("child.pid")).await;
+    let _ = running.child.wait().await;
+    expect!(is_running(&child_pid)).to(be_true());
+
+    running.shutdown().await;
+    expect!(is_running(&child_pid)).to(be_false());
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[cfg(target_os = "linux")]
+  #[test_log::test(tokio::test)]
+  async fn dropping_the_running_provider_kills_the_process_group() {
+    let dir = std::env::temp_dir().join(format!("pact-provider-process-drop-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let config = ProviderProcess {
+      cwd: Some(dir.clone()),
+      readiness: ProviderReadiness::None,
+      .. ProviderProcess::new("sleep 30 & echo $! > child.pid; wait")
+    };
+
+    let running = start_provider(&config, &ProviderInfo::default(), &reqwest::Client::new()).await.unwrap();
+    let child_pid = read_child_pid(&dir.join("child.pid")).await;
+    expect!(is_running(&child_pid)).to(be_true());
+
+    drop(running);
+    let mut running = true;
+    for _ in 0..50 {
+      running = is_running(&child_pid);
+      if !running {
+        break;
+      }
+      tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    expect!(running).to(be_false());
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+}
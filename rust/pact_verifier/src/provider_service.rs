@@ -0,0 +1,86 @@
+//! Support for verifying HTTP interactions against a provider that is called directly in-process
+//! (i.e. a `tower::Service` like an axum router), instead of making requests over the network.
+
+use std::error::Error;
+use std::fmt::{Debug, Formatter};
+use std::future::{Future, poll_fn};
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use http::{Request, Response};
+use http_body_util::{BodyExt, Full};
+use http_body_util::combinators::UnsyncBoxBody;
+use tower_service::Service;
+
+/// Body of the requests sent to an in-process provider service
+pub type ProviderServiceBody = Full<Bytes>;
+
+type BoxError = Box<dyn Error + Send + Sync>;
+type ServiceResponse = Response<UnsyncBoxBody<Bytes, BoxError>>;
+type ServiceFn = dyn Fn(Request<ProviderServiceBody>) -> BoxFuture<'static, anyhow::Result<ServiceResponse>> + Send + Sync;
+
+/// Provider that is called directly by the verifier. Requests to the provider are converted into
+/// HTTP requests and passed to the service, and the responses converted back into Pact responses
+/// in the same way as for requests made over the network.
+#[derive(Clone)]
+pub struct ProviderService {
+  service: Arc<ServiceFn>
+}
+
+impl ProviderService {
+  /// Create a provider service from a `tower::Service` (for instance, an axum router). The service
+  /// is cloned for each request.
+  pub fn from_service<S, B>(service: S) -> Self
+    where S: Service<Request<ProviderServiceBody>, Response = Response<B>> + Clone + Send + Sync + 'static,
+          S::Future: Send + 'static,
+          S::Error: Into<BoxError>,
+          B: http_body::Body<Data = Bytes> + Send + 'static,
+          B::Error: Into<BoxError> {
+    ProviderService {
+      service: Arc::new(move |request| {
+        let mut service = service.clone();
+        async move {
+          poll_fn(|cx| service.poll_ready(cx)).await
+            .map_err(|err| anyhow!("Provider service is not ready: {}", err.into()))?;
+          let response = service.call(request).await
+            .map_err(|err| anyhow!("Provider service failed: {}", err.into()))?;
+          Ok(response.map(|body| body.map_err(Into::into).boxed_unsync()))
+        }.boxed()
+      })
+    }
+  }
+
+  /// Create a provider service from a function that returns a future for the response
+  pub fn from_fn<F, Fut, B>(f: F) -> Self
+    where F: Fn(Request<ProviderServiceBody>) -> Fut + Send + Sync + 'static,
+          Fut: Future<Output = Response<B>> + Send + 'static,
+          B: http_body::Body<Data = Bytes> + Send + 'static,
+          B::Error: Into<BoxError> {
+    ProviderService {
+      service: Arc::new(move |request| {
+        f(request)
+          .map(|response| Ok(response.map(|body| body.map_err(Into::into).boxed_unsync())))
+          .boxed()
+      })
+    }
+  }
+
+  /// Calls the provider service with the request
+  pub(crate) async fn call(&self, request: Request<ProviderServiceBody>) -> anyhow::Result<ServiceResponse> {
+    (self.service)(request).await
+  }
+}
+
+// The service is only called by the verifier, which does not use it again if it panics
+impl UnwindSafe for ProviderService {}
+impl RefUnwindSafe for ProviderService {}
+
+impl Debug for ProviderService {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("ProviderService").finish_non_exhaustive()
+  }
+}
@@ -0,0 +1,596 @@
+//! Built-in request filters that sign or authenticate the requests made to the provider. The
+//! filters are applied to the final request (after any generators have been applied), so the
+//! signatures cover the method, path, query parameters and body that are sent to the provider.
+//!
+//! * `hmac` - Signs the request with a HMAC-SHA256 signature of the method, path, query string,
+//!   timestamp and a SHA-256 hash of the body.
+//! * `sigv4` - Signs the request with an AWS Signature Version 4 style signature.
+//! * `oauth2` - Adds a bearer token fetched from a token endpoint using the OAuth2 client
+//!   credentials grant. The token is cached until it expires.
+
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use itertools::{Either, Itertools};
+use pact_models::bodies::OptionalBody;
+use pact_models::v4::http_parts::HttpRequest;
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tracing::{debug, error};
+
+use crate::callback_executors::RequestFilterExecutor;
+use crate::provider_client::join_paths;
+
+/// Default header that the HMAC signature is added to
+pub const DEFAULT_SIGNATURE_HEADER: &str = "X-Signature";
+/// Default header that the timestamp used for the HMAC signature is added to
+pub const DEFAULT_TIMESTAMP_HEADER: &str = "X-Timestamp";
+
+/// Cached OAuth2 tokens are refreshed this long before they expire
+const TOKEN_EXPIRY_MARGIN: Duration = Duration::from_secs(30);
+
+/// Signs requests with a HMAC-SHA256 signature. The signature is calculated over the following
+/// values, separated by newlines: the method, the path (including the provider base path), the
+/// query string (sorted by key and form URL encoded), the timestamp (seconds since the Unix epoch)
+/// and the SHA-256 hash of the body (hex encoded). The hex encoded signature and timestamp are
+/// added as headers.
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HmacRequestSigner {
+  /// Secret key used to sign the requests
+  pub secret: String,
+  /// Header to add the signature to (defaults to `X-Signature`)
+  #[serde(default = "default_signature_header")]
+  pub signature_header: String,
+  /// Header to add the timestamp to (defaults to `X-Timestamp`)
+  #[serde(default = "default_timestamp_header")]
+  pub timestamp_header: String
+}
+
+fn default_signature_header() -> String {
+  DEFAULT_SIGNATURE_HEADER.to_string()
+}
+
+fn default_timestamp_header() -> String {
+  DEFAULT_TIMESTAMP_HEADER.to_string()
+}
+
+impl HmacRequestSigner {
+  /// Create a signer with the secret key and the default headers
+  pub fn new<S: Into<String>>(secret: S) -> Self {
+    HmacRequestSigner {
+      secret: secret.into(),
+      signature_header: default_signature_header(),
+      timestamp_header: default_timestamp_header()
+    }
+  }
+
+  fn sign(&self, request: &HttpRequest, base_path: &str, now: DateTime<Utc>) -> HttpRequest {
+    let timestamp = now.timestamp().to_string();
+    let string_to_sign = [
+      request.method.to_uppercase(),
+      join_paths(base_path, &request.path),
+      form_query_string(request),
+      timestamp.clone(),
+      hex::encode(Sha256::digest(body_bytes(request)))
+    ].join("\n");
+    debug!("HMAC string to sign: {:?}", string_to_sign);
+
+    let mut request = request.clone();
+    set_header(&mut request, &self.timestamp_header, timestamp);
+    set_header(&mut request, &self.signature_header,
+      hex::encode(hmac_sha256(self.secret.as_bytes(), string_to_sign.as_bytes())));
+    request
+  }
+}
+
+impl Debug for HmacRequestSigner {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("HmacRequestSigner")
+      .field("secret", &"*****")
+      .field("signature_header", &self.signature_header)
+      .field("timestamp_header", &self.timestamp_header)
+      .finish()
+  }
+}
+
+/// Signs requests with an AWS Signature Version 4 style signature. The `Host`, `Content-Type` and
+/// any `X-Amz-*` headers are signed, and the signature added as the `Authorization` header.
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SigV4RequestSigner {
+  /// Access key ID
+  pub access_key_id: String,
+  /// Secret access key
+  pub secret_access_key: String,
+  /// Optional session token, added as the `X-Amz-Security-Token` header
+  pub session_token: Option<String>,
+  /// Region of the service (i.e. `us-east-1`)
+  pub region: String,
+  /// Name of the service (i.e. `execute-api`)
+  pub service: String,
+  /// Host (and port) to add as the `Host` header and include in the signature. The host must be
+  /// signed for AWS services.
+  pub host: Option<String>
+}
+
+impl SigV4RequestSigner {
+  /// Create a signer with the credentials, region and service name
+  pub fn new<S: Into<String>>(access_key_id: S, secret_access_key: S, region: S, service: S) -> Self {
+    SigV4RequestSigner {
+      access_key_id: access_key_id.into(),
+      secret_access_key: secret_access_key.into(),
+      session_token: None,
+      region: region.into(),
+      service: service.into(),
+      host: None
+    }
+  }
+
+  fn sign(&self, request: &HttpRequest, base_path: &str, now: DateTime<Utc>) -> HttpRequest {
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+
+    let mut request = request.clone();
+    remove_header(&mut request, "Authorization");
+    if let Some(host) = &self.host {
+      set_header(&mut request, "Host", host.clone());
+    }
+    set_header(&mut request, "X-Amz-Date", amz_date.clone());
+    if let Some(token) = &self.session_token {
+      set_header(&mut request, "X-Amz-Security-Token", token.clone());
+    }
+
+    let canonical_headers = request.headers.iter()
+      .flatten()
+      .map(|(k, v)| (k.to_lowercase(), v.iter().map(|v| v.split_whitespace().join(" ")).join(",")))
+      .filter(|(k, _)| k == "host" || k == "content-type" || k.starts_with("x-amz-"))
+      .sorted()
+      .collect_vec();
+    let signed_headers = canonical_headers.iter().map(|(k, _)| k.as_str()).join(";");
+    let canonical_request = [
+      request.method.to_uppercase(),
+      self.canonical_uri(&join_paths(base_path, &request.path)),
+      canonical_query_string(&request),
+      canonical_headers.iter().map(|(k, v)| format!("{}:{}\n", k, v)).join(""),
+      signed_headers.clone(),
+      hex::encode(Sha256::digest(body_bytes(&request)))
+    ].join("\n");
+    debug!("SigV4 canonical request: {:?}", canonical_request);
+
+    let scope = format!("{}/{}/{}/aws4_request", date, self.region, self.service);
+    let string_to_sign = format!("AWS4-HMAC-SHA256\n{}\n{}\n{}", amz_date, scope,
+      hex::encode(Sha256::digest(canonical_request.as_bytes())));
+    let signing_key = [self.region.as_str(), self.service.as_str(), "aws4_request"].iter()
+      .fold(hmac_sha256(format!("AWS4{}", self.secret_access_key).as_bytes(), date.as_bytes()),
+        |key, value| hmac_sha256(&key, value.as_bytes()));
+    let signature = hex::encode(hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+
+    set_header(&mut request, "Authorization", format!(
+      "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+      self.access_key_id, scope, signed_headers, signature));
+    request
+  }
+
+  /// Each path segment is URI encoded twice, except for S3 which only requires it once
+  fn canonical_uri(&self, path: &str) -> String {
+    path.split('/')
+      .map(|segment| if self.service == "s3" {
+        uri_encode(segment)
+      } else {
+        uri_encode(&uri_encode(segment))
+      })
+      .join("/")
+  }
+}
+
+impl Debug for SigV4RequestSigner {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("SigV4RequestSigner")
+      .field("access_key_id", &self.access_key_id)
+      .field("secret_access_key", &"*****")
+      .field("session_token", &self.session_token.as_ref().map(|_| "*****"))
+      .field("region", &self.region)
+      .field("service", &self.service)
+      .field("host", &self.host)
+      .finish()
+  }
+}
+
+#[derive(Debug, Clone)]
+struct CachedToken {
+  access_token: String,
+  expires_at: Option<Instant>
+}
+
+/// Adds a bearer token to the requests, which is fetched from a token endpoint using the OAuth2
+/// client credentials grant. The client ID and secret are sent using HTTP basic authentication.
+/// The token is cached until shortly before it expires.
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuth2ClientCredentials {
+  /// URL of the token endpoint
+  pub token_url: String,
+  /// Client ID
+  pub client_id: String,
+  /// Client secret
+  pub client_secret: String,
+  /// Optional scope to request
+  pub scope: Option<String>,
+  /// Optional audience to request
+  pub audience: Option<String>,
+  #[serde(skip)]
+  token: Arc<Mutex<Option<CachedToken>>>
+}
+
+impl OAuth2ClientCredentials {
+  /// Create a filter for the token endpoint and client credentials
+  pub fn new<S: Into<String>>(token_url: S, client_id: S, client_secret: S) -> Self {
+    OAuth2ClientCredentials {
+      token_url: token_url.into(),
+      client_id: client_id.into(),
+      client_secret: client_secret.into(),
+      scope: None,
+      audience: None,
+      token: Default::default()
+    }
+  }
+
+  /// Returns the cached access token, or fetches a new one if there is none or it has expired
+  fn access_token(&self) -> anyhow::Result<String> {
+    let mut cached = self.token.lock().unwrap();
+    if let Some(token) = cached.as_ref() {
+      if token.expires_at.map(|expires_at| expires_at > Instant::now()).unwrap_or(true) {
+        return Ok(token.access_token.clone());
+      }
+    }
+
+    let token = self.fetch_token()?;
+    let access_token = token.access_token.clone();
+    *cached = Some(token);
+    Ok(access_token)
+  }
+
+  fn fetch_token(&self) -> anyhow::Result<CachedToken> {
+    debug!("Fetching OAuth2 access token from {}", self.token_url);
+    let mut form = vec![("grant_type", "client_credentials")];
+    if let Some(scope) = &self.scope {
+      form.push(("scope", scope.as_str()));
+    }
+    if let Some(audience) = &self.audience {
+      form.push(("audience", audience.as_str()));
+    }
+
+    // Request filters are called synchronously, so the token request is made with the blocking
+    // client on a separate thread, as it can not be used from within the async runtime
+    let json = thread::scope(|scope| {
+      scope.spawn(|| -> anyhow::Result<Value> {
+        let response = reqwest::blocking::Client::builder()
+          .timeout(Duration::from_secs(30))
+          .build()?
+          .post(&self.token_url)
+          .basic_auth(&self.client_id, Some(&self.client_secret))
+          .form(&form)
+          .send()?;
+        let status = response.status();
+        if status.is_success() {
+          Ok(response.json()?)
+        } else {
+          Err(anyhow!("The OAuth2 token request to '{}' failed with status {}", self.token_url, status))
+        }
+      }).join()
+    }).map_err(|_| anyhow!("The OAuth2 token request to '{}' panicked", self.token_url))??;
+
+    let access_token = json.get("access_token")
+      .and_then(|token| token.as_str())
+      .ok_or_else(|| anyhow!("The OAuth2 token response from '{}' did not contain an access token", self.token_url))?;
+    let expires_at = json.get("expires_in")
+      .and_then(|expires_in| expires_in.as_u64())
+      .map(|expires_in| Instant::now() + Duration::from_secs(expires_in).saturating_sub(TOKEN_EXPIRY_MARGIN));
+    Ok(CachedToken { access_token: access_token.to_string(), expires_at })
+  }
+}
+
+impl Debug for OAuth2ClientCredentials {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("OAuth2ClientCredentials")
+      .field("token_url", &self.token_url)
+      .field("client_id", &self.client_id)
+      .field("client_secret", &"*****")
+      .field("scope", &self.scope)
+      .field("audience", &self.audience)
+      .finish()
+  }
+}
+
+/// Built-in request filter. When configured from JSON, the type of filter is set with the `type`
+/// attribute (`hmac`, `sigv4` or `oauth2`), with the remaining attributes in camel case.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum RequestFilter {
+  /// Sign the requests with a HMAC-SHA256 signature
+  Hmac(HmacRequestSigner),
+  /// Sign the requests with an AWS SigV4 style signature
+  SigV4(SigV4RequestSigner),
+  /// Add an OAuth2 bearer token fetched with the client credentials grant
+  OAuth2(OAuth2ClientCredentials)
+}
+
+impl RequestFilter {
+  /// Parse the filter configuration from a JSON string
+  pub fn from_json_str(json: &str) -> anyhow::Result<RequestFilter> {
+    serde_json::from_str(json).map_err(|err| anyhow!("Invalid request filter configuration: {}", err))
+  }
+
+  fn apply(&self, request: &HttpRequest, base_path: &str, now: DateTime<Utc>) -> HttpRequest {
+    match self {
+      RequestFilter::Hmac(signer) => signer.sign(request, base_path, now),
+      RequestFilter::SigV4(signer) => signer.sign(request, base_path, now),
+      RequestFilter::OAuth2(oauth2) => match oauth2.access_token() {
+        Ok(token) => {
+          let mut request = request.clone();
+          set_header(&mut request, "Authorization", format!("Bearer {}", token));
+          request
+        }
+        Err(err) => {
+          error!("Failed to get an OAuth2 access token, the request will not be authenticated - {}", err);
+          request.clone()
+        }
+      }
+    }
+  }
+}
+
+/// Request filter executor that applies the built-in request filters, in order
+#[derive(Debug, Clone, Default)]
+pub struct RequestFilters {
+  /// Base path of the provider that the request paths are relative to, which is included in the
+  /// signatures
+  pub base_path: String,
+  /// Filters to apply
+  pub filters: Vec<RequestFilter>
+}
+
+impl RequestFilters {
+  /// Create an executor for the filters
+  pub fn new(filters: Vec<RequestFilter>) -> Self {
+    RequestFilters {
+      filters,
+      .. RequestFilters::default()
+    }
+  }
+
+  /// If there are no filters configured
+  pub fn is_empty(&self) -> bool {
+    self.filters.is_empty()
+  }
+}
+
+impl RequestFilterExecutor for RequestFilters {
+  fn call(self: Arc<Self>, request: &HttpRequest) -> HttpRequest {
+    let now = Utc::now();
+    self.filters.iter()
+      .fold(request.clone(), |request, filter| filter.apply(&request, &self.base_path, now))
+  }
+
+  fn call_non_http(
+    &self,
+    request_body: &OptionalBody,
+    metadata: &HashMap<String, Either<Value, Bytes>>
+  ) -> (OptionalBody, HashMap<String, Either<Value, Bytes>>) {
+    let mut metadata = metadata.clone();
+    for filter in &self.filters {
+      match filter {
+        RequestFilter::OAuth2(oauth2) => match oauth2.access_token() {
+          Ok(token) => {
+            metadata.insert("authorization".to_string(), Either::Left(Value::String(format!("Bearer {}", token))));
+          }
+          Err(err) => error!("Failed to get an OAuth2 access token, the request will not be authenticated - {}", err)
+        },
+        _ => debug!("Request signatures can only be applied to HTTP requests, ignoring {:?}", filter)
+      }
+    }
+    (request_body.clone(), metadata)
+  }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+  let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC can take a key of any size");
+  mac.update(data);
+  mac.finalize().into_bytes().to_vec()
+}
+
+fn body_bytes(request: &HttpRequest) -> Bytes {
+  request.body.value().unwrap_or_default()
+}
+
+fn set_header(request: &mut HttpRequest, name: &str, value: String) {
+  let headers = request.headers.get_or_insert_with(HashMap::new);
+  headers.retain(|k, _| !k.eq_ignore_ascii_case(name));
+  headers.insert(name.to_string(), vec![value]);
+}
+
+fn remove_header(request: &mut HttpRequest, name: &str) {
+  if let Some(headers) = request.headers.as_mut() {
+    headers.retain(|k, _| !k.eq_ignore_ascii_case(name));
+  }
+}
+
+/// Query string in the same form as it is sent to the provider
+fn form_query_string(request: &HttpRequest) -> String {
+  url::form_urlencoded::Serializer::new(String::new())
+    .extend_pairs(request.query.iter()
+      .flatten()
+      .sorted_by(|a, b| Ord::cmp(&a.0, &b.0))
+      .flat_map(|(k, v)| v.iter().map(move |v| (k, v.clone().unwrap_or_default()))))
+    .finish()
+}
+
+fn canonical_query_string(request: &HttpRequest) -> String {
+  request.query.iter()
+    .flatten()
+    .flat_map(|(k, v)| v.iter().map(move |v| (uri_encode(k), uri_encode(v.as_deref().unwrap_or_default()))))
+    .sorted()
+    .map(|(k, v)| format!("{}={}", k, v))
+    .join("&")
+}
+
+/// URI encodes all characters except the unreserved ones (RFC 3986)
+fn uri_encode(value: &str) -> String {
+  value.bytes()
+    .map(|b| match b {
+      b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+      _ => format!("%{:02X}", b)
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::TimeZone;
+  use expectest::prelude::*;
+  use maplit::hashmap;
+  use pact_consumer::*;
+  use pact_consumer::prelude::*;
+  use pact_models::content_types::JSON;
+  use serde_json::json;
+
+  use super::*;
+
+  fn header(request: &HttpRequest, name: &str) -> Option<String> {
+    request.headers.as_ref()
+      .and_then(|headers| headers.get(name))
+      .and_then(|values| values.first().cloned())
+  }
+
+  #[test]
+  fn hmac_signs_the_method_path_query_timestamp_and_body() {
+    let request = HttpRequest {
+      method: "post".to_string(),
+      path: "/users".to_string(),
+      query: Some(hashmap!{
+        "b".to_string() => vec![Some("2".to_string())],
+        "a".to_string() => vec![Some("1".to_string())]
+      }),
+      body: OptionalBody::Present(Bytes::from("{\"id\":1}"), Some(JSON.clone()), None),
+      .. HttpRequest::default()
+    };
+    let now = Utc.with_ymd_and_hms(2024, 5, 1, 10, 0, 0).unwrap();
+
+    let signed = HmacRequestSigner::new("secret").sign(&request, "/api", now);
+    expect!(header(&signed, "X-Timestamp")).to(be_some().value("1714557600"));
+    expect!(header(&signed, "X-Signature")).to(be_some().value(
+      "24bc03c5f72d2210e68fe6990310aa650801d1ada5af78498cd9f2d8d6762d3e"));
+  }
+
+  // Test vectors from the AWS Signature Version 4 test suite
+  #[test]
+  fn sigv4_signs_the_request() {
+    let mut signer = SigV4RequestSigner::new("AKIDEXAMPLE", "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+      "us-east-1", "service");
+    signer.host = Some("example.amazonaws.com".to_string());
+    let now = Utc.with_ymd_and_hms(2015, 8, 30, 12, 36, 0).unwrap();
+
+    let signed = signer.sign(&HttpRequest::default(), "", now);
+    expect!(header(&signed, "X-Amz-Date")).to(be_some().value("20150830T123600Z"));
+    expect!(header(&signed, "Authorization")).to(be_some().value(
+      "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+      SignedHeaders=host;x-amz-date, Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"));
+
+    let request = HttpRequest {
+      query: Some(hashmap!{
+        "Param2".to_string() => vec![Some("value2".to_string())],
+        "Param1".to_string() => vec![Some("value1".to_string())]
+      }),
+      .. HttpRequest::default()
+    };
+    let signed = signer.sign(&request, "", now);
+    expect!(header(&signed, "Authorization")).to(be_some().value(
+      "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+      SignedHeaders=host;x-amz-date, Signature=b97d918cfa904a5beff61c982a1b6f458b799221646efd99d3219ec94cdf2500"));
+  }
+
+  #[test]
+  fn uri_encode_test() {
+    expect!(uri_encode("abc-_.~123")).to(be_equal_to("abc-_.~123"));
+    expect!(uri_encode("a b/c=d")).to(be_equal_to("a%20b%2Fc%3Dd"));
+  }
+
+  #[test]
+  fn parses_the_filter_configuration_from_json() {
+    let filter = RequestFilter::from_json_str(r#"{"type": "hmac", "secret": "secret", "signatureHeader": "X-Sig"}"#).unwrap();
+    match filter {
+      RequestFilter::Hmac(signer) => {
+        expect!(signer.signature_header).to(be_equal_to("X-Sig"));
+        expect!(signer.timestamp_header).to(be_equal_to(DEFAULT_TIMESTAMP_HEADER));
+      }
+      _ => panic!("Expected a HMAC filter, got {:?}", filter)
+    }
+
+    let filter = RequestFilter::from_json_str(r#"{"type": "oauth2", "tokenUrl": "http://localhost/token",
+      "clientId": "id", "clientSecret": "secret", "scope": "read"}"#).unwrap();
+    expect!(matches!(filter, RequestFilter::OAuth2(_))).to(be_true());
+
+    expect!(RequestFilter::from_json_str(r#"{"type": "sigv4", "region": "us-east-1"}"#)).to(be_err());
+    expect!(RequestFilter::from_json_str(r#"{"type": "other"}"#)).to(be_err());
+  }
+
+  #[test]
+  fn debug_does_not_include_the_secrets() {
+    let filters = RequestFilters::new(vec![
+      RequestFilter::Hmac(HmacRequestSigner::new("hmac-secret")),
+      RequestFilter::OAuth2(OAuth2ClientCredentials::new("http://localhost/token", "id", "client-secret"))
+    ]);
+    let debug = format!("{:?}", filters);
+    expect!(debug.contains("hmac-secret")).to(be_false());
+    expect!(debug.contains("client-secret")).to(be_false());
+  }
+
+  #[test_log::test(tokio::test(flavor = "multi_thread"))]
+  async fn oauth2_adds_the_bearer_token_from_the_token_endpoint() {
+    let server = PactBuilderAsync::new("RequestFilters", "TokenEndpoint")
+      .interaction("a token request", "", |mut i| async move {
+        i.request.method("POST");
+        i.request.path("/token");
+        i.request.header("Authorization", "Basic aWQ6c2VjcmV0");
+        i.request.header("Content-Type", "application/x-www-form-urlencoded");
+        i.request.body("grant_type=client_credentials&scope=read");
+        i.response.ok().json_body(json_pattern!({
+          "access_token": "abc123",
+          "token_type": "Bearer",
+          "expires_in": 3600
+        }));
+        i
+      })
+      .await
+      .start_mock_server(None);
+
+    let mut oauth2 = OAuth2ClientCredentials::new(server.path("/token").to_string(), "id".to_string(),
+      "secret".to_string());
+    oauth2.scope = Some("read".to_string());
+    let filters = Arc::new(RequestFilters::new(vec![RequestFilter::OAuth2(oauth2)]));
+
+    let request = filters.clone().call(&HttpRequest::default());
+    expect!(header(&request, "Authorization")).to(be_some().value("Bearer abc123"));
+
+    let (_, metadata) = filters.call_non_http(&OptionalBody::Missing, &hashmap!{});
+    expect!(metadata.get("authorization")).to(be_some().value(&Either::Left(json!("Bearer abc123"))));
+  }
+
+  #[test]
+  fn oauth2_returns_the_request_unchanged_if_the_token_can_not_be_fetched() {
+    let filters = Arc::new(RequestFilters::new(vec![
+      RequestFilter::OAuth2(OAuth2ClientCredentials::new("http://127.0.0.1:1/token", "id", "secret"))
+    ]));
+    let request = filters.call(&HttpRequest::default());
+    expect!(header(&request, "Authorization")).to(be_none());
+  }
+}
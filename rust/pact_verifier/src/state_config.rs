@@ -0,0 +1,693 @@
+//! Provider state executor that is driven by a configuration file (YAML or JSON). The file maps
+//! provider state names (either exactly or by a regular expression) to the actions to perform to
+//! set up and tear down the state. The actions can be HTTP requests, shell commands or static
+//! values to return for provider state generators.
+//!
+//! Any values in the actions can contain expressions (i.e. `${id}`) which will be replaced with
+//! the parameters of the provider state, the name of the state (`${state}`), any named groups
+//! from the regular expression, and any values returned by previous actions. Values used in
+//! commands are quoted for the shell, so they are always passed as single arguments. Expressions
+//! that are already in quotes in the command are escaped for those quotes instead. Values used in
+//! URLs are percent-encoded. The parameters are also available to commands as a JSON object in the
+//! `PACT_PROVIDER_STATE_PARAMS` environment variable.
+//!
+//! ```yaml
+//! states:
+//!   - name: a user exists
+//!     setup:
+//!       - values:
+//!           id: 100
+//!       - http:
+//!           url: http://localhost:8080/_test/users
+//!           body:
+//!             id: ${id}
+//!             name: ${name}
+//!     teardown:
+//!       - command: ./scripts/delete-user.sh ${id}
+//!   - regex: 'an order (?P<order>\d+) exists'
+//!     setup:
+//!       - command: ./scripts/create-order.sh ${order}
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::fs;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use maplit::hashmap;
+use pact_models::bodies::OptionalBody;
+use pact_models::content_types::JSON;
+use pact_models::expression_parser::{contains_expressions, MapValueResolver, parse_expression};
+use pact_models::json_utils::json_to_string;
+use pact_models::provider_states::ProviderState;
+use pact_models::v4::http_parts::HttpRequest;
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use tracing::{debug, warn};
+
+use crate::callback_executors::{ProviderStateError, ProviderStateExecutor};
+use crate::provider_client::make_state_change_request;
+use crate::provider_process::shell_command;
+
+/// Provider state configuration loaded from a file
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+pub struct StateConfig {
+  /// Provider states that are configured
+  #[serde(default)]
+  pub states: Vec<StateConfigEntry>
+}
+
+/// Configuration for a provider state
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+pub struct StateConfigEntry {
+  /// Name of the provider state to match exactly
+  pub name: Option<String>,
+  /// Regular expression to match the name of the provider state with
+  pub regex: Option<String>,
+  /// Actions to perform to set up the provider state
+  #[serde(default)]
+  pub setup: Vec<StateAction>,
+  /// Actions to perform to tear down the provider state
+  #[serde(default)]
+  pub teardown: Vec<StateAction>
+}
+
+/// Action to perform for a provider state
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StateAction {
+  /// Make a HTTP request. If the response has a JSON object body, the values are returned.
+  Http(HttpStateAction),
+  /// Run a shell command. Any values used in expressions are quoted for the shell. If the command
+  /// writes a JSON object to standard output, the values are returned.
+  Command(String),
+  /// Return the static values
+  Values(Map<String, Value>)
+}
+
+/// HTTP request to make for a provider state
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+pub struct HttpStateAction {
+  /// HTTP method (defaults to POST)
+  pub method: Option<String>,
+  /// URL to make the request to
+  pub url: String,
+  /// Headers to add to the request
+  #[serde(default)]
+  pub headers: HashMap<String, String>,
+  /// Body of the request. Strings are sent as is, anything else is sent as JSON.
+  pub body: Option<Value>
+}
+
+impl StateConfig {
+  /// Parse the configuration from a YAML or JSON string
+  pub fn parse(config: &str) -> anyhow::Result<StateConfig> {
+    // serde_yaml expects YAML tags for enum variants, so go via a JSON value to allow `http:` style maps
+    let value: Value = serde_yaml::from_str(config)
+      .map_err(|err| anyhow!("Failed to parse the provider state configuration: {}", err))?;
+    serde_json::from_value(value)
+      .map_err(|err| anyhow!("Failed to parse the provider state configuration: {}", err))
+  }
+
+  /// Load the configuration from a YAML or JSON file
+  pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<StateConfig> {
+    let path = path.as_ref();
+    let contents = fs::read_to_string(path)
+      .with_context(|| format!("Failed to read the provider state configuration file '{}'", path.display()))?;
+    if path.extension().map(|ext| ext.eq_ignore_ascii_case("json")).unwrap_or(false) {
+      serde_json::from_str(&contents)
+        .map_err(|err| anyhow!("Failed to parse the provider state configuration: {}", err))
+    } else {
+      StateConfig::parse(&contents)
+    }
+  }
+}
+
+#[derive(Clone)]
+struct CompiledEntry {
+  entry: StateConfigEntry,
+  regex: Option<Regex>
+}
+
+impl CompiledEntry {
+  /// If the entry matches the provider state, returns any values captured by the regex
+  fn matches(&self, state: &str) -> Option<HashMap<String, Value>> {
+    if let Some(regex) = &self.regex {
+      regex.captures(state).map(|captures| {
+        regex.capture_names()
+          .flatten()
+          .filter_map(|name| captures.name(name).map(|m| (name.to_string(), Value::String(m.as_str().to_string()))))
+          .collect()
+      })
+    } else if self.entry.name.as_deref() == Some(state) {
+      Some(hashmap!{})
+    } else {
+      None
+    }
+  }
+}
+
+/// Provider state executor that performs the actions from a provider state configuration file
+#[derive(Clone)]
+pub struct ConfigProviderStateExecutor {
+  entries: Vec<CompiledEntry>,
+  teardown: bool,
+  /// Timeout for running commands
+  pub command_timeout: Duration,
+  /// Number of times to retry the HTTP requests, zero means none
+  pub retries: u8
+}
+
+impl ConfigProviderStateExecutor {
+  /// Create an executor for the configuration. Returns an error if an entry does not have a name
+  /// or regex, or the regex is not valid.
+  pub fn new(config: StateConfig) -> anyhow::Result<Self> {
+    let entries = config.states.into_iter()
+      .map(|entry| {
+        let regex = match (&entry.name, &entry.regex) {
+          (_, Some(regex)) => Some(Regex::new(&format!("^(?:{})$", regex))
+            .map_err(|err| anyhow!("'{}' is not a valid provider state regex: {}", regex, err))?),
+          (Some(_), None) => None,
+          (None, None) => return Err(anyhow!("Provider state configuration entries must have a name or regex"))
+        };
+        Ok(CompiledEntry { entry, regex })
+      })
+      .collect::<anyhow::Result<Vec<_>>>()?;
+    let teardown = entries.iter().any(|entry| !entry.entry.teardown.is_empty());
+    if !teardown {
+      warn!("No teardown actions are configured, provider state teardown will not be performed");
+    }
+    Ok(ConfigProviderStateExecutor {
+      entries,
+      teardown,
+      command_timeout: Duration::from_secs(30),
+      retries: 3
+    })
+  }
+
+  /// Create an executor from a YAML or JSON configuration file
+  pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+    ConfigProviderStateExecutor::new(StateConfig::load(path)?)
+  }
+
+  async fn execute_action(
+    &self,
+    action: &StateAction,
+    context: &HashMap<String, Value>,
+    client: &reqwest::Client
+  ) -> anyhow::Result<HashMap<String, Value>> {
+    match action {
+      StateAction::Values(values) => values.iter()
+        .map(|(k, v)| Ok((k.clone(), apply_template(v, context)?)))
+        .collect(),
+      StateAction::Http(http) => {
+        let mut headers: HashMap<String, Vec<String>> = http.headers.iter()
+          .map(|(k, v)| Ok((k.clone(), vec![template_string(v, context)?])))
+          .collect::<anyhow::Result<_>>()?;
+        let body = match &http.body {
+          Some(Value::String(body)) => OptionalBody::Present(template_string(body, context)?.into(), None, None),
+          Some(body) => {
+            if !headers.keys().any(|k| k.eq_ignore_ascii_case("content-type")) {
+              headers.insert("Content-Type".to_string(), vec![JSON.to_string()]);
+            }
+            OptionalBody::Present(apply_template(body, context)?.to_string().into(), Some(JSON.clone()), None)
+          }
+          None => OptionalBody::Missing
+        };
+        let request = HttpRequest {
+          method: http.method.clone().unwrap_or_else(|| "POST".to_string()).to_uppercase(),
+          headers: if headers.is_empty() { None } else { Some(headers) },
+          body,
+          .. HttpRequest::default()
+        };
+        let url = substitute_values(&http.url, context, |_, value| urlencoding::encode(value).to_string())?;
+        debug!("Making provider state request {} {}", request.method, url);
+        make_state_change_request(client, &url, &request, self.retries).await
+      }
+      StateAction::Command(command) => {
+        let command = substitute_values(command, context,
+          |preceding, value| shell_quote(value, quote_in_effect(preceding)))?;
+        self.run_command(&command, context).await
+      }
+    }
+  }
+
+  async fn run_command(
+    &self,
+    command_line: &str,
+    context: &HashMap<String, Value>
+  ) -> anyhow::Result<HashMap<String, Value>> {
+    debug!("Running provider state command: {}", command_line);
+    let mut command = shell_command(command_line);
+    command
+      .env("PACT_PROVIDER_STATE", context.get("state").map(json_to_string).unwrap_or_default())
+      .env("PACT_PROVIDER_STATE_PARAMS", Value::Object(context.iter()
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect()).to_string())
+      .stdin(Stdio::null())
+      .stdout(Stdio::piped())
+      .stderr(Stdio::piped())
+      .kill_on_drop(true);
+    let child = command.spawn()
+      .map_err(|err| anyhow!("Failed to run the provider state command '{}': {}", command_line, err))?;
+    let output = tokio::time::timeout(self.command_timeout, child.wait_with_output()).await
+      .map_err(|_| anyhow!("The provider state command '{}' did not complete within {:?}", command_line, self.command_timeout))?
+      .map_err(|err| anyhow!("Failed to run the provider state command '{}': {}", command_line, err))?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+      return Err(anyhow!("The provider state command '{}' failed with {}: {}", command_line, output.status, stderr.trim()));
+    }
+    if !stderr.trim().is_empty() {
+      debug!("Provider state command stderr:\n{}", stderr);
+    }
+
+    match serde_json::from_slice::<Value>(&output.stdout) {
+      Ok(Value::Object(values)) => Ok(values.into_iter().collect()),
+      _ => Ok(hashmap!{})
+    }
+  }
+}
+
+fn template_string(value: &str, context: &HashMap<String, Value>) -> anyhow::Result<String> {
+  if contains_expressions(value) {
+    let resolver = MapValueResolver { context: context.iter().map(|(k, v)| (k.as_str(), v.clone())).collect() };
+    parse_expression(value, &resolver).map(|value| json_to_string(&value))
+  } else {
+    Ok(value.to_string())
+  }
+}
+
+/// Replaces any expressions in the template with the values from the context. The escape function
+/// is called with the template text before the expression and the value, and returns the text to
+/// use.
+fn substitute_values<F>(template: &str, context: &HashMap<String, Value>, escape: F) -> anyhow::Result<String>
+  where F: Fn(&str, &str) -> String {
+  let mut result = String::new();
+  let mut position = 0;
+  while let Some(index) = template[position..].find("${").map(|index| position + index) {
+    result.push_str(&template[position..index]);
+    let end = template[index..].find('}').map(|end| index + end)
+      .ok_or_else(|| anyhow!("Missing closing brace in expression string '{}'", template))?;
+    let key = &template[(index + 2)..end];
+    let value = context.get(key).ok_or_else(|| anyhow!("No value for '{}' found", key))?;
+    result.push_str(&escape(&template[..index], &json_to_string(value)));
+    position = end + 1;
+  }
+  result.push_str(&template[position..]);
+  Ok(result)
+}
+
+/// Returns the quote character that is open at the end of the command text, if any
+#[cfg(not(windows))]
+fn quote_in_effect(command: &str) -> Option<char> {
+  let mut quote = None;
+  let mut chars = command.chars();
+  while let Some(c) = chars.next() {
+    match (quote, c) {
+      (Some('\''), '\'') => quote = None,
+      (Some('\''), _) => {}
+      (_, '\\') => { chars.next(); }
+      (None, '\'' | '"') => quote = Some(c),
+      (Some('"'), '"') => quote = None,
+      _ => {}
+    }
+  }
+  quote
+}
+
+/// Returns the quote character that is open at the end of the command text, if any
+#[cfg(windows)]
+fn quote_in_effect(command: &str) -> Option<char> {
+  if command.chars().filter(|c| *c == '"').count() % 2 == 1 {
+    Some('"')
+  } else {
+    None
+  }
+}
+
+/// Quotes the value so the shell treats it as a single argument without expanding it. If the
+/// value is already in quotes, it is escaped for those quotes instead.
+#[cfg(not(windows))]
+fn shell_quote(value: &str, quote: Option<char>) -> String {
+  match quote {
+    Some('\'') => value.replace('\'', r"'\''"),
+    Some(_) => value.chars().fold(String::new(), |mut result, c| {
+      if "\\\"$`".contains(c) {
+        result.push('\\');
+      }
+      result.push(c);
+      result
+    }),
+    None if !value.is_empty() && value.chars().all(|c| c.is_ascii_alphanumeric() || "_-.,/:=@+%".contains(c)) => value.to_string(),
+    None => format!("'{}'", value.replace('\'', r"'\''"))
+  }
+}
+
+/// Quotes the value so cmd treats it as a single argument. If the value is already in quotes, it
+/// is escaped for those quotes instead. Note that cmd will still expand any `%` variables in
+/// quoted values.
+#[cfg(windows)]
+fn shell_quote(value: &str, quote: Option<char>) -> String {
+  match quote {
+    Some(_) => value.replace('"', "\"\""),
+    None if !value.is_empty() && value.chars().all(|c| c.is_ascii_alphanumeric() || "_-.,/:=@+".contains(c)) => value.to_string(),
+    None => format!("\"{}\"", value.replace('"', "\"\""))
+  }
+}
+
+fn apply_template(value: &Value, context: &HashMap<String, Value>) -> anyhow::Result<Value> {
+  match value {
+    Value::String(s) if contains_expressions(s) => {
+      let resolver = MapValueResolver { context: context.iter().map(|(k, v)| (k.as_str(), v.clone())).collect() };
+      parse_expression(s, &resolver)
+    }
+    Value::Array(values) => values.iter()
+      .map(|v| apply_template(v, context))
+      .collect::<anyhow::Result<Vec<_>>>()
+      .map(Value::Array),
+    Value::Object(map) => map.iter()
+      .map(|(k, v)| Ok((k.clone(), apply_template(v, context)?)))
+      .collect::<anyhow::Result<Map<_, _>>>()
+      .map(Value::Object),
+    _ => Ok(value.clone())
+  }
+}
+
+#[async_trait]
+impl ProviderStateExecutor for ConfigProviderStateExecutor {
+  async fn call(
+    self: Arc<Self>,
+    interaction_id: Option<String>,
+    provider_state: &ProviderState,
+    setup: bool,
+    client: Option<&reqwest::Client>
+  ) -> anyhow::Result<HashMap<String, Value>> {
+    if provider_state.name.is_empty() {
+      return Ok(hashmap!{});
+    }
+
+    let matched = self.entries.iter()
+      .find_map(|entry| entry.matches(&provider_state.name).map(|captures| (entry, captures)));
+    let (entry, captures) = match matched {
+      Some(matched) => matched,
+      None => {
+        return if setup {
+          Err(ProviderStateError {
+            description: format!("No provider state configuration found for '{}'", provider_state.name),
+            interaction_id
+          }.into())
+        } else {
+          Ok(hashmap!{})
+        };
+      }
+    };
+
+    let mut context: HashMap<String, Value> = captures;
+    context.insert("state".to_string(), Value::String(provider_state.name.clone()));
+    context.extend(provider_state.params.iter().map(|(k, v)| (k.clone(), v.clone())));
+
+    let default_client = reqwest::Client::default();
+    let client = client.unwrap_or(&default_client);
+    let actions = if setup { &entry.entry.setup } else { &entry.entry.teardown };
+    let mut result = hashmap!{};
+    for action in actions {
+      let values = self.execute_action(action, &context, client).await
+        .map_err(|err| ProviderStateError {
+          description: format!("{} '{}' failed: {}", if setup { "Setup of" } else { "Teardown of" },
+            provider_state.name, err),
+          interaction_id: interaction_id.clone()
+        })?;
+      context.extend(values.iter().map(|(k, v)| (k.clone(), v.clone())));
+      result.extend(values);
+    }
+    Ok(result)
+  }
+
+  fn teardown(&self) -> bool {
+    self.teardown
+  }
+}
+
+impl Debug for ConfigProviderStateExecutor {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("ConfigProviderStateExecutor")
+      .field("states", &self.entries.iter().map(|e| &e.entry).collect::<Vec<_>>())
+      .field("command_timeout", &self.command_timeout)
+      .field("retries", &self.retries)
+      .finish()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use pact_consumer::*;
+  use pact_consumer::prelude::*;
+  use serde_json::json;
+
+  use super::*;
+
+  const CONFIG: &str = r#"
+states:
+  - name: a user exists
+    setup:
+      - values:
+          id: 100
+          name: ${name}
+  - regex: 'an order (?P<order>\d+) exists'
+    setup:
+      - values:
+          order: ${order}
+          description: order ${order} for ${state}
+    teardown:
+      - values:
+          deleted: true
+"#;
+
+  fn executor() -> Arc<ConfigProviderStateExecutor> {
+    Arc::new(ConfigProviderStateExecutor::new(StateConfig::parse(CONFIG).unwrap()).unwrap())
+  }
+
+  #[test]
+  fn parses_the_configuration() {
+    let config = StateConfig::parse(r#"
+states:
+  - name: state one
+    setup:
+      - http:
+          url: http://localhost/state
+          body: { "id": "${id}" }
+      - command: echo one
+    teardown:
+      - command: echo two
+"#).unwrap();
+    expect!(config.states.len()).to(be_equal_to(1));
+    expect!(config.states[0].setup.clone()).to(be_equal_to(vec![
+      StateAction::Http(HttpStateAction {
+        url: "http://localhost/state".to_string(),
+        body: Some(json!({ "id": "${id}" })),
+        .. HttpStateAction::default()
+      }),
+      StateAction::Command("echo one".to_string())
+    ]));
+    expect!(config.states[0].teardown.clone()).to(be_equal_to(vec![StateAction::Command("echo two".to_string())]));
+  }
+
+  #[test]
+  fn entries_require_a_name_or_valid_regex() {
+    expect!(ConfigProviderStateExecutor::new(StateConfig::parse("states: [ { setup: [] } ]").unwrap())).to(be_err());
+    expect!(ConfigProviderStateExecutor::new(StateConfig::parse("states: [ { regex: '(' } ]").unwrap())).to(be_err());
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn returns_values_for_a_state_matched_by_name() {
+    let state = ProviderState {
+      name: "a user exists".to_string(),
+      params: hashmap!{ "name".to_string() => json!("Fred") }
+    };
+    let result = executor().call(None, &state, true, None).await.unwrap();
+    expect!(result).to(be_equal_to(hashmap!{
+      "id".to_string() => json!(100),
+      "name".to_string() => json!("Fred")
+    }));
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn returns_values_for_a_state_matched_by_regex() {
+    let state = ProviderState::default("an order 1234 exists");
+    let result = executor().call(None, &state, true, None).await.unwrap();
+    expect!(result).to(be_equal_to(hashmap!{
+      "order".to_string() => json!("1234"),
+      "description".to_string() => json!("order 1234 for an order 1234 exists")
+    }));
+
+    let result = executor().call(None, &state, false, None).await.unwrap();
+    expect!(result).to(be_equal_to(hashmap!{ "deleted".to_string() => json!(true) }));
+    expect!(executor().teardown()).to(be_true());
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn returns_an_error_for_unknown_states() {
+    let state = ProviderState::default("an unknown state");
+    let result = executor().call(Some("1234".to_string()), &state, true, None).await;
+    expect!(result.unwrap_err().to_string()).to(be_equal_to(
+      "Provider state failed: (interaction_id: 1234) No provider state configuration found for 'an unknown state'"));
+
+    let result = executor().call(None, &state, false, None).await;
+    expect!(result).to(be_ok());
+  }
+
+  #[cfg(unix)]
+  #[test_log::test(tokio::test)]
+  async fn runs_commands_with_the_state_parameters() {
+    let config = StateConfig::parse(r#"
+states:
+  - name: a user exists
+    setup:
+      - command: 'echo "{\"id\": ${id}, \"state\": \"$PACT_PROVIDER_STATE\"}"'
+      - command: test "${id}" = 100 || exit 1
+  - name: a failing state
+    setup:
+      - command: echo failed >&2; exit 2
+"#).unwrap();
+    let executor = Arc::new(ConfigProviderStateExecutor::new(config).unwrap());
+
+    let state = ProviderState {
+      name: "a user exists".to_string(),
+      params: hashmap!{ "id".to_string() => json!(100) }
+    };
+    let result = executor.clone().call(None, &state, true, None).await.unwrap();
+    expect!(result).to(be_equal_to(hashmap!{
+      "id".to_string() => json!(100),
+      "state".to_string() => json!("a user exists")
+    }));
+
+    let result = executor.call(None, &ProviderState::default("a failing state"), true, None).await;
+    expect!(result.unwrap_err().to_string()).to(be_equal_to(
+      "Provider state failed: Setup of 'a failing state' failed: The provider state command 'echo failed >&2; exit 2' failed with exit status: 2: failed"));
+  }
+
+  #[cfg(unix)]
+  #[test_log::test(tokio::test)]
+  async fn does_not_let_the_shell_interpret_the_state_parameters() {
+    let marker = std::env::temp_dir().join(format!("pact-state-config-{}", std::process::id()));
+    let config = StateConfig::parse(r#"
+states:
+  - name: a user exists
+    setup:
+      - command: printf '{"name":"%s"}' ${name}
+"#).unwrap();
+    let executor = Arc::new(ConfigProviderStateExecutor::new(config).unwrap());
+
+    let name = format!("Fred'; touch {} #$(touch {}) `touch {}`", marker.display(), marker.display(), marker.display());
+    let state = ProviderState {
+      name: "a user exists".to_string(),
+      params: hashmap!{ "name".to_string() => json!(name) }
+    };
+    let result = executor.call(None, &state, true, None).await.unwrap();
+    expect!(result).to(be_equal_to(hashmap!{ "name".to_string() => json!(name) }));
+    expect!(marker.exists()).to(be_false());
+  }
+
+  #[cfg(not(windows))]
+  #[test]
+  fn shell_quote_test() {
+    expect!(shell_quote("100", None)).to(be_equal_to("100"));
+    expect!(shell_quote("", None)).to(be_equal_to("''"));
+    expect!(shell_quote("a b", None)).to(be_equal_to("'a b'"));
+    expect!(shell_quote("it's; $(rm -rf /)", None)).to(be_equal_to(r"'it'\''s; $(rm -rf /)'"));
+    expect!(shell_quote("it's; $(rm -rf /)", Some('\''))).to(be_equal_to(r"it'\''s; $(rm -rf /)"));
+    expect!(shell_quote(r#"a "b" $(c) `d` \e"#, Some('"'))).to(be_equal_to(r#"a \"b\" \$(c) \`d\` \\e"#));
+  }
+
+  #[cfg(not(windows))]
+  #[test]
+  fn quote_in_effect_test() {
+    expect!(quote_in_effect("echo ")).to(be_none());
+    expect!(quote_in_effect("echo '")).to(be_some().value('\''));
+    expect!(quote_in_effect("echo \"")).to(be_some().value('"'));
+    expect!(quote_in_effect("echo \"it's ")).to(be_some().value('"'));
+    expect!(quote_in_effect("echo 'a\\' ")).to(be_none());
+    expect!(quote_in_effect("echo \\' \"a\\\" ")).to(be_some().value('"'));
+    expect!(quote_in_effect("echo 'a' \"b\" ")).to(be_none());
+  }
+
+  #[cfg(unix)]
+  #[test_log::test(tokio::test)]
+  async fn escapes_values_for_placeholders_that_are_already_quoted() {
+    let config = StateConfig::parse(r#"
+states:
+  - name: a user exists
+    setup:
+      - command: printf '{"name":"%s","quoted":"%s"}' "${name}" '${name}'
+"#).unwrap();
+    let executor = Arc::new(ConfigProviderStateExecutor::new(config).unwrap());
+
+    let state = ProviderState {
+      name: "a user exists".to_string(),
+      params: hashmap!{ "name".to_string() => json!("Fred Blogs") }
+    };
+    let result = executor.call(None, &state, true, None).await.unwrap();
+    expect!(result).to(be_equal_to(hashmap!{
+      "name".to_string() => json!("Fred Blogs"),
+      "quoted".to_string() => json!("Fred Blogs")
+    }));
+  }
+
+  #[test]
+  fn substitute_values_test() {
+    let context = hashmap!{
+      "id".to_string() => json!(100),
+      "name".to_string() => json!("a/b c")
+    };
+    let encode = |_: &str, value: &str| urlencoding::encode(value).to_string();
+    expect!(substitute_values("http://localhost/users/${id}?name=${name}", &context, encode).unwrap())
+      .to(be_equal_to("http://localhost/users/100?name=a%2Fb%20c"));
+    expect!(substitute_values("{\"a\": 1} ${id}", &context, encode).unwrap()).to(be_equal_to("{\"a\": 1} 100"));
+    expect!(substitute_values("${other}", &context, encode)).to(be_err());
+    expect!(substitute_values("${id", &context, encode)).to(be_err());
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn makes_http_requests_with_templated_bodies() {
+    let server = PactBuilderAsync::new("state_config", "provider")
+      .interaction("state change request", "", |mut i| async move {
+        i.request.method("PUT").path("/_test/users")
+          .header("X-State", "a user exists")
+          .json_body(json_pattern!({ "id": 100, "name": "user-100" }));
+        i.response.ok().content_type("application/json").json_body(json_pattern!({ "token": "abc" }));
+        i
+      })
+      .await
+      .start_mock_server(None);
+
+    let config = StateConfig::parse(&format!(r#"
+states:
+  - name: a user exists
+    setup:
+      - http:
+          method: put
+          url: {}_test/users
+          headers:
+            X-State: ${{state}}
+          body:
+            id: ${{id}}
+            name: user-${{id}}
+"#, server.url())).unwrap();
+    let executor = Arc::new(ConfigProviderStateExecutor::new(config).unwrap());
+    let state = ProviderState {
+      name: "a user exists".to_string(),
+      params: hashmap!{ "id".to_string() => json!(100) }
+    };
+
+    let result = executor.call(None, &state, true, None).await.unwrap();
+    expect!(result).to(be_equal_to(hashmap!{ "token".to_string() => json!("abc") }));
+  }
+}
@@ -10,6 +10,7 @@ use async_trait::async_trait;
 use expectest::prelude::*;
 use maplit::*;
 use pact_models::Consumer;
+use pact_models::lazy_pact::LazyPact;
 use pact_models::pact::Pact;
 use pact_models::provider_states::*;
 use pact_models::sync_interaction::RequestResponseInteraction;
@@ -20,7 +21,7 @@ use serde_json::{json, Value};
 use pact_consumer::*;
 use pact_consumer::prelude::*;
 
-use crate::{NullRequestFilterExecutor, PactSource, ProviderInfo, ProviderStateExecutor, ProviderTransport, publish_result, PublishOptions, VerificationOptions};
+use crate::{NullRequestFilterExecutor, PactSource, ProviderClients, ProviderInfo, ProviderStateExecutor, ProviderTransport, publish_result, PublishOptions, TlsConfig, VerificationOptions};
 use crate::callback_executors::HttpRequestProviderStateExecutor;
 use crate::pact_broker::Link;
 use crate::verification_result::VerificationInteractionResult;
@@ -273,7 +274,7 @@ fn publish_result_does_nothing_if_not_from_broker() {
         provider_tags: vec![],
         .. super::PublishOptions::default()
       };
-      super::publish_result(&vec![], &PactSource::File("/tmp/test".into()), &options, None).await;
+      super::publish_result(&[], &PactSource::File("/tmp/test".into()), &options, None, &TlsConfig::default()).await;
     })
   });
   expect!(server_response).to(be_err());
@@ -325,7 +326,7 @@ async fn publish_successful_result_to_broker() {
       result: Ok(()),
       pending: false,
       duration: Default::default(),
-    }], &source, &options, None
+    }], &source, &options, None, &TlsConfig::default()
   ).await;
 
   // Same publish but with dynamic configuration as pact source:
@@ -348,7 +349,7 @@ async fn publish_successful_result_to_broker() {
       result: Ok(()),
       pending: false,
       duration: Default::default(),
-    }], &source, &options, None
+    }], &source, &options, None, &TlsConfig::default()
   ).await;
 }
 
@@ -529,7 +530,7 @@ async fn test_fetch_pact_from_url_with_links() {
 
   let url = server.url().join(path).unwrap();
   let provider = ProviderInfo::default();
-  let result = super::fetch_pact(PactSource::URL(url.to_string(), None), &provider).await;
+  let result = super::fetch_pact(PactSource::URL(url.to_string(), None), &provider, &TlsConfig::default()).await;
 
   let first_result = result.get(0).unwrap().as_ref();
   let (_, _, source, _) = &first_result.clone().unwrap();
@@ -554,7 +555,8 @@ fn transport_base_url_test() {
     transport: "https".to_string(),
     port: None,
     path: None,
-    scheme: Some("https".to_string())
+    scheme: Some("https".to_string()),
+    .. ProviderTransport::default()
   };
   expect!(transport.base_url("HOST")).to(be_equal_to("https://HOST"));
 
@@ -562,7 +564,8 @@ fn transport_base_url_test() {
     transport: "https".to_string(),
     port: None,
     path: Some("/a/b/c".to_string()),
-    scheme: Some("https".to_string())
+    scheme: Some("https".to_string()),
+    .. ProviderTransport::default()
   };
   expect!(transport.base_url("HOST")).to(be_equal_to("https://HOST/a/b/c"));
 
@@ -570,7 +573,8 @@ fn transport_base_url_test() {
     transport: "https".to_string(),
     port: Some(5678),
     path: None,
-    scheme: Some("https".to_string())
+    scheme: Some("https".to_string()),
+    .. ProviderTransport::default()
   };
   expect!(transport.base_url("HOST")).to(be_equal_to("https://HOST:5678"));
 
@@ -578,11 +582,25 @@ fn transport_base_url_test() {
     transport: "https".to_string(),
     port: Some(7765),
     path: Some("/a/b/c".to_string()),
-    scheme: None
+    scheme: None,
+    .. ProviderTransport::default()
   };
   expect!(transport.base_url("HOST")).to(be_equal_to("http://HOST:7765/a/b/c"));
 }
 
+#[test]
+fn transport_base_url_for_unix_socket_test() {
+  let transport = ProviderTransport {
+    port: None,
+    path: Some("/a/b/c".to_string()),
+    scheme: Some("unix".to_string()),
+    socket_path: Some("/tmp/provider.sock".to_string()),
+    .. ProviderTransport::default()
+  };
+  expect!(transport.is_unix_socket()).to(be_true());
+  expect!(transport.base_url("HOST")).to(be_equal_to("http://localhost/a/b/c"));
+}
+
 #[derive(Debug)]
 struct DummyProviderStateExecutor;
 
@@ -669,7 +687,8 @@ async fn when_no_pacts_is_error_is_false_should_not_generate_error() {
       transport: "HTTP".to_string(),
       port: None,
       path: None,
-      scheme: Some("http".to_string())
+      scheme: Some("http".to_string()),
+      .. ProviderTransport::default()
     } ],
     .. ProviderInfo::default()
   };
@@ -761,7 +780,8 @@ async fn when_no_pacts_is_error_is_false_should_not_generate_error_if_it_is_404_
       transport: "HTTP".to_string(),
       port: None,
       path: None,
-      scheme: Some("http".to_string())
+      scheme: Some("http".to_string()),
+      .. ProviderTransport::default()
     } ],
     .. ProviderInfo::default()
   };
@@ -1013,7 +1033,7 @@ async fn test_publish_results_from_url_source_with_provider_branch() {
 
   let url = server.url().join(path).unwrap();
   let provider = ProviderInfo::default();
-  let pact_result = super::fetch_pact(PactSource::URL(url.to_string(), None), &provider).await;
+  let pact_result = super::fetch_pact(PactSource::URL(url.to_string(), None), &provider, &TlsConfig::default()).await;
 
   let first_result = pact_result.get(0).unwrap().as_ref();
   let (_, _, source, _) = &first_result.clone().unwrap();
@@ -1025,7 +1045,7 @@ async fn test_publish_results_from_url_source_with_provider_branch() {
   };
   let verification_result = vec![];
 
-  publish_result(&verification_result, &source, &options, None).await;
+  publish_result(&verification_result, &source, &options, None, &TlsConfig::default()).await;
 }
 
 #[test_log::test(tokio::test)]
@@ -1036,9 +1056,70 @@ async fn fetch_pact_from_dir_filters_by_provider_name() {
   };
   let pacts_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
     .join("tests/pacts");
-  let result = super::fetch_pact(PactSource::Dir(pacts_path.to_string_lossy().to_string()), &provider).await;
+  let result = super::fetch_pact(PactSource::Dir(pacts_path.to_string_lossy().to_string()), &provider, &TlsConfig::default()).await;
   expect!(result.len()).to(be_equal_to(1));
   let first_result = result.first().unwrap().as_ref();
   let (pact, _, _, _) = first_result.unwrap();
   expect!(pact.provider().name).to(be_equal_to(provider.name));
 }
+
+#[test_log::test(tokio::test)]
+async fn verify_pact_returns_an_error_if_an_interaction_can_not_be_loaded() {
+  let pact = LazyPact::from_json("test", &json!({
+    "consumer": { "name": "test_consumer" },
+    "provider": { "name": "test_provider" },
+    "interactions": [
+      { "type": "Not a type", "description": "invalid interaction" }
+    ],
+    "metadata": { "pactSpecification": { "version": "4.0" } }
+  })).unwrap();
+  let provider = ProviderInfo {
+    name: "test_provider".to_string(),
+    .. ProviderInfo::default()
+  };
+  let options = VerificationOptions::<NullRequestFilterExecutor>::default();
+  let provider_states = Arc::new(DummyProviderStateExecutor{});
+
+  let result = super::verify_pact_internal(&provider, &FilterInfo::None, pact.boxed(), &options,
+    &provider_states, false, Duration::default()).await;
+
+  expect!(result.err().map(|err| err.to_string())).to(
+    be_some().value("Failed to load the interactions at index 0 from the Pact file, see the logs for the errors"));
+}
+
+#[test]
+fn provider_clients_only_creates_the_http2_client_if_a_transport_uses_it() {
+  let options = VerificationOptions::<NullRequestFilterExecutor>::default();
+  let provider = ProviderInfo::default();
+  let clients = ProviderClients::new(&provider, &options).unwrap();
+  expect!(clients.http2.is_none()).to(be_true());
+
+  let h2c = ProviderTransport::new("http", Some(8080), None, None).with_http2(true);
+  let provider = ProviderInfo {
+    transports: vec![ProviderTransport::default(), h2c.clone()],
+    .. ProviderInfo::default()
+  };
+  let clients = ProviderClients::new(&provider, &options).unwrap();
+  let http2 = clients.http2.clone().unwrap();
+  expect!(std::ptr::eq(clients.for_transport(Some(&h2c)), http2.as_ref())).to(be_true());
+  expect!(std::ptr::eq(clients.for_transport(None), clients.http.as_ref())).to(be_true());
+  expect!(std::ptr::eq(clients.for_transport(Some(&ProviderTransport::default())), clients.http.as_ref())).to(be_true());
+}
+
+#[test]
+fn provider_clients_only_creates_a_separate_state_change_client_if_it_has_its_own_tls_configuration() {
+  let provider = ProviderInfo::default();
+  let options = VerificationOptions::<NullRequestFilterExecutor>::default();
+  let clients = ProviderClients::new(&provider, &options).unwrap();
+  expect!(Arc::ptr_eq(&clients.state_change, &clients.http)).to(be_true());
+
+  let options = VerificationOptions::<NullRequestFilterExecutor> {
+    state_change_tls: Some(TlsConfig {
+      ca_certificates: vec![PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/tls/ca.pem")],
+      .. TlsConfig::default()
+    }),
+    .. VerificationOptions::default()
+  };
+  let clients = ProviderClients::new(&provider, &options).unwrap();
+  expect!(Arc::ptr_eq(&clients.state_change, &clients.http)).to(be_false());
+}
@@ -0,0 +1,290 @@
+//! TLS configuration for the HTTP clients used by the verifier. This allows custom CA certificates
+//! to be trusted and client certificates to be used for mutual TLS, separately for the requests to
+//! the provider, the provider state change requests and the requests to the Pact Broker.
+
+use std::fmt::{Debug, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context};
+use reqwest::{Certificate, ClientBuilder, Identity};
+use tracing::debug;
+
+/// Client certificate to present for mutual TLS
+#[derive(Clone, PartialEq)]
+pub enum ClientIdentity {
+  /// PEM encoded certificate chain and private key. If the private key is not set, it must be
+  /// included in the certificate file.
+  Pem {
+    /// PEM file with the client certificate chain
+    certificate: PathBuf,
+    /// PEM file with the private key
+    private_key: Option<PathBuf>
+  },
+  /// PKCS#12 archive with the client certificate chain and private key. Requires the `native-tls`
+  /// feature.
+  Pkcs12 {
+    /// PKCS#12 (.p12 or .pfx) file
+    archive: PathBuf,
+    /// Password for the archive
+    password: Option<String>
+  }
+}
+
+impl Debug for ClientIdentity {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ClientIdentity::Pem { certificate, private_key } => f.debug_struct("Pem")
+        .field("certificate", certificate)
+        .field("private_key", private_key)
+        .finish(),
+      ClientIdentity::Pkcs12 { archive, password } => f.debug_struct("Pkcs12")
+        .field("archive", archive)
+        .field("password", &password.as_ref().map(|_| "*****"))
+        .finish()
+    }
+  }
+}
+
+/// TLS configuration for a HTTP client
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TlsConfig {
+  /// PEM files with CA certificates to trust, in addition to the system root certificates. Each
+  /// file can contain multiple certificates.
+  pub ca_certificates: Vec<PathBuf>,
+  /// Client certificate to present for mutual TLS
+  pub client_identity: Option<ClientIdentity>
+}
+
+impl TlsConfig {
+  /// If there is no custom TLS configuration
+  pub fn is_empty(&self) -> bool {
+    self.ca_certificates.is_empty() && self.client_identity.is_none()
+  }
+
+  /// Checks that the CA certificates and client certificate can be loaded
+  pub fn validate(&self) -> anyhow::Result<()> {
+    self.configure_client(ClientBuilder::new()).map(|_| ())
+  }
+
+  /// Adds the CA certificates and client certificate to the HTTP client builder
+  pub fn configure_client(&self, builder: ClientBuilder) -> anyhow::Result<ClientBuilder> {
+    let mut builder = builder;
+
+    for path in &self.ca_certificates {
+      debug!("Adding CA certificates from {}", path.display());
+      let pem = read_file(path, "CA certificate")?;
+      let certificates = Certificate::from_pem_bundle(&pem)
+        .map_err(|err| anyhow!("Failed to load the CA certificates from '{}' - {}", path.display(), err))?;
+      if certificates.is_empty() {
+        return Err(anyhow!("No CA certificates were found in '{}'", path.display()));
+      }
+      for certificate in certificates {
+        builder = builder.add_root_certificate(certificate);
+      }
+    }
+
+    match &self.client_identity {
+      Some(ClientIdentity::Pem { certificate, private_key }) => {
+        debug!("Using client certificate from {}", certificate.display());
+        let mut pem = read_file(certificate, "client certificate")?;
+        if let Some(private_key) = private_key.as_ref().filter(|key| *key != certificate) {
+          pem.push(b'\n');
+          pem.extend(read_file(private_key, "client private key")?);
+        }
+        let identity = Identity::from_pem(&pem)
+          .map_err(|err| anyhow!("Failed to load the client certificate from '{}' - {}", certificate.display(), err))?;
+        #[cfg(feature = "native-tls")]
+        {
+          builder = builder.use_rustls_tls();
+        }
+        builder = builder.identity(identity);
+      }
+      Some(ClientIdentity::Pkcs12 { archive, password }) => {
+        #[cfg(feature = "native-tls")]
+        {
+          debug!("Using client certificate from {}", archive.display());
+          let der = read_file(archive, "client certificate")?;
+          let identity = Identity::from_pkcs12_der(&der, password.as_deref().unwrap_or_default())
+            .map_err(|err| anyhow!("Failed to load the client certificate from '{}' - {}", archive.display(), err))?;
+          builder = builder.use_native_tls().identity(identity);
+        }
+        #[cfg(not(feature = "native-tls"))]
+        {
+          let _ = password;
+          return Err(anyhow!("Can not use the PKCS#12 client certificate '{}' as the native-tls feature is not enabled. \
+            Convert it to PEM format or enable the feature", archive.display()));
+        }
+      }
+      None => {}
+    }
+
+    Ok(builder)
+  }
+}
+
+fn read_file(path: &Path, description: &str) -> anyhow::Result<Vec<u8>> {
+  fs::read(path).with_context(|| format!("Failed to read the {} file '{}'", description, path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+  use std::convert::Infallible;
+  use std::fs::File;
+  use std::io::BufReader;
+  use std::net::SocketAddr;
+  use std::sync::Arc;
+
+  use bytes::Bytes;
+  use expectest::prelude::*;
+  use http_body_util::Full;
+  use hyper::Response;
+  use hyper::service::service_fn;
+  use hyper_util::rt::TokioIo;
+  use tokio::net::TcpListener;
+  use tokio_rustls::rustls::{RootCertStore, ServerConfig};
+  use tokio_rustls::rustls::server::WebPkiClientVerifier;
+  use tokio_rustls::TlsAcceptor;
+
+  use super::*;
+
+  fn tls_file(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/tls").join(name)
+  }
+
+  // Starts a HTTPS server using the test certificates (see tests/tls/generate.sh), which requires
+  // a client certificate signed by the test CA
+  async fn start_tls_server() -> SocketAddr {
+    let certificates = rustls_pemfile::certs(&mut BufReader::new(File::open(tls_file("server.pem")).unwrap()))
+      .collect::<Result<Vec<_>, _>>()
+      .unwrap();
+    let key = rustls_pemfile::private_key(&mut BufReader::new(File::open(tls_file("server.key")).unwrap()))
+      .unwrap()
+      .unwrap();
+    let mut roots = RootCertStore::empty();
+    for certificate in rustls_pemfile::certs(&mut BufReader::new(File::open(tls_file("ca.pem")).unwrap())) {
+      roots.add(certificate.unwrap()).unwrap();
+    }
+    let config = ServerConfig::builder()
+      .with_client_cert_verifier(WebPkiClientVerifier::builder(Arc::new(roots)).build().unwrap())
+      .with_single_cert(certificates, key)
+      .unwrap();
+    let acceptor = TlsAcceptor::from(Arc::new(config));
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+      while let Ok((stream, _)) = listener.accept().await {
+        let acceptor = acceptor.clone();
+        tokio::spawn(async move {
+          if let Ok(stream) = acceptor.accept(stream).await {
+            let _ = hyper::server::conn::http1::Builder::new()
+              .serve_connection(TokioIo::new(stream), service_fn(|_| async {
+                Ok::<_, Infallible>(Response::new(Full::new(Bytes::from("secure"))))
+              }))
+              .await;
+          }
+        });
+      }
+    });
+    address
+  }
+
+  async fn get(tls: &TlsConfig, address: SocketAddr) -> anyhow::Result<String> {
+    let client = tls.configure_client(reqwest::Client::builder())?.build()?;
+    let response = client.get(format!("https://localhost:{}/", address.port())).send().await?;
+    Ok(response.text().await?)
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn uses_the_ca_certificates_and_client_certificate() {
+    let address = start_tls_server().await;
+    let tls = TlsConfig {
+      ca_certificates: vec![tls_file("ca.pem")],
+      client_identity: Some(ClientIdentity::Pem {
+        certificate: tls_file("client.pem"),
+        private_key: Some(tls_file("client.key"))
+      })
+    };
+
+    expect!(get(&tls, address).await).to(be_ok().value("secure"));
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn fails_without_the_client_certificate() {
+    let address = start_tls_server().await;
+    let tls = TlsConfig {
+      ca_certificates: vec![tls_file("ca.pem")],
+      client_identity: None
+    };
+
+    expect!(get(&tls, address).await).to(be_err());
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn fails_without_the_ca_certificate() {
+    let address = start_tls_server().await;
+    let tls = TlsConfig {
+      ca_certificates: vec![],
+      client_identity: Some(ClientIdentity::Pem {
+        certificate: tls_file("client.pem"),
+        private_key: Some(tls_file("client.key"))
+      })
+    };
+
+    expect!(get(&tls, address).await).to(be_err());
+  }
+
+  #[cfg(feature = "native-tls")]
+  #[test_log::test(tokio::test)]
+  async fn uses_a_pkcs12_client_certificate() {
+    let address = start_tls_server().await;
+    let tls = TlsConfig {
+      ca_certificates: vec![tls_file("ca.pem")],
+      client_identity: Some(ClientIdentity::Pkcs12 {
+        archive: tls_file("client.p12"),
+        password: Some("changeit".to_string())
+      })
+    };
+
+    expect!(get(&tls, address).await).to(be_ok().value("secure"));
+  }
+
+  #[cfg(not(feature = "native-tls"))]
+  #[test]
+  fn pkcs12_client_certificates_require_the_native_tls_feature() {
+    let tls = TlsConfig {
+      ca_certificates: vec![],
+      client_identity: Some(ClientIdentity::Pkcs12 {
+        archive: tls_file("client.p12"),
+        password: Some("changeit".to_string())
+      })
+    };
+
+    let result = tls.configure_client(reqwest::Client::builder());
+    expect!(result.unwrap_err().to_string().contains("native-tls feature is not enabled")).to(be_true());
+  }
+
+  #[test]
+  fn returns_an_error_if_the_files_can_not_be_loaded() {
+    let tls = TlsConfig {
+      ca_certificates: vec![tls_file("does-not-exist.pem")],
+      client_identity: None
+    };
+    let result = tls.configure_client(reqwest::Client::builder());
+    expect!(result.unwrap_err().to_string()).to(be_equal_to(
+      format!("Failed to read the CA certificate file '{}'", tls_file("does-not-exist.pem").display())));
+
+    let tls = TlsConfig {
+      ca_certificates: vec![tls_file("generate.sh")],
+      client_identity: None
+    };
+    expect!(tls.configure_client(reqwest::Client::builder())).to(be_err());
+  }
+
+  #[test]
+  fn debug_does_not_include_the_pkcs12_password() {
+    let identity = ClientIdentity::Pkcs12 { archive: tls_file("client.p12"), password: Some("changeit".to_string()) };
+    expect!(format!("{:?}", identity).contains("changeit")).to(be_false());
+  }
+}
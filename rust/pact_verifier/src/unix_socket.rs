@@ -0,0 +1,180 @@
+//! Client for making requests to a provider that is listening on a Unix domain socket. The
+//! requests are sent using HTTP/1.1, or HTTP/2 with prior knowledge if configured for the transport.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use bytes::Bytes;
+use http::Request;
+use http_body_util::Full;
+use pact_models::v4::http_parts::{HttpRequest, HttpResponse};
+use tracing::debug;
+
+use crate::ProviderTransport;
+use crate::provider_client::{create_http_request, http_response_to_pact_response};
+
+/// Sends the request to the provider via the Unix domain socket configured for the transport
+pub(crate) async fn send_request(
+  transport: &ProviderTransport,
+  request: &HttpRequest,
+  custom_headers: &HashMap<String, String>,
+  timeout: Duration,
+  event_count: Option<usize>
+) -> anyhow::Result<HttpResponse> {
+  let socket_path = transport.socket_path.as_deref()
+    .ok_or_else(|| anyhow!("The transport '{}' does not have a Unix domain socket path configured", transport.transport))?;
+  let native_request = build_request(transport, request, custom_headers)?;
+  debug!(%socket_path, http2 = transport.http2, "Sending request to provider via Unix domain socket");
+
+  tokio::time::timeout(timeout, send(socket_path, transport.http2, native_request, event_count)).await
+    .map_err(|_| anyhow!("Request to the provider via the Unix domain socket '{}' timed out after {} ms",
+      socket_path, timeout.as_millis()))?
+}
+
+#[cfg(unix)]
+async fn send(
+  socket_path: &str,
+  http2: bool,
+  request: Request<Full<Bytes>>,
+  event_count: Option<usize>
+) -> anyhow::Result<HttpResponse> {
+  use hyper_util::rt::{TokioExecutor, TokioIo};
+  use tokio::net::UnixStream;
+
+  let stream = UnixStream::connect(socket_path).await
+    .map_err(|err| anyhow!("Failed to connect to the Unix domain socket '{}': {}", socket_path, err))?;
+  let io = TokioIo::new(stream);
+
+  let response = if http2 {
+    let (mut sender, connection) = hyper::client::conn::http2::handshake(TokioExecutor::new(), io).await?;
+    tokio::spawn(async move {
+      if let Err(err) = connection.await {
+        debug!("HTTP/2 connection to the provider failed: {}", err);
+      }
+    });
+    sender.send_request(request).await?
+  } else {
+    let (mut sender, connection) = hyper::client::conn::http1::handshake(io).await?;
+    tokio::spawn(async move {
+      if let Err(err) = connection.await {
+        debug!("HTTP/1.1 connection to the provider failed: {}", err);
+      }
+    });
+    sender.send_request(request).await?
+  };
+
+  http_response_to_pact_response(response, event_count).await
+}
+
+#[cfg(not(unix))]
+async fn send(
+  socket_path: &str,
+  _http2: bool,
+  _request: Request<Full<Bytes>>,
+  _event_count: Option<usize>
+) -> anyhow::Result<HttpResponse> {
+  Err(anyhow!("Unix domain sockets are not supported on this platform (socket path '{}')", socket_path))
+}
+
+fn build_request(
+  transport: &ProviderTransport,
+  request: &HttpRequest,
+  custom_headers: &HashMap<String, String>
+) -> anyhow::Result<Request<Full<Bytes>>> {
+  // HTTP/2 requires the scheme and authority, HTTP/1.1 uses the path with a host header
+  create_http_request(&transport.path.clone().unwrap_or_default(), request, custom_headers, transport.http2)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+  use expectest::prelude::*;
+  use hyper::service::service_fn;
+  use hyper_util::rt::{TokioExecutor, TokioIo};
+  use maplit::hashmap;
+  use pact_models::bodies::OptionalBody;
+  use tokio::io::{AsyncReadExt, AsyncWriteExt};
+  use tokio::net::UnixListener;
+
+  use super::*;
+
+  fn socket_path(name: &str) -> String {
+    let path = std::env::temp_dir().join(format!("pact-verifier-{}-{}.sock", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path.to_string_lossy().to_string()
+  }
+
+  fn transport(socket_path: &str, http2: bool) -> ProviderTransport {
+    ProviderTransport {
+      transport: "http".to_string(),
+      port: None,
+      path: Some("/api".to_string()),
+      scheme: Some(crate::UNIX_SOCKET_SCHEME.to_string()),
+      socket_path: Some(socket_path.to_string()),
+      http2,
+      command: None
+    }
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn sends_http1_requests_via_unix_socket() {
+    let path = socket_path("http1");
+    let listener = UnixListener::bind(&path).unwrap();
+    let server = tokio::spawn(async move {
+      let (mut stream, _) = listener.accept().await.unwrap();
+      let mut buffer = vec![0_u8; 4096];
+      let n = stream.read(&mut buffer).await.unwrap();
+      stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 11\r\n\r\n{\"id\": 100}").await.unwrap();
+      String::from_utf8_lossy(&buffer[..n]).to_string()
+    });
+
+    let request = HttpRequest {
+      method: "POST".to_string(),
+      path: "/users".to_string(),
+      query: Some(hashmap!{ "a".to_string() => vec![Some("1 2".to_string())] }),
+      headers: Some(hashmap!{ "Content-Type".to_string() => vec!["application/json".to_string()] }),
+      body: OptionalBody::Present(Bytes::from("{}"), None, None),
+      .. HttpRequest::default()
+    };
+    let response = send_request(&transport(&path, false), &request, &hashmap!{},
+      Duration::from_secs(5), None).await.unwrap();
+    expect!(response.status).to(be_equal_to(200));
+    expect!(response.body.value_as_string()).to(be_some().value("{\"id\": 100}"));
+
+    let received = server.await.unwrap();
+    expect!(received.starts_with("POST /api/users?a=1+2 HTTP/1.1\r\n")).to(be_true());
+    expect!(received.to_lowercase().contains("host: localhost")).to(be_true());
+    let _ = std::fs::remove_file(&path);
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn sends_http2_requests_via_unix_socket() {
+    let path = socket_path("http2");
+    let listener = UnixListener::bind(&path).unwrap();
+    tokio::spawn(async move {
+      let (stream, _) = listener.accept().await.unwrap();
+      let service = service_fn(|request: Request<hyper::body::Incoming>| async move {
+        let version = format!("{:?} {}", request.version(), request.uri().path());
+        Ok::<_, hyper::Error>(hyper::Response::new(Full::new(Bytes::from(version))))
+      });
+      let _ = hyper::server::conn::http2::Builder::new(TokioExecutor::new())
+        .serve_connection(TokioIo::new(stream), service)
+        .await;
+    });
+
+    let response = send_request(&transport(&path, true), &HttpRequest::default(), &hashmap!{},
+      Duration::from_secs(5), None).await.unwrap();
+    expect!(response.status).to(be_equal_to(200));
+    let body = response.body.value().unwrap_or_default();
+    expect!(String::from_utf8_lossy(&body).to_string()).to(be_equal_to("HTTP/2.0 /api".to_string()));
+    let _ = std::fs::remove_file(&path);
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn reports_an_error_if_the_socket_does_not_exist() {
+    let path = socket_path("missing");
+    let result = send_request(&transport(&path, false), &HttpRequest::default(), &hashmap!{},
+      Duration::from_secs(5), None).await;
+    expect!(result.unwrap_err().to_string().starts_with("Failed to connect to the Unix domain socket")).to(be_true());
+  }
+}
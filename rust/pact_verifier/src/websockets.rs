@@ -0,0 +1,347 @@
+//! Built-in WebSocket transport for verifying V4 synchronous messages. The request message
+//! contents are sent to the provider as a single frame, and each frame received back from the
+//! provider is matched against the corresponding expected response.
+
+use std::collections::HashMap;
+use std::panic::RefUnwindSafe;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use bytes::Bytes;
+use futures::{SinkExt, StreamExt};
+use http::{HeaderName, HeaderValue};
+use pact_matching::generators::apply_generators_to_sync_message;
+use pact_matching::match_sync_message_response;
+use pact_models::bodies::OptionalBody;
+use pact_models::content_types::TEXT;
+use pact_models::generators::GeneratorTestMode;
+use pact_models::interaction::Interaction;
+use pact_models::json_utils::json_to_string;
+use pact_models::prelude::Pact;
+use pact_models::v4::message_parts::MessageContents;
+use pact_models::v4::sync_message::SynchronousMessage;
+use serde_json::Value;
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::Message as Frame;
+use tracing::{debug, trace};
+
+use crate::{MismatchResult, ProviderInfo, ProviderTransport, SYNC_MESSAGE_TRANSPORTS, VerificationOptions};
+use crate::callback_executors::RequestFilterExecutor;
+use crate::utils::as_safe_ref;
+
+/// If the transport name is one of the names of the built-in WebSocket transport (`websocket`, `ws`
+/// or `wss`, which are treated as aliases)
+pub(crate) fn is_websocket_transport_name(name: &str) -> bool {
+  matches!(name, "websocket" | "ws" | "wss")
+}
+
+/// If the transport is one handled by the built-in WebSocket transport
+pub(crate) fn is_websocket_transport(transport: &ProviderTransport) -> bool {
+  is_websocket_transport_name(transport.transport.as_str()) ||
+    matches!(transport.scheme.as_deref(), Some("ws") | Some("wss"))
+}
+
+/// Selects the provider WebSocket transport to use for the message, if there is one. If the
+/// message does not specify a transport, the WebSocket transport is only used if the provider does
+/// not have a message transport configured. The WebSocket transport names are aliases, so a message
+/// with a `ws` transport will use a provider `websocket` transport.
+#[allow(deprecated)]
+pub(crate) fn websocket_transport(
+  provider: &ProviderInfo,
+  message: &SynchronousMessage
+) -> Option<ProviderTransport> {
+  let transport = if let Some(transport) = &message.transport {
+    provider.transports.iter()
+      .find(|t| is_websocket_transport(t) &&
+        (&t.transport == transport || (is_websocket_transport_name(transport) && is_websocket_transport_name(&t.transport))))
+  } else if provider.transports.iter().any(|t| SYNC_MESSAGE_TRANSPORTS.contains(&t.transport.as_str())) {
+    None
+  } else {
+    provider.transports.iter().find(|t| is_websocket_transport(t))
+  };
+
+  transport.map(|t| {
+    let scheme = t.scheme.clone().unwrap_or_else(|| {
+      if t.transport == "wss" { "wss".to_string() } else { "ws".to_string() }
+    });
+    let path = t.path.clone().or_else(|| {
+      if provider.path.is_empty() || provider.path == "/" { None } else { Some(provider.path.clone()) }
+    });
+    ProviderTransport {
+      scheme: Some(scheme),
+      path,
+      .. t.clone()
+    }
+  })
+}
+
+/// Verifies a synchronous message by sending the request contents to the provider over a
+/// WebSocket connection and matching the frames received back against the expected responses
+pub(crate) async fn verify_sync_message_over_websocket<'a, F: RequestFilterExecutor>(
+  provider: &ProviderInfo,
+  pact: &Box<dyn Pact + Send + Sync + RefUnwindSafe + 'a>,
+  message: &SynchronousMessage,
+  transport: &ProviderTransport,
+  options: &VerificationOptions<F>,
+  context: &HashMap<&str, Value>
+) -> Result<Option<String>, MismatchResult> {
+  let (request, _) = apply_generators_to_sync_message(message, &GeneratorTestMode::Provider,
+    context, &pact.plugin_data(), &message.plugin_config).await;
+  let url = transport.base_url(&provider.host);
+
+  match exchange_frames(&url, &request, message, options).await {
+    Ok(actual_responses) => {
+      let actual = SynchronousMessage {
+        request,
+        response: actual_responses,
+        .. SynchronousMessage::default()
+      };
+      debug!("actual synchronous message = {:?}", actual);
+
+      let mismatches = match_sync_message_response(message, &message.response, &actual.response, pact).await;
+      if mismatches.is_empty() {
+        Ok(message.id().clone())
+      } else {
+        Err(MismatchResult::Mismatches {
+          mismatches,
+          expected: as_safe_ref(message),
+          actual: as_safe_ref(&actual),
+          interaction_id: message.id().clone()
+        })
+      }
+    }
+    Err(err) => Err(MismatchResult::Error(err.to_string(), message.id().clone()))
+  }
+}
+
+async fn exchange_frames<F: RequestFilterExecutor>(
+  url: &str,
+  request: &MessageContents,
+  message: &SynchronousMessage,
+  options: &VerificationOptions<F>
+) -> anyhow::Result<Vec<MessageContents>> {
+  debug!(%url, "Connecting to provider WebSocket");
+  let mut handshake = url.into_client_request()?;
+  for (name, value) in handshake_headers(request, &options.custom_headers) {
+    handshake.headers_mut().insert(HeaderName::from_str(&name)?, HeaderValue::from_str(&value)?);
+  }
+
+  let timeout = Duration::from_millis(options.request_timeout);
+  let (mut stream, response) = tokio::time::timeout(timeout, connect_async(handshake)).await
+    .map_err(|_| anyhow!("Timed out connecting to the provider WebSocket at {}", url))?
+    .map_err(|err| anyhow!("Failed to connect to the provider WebSocket at {}: {}", url, err))?;
+  let response_metadata: HashMap<String, Value> = response.headers().iter()
+    .filter_map(|(name, value)| value.to_str().ok()
+      .map(|value| (name.as_str().to_string(), Value::String(value.to_string()))))
+    .collect();
+
+  stream.send(request_frame(request)).await?;
+
+  let mut responses = vec![];
+  while responses.len() < message.response.len() {
+    let frame = tokio::time::timeout(timeout, stream.next()).await
+      .map_err(|_| anyhow!("Timed out waiting for response {} of {} from the provider WebSocket",
+        responses.len() + 1, message.response.len()))?;
+    match frame {
+      Some(Ok(Frame::Text(text))) => {
+        responses.push(response_contents(Bytes::from(text), &response_metadata, message, responses.len()));
+      }
+      Some(Ok(Frame::Binary(data))) => {
+        responses.push(response_contents(Bytes::from(data), &response_metadata, message, responses.len()));
+      }
+      Some(Ok(Frame::Close(_))) | None => break,
+      Some(Ok(frame)) => trace!("Ignoring WebSocket control frame {:?}", frame),
+      Some(Err(err)) => return Err(anyhow!("Failed to read from the provider WebSocket: {}", err))
+    }
+  }
+
+  // The responses have been received, so a failure to close the connection cleanly is not an error
+  if let Err(err) = stream.close(None).await {
+    debug!("Failed to close the provider WebSocket: {}", err);
+  }
+
+  Ok(responses)
+}
+
+/// Message metadata (other than the content type) is sent as headers with the connection handshake
+fn handshake_headers(
+  request: &MessageContents,
+  custom_headers: &HashMap<String, String>
+) -> Vec<(String, String)> {
+  let mut headers: Vec<(String, String)> = request.metadata.iter()
+    .filter(|(key, _)| !key.eq_ignore_ascii_case("contentType") && !key.eq_ignore_ascii_case("content-type"))
+    .map(|(key, value)| (key.clone(), json_to_string(value)))
+    .collect();
+  headers.extend(custom_headers.iter().map(|(key, value)| (key.clone(), value.clone())));
+  headers
+}
+
+/// Text contents are sent as a text frame, everything else as a binary frame
+fn request_frame(request: &MessageContents) -> Frame {
+  let data = request.contents.value().unwrap_or_default();
+  let is_text = request.message_content_type()
+    .map(|ct| ct.is_text())
+    .unwrap_or(true);
+  match (is_text, String::from_utf8(data.to_vec())) {
+    (true, Ok(text)) => Frame::Text(text),
+    _ => Frame::Binary(data.to_vec())
+  }
+}
+
+/// Frames do not have a content type, so the content type of the expected response is used
+fn response_contents(
+  data: Bytes,
+  handshake_metadata: &HashMap<String, Value>,
+  message: &SynchronousMessage,
+  index: usize
+) -> MessageContents {
+  let content_type = message.response.get(index)
+    .and_then(|response| response.message_content_type())
+    .or_else(|| message.request.message_content_type())
+    .unwrap_or_else(|| TEXT.clone());
+  let mut metadata = handshake_metadata.clone();
+  metadata.insert("contentType".to_string(), Value::String(content_type.to_string()));
+  MessageContents {
+    contents: OptionalBody::Present(data, Some(content_type), None),
+    metadata,
+    .. MessageContents::default()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use maplit::hashmap;
+  use pact_models::content_types::JSON;
+  use pact_models::v4::interaction::V4Interaction;
+  use pact_models::v4::pact::V4Pact;
+  use serde_json::json;
+  use tokio::net::TcpListener;
+
+  use crate::callback_executors::NullRequestFilterExecutor;
+
+  use super::*;
+
+  fn provider(transports: Vec<ProviderTransport>) -> ProviderInfo {
+    ProviderInfo {
+      name: "test".to_string(),
+      host: "127.0.0.1".to_string(),
+      transports,
+      .. ProviderInfo::default()
+    }
+  }
+
+  fn json_contents(json: Value) -> MessageContents {
+    MessageContents {
+      contents: OptionalBody::Present(Bytes::from(json.to_string()), Some(JSON.clone()), None),
+      metadata: hashmap! { "contentType".to_string() => json!("application/json") },
+      .. MessageContents::default()
+    }
+  }
+
+  #[test]
+  fn websocket_transport_selection() {
+    let ws = ProviderTransport {
+      transport: "websocket".to_string(),
+      port: Some(9000),
+      path: Some("/ws".to_string()),
+      scheme: None,
+      .. ProviderTransport::default()
+    };
+    let message = SynchronousMessage::default();
+
+    let selected = websocket_transport(&provider(vec![ws.clone()]), &message);
+    expect!(selected.map(|t| t.base_url("127.0.0.1"))).to(be_some().value("ws://127.0.0.1:9000/ws"));
+
+    let message_transport = ProviderTransport {
+      transport: "message".to_string(),
+      .. ProviderTransport::default()
+    };
+    expect!(websocket_transport(&provider(vec![ws.clone(), message_transport.clone()]), &message)).to(be_none());
+
+    let message = SynchronousMessage { transport: Some("websocket".to_string()), .. SynchronousMessage::default() };
+    expect!(websocket_transport(&provider(vec![message_transport.clone(), ws.clone()]), &message)).to(be_some());
+    expect!(websocket_transport(&provider(vec![message_transport.clone()]), &message)).to(be_none());
+
+    for alias in ["ws", "wss", "websocket"] {
+      let message = SynchronousMessage { transport: Some(alias.to_string()), .. SynchronousMessage::default() };
+      let selected = websocket_transport(&provider(vec![message_transport.clone(), ws.clone()]), &message);
+      expect!(selected.map(|t| t.base_url("127.0.0.1"))).to(be_some().value("ws://127.0.0.1:9000/ws"));
+    }
+
+    let wss = ProviderTransport { transport: "wss".to_string(), port: Some(443), path: None, scheme: None, .. ProviderTransport::default() };
+    let message = SynchronousMessage::default();
+    let selected = websocket_transport(&provider(vec![wss]), &message);
+    expect!(selected.map(|t| t.base_url("example.com"))).to(be_some().value("wss://example.com:443"));
+  }
+
+  #[test]
+  fn handshake_headers_from_metadata() {
+    let request = MessageContents {
+      metadata: hashmap! {
+        "contentType".to_string() => json!("application/json"),
+        "x-api-key".to_string() => json!("1234"),
+        "x-retries".to_string() => json!(3)
+      },
+      .. MessageContents::default()
+    };
+    let mut headers = handshake_headers(&request, &hashmap! { "Authorization".to_string() => "Bearer abc".to_string() });
+    headers.sort();
+    expect!(headers).to(be_equal_to(vec![
+      ("Authorization".to_string(), "Bearer abc".to_string()),
+      ("x-api-key".to_string(), "1234".to_string()),
+      ("x-retries".to_string(), "3".to_string())
+    ]));
+  }
+
+  async fn start_provider(replies: Vec<&'static str>) -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+      let (stream, _) = listener.accept().await.unwrap();
+      let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+      if let Some(Ok(Frame::Text(_))) = ws.next().await {
+        for reply in replies {
+          ws.send(Frame::Text(reply.to_string())).await.unwrap();
+        }
+      }
+      let _ = ws.close(None).await;
+    });
+    port
+  }
+
+  #[test_log::test(tokio::test)]
+  async fn verify_sync_message_over_websocket_test() {
+    let message = SynchronousMessage {
+      description: "get the user".to_string(),
+      request: json_contents(json!({ "id": 100 })),
+      response: vec![json_contents(json!({ "id": 100, "name": "Fred" }))],
+      transport: Some("websocket".to_string()),
+      .. SynchronousMessage::default()
+    };
+    let pact: Box<dyn Pact + Send + Sync + RefUnwindSafe> = V4Pact {
+      interactions: vec![message.boxed_v4()],
+      .. V4Pact::default()
+    }.boxed();
+    let options: VerificationOptions<NullRequestFilterExecutor> = VerificationOptions::default();
+
+    let port = start_provider(vec![r#"{"id":100,"name":"Fred"}"#]).await;
+    let transport = ProviderTransport { transport: "websocket".to_string(), port: Some(port), path: None, scheme: Some("ws".to_string()), .. ProviderTransport::default() };
+    let result = verify_sync_message_over_websocket(&provider(vec![transport.clone()]), &pact, &message,
+      &transport, &options, &hashmap!{}).await;
+    expect!(result).to(be_ok());
+
+    let port = start_provider(vec![r#"{"id":100,"name":"Mary"}"#]).await;
+    let transport = ProviderTransport { port: Some(port), .. transport };
+    let result = verify_sync_message_over_websocket(&provider(vec![transport.clone()]), &pact, &message,
+      &transport, &options, &hashmap!{}).await;
+    match result {
+      Err(MismatchResult::Mismatches { mismatches, .. }) => {
+        expect!(mismatches.len()).to(be_equal_to(1));
+      }
+      _ => panic!("Expected a mismatch result")
+    }
+  }
+}
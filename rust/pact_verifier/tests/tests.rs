@@ -22,9 +22,11 @@ use pact_consumer::*;
 use pact_consumer::prelude::*;
 use pact_verifier::{
   FilterInfo,
+  MessageProviderRegistry,
   NullRequestFilterExecutor,
   PactSource,
   ProviderInfo,
+  ProviderService,
   ProviderTransport,
   PublishOptions,
   VerificationOptions,
@@ -98,12 +100,7 @@ async fn verify_pact_with_match_values_matcher() {
     name: "MatchValuesProvider".to_string(),
     host: "127.0.0.1".to_string(),
     port: server.url().port(),
-    transports: vec![ ProviderTransport {
-      transport: "HTTP".to_string(),
-      port: server.url().port(),
-      path: None,
-      scheme: Some("http".to_string())
-    } ],
+    transports: vec![ ProviderTransport::new("HTTP", server.url().port(), None, Some("http".to_string())) ],
     .. ProviderInfo::default()
   };
 
@@ -153,12 +150,7 @@ async fn verify_pact_with_attributes_with_special_values() {
     name: "BookProvider".to_string(),
     host: "127.0.0.1".to_string(),
     port: server.url().port(),
-    transports: vec![ ProviderTransport {
-      transport: "HTTP".to_string(),
-      port: server.url().port(),
-      path: None,
-      scheme: Some("http".to_string())
-    } ],
+    transports: vec![ ProviderTransport::new("HTTP", server.url().port(), None, Some("http".to_string())) ],
     .. ProviderInfo::default()
   };
 
@@ -238,12 +230,7 @@ async fn verifying_a_pact_with_min_type_matcher_and_child_arrays() {
     name: "Issue396Service".to_string(),
     host: "127.0.0.1".to_string(),
     port: server.url().port(),
-    transports: vec![ ProviderTransport {
-      transport: "HTTP".to_string(),
-      port: server.url().port(),
-      path: None,
-      scheme: Some("http".to_string())
-    } ],
+    transports: vec![ ProviderTransport::new("HTTP", server.url().port(), None, Some("http".to_string())) ],
     .. ProviderInfo::default()
   };
 
@@ -482,12 +469,7 @@ async fn verify_multiple_pacts() {
     name: "Alice Service".to_string(),
     host: "127.0.0.1".to_string(),
     port: provider.url().port(),
-    transports: vec![ ProviderTransport {
-      transport: "HTTP".to_string(),
-      port: provider.url().port(),
-      path: None,
-      scheme: Some("http".to_string())
-    } ],
+    transports: vec![ ProviderTransport::new("HTTP", provider.url().port(), None, Some("http".to_string())) ],
     .. ProviderInfo::default()
   };
 
@@ -677,12 +659,7 @@ async fn verify_message_pact_with_two_interactions() {
     name: "message_test".to_string(),
     host: "127.0.0.1".to_string(),
     port: provider.url().port(),
-    transports: vec![ ProviderTransport {
-      transport: "HTTP".to_string(),
-      port: provider.url().port(),
-      path: None,
-      scheme: Some("http".to_string())
-    } ],
+    transports: vec![ ProviderTransport::new("HTTP", provider.url().port(), None, Some("http".to_string())) ],
     .. ProviderInfo::default()
   };
 
@@ -751,12 +728,7 @@ async fn verify_message_pact_with_matching_rules_on_metadata() {
     name: "message_test".to_string(),
     host: "127.0.0.1".to_string(),
     port: provider.url().port(),
-    transports: vec![ ProviderTransport {
-      transport: "HTTP".to_string(),
-      port: provider.url().port(),
-      path: None,
-      scheme: Some("http".to_string())
-    } ],
+    transports: vec![ ProviderTransport::new("HTTP", provider.url().port(), None, Some("http".to_string())) ],
     .. ProviderInfo::default()
   };
 
@@ -810,12 +782,7 @@ async fn verify_pact_with_matcher_on_last_modified_header() {
       name: "http-service".to_string(),
       host: "127.0.0.1".to_string(),
       port: server.url().port(),
-      transports: vec![ProviderTransport {
-        transport: "HTTP".to_string(),
-        port: server.url().port(),
-        path: None,
-        scheme: Some("http".to_string())
-      }],
+      transports: vec![ProviderTransport::new("HTTP", server.url().port(), None, Some("http".to_string()))],
       ..ProviderInfo::default()
     };
 
@@ -903,12 +870,7 @@ async fn broker_validation_errors_should_be_shown_to_the_user() {
     name: "Alice Service".to_string(),
     host: "127.0.0.1".to_string(),
     port: Some(8080),
-    transports: vec![ ProviderTransport {
-      transport: "HTTP".to_string(),
-      port: Some(8080),
-      path: None,
-      scheme: Some("http".to_string())
-    } ],
+    transports: vec![ ProviderTransport::new("HTTP", Some(8080), None, Some("http".to_string())) ],
     .. ProviderInfo::default()
   };
 
@@ -972,12 +934,7 @@ async fn verify_pact_with_body_but_no_content_type() {
     name: "no content-type".to_string(),
     host: "127.0.0.1".to_string(),
     port: server.url().port(),
-    transports: vec![ ProviderTransport {
-      transport: "HTTP".to_string(),
-      port: server.url().port(),
-      path: None,
-      scheme: Some("http".to_string())
-    } ],
+    transports: vec![ ProviderTransport::new("HTTP", server.url().port(), None, Some("http".to_string())) ],
     .. ProviderInfo::default()
   };
 
@@ -998,3 +955,248 @@ async fn verify_pact_with_body_but_no_content_type() {
 
   expect!(result.unwrap().results.get(0).unwrap().result.as_ref()).to(be_ok());
 }
+
+#[test_log::test(tokio::test)]
+async fn verify_pact_against_an_in_process_service() {
+  let pact = PactBuilder::new_v4("consumer", "in-process provider")
+    .interaction("a request for a user", "", |mut i| {
+      i.request.method("GET");
+      i.request.path("/users/100").query_param("fields", "name");
+      i.response.ok()
+        .content_type("application/json")
+        .json_body(json_pattern!({ "id": like!(100), "name": like!("Fred") }));
+      i
+    })
+    .build();
+
+  let service = tower::service_fn(|request: http::Request<http_body_util::Full<bytes::Bytes>>| async move {
+    let body = if request.uri() == "/api/users/100?fields=name" && request.headers().contains_key("x-test") {
+      json!({ "id": 200, "name": "Mary" })
+    } else {
+      json!({ "path": request.uri().to_string() })
+    };
+    http::Response::builder()
+      .header("Content-Type", "application/json")
+      .body(http_body_util::Full::new(bytes::Bytes::from(body.to_string())))
+  });
+
+  #[allow(deprecated)]
+  let provider = ProviderInfo {
+    name: "in-process provider".to_string(),
+    port: Some(1),
+    path: "/api".to_string(),
+    .. ProviderInfo::default()
+  };
+  let options: VerificationOptions<NullRequestFilterExecutor> = VerificationOptions {
+    custom_headers: hashmap!{ "X-Test".to_string() => "true".to_string() },
+    provider_service: Some(ProviderService::from_service(service)),
+    .. VerificationOptions::default()
+  };
+  let provider_states = Arc::new(DummyProviderStateExecutor{});
+
+  let result = verify_pact_internal(
+    &provider,
+    &FilterInfo::None,
+    pact,
+    &options,
+    &provider_states,
+    false,
+    Duration::default()
+  ).await;
+
+  expect!(result.unwrap().results.first().unwrap().result.as_ref()).to(be_ok());
+}
+
+#[test_log::test(tokio::test)]
+async fn verify_pact_against_an_in_process_function_reports_mismatches() {
+  let pact = PactBuilder::new_v4("consumer", "in-process provider")
+    .interaction("a request to create a user", "", |mut i| {
+      i.request.method("POST").path("/users").json_body(json_pattern!({ "name": "Fred" }));
+      i.response.created();
+      i
+    })
+    .build();
+
+  let provider_service = ProviderService::from_fn(|request: http::Request<http_body_util::Full<bytes::Bytes>>| async move {
+    let status = if request.method() == http::Method::POST { 400 } else { 405 };
+    http::Response::builder()
+      .status(status)
+      .body(http_body_util::Empty::<bytes::Bytes>::new())
+      .unwrap()
+  });
+  let provider = ProviderInfo::default();
+  let options: VerificationOptions<NullRequestFilterExecutor> = VerificationOptions {
+    provider_service: Some(provider_service),
+    .. VerificationOptions::default()
+  };
+  let provider_states = Arc::new(DummyProviderStateExecutor{});
+
+  let result = verify_pact_internal(
+    &provider,
+    &FilterInfo::None,
+    pact,
+    &options,
+    &provider_states,
+    false,
+    Duration::default()
+  ).await.unwrap();
+
+  let interaction_result = result.results.first().unwrap();
+  match &interaction_result.result {
+    Err(pact_verifier::MismatchResult::Mismatches { mismatches, .. }) => {
+      expect!(mismatches.iter().map(|m| m.description()).collect::<Vec<_>>())
+        .to(be_equal_to(vec!["expected 201 but was 400".to_string()]));
+    }
+    _ => panic!("Expected a status mismatch, got {:?}", interaction_result.result)
+  }
+}
+
+fn json_message_contents(body: Value) -> pact_models::v4::message_parts::MessageContents {
+  pact_models::v4::message_parts::MessageContents {
+    contents: pact_models::bodies::OptionalBody::Present(bytes::Bytes::from(body.to_string()),
+      Some("application/json".into()), None),
+    metadata: hashmap!{ "contentType".to_string() => json!("application/json") },
+    .. pact_models::v4::message_parts::MessageContents::default()
+  }
+}
+
+#[test_log::test(tokio::test)]
+async fn verify_message_pact_with_an_in_process_message_provider() {
+  let pact = PactBuilder::new_v4("consumer", "message producer")
+    .message_interaction("a user created event", |mut i| {
+      i.given("user 100 exists");
+      i.json_body(json_pattern!({ "id": like!(100), "name": like!("Fred") }));
+      i
+    })
+    .synchronous_message_interaction("a request for a user", |mut i| {
+      i.request_json_body(json_pattern!({ "id": like!(100) }));
+      i.response_json_body(json_pattern!({ "id": like!(100), "name": like!("Fred") }));
+      i
+    })
+    .build();
+
+  let mut registry = MessageProviderRegistry::new();
+  registry
+    .add_message("a user created event", |states| {
+      expect!(states.iter().map(|s| s.name.clone()).collect::<Vec<_>>())
+        .to(be_equal_to(vec!["user 100 exists".to_string()]));
+      Ok(json_message_contents(json!({ "id": 100, "name": "Mary" })))
+    })
+    .add_sync_message("a request for a user", |request, _| {
+      let request: Value = serde_json::from_slice(&request.contents.value().unwrap_or_default())?;
+      Ok(vec![json_message_contents(json!({ "id": request["id"], "name": "Mary" }))])
+    });
+
+  let provider = ProviderInfo::default();
+  let options: VerificationOptions<NullRequestFilterExecutor> = VerificationOptions {
+    message_provider: Some(Arc::new(registry)),
+    .. VerificationOptions::default()
+  };
+  let provider_states = Arc::new(DummyProviderStateExecutor{});
+
+  let result = verify_pact_internal(
+    &provider,
+    &FilterInfo::None,
+    pact,
+    &options,
+    &provider_states,
+    false,
+    Duration::default()
+  ).await.unwrap();
+
+  expect!(result.results.len()).to(be_equal_to(2));
+  for interaction_result in &result.results {
+    expect!(interaction_result.result.as_ref()).to(be_ok());
+  }
+}
+
+#[test_log::test(tokio::test)]
+async fn verify_sync_message_with_an_in_process_message_provider_applies_the_request_generators() {
+  use pact_models::generators::{Generator, GeneratorCategory};
+  use pact_models::pact::Pact;
+  use pact_models::path_exp::DocPath;
+  use pact_models::v4::interaction::V4Interaction;
+
+  let mut request = json_message_contents(json!({ "id": 100 }));
+  request.generators.add_generator_with_subcategory(&GeneratorCategory::BODY, DocPath::new_unwrap("$.id"),
+    Generator::RandomInt(1000, 2000));
+  let message = pact_models::v4::sync_message::SynchronousMessage {
+    description: "a request for a user".to_string(),
+    request,
+    response: vec![json_message_contents(json!({ "generated": true }))],
+    .. pact_models::v4::sync_message::SynchronousMessage::default()
+  };
+  let pact = pact_models::v4::pact::V4Pact {
+    interactions: vec![message.boxed_v4()],
+    .. pact_models::v4::pact::V4Pact::default()
+  };
+
+  let mut registry = MessageProviderRegistry::new();
+  registry.add_sync_message("a request for a user", |request, _| {
+    let request: Value = serde_json::from_slice(&request.contents.value().unwrap_or_default())?;
+    let id = request["id"].as_i64().unwrap_or_default();
+    Ok(vec![json_message_contents(json!({ "generated": (1000..=2000).contains(&id) }))])
+  });
+
+  let options: VerificationOptions<NullRequestFilterExecutor> = VerificationOptions {
+    message_provider: Some(Arc::new(registry)),
+    .. VerificationOptions::default()
+  };
+  let provider_states = Arc::new(DummyProviderStateExecutor{});
+
+  let result = verify_pact_internal(
+    &ProviderInfo::default(),
+    &FilterInfo::None,
+    pact.boxed(),
+    &options,
+    &provider_states,
+    false,
+    Duration::default()
+  ).await.unwrap();
+
+  expect!(result.results.len()).to(be_equal_to(1));
+  expect!(result.results[0].result.as_ref()).to(be_ok());
+}
+
+#[test_log::test(tokio::test)]
+async fn verify_message_pact_with_an_in_process_message_provider_reports_mismatches() {
+  let pact = PactBuilder::new_v4("consumer", "message producer")
+    .message_interaction("a user created event", |mut i| {
+      i.json_body(json_pattern!({ "id": like!(100) }));
+      i
+    })
+    .message_interaction("a user deleted event", |mut i| {
+      i.json_body(json_pattern!({ "id": like!(100) }));
+      i
+    })
+    .build();
+
+  let mut registry = MessageProviderRegistry::new();
+  registry.add_message("a user created event", |_| Ok(json_message_contents(json!({ "id": "100" }))));
+
+  let options: VerificationOptions<NullRequestFilterExecutor> = VerificationOptions {
+    message_provider: Some(Arc::new(registry)),
+    .. VerificationOptions::default()
+  };
+  let provider_states = Arc::new(DummyProviderStateExecutor{});
+
+  let result = verify_pact_internal(
+    &ProviderInfo::default(),
+    &FilterInfo::None,
+    pact,
+    &options,
+    &provider_states,
+    false,
+    Duration::default()
+  ).await.unwrap();
+
+  let created = result.results.iter().find(|r| r.interaction_description == "a user created event").unwrap();
+  expect!(matches!(created.result, Err(pact_verifier::MismatchResult::Mismatches { .. }))).to(be_true());
+  let deleted = result.results.iter().find(|r| r.interaction_description == "a user deleted event").unwrap();
+  match &deleted.result {
+    Err(pact_verifier::MismatchResult::Error(error, _)) => {
+      expect!(error.as_str()).to(be_equal_to("No message producer has been registered for 'a user deleted event'"));
+    }
+    _ => panic!("Expected an error, got {:?}", deleted.result)
+  }
+}
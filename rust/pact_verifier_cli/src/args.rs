@@ -1,5 +1,6 @@
 use clap::{Arg, ArgAction, ArgGroup, Command, command};
 use clap::builder::{NonEmptyStringValueParser, PossibleValuesParser};
+use pact_verifier::ProviderTransport;
 use regex::Regex;
 
 fn port_value(v: &str) -> Result<u16, String> {
@@ -20,14 +21,26 @@ fn validate_regex(val: &str) -> Result<String, String> {
   }
 }
 
-fn transport_value(v: &str) -> Result<(String, u16), String> {
-  let (transport, port) = v.split_once(':')
-    .ok_or_else(|| format!("'{}' is not a valid transport, it must be in the form TRANSPORT:PORT", v))?;
+fn transport_value(v: &str) -> Result<ProviderTransport, String> {
+  let (transport, address) = v.split_once(':')
+    .ok_or_else(|| format!("'{}' is not a valid transport, it must be in the form TRANSPORT:PORT or TRANSPORT:unix:SOCKET_PATH", v))?;
+  let (transport, http2) = match transport.strip_suffix("+h2c") {
+    Some(transport) => (transport, true),
+    None => (transport, false)
+  };
   if transport.is_empty() {
     return Err(format!("'{}' is not a valid transport, the transport part is empty", v));
   }
-  port.parse::<u16>().map(|port| (transport.to_string(), port))
-    .map_err(|e| format!("'{}' is not a valid port value: {}", port, e) )
+  if let Some(socket_path) = address.strip_prefix("unix:") {
+    if socket_path.is_empty() {
+      return Err(format!("'{}' is not a valid transport, the socket path is empty", v));
+    }
+    Ok(ProviderTransport::unix_socket(transport, socket_path).with_http2(http2))
+  } else {
+    address.parse::<u16>()
+      .map(|port| ProviderTransport::new(transport, Some(port), None, None).with_http2(http2))
+      .map_err(|e| format!("'{}' is not a valid port value: {}", address, e))
+  }
 }
 
 pub(crate) fn setup_app() -> Command {
@@ -180,8 +193,13 @@ pub(crate) fn setup_app() -> Command {
       .action(ArgAction::Append)
       .value_parser(NonEmptyStringValueParser::new())
       .value_delimiter(' ')
-      .help("Allows multiple protocol transports to be configured (http, https, grpc, etc.) with their associated port numbers separated by a colon. For example, use --transports http:8080 grpc:5555 to configure both.")
+      .help("Allows multiple protocol transports to be configured (http, https, grpc, websocket, etc.) with their associated port numbers separated by a colon. For example, use --transports http:8080 grpc:5555 to configure both. A Unix domain socket can be used in place of the port with TRANSPORT:unix:SOCKET_PATH, and appending +h2c to the transport name will send requests using HTTP/2 without TLS (i.e. http+h2c:8080).")
       .value_parser(transport_value))
+    .arg(Arg::new("message-command")
+      .long("message-command")
+      .action(ArgAction::Set)
+      .value_parser(NonEmptyStringValueParser::new())
+      .help("Command to run to get the messages for message interactions, instead of fetching them from the provider over HTTP. The message request is written to the standard input of the command as JSON, and the command must write the message to standard output as JSON in the form {\"contents\": ..., \"metadata\": {...}}."))
     .arg(Arg::new("provider-name")
       .short('n')
       .long("provider-name")
@@ -210,6 +228,217 @@ pub(crate) fn setup_app() -> Command {
       .action(ArgAction::SetTrue)
       .help("Disables validation of SSL certificates"))
 
+    .group(ArgGroup::new("request-filters").multiple(true))
+    .next_help_heading("Request signing options")
+    .arg(Arg::new("hmac-secret")
+      .long("hmac-secret")
+      .action(ArgAction::Set)
+      .value_parser(NonEmptyStringValueParser::new())
+      .help("Sign the requests to the provider with a HMAC-SHA256 signature using this secret key. The signature covers the method, path, query string, timestamp and body of the request."))
+    .arg(Arg::new("hmac-signature-header")
+      .long("hmac-signature-header")
+      .action(ArgAction::Set)
+      .requires("hmac-secret")
+      .value_parser(NonEmptyStringValueParser::new())
+      .help("Header to add the HMAC signature to (defaults to X-Signature)"))
+    .arg(Arg::new("hmac-timestamp-header")
+      .long("hmac-timestamp-header")
+      .action(ArgAction::Set)
+      .requires("hmac-secret")
+      .value_parser(NonEmptyStringValueParser::new())
+      .help("Header to add the timestamp used for the HMAC signature to (defaults to X-Timestamp)"))
+    .arg(Arg::new("sigv4-access-key-id")
+      .long("sigv4-access-key-id")
+      .action(ArgAction::Set)
+      .requires_all(["sigv4-secret-access-key", "sigv4-region", "sigv4-service"])
+      .value_parser(NonEmptyStringValueParser::new())
+      .help("Sign the requests to the provider with an AWS SigV4 style signature using this access key ID"))
+    .arg(Arg::new("sigv4-secret-access-key")
+      .long("sigv4-secret-access-key")
+      .action(ArgAction::Set)
+      .requires("sigv4-access-key-id")
+      .value_parser(NonEmptyStringValueParser::new())
+      .help("Secret access key to use for the SigV4 signatures"))
+    .arg(Arg::new("sigv4-session-token")
+      .long("sigv4-session-token")
+      .action(ArgAction::Set)
+      .requires("sigv4-access-key-id")
+      .value_parser(NonEmptyStringValueParser::new())
+      .help("Session token to add to the requests signed with SigV4 signatures"))
+    .arg(Arg::new("sigv4-region")
+      .long("sigv4-region")
+      .action(ArgAction::Set)
+      .requires("sigv4-access-key-id")
+      .value_parser(NonEmptyStringValueParser::new())
+      .help("Region to use for the SigV4 signatures (i.e. us-east-1)"))
+    .arg(Arg::new("sigv4-service")
+      .long("sigv4-service")
+      .action(ArgAction::Set)
+      .requires("sigv4-access-key-id")
+      .value_parser(NonEmptyStringValueParser::new())
+      .help("Service name to use for the SigV4 signatures (i.e. execute-api)"))
+    .arg(Arg::new("oauth2-token-url")
+      .long("oauth2-token-url")
+      .action(ArgAction::Set)
+      .requires_all(["oauth2-client-id", "oauth2-client-secret"])
+      .value_parser(NonEmptyStringValueParser::new())
+      .help("Add a bearer token to the requests to the provider, fetched from this token endpoint using the OAuth2 client credentials grant. The token is cached until it expires."))
+    .arg(Arg::new("oauth2-client-id")
+      .long("oauth2-client-id")
+      .action(ArgAction::Set)
+      .requires("oauth2-token-url")
+      .value_parser(NonEmptyStringValueParser::new())
+      .help("Client ID to use to fetch the OAuth2 token"))
+    .arg(Arg::new("oauth2-client-secret")
+      .long("oauth2-client-secret")
+      .action(ArgAction::Set)
+      .requires("oauth2-token-url")
+      .value_parser(NonEmptyStringValueParser::new())
+      .help("Client secret to use to fetch the OAuth2 token"))
+    .arg(Arg::new("oauth2-scope")
+      .long("oauth2-scope")
+      .action(ArgAction::Set)
+      .requires("oauth2-token-url")
+      .value_parser(NonEmptyStringValueParser::new())
+      .help("Scope to request for the OAuth2 token"))
+    .arg(Arg::new("oauth2-audience")
+      .long("oauth2-audience")
+      .action(ArgAction::Set)
+      .requires("oauth2-token-url")
+      .value_parser(NonEmptyStringValueParser::new())
+      .help("Audience to request for the OAuth2 token"))
+
+    .group(ArgGroup::new("tls").multiple(true))
+    .next_help_heading("TLS options")
+    .arg(Arg::new("provider-ca-cert")
+      .long("provider-ca-cert")
+      .env("PACT_PROVIDER_CA_CERT")
+      .action(ArgAction::Append)
+      .value_parser(NonEmptyStringValueParser::new())
+      .help("PEM file with CA certificates to trust when connecting to the provider, in addition to the system root certificates. Can be repeated."))
+    .arg(Arg::new("provider-client-cert")
+      .long("provider-client-cert")
+      .env("PACT_PROVIDER_CLIENT_CERT")
+      .action(ArgAction::Set)
+      .value_parser(NonEmptyStringValueParser::new())
+      .conflicts_with("provider-client-pkcs12")
+      .help("PEM file with the client certificate to use for mutual TLS with the provider. The file can also contain the private key."))
+    .arg(Arg::new("provider-client-key")
+      .long("provider-client-key")
+      .env("PACT_PROVIDER_CLIENT_KEY")
+      .action(ArgAction::Set)
+      .value_parser(NonEmptyStringValueParser::new())
+      .requires("provider-client-cert")
+      .help("PEM file with the private key for the provider client certificate"))
+    .arg(Arg::new("provider-client-pkcs12")
+      .long("provider-client-pkcs12")
+      .env("PACT_PROVIDER_CLIENT_PKCS12")
+      .action(ArgAction::Set)
+      .value_parser(NonEmptyStringValueParser::new())
+      .help("PKCS#12 archive with the client certificate and private key to use for mutual TLS with the provider. Requires the native-tls feature."))
+    .arg(Arg::new("provider-client-pkcs12-password")
+      .long("provider-client-pkcs12-password")
+      .env("PACT_PROVIDER_CLIENT_PKCS12_PASSWORD")
+      .action(ArgAction::Set)
+      .requires("provider-client-pkcs12")
+      .help("Password for the provider PKCS#12 archive"))
+    .arg(Arg::new("state-change-ca-cert")
+      .long("state-change-ca-cert")
+      .env("PACT_STATE_CHANGE_CA_CERT")
+      .action(ArgAction::Append)
+      .value_parser(NonEmptyStringValueParser::new())
+      .help("PEM file with CA certificates to trust when calling the provider state change URL, in addition to the system root certificates. Can be repeated."))
+    .arg(Arg::new("state-change-client-cert")
+      .long("state-change-client-cert")
+      .env("PACT_STATE_CHANGE_CLIENT_CERT")
+      .action(ArgAction::Set)
+      .value_parser(NonEmptyStringValueParser::new())
+      .conflicts_with("state-change-client-pkcs12")
+      .help("PEM file with the client certificate to use for mutual TLS with the provider state change URL. The file can also contain the private key."))
+    .arg(Arg::new("state-change-client-key")
+      .long("state-change-client-key")
+      .env("PACT_STATE_CHANGE_CLIENT_KEY")
+      .action(ArgAction::Set)
+      .value_parser(NonEmptyStringValueParser::new())
+      .requires("state-change-client-cert")
+      .help("PEM file with the private key for the provider state change URL client certificate"))
+    .arg(Arg::new("state-change-client-pkcs12")
+      .long("state-change-client-pkcs12")
+      .env("PACT_STATE_CHANGE_CLIENT_PKCS12")
+      .action(ArgAction::Set)
+      .value_parser(NonEmptyStringValueParser::new())
+      .help("PKCS#12 archive with the client certificate and private key to use for mutual TLS with the provider state change URL. Requires the native-tls feature."))
+    .arg(Arg::new("state-change-client-pkcs12-password")
+      .long("state-change-client-pkcs12-password")
+      .env("PACT_STATE_CHANGE_CLIENT_PKCS12_PASSWORD")
+      .action(ArgAction::Set)
+      .requires("state-change-client-pkcs12")
+      .help("Password for the provider state change URL PKCS#12 archive"))
+    .arg(Arg::new("broker-ca-cert")
+      .long("broker-ca-cert")
+      .env("PACT_BROKER_CA_CERT")
+      .action(ArgAction::Append)
+      .value_parser(NonEmptyStringValueParser::new())
+      .help("PEM file with CA certificates to trust when connecting to the Pact Broker or fetching pacts from URLs, in addition to the system root certificates. Can be repeated."))
+    .arg(Arg::new("broker-client-cert")
+      .long("broker-client-cert")
+      .env("PACT_BROKER_CLIENT_CERT")
+      .action(ArgAction::Set)
+      .value_parser(NonEmptyStringValueParser::new())
+      .conflicts_with("broker-client-pkcs12")
+      .help("PEM file with the client certificate to use for mutual TLS with the Pact Broker. The file can also contain the private key."))
+    .arg(Arg::new("broker-client-key")
+      .long("broker-client-key")
+      .env("PACT_BROKER_CLIENT_KEY")
+      .action(ArgAction::Set)
+      .value_parser(NonEmptyStringValueParser::new())
+      .requires("broker-client-cert")
+      .help("PEM file with the private key for the Pact Broker client certificate"))
+    .arg(Arg::new("broker-client-pkcs12")
+      .long("broker-client-pkcs12")
+      .env("PACT_BROKER_CLIENT_PKCS12")
+      .action(ArgAction::Set)
+      .value_parser(NonEmptyStringValueParser::new())
+      .help("PKCS#12 archive with the client certificate and private key to use for mutual TLS with the Pact Broker. Requires the native-tls feature."))
+    .arg(Arg::new("broker-client-pkcs12-password")
+      .long("broker-client-pkcs12-password")
+      .env("PACT_BROKER_CLIENT_PKCS12_PASSWORD")
+      .action(ArgAction::Set)
+      .requires("broker-client-pkcs12")
+      .help("Password for the Pact Broker PKCS#12 archive"))
+
+    .group(ArgGroup::new("provider-process").multiple(true))
+    .next_help_heading("Provider process options")
+    .arg(Arg::new("provider-command")
+      .long("provider-command")
+      .action(ArgAction::Set)
+      .value_parser(NonEmptyStringValueParser::new())
+      .help("Command to start the provider with before running the verification. The provider process will be shut down once the verification is complete."))
+    .arg(Arg::new("provider-env")
+      .long("provider-env")
+      .action(ArgAction::Append)
+      .requires("provider-command")
+      .value_parser(NonEmptyStringValueParser::new())
+      .help("Environment variable to set for the provider process. Values must be in the form KEY=VALUE. Can be repeated."))
+    .arg(Arg::new("provider-cwd")
+      .long("provider-cwd")
+      .action(ArgAction::Set)
+      .requires("provider-command")
+      .value_parser(NonEmptyStringValueParser::new())
+      .help("Working directory to start the provider process in (defaults to the current directory)"))
+    .arg(Arg::new("provider-health-url")
+      .long("provider-health-url")
+      .action(ArgAction::Set)
+      .requires("provider-command")
+      .value_parser(NonEmptyStringValueParser::new())
+      .help("URL to poll until it returns a successful response to determine that the provider is ready. If not set, the verifier will wait for the provider port to accept connections."))
+    .arg(Arg::new("provider-startup-timeout")
+      .long("provider-startup-timeout")
+      .action(ArgAction::Set)
+      .requires("provider-command")
+      .value_parser(integer_value)
+      .help("Time in seconds to wait for the provider process to be ready (defaults to 30)"))
+
     .group(ArgGroup::new("states").multiple(true))
     .next_help_heading("Provider state options")
     .arg(Arg::new("state-change-url")
@@ -226,6 +455,12 @@ pub(crate) fn setup_app() -> Command {
       .long("state-change-teardown")
       .action(ArgAction::SetTrue)
       .help("State change teardown requests are to be made after each interaction"))
+    .arg(Arg::new("state-config")
+      .long("state-config")
+      .action(ArgAction::Set)
+      .value_parser(NonEmptyStringValueParser::new())
+      .conflicts_with_all(["state-change-url", "state-change-as-query", "state-change-teardown"])
+      .help("YAML or JSON file that configures the actions to perform to set up and tear down the provider states, instead of using a state change URL"))
 
     .group(ArgGroup::new("filtering").multiple(true))
     .next_help_heading("Filtering interactions")
@@ -255,6 +490,16 @@ pub(crate) fn setup_app() -> Command {
       .action(ArgAction::Append)
       .value_parser(NonEmptyStringValueParser::new())
       .help("Consumer name to filter the pacts to be verified (can be repeated)"))
+    .arg(Arg::new("dry-run")
+      .long("dry-run")
+      .action(ArgAction::SetTrue)
+      .help("List the pacts and interactions that would be verified after the filters have been applied, without calling the provider or publishing any results"))
+    .arg(Arg::new("dry-run-format")
+      .long("dry-run-format")
+      .action(ArgAction::Set)
+      .requires("dry-run")
+      .value_parser(PossibleValuesParser::new(["table", "json"]))
+      .help("Format to output the dry run in (defaults to table)"))
 
     .group(ArgGroup::new("publish-options").multiple(true))
     .next_help_heading("Publishing options")
@@ -346,6 +591,30 @@ mod test {
     expect!(transport_value("x:")).to(be_err());
     expect!(transport_value("x:x")).to(be_err());
     expect!(transport_value("x:1234x")).to(be_err());
+    expect!(transport_value("http:unix:")).to(be_err());
+    expect!(transport_value("+h2c:1234")).to(be_err());
+  }
+
+  #[test]
+  fn transport_value_supports_unix_sockets_and_http2() {
+    let transport = transport_value("http:1234").unwrap();
+    expect!(transport.port).to(be_some().value(1234));
+    expect!(transport.http2).to(be_false());
+
+    let transport = transport_value("http:unix:/tmp/provider.sock").unwrap();
+    expect!(transport.transport).to(be_equal_to("http"));
+    expect!(transport.port).to(be_none());
+    expect!(transport.scheme).to(be_some().value("unix"));
+    expect!(transport.socket_path).to(be_some().value("/tmp/provider.sock"));
+
+    let transport = transport_value("http+h2c:8080").unwrap();
+    expect!(transport.transport).to(be_equal_to("http"));
+    expect!(transport.port).to(be_some().value(8080));
+    expect!(transport.http2).to(be_true());
+
+    let transport = transport_value("http+h2c:unix:/tmp/provider.sock").unwrap();
+    expect!(transport.socket_path).to(be_some().value("/tmp/provider.sock"));
+    expect!(transport.http2).to(be_true());
   }
 
   #[test]
@@ -43,7 +43,9 @@
 //!       --transport <transport>
 //!           Provider protocol transport to use (http, https, grpc, etc.) [default: http]
 //!       --transports <transports>
-//!           Allows multiple protocol transports to be configured (http, https, grpc, etc.) with their associated port numbers separated by a colon. For example, use --transports http:8080 grpc:5555 to configure both.
+//!           Allows multiple protocol transports to be configured (http, https, grpc, websocket, etc.) with their associated port numbers separated by a colon. For example, use --transports http:8080 grpc:5555 to configure both. A Unix domain socket can be used in place of the port with TRANSPORT:unix:SOCKET_PATH, and appending +h2c to the transport name will send requests using HTTP/2 without TLS (i.e. http+h2c:8080).
+//!       --message-command <message-command>
+//!           Command to run to get the messages for message interactions, instead of fetching them from the provider over HTTP. The message request is written to the standard input of the command as JSON, and the command must write the message to standard output as JSON in the form {"contents": ..., "metadata": {...}}.
 //!   -n, --provider-name <provider-name>
 //!           Provider name (defaults to provider)
 //!       --base-path <base-path>
@@ -55,6 +57,78 @@
 //!       --disable-ssl-verification
 //!           Disables validation of SSL certificates
 //!
+//! Request signing options:
+//!       --hmac-secret <hmac-secret>
+//!           Sign the requests to the provider with a HMAC-SHA256 signature using this secret key. The signature covers the method, path, query string, timestamp and body of the request.
+//!       --hmac-signature-header <hmac-signature-header>
+//!           Header to add the HMAC signature to (defaults to X-Signature)
+//!       --hmac-timestamp-header <hmac-timestamp-header>
+//!           Header to add the timestamp used for the HMAC signature to (defaults to X-Timestamp)
+//!       --sigv4-access-key-id <sigv4-access-key-id>
+//!           Sign the requests to the provider with an AWS SigV4 style signature using this access key ID
+//!       --sigv4-secret-access-key <sigv4-secret-access-key>
+//!           Secret access key to use for the SigV4 signatures
+//!       --sigv4-session-token <sigv4-session-token>
+//!           Session token to add to the requests signed with SigV4 signatures
+//!       --sigv4-region <sigv4-region>
+//!           Region to use for the SigV4 signatures (i.e. us-east-1)
+//!       --sigv4-service <sigv4-service>
+//!           Service name to use for the SigV4 signatures (i.e. execute-api)
+//!       --oauth2-token-url <oauth2-token-url>
+//!           Add a bearer token to the requests to the provider, fetched from this token endpoint using the OAuth2 client credentials grant. The token is cached until it expires.
+//!       --oauth2-client-id <oauth2-client-id>
+//!           Client ID to use to fetch the OAuth2 token
+//!       --oauth2-client-secret <oauth2-client-secret>
+//!           Client secret to use to fetch the OAuth2 token
+//!       --oauth2-scope <oauth2-scope>
+//!           Scope to request for the OAuth2 token
+//!       --oauth2-audience <oauth2-audience>
+//!           Audience to request for the OAuth2 token
+//!
+//! TLS options:
+//!       --provider-ca-cert <provider-ca-cert>
+//!           PEM file with CA certificates to trust when connecting to the provider, in addition to the system root certificates. Can be repeated. [env: PACT_PROVIDER_CA_CERT=]
+//!       --provider-client-cert <provider-client-cert>
+//!           PEM file with the client certificate to use for mutual TLS with the provider. The file can also contain the private key. [env: PACT_PROVIDER_CLIENT_CERT=]
+//!       --provider-client-key <provider-client-key>
+//!           PEM file with the private key for the provider client certificate [env: PACT_PROVIDER_CLIENT_KEY=]
+//!       --provider-client-pkcs12 <provider-client-pkcs12>
+//!           PKCS#12 archive with the client certificate and private key to use for mutual TLS with the provider. Requires the native-tls feature. [env: PACT_PROVIDER_CLIENT_PKCS12=]
+//!       --provider-client-pkcs12-password <provider-client-pkcs12-password>
+//!           Password for the provider PKCS#12 archive [env: PACT_PROVIDER_CLIENT_PKCS12_PASSWORD=]
+//!       --state-change-ca-cert <state-change-ca-cert>
+//!           PEM file with CA certificates to trust when calling the provider state change URL, in addition to the system root certificates. Can be repeated. [env: PACT_STATE_CHANGE_CA_CERT=]
+//!       --state-change-client-cert <state-change-client-cert>
+//!           PEM file with the client certificate to use for mutual TLS with the provider state change URL. The file can also contain the private key. [env: PACT_STATE_CHANGE_CLIENT_CERT=]
+//!       --state-change-client-key <state-change-client-key>
+//!           PEM file with the private key for the provider state change URL client certificate [env: PACT_STATE_CHANGE_CLIENT_KEY=]
+//!       --state-change-client-pkcs12 <state-change-client-pkcs12>
+//!           PKCS#12 archive with the client certificate and private key to use for mutual TLS with the provider state change URL. Requires the native-tls feature. [env: PACT_STATE_CHANGE_CLIENT_PKCS12=]
+//!       --state-change-client-pkcs12-password <state-change-client-pkcs12-password>
+//!           Password for the provider state change URL PKCS#12 archive [env: PACT_STATE_CHANGE_CLIENT_PKCS12_PASSWORD=]
+//!       --broker-ca-cert <broker-ca-cert>
+//!           PEM file with CA certificates to trust when connecting to the Pact Broker or fetching pacts from URLs, in addition to the system root certificates. Can be repeated. [env: PACT_BROKER_CA_CERT=]
+//!       --broker-client-cert <broker-client-cert>
+//!           PEM file with the client certificate to use for mutual TLS with the Pact Broker. The file can also contain the private key. [env: PACT_BROKER_CLIENT_CERT=]
+//!       --broker-client-key <broker-client-key>
+//!           PEM file with the private key for the Pact Broker client certificate [env: PACT_BROKER_CLIENT_KEY=]
+//!       --broker-client-pkcs12 <broker-client-pkcs12>
+//!           PKCS#12 archive with the client certificate and private key to use for mutual TLS with the Pact Broker. Requires the native-tls feature. [env: PACT_BROKER_CLIENT_PKCS12=]
+//!       --broker-client-pkcs12-password <broker-client-pkcs12-password>
+//!           Password for the Pact Broker PKCS#12 archive [env: PACT_BROKER_CLIENT_PKCS12_PASSWORD=]
+//!
+//! Provider process options:
+//!       --provider-command <provider-command>
+//!           Command to start the provider with before running the verification. The provider process will be shut down once the verification is complete.
+//!       --provider-env <provider-env>
+//!           Environment variable to set for the provider process. Values must be in the form KEY=VALUE. Can be repeated.
+//!       --provider-cwd <provider-cwd>
+//!           Working directory to start the provider process in (defaults to the current directory)
+//!       --provider-health-url <provider-health-url>
+//!           URL to poll until it returns a successful response to determine that the provider is ready. If not set, the verifier will wait for the provider port to accept connections.
+//!       --provider-startup-timeout <provider-startup-timeout>
+//!           Time in seconds to wait for the provider process to be ready (defaults to 30)
+//!
 //! Provider state options:
 //!   -s, --state-change-url <state-change-url>
 //!           URL to post state change requests to
@@ -62,6 +136,8 @@
 //!           State change request data will be sent as query parameters instead of in the request body
 //!       --state-change-teardown
 //!           State change teardown requests are to be made after each interaction
+//!       --state-config <state-config>
+//!           YAML or JSON file that configures the actions to perform to set up and tear down the provider states, instead of using a state change URL
 //!
 //! Filtering interactions:
 //!       --filter-description <filter-description>
@@ -72,6 +148,10 @@
 //!           Only validate interactions that have no defined provider state [env: PACT_PROVIDER_NO_STATE=]
 //!   -c, --filter-consumer <filter-consumer>
 //!           Consumer name to filter the pacts to be verified (can be repeated)
+//!       --dry-run
+//!           List the pacts and interactions that would be verified after the filters have been applied, without calling the provider or publishing any results
+//!       --dry-run-format <dry-run-format>
+//!           Format to output the dry run in (defaults to table) [possible values: table, json]
 //!
 //! Publishing options:
 //!       --publish
@@ -151,6 +231,42 @@
 //!
 //! This option will filter the interactions that are verified that don't have a defined provider state. Can't be used with the `--filter-state` option.
 //!
+//! #### `--dry-run`
+//!
+//! Fetches the pacts and applies the filters, and then lists the pacts and interactions that would be verified without calling the provider, executing any provider states or publishing any results. For each pact, the source it was loaded from, the selector that caused the Pact Broker to include it and whether it is pending or a work in progress pact are listed, along with the provider states, type and transport of each interaction. The list is displayed as a table, or as a JSON document with `--dry-run-format json`. The exit code is 1 if any of the pacts could not be loaded.
+//!
+//! ### Signing and authenticating requests
+//!
+//! If the provider requires the requests to be signed or authenticated, the verifier can add the signatures or tokens to the requests once they have been generated, so that the signatures cover the actual method, path (including any base path), query parameters and body sent to the provider. Any headers set with `--header` are added after the requests have been signed.
+//!
+//! #### `--hmac-secret <hmac-secret>`
+//!
+//! Signs the requests with a HMAC-SHA256 signature. The signature is calculated over the method, path, query string (sorted by key and form URL encoded), timestamp (seconds since the Unix epoch) and the hex encoded SHA-256 hash of the body, separated by newlines. The hex encoded signature is added as the `X-Signature` header and the timestamp as the `X-Timestamp` header (these can be changed with `--hmac-signature-header` and `--hmac-timestamp-header`).
+//!
+//! #### `--sigv4-access-key-id <sigv4-access-key-id>`
+//!
+//! Signs the requests with an AWS Signature Version 4 style signature, using the credentials from `--sigv4-access-key-id`, `--sigv4-secret-access-key` and (optionally) `--sigv4-session-token`, and the region and service from `--sigv4-region` and `--sigv4-service`. The host (and port) of the provider is signed along with the `Content-Type` and `X-Amz-*` headers.
+//!
+//! #### `--oauth2-token-url <oauth2-token-url>`
+//!
+//! Fetches a bearer token from the token endpoint using the OAuth2 client credentials grant, and adds it as the `Authorization` header. The client ID and secret (`--oauth2-client-id` and `--oauth2-client-secret`) are sent using basic authentication, along with any scope (`--oauth2-scope`) or audience (`--oauth2-audience`). The token is cached until shortly before it expires.
+//!
+//! ### TLS options
+//!
+//! If the provider, the provider state change URL or the Pact Broker use certificates signed by a private CA, or require a client certificate (mutual TLS), these can be configured separately for each of them. The `--provider-*` options are used for the requests to the provider (and, if no `--state-change-*` options are given, the provider state change requests). The `--broker-*` options are used for the requests to the Pact Broker, including fetching pacts from URLs and publishing the verification results. All the options can also be set with environment variables.
+//!
+//! #### `--provider-ca-cert <provider-ca-cert>`
+//!
+//! PEM file with the CA certificates to trust, in addition to the system root certificates. The file can contain multiple certificates, and the option can be repeated.
+//!
+//! #### `--provider-client-cert <provider-client-cert>`
+//!
+//! PEM file with the client certificate (and any intermediate certificates) to present to the server. If the private key is not included in the file, it can be given with `--provider-client-key`.
+//!
+//! #### `--provider-client-pkcs12 <provider-client-pkcs12>`
+//!
+//! PKCS#12 archive (`.p12` or `.pfx`) with the client certificate and private key, with an optional password given with `--provider-client-pkcs12-password`. Using PKCS#12 archives requires the verifier to be built with the `native-tls` feature, otherwise the archive can be converted to PEM files with `openssl pkcs12 -in client.p12 -nodes -out client.pem`.
+//!
 //! ### State change requests
 //!
 //! Provider states are a mechanism to define the state that the provider needs to be in to be able to verify a particular request. This is achieved by setting a state change URL that will receive a POST request with the provider state before the actual request is made.
@@ -167,6 +283,28 @@
 //!
 //! This option will cause the verifier to also make a tear down request after the main request is made. It will receive a second field in the body or a query parameter named `action` with the value `teardown`.
 //!
+//! #### `--state-config <state-config>`
+//!
+//! Instead of a state change URL, the provider states can be configured with a YAML or JSON file. The file maps the provider state names (exactly with `name`, or with a regular expression with `regex`) to `setup` and `teardown` actions, which can be HTTP requests (`http`), shell commands (`command`) or static values to return for provider state generators (`values`). Expressions like `${id}` in the actions are replaced with the parameters of the provider state, the state name (`${state}`) and any values returned by earlier actions. Values returned by the actions (JSON objects returned by the HTTP requests or written to standard output by the commands) are used for any provider state generators.
+//!
+//! ```yaml
+//! states:
+//!   - name: a user exists
+//!     setup:
+//!       - http:
+//!           method: POST
+//!           url: http://localhost:8080/_test/users
+//!           body:
+//!             id: ${id}
+//!     teardown:
+//!       - command: ./scripts/delete-user.sh ${id}
+//!   - regex: 'an order (?P<order>\d+) exists'
+//!     setup:
+//!       - command: ./scripts/create-order.sh ${order}
+//!       - values:
+//!           status: PENDING
+//! ```
+//!
 //! ## Example run
 //!
 //! This will verify all the pacts for the `happy_provider` found in the pact broker (running on localhost) against the provider running on localhost port 5050. Only the pacts for the consumers `Consumer` and `Consumer2` will be verified.
@@ -263,6 +401,7 @@
 #![type_length_limit="100000000"]
 
 use std::env;
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
@@ -278,18 +417,24 @@ use tracing::{debug, debug_span, error, Instrument, warn};
 use tracing_subscriber::FmtSubscriber;
 
 use pact_verifier::{
+  ClientIdentity,
+  dry_run_verification,
   FilterInfo,
-  NullRequestFilterExecutor,
   PactSource,
   ProviderInfo,
+  ProviderProcess,
+  ProviderReadiness,
   PublishOptions,
+  TlsConfig,
   VerificationOptions,
   verify_provider_async,
   ProviderTransport
 };
-use pact_verifier::callback_executors::HttpRequestProviderStateExecutor;
+use pact_verifier::callback_executors::{HttpRequestProviderStateExecutor, ProviderStateExecutor};
 use pact_verifier::metrics::VerificationMetrics;
+use pact_verifier::request_filters::{HmacRequestSigner, OAuth2ClientCredentials, RequestFilter, RequestFilters, SigV4RequestSigner};
 use pact_verifier::selectors::{consumer_tags_to_selectors, json_to_selectors};
+use pact_verifier::state_config::ConfigProviderStateExecutor;
 use tracing_log::LogTracer;
 
 mod args;
@@ -329,13 +474,6 @@ async fn handle_matches(matches: &ArgMatches) -> Result<(), i32> {
   let provider = configure_provider(matches);
   let source = pact_source(matches);
   let filter = interaction_filter(matches);
-  let provider_state_executor = Arc::new(HttpRequestProviderStateExecutor {
-    state_change_url: matches.get_one::<String>("state-change-url").cloned(),
-    state_change_body: !matches.get_flag("state-change-as-query"),
-    state_change_teardown: matches.get_flag("state-change-teardown"),
-    .. HttpRequestProviderStateExecutor::default()
-  });
-
   let mut custom_headers = hashmap!{};
   if let Some(headers) = matches.get_many::<String>("custom-header") {
     for header in headers {
@@ -348,15 +486,23 @@ async fn handle_matches(matches: &ArgMatches) -> Result<(), i32> {
   }
 
   let verification_options = VerificationOptions {
-    request_filter: None::<Arc<NullRequestFilterExecutor>>,
+    request_filter: configure_request_filters(matches, &provider),
     disable_ssl_verification: matches.get_flag("disable-ssl-verification"),
     request_timeout: matches.get_one::<u64>("request-timeout").map(|v| *v).unwrap_or(5000),
     custom_headers,
     coloured_output,
     no_pacts_is_error: !matches.get_flag("ignore-no-pacts-error"),
+    provider_process: configure_provider_process(matches)?,
+    provider_tls: configure_tls(matches, "provider"),
+    state_change_tls: Some(configure_tls(matches, "state-change")).filter(|tls| !tls.is_empty()),
+    broker_tls: configure_tls(matches, "broker"),
     .. VerificationOptions::default()
   };
 
+  if matches.get_flag("dry-run") {
+    return dry_run(matches, &provider, source, &filter, &verification_options).await;
+  }
+
   let publish_options = if matches.get_flag("publish") {
     Some(PublishOptions {
       provider_version: matches.get_one::<String>("provider-version").cloned(),
@@ -373,15 +519,72 @@ async fn handle_matches(matches: &ArgMatches) -> Result<(), i32> {
     debug!("Pact source to verify = {}", s);
   };
 
+  if let Some(state_config) = matches.get_one::<String>("state-config") {
+    let provider_state_executor = ConfigProviderStateExecutor::load(state_config)
+      .map_err(|err| {
+        error!("Failed to load the provider state configuration '{}' - {}", state_config, err);
+        3
+      })?;
+    run_verification(matches, provider, source, filter, &verification_options, publish_options.as_ref(),
+      &Arc::new(provider_state_executor)).await
+  } else {
+    let provider_state_executor = Arc::new(HttpRequestProviderStateExecutor {
+      state_change_url: matches.get_one::<String>("state-change-url").cloned(),
+      state_change_body: !matches.get_flag("state-change-as-query"),
+      state_change_teardown: matches.get_flag("state-change-teardown"),
+      .. HttpRequestProviderStateExecutor::default()
+    });
+    run_verification(matches, provider, source, filter, &verification_options, publish_options.as_ref(),
+      &provider_state_executor).await
+  }
+}
+
+async fn dry_run(
+  matches: &ArgMatches,
+  provider: &ProviderInfo,
+  source: Vec<PactSource>,
+  filter: &FilterInfo,
+  verification_options: &VerificationOptions<RequestFilters>
+) -> Result<(), i32> {
+  let result = dry_run_verification(
+    provider,
+    source,
+    filter,
+    matches.get_many::<String>("filter-consumer").unwrap_or_default().map(|v| v.to_string()).collect::<Vec<_>>(),
+    verification_options
+  ).await;
+
+  if matches.get_one::<String>("dry-run-format").map(|format| format.as_str()) == Some("json") {
+    let json = serde_json::to_string_pretty(&result).map_err(|err| {
+      error!("Failed to generate the dry run JSON - {}", err);
+      2
+    })?;
+    println!("{}", json);
+  } else {
+    print!("{}", result.to_table());
+  }
+
+  if result.errors.is_empty() { Ok(()) } else { Err(1) }
+}
+
+async fn run_verification<S: ProviderStateExecutor>(
+  matches: &ArgMatches,
+  provider: ProviderInfo,
+  source: Vec<PactSource>,
+  filter: FilterInfo,
+  verification_options: &VerificationOptions<RequestFilters>,
+  publish_options: Option<&PublishOptions>,
+  provider_state_executor: &Arc<S>
+) -> Result<(), i32> {
   let provider_name = provider.name.clone();
   verify_provider_async(
     provider,
     source,
     filter,
     matches.get_many::<String>("filter-consumer").unwrap_or_default().map(|v| v.to_string()).collect::<Vec<_>>(),
-    &verification_options,
-    publish_options.as_ref(),
-    &provider_state_executor,
+    verification_options,
+    publish_options,
+    provider_state_executor,
     Some(VerificationMetrics {
       test_framework: "pact_verifier_cli".to_string(),
       app_name: "pact_verifier_cli".to_string(),
@@ -483,17 +686,13 @@ fn setup_pretty_log(level: &str, coloured_output: bool) {
 #[allow(deprecated)]
 pub(crate) fn configure_provider(matches: &ArgMatches) -> ProviderInfo {
   // It is ok to unwrap values here, as they have all been validated by the CLI
-  let transports = matches.get_many::<(String, u16)>("transports")
-    .map(|values| {
-      values.map(|(transport, port)| {
-        ProviderTransport {
-          transport: transport.to_string(),
-          port: Some(*port),
-          path: None,
-          scheme: None
-        }
-      }).collect()
-    }).unwrap_or_default();
+  let mut transports: Vec<ProviderTransport> = matches.get_many::<ProviderTransport>("transports")
+    .map(|values| values.cloned().collect())
+    .unwrap_or_default();
+  if let Some(command) = matches.get_one::<String>("message-command") {
+    // The command transport is added first, so it is used in preference to any other message transport
+    transports.insert(0, ProviderTransport::message_command("message", command.clone()));
+  }
   ProviderInfo {
     host: matches.get_one::<String>("hostname").cloned().unwrap_or("localhost".to_string()),
     port: matches.get_one::<u16>("port").map(|p| *p),
@@ -505,6 +704,126 @@ pub(crate) fn configure_provider(matches: &ArgMatches) -> ProviderInfo {
   }
 }
 
+pub(crate) fn configure_provider_process(matches: &ArgMatches) -> Result<Option<ProviderProcess>, i32> {
+  let command = match matches.get_one::<String>("provider-command") {
+    Some(command) => command,
+    None => return Ok(None)
+  };
+
+  let mut env = hashmap!{};
+  if let Some(values) = matches.get_many::<String>("provider-env") {
+    for value in values {
+      let (key, value) = value.split_once('=').ok_or_else(|| {
+        error!("Provider environment values must be in the form KEY=VALUE");
+        3
+      })?;
+      env.insert(key.to_string(), value.to_string());
+    }
+  }
+
+  let mut process = ProviderProcess::new(command);
+  process.env = env;
+  process.cwd = matches.get_one::<String>("provider-cwd").map(PathBuf::from);
+  if let Some(url) = matches.get_one::<String>("provider-health-url") {
+    process.readiness = ProviderReadiness::HealthUrl(url.clone());
+  }
+  if let Some(timeout) = matches.get_one::<u64>("provider-startup-timeout") {
+    process.startup_timeout = Duration::from_secs(*timeout);
+  }
+  Ok(Some(process))
+}
+
+/// Configures the built-in request filters that sign or authenticate the requests to the provider
+#[allow(deprecated)]
+pub(crate) fn configure_request_filters(matches: &ArgMatches, provider: &ProviderInfo) -> Option<Arc<RequestFilters>> {
+  // The same transport is selected as the verification uses for HTTP interactions
+  let transport = provider.http_transport();
+  let mut filters = vec![];
+
+  if let Some(token_url) = matches.get_one::<String>("oauth2-token-url") {
+    // It is ok to unwrap values here, as they are required by the CLI
+    let mut oauth2 = OAuth2ClientCredentials::new(
+      token_url.clone(),
+      matches.get_one::<String>("oauth2-client-id").unwrap().clone(),
+      matches.get_one::<String>("oauth2-client-secret").unwrap().clone()
+    );
+    oauth2.scope = matches.get_one::<String>("oauth2-scope").cloned();
+    oauth2.audience = matches.get_one::<String>("oauth2-audience").cloned();
+    filters.push(RequestFilter::OAuth2(oauth2));
+  }
+
+  if let Some(secret) = matches.get_one::<String>("hmac-secret") {
+    let mut signer = HmacRequestSigner::new(secret.clone());
+    if let Some(header) = matches.get_one::<String>("hmac-signature-header") {
+      signer.signature_header = header.clone();
+    }
+    if let Some(header) = matches.get_one::<String>("hmac-timestamp-header") {
+      signer.timestamp_header = header.clone();
+    }
+    filters.push(RequestFilter::Hmac(signer));
+  }
+
+  if let Some(access_key_id) = matches.get_one::<String>("sigv4-access-key-id") {
+    let mut signer = SigV4RequestSigner::new(
+      access_key_id.clone(),
+      matches.get_one::<String>("sigv4-secret-access-key").unwrap().clone(),
+      matches.get_one::<String>("sigv4-region").unwrap().clone(),
+      matches.get_one::<String>("sigv4-service").unwrap().clone()
+    );
+    signer.session_token = matches.get_one::<String>("sigv4-session-token").cloned();
+    signer.host = Some(provider_host(provider, transport.as_ref()));
+    filters.push(RequestFilter::SigV4(signer));
+  }
+
+  if filters.is_empty() {
+    None
+  } else {
+    #[allow(deprecated)]
+    let base_path = match &transport {
+      Some(transport) => transport.path.clone().unwrap_or_default(),
+      None => provider.path.clone()
+    };
+    Some(Arc::new(RequestFilters {
+      base_path,
+      filters
+    }))
+  }
+}
+
+/// Returns the host (and port) that requests for HTTP interactions are sent to with the transport
+#[allow(deprecated)]
+fn provider_host(provider: &ProviderInfo, transport: Option<&ProviderTransport>) -> String {
+  let port = match transport {
+    Some(transport) if transport.is_unix_socket() => return "localhost".to_string(),
+    Some(transport) => transport.port,
+    None => provider.port
+  };
+  match port {
+    Some(port) => format!("{}:{}", provider.host, port),
+    None => provider.host.clone()
+  }
+}
+
+/// Configures the CA certificates and client certificate from the TLS options with the given
+/// prefix (provider, state-change or broker)
+pub(crate) fn configure_tls(matches: &ArgMatches, prefix: &str) -> TlsConfig {
+  let ca_certificates = matches.get_many::<String>(format!("{}-ca-cert", prefix).as_str())
+    .map_or_else(Vec::new, |files| files.map(PathBuf::from).collect());
+  let client_identity = if let Some(certificate) = matches.get_one::<String>(format!("{}-client-cert", prefix).as_str()) {
+    Some(ClientIdentity::Pem {
+      certificate: PathBuf::from(certificate),
+      private_key: matches.get_one::<String>(format!("{}-client-key", prefix).as_str()).map(PathBuf::from)
+    })
+  } else {
+    matches.get_one::<String>(format!("{}-client-pkcs12", prefix).as_str())
+      .map(|archive| ClientIdentity::Pkcs12 {
+        archive: PathBuf::from(archive),
+        password: matches.get_one::<String>(format!("{}-client-pkcs12-password", prefix).as_str()).cloned()
+      })
+  };
+  TlsConfig { ca_certificates, client_identity }
+}
+
 fn print_version(version: &str) {
   println!("pact verifier version   : v{}", version);
   println!("pact specification      : v{}", PactSpecification::V4.version_str());
@@ -649,7 +968,15 @@ fn init_windows() { }
 mod tests {
   use expectest::prelude::*;
 
-  use crate::{args, configure_provider};
+  use std::path::PathBuf;
+  use std::time::Duration;
+
+  use maplit::hashmap;
+  use pact_verifier::ProviderReadiness;
+  use pact_verifier::{ClientIdentity, TlsConfig};
+  use pact_verifier::request_filters::RequestFilter;
+
+  use crate::{args, configure_provider, configure_provider_process, configure_request_filters, configure_tls};
 
   #[test]
   #[allow(deprecated)]
@@ -693,4 +1020,169 @@ mod tests {
 
     expect!(provider.protocol).to(be_equal_to("https"));
   }
+
+  #[test]
+  fn parse_provider_process_args() {
+    let args = args::setup_app();
+    let matches = args.get_matches_from(vec!["test", "-f", "test"]);
+    expect!(configure_provider_process(&matches).unwrap()).to(be_none());
+
+    let args = args::setup_app();
+    let matches = args.get_matches_from(vec![
+      "test", "-f", "test", "--provider-command", "npm start", "--provider-env", "PORT=8080",
+      "--provider-env", "MODE=test", "--provider-cwd", "/tmp", "--provider-health-url",
+      "http://localhost:8080/health", "--provider-startup-timeout", "60"
+    ]);
+    let process = configure_provider_process(&matches).unwrap().unwrap();
+
+    expect!(process.command).to(be_equal_to("npm start"));
+    expect!(process.env).to(be_equal_to(hashmap!{
+      "PORT".to_string() => "8080".to_string(),
+      "MODE".to_string() => "test".to_string()
+    }));
+    expect!(process.cwd).to(be_some().value(PathBuf::from("/tmp")));
+    expect!(process.readiness).to(be_equal_to(ProviderReadiness::HealthUrl("http://localhost:8080/health".to_string())));
+    expect!(process.startup_timeout).to(be_equal_to(Duration::from_secs(60)));
+  }
+
+  #[test]
+  fn parse_provider_process_args_with_invalid_env() {
+    let args = args::setup_app();
+    let matches = args.get_matches_from(vec![
+      "test", "-f", "test", "--provider-command", "npm start", "--provider-env", "PORT"
+    ]);
+    expect!(configure_provider_process(&matches)).to(be_err().value(3));
+  }
+
+  #[test]
+  fn parse_provider_args_with_message_command() {
+    let args = args::setup_app();
+    let matches = args.get_matches_from(vec![
+      "test", "-f", "test", "--transports", "message:8080", "--message-command", "node produce.js"
+    ]);
+    let provider = configure_provider(&matches);
+
+    expect!(provider.transports.len()).to(be_equal_to(2));
+    let transport = &provider.transports[0];
+    expect!(transport.transport.as_str()).to(be_equal_to("message"));
+    expect!(transport.is_command()).to(be_true());
+    expect!(transport.command.clone()).to(be_some().value("node produce.js"));
+  }
+
+  #[test]
+  fn state_config_can_not_be_used_with_a_state_change_url() {
+    let matches = args::setup_app().try_get_matches_from(vec![
+      "test", "-f", "test", "--state-config", "states.yml"
+    ]).unwrap();
+    expect!(matches.get_one::<String>("state-config").cloned()).to(be_some().value("states.yml"));
+
+    let result = args::setup_app().try_get_matches_from(vec![
+      "test", "-f", "test", "--state-config", "states.yml", "--state-change-url", "http://localhost/state"
+    ]);
+    expect!(result).to(be_err());
+  }
+
+  #[test]
+  fn parse_request_filter_args() {
+    let args = args::setup_app();
+    let matches = args.get_matches_from(vec!["test", "-f", "test"]);
+    expect!(configure_request_filters(&matches, &configure_provider(&matches))).to(be_none());
+
+    let args = args::setup_app();
+    let matches = args.get_matches_from(vec![
+      "test", "-f", "test", "-p", "8080", "--base-path", "/api", "--hmac-secret", "secret",
+      "--oauth2-token-url", "http://localhost/token", "--oauth2-client-id", "id", "--oauth2-client-secret", "secret",
+      "--sigv4-access-key-id", "key", "--sigv4-secret-access-key", "secret", "--sigv4-region", "us-east-1",
+      "--sigv4-service", "execute-api"
+    ]);
+    let filters = configure_request_filters(&matches, &configure_provider(&matches)).unwrap();
+
+    expect!(filters.base_path.as_str()).to(be_equal_to("/api"));
+    expect!(filters.filters.len()).to(be_equal_to(3));
+    match &filters.filters[2] {
+      RequestFilter::SigV4(signer) => {
+        expect!(signer.host.clone()).to(be_some().value("localhost:8080"));
+      }
+      filter => panic!("Expected a SigV4 filter, got {:?}", filter)
+    }
+  }
+
+  #[test]
+  fn request_filters_use_the_host_and_path_of_the_http_transport() {
+    let args = args::setup_app();
+    let matches = args.get_matches_from(vec![
+      "test", "-f", "test", "--transports", "http:9000", "-p", "8080", "--base-path", "/api",
+      "--sigv4-access-key-id", "key", "--sigv4-secret-access-key", "secret", "--sigv4-region", "us-east-1",
+      "--sigv4-service", "execute-api"
+    ]);
+    let filters = configure_request_filters(&matches, &configure_provider(&matches)).unwrap();
+
+    expect!(filters.base_path.as_str()).to(be_equal_to(""));
+    match &filters.filters[0] {
+      RequestFilter::SigV4(signer) => {
+        expect!(signer.host.clone()).to(be_some().value("localhost:9000"));
+      }
+      filter => panic!("Expected a SigV4 filter, got {:?}", filter)
+    }
+  }
+
+  #[test]
+  fn sigv4_args_require_the_secret_region_and_service() {
+    let result = args::setup_app().try_get_matches_from(vec![
+      "test", "-f", "test", "--sigv4-access-key-id", "key", "--sigv4-region", "us-east-1"
+    ]);
+    expect!(result).to(be_err());
+  }
+
+  #[test]
+  fn parse_tls_args() {
+    let args = args::setup_app();
+    let matches = args.get_matches_from(vec![
+      "test", "-f", "test", "--provider-ca-cert", "ca.pem", "--provider-ca-cert", "other-ca.pem",
+      "--provider-client-cert", "client.pem", "--provider-client-key", "client.key",
+      "--broker-client-pkcs12", "client.p12", "--broker-client-pkcs12-password", "changeit"
+    ]);
+
+    expect!(configure_tls(&matches, "provider")).to(be_equal_to(TlsConfig {
+      ca_certificates: vec![PathBuf::from("ca.pem"), PathBuf::from("other-ca.pem")],
+      client_identity: Some(ClientIdentity::Pem {
+        certificate: PathBuf::from("client.pem"),
+        private_key: Some(PathBuf::from("client.key"))
+      })
+    }));
+    expect!(configure_tls(&matches, "state-change").is_empty()).to(be_true());
+    expect!(configure_tls(&matches, "broker")).to(be_equal_to(TlsConfig {
+      ca_certificates: vec![],
+      client_identity: Some(ClientIdentity::Pkcs12 {
+        archive: PathBuf::from("client.p12"),
+        password: Some("changeit".to_string())
+      })
+    }));
+  }
+
+  #[test]
+  fn client_certificate_and_pkcs12_args_can_not_be_used_together() {
+    let result = args::setup_app().try_get_matches_from(vec![
+      "test", "-f", "test", "--provider-client-cert", "client.pem", "--provider-client-pkcs12", "client.p12"
+    ]);
+    expect!(result).to(be_err());
+  }
+
+  #[test]
+  fn dry_run_format_requires_dry_run() {
+    let result = args::setup_app().try_get_matches_from(vec![
+      "test", "-f", "test", "--dry-run-format", "json"
+    ]);
+    expect!(result).to(be_err());
+
+    let result = args::setup_app().try_get_matches_from(vec![
+      "test", "-f", "test", "--dry-run", "--dry-run-format", "csv"
+    ]);
+    expect!(result).to(be_err());
+
+    let result = args::setup_app().try_get_matches_from(vec![
+      "test", "-f", "test", "--dry-run", "--dry-run-format", "json"
+    ]);
+    expect!(result).to(be_ok());
+  }
 }
@@ -1,4 +1,4 @@
-//! WASM bindings for Pact models
+//! WASM bindings for Pact models, matching and Pact file verification
 
 use std::rc::Rc;
 
@@ -10,6 +10,9 @@ use pact_models::v4::pact::V4Pact;
 use pact_models::v4::synch_http::SynchronousHttp;
 use wasm_bindgen::prelude::*;
 
+pub mod matching;
+pub mod verification;
+
 /// Library version
 #[wasm_bindgen(js_name = libVersion)]
 pub fn lib_version() -> String {
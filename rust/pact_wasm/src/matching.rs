@@ -0,0 +1,225 @@
+//! Bindings to evaluate matching rules and match JSON documents
+
+use anyhow::anyhow;
+use bytes::Bytes;
+use pact_matching::{CoreMatchingContext, DiffConfig, Mismatch};
+use pact_matching::json::match_json as match_json_bodies;
+use pact_matching::matchers::match_values;
+use pact_models::bodies::OptionalBody;
+use pact_models::content_types::JSON;
+use pact_models::matchingrules::{matchers_from_json, MatchingRule, MatchingRuleCategory, RuleList};
+use pact_models::matchingrules::expressions::{MatchingRuleDefinition, parse_matcher_def};
+use pact_models::path_exp::DocPath;
+use pact_models::v4::http_parts::HttpRequest;
+use serde_json::{json, Value};
+use wasm_bindgen::prelude::*;
+
+fn parse_json(json: &str, name: &str) -> anyhow::Result<Value> {
+  serde_json::from_str(json).map_err(|err| anyhow!("Failed to parse {} JSON: {}", name, err))
+}
+
+fn to_js_error(err: anyhow::Error) -> JsValue {
+  JsValue::from(err.to_string())
+}
+
+/// Mismatch resulting from matching a value
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct PactMismatch {
+  mismatch: Mismatch
+}
+
+#[wasm_bindgen]
+impl PactMismatch {
+  /// Type of the mismatch
+  #[wasm_bindgen(getter, js_name = type)]
+  pub fn mismatch_type(&self) -> String {
+    self.mismatch.mismatch_type().to_string()
+  }
+
+  /// Path to the item that did not match, if the mismatch has one
+  #[wasm_bindgen(getter)]
+  pub fn path(&self) -> Option<String> {
+    self.mismatch.to_json().get("path")
+      .and_then(|path| path.as_str())
+      .map(|path| path.to_string())
+  }
+
+  /// Short summary of the mismatch
+  #[wasm_bindgen(getter)]
+  pub fn summary(&self) -> String {
+    self.mismatch.summary()
+  }
+
+  /// Full description of the mismatch
+  #[wasm_bindgen(getter)]
+  pub fn description(&self) -> String {
+    self.mismatch.description()
+  }
+
+  /// Mismatch formatted as JSON
+  #[wasm_bindgen(js_name = toJson)]
+  pub fn to_json(&self) -> String {
+    self.mismatch.to_json().to_string()
+  }
+}
+
+impl From<Mismatch> for PactMismatch {
+  fn from(mismatch: Mismatch) -> Self {
+    PactMismatch { mismatch }
+  }
+}
+
+/// Matching rule that can be evaluated against values
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct PactMatchingRule {
+  rule: MatchingRule
+}
+
+#[wasm_bindgen]
+impl PactMatchingRule {
+  /// Parse a matching rule from its JSON form (i.e. `{"match": "regex", "regex": "\\d+"}`)
+  #[wasm_bindgen(js_name = fromJson)]
+  pub fn from_json(json: String) -> Result<PactMatchingRule, JsValue> {
+    parse_json(&json, "matching rule")
+      .and_then(|json| MatchingRule::from_json(&json))
+      .map(|rule| PactMatchingRule { rule })
+      .map_err(to_js_error)
+  }
+
+  /// Name of the matching rule
+  #[wasm_bindgen(getter, js_name = type)]
+  pub fn rule_type(&self) -> String {
+    self.rule.name()
+  }
+
+  /// Matching rule formatted as JSON
+  #[wasm_bindgen(js_name = toJson)]
+  pub fn to_json(&self) -> String {
+    self.rule.to_json().to_string()
+  }
+
+  /// Evaluate the matching rule against an actual value given the expected one. Both values are
+  /// in JSON form. Returns an array of mismatch messages, which will be empty if the value matched.
+  #[wasm_bindgen(js_name = matchValue)]
+  pub fn match_value(&self, expected: String, actual: String) -> Result<Box<[JsValue]>, JsValue> {
+    let expected = parse_json(&expected, "expected").map_err(to_js_error)?;
+    let actual = parse_json(&actual, "actual").map_err(to_js_error)?;
+    let rules = RuleList::new(self.rule.clone());
+    let messages = match match_values(&DocPath::root(), &rules, &expected, &actual) {
+      Ok(_) => vec![],
+      Err(messages) => messages.iter().map(|message| JsValue::from(message)).collect()
+    };
+    Ok(messages.into_boxed_slice())
+  }
+}
+
+/// Result of parsing a matching rule definition expression
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct PactMatcherDefinition {
+  definition: MatchingRuleDefinition
+}
+
+#[wasm_bindgen]
+impl PactMatcherDefinition {
+  /// Example value from the definition
+  #[wasm_bindgen(getter)]
+  pub fn value(&self) -> String {
+    self.definition.value.clone()
+  }
+
+  /// Type of the example value
+  #[wasm_bindgen(getter, js_name = valueType)]
+  pub fn value_type(&self) -> String {
+    format!("{:?}", self.definition.value_type)
+  }
+
+  /// Number of matching rules in the definition
+  #[wasm_bindgen(js_name = numRules)]
+  pub fn num_rules(&self) -> usize {
+    self.definition.rules.len()
+  }
+
+  /// Matching rule at the given index. References to other attributes are not matching rules,
+  /// so will return `undefined`.
+  #[wasm_bindgen]
+  pub fn rule(&self, index: usize) -> Option<PactMatchingRule> {
+    self.definition.rules.get(index)
+      .and_then(|rule| rule.clone().left())
+      .map(|rule| PactMatchingRule { rule })
+  }
+
+  /// Generator from the definition formatted as JSON
+  #[wasm_bindgen(getter)]
+  pub fn generator(&self) -> Option<String> {
+    self.definition.generator.as_ref()
+      .and_then(|generator| generator.to_json())
+      .map(|json| json.to_string())
+  }
+
+  /// Definition formatted as JSON
+  #[wasm_bindgen(js_name = toJson)]
+  pub fn to_json(&self) -> String {
+    json!({
+      "value": self.definition.value,
+      "valueType": self.value_type(),
+      "rules": self.definition.rules.iter().map(|rule| {
+        rule.as_ref().either(|rule| rule.to_json(), |reference| json!({ "reference": reference.name }))
+      }).collect::<Vec<Value>>(),
+      "generator": self.definition.generator.as_ref().and_then(|generator| generator.to_json())
+    }).to_string()
+  }
+}
+
+/// Parse a matching rule definition expression (i.e. `matching(regex, '\d+', '1234')`)
+#[wasm_bindgen(js_name = parseMatcherDefinition)]
+pub fn parse_matcher_definition(expression: String) -> Result<PactMatcherDefinition, JsValue> {
+  parse_matcher_def(&expression)
+    .map(|definition| PactMatcherDefinition { definition })
+    .map_err(to_js_error)
+}
+
+fn json_part(json: String, name: &str) -> anyhow::Result<HttpRequest> {
+  let json = parse_json(&json, name)?;
+  Ok(HttpRequest {
+    body: OptionalBody::Present(Bytes::from(json.to_string()), Some(JSON.clone()), None),
+    .. HttpRequest::default()
+  })
+}
+
+/// Match an actual JSON document against an expected one. Matching rules can be provided in the
+/// Pact file `matchingRules` format (i.e. `{"body": {"$.id": {"matchers": [{"match": "integer"}]}}}`).
+/// Returns an array of `PactMismatch`, which will be empty if the documents matched.
+#[wasm_bindgen(js_name = matchJson)]
+pub fn match_json(
+  expected: String,
+  actual: String,
+  matching_rules: Option<String>,
+  allow_unexpected_keys: bool
+) -> Result<Box<[JsValue]>, JsValue> {
+  let expected = json_part(expected, "expected").map_err(to_js_error)?;
+  let actual = json_part(actual, "actual").map_err(to_js_error)?;
+  let rules = match matching_rules {
+    Some(rules) => parse_json(&rules, "matching rules")
+      .and_then(|rules| matchers_from_json(&json!({ "matchingRules": rules }), &None))
+      .map_err(to_js_error)?
+      .rules_for_category("body")
+      .unwrap_or_else(|| MatchingRuleCategory::empty("body")),
+    None => MatchingRuleCategory::empty("body")
+  };
+  let config = if allow_unexpected_keys {
+    DiffConfig::AllowUnexpectedKeys
+  } else {
+    DiffConfig::NoUnexpectedKeys
+  };
+  let context = CoreMatchingContext::new(config, &rules, &Default::default());
+  let mismatches = match match_json_bodies(&expected, &actual, &context) {
+    Ok(_) => vec![],
+    Err(mismatches) => mismatches.iter()
+      .map(|mismatch| JsValue::from(PactMismatch { mismatch: mismatch.clone() }))
+      .collect()
+  };
+  Ok(mismatches.into_boxed_slice())
+}
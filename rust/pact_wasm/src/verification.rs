@@ -0,0 +1,105 @@
+//! Bindings to verify the format of a Pact file
+
+use pact_models::message_pact::MessagePact;
+use pact_models::pact::{determine_spec_version, parse_meta_data};
+use pact_models::PactSpecification;
+use pact_models::sync_pact::RequestResponsePact;
+use pact_models::v4::pact::V4Pact;
+use pact_models::verify_json::{json_type_of, PactFileVerificationResult, PactJsonVerifier, ResultLevel};
+use serde_json::Value;
+use wasm_bindgen::prelude::*;
+
+/// Single result from verifying a Pact file
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct PactVerificationResult {
+  result: PactFileVerificationResult
+}
+
+#[wasm_bindgen]
+impl PactVerificationResult {
+  /// Path into the Pact JSON
+  #[wasm_bindgen(getter)]
+  pub fn path(&self) -> String {
+    self.result.path.clone()
+  }
+
+  /// Level of the result (ERROR, WARNING or NOTICE)
+  #[wasm_bindgen(getter)]
+  pub fn level(&self) -> String {
+    format!("{:?}", self.result.level)
+  }
+
+  /// Message associated with the result
+  #[wasm_bindgen(getter)]
+  pub fn message(&self) -> String {
+    self.result.message.clone()
+  }
+}
+
+/// Results from verifying a Pact file
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct PactVerification {
+  results: Vec<PactFileVerificationResult>
+}
+
+#[wasm_bindgen]
+impl PactVerification {
+  /// If any of the results are errors
+  #[wasm_bindgen(getter, js_name = hasErrors)]
+  pub fn has_errors(&self) -> bool {
+    self.results.iter().any(|result| result.level == ResultLevel::ERROR)
+  }
+
+  /// Number of results
+  #[wasm_bindgen(js_name = numResults)]
+  pub fn num_results(&self) -> usize {
+    self.results.len()
+  }
+
+  /// Result at the given index
+  #[wasm_bindgen]
+  pub fn result(&self, index: usize) -> Option<PactVerificationResult> {
+    self.results.get(index).map(|result| PactVerificationResult { result: result.clone() })
+  }
+
+  /// Results formatted as JSON
+  #[wasm_bindgen(js_name = toJson)]
+  pub fn to_json(&self) -> String {
+    serde_json::to_string(&self.results).unwrap_or_default()
+  }
+}
+
+/// Verify the format of a Pact file. If the specification version is not provided, it is
+/// determined from the metadata in the Pact. In strict mode, unknown attributes are reported as
+/// errors instead of warnings.
+#[wasm_bindgen(js_name = verifyPact)]
+pub fn verify_pact(json: String, spec_version: Option<String>, strict: bool) -> PactVerification {
+  let results = match serde_json::from_str::<Value>(&json) {
+    Ok(pact_json) => {
+      let spec_version = match spec_version.map(PactSpecification::from) {
+        Some(PactSpecification::Unknown) | None => {
+          let metadata = parse_meta_data(&pact_json);
+          determine_spec_version("<JSON>", &metadata)
+        }
+        Some(spec_version) => spec_version
+      };
+      match spec_version {
+        PactSpecification::V4 => V4Pact::verify_json("/", &pact_json, strict, spec_version),
+        _ => match &pact_json {
+          Value::Object(map) => if map.contains_key("messages") {
+            MessagePact::verify_json("/", &pact_json, strict, spec_version)
+          } else {
+            RequestResponsePact::verify_json("/", &pact_json, strict, spec_version)
+          },
+          _ => vec![PactFileVerificationResult::new("/", ResultLevel::ERROR,
+            format!("Must be an Object, got {}", json_type_of(&pact_json)))]
+        }
+      }
+    }
+    Err(err) => vec![PactFileVerificationResult::new("/", ResultLevel::ERROR,
+      format!("Failed to parse Pact JSON: {}", err))]
+  };
+  PactVerification { results }
+}
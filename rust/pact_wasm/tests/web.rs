@@ -0,0 +1,93 @@
+//! Tests for the WASM bindings. Run with `wasm-pack test --node` (or `--headless --firefox`)
+
+#![cfg(target_family = "wasm")]
+
+use expectest::prelude::*;
+use pact_matching::Mismatch;
+use wasm_bindgen_test::*;
+
+use pact_wasm::matching::{match_json, parse_matcher_definition, PactMatchingRule, PactMismatch};
+use pact_wasm::verification::verify_pact;
+
+#[wasm_bindgen_test]
+fn verify_pact_with_valid_pact() {
+  let result = verify_pact(r#"{
+    "consumer": { "name": "consumer" },
+    "provider": { "name": "provider" },
+    "interactions": [],
+    "metadata": { "pactSpecification": { "version": "4.0" } }
+  }"#.to_string(), None, false);
+  expect!(result.has_errors()).to(be_false());
+}
+
+#[wasm_bindgen_test]
+fn verify_pact_with_invalid_pact() {
+  let result = verify_pact(r#"{ "consumer": "consumer", "interactions": {} }"#.to_string(),
+    Some("V3".to_string()), true);
+  expect!(result.has_errors()).to(be_true());
+  expect!(result.num_results() > 0).to(be_true());
+  let first = result.result(0).unwrap();
+  expect!(first.level()).to(be_equal_to("ERROR"));
+}
+
+#[wasm_bindgen_test]
+fn verify_pact_with_invalid_json() {
+  let result = verify_pact("{".to_string(), None, false);
+  expect!(result.has_errors()).to(be_true());
+  expect!(result.result(0).unwrap().path()).to(be_equal_to("/"));
+}
+
+#[wasm_bindgen_test]
+fn parse_matcher_definition_test() {
+  let definition = parse_matcher_definition("matching(regex, '\\d+', '1234')".to_string()).unwrap();
+  expect!(definition.value()).to(be_equal_to("1234"));
+  expect!(definition.num_rules()).to(be_equal_to(1));
+  let rule = definition.rule(0).unwrap();
+  expect!(rule.rule_type()).to(be_equal_to("regex"));
+
+  expect!(parse_matcher_definition("matching(".to_string())).to(be_err());
+}
+
+#[wasm_bindgen_test]
+fn matching_rule_match_value() {
+  let rule = PactMatchingRule::from_json(r#"{"match": "regex", "regex": "\\d+"}"#.to_string()).unwrap();
+  expect!(rule.match_value("\"100\"".to_string(), "\"1234\"".to_string()).unwrap().len()).to(be_equal_to(0));
+  expect!(rule.match_value("\"100\"".to_string(), "\"12a34\"".to_string()).unwrap().len()).to(be_equal_to(1));
+
+  let rule = PactMatchingRule::from_json(r#"{"match": "integer"}"#.to_string()).unwrap();
+  expect!(rule.match_value("100".to_string(), "200".to_string()).unwrap().len()).to(be_equal_to(0));
+  expect!(rule.match_value("100".to_string(), "200.5".to_string()).unwrap().len()).to(be_equal_to(1));
+}
+
+#[wasm_bindgen_test]
+fn match_json_test() {
+  let expected = r#"{"id": 100, "name": "Fred"}"#.to_string();
+  let rules = r#"{"body": {"$.id": {"matchers": [{"match": "integer"}]}}}"#.to_string();
+
+  let result = match_json(expected.clone(), r#"{"id": 200, "name": "Fred"}"#.to_string(),
+    Some(rules.clone()), false).unwrap();
+  expect!(result.len()).to(be_equal_to(0));
+
+  let result = match_json(expected.clone(), r#"{"id": "200", "name": "Fred"}"#.to_string(),
+    Some(rules.clone()), false).unwrap();
+  expect!(result.len()).to(be_equal_to(1));
+
+  let result = match_json(expected.clone(), r#"{"id": 200, "name": "Fred"}"#.to_string(),
+    None, false).unwrap();
+  expect!(result.len()).to(be_equal_to(1));
+
+  expect!(match_json(expected, "{".to_string(), None, false)).to(be_err());
+}
+
+#[wasm_bindgen_test]
+fn mismatch_details() {
+  let mismatch = PactMismatch::from(Mismatch::BodyMismatch {
+    path: "$.name".to_string(),
+    expected: Some("\"Fred\"".into()),
+    actual: Some("\"Mary\"".into()),
+    mismatch: "Expected 'Fred' to be equal to 'Mary'".to_string()
+  });
+  expect!(mismatch.mismatch_type()).to(be_equal_to("BodyMismatch"));
+  expect!(mismatch.path()).to(be_some().value("$.name"));
+  expect!(mismatch.description()).to(be_equal_to("$.name -> Expected 'Fred' to be equal to 'Mary'"));
+}